@@ -0,0 +1,38 @@
+//! The LED at row 1, column 1 of the matrix, driven through the GPIO registers.
+
+use crate::reg::Reg;
+
+// GPIO registers for nRF52833
+pub const GPIO_P0_OUTSET: Reg<u32> = Reg::at(0x5000_0508);
+pub const GPIO_P0_OUTCLR: Reg<u32> = Reg::at(0x5000_050C);
+pub const GPIO_P0_PIN_CNF: Reg<u32> = Reg::at(0x5000_0700);
+
+// micro:bit LED matrix pins
+pub const ROW1_PIN: u32 = 21; // P0.21
+pub const COL1_PIN: u32 = 28; // P0.28
+
+/// Make the row and column pins outputs, and activate the column
+#[inline(always)]
+pub fn init() {
+    // Nothing else in these programs uses GPIO port 0
+    unsafe {
+        // Configure P0.21 (Row 1) as output
+        GPIO_P0_PIN_CNF.offset(ROW1_PIN as usize).write(1); // DIR=1 (output)
+
+        // Configure P0.28 (Col 1) as output and set low (column active)
+        GPIO_P0_PIN_CNF.offset(COL1_PIN as usize).write(1); // DIR=1 (output)
+        GPIO_P0_OUTCLR.write(1 << COL1_PIN); // Set column active
+    }
+}
+
+/// Turn the LED on (set row low)
+#[inline(always)]
+pub fn on() {
+    unsafe { GPIO_P0_OUTCLR.write(1 << ROW1_PIN) }
+}
+
+/// Turn the LED off (set row high)
+#[inline(always)]
+pub fn off() {
+    unsafe { GPIO_P0_OUTSET.write(1 << ROW1_PIN) }
+}
@@ -0,0 +1,16 @@
+#![no_std]
+
+//! The register code of the LED example, apart from the runtime, so it can be tested on the PC.
+//!
+//! - [`reg`] - a register at a fixed address, with volatile reads and writes
+//! - [`led`] - the GPIO registers and pins of the LED, and the writes that drive it
+//! - [`mock`] - on the PC only, the log of register accesses the host tests check
+//!
+//! See `tests/registers.rs` for the host tests.
+
+pub mod led;
+pub mod reg;
+
+// The mock needs `std` (a per-thread log) so it only exists in host builds
+#[cfg(not(target_os = "none"))]
+pub mod mock;
@@ -0,0 +1,94 @@
+//! Host tests for the LED's register writes, checked against the recording mock: the exact addresses and values
+//! `main` writes, in order, with the addresses taken from the nRF52833 Product Specification rather than from
+//! `led.rs`.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use example_02_hello_world_minimal_dependencies::{
+    led,
+    mock::{self, Access},
+    reg::Reg,
+};
+
+// GPIO P0 at 0x5000_0000: OUTSET +0x508, OUTCLR +0x50C, PIN_CNF[n] +0x700 + 4n
+const OUTSET: usize = 0x5000_0508;
+const OUTCLR: usize = 0x5000_050C;
+const PIN_CNF_21: usize = 0x5000_0754;
+const PIN_CNF_28: usize = 0x5000_0770;
+
+const ROW1: u32 = 1 << 21;
+const COL1: u32 = 1 << 28;
+
+fn read(address: usize, value: u32) -> Access {
+    Access::Read { address, value }
+}
+
+fn write(address: usize, value: u32) -> Access {
+    Access::Write { address, value }
+}
+
+#[test]
+fn init_makes_both_pins_outputs_then_activates_the_column() {
+    led::init();
+    assert_eq!(mock::take_writes(), [(PIN_CNF_21, 1), (PIN_CNF_28, 1), (OUTCLR, COL1)]);
+}
+
+#[test]
+fn on_and_off_write_only_the_row_bit() {
+    led::on();
+    assert_eq!(mock::take_writes(), [(OUTCLR, ROW1)]);
+    led::off();
+    assert_eq!(mock::take_writes(), [(OUTSET, ROW1)]);
+}
+
+#[test]
+fn a_blink_is_writes_only() {
+    // What `main` does up to the second time round its loop: no read-modify-write anywhere
+    led::init();
+    led::on();
+    led::off();
+    led::on();
+    assert_eq!(
+        mock::take(),
+        [
+            write(PIN_CNF_21, 1),
+            write(PIN_CNF_28, 1),
+            write(OUTCLR, COL1),
+            write(OUTCLR, ROW1),
+            write(OUTSET, ROW1),
+            write(OUTCLR, ROW1),
+        ]
+    );
+}
+
+#[test]
+fn arrays_step_by_the_register_width() {
+    assert_eq!(Reg::<u32>::at(0x700).offset(21).address(), 0x754);
+    assert_eq!(Reg::<u16>::at(0x700).offset(21).address(), 0x72A);
+    assert_eq!(Reg::<u8>::at(0x700).offset(21).address(), 0x715);
+}
+
+#[test]
+fn reads_see_set_and_written_values() {
+    let input = Reg::<u32>::at(0x5000_0510);
+    let byte = Reg::<u8>::at(0x4000_0000);
+    mock::set(input.address(), 0x0000_4000);
+    unsafe {
+        assert_eq!(input.read(), 0x0000_4000);
+        byte.write(0x5A);
+        assert_eq!(byte.read(), 0x5A);
+    }
+    assert_eq!(
+        mock::take(),
+        [
+            read(0x5000_0510, 0x0000_4000),
+            write(0x4000_0000, 0x5A),
+            read(0x4000_0000, 0x5A),
+        ]
+    );
+
+    // Taking the log keeps the values; resetting forgets them
+    assert_eq!(unsafe { input.read() }, 0x0000_4000);
+    mock::reset();
+    assert_eq!(unsafe { input.read() }, 0);
+}
@@ -0,0 +1,35 @@
+//! Generate the register module from the nRF52833 SVD file, with `src/svd.rs`.
+//!
+//! The output goes to `$OUT_DIR/nrf52833.rs`, which `src/nrf52833.rs` includes. Only the peripherals in
+//! `PERIPHERALS` are generated, so the module stays small with Nordic's complete file too.
+
+use std::{env, fs, path::PathBuf, process};
+
+// Only the parser and the generator are needed here; `tests/svd.rs` uses the rest
+#[allow(dead_code)]
+#[path = "src/svd.rs"]
+mod svd;
+
+/// Relative to this directory
+const SVD: &str = "../doc/nrf52833.svd";
+
+const PERIPHERALS: &[&str] = &[
+    "CLOCK", "P0", "P1", "RTC0", "RTC1", "RTC2", "TIMER0", "TIMER1", "TIMER2", "TIMER3", "TIMER4",
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", SVD);
+    println!("cargo:rerun-if-changed=src/svd.rs");
+
+    let text = fs::read_to_string(SVD).unwrap_or_else(|error| fail(error));
+    let device = svd::Device::parse(&text).unwrap_or_else(|error| fail(error));
+    let module = svd::generate(&device, PERIPHERALS, "doc/nrf52833.svd").unwrap_or_else(|error| fail(error));
+
+    let out = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets OUT_DIR")).join("nrf52833.rs");
+    fs::write(&out, module).unwrap_or_else(|error| fail(error));
+}
+
+fn fail(error: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", SVD, error);
+    process::exit(1);
+}
@@ -0,0 +1,36 @@
+//! The LED at row 1, column 1 of the matrix, driven through the GPIO registers generated from the SVD file.
+
+use crate::nrf52833::p0::{self, pin_cnf};
+
+// micro:bit LED matrix pins
+pub const ROW1_PIN: u32 = 21; // P0.21
+pub const COL1_PIN: u32 = 28; // P0.28
+
+/// Output, input buffer connected, no pull, standard drive, no sensing: what example 02 writes as `1`
+const OUTPUT: u32 = pin_cnf::dir::OUTPUT << pin_cnf::dir::POS;
+
+/// Make the row and column pins outputs, and activate the column
+#[inline(always)]
+pub fn init() {
+    // Nothing else in this program uses GPIO port 0
+    unsafe {
+        // Configure P0.21 (Row 1) as output
+        p0::PIN_CNF.offset(ROW1_PIN as usize).write(OUTPUT);
+
+        // Configure P0.28 (Col 1) as output and set low (column active)
+        p0::PIN_CNF.offset(COL1_PIN as usize).write(OUTPUT);
+        p0::OUTCLR.write(1 << COL1_PIN); // Set column active
+    }
+}
+
+/// Turn the LED on (set row low)
+#[inline(always)]
+pub fn on() {
+    unsafe { p0::OUTCLR.write(1 << ROW1_PIN) }
+}
+
+/// Turn the LED off (set row high)
+#[inline(always)]
+pub fn off() {
+    unsafe { p0::OUTSET.write(1 << ROW1_PIN) }
+}
@@ -0,0 +1,24 @@
+#![no_std]
+
+//! The register code of the LED example, apart from the runtime, so it can be tested on the PC.
+//!
+//! `Reg<T>` and its mock are the same as example 02's, copied so that each example stands on its own. The
+//! addresses are not typed in: `build.rs` generates them from the nRF52833 SVD file.
+//!
+//! - [`reg`] - a register at a fixed address, with volatile reads and writes
+//! - [`nrf52833`] - the registers, their fields and their values, generated from `doc/nrf52833.svd`
+//! - [`led`] - the writes that drive the LED
+//! - [`mock`] - on the PC only, the log of register accesses the host tests check
+//! - [`svd`] - on the PC only, the SVD reader and generator `build.rs` uses, for the host tests
+//!
+//! See `tests/registers.rs` and `tests/svd.rs` for the host tests.
+
+pub mod led;
+pub mod nrf52833;
+pub mod reg;
+
+// The mock and the SVD reader need `std` so they only exist in host builds
+#[cfg(not(target_os = "none"))]
+pub mod mock;
+#[cfg(not(target_os = "none"))]
+pub mod svd;
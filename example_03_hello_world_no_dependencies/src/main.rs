@@ -1,11 +1,15 @@
-#![no_main]
-#![no_std]
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use example_03_no_dependencies::led;
 
 // ============================================================================
 // VECTOR TABLE & RESET HANDLER - Replacing cortex-m-rt
 // ============================================================================
 
 // External symbols from linker script
+#[cfg(target_os = "none")]
 extern "C" {
     static mut _sbss: u32; // Start of .bss section
     static mut _ebss: u32; // End of .bss section
@@ -16,6 +20,7 @@ extern "C" {
 }
 
 // Reset handler - this is where execution begins after power-on
+#[cfg(target_os = "none")]
 #[no_mangle]
 pub unsafe extern "C" fn Reset() -> ! {
     // 1. Initialize RAM (.data and .bss sections)
@@ -45,6 +50,7 @@ pub unsafe extern "C" fn Reset() -> ! {
 }
 
 // Default handler for unused interrupts
+#[cfg(target_os = "none")]
 #[no_mangle]
 pub extern "C" fn DefaultHandler() -> ! {
     loop {}
@@ -52,6 +58,7 @@ pub extern "C" fn DefaultHandler() -> ! {
 
 // ARM Cortex-M Vector Table - using function pointers
 // This MUST be placed at address 0x00000000 (start of flash)
+#[cfg(target_os = "none")]
 #[repr(C)]
 pub struct VectorTable {
     pub stack_pointer: u32,
@@ -69,6 +76,7 @@ pub struct VectorTable {
     pub sys_tick: unsafe extern "C" fn() -> !,
 }
 
+#[cfg(target_os = "none")]
 #[link_section = ".vector_table"]
 #[no_mangle]
 pub static VECTOR_TABLE: VectorTable = VectorTable {
@@ -88,39 +96,24 @@ pub static VECTOR_TABLE: VectorTable = VectorTable {
 };
 
 // Custom panic handler - replaces panic-halt crate
+#[cfg(target_os = "none")]
 #[panic_handler]
 fn panic(_: &core::panic::PanicInfo) -> ! {
     // Just halt - minimal panic handling
     loop {}
 }
 
-// GPIO registers for nRF52833
-const GPIO_P0_OUTSET: *mut u32 = 0x5000_0508 as *mut u32;
-const GPIO_P0_OUTCLR: *mut u32 = 0x5000_050C as *mut u32;
-const GPIO_P0_PIN_CNF: *mut u32 = 0x5000_0700 as *mut u32;
-
-// micro:bit LED matrix pins
-const ROW1_PIN: u32 = 21; // P0.21
-const COL1_PIN: u32 = 28; // P0.28
+// The GPIO registers and pins are in src/led.rs, with addresses generated from the SVD file
 
+#[cfg(target_os = "none")]
 #[no_mangle]
 pub extern "C" fn main() -> ! {
-    unsafe {
-        // Configure P0.21 (Row 1) as output
-        let pin_cnf_21 = GPIO_P0_PIN_CNF.add(ROW1_PIN as usize);
-        core::ptr::write_volatile(pin_cnf_21, 1); // DIR=1 (output)
-
-        // Configure P0.28 (Col 1) as output and set low (column active)
-        let pin_cnf_28 = GPIO_P0_PIN_CNF.add(COL1_PIN as usize);
-        core::ptr::write_volatile(pin_cnf_28, 1); // DIR=1 (output)
-        core::ptr::write_volatile(GPIO_P0_OUTCLR, 1 << COL1_PIN); // Set column active
-    }
+    // Row 1 and column 1 as outputs, column active
+    led::init();
 
     loop {
-        unsafe {
-            // Turn LED on (set row low)
-            core::ptr::write_volatile(GPIO_P0_OUTCLR, 1 << ROW1_PIN);
-        }
+        // Turn LED on (set row low)
+        led::on();
 
         // Delay ~1s (on time)
         for _ in 0..400_00 {
@@ -129,10 +122,8 @@ pub extern "C" fn main() -> ! {
             }
         }
 
-        unsafe {
-            // Turn LED off (set row high)
-            core::ptr::write_volatile(GPIO_P0_OUTSET, 1 << ROW1_PIN);
-        }
+        // Turn LED off (set row high)
+        led::off();
 
         // Delay ~1s (off time)
         for _ in 0..800_00 {
@@ -142,3 +133,7 @@ pub extern "C" fn main() -> ! {
         }
     }
 }
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
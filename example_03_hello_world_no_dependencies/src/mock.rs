@@ -0,0 +1,78 @@
+//! The registers on the PC: a log of every access made through [`Reg`](crate::reg::Reg), for host tests.
+//!
+//! Each test runs in its own thread, so the log and the register values are per thread, and tests running in
+//! parallel do not see each other's accesses. A register reads as the last value written to it, or one given
+//! with [`set`], or 0.
+
+extern crate std;
+
+use std::{cell::RefCell, collections::HashMap, vec::Vec};
+
+/// One register access, in the order the code made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read { address: usize, value: u32 },
+    Write { address: usize, value: u32 },
+}
+
+#[derive(Default)]
+struct State {
+    log: Vec<Access>,
+    values: HashMap<usize, u32>,
+}
+
+std::thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Take the accesses made since the last call, leaving the register values as they are
+pub fn take() -> Vec<Access> {
+    STATE.with(|state| std::mem::take(&mut state.borrow_mut().log))
+}
+
+/// Take only the writes, as `(address, value)`
+pub fn take_writes() -> Vec<(usize, u32)> {
+    take()
+        .into_iter()
+        .filter_map(|access| match access {
+            Access::Write { address, value } => Some((address, value)),
+            Access::Read { .. } => None,
+        })
+        .collect()
+}
+
+/// Give the register at `address` a value for the code to read, without logging a write
+pub fn set(address: usize, value: u32) {
+    STATE.with(|state| state.borrow_mut().values.insert(address, value));
+}
+
+/// Forget every access and every value
+pub fn reset() {
+    STATE.with(|state| *state.borrow_mut() = State::default());
+}
+
+pub(crate) fn read(address: usize, size: usize) -> u32 {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let value = state.values.get(&address).copied().unwrap_or(0) & mask(size);
+        state.log.push(Access::Read { address, value });
+        value
+    })
+}
+
+pub(crate) fn write(address: usize, size: usize, value: u32) {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let value = value & mask(size);
+        state.values.insert(address, value);
+        state.log.push(Access::Write { address, value });
+    })
+}
+
+fn mask(size: usize) -> u32 {
+    match size {
+        1 => 0xFF,
+        2 => 0xFFFF,
+        _ => 0xFFFF_FFFF,
+    }
+}
@@ -0,0 +1,14 @@
+//! The nRF52833's registers, generated at build time from the SVD file in `doc/` by `build.rs`.
+//!
+//! Each peripheral is a module with its `BASE` address and a [`Reg`](crate::reg::Reg) for each register. A
+//! register array such as `PIN_CNF[n]` is its first element, with `offset` for the rest and `PIN_CNF_LEN` for the
+//! count. A register with fields has a module of the same name, lowercase, with a module for each field: its
+//! `POS` and `MASK`, and its enumerated values, unshifted.
+//!
+//! ```ignore
+//! use example_03_no_dependencies::nrf52833::p0::{self, pin_cnf};
+//!
+//! p0::PIN_CNF.offset(21).write(pin_cnf::dir::OUTPUT << pin_cnf::dir::POS);
+//! ```
+
+include!(concat!(env!("OUT_DIR"), "/nrf52833.rs"));
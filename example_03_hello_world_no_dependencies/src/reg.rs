@@ -0,0 +1,89 @@
+//! A memory-mapped register: an address, and the width of the accesses made to it.
+//!
+//! On the micro:bit, [`Reg::write`] is `core::ptr::write_volatile` to the address and [`Reg::read`] is
+//! `core::ptr::read_volatile`, inlined, so a `Reg` compiles to the same `str` and `ldr` instructions as the raw
+//! pointers it replaces. Built for the PC, where there are no registers, the same calls go to [`crate::mock`]
+//! instead, which records them for the host tests to check.
+
+use core::marker::PhantomData;
+
+/// A register whose accesses are `T` wide: `u8`, `u16` or `u32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reg<T> {
+    address: usize,
+    width: PhantomData<T>,
+}
+
+/// The widths a register can be accessed at
+pub trait Width: Copy {
+    fn to_u32(self) -> u32;
+    fn from_u32(value: u32) -> Self;
+}
+
+macro_rules! width {
+    ($($t:ty),*) => {$(
+        impl Width for $t {
+            fn to_u32(self) -> u32 {
+                self.into()
+            }
+
+            fn from_u32(value: u32) -> Self {
+                value as $t
+            }
+        }
+    )*};
+}
+
+width!(u8, u16, u32);
+
+impl<T: Width> Reg<T> {
+    #[inline(always)]
+    pub const fn at(address: usize) -> Self {
+        Self {
+            address,
+            width: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn address(self) -> usize {
+        self.address
+    }
+
+    /// Register `index` of an array of them, such as `PIN_CNF[index]`. Unchecked, like `pointer::add`, so debug
+    /// builds do not add overflow checks the raw pointers did not have.
+    #[inline(always)]
+    pub const fn offset(self, index: usize) -> Self {
+        Self::at(self.address.wrapping_add(index.wrapping_mul(core::mem::size_of::<T>())))
+    }
+
+    /// Read the register.
+    ///
+    /// # Safety
+    ///
+    /// `self` must be a register that is readable at width `T`, and reading it must not disturb other code: some
+    /// registers clear themselves when read.
+    #[inline(always)]
+    pub unsafe fn read(self) -> T {
+        #[cfg(target_os = "none")]
+        return core::ptr::read_volatile(self.address as *const T);
+
+        #[cfg(not(target_os = "none"))]
+        return T::from_u32(crate::mock::read(self.address, core::mem::size_of::<T>()));
+    }
+
+    /// Write `value` to the register.
+    ///
+    /// # Safety
+    ///
+    /// `self` must be a register that is writable at width `T`, and owned by the caller: the write can change
+    /// what the hardware does for any code using it.
+    #[inline(always)]
+    pub unsafe fn write(self, value: T) {
+        #[cfg(target_os = "none")]
+        core::ptr::write_volatile(self.address as *mut T, value);
+
+        #[cfg(not(target_os = "none"))]
+        crate::mock::write(self.address, core::mem::size_of::<T>(), value.to_u32());
+    }
+}
@@ -0,0 +1,601 @@
+//! A CMSIS-SVD reader and register module generator, with no dependencies.
+//!
+//! An SVD file is XML describing a chip's peripherals: each one's base address, its registers' offsets and sizes,
+//! and their fields' bit positions and named values. Nordic publishes one for the nRF52833. `build.rs` reads it with
+//! this module and writes the Rust constants `src/nrf52833.rs` includes; `tests/svd.rs` reads it again to check the
+//! addresses typed into the examples.
+//!
+//! The XML reader handles what SVD files use: elements, attributes, text, comments and the five predefined entities.
+//! Of SVD itself it reads peripherals (with `derivedFrom`), registers (with `dim` arrays), fields (`lsb`/`msb`,
+//! `bitOffset`/`bitWidth` or `bitRange`) and enumerated values. Register clusters are skipped: none of the
+//! peripherals the examples use has one.
+//!
+//! This file is also `build.rs`'s, through a `#[path]` attribute, so it only uses `std`.
+
+extern crate std;
+
+use std::{
+    fmt::{self, Write},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// What is wrong with an SVD file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvdError {
+    /// Not well-formed XML, at this byte offset
+    Xml { offset: usize, problem: &'static str },
+    /// An element without a child SVD requires
+    Missing { element: String, child: &'static str },
+    /// A number that does not parse
+    Number(String),
+    /// `derivedFrom` names a peripheral that is not in the file
+    NoSuchPeripheral(String),
+    /// A register the generated code cannot describe
+    Unsupported { register: String, problem: &'static str },
+}
+
+impl fmt::Display for SvdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xml { offset, problem } => write!(f, "bad XML at byte {}: {}", offset, problem),
+            Self::Missing { element, child } => write!(f, "<{}> has no <{}>", element, child),
+            Self::Number(text) => write!(f, "{:?} is not a number", text),
+            Self::NoSuchPeripheral(name) => write!(f, "no peripheral {} to derive from", name),
+            Self::Unsupported { register, problem } => write!(f, "register {}: {}", register, problem),
+        }
+    }
+}
+
+// ============================================================================
+// XML
+// ============================================================================
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The trimmed text of child `name`, if there is one
+    fn text_of(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim())
+    }
+
+    fn require(&self, name: &'static str) -> Result<&str, SvdError> {
+        self.text_of(name).ok_or_else(|| SvdError::Missing {
+            element: self.name.clone(),
+            child: name,
+        })
+    }
+
+    fn number(&self, name: &'static str) -> Result<Option<u32>, SvdError> {
+        self.text_of(name).map(number).transpose()
+    }
+}
+
+/// Parse `text` into its root element
+fn parse_xml(text: &str) -> Result<Element, SvdError> {
+    let error = |offset, problem| SvdError::Xml { offset, problem };
+    let mut stack = std::vec![Element::default()];
+    let mut at = 0;
+
+    while at < text.len() {
+        let rest = &text[at..];
+        if !rest.starts_with('<') {
+            let end = rest.find('<').map_or(text.len(), |end| at + end);
+            let top = stack.last_mut().expect("the document is always on the stack");
+            top.text.push_str(&unescape(&text[at..end]));
+            at = end;
+            continue;
+        }
+
+        // Declarations, comments and DOCTYPEs carry nothing SVD needs
+        let skip = [("<?", "?>"), ("<!--", "-->"), ("<!", ">")];
+        if let Some(&(_, close)) = skip.iter().find(|(open, _)| rest.starts_with(open)) {
+            let end = rest
+                .find(close)
+                .ok_or(error(at, "unterminated declaration or comment"))?;
+            at += end + close.len();
+            continue;
+        }
+
+        let end = rest.find('>').ok_or(error(at, "unterminated tag"))?;
+        let tag = &rest[1..end];
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack
+                .pop()
+                .filter(|_| !stack.is_empty())
+                .ok_or(error(at, "end tag at top level"))?;
+            if element.name != name.trim() {
+                return Err(error(at, "end tag does not match the start tag"));
+            }
+            stack.last_mut().expect("checked above").children.push(element);
+        } else {
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_tag(tag).ok_or(error(at, "malformed start tag"))?;
+            if empty {
+                stack
+                    .last_mut()
+                    .expect("the document is always on the stack")
+                    .children
+                    .push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+        at += end + 1;
+    }
+
+    let mut document = stack
+        .pop()
+        .filter(|_| stack.is_empty())
+        .ok_or(error(at, "unclosed element"))?;
+    match document.children.len() {
+        1 => Ok(document.children.remove(0)),
+        _ => Err(error(0, "not exactly one root element")),
+    }
+}
+
+/// A start tag's name and attributes, from between `<` and `>`
+fn parse_tag(tag: &str) -> Option<Element> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut element = Element {
+        name: tag[..name_end].to_string(),
+        ..Element::default()
+    };
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|&quote| quote == '"' || quote == '\'')?;
+        let (value, after) = after[1..].split_once(quote)?;
+        element.attributes.push((key.trim().to_string(), unescape(value)));
+        rest = after.trim_start();
+    }
+    (!element.name.is_empty()).then_some(element)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// An SVD number: decimal, `0x` hexadecimal, or `#` binary
+fn number(text: &str) -> Result<u32, SvdError> {
+    let parsed = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16)
+    } else if let Some(binary) = text.strip_prefix('#') {
+        u32::from_str_radix(binary, 2)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|_| SvdError::Number(text.to_string()))
+}
+
+// ============================================================================
+// SVD
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub name: String,
+    pub peripherals: Vec<Peripheral>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peripheral {
+    pub name: String,
+    pub description: String,
+    pub base: u32,
+    pub registers: Vec<Register>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    /// Without the `[%s]` of an array
+    pub name: String,
+    pub description: String,
+    pub offset: u32,
+    /// In bits
+    pub size: u32,
+    /// How many registers: more than 1 for an array
+    pub dim: u32,
+    /// Bytes from one array element to the next
+    pub increment: u32,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub description: String,
+    pub lsb: u32,
+    pub width: u32,
+    pub values: Vec<Value>,
+}
+
+/// An enumerated value of a field, unshifted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value {
+    pub name: String,
+    pub description: String,
+    pub value: u32,
+}
+
+/// What is at an address: a peripheral's base, or one of its registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub peripheral: &'a str,
+    pub register: Option<&'a str>,
+    /// The element, for a register array
+    pub index: Option<u32>,
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.peripheral)?;
+        if let Some(register) = self.register {
+            write!(f, ".{}", register)?;
+        }
+        if let Some(index) = self.index {
+            write!(f, "[{}]", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl Device {
+    pub fn parse(text: &str) -> Result<Self, SvdError> {
+        let root = parse_xml(text)?;
+        let size = root.number("size")?.unwrap_or(32);
+        let list = root.child("peripherals").ok_or(SvdError::Missing {
+            element: root.name.clone(),
+            child: "peripherals",
+        })?;
+
+        let mut peripherals: Vec<Peripheral> = Vec::new();
+        for element in list.children("peripheral") {
+            let size = element.number("size")?.unwrap_or(size);
+            let mut peripheral = Peripheral {
+                name: element.require("name")?.to_string(),
+                description: description(element),
+                base: number(element.require("baseAddress")?)?,
+                registers: Vec::new(),
+            };
+            // A derived peripheral has the registers of the one it names, at its own base address
+            if let Some(from) = element.attribute("derivedFrom") {
+                let original = peripherals
+                    .iter()
+                    .find(|original| original.name == from)
+                    .ok_or_else(|| SvdError::NoSuchPeripheral(from.to_string()))?;
+                peripheral.registers = original.registers.clone();
+                if peripheral.description.is_empty() {
+                    peripheral.description = original.description.clone();
+                }
+            }
+            if let Some(registers) = element.child("registers") {
+                for register in registers.children("register") {
+                    peripheral.registers.extend(parse_register(register, size)?);
+                }
+            }
+            peripherals.push(peripheral);
+        }
+
+        Ok(Self {
+            name: root.text_of("name").unwrap_or_default().to_string(),
+            peripherals,
+        })
+    }
+
+    pub fn peripheral(&self, name: &str) -> Option<&Peripheral> {
+        self.peripherals.iter().find(|peripheral| peripheral.name == name)
+    }
+
+    /// The address of `peripheral`'s `register`, or element `index` of it for an array
+    pub fn address(&self, peripheral: &str, register: &str, index: u32) -> Option<u32> {
+        let peripheral = self.peripheral(peripheral)?;
+        let register = peripheral
+            .registers
+            .iter()
+            .find(|candidate| candidate.name == register)?;
+        (index < register.dim).then(|| peripheral.base + register.offset + index * register.increment)
+    }
+
+    /// Everything at `address`: a peripheral base can also be its first register
+    pub fn at(&self, address: u32) -> Vec<Location<'_>> {
+        let mut found = Vec::new();
+        for peripheral in &self.peripherals {
+            if peripheral.base == address {
+                found.push(Location {
+                    peripheral: &peripheral.name,
+                    register: None,
+                    index: None,
+                });
+            }
+            for register in &peripheral.registers {
+                let start = peripheral.base + register.offset;
+                let Some(from_start) = address.checked_sub(start) else {
+                    continue;
+                };
+                let index = from_start / register.increment.max(1);
+                if index < register.dim && from_start % register.increment.max(1) == 0 {
+                    found.push(Location {
+                        peripheral: &peripheral.name,
+                        register: Some(&register.name),
+                        index: (register.dim > 1).then_some(index),
+                    });
+                }
+            }
+        }
+        found
+    }
+}
+
+fn description(element: &Element) -> String {
+    // Descriptions wrap over several lines in Nordic's file
+    element
+        .text_of("description")
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One register, or for `NAME%s` without brackets, one for each index
+fn parse_register(element: &Element, size: u32) -> Result<Vec<Register>, SvdError> {
+    let name = element.require("name")?;
+    let dim = element.number("dim")?.unwrap_or(1);
+    let size = element.number("size")?.unwrap_or(size);
+    let mut register = Register {
+        name: name.replace("[%s]", ""),
+        description: description(element),
+        offset: number(element.require("addressOffset")?)?,
+        size,
+        dim,
+        increment: element.number("dimIncrement")?.unwrap_or(size / 8),
+        fields: Vec::new(),
+    };
+    if let Some(fields) = element.child("fields") {
+        for field in fields.children("field") {
+            register.fields.push(parse_field(field)?);
+        }
+    }
+
+    if !name.contains("%s") || name.contains("[%s]") {
+        return Ok(std::vec![register]);
+    }
+    Ok((0..dim)
+        .map(|index| Register {
+            name: name.replace("%s", &index.to_string()),
+            offset: register.offset + index * register.increment,
+            dim: 1,
+            ..register.clone()
+        })
+        .collect())
+}
+
+fn parse_field(element: &Element) -> Result<Field, SvdError> {
+    let (lsb, width) = if let (Some(lsb), Some(msb)) = (element.number("lsb")?, element.number("msb")?) {
+        (lsb, msb.saturating_sub(lsb) + 1)
+    } else if let Some(offset) = element.number("bitOffset")? {
+        (offset, element.number("bitWidth")?.unwrap_or(1))
+    } else {
+        // [msb:lsb]
+        let range = element.require("bitRange")?;
+        let (msb, lsb) = range
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split_once(':')
+            .ok_or_else(|| SvdError::Number(range.to_string()))?;
+        let (msb, lsb) = (number(msb)?, number(lsb)?);
+        (lsb, msb.saturating_sub(lsb) + 1)
+    };
+
+    // Nordic gives read and write values separately: the same names can appear in both
+    let mut values: Vec<Value> = Vec::new();
+    for list in element.children("enumeratedValues") {
+        for value in list.children("enumeratedValue") {
+            let Some(text) = value.text_of("value") else {
+                continue; // isDefault: no single value
+            };
+            let value = Value {
+                name: value.require("name")?.to_string(),
+                description: description(value),
+                value: number(text)?,
+            };
+            if !values.iter().any(|existing| existing.name == value.name) {
+                values.push(value);
+            }
+        }
+    }
+
+    Ok(Field {
+        name: element.require("name")?.to_string(),
+        description: description(element),
+        lsb,
+        width,
+        values,
+    })
+}
+
+// ============================================================================
+// GENERATOR
+// ============================================================================
+
+/// The Rust module for `peripherals` of `device`: a module for each peripheral with its base address and a
+/// `Reg` for each register, and in it a module for each register with fields, holding each field's position, mask
+/// and enumerated values.
+pub fn generate(device: &Device, peripherals: &[&str], source: &str) -> Result<String, SvdError> {
+    let mut out = String::new();
+    let _ = writeln!(out, "// Generated by build.rs from {}. Do not edit.", source);
+    let _ = writeln!(out, "//");
+    let _ = writeln!(
+        out,
+        "// Field values are unshifted: write `VALUE << POS`, read `(register & MASK) >> POS`."
+    );
+    for &name in peripherals {
+        let peripheral = device
+            .peripheral(name)
+            .ok_or_else(|| SvdError::NoSuchPeripheral(name.to_string()))?;
+        out.push('\n');
+        generate_peripheral(&mut out, peripheral)?;
+    }
+    Ok(out)
+}
+
+fn generate_peripheral(out: &mut String, peripheral: &Peripheral) -> Result<(), SvdError> {
+    doc(out, 0, &peripheral.description);
+    let _ = writeln!(out, "pub mod {} {{", identifier(&peripheral.name.to_lowercase()));
+    let _ = writeln!(out, "    use crate::reg::Reg;");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    pub const BASE: usize = {};", hex(peripheral.base));
+
+    for register in &peripheral.registers {
+        let width = match register.size {
+            8 => "u8",
+            16 => "u16",
+            32 => "u32",
+            _ => return Err(unsupported(register, "not 8, 16 or 32 bits wide")),
+        };
+        if register.dim > 1 && register.increment != register.size / 8 {
+            return Err(unsupported(register, "array elements not next to each other"));
+        }
+        let name = constant(&register.name);
+        let address = peripheral.base + register.offset;
+        let _ = writeln!(out);
+        doc(out, 4, &register.description);
+        let _ = writeln!(
+            out,
+            "    pub const {}: Reg<{}> = Reg::at({});",
+            name,
+            width,
+            hex(address)
+        );
+        if register.dim > 1 {
+            let _ = writeln!(out, "    pub const {}_LEN: usize = {};", name, register.dim);
+        }
+    }
+
+    for register in peripheral
+        .registers
+        .iter()
+        .filter(|register| !register.fields.is_empty())
+    {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "    /// Fields of [`{0}`](super::{0})", constant(&register.name));
+        let _ = writeln!(out, "    pub mod {} {{", identifier(&register.name.to_lowercase()));
+        for (number, field) in register.fields.iter().enumerate() {
+            if number > 0 {
+                let _ = writeln!(out);
+            }
+            let mask = (u32::MAX >> (32 - field.width.clamp(1, 32))) << field.lsb;
+            doc(out, 8, &field.description);
+            // The SVD often names a register's only field after the register, such as BITMODE.BITMODE
+            if field.name == register.name {
+                let _ = writeln!(out, "        #[allow(clippy::module_inception)]");
+            }
+            let _ = writeln!(out, "        pub mod {} {{", identifier(&field.name.to_lowercase()));
+            let _ = writeln!(out, "            pub const POS: u32 = {};", field.lsb);
+            let _ = writeln!(out, "            pub const MASK: u32 = {};", hex(mask));
+            for value in &field.values {
+                doc(out, 12, &value.description);
+                let _ = writeln!(
+                    out,
+                    "            pub const {}: u32 = {};",
+                    constant(&value.name),
+                    value.value
+                );
+            }
+            let _ = writeln!(out, "        }}");
+        }
+        let _ = writeln!(out, "    }}");
+    }
+
+    let _ = writeln!(out, "}}");
+    Ok(())
+}
+
+fn unsupported(register: &Register, problem: &'static str) -> SvdError {
+    SvdError::Unsupported {
+        register: register.name.clone(),
+        problem,
+    }
+}
+
+fn doc(out: &mut String, indent: usize, text: &str) {
+    if !text.is_empty() {
+        let _ = writeln!(out, "{:indent$}/// {}", "", text, indent = indent);
+    }
+}
+
+/// `0x5000_0508`
+fn hex(value: u32) -> String {
+    format!("0x{:04X}_{:04X}", value >> 16, value & 0xFFFF)
+}
+
+/// An SVD name as a constant: `Pullup` to `PULLUP`, `NotRunning` to `NOT_RUNNING`, `16Bit` to `_16BIT`
+fn constant(name: &str) -> String {
+    let mut constant = String::new();
+    let mut previous = ' ';
+    for c in name.chars() {
+        if previous.is_ascii_lowercase() && c.is_ascii_uppercase() {
+            constant.push('_');
+        }
+        constant.push(if c.is_ascii_alphanumeric() {
+            c.to_ascii_uppercase()
+        } else {
+            '_'
+        });
+        previous = c;
+    }
+    let name = constant;
+    match name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+/// An SVD name as a module: Rust keywords get a trailing underscore
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+        "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self",
+        "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+    ];
+    let name = constant(name).to_lowercase();
+    match KEYWORDS.contains(&name.as_str()) {
+        true => format!("{}_", name),
+        false => name,
+    }
+}
@@ -0,0 +1,42 @@
+//! Host tests for the LED's register writes. The code is example 02's (see its `tests/registers.rs` for the
+//! register and mock tests); this checks the writes this example's `main` makes, built into this crate.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use example_03_no_dependencies::{
+    led,
+    mock::{self, Access},
+};
+
+// GPIO P0 at 0x5000_0000: OUTSET +0x508, OUTCLR +0x50C, PIN_CNF[n] +0x700 + 4n
+const OUTSET: usize = 0x5000_0508;
+const OUTCLR: usize = 0x5000_050C;
+const PIN_CNF_21: usize = 0x5000_0754;
+const PIN_CNF_28: usize = 0x5000_0770;
+
+#[test]
+fn main_configures_then_toggles_the_row() {
+    // Up to the second time round the loop
+    led::init();
+    led::on();
+    led::off();
+    led::on();
+    assert_eq!(
+        mock::take_writes(),
+        [
+            (PIN_CNF_21, 1),
+            (PIN_CNF_28, 1),
+            (OUTCLR, 1 << 28),
+            (OUTCLR, 1 << 21),
+            (OUTSET, 1 << 21),
+            (OUTCLR, 1 << 21),
+        ]
+    );
+}
+
+#[test]
+fn nothing_is_read() {
+    led::init();
+    led::off();
+    assert!(mock::take().iter().all(|access| matches!(access, Access::Write { .. })));
+}
@@ -0,0 +1,393 @@
+//! Host tests for the SVD reader and the generated registers, and the check that every peripheral address typed
+//! into the examples is where the SVD file says, under the name it says.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use example_03_no_dependencies::{
+    nrf52833::{clock, p0, p1, rtc1, timer4},
+    svd::{generate, Device, Location, SvdError},
+};
+
+fn repository() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+fn device() -> Device {
+    let text = fs::read_to_string(repository().join("doc/nrf52833.svd")).unwrap();
+    Device::parse(&text).unwrap()
+}
+
+// ============================================================================
+// READING THE SVD FILE
+// ============================================================================
+
+#[test]
+fn gpio_port() {
+    let device = device();
+    assert_eq!(device.name, "nrf52833");
+    let p0 = device.peripheral("P0").unwrap();
+    assert_eq!(p0.base, 0x5000_0000);
+
+    let pin_cnf = p0.registers.iter().find(|register| register.name == "PIN_CNF").unwrap();
+    assert_eq!(
+        (pin_cnf.offset, pin_cnf.dim, pin_cnf.increment, pin_cnf.size),
+        (0x700, 32, 4, 32)
+    );
+    let pull = pin_cnf.fields.iter().find(|field| field.name == "PULL").unwrap();
+    assert_eq!((pull.lsb, pull.width), (2, 2));
+    let values: Vec<(&str, u32)> = pull
+        .values
+        .iter()
+        .map(|value| (value.name.as_str(), value.value))
+        .collect();
+    assert_eq!(values, [("Disabled", 0), ("Pulldown", 1), ("Pullup", 3)]);
+
+    assert_eq!(device.address("P0", "PIN_CNF", 21), Some(0x5000_0754));
+    assert_eq!(device.address("P0", "PIN_CNF", 32), None);
+    assert_eq!(device.address("P0", "OUTSET", 0), Some(0x5000_0508));
+}
+
+#[test]
+fn derived_peripherals_have_their_own_base() {
+    let device = device();
+    assert_eq!(device.address("P1", "OUTSET", 0), Some(0x5000_0808));
+    assert_eq!(device.address("RTC1", "CC", 1), Some(0x4001_1544));
+    assert_eq!(device.peripheral("P1").unwrap().description, "GPIO Port 2");
+
+    let locations: Vec<String> = device.at(0x4001_1544).iter().map(Location::to_string).collect();
+    assert_eq!(locations, ["RTC1.CC[1]"]);
+    // CLOCK's first register is at its base, which POWER shares
+    let locations: Vec<String> = device.at(0x4000_0000).iter().map(Location::to_string).collect();
+    assert_eq!(locations, ["CLOCK", "CLOCK.TASKS_HFCLKSTART", "POWER"]);
+}
+
+#[test]
+fn generated_registers_match_the_svd() {
+    let device = device();
+    let address = |peripheral, register, index| device.address(peripheral, register, index).unwrap() as usize;
+    assert_eq!(p0::OUTSET.address(), address("P0", "OUTSET", 0));
+    assert_eq!(p0::PIN_CNF.offset(28).address(), address("P0", "PIN_CNF", 28));
+    assert_eq!(p1::BASE, 0x5000_0300);
+    assert_eq!(p1::PIN_CNF_LEN, 32);
+    assert_eq!(rtc1::CC.offset(1).address(), address("RTC1", "CC", 1));
+    assert_eq!(clock::LFCLKSRC.address(), address("CLOCK", "LFCLKSRC", 0));
+    assert_eq!(timer4::PRESCALER.address(), address("TIMER4", "PRESCALER", 0));
+
+    assert_eq!(p0::pin_cnf::dir::OUTPUT << p0::pin_cnf::dir::POS, 1);
+    assert_eq!(p0::pin_cnf::pull::PULLUP << p0::pin_cnf::pull::POS, 0b1100);
+    assert_eq!(p0::pin_cnf::sense::MASK, 0x0003_0000);
+    assert_eq!(clock::lfclksrc::src::XTAL, 1);
+    assert_eq!(timer4::bitmode::bitmode::_32BIT, 3);
+}
+
+/// Every way of writing a field, with the device's default size overridden
+const SMALL: &str = r#"<?xml version="1.0"?>
+<device>
+  <name>small</name>
+  <size>16</size>
+  <peripherals>
+    <peripheral>
+      <name>UNIT</name>
+      <description>A unit &amp; its registers</description>
+      <baseAddress>0x40001000</baseAddress>
+      <registers>
+        <register>
+          <name>CTRL</name>
+          <addressOffset>0x0</addressOffset>
+          <fields>
+            <field><name>ON</name><bitOffset>0</bitOffset></field>
+            <field><name>LEVEL</name><bitRange>[7:4]</bitRange>
+              <enumeratedValues>
+                <enumeratedValue><name>Quiet</name><value>#0010</value></enumeratedValue>
+              </enumeratedValues>
+              <enumeratedValues>
+                <enumeratedValue><name>Quiet</name><value>2</value></enumeratedValue>
+                <enumeratedValue><name>Loud</name><value>0xA</value></enumeratedValue>
+              </enumeratedValues>
+            </field>
+          </fields>
+        </register>
+        <register>
+          <dim>2</dim>
+          <dimIncrement>8</dimIncrement>
+          <name>SLOT%s</name>
+          <addressOffset>0x10</addressOffset>
+          <size>32</size>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#;
+
+#[test]
+fn other_ways_of_writing_things() {
+    let device = Device::parse(SMALL).unwrap();
+    let unit = device.peripheral("UNIT").unwrap();
+    assert_eq!(unit.description, "A unit & its registers");
+
+    let level = &unit.registers[0].fields[1];
+    assert_eq!((level.lsb, level.width), (4, 4));
+    // The read and write lists both have Quiet: it appears once
+    let values: Vec<(&str, u32)> = level
+        .values
+        .iter()
+        .map(|value| (value.name.as_str(), value.value))
+        .collect();
+    assert_eq!(values, [("Quiet", 2), ("Loud", 10)]);
+
+    // SLOT%s without brackets is separate registers, not an array
+    assert_eq!(device.address("UNIT", "SLOT1", 0), Some(0x4000_1018));
+
+    let module = generate(&device, &["UNIT"], "small.svd").unwrap();
+    assert!(
+        module.contains("pub const CTRL: Reg<u16> = Reg::at(0x4000_1000);"),
+        "{}",
+        module
+    );
+    assert!(
+        module.contains("pub const SLOT1: Reg<u32> = Reg::at(0x4000_1018);"),
+        "{}",
+        module
+    );
+    assert!(module.contains("pub const MASK: u32 = 0x0000_00F0;"), "{}", module);
+    assert!(module.contains("pub const LOUD: u32 = 10;"), "{}", module);
+}
+
+#[test]
+fn bad_files_are_errors() {
+    assert!(matches!(
+        Device::parse("<device><name>x</device>"),
+        Err(SvdError::Xml { .. })
+    ));
+    assert!(matches!(
+        Device::parse("<device><name>x</name>"),
+        Err(SvdError::Xml { .. })
+    ));
+
+    let no_base = "<device><peripherals><peripheral><name>A</name></peripheral></peripherals></device>";
+    assert_eq!(
+        Device::parse(no_base),
+        Err(SvdError::Missing {
+            element: "peripheral".to_string(),
+            child: "baseAddress"
+        })
+    );
+
+    let derived = r#"<device><peripherals><peripheral derivedFrom="B"><name>A</name><baseAddress>0</baseAddress>
+        </peripheral></peripherals></device>"#;
+    assert_eq!(Device::parse(derived), Err(SvdError::NoSuchPeripheral("B".to_string())));
+
+    let bad_number = "<device><peripherals><peripheral><name>A</name><baseAddress>0x4000_0000</baseAddress>
+        </peripheral></peripherals></device>";
+    assert_eq!(
+        Device::parse(bad_number),
+        Err(SvdError::Number("0x4000_0000".to_string()))
+    );
+
+    // An array with gaps between its elements cannot be a Reg and offset
+    let gaps = SMALL.replace("SLOT%s", "SLOT[%s]");
+    assert!(matches!(
+        generate(&Device::parse(&gaps).unwrap(), &["UNIT"], "gaps.svd"),
+        Err(SvdError::Unsupported { .. })
+    ));
+    assert_eq!(
+        generate(&device(), &["QSPI"], "nrf52833.svd"),
+        Err(SvdError::NoSuchPeripheral("QSPI".to_string()))
+    );
+}
+
+// ============================================================================
+// THE ADDRESSES IN THE EXAMPLES
+// ============================================================================
+
+/// Where the nRF52833 has peripherals: APB from 0x4000_0000, and the GPIO ports on AHB at 0x5000_0000. Other
+/// numbers in the same range, like BLE access addresses and CRCs, are not addresses.
+const PERIPHERALS: [core::ops::Range<u32>; 2] = [0x4000_0000..0x4004_0000, 0x5000_0000..0x5000_1000];
+
+/// The peripheral addresses in `text`, `0x5000_0508` or `0x50000508`, with their line numbers and lines
+fn addresses(text: &str) -> Vec<(usize, &str, u32)> {
+    let mut found = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        for (start, _) in line.match_indices("0x") {
+            let digits: String = line[start + 2..]
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit() || *c == '_')
+                .filter(|&c| c != '_')
+                .collect();
+            let Ok(address) = u32::from_str_radix(&digits, 16) else {
+                continue;
+            };
+            if digits.len() == 8 && PERIPHERALS.iter().any(|range| range.contains(&address)) {
+                found.push((number + 1, line, address));
+            }
+        }
+    }
+    found
+}
+
+/// The name a line gives its one address: `const NAME` in Rust, `.equ NAME,` in assembly
+fn defined_name(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("const ").or_else(|| line.split_once(".equ "))?;
+    let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')?;
+    Some(&rest[..end])
+}
+
+/// Whether `name` fits `location`: it ends with the register, and any peripheral it mentions is the right one
+fn fits(device: &Device, name: &str, location: &Location) -> bool {
+    let normalise = |name: &str| name.replace('_', "").to_uppercase();
+    let name = normalise(name);
+    let name = name.strip_suffix("BASE").unwrap_or(&name);
+
+    let mut suffixes = Vec::new();
+    match (location.register, location.index) {
+        (None, _) => suffixes.push(location.peripheral.to_string()),
+        (Some(register), None) => suffixes.push(normalise(register)),
+        (Some(register), Some(index)) => {
+            suffixes.push(format!("{}{}", normalise(register), index));
+            if index == 0 {
+                suffixes.push(normalise(register));
+            }
+        }
+    }
+    suffixes.iter().any(|suffix| match name.strip_suffix(suffix.as_str()) {
+        Some(prefix) => {
+            let mentioned = |peripheral: &str| prefix.contains(peripheral);
+            mentioned(location.peripheral) || !device.peripherals.iter().any(|other| mentioned(&other.name))
+        }
+        None => false,
+    })
+}
+
+/// What is wrong with the addresses in `text`
+fn check(device: &Device, file: &str, text: &str) -> (usize, Vec<String>) {
+    let found = addresses(text);
+    let mut problems = Vec::new();
+    for &(line_number, line, address) in &found {
+        let locations = device.at(address);
+        let only_address = found.iter().filter(|(number, ..)| *number == line_number).count() == 1;
+        let name = defined_name(line).filter(|_| only_address);
+        if locations.is_empty() {
+            problems.push(format!(
+                "{}:{}: {:#010x} is not in the SVD file",
+                file, line_number, address
+            ));
+        } else if let Some(name) = name {
+            if !locations.iter().any(|location| fits(device, name, location)) {
+                let at: Vec<String> = locations.iter().map(Location::to_string).collect();
+                problems.push(format!(
+                    "{}:{}: {} is {:#010x}, which is {}",
+                    file,
+                    line_number,
+                    name,
+                    address,
+                    at.join(" and ")
+                ));
+            }
+        }
+    }
+    (found.len(), problems)
+}
+
+/// Rust and assembly files under `dir`
+fn sources(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            sources(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| ["rs", "s", "S"].contains(&extension.to_str().unwrap()))
+        {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn every_address_in_the_examples_matches_the_svd() {
+    let device = device();
+    let mut files = Vec::new();
+    for entry in fs::read_dir(repository()).unwrap().flatten() {
+        let name = entry.file_name().into_string().unwrap();
+        if name.starts_with("example_") {
+            files.push(entry.path().join("build.rs"));
+            sources(&entry.path().join("src"), &mut files);
+            sources(&entry.path().join("tests"), &mut files);
+        }
+    }
+    files.retain(|file| file.exists());
+    // This file's own addresses are made wrong on purpose, below
+    files.retain(|file| !file.ends_with("example_03_hello_world_no_dependencies/tests/svd.rs"));
+
+    let mut total = 0;
+    let mut problems = Vec::new();
+    let mut seen = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file).unwrap();
+        let shown = file.strip_prefix(repository()).unwrap().display().to_string();
+        let (found, wrong) = check(&device, &shown, &text);
+        total += found;
+        problems.extend(wrong);
+        seen.extend(
+            addresses(&text)
+                .into_iter()
+                .map(|(.., address)| (shown.clone(), address)),
+        );
+    }
+    assert!(problems.is_empty(), "\n{}", problems.join("\n"));
+    // Examples 02, 04, 25, 28 and 30 have them: a check that found none would be checking nothing
+    assert!(total > 40, "only {} addresses found", total);
+    for (file, address) in [
+        ("example_02_hello_world_minimal_dependencies/src/led.rs", 0x5000_0508),
+        ("example_04_hello_world_asm/src/main.rs", 0x5000_0700),
+    ] {
+        assert!(
+            seen.iter().any(|(shown, found)| shown == file && *found == address),
+            "{:#010x} not checked in {}",
+            address,
+            file
+        );
+    }
+}
+
+#[test]
+fn wrong_addresses_are_caught() {
+    let device = device();
+    let text = "
+        const GPIO_P0_OUTSET: *mut u32 = 0x5000_050C as *mut u32;
+        const RTC0_CC1: *mut u32 = 0x4001_1544 as *mut u32;
+            .equ GPIO_P0_PIN_CNF_BASE, 0x50000704
+        const TIMER_BASE: u32 = 0x4000_8004;
+        // QSPI is at 0x4002_9000 on the nRF52840
+    ";
+    let (found, problems) = check(&device, "text", text);
+    assert_eq!(found, 5);
+    assert_eq!(
+        problems,
+        [
+            "text:2: GPIO_P0_OUTSET is 0x5000050c, which is P0.OUTCLR",
+            "text:3: RTC0_CC1 is 0x40011544, which is RTC1.CC[1]",
+            "text:4: GPIO_P0_PIN_CNF_BASE is 0x50000704, which is P0.PIN_CNF[1]",
+            "text:5: TIMER_BASE is 0x40008004, which is TIMER0.TASKS_STOP",
+            "text:6: 0x40029000 is not in the SVD file",
+        ]
+    );
+
+    // Right names, in the forms the examples use
+    let text = "
+        const GPIO_P0_PIN_CNF: *mut u32 = 0x5000_0700 as *mut u32;
+        const PIN_CNF_21: usize = 0x5000_0754;
+        pub const P1_BASE: u32 = 0x5000_0300;
+        const CLOCK_TASKS_LFCLKSTART: *mut u32 = 0x4000_0008 as *mut u32;
+        const RTC1_EVENTS_COMPARE1: *mut u32 = 0x4001_1144 as *mut u32;
+    ";
+    assert_eq!(check(&device, "text", text), (5, Vec::new()));
+}
@@ -27,14 +27,15 @@ core::arch::global_asm!(
 vector_table:
     // ARM Cortex-M Vector Table Entry 0: Initial Main Stack Pointer (MSP)
     // Hardware loads this value into SP register on reset/power-up
-    // 0x20020000 = nRF52833 RAM base (0x20000000) + RAM size (128KB = 0x20000)
-    .long 0x20020000            
+    // _stack_start comes from minimal.ld: RAM base (0x20000000) + RAM size (128KB = 0x20000) = 0x20020000
+    .long _stack_start
 
     // ARM Cortex-M Vector Table Entry 1: Reset Handler Address  
     // Hardware jumps to this address after loading stack pointer
-    // +1 sets Thumb bit (LSB=1) indicating Thumb instruction set mode
+    // Bit 0 must be set (Thumb mode): .thumb_func on Reset already sets it in the symbol's value,
+    // so "Reset + 1" would clear it again and the core would fault before its first instruction
     // All Cortex-M code must be Thumb mode (16/32-bit mixed instructions)
-    .long Reset + 1             
+    .long Reset                 
 
     // Standard ARM Cortex-M vector table contains 48+ entries for exceptions/interrupts
     // This minimal implementation omits unused handlers to save flash space
@@ -50,23 +51,74 @@ vector_table:
 
 // .thumb_func directive: Marks following symbol as Thumb mode function
 // Enables proper disassembly and debugging tools recognition
+// Sets bit 0 of the symbol, so the vector table entry and any "bx" to it stay in Thumb mode
 // Required for all ARM Cortex-M functions (no ARM mode support)
 .thumb_func
 
 // .global directive: Export Reset symbol for linker visibility
-// Linker needs to resolve "Reset + 1" reference from vector table
+// Linker needs to resolve the "Reset" reference from vector table
 .global Reset
 
 Reset:
-    // Minimal reset handler with explicit stack pointer initialization
+    // Reset handler: everything Rust code expects to be true before its first instruction
     // Note: Hardware should set SP from vector table, but explicit set ensures reliability
-    
-    ldr r0, =0x20020000        // Load stack pointer address (RAM top)
+
+    ldr r0, =_stack_start      // Load stack pointer address (RAM top)
     mov sp, r0                 // Initialize stack pointer register
-    
-    // Skip .data/.bss initialization - no global variables used
+
+    // 1. Enable the FPU: full access to coprocessors CP10 and CP11
+    // thumbv7em-none-eabihf passes floats in FPU registers, so any Rust function taking or
+    // returning an f32 uses FPU instructions, which fault (UsageFault: NOCP) until this is done
+    .equ SCB_CPACR, 0xE000ED88  // Coprocessor Access Control Register
+    ldr r0, =SCB_CPACR
+    ldr r1, [r0]
+    orr r1, r1, #(0xF << 20)    // CP10 and CP11 = 0b11, full access
+    str r1, [r0]
+    dsb                         // Wait for the write to complete...
+    isb                         // ...and refetch, so the next instruction sees the FPU enabled
+
+    // 2. Copy .data from flash to RAM
+    // Initialised statics live in RAM, but RAM is undefined at power-up: their initial values
+    // are stored in flash at _sidata, and copied to _sdata.._edata (addresses from minimal.ld)
+    ldr r0, =_sdata             // Destination: start of .data in RAM
+    ldr r1, =_edata             // End of .data in RAM
+    ldr r2, =_sidata            // Source: initial values in flash
+copy_data:
+    cmp r0, r1                  // Reached the end?
+    bhs copy_data_done          // Unsigned >=: also stops at once if .data is empty
+    ldr r3, [r2], #4            // Load a word from flash, then r2 += 4
+    str r3, [r0], #4            // Store it in RAM, then r0 += 4
+    b copy_data
+copy_data_done:
+
+    // 3. Zero .bss
+    // Statics initialised to zero take no flash: Rust expects them to read as 0
+    ldr r0, =_sbss              // Start of .bss
+    ldr r1, =_ebss              // End of .bss
+    movs r2, #0
+zero_bss:
+    cmp r0, r1
+    bhs zero_bss_done
+    str r2, [r0], #4            // Store 0, then r0 += 4
+    b zero_bss
+zero_bss_done:
+
+    // 4. Call the constructors in .init_array
+    // Each entry is a function pointer (Thumb bit set), called in order with no arguments.
+    // They run after steps 2 and 3, so they can read and write statics, and before main.
+    // r4 and r5 are callee-saved: the constructors return with them unchanged
+    ldr r4, =__init_array_start
+    ldr r5, =__init_array_end
+call_constructors:
+    cmp r4, r5
+    bhs call_constructors_done
+    ldr r0, [r4], #4            // Load the function pointer, then r4 += 4
+    blx r0                      // Call it: bit 0 keeps the core in Thumb state
+    b call_constructors
+call_constructors_done:
+
     bl main                    // Branch to main application function
-    
+
     // Infinite loop if main function returns (should never happen)
 reset_loop:
     b reset_loop
@@ -135,7 +187,8 @@ blink_loop:
     str r1, [r0]               // Clear P0.21 output (LED ON state)
     
     // Software delay loop - approximately 1 second at 64MHz CPU clock
-    ldr r2, =8000000           // Load delay counter value
+    ldr r2, =ON_DELAY          // Address of the delay count: a Rust static in .data (see below)
+    ldr r2, [r2]               // Load delay counter value from RAM (8000000, copied there by Reset)
 delay1:
     subs r2, r2, #1            // Decrement counter and set flags
     bne delay1                 // Branch if not zero (continue loop)
@@ -147,7 +200,8 @@ delay1:
     str r1, [r0]               // Set P0.21 output high (LED OFF state)
     
     // Software delay loop - approximately 0.1 second
-    ldr r2, =800000            // Load shorter delay counter value
+    ldr r2, =OFF_DELAY         // Address of the shorter delay count: a Rust static in .bss
+    ldr r2, [r2]               // Load it (800000, set by a constructor before main ran)
 delay2:
     subs r2, r2, #1            // Decrement counter and set flags
     bne delay2                 // Branch if not zero (continue loop)
@@ -157,6 +211,34 @@ delay2:
 "#
 );
 
+// ============================================================================
+// RUST STATICS USED BY THE ASSEMBLY
+// ============================================================================
+
+// The blink delays, in delay loop iterations. main loads them by name, so they are #[no_mangle].
+// Each one is only right if Reset did its part: ON_DELAY's 8000000 is in flash until Reset copies
+// .data to RAM, and OFF_DELAY is in .bss, zeroed by Reset, then set by the constructor below.
+
+/// LED on time: in .data, because its initial value is not zero
+#[no_mangle]
+static mut ON_DELAY: u32 = 8_000_000;
+
+/// LED off time: in .bss, because its initial value is zero
+#[no_mangle]
+static mut OFF_DELAY: u32 = 0;
+
+/// Constructor: Reset calls every function pointer in .init_array before main
+#[used]
+#[link_section = ".init_array"]
+static SET_OFF_DELAY: extern "C" fn() = set_off_delay;
+
+/// The LED stays off a tenth as long as it stays on
+extern "C" fn set_off_delay() {
+    unsafe {
+        OFF_DELAY = ON_DELAY / 10;
+    }
+}
+
 // ============================================================================
 // RUST LANGUAGE REQUIREMENTS
 // ============================================================================
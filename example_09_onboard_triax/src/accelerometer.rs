@@ -0,0 +1,78 @@
+//! Accelerometer application layer.
+//!
+//! Wraps the `lsm303agr` driver with the initialisation sequence, mode changes and readout used by this example.
+//! The only requirement on the bus is `embedded_hal::i2c::I2c`, which `Twim` implements on the micro:bit and
+//! [`crate::sim::SimulatedLsm303agr`] implements on the PC.
+
+use embedded_hal::{delay::DelayNs, i2c::I2c};
+use lsm303agr::{interface::I2cInterface, mode::MagOneShot, AccelMode, AccelOutputDataRate, Lsm303agr};
+
+/// Value of the accelerometer WHO_AM_I_A register (0x0F) on a genuine LSM303AGR: 0x33 = 51
+pub const EXPECTED_ACCEL_ID: u8 = 0x33;
+
+/// Errors reported by [`Accelerometer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The I²C bus reported an error (NACK, arbitration loss, ...)
+    Comm(E),
+    /// Something answered on the accelerometer address, but it is not an LSM303AGR
+    UnexpectedId(u8),
+    /// The driver rejected the requested configuration (e.g. an unsupported mode/data rate combination)
+    InvalidConfiguration,
+}
+
+impl<E> From<lsm303agr::Error<E>> for Error<E> {
+    fn from(error: lsm303agr::Error<E>) -> Self {
+        match error {
+            lsm303agr::Error::Comm(e) => Error::Comm(e),
+            lsm303agr::Error::InvalidInputData => Error::InvalidConfiguration,
+        }
+    }
+}
+
+/// The onboard LSM303AGR accelerometer, generic over the I²C bus it is connected to
+pub struct Accelerometer<I2C> {
+    sensor: Lsm303agr<I2cInterface<I2C>, MagOneShot>,
+}
+
+impl<I2C, E> Accelerometer<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Wrap an I²C bus. No bus traffic happens until [`Accelerometer::init`] is called.
+    pub fn new(i2c: I2C) -> Self {
+        Self {
+            sensor: Lsm303agr::new_with_i2c(i2c),
+        }
+    }
+
+    /// Check the WHO_AM_I register and put the sensor into high resolution mode at 50 Hz.
+    ///
+    /// Returns the accelerometer ID that was read.
+    pub fn init<D: DelayNs>(&mut self, delay: &mut D) -> Result<u8, Error<E>> {
+        let id = self.sensor.accelerometer_id()?.raw();
+        if id != EXPECTED_ACCEL_ID {
+            return Err(Error::UnexpectedId(id));
+        }
+
+        self.sensor.init()?;
+        self.set_mode(delay, AccelMode::HighResolution, AccelOutputDataRate::Hz50)?;
+        Ok(id)
+    }
+
+    /// Change the power mode and output data rate. The driver waits for the sensor turn-on time using `delay`.
+    pub fn set_mode<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        mode: AccelMode,
+        odr: AccelOutputDataRate,
+    ) -> Result<(), Error<E>> {
+        self.sensor.set_accel_mode_and_odr(delay, mode, odr)?;
+        Ok(())
+    }
+
+    /// Read the latest acceleration sample in milligravities (x, y, z)
+    pub fn read_mg(&mut self) -> Result<(i32, i32, i32), Error<E>> {
+        Ok(self.sensor.acceleration()?.xyz_mg())
+    }
+}
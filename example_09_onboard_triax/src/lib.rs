@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Hardware independent part of the onboard accelerometer example.
+//!
+//! Everything in here is written against the `embedded-hal` I²C trait rather than the nRF52833 `Twim`
+//! peripheral, so the same code runs on the micro:bit (with `Twim`) and on the PC (with the simulated sensor
+//! in [`sim`]). See `tests/accelerometer.rs` for the host tests.
+
+pub mod accelerometer;
+
+// The simulated sensor needs `std` (shared state, sample queue) so it only exists in host builds
+#[cfg(not(target_os = "none"))]
+pub mod sim;
+
+pub use accelerometer::{Accelerometer, Error};
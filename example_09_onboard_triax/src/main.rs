@@ -1,18 +1,20 @@
-#![no_main]
-#![no_std]
-
-use cortex_m_rt::entry;
-use embedded_hal::delay::DelayNs;
-use panic_rtt_target as _;
-use rtt_target::{rprintln, rtt_init_print};
-
-use microbit::{
-    hal::{twim, Timer},
-    pac::twim0::frequency::FREQUENCY_A,
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::delay::DelayNs,
+    example_09_onboard_triax::Accelerometer,
+    microbit::{
+        hal::{twim, Timer},
+        pac::twim0::frequency::FREQUENCY_A,
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
 };
 
-use lsm303agr::{AccelMode, AccelOutputDataRate, Lsm303agr};
-
+#[cfg(target_os = "none")]
 #[entry]
 fn main() -> ! {
     rtt_init_print!();
@@ -20,19 +22,20 @@ fn main() -> ! {
 
     let mut timer0 = Timer::new(board.TIMER0);
 
+    // Twim implements the embedded-hal I2c trait, which is all the accelerometer code needs
     let i2c = { twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100) };
-    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    let mut accelerometer = Accelerometer::new(i2c);
 
-    let id = sensor.accelerometer_id().unwrap();
-    rprintln!("Accelerometer ID: {:?} (expected: 51)", id);
-
-    sensor
-        .set_accel_mode_and_odr(&mut timer0, AccelMode::HighResolution, AccelOutputDataRate::Hz50)
-        .unwrap();
+    let id = accelerometer.init(&mut timer0).unwrap();
+    rprintln!("Accelerometer ID: {} (expected: 51)", id);
 
     loop {
-        let (x, y, z) = sensor.acceleration().unwrap().xyz_mg();
+        let (x, y, z) = accelerometer.read_mg().unwrap();
         rprintln!("Accelerometer: x {} y {} z {}", x, y, z);
         timer0.delay_ms(250);
     }
 }
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
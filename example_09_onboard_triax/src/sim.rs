@@ -0,0 +1,266 @@
+//! Simulated LSM303AGR for host tests.
+//!
+//! The simulator implements `embedded_hal::i2c::I2c` and behaves like the real chip on the bus:
+//! - Two devices: the accelerometer on 0x19 and the magnetometer on 0x1E, anything else is NACKed
+//! - Each device is a 128 byte register file with the datasheet WHO_AM_I values (0x33 and 0x40)
+//! - The first byte of a write selects the register, further bytes are written to consecutive registers
+//! - Accelerometer auto-increment only happens when bit 7 of the register address is set (datasheet 6.1.1),
+//!   the magnetometer always auto-increments
+//!
+//! Acceleration samples are queued in milligravities. Reading OUT_X_L_A latches the next sample into the output
+//! registers, encoded exactly as the chip would for the power mode and full scale currently programmed in
+//! CTRL_REG1_A / CTRL_REG4_A. Errors can be injected for the next transaction(s).
+//!
+//! The simulator is a cheap handle around shared state: clone it, give one copy to the code under test and keep
+//! the other to queue samples and inspect registers.
+
+extern crate std;
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation, SevenBitAddress};
+
+/// I²C address of the accelerometer
+pub const ACCEL_ADDR: u8 = 0x19;
+/// I²C address of the magnetometer
+pub const MAG_ADDR: u8 = 0x1E;
+
+// Accelerometer registers (datasheet section 8)
+pub const STATUS_REG_AUX_A: u8 = 0x07;
+pub const WHO_AM_I_A: u8 = 0x0F;
+pub const CTRL_REG1_A: u8 = 0x20;
+pub const CTRL_REG4_A: u8 = 0x23;
+pub const STATUS_REG_A: u8 = 0x27;
+pub const OUT_X_L_A: u8 = 0x28;
+
+// Magnetometer registers
+pub const WHO_AM_I_M: u8 = 0x4F;
+pub const CFG_REG_A_M: u8 = 0x60;
+
+const CTRL_REG1_A_LPEN: u8 = 1 << 3;
+const CTRL_REG4_A_HR: u8 = 1 << 3;
+const STATUS_REG_A_ZYXDA: u8 = 1 << 3;
+const AUTO_INCREMENT: u8 = 0x80;
+
+/// Error returned by the simulator, carrying the `embedded-hal` error kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimError(pub ErrorKind);
+
+impl i2c::Error for SimError {
+    fn kind(&self) -> ErrorKind {
+        self.0
+    }
+}
+
+/// One device on the simulated bus
+struct Device {
+    registers: [u8; 128],
+    pointer: u8,
+    auto_increment: bool,
+}
+
+impl Device {
+    fn new(who_am_i_reg: u8, who_am_i: u8) -> Self {
+        let mut registers = [0; 128];
+        registers[who_am_i_reg as usize] = who_am_i;
+        Self {
+            registers,
+            pointer: 0,
+            auto_increment: false,
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.auto_increment {
+            self.pointer = (self.pointer + 1) & 0x7F;
+        }
+    }
+}
+
+struct State {
+    accel: Device,
+    mag: Device,
+    samples: VecDeque<(i32, i32, i32)>,
+    current: (i32, i32, i32),
+    failures: VecDeque<ErrorKind>,
+    transactions: usize,
+}
+
+/// Simulated LSM303AGR, see the module documentation
+#[derive(Clone)]
+pub struct SimulatedLsm303agr {
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for SimulatedLsm303agr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedLsm303agr {
+    /// A sensor in its power-on state, at rest (0, 0, 0 mg) until samples are queued
+    pub fn new() -> Self {
+        let mut accel = Device::new(WHO_AM_I_A, 0x33);
+        accel.registers[CTRL_REG1_A as usize] = 0x07; // Reset value: X, Y and Z enabled, power-down
+        let mut mag = Device::new(WHO_AM_I_M, 0x40);
+        mag.registers[CFG_REG_A_M as usize] = 0x03; // Reset value: idle mode
+
+        let state = State {
+            accel,
+            mag,
+            samples: VecDeque::new(),
+            current: (0, 0, 0),
+            failures: VecDeque::new(),
+            transactions: 0,
+        };
+        Self {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Queue an acceleration sample (mg). Each read of the output registers consumes one sample; once the queue
+    /// is empty the last sample keeps being reported, like a sensor lying still.
+    pub fn push_acceleration(&self, x: i32, y: i32, z: i32) {
+        self.state.borrow_mut().samples.push_back((x, y, z));
+    }
+
+    /// Number of queued samples that have not been read yet
+    pub fn pending_samples(&self) -> usize {
+        self.state.borrow().samples.len()
+    }
+
+    /// Make the next transaction fail with `kind`. Calls queue up: two calls fail the next two transactions.
+    pub fn fail_next_transaction(&self, kind: ErrorKind) {
+        self.state.borrow_mut().failures.push_back(kind);
+    }
+
+    /// Current value of an accelerometer register
+    pub fn accel_register(&self, register: u8) -> u8 {
+        self.state.borrow().accel.registers[(register & 0x7F) as usize]
+    }
+
+    /// Overwrite an accelerometer register, e.g. WHO_AM_I_A to pretend to be a different chip
+    pub fn set_accel_register(&self, register: u8, value: u8) {
+        self.state.borrow_mut().accel.registers[(register & 0x7F) as usize] = value;
+    }
+
+    /// Current value of a magnetometer register
+    pub fn mag_register(&self, register: u8) -> u8 {
+        self.state.borrow().mag.registers[(register & 0x7F) as usize]
+    }
+
+    /// Number of I²C transactions seen so far (including failed ones)
+    pub fn transaction_count(&self) -> usize {
+        self.state.borrow().transactions
+    }
+}
+
+impl State {
+    /// Latch the next queued sample into OUT_X_L_A..OUT_Z_H_A, encoded for the current mode and full scale
+    fn latch_sample(&mut self) {
+        if let Some(sample) = self.samples.pop_front() {
+            self.current = sample;
+        }
+
+        let ctrl1 = self.accel.registers[CTRL_REG1_A as usize];
+        let ctrl4 = self.accel.registers[CTRL_REG4_A as usize];
+        let full_scale = ((ctrl4 >> 4) & 0b11) as usize;
+
+        // Output is left-justified two's complement: 8 bit in low power, 10 bit normal, 12 bit high resolution.
+        // Sensitivities in mg/digit for ±2/4/8/16 g come from datasheet table 4.
+        let (bits, sensitivity) = if ctrl1 & CTRL_REG1_A_LPEN != 0 {
+            (8, [16, 32, 64, 192][full_scale])
+        } else if ctrl4 & CTRL_REG4_A_HR != 0 {
+            (12, [1, 2, 4, 12][full_scale])
+        } else {
+            (10, [4, 8, 16, 48][full_scale])
+        };
+        let max = (1 << (bits - 1)) - 1;
+        let encode = |mg: i32| (((mg / sensitivity).clamp(-max - 1, max) << (16 - bits)) as i16).to_le_bytes();
+
+        let (x, y, z) = self.current;
+        let out = OUT_X_L_A as usize;
+        self.accel.registers[out..out + 2].copy_from_slice(&encode(x));
+        self.accel.registers[out + 2..out + 4].copy_from_slice(&encode(y));
+        self.accel.registers[out + 4..out + 6].copy_from_slice(&encode(z));
+        self.accel.registers[STATUS_REG_A as usize] |= STATUS_REG_A_ZYXDA;
+    }
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) {
+        for byte in buffer.iter_mut() {
+            if address == ACCEL_ADDR {
+                match self.accel.pointer {
+                    OUT_X_L_A => self.latch_sample(),
+                    // Reading the last output register completes the sample
+                    0x2D => self.accel.registers[STATUS_REG_A as usize] &= !STATUS_REG_A_ZYXDA,
+                    _ => {}
+                }
+            }
+
+            let device = self.device(address);
+            *byte = device.registers[device.pointer as usize];
+            device.advance();
+        }
+    }
+
+    fn write(&mut self, address: u8, bytes: &[u8]) {
+        let device = self.device(address);
+        let Some((&register, data)) = bytes.split_first() else {
+            return;
+        };
+
+        device.pointer = register & 0x7F;
+        device.auto_increment = address == MAG_ADDR || register & AUTO_INCREMENT != 0;
+
+        for &value in data {
+            if !is_read_only(address, device.pointer) {
+                device.registers[device.pointer as usize] = value;
+            }
+            device.advance();
+        }
+    }
+
+    fn device(&mut self, address: u8) -> &mut Device {
+        if address == ACCEL_ADDR {
+            &mut self.accel
+        } else {
+            &mut self.mag
+        }
+    }
+}
+
+/// WHO_AM_I, status and output registers ignore writes
+fn is_read_only(address: u8, register: u8) -> bool {
+    if address == ACCEL_ADDR {
+        matches!(register, STATUS_REG_AUX_A | WHO_AM_I_A | STATUS_REG_A..=0x2D)
+    } else {
+        matches!(register, WHO_AM_I_M | 0x67..=0x6D)
+    }
+}
+
+impl ErrorType for SimulatedLsm303agr {
+    type Error = SimError;
+}
+
+impl I2c<SevenBitAddress> for SimulatedLsm303agr {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        state.transactions += 1;
+
+        if let Some(kind) = state.failures.pop_front() {
+            return Err(SimError(kind));
+        }
+        if address != ACCEL_ADDR && address != MAG_ADDR {
+            return Err(SimError(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)));
+        }
+
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => state.write(address, bytes),
+                Operation::Read(buffer) => state.read(address, buffer),
+            }
+        }
+        Ok(())
+    }
+}
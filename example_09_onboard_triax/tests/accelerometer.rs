@@ -0,0 +1,130 @@
+//! Host tests for the accelerometer application code, run against the simulated LSM303AGR.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorKind, NoAcknowledgeSource},
+};
+use example_09_onboard_triax::{
+    accelerometer::EXPECTED_ACCEL_ID,
+    sim::{SimError, SimulatedLsm303agr, CTRL_REG1_A, CTRL_REG4_A, WHO_AM_I_A},
+    Accelerometer, Error,
+};
+use lsm303agr::{AccelMode, AccelOutputDataRate};
+
+/// Delays are irrelevant against the simulator, just count how long the driver asked to wait
+#[derive(Default)]
+struct NoDelay {
+    total_ns: u64,
+}
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.total_ns += u64::from(ns);
+    }
+}
+
+fn initialised() -> (SimulatedLsm303agr, Accelerometer<SimulatedLsm303agr>) {
+    let sim = SimulatedLsm303agr::new();
+    let mut accelerometer = Accelerometer::new(sim.clone());
+    accelerometer.init(&mut NoDelay::default()).unwrap();
+    (sim, accelerometer)
+}
+
+#[test]
+fn init_reads_id_and_configures_high_resolution_50hz() {
+    let sim = SimulatedLsm303agr::new();
+    let mut accelerometer = Accelerometer::new(sim.clone());
+    let mut delay = NoDelay::default();
+
+    assert_eq!(accelerometer.init(&mut delay), Ok(EXPECTED_ACCEL_ID));
+
+    // ODR = 0100 (50 Hz), LPen = 0, X/Y/Z enabled
+    assert_eq!(sim.accel_register(CTRL_REG1_A), 0b0100_0111);
+    // HR bit set
+    assert_ne!(sim.accel_register(CTRL_REG4_A) & (1 << 3), 0);
+    // The driver waits for the mode change to settle
+    assert!(delay.total_ns > 0);
+}
+
+#[test]
+fn init_rejects_unexpected_id() {
+    let sim = SimulatedLsm303agr::new();
+    sim.set_accel_register(WHO_AM_I_A, 0x32);
+    let mut accelerometer = Accelerometer::new(sim.clone());
+
+    assert_eq!(
+        accelerometer.init(&mut NoDelay::default()),
+        Err(Error::UnexpectedId(0x32))
+    );
+    // Nothing was configured, CTRL_REG1_A still holds its reset value
+    assert_eq!(sim.accel_register(CTRL_REG1_A), 0x07);
+}
+
+#[test]
+fn readout_in_high_resolution() {
+    let (sim, mut accelerometer) = initialised();
+    sim.push_acceleration(-16, 32, 1008);
+    sim.push_acceleration(250, -500, 980);
+
+    assert_eq!(accelerometer.read_mg(), Ok((-16, 32, 1008)));
+    assert_eq!(accelerometer.read_mg(), Ok((250, -500, 980)));
+    assert_eq!(sim.pending_samples(), 0);
+
+    // No new samples: the last one is held
+    assert_eq!(accelerometer.read_mg(), Ok((250, -500, 980)));
+}
+
+#[test]
+fn low_power_mode_quantises_to_16mg() {
+    let (sim, mut accelerometer) = initialised();
+    accelerometer
+        .set_mode(&mut NoDelay::default(), AccelMode::LowPower, AccelOutputDataRate::Hz10)
+        .unwrap();
+
+    // LPen set, ODR = 0010 (10 Hz)
+    assert_eq!(sim.accel_register(CTRL_REG1_A), 0b0010_1111);
+
+    sim.push_acceleration(-20, 40, 1000);
+    assert_eq!(accelerometer.read_mg(), Ok((-16, 32, 992)));
+}
+
+#[test]
+fn normal_mode_quantises_to_4mg() {
+    let (sim, mut accelerometer) = initialised();
+    accelerometer
+        .set_mode(&mut NoDelay::default(), AccelMode::Normal, AccelOutputDataRate::Hz100)
+        .unwrap();
+
+    sim.push_acceleration(-18, 33, 1001);
+    assert_eq!(accelerometer.read_mg(), Ok((-16, 32, 1000)));
+}
+
+#[test]
+fn bus_errors_are_reported() {
+    let (sim, mut accelerometer) = initialised();
+    sim.fail_next_transaction(ErrorKind::ArbitrationLoss);
+
+    assert_eq!(
+        accelerometer.read_mg(),
+        Err(Error::Comm(SimError(ErrorKind::ArbitrationLoss)))
+    );
+
+    // The failure only affects one transaction
+    sim.push_acceleration(0, 0, 1000);
+    assert_eq!(accelerometer.read_mg(), Ok((0, 0, 1000)));
+}
+
+#[test]
+fn init_reports_error_when_sensor_does_not_answer() {
+    let sim = SimulatedLsm303agr::new();
+    sim.fail_next_transaction(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+    let mut accelerometer = Accelerometer::new(sim.clone());
+
+    assert!(matches!(
+        accelerometer.init(&mut NoDelay::default()),
+        Err(Error::Comm(_))
+    ));
+    assert_eq!(sim.transaction_count(), 1);
+}
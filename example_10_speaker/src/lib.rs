@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Speaker tones and melodies for the micro:bit v2.
+//!
+//! - [`melody`] parses MicroPython style melody strings into frequency/duration pairs
+//! - [`player`] sequences a melody into tone/silence steps
+//! - [`speaker`] drives the onboard speaker with the PWM peripheral (target only)
+//!
+//! `melody` and `player` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod melody;
+pub mod player;
+
+#[cfg(target_os = "none")]
+pub mod speaker;
@@ -0,0 +1,86 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::cell::RefCell,
+    cortex_m::interrupt::Mutex,
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_10_speaker::{
+        melody::Tempo,
+        speaker::{BackgroundPlayer, Speaker},
+    },
+    microbit::hal::{
+        gpio::Level,
+        pac::{self, interrupt, PWM0, TIMER1},
+        Timer,
+    },
+    panic_halt as _,
+};
+
+/// "Ode to Joy" in MicroPython notation: note name, octave, and duration in ticks after the colon
+#[cfg(target_os = "none")]
+const MELODY: &str = "E4:4 E F G G F E D C C D E E:6 D:2 D:8";
+
+// The player is used by main (to start a melody) and by the TIMER1 interrupt (to advance it)
+#[cfg(target_os = "none")]
+static PLAYER: Mutex<RefCell<Option<BackgroundPlayer<PWM0, TIMER1>>>> = Mutex::new(RefCell::new(None));
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+
+    let speaker_pin = board.speaker_pin.into_push_pull_output(Level::Low).degrade();
+    let mut speaker = Speaker::new(board.PWM0, speaker_pin);
+
+    // Blocking tones: frequency and duration, the CPU waits in the delay
+    speaker.play(880, 100, &mut timer0);
+    timer0.delay_ms(50);
+    speaker.play(1760, 100, &mut timer0);
+    timer0.delay_ms(500);
+
+    // Non-blocking melody: TIMER1 interrupts step through the notes
+    let mut player = BackgroundPlayer::new(speaker, Timer::new(board.TIMER1));
+    player.play(MELODY, Tempo::default(), false).unwrap();
+    cortex_m::interrupt::free(|cs| PLAYER.borrow(cs).replace(Some(player)));
+
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::TIMER1);
+    }
+
+    // Main is free while the melody plays: poll button A to restart it
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_was_pressed = false;
+
+    loop {
+        let button_pressed = button_a.is_low().unwrap();
+        if button_pressed && !button_was_pressed {
+            cortex_m::interrupt::free(|cs| {
+                if let Some(player) = PLAYER.borrow(cs).borrow_mut().as_mut() {
+                    player.play(MELODY, Tempo::default(), false).unwrap();
+                }
+            });
+        }
+        button_was_pressed = button_pressed;
+
+        timer0.delay_ms(10); // Simple debounce
+    }
+}
+
+// End of the current note or gap: start the next one
+#[cfg(target_os = "none")]
+#[interrupt]
+fn TIMER1() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(player) = PLAYER.borrow(cs).borrow_mut().as_mut() {
+            player.on_timer_interrupt();
+        }
+    });
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,223 @@
+//! Melody notation parsing.
+//!
+//! Melodies use the same compact notation as MicroPython's `music` module: a whitespace separated list of notes,
+//! each written as `NAME[#|b][OCTAVE][:DURATION]`, for example `"C4:4 D E F G:8 R:4"`.
+//! - `NAME` is `A` to `G`, or `R` for a rest
+//! - `#` raises the note a semitone, `b` lowers it
+//! - `OCTAVE` is 0 to 8, middle C is `C4`
+//! - `DURATION` is a number of ticks
+//!
+//! Octave and duration are sticky: when left out, the values of the previous note are used (initially octave 4
+//! and 4 ticks). How long a tick is depends on the [`Tempo`].
+//!
+//! Parsing never allocates; [`Melody`] is an iterator over the notes of a `&str`.
+
+/// Playback speed: `bpm` beats per minute, each beat split into `ticks_per_beat` ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tempo {
+    bpm: u32,
+    ticks_per_beat: u32,
+}
+
+/// What was wrong with a tempo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempoError {
+    /// Zero beats per minute: a tick would last forever
+    ZeroBpm,
+    /// Zero ticks per beat: a tick would last forever
+    ZeroTicksPerBeat,
+}
+
+impl Tempo {
+    /// A tempo of `bpm` beats per minute and `ticks_per_beat` ticks per beat, neither of them zero
+    pub fn new(bpm: u32, ticks_per_beat: u32) -> Result<Self, TempoError> {
+        if bpm == 0 {
+            Err(TempoError::ZeroBpm)
+        } else if ticks_per_beat == 0 {
+            Err(TempoError::ZeroTicksPerBeat)
+        } else {
+            Ok(Self { bpm, ticks_per_beat })
+        }
+    }
+
+    pub fn bpm(&self) -> u32 {
+        self.bpm
+    }
+
+    pub fn ticks_per_beat(&self) -> u32 {
+        self.ticks_per_beat
+    }
+
+    /// Duration of `ticks` ticks in milliseconds, at most `u32::MAX` (about 50 days)
+    pub fn ticks_to_ms(&self, ticks: u32) -> u32 {
+        // In u64 neither the product nor the divisor can overflow, and `new` keeps the divisor above zero
+        let ms = u64::from(ticks) * 60_000 / (u64::from(self.bpm) * u64::from(self.ticks_per_beat));
+        u32::try_from(ms).unwrap_or(u32::MAX)
+    }
+}
+
+impl Default for Tempo {
+    /// 120 bpm, 4 ticks per beat (MicroPython's default): one tick is 125 ms
+    fn default() -> Self {
+        Self {
+            bpm: 120,
+            ticks_per_beat: 4,
+        }
+    }
+}
+
+/// A note ready to play: a frequency (0 for a rest) and how long to play it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    pub frequency_hz: u32,
+    pub duration_ms: u32,
+}
+
+impl Note {
+    pub fn is_rest(&self) -> bool {
+        self.frequency_hz == 0
+    }
+}
+
+/// What was wrong with a note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The note name is not `A`-`G` or `R`
+    InvalidName,
+    /// The octave is not a single digit from 0 to 8
+    InvalidOctave,
+    /// The duration after `:` is missing, zero or too large
+    InvalidDuration,
+}
+
+/// A parse error, with the byte offset of the offending note in the melody string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+/// Frequencies of octave 8 in Hz (C8 to B8). Lower octaves are derived by halving, which keeps the table small and
+/// the rounding error below half a Hz.
+const OCTAVE_8_HZ: [u32; 12] = [4186, 4435, 4699, 4978, 5274, 5588, 5920, 6272, 6645, 7040, 7459, 7902];
+
+/// Frequency in Hz of a semitone (0 = C .. 11 = B) in an octave (0 to 8)
+pub fn frequency(semitone: u8, octave: u8) -> u32 {
+    let shift = 8 - u32::from(octave);
+    let hz = OCTAVE_8_HZ[usize::from(semitone)];
+    if shift == 0 {
+        hz
+    } else {
+        (hz + (1 << (shift - 1))) >> shift
+    }
+}
+
+/// Iterator over the notes in a melody string, see the module documentation for the notation
+#[derive(Debug, Clone)]
+pub struct Melody<'a> {
+    text: &'a str,
+    position: usize,
+    tempo: Tempo,
+    octave: u8,
+    ticks: u32,
+}
+
+impl<'a> Melody<'a> {
+    pub fn new(text: &'a str, tempo: Tempo) -> Self {
+        Self {
+            text,
+            position: 0,
+            tempo,
+            octave: 4,
+            ticks: 4,
+        }
+    }
+
+    /// Check a whole melody up front, e.g. before handing it to an interrupt driven player
+    pub fn validate(text: &str) -> Result<(), ParseError> {
+        Melody::new(text, Tempo::default()).try_for_each(|note| note.map(|_| ()))
+    }
+
+    fn parse(&mut self, token: &str, position: usize) -> Result<Note, ParseError> {
+        let error = |kind| ParseError { position, kind };
+        let (name, duration) = match token.split_once(':') {
+            Some((name, duration)) => (name, Some(duration)),
+            None => (token, None),
+        };
+
+        let mut chars = name.chars();
+        let semitone: Option<i8> = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => Some(0),
+            Some('D') => Some(2),
+            Some('E') => Some(4),
+            Some('F') => Some(5),
+            Some('G') => Some(7),
+            Some('A') => Some(9),
+            Some('B') => Some(11),
+            Some('R') => None,
+            _ => return Err(error(ParseErrorKind::InvalidName)),
+        };
+
+        let mut rest = chars.as_str();
+        let accidental = if let Some(r) = rest.strip_prefix('#') {
+            rest = r;
+            1
+        } else if let Some(r) = rest.strip_prefix('b') {
+            rest = r;
+            -1
+        } else {
+            0
+        };
+
+        if !rest.is_empty() {
+            match rest.parse::<u8>() {
+                Ok(octave) if octave <= 8 && rest.len() == 1 => self.octave = octave,
+                _ => return Err(error(ParseErrorKind::InvalidOctave)),
+            }
+        }
+
+        if let Some(duration) = duration {
+            match duration.parse::<u32>() {
+                Ok(ticks) if ticks > 0 && ticks <= 1000 => self.ticks = ticks,
+                _ => return Err(error(ParseErrorKind::InvalidDuration)),
+            }
+        }
+
+        let frequency_hz = match semitone {
+            Some(semitone) => {
+                // Sharps/flats may cross into the neighbouring octave (B# is the next C, Cb the previous B)
+                let mut semitone = semitone + accidental;
+                let mut octave = self.octave as i8;
+                if semitone < 0 {
+                    semitone += 12;
+                    octave -= 1;
+                } else if semitone > 11 {
+                    semitone -= 12;
+                    octave += 1;
+                }
+                if !(0..=8).contains(&octave) {
+                    return Err(error(ParseErrorKind::InvalidOctave));
+                }
+                frequency(semitone as u8, octave as u8)
+            }
+            None => 0,
+        };
+
+        Ok(Note {
+            frequency_hz,
+            duration_ms: self.tempo.ticks_to_ms(self.ticks),
+        })
+    }
+}
+
+impl Iterator for Melody<'_> {
+    type Item = Result<Note, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = &self.text[self.position..];
+        let start = self.position + (remaining.len() - remaining.trim_start().len());
+        let token = self.text[start..].split_whitespace().next()?;
+        self.position = start + token.len();
+        Some(self.parse(token, start))
+    }
+}
@@ -0,0 +1,109 @@
+//! Playback sequencing, independent of the hardware.
+//!
+//! A [`Player`] turns a melody into the [`Step`]s the speaker driver executes one by one: each step says what the
+//! speaker should do and for how long, and the timer interrupt asks for the next step when that time is up.
+//! Notes are followed by a short silence so that repeated notes (`"C C C"`) are heard separately.
+
+use crate::melody::{Melody, Note, Tempo};
+
+/// Silence inserted after every note, taken out of the note's own duration
+pub const ARTICULATION_MS: u32 = 10;
+
+/// How long to set a timer for, and what is left of `duration_ms` after that: a timer that counts at most `max_ms`
+/// at once waits out a longer step in several goes. Never 0, so the timer always fires.
+pub fn split_wait(duration_ms: u32, max_ms: u32) -> (u32, u32) {
+    let wait_ms = duration_ms.clamp(1, max_ms);
+    (wait_ms, duration_ms.saturating_sub(wait_ms))
+}
+
+/// One thing for the speaker to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Play a square wave at `frequency_hz`
+    Tone { frequency_hz: u32, duration_ms: u32 },
+    /// Speaker off
+    Silence { duration_ms: u32 },
+}
+
+impl Step {
+    pub fn duration_ms(&self) -> u32 {
+        match *self {
+            Step::Tone { duration_ms, .. } | Step::Silence { duration_ms } => duration_ms,
+        }
+    }
+}
+
+/// Iterator over the playback steps of a melody.
+///
+/// Playback ends at the end of the melody (or restarts when looping) and stops early at the first invalid note;
+/// use [`Melody::validate`] beforehand to catch those.
+#[derive(Debug, Clone)]
+pub struct Player<'a> {
+    text: &'a str,
+    tempo: Tempo,
+    melody: Melody<'a>,
+    gap_ms: u32,
+    looping: bool,
+}
+
+impl<'a> Player<'a> {
+    pub fn new(text: &'a str, tempo: Tempo) -> Self {
+        Self {
+            text,
+            tempo,
+            melody: Melody::new(text, tempo),
+            gap_ms: 0,
+            looping: false,
+        }
+    }
+
+    /// Start again from the first note when the melody ends
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    fn next_note(&mut self) -> Option<Note> {
+        match self.melody.next() {
+            Some(Ok(note)) => Some(note),
+            Some(Err(_)) => None,
+            None if self.looping => {
+                self.melody = Melody::new(self.text, self.tempo);
+                // An empty melody would otherwise loop forever without producing anything
+                self.melody.next().and_then(Result::ok)
+            }
+            None => None,
+        }
+    }
+}
+
+impl Iterator for Player<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        if self.gap_ms > 0 {
+            let duration_ms = core::mem::take(&mut self.gap_ms);
+            return Some(Step::Silence { duration_ms });
+        }
+
+        let note = self.next_note()?;
+        if note.is_rest() {
+            return Some(Step::Silence {
+                duration_ms: note.duration_ms,
+            });
+        }
+
+        if note.duration_ms > ARTICULATION_MS {
+            self.gap_ms = ARTICULATION_MS;
+            Some(Step::Tone {
+                frequency_hz: note.frequency_hz,
+                duration_ms: note.duration_ms - ARTICULATION_MS,
+            })
+        } else {
+            Some(Step::Tone {
+                frequency_hz: note.frequency_hz,
+                duration_ms: note.duration_ms,
+            })
+        }
+    }
+}
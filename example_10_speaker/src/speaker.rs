@@ -0,0 +1,157 @@
+//! Onboard speaker driver (micro:bit v2 speaker on P0.00).
+//!
+//! The speaker is driven with a 50% duty square wave from the PWM peripheral, so once a tone is started it keeps
+//! playing without any CPU involvement. Two ways of using it:
+//! - [`Speaker`] - start/stop tones directly, or play a tone for a duration with a blocking delay
+//! - [`BackgroundPlayer`] - plays a whole melody from a timer interrupt while `main` carries on
+
+use embedded_hal::delay::DelayNs;
+use microbit::hal::{
+    gpio::{Output, Pin, PushPull},
+    pwm::{self, Channel, CounterMode, Prescaler, Pwm},
+    time::Hertz,
+    timer::{self, Timer},
+};
+
+use crate::{
+    melody::{Melody, ParseError, Tempo},
+    player::{split_wait, Player, Step},
+};
+
+/// PWM clock after the /16 prescaler
+const PWM_CLOCK_HZ: u32 = 1_000_000;
+
+/// Lowest tone: COUNTERTOP is 15 bits, so the period can be at most 32767 PWM clocks
+pub const MIN_FREQUENCY_HZ: u32 = PWM_CLOCK_HZ / 32_767 + 1;
+/// Highest tone, roughly the limit of human hearing (and well beyond what the small speaker reproduces)
+pub const MAX_FREQUENCY_HZ: u32 = 20_000;
+
+/// Timer ticks per millisecond (nrf52833-hal timers run at 1 MHz)
+const TIMER_TICKS_PER_MS: u32 = 1_000;
+/// The longest the timer can count in one go, about 71 minutes: longer steps take several
+const TIMER_MAX_MS: u32 = u32::MAX / TIMER_TICKS_PER_MS;
+
+/// The onboard speaker, driven by a PWM instance
+pub struct Speaker<T: pwm::Instance> {
+    pwm: Pwm<T>,
+}
+
+impl<T: pwm::Instance> Speaker<T> {
+    /// Take over a PWM instance and the speaker pin. The speaker starts silent.
+    pub fn new(pwm: T, pin: Pin<Output<PushPull>>) -> Self {
+        let pwm = Pwm::new(pwm);
+        pwm.set_output_pin(Channel::C0, pin)
+            .set_prescaler(Prescaler::Div16)
+            .set_counter_mode(CounterMode::Up);
+        pwm.disable();
+        Self { pwm }
+    }
+
+    /// Start a tone. The frequency is clamped to [`MIN_FREQUENCY_HZ`]..=[`MAX_FREQUENCY_HZ`], 0 stops the speaker.
+    pub fn tone(&mut self, frequency_hz: u32) {
+        if frequency_hz == 0 {
+            self.stop();
+            return;
+        }
+
+        let frequency_hz = frequency_hz.clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ);
+        self.pwm.set_period(Hertz(frequency_hz));
+        self.pwm.enable();
+        // Half of COUNTERTOP: a symmetric square wave
+        self.pwm.set_duty_on_common(self.pwm.max_duty() / 2);
+    }
+
+    /// Silence the speaker
+    pub fn stop(&mut self) {
+        self.pwm.disable();
+    }
+
+    /// Play a tone for `duration_ms`, blocking until it is finished
+    pub fn play<D: DelayNs>(&mut self, frequency_hz: u32, duration_ms: u32, delay: &mut D) {
+        self.tone(frequency_hz);
+        delay.delay_ms(duration_ms);
+        self.stop();
+    }
+
+    /// Start one playback step (the caller takes care of the timing)
+    pub fn start_step(&mut self, step: Step) {
+        match step {
+            Step::Tone { frequency_hz, .. } => self.tone(frequency_hz),
+            Step::Silence { .. } => self.stop(),
+        }
+    }
+}
+
+/// Plays melodies in the background: a one-shot timer fires at the end of every step and its interrupt handler
+/// starts the next one.
+///
+/// The player is shared between `main` and the timer interrupt, so it lives in a
+/// `Mutex<RefCell<Option<BackgroundPlayer<..>>>>` and the interrupt handler calls
+/// [`BackgroundPlayer::on_timer_interrupt`].
+pub struct BackgroundPlayer<T: pwm::Instance, U: timer::Instance> {
+    speaker: Speaker<T>,
+    timer: Timer<U>,
+    steps: Option<Player<'static>>,
+    /// What is left of the current step after the timer fires
+    remaining_ms: u32,
+}
+
+impl<T: pwm::Instance, U: timer::Instance> BackgroundPlayer<T, U> {
+    pub fn new(speaker: Speaker<T>, mut timer: Timer<U>) -> Self {
+        timer.enable_interrupt();
+        Self {
+            speaker,
+            timer,
+            steps: None,
+            remaining_ms: 0,
+        }
+    }
+
+    /// Start playing `melody`, replacing anything that is currently playing. The melody is checked first, so an
+    /// invalid note is reported here instead of silently cutting playback short.
+    pub fn play(&mut self, melody: &'static str, tempo: Tempo, looping: bool) -> Result<(), ParseError> {
+        Melody::validate(melody)?;
+        self.steps = Some(Player::new(melody, tempo).looping(looping));
+        self.remaining_ms = 0;
+        self.advance();
+        Ok(())
+    }
+
+    /// Stop playback immediately
+    pub fn stop(&mut self) {
+        self.steps = None;
+        self.remaining_ms = 0;
+        self.speaker.stop();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.steps.is_some()
+    }
+
+    /// Call from the timer's interrupt handler: wait out the rest of the current step, or start the next one
+    pub fn on_timer_interrupt(&mut self) {
+        self.timer.reset_event();
+        self.advance();
+    }
+
+    fn advance(&mut self) {
+        if self.remaining_ms > 0 {
+            self.wait(self.remaining_ms);
+            return;
+        }
+        match self.steps.as_mut().and_then(Iterator::next) {
+            Some(step) => {
+                self.speaker.start_step(step);
+                self.wait(step.duration_ms());
+            }
+            None => self.stop(),
+        }
+    }
+
+    /// Set the timer for `duration_ms`, or as much of it as the timer can count
+    fn wait(&mut self, duration_ms: u32) {
+        let (wait_ms, remaining_ms) = split_wait(duration_ms, TIMER_MAX_MS);
+        self.remaining_ms = remaining_ms;
+        self.timer.start(wait_ms * TIMER_TICKS_PER_MS);
+    }
+}
@@ -0,0 +1,186 @@
+//! Host tests for melody parsing and playback sequencing.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use example_10_speaker::{
+    melody::{frequency, Melody, Note, ParseError, ParseErrorKind, Tempo, TempoError},
+    player::{split_wait, Player, Step, ARTICULATION_MS},
+};
+
+fn notes(text: &str) -> Vec<Note> {
+    Melody::new(text, Tempo::default()).map(Result::unwrap).collect()
+}
+
+fn note(frequency_hz: u32, duration_ms: u32) -> Note {
+    Note {
+        frequency_hz,
+        duration_ms,
+    }
+}
+
+#[test]
+fn note_frequencies_follow_equal_temperament() {
+    assert_eq!(frequency(9, 4), 440); // A4
+    assert_eq!(frequency(0, 4), 262); // Middle C
+    assert_eq!(frequency(9, 5), 880); // A5
+    assert_eq!(frequency(9, 0), 28); // A0 (27.5 Hz)
+    assert_eq!(frequency(11, 8), 7902); // B8
+}
+
+#[test]
+fn parses_micropython_example() {
+    // One tick is 125 ms at the default 120 bpm with 4 ticks per beat
+    assert_eq!(notes("C4:4 D E"), [note(262, 500), note(294, 500), note(330, 500)]);
+}
+
+#[test]
+fn octave_and_duration_are_sticky() {
+    assert_eq!(
+        notes("A5:2 B C6:8 D"),
+        [note(880, 250), note(988, 250), note(1047, 1000), note(1175, 1000)]
+    );
+}
+
+#[test]
+fn sharps_flats_and_rests() {
+    assert_eq!(
+        notes("C#4 Db4 r:2 Bb3 B#3 Cb4"),
+        [
+            note(277, 500),
+            note(277, 500),
+            note(0, 250),
+            note(233, 250),
+            note(262, 250),
+            note(247, 250)
+        ]
+    );
+}
+
+#[test]
+fn whitespace_is_flexible() {
+    assert_eq!(
+        notes("  C4:1\t\tE\n G  "),
+        [note(262, 125), note(330, 125), note(392, 125)]
+    );
+    assert_eq!(notes(""), []);
+}
+
+#[test]
+fn tempo_sets_tick_length() {
+    let tempo = Tempo::new(60, 2).unwrap();
+    let melody: Vec<_> = Melody::new("A4:1 A4:3", tempo).map(Result::unwrap).collect();
+    assert_eq!(melody, [note(440, 500), note(440, 1500)]);
+}
+
+#[test]
+fn tempo_rejects_zero() {
+    assert_eq!(Tempo::new(0, 4), Err(TempoError::ZeroBpm));
+    assert_eq!(Tempo::new(120, 0), Err(TempoError::ZeroTicksPerBeat));
+    assert_eq!(Tempo::new(120, 4), Ok(Tempo::default()));
+}
+
+#[test]
+fn tempo_does_not_overflow() {
+    // ticks * 60_000 and bpm * ticks_per_beat are both past u32::MAX here
+    let fast = Tempo::new(u32::MAX, u32::MAX).unwrap();
+    assert_eq!(fast.ticks_to_ms(u32::MAX), 0);
+    assert_eq!(Tempo::new(60, 1).unwrap().ticks_to_ms(100_000), 100_000_000);
+    // Longer than u32::MAX ms: the most there is
+    assert_eq!(Tempo::new(1, 1).unwrap().ticks_to_ms(u32::MAX), u32::MAX);
+}
+
+#[test]
+fn errors_report_position_and_kind() {
+    let error = |text, position, kind| {
+        assert_eq!(Melody::validate(text), Err(ParseError { position, kind }), "{text:?}");
+    };
+    error("C4 H4", 3, ParseErrorKind::InvalidName);
+    error("C9", 0, ParseErrorKind::InvalidOctave);
+    error("C4 D44", 3, ParseErrorKind::InvalidOctave);
+    error("Cb0", 0, ParseErrorKind::InvalidOctave);
+    error("C4 E:0", 3, ParseErrorKind::InvalidDuration);
+    error("C4 E: F", 3, ParseErrorKind::InvalidDuration);
+    error("C:x", 0, ParseErrorKind::InvalidDuration);
+    assert_eq!(Melody::validate("C4:4 D E"), Ok(()));
+}
+
+#[test]
+fn player_separates_notes_with_a_gap() {
+    let steps: Vec<_> = Player::new("C4:1 C R:2", Tempo::default()).collect();
+    assert_eq!(
+        steps,
+        [
+            Step::Tone {
+                frequency_hz: 262,
+                duration_ms: 125 - ARTICULATION_MS
+            },
+            Step::Silence {
+                duration_ms: ARTICULATION_MS
+            },
+            Step::Tone {
+                frequency_hz: 262,
+                duration_ms: 125 - ARTICULATION_MS
+            },
+            Step::Silence {
+                duration_ms: ARTICULATION_MS
+            },
+            Step::Silence { duration_ms: 250 },
+        ]
+    );
+}
+
+#[test]
+fn player_total_duration_matches_melody() {
+    let melody = "E4:4 E F G G F E D C C D E E:6 D:2 D:8";
+    let total: u32 = Player::new(melody, Tempo::default())
+        .map(|step| step.duration_ms())
+        .sum();
+    assert_eq!(total, (12 * 4 + 6 + 2 + 8) * 125);
+}
+
+#[test]
+fn player_stops_at_invalid_note() {
+    let steps: Vec<_> = Player::new("A4:1 X B", Tempo::default()).collect();
+    assert_eq!(steps.len(), 2);
+}
+
+#[test]
+fn long_steps_are_waited_in_chunks() {
+    // The same limit as the speaker's 1 MHz timer: u32::MAX ticks
+    const MAX_MS: u32 = u32::MAX / 1_000;
+
+    // 1000 ticks at 1 bpm: almost 17 hours, far more than the timer counts in one go
+    let step = Player::new("C:1000", Tempo::new(1, 1).unwrap()).next().unwrap();
+    assert_eq!(step.duration_ms(), 60_000_000 - ARTICULATION_MS);
+
+    let mut waits = Vec::new();
+    let mut remaining_ms = step.duration_ms();
+    while remaining_ms > 0 {
+        let (wait_ms, rest_ms) = split_wait(remaining_ms, MAX_MS);
+        assert!(wait_ms.checked_mul(1_000).is_some(), "{wait_ms} ms overflows the timer");
+        waits.push(wait_ms);
+        remaining_ms = rest_ms;
+    }
+    assert_eq!(waits.len(), 14);
+    assert_eq!(
+        waits.iter().map(|&ms| u64::from(ms)).sum::<u64>(),
+        u64::from(step.duration_ms())
+    );
+
+    assert_eq!(split_wait(125, MAX_MS), (125, 0));
+    // A zero length step still waits, so the timer fires and playback goes on
+    assert_eq!(split_wait(0, MAX_MS), (1, 0));
+}
+
+#[test]
+fn looping_player_restarts() {
+    let steps: Vec<_> = Player::new("A4:1 R:1", Tempo::default())
+        .looping(true)
+        .take(6)
+        .collect();
+    assert_eq!(steps[0], steps[3]);
+    assert_eq!(steps[2], Step::Silence { duration_ms: 125 });
+    assert_eq!(steps[5], Step::Silence { duration_ms: 125 });
+
+    assert_eq!(Player::new("", Tempo::default()).looping(true).next(), None);
+}
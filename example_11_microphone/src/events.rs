@@ -0,0 +1,54 @@
+//! Loud/quiet events with hysteresis.
+//!
+//! A single threshold would chatter: a level hovering around it would flip between loud and quiet on every buffer.
+//! [`SoundDetector`] uses two thresholds instead. It only becomes loud once the level reaches `loud_db`, and only
+//! becomes quiet again once the level has dropped to `quiet_db`. Anything in between keeps the current state.
+
+/// A change in sound state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEvent {
+    /// The level rose to the loud threshold
+    Loud,
+    /// The level fell back to the quiet threshold
+    Quiet,
+}
+
+/// Turns a stream of levels into [`SoundEvent`]s, see the module documentation
+#[derive(Debug, Clone)]
+pub struct SoundDetector {
+    loud_db: f32,
+    quiet_db: f32,
+    loud: bool,
+}
+
+impl SoundDetector {
+    /// Create a detector, starting in the quiet state.
+    ///
+    /// Panics if `quiet_db` is not below `loud_db`: without a gap between them there is no hysteresis.
+    pub fn new(loud_db: f32, quiet_db: f32) -> Self {
+        assert!(quiet_db < loud_db, "quiet threshold must be below the loud threshold");
+        Self {
+            loud_db,
+            quiet_db,
+            loud: false,
+        }
+    }
+
+    /// True between a [`SoundEvent::Loud`] and the following [`SoundEvent::Quiet`]
+    pub fn is_loud(&self) -> bool {
+        self.loud
+    }
+
+    /// Feed the next level (dBFS). Returns an event when the state changes.
+    pub fn update(&mut self, level_db: f32) -> Option<SoundEvent> {
+        if !self.loud && level_db >= self.loud_db {
+            self.loud = true;
+            Some(SoundEvent::Loud)
+        } else if self.loud && level_db <= self.quiet_db {
+            self.loud = false;
+            Some(SoundEvent::Quiet)
+        } else {
+            None
+        }
+    }
+}
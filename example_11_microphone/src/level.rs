@@ -0,0 +1,56 @@
+//! Sound level measurement.
+//!
+//! Levels are in dBFS (decibels relative to full scale): 0 dB is an amplitude of [`FULL_SCALE`], the largest
+//! swing the 12 bit SAADC can report around the middle of its range. Every halving of the amplitude is -6 dB.
+//! A full scale sine wave therefore has a peak level of 0 dB and an RMS level of -3 dB.
+//!
+//! The microphone output sits on a DC bias, so the mean of each buffer is removed before measuring.
+
+/// Largest amplitude around the mid point for 12 bit samples
+pub const FULL_SCALE: f32 = 2048.0;
+
+/// Level reported for silence (an amplitude of zero would be -infinity dB)
+pub const FLOOR_DB: f32 = -100.0;
+
+/// Sound level of one buffer of samples
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level {
+    /// Root mean square level: how loud the buffer sounds on average
+    pub rms_db: f32,
+    /// Largest single deviation from the mean: catches short clicks and claps that barely move the RMS
+    pub peak_db: f32,
+}
+
+/// Convert an amplitude (in ADC counts) to dBFS, limited to [`FLOOR_DB`]
+pub fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return FLOOR_DB;
+    }
+    (20.0 * libm::log10f(amplitude / FULL_SCALE)).max(FLOOR_DB)
+}
+
+/// Measure RMS and peak level of a buffer of raw SAADC samples. An empty buffer is silence.
+pub fn measure(samples: &[i16]) -> Level {
+    if samples.is_empty() {
+        return Level {
+            rms_db: FLOOR_DB,
+            peak_db: FLOOR_DB,
+        };
+    }
+
+    let count = samples.len() as f32;
+    let mean = samples.iter().map(|&s| i32::from(s)).sum::<i32>() as f32 / count;
+
+    let mut sum_of_squares = 0.0;
+    let mut peak: f32 = 0.0;
+    for &sample in samples {
+        let deviation = f32::from(sample) - mean;
+        sum_of_squares += deviation * deviation;
+        peak = peak.max(libm::fabsf(deviation));
+    }
+
+    Level {
+        rms_db: to_dbfs(libm::sqrtf(sum_of_squares / count)),
+        peak_db: to_dbfs(peak),
+    }
+}
@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Sound level measurement with the micro:bit v2 onboard microphone.
+//!
+//! - [`level`] computes RMS and peak levels in dBFS from a buffer of samples
+//! - [`events`] turns levels into loud/quiet events with hysteresis
+//! - [`microphone`] samples the microphone with SAADC and EasyDMA double buffering (target only)
+//!
+//! `level` and `events` are plain Rust and are tested on the PC with synthetic waveforms, see `tests/`.
+
+pub mod events;
+pub mod level;
+
+#[cfg(target_os = "none")]
+pub mod microphone;
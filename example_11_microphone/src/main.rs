@@ -0,0 +1,95 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::cell::{Cell, RefCell},
+    cortex_m::interrupt::Mutex,
+    cortex_m_rt::entry,
+    example_11_microphone::{
+        events::{SoundDetector, SoundEvent},
+        level::{self, Level},
+        microphone::{Buffers, Microphone, BUFFER_LEN},
+    },
+    microbit::hal::pac::{self, interrupt},
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// Become loud at -20 dBFS, quiet again below -30 dBFS
+#[cfg(target_os = "none")]
+const LOUD_DB: f32 = -20.0;
+#[cfg(target_os = "none")]
+const QUIET_DB: f32 = -30.0;
+
+/// Print the level every 16 buffers (about every half second)
+#[cfg(target_os = "none")]
+const PRINT_EVERY: u32 = 16;
+
+// The microphone is owned by the SAADC interrupt, which hands the level of every full buffer to main
+#[cfg(target_os = "none")]
+static MICROPHONE: Mutex<RefCell<Option<Microphone>>> = Mutex::new(RefCell::new(None));
+#[cfg(target_os = "none")]
+static LATEST_LEVEL: Mutex<Cell<Option<Level>>> = Mutex::new(Cell::new(None));
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let buffers: &'static mut Buffers = cortex_m::singleton!(: Buffers = [[0; BUFFER_LEN]; 2]).unwrap();
+    let mut microphone = Microphone::new(
+        board.ADC,
+        board.microphone_pins.mic_in,
+        board.microphone_pins.mic_run,
+        buffers,
+    );
+    microphone.start();
+    cortex_m::interrupt::free(|cs| MICROPHONE.borrow(cs).replace(Some(microphone)));
+
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::SAADC);
+    }
+
+    rprintln!("Microphone started, make some noise!");
+
+    let mut detector = SoundDetector::new(LOUD_DB, QUIET_DB);
+    let mut buffer_count: u32 = 0;
+
+    loop {
+        // Sleep until the next buffer has been measured
+        cortex_m::asm::wfi();
+        let Some(level) = cortex_m::interrupt::free(|cs| LATEST_LEVEL.borrow(cs).take()) else {
+            continue;
+        };
+
+        match detector.update(level.rms_db) {
+            Some(SoundEvent::Loud) => rprintln!("LOUD  ({:.1} dB)", level.rms_db),
+            Some(SoundEvent::Quiet) => rprintln!("QUIET ({:.1} dB)", level.rms_db),
+            None => {}
+        }
+
+        buffer_count += 1;
+        if buffer_count.is_multiple_of(PRINT_EVERY) {
+            rprintln!("Level: rms {:.1} dB  peak {:.1} dB", level.rms_db, level.peak_db);
+        }
+    }
+}
+
+// A DMA buffer is full: measure it while the SAADC fills the other one
+#[cfg(target_os = "none")]
+#[interrupt]
+fn SAADC() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(microphone) = MICROPHONE.borrow(cs).borrow_mut().as_mut() {
+            if let Some(samples) = microphone.on_interrupt() {
+                LATEST_LEVEL.borrow(cs).set(Some(level::measure(samples)));
+            }
+        }
+    });
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
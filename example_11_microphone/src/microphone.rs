@@ -0,0 +1,150 @@
+//! Onboard microphone driver: SAADC sampling with EasyDMA double buffering.
+//!
+//! The micro:bit v2 MEMS microphone output is on P0.05 (analog input AIN3). P0.20 powers the microphone and also
+//! lights the microphone LED next to the logo, so it is on whenever the microphone is.
+//!
+//! The SAADC samples continuously from its own internal timer and EasyDMA writes the results straight into RAM.
+//! Two buffers take turns: while DMA fills one, the application processes the other.
+//!
+//! ```text
+//! DMA writes:   [ buffer 0 ][ buffer 1 ][ buffer 0 ][ buffer 1 ] ...
+//! CPU reads:               [ buffer 0 ][ buffer 1 ][ buffer 0 ] ...
+//!                          ^ END event: buffer 0 full, START the next one
+//! ```
+//!
+//! RESULT.PTR is double buffered in hardware: the address is latched when the START task runs (STARTED event),
+//! after which the register can already be set to the next buffer.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use microbit::hal::{
+    gpio::{
+        p0::{P0_05, P0_20},
+        Disconnected, DriveConfig, Level, Output, PushPull,
+    },
+    pac::SAADC,
+};
+
+/// Samples per second
+pub const SAMPLE_RATE_HZ: u32 = 16_000;
+/// Samples per DMA buffer: 512 samples at 16 kHz is 32 ms of sound
+pub const BUFFER_LEN: usize = 512;
+
+/// The SAADC internal timer runs from the 16 MHz clock, CC sets the number of clocks between samples
+const SAMPLERATE_CC: u16 = (16_000_000 / SAMPLE_RATE_HZ) as u16;
+
+/// The two DMA buffers. They must be in RAM and live forever, e.g. from `cortex_m::singleton!`.
+pub type Buffers = [[i16; BUFFER_LEN]; 2];
+
+/// The microphone, powered and ready to sample
+pub struct Microphone {
+    saadc: SAADC,
+    buffers: &'static mut Buffers,
+    /// Buffer DMA is currently writing
+    active: usize,
+    _mic_in: P0_05<Disconnected>,
+    _mic_run: P0_20<Output<PushPull>>,
+}
+
+impl Microphone {
+    /// Power the microphone and configure SAADC channel 0 for it. Sampling starts with [`Microphone::start`].
+    pub fn new(
+        saadc: SAADC,
+        mic_in: P0_05<Disconnected>,
+        mic_run: P0_20<Disconnected>,
+        buffers: &'static mut Buffers,
+    ) -> Self {
+        // The microphone draws its supply from this pin, so it needs high drive strength
+        let mic_run = mic_run.into_push_pull_output_drive(Level::High, DriveConfig::HighDrive0HighDrive1);
+
+        // Single ended input on AIN3, gain 4 with the 0.6 V internal reference: full scale is 0.15 V,
+        // which suits the small signal from the microphone
+        saadc.ch[0].pselp.write(|w| w.pselp().analog_input3());
+        saadc.ch[0].pseln.write(|w| w.pseln().nc());
+        saadc.ch[0].config.write(|w| {
+            w.resp().bypass();
+            w.resn().bypass();
+            w.gain().gain4();
+            w.refsel().internal();
+            w.tacq()._3us();
+            w.mode().se();
+            w.burst().disabled()
+        });
+
+        saadc.resolution.write(|w| w.val()._12bit());
+        saadc.oversample.write(|w| w.oversample().bypass());
+        // Continuous sampling paced by the SAADC's own timer: no TIMER or PPI needed
+        saadc
+            .samplerate
+            .write(|w| unsafe { w.cc().bits(SAMPLERATE_CC) }.mode().timers());
+        saadc.enable.write(|w| w.enable().enabled());
+
+        // Offset calibration once at startup
+        saadc.events_calibratedone.write(|w| unsafe { w.bits(0) });
+        saadc.tasks_calibrateoffset.write(|w| unsafe { w.bits(1) });
+        while saadc.events_calibratedone.read().bits() == 0 {}
+        saadc.events_calibratedone.write(|w| unsafe { w.bits(0) });
+
+        Self {
+            saadc,
+            buffers,
+            active: 0,
+            _mic_in: mic_in,
+            _mic_run: mic_run,
+        }
+    }
+
+    /// Start continuous sampling. Unmask the SAADC interrupt and call [`Microphone::on_interrupt`] from it.
+    pub fn start(&mut self) {
+        self.active = 0;
+        self.set_buffer(0);
+        self.saadc
+            .result
+            .maxcnt
+            .write(|w| unsafe { w.maxcnt().bits(BUFFER_LEN as u16) });
+
+        self.saadc.events_started.write(|w| unsafe { w.bits(0) });
+        self.saadc.events_end.write(|w| unsafe { w.bits(0) });
+        self.saadc.intenset.write(|w| w.started().set().end().set());
+
+        self.saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+        // In timer mode one SAMPLE task starts sampling, the internal timer keeps it going
+        self.saadc.tasks_sample.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Stop sampling
+    pub fn stop(&mut self) {
+        self.saadc.intenclr.write(|w| w.started().clear().end().clear());
+        self.saadc.tasks_stop.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Handle the SAADC interrupt. Returns the buffer that has just been filled, if any.
+    ///
+    /// The returned samples stay valid until the next buffer completes (32 ms), after that DMA writes to them again.
+    pub fn on_interrupt(&mut self) -> Option<&[i16]> {
+        if self.saadc.events_started.read().bits() != 0 {
+            self.saadc.events_started.write(|w| unsafe { w.bits(0) });
+            // The active buffer address has been latched, queue the other one for the next START
+            self.set_buffer(self.active ^ 1);
+        }
+
+        if self.saadc.events_end.read().bits() != 0 {
+            self.saadc.events_end.write(|w| unsafe { w.bits(0) });
+            let done = self.active;
+            self.active ^= 1;
+            // Switch DMA to the queued buffer straight away to lose as few samples as possible
+            self.saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+
+            // Do not let the compiler move reads of the buffer before the END event was seen
+            compiler_fence(Ordering::SeqCst);
+            return Some(&self.buffers[done]);
+        }
+
+        None
+    }
+
+    fn set_buffer(&mut self, index: usize) {
+        let address = self.buffers[index].as_mut_ptr() as u32;
+        self.saadc.result.ptr.write(|w| unsafe { w.ptr().bits(address) });
+    }
+}
@@ -0,0 +1,134 @@
+//! Host tests for sound level measurement and loud/quiet events, using synthetic waveforms.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use std::f32::consts::PI;
+
+use example_11_microphone::{
+    events::{SoundDetector, SoundEvent},
+    level::{measure, to_dbfs, FLOOR_DB, FULL_SCALE},
+};
+
+/// DC bias the microphone output sits on, roughly mid-range for 12 bit samples
+const BIAS: f32 = 2048.0;
+
+fn sine(amplitude: f32, cycles: f32, len: usize) -> Vec<i16> {
+    (0..len)
+        .map(|i| (BIAS + amplitude * (2.0 * PI * cycles * i as f32 / len as f32).sin()).round() as i16)
+        .collect()
+}
+
+fn square(amplitude: f32, half_period: usize, len: usize) -> Vec<i16> {
+    (0..len)
+        .map(|i| if (i / half_period).is_multiple_of(2) { BIAS + amplitude } else { BIAS - amplitude } as i16)
+        .collect()
+}
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 0.1,
+        "expected {expected} dB, got {actual} dB"
+    );
+}
+
+#[test]
+fn dbfs_conversion() {
+    assert_close(to_dbfs(FULL_SCALE), 0.0);
+    assert_close(to_dbfs(FULL_SCALE / 2.0), -6.02);
+    assert_close(to_dbfs(FULL_SCALE / 10.0), -20.0);
+    assert_eq!(to_dbfs(0.0), FLOOR_DB);
+    assert_eq!(to_dbfs(1e-9), FLOOR_DB);
+}
+
+#[test]
+fn full_scale_sine() {
+    // Whole number of cycles so the mean is exactly the bias
+    let level = measure(&sine(2047.0, 8.0, 512));
+    assert_close(level.peak_db, 0.0);
+    assert_close(level.rms_db, -3.01);
+}
+
+#[test]
+fn quiet_sine_is_20db_down() {
+    let level = measure(&sine(204.8, 4.0, 512));
+    assert_close(level.peak_db, -20.0);
+    assert_close(level.rms_db, -23.01);
+}
+
+#[test]
+fn square_wave_rms_equals_peak() {
+    let level = measure(&square(512.0, 16, 512));
+    assert_close(level.peak_db, -12.04);
+    assert_close(level.rms_db, -12.04);
+}
+
+#[test]
+fn dc_offset_is_ignored() {
+    let low = measure(&[100; 64]);
+    let high = measure(&[3000; 64]);
+    assert_eq!(low.rms_db, FLOOR_DB);
+    assert_eq!(high.peak_db, FLOOR_DB);
+    assert_eq!(measure(&[]).rms_db, FLOOR_DB);
+}
+
+#[test]
+fn click_raises_peak_more_than_rms() {
+    let mut samples = vec![2048; 512];
+    samples[100] = 2048 + 1024;
+    let level = measure(&samples);
+    assert_close(level.peak_db, -6.02);
+    assert!(level.rms_db < -30.0);
+}
+
+#[test]
+fn detector_uses_hysteresis() {
+    let mut detector = SoundDetector::new(-20.0, -30.0);
+    let events: Vec<_> = [-50.0, -25.0, -20.0, -15.0, -25.0, -29.9, -30.0, -25.0, -10.0]
+        .into_iter()
+        .map(|db| detector.update(db))
+        .collect();
+
+    assert_eq!(
+        events,
+        [
+            None,
+            None,
+            Some(SoundEvent::Loud),
+            None,
+            None, // Below the loud threshold but not quiet yet
+            None,
+            Some(SoundEvent::Quiet),
+            None,
+            Some(SoundEvent::Loud),
+        ]
+    );
+    assert!(detector.is_loud());
+}
+
+#[test]
+fn detector_does_not_chatter_around_one_threshold() {
+    let mut detector = SoundDetector::new(-20.0, -30.0);
+    // Noise hovering around the loud threshold produces a single event
+    let events = [-21.0, -19.0, -21.0, -19.5, -20.5, -19.0]
+        .into_iter()
+        .filter_map(|db| detector.update(db))
+        .count();
+    assert_eq!(events, 1);
+}
+
+#[test]
+fn detector_from_waveforms() {
+    let mut detector = SoundDetector::new(-20.0, -30.0);
+    let loud = measure(&sine(1000.0, 8.0, 512)).rms_db;
+    let quiet = measure(&sine(20.0, 8.0, 512)).rms_db;
+
+    assert_eq!(detector.update(quiet), None);
+    assert_eq!(detector.update(loud), Some(SoundEvent::Loud));
+    assert_eq!(detector.update(quiet), Some(SoundEvent::Quiet));
+}
+
+#[test]
+#[should_panic]
+fn thresholds_must_not_overlap() {
+    SoundDetector::new(-30.0, -20.0);
+}
@@ -0,0 +1,57 @@
+//! Converts every WAV file in `audio/` into a `pub static NAME: &[u8]` table of 8 kHz, 8 bit mono samples.
+//!
+//! The generated file is `$OUT_DIR/clips.rs`, included by the `clips` module in `src/lib.rs`. Converting at build
+//! time keeps the WAV parsing out of the firmware and stores only the samples the speaker actually plays.
+
+use std::{env, fmt::Write, fs, path::PathBuf};
+
+// The conversion itself lives in the library so it can be tested; only part of it is needed here
+#[allow(dead_code)]
+#[path = "src/wav.rs"]
+mod wav;
+
+fn main() {
+    println!("cargo:rerun-if-changed=audio");
+    println!("cargo:rerun-if-changed=src/wav.rs");
+
+    let mut paths: Vec<PathBuf> = fs::read_dir("audio")
+        .expect("audio/ directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav")))
+        .collect();
+    paths.sort();
+
+    let mut code = String::new();
+    for path in paths {
+        let bytes = fs::read(&path).unwrap();
+        let wav = wav::Wav::parse(&bytes).unwrap_or_else(|e| panic!("{}: {:?}", path.display(), e));
+        let samples: Vec<u8> = wav.to_pcm8(wav::OUTPUT_RATE_HZ).collect();
+
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        let name: String = stem
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+
+        writeln!(
+            code,
+            "/// `audio/{}.wav`: {} ms, converted from {} Hz, {} bit, {} channel(s)",
+            stem,
+            wav.duration_ms(),
+            wav.sample_rate,
+            wav.bits_per_sample,
+            wav.channels
+        )
+        .unwrap();
+        writeln!(code, "pub static {}: &[u8] = &{:?};", name, samples).unwrap();
+    }
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("clips.rs");
+    fs::write(out, code).unwrap();
+}
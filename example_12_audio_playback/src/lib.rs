@@ -0,0 +1,22 @@
+#![no_std]
+
+//! Audio clip playback for the micro:bit v2 speaker.
+//!
+//! - [`wav`] converts WAV files to 8 kHz, 8 bit mono PCM (used by `build.rs`)
+//! - [`pcm`] turns PCM samples into PWM duty cycles
+//! - [`clips`] holds the clips from `audio/`, converted at build time
+//! - [`playback`] streams a clip to the speaker with PWM sequences and EasyDMA (target only)
+//!
+//! `wav` and `pcm` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod pcm;
+pub mod wav;
+
+/// Clips converted from `audio/*.wav` by `build.rs`: one `&[u8]` of 8 kHz, 8 bit mono samples per file, named
+/// after the file (`audio/chime.wav` becomes `CHIME`)
+pub mod clips {
+    include!(concat!(env!("OUT_DIR"), "/clips.rs"));
+}
+
+#[cfg(target_os = "none")]
+pub mod playback;
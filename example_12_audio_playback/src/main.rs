@@ -0,0 +1,88 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::cell::RefCell,
+    cortex_m::interrupt::Mutex,
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_12_audio_playback::{
+        clips,
+        playback::{Buffers, ClipPlayer, CHUNK},
+    },
+    microbit::hal::{
+        gpio::Level,
+        pac::{self, interrupt},
+        Timer,
+    },
+    panic_halt as _,
+};
+
+// The player is used by main (to start a clip) and by the PWM0 interrupt (to refill the sequence buffers)
+#[cfg(target_os = "none")]
+static PLAYER: Mutex<RefCell<Option<ClipPlayer>>> = Mutex::new(RefCell::new(None));
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+
+    let speaker_pin = board.speaker_pin.into_push_pull_output(Level::Low).degrade();
+    let buffers: &'static mut Buffers = cortex_m::singleton!(: Buffers = [[0; CHUNK]; 2]).unwrap();
+
+    let mut player = ClipPlayer::new(board.PWM0, speaker_pin, buffers);
+    player.play(clips::CHIME);
+    cortex_m::interrupt::free(|cs| PLAYER.borrow(cs).replace(Some(player)));
+
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::PWM0);
+    }
+
+    // Button A plays the chime, button B the sweep
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_b = board.buttons.button_b.into_floating_input();
+    let mut a_was_pressed = false;
+    let mut b_was_pressed = false;
+
+    loop {
+        let a_pressed = button_a.is_low().unwrap();
+        let b_pressed = button_b.is_low().unwrap();
+
+        let clip = if a_pressed && !a_was_pressed {
+            Some(clips::CHIME)
+        } else if b_pressed && !b_was_pressed {
+            Some(clips::SWEEP)
+        } else {
+            None
+        };
+
+        if let Some(clip) = clip {
+            cortex_m::interrupt::free(|cs| {
+                if let Some(player) = PLAYER.borrow(cs).borrow_mut().as_mut() {
+                    player.play(clip);
+                }
+            });
+        }
+
+        a_was_pressed = a_pressed;
+        b_was_pressed = b_pressed;
+        timer0.delay_ms(10); // Simple debounce
+    }
+}
+
+// A sequence buffer has finished playing: refill it with the next chunk of the clip
+#[cfg(target_os = "none")]
+#[interrupt]
+fn PWM0() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(player) = PLAYER.borrow(cs).borrow_mut().as_mut() {
+            player.on_interrupt();
+        }
+    });
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
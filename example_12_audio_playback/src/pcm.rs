@@ -0,0 +1,46 @@
+//! PCM samples to PWM duty cycles.
+//!
+//! The PWM peripheral plays audio as a 64 kHz square wave whose duty cycle follows the sample value. The speaker
+//! cannot follow the 64 kHz carrier, so what you hear is the average: the audio signal.
+//!
+//! ```text
+//! 16 MHz PWM clock / COUNTERTOP 250     = 64 kHz carrier
+//! 64 kHz / (REFRESH 7 + 1) periods each = 8 kHz sample rate
+//! ```
+
+use crate::wav::OUTPUT_RATE_HZ;
+
+/// PWM clock with prescaler /1
+pub const PWM_CLOCK_HZ: u32 = 16_000_000;
+/// PWM period in clocks: 250 clocks at 16 MHz is a 64 kHz carrier, and duty values 0-250 cover 8 bit samples
+pub const COUNTERTOP: u16 = 250;
+/// Extra PWM periods each sample is held for: every sample plays for `REFRESH + 1` periods
+pub const REFRESH: u32 = 7;
+
+/// Duty cycle for silence (sample value 128)
+pub const SILENCE: u16 = duty(128);
+
+// The carrier and refresh count must give exactly the clip sample rate
+const _: () = assert!(PWM_CLOCK_HZ / COUNTERTOP as u32 / (REFRESH + 1) == OUTPUT_RATE_HZ);
+
+/// PWM compare value for an unsigned 8 bit sample, scaled from 0-255 to 0-COUNTERTOP
+pub const fn duty(sample: u8) -> u16 {
+    ((sample as u32 * COUNTERTOP as u32 + 127) / 255) as u16
+}
+
+/// Fill a PWM sequence buffer with the next samples, padding with silence once `samples` runs out.
+///
+/// Returns the number of real samples written (0 means the buffer is all silence).
+pub fn fill(buffer: &mut [u16], samples: &mut impl Iterator<Item = u8>) -> usize {
+    let mut written = 0;
+    for slot in buffer.iter_mut() {
+        match samples.next() {
+            Some(sample) => {
+                *slot = duty(sample);
+                written += 1;
+            }
+            None => *slot = SILENCE,
+        }
+    }
+    written
+}
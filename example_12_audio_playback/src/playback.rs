@@ -0,0 +1,128 @@
+//! Clip playback through the PWM peripheral's sequence mode (EasyDMA).
+//!
+//! In sequence mode the PWM reads its duty cycles from RAM with EasyDMA, one value per sample, and holds each for
+//! `REFRESH + 1` periods (see [`crate::pcm`]). Clips are 8 bit samples in flash, but the PWM needs 16 bit duty
+//! values in RAM, so the clip is converted a chunk at a time into two RAM buffers that take turns:
+//!
+//! ```text
+//! PWM plays:    [ SEQ0 ][ SEQ1 ][ SEQ0 ][ SEQ1 ] ...
+//! CPU refills:          [ SEQ0 ][ SEQ1 ][ SEQ0 ] ...
+//!                       ^ SEQEND0: SEQ0 finished, refill it while SEQ1 plays
+//! ```
+//!
+//! `LOOP.CNT = 1` plays SEQ0 then SEQ1, and the LOOPSDONE -> SEQSTART0 shortcut starts over without CPU
+//! involvement. The interrupt handler only has to refill the buffer that just finished.
+
+use core::{
+    iter::Copied,
+    slice,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+use microbit::hal::{
+    gpio::{Output, Pin, PushPull},
+    pac::PWM0,
+};
+
+use crate::pcm::{self, COUNTERTOP, REFRESH};
+
+/// Samples per sequence buffer: 256 samples at 8 kHz is 32 ms
+pub const CHUNK: usize = 256;
+
+/// The two sequence buffers. They must be in RAM and live forever, e.g. from `cortex_m::singleton!`.
+pub type Buffers = [[u16; CHUNK]; 2];
+
+/// Plays clips on the speaker, see the module documentation
+pub struct ClipPlayer {
+    pwm: PWM0,
+    buffers: &'static mut Buffers,
+    samples: Option<Copied<slice::Iter<'static, u8>>>,
+    /// Real (non silence) samples in each buffer
+    real: [usize; 2],
+    _pin: Pin<Output<PushPull>>,
+}
+
+impl ClipPlayer {
+    /// Configure PWM0 on the speaker pin. Unmask the PWM0 interrupt and call [`ClipPlayer::on_interrupt`] from it.
+    pub fn new(pwm: PWM0, speaker_pin: Pin<Output<PushPull>>, buffers: &'static mut Buffers) -> Self {
+        pwm.psel.out[0].write(|w| unsafe { w.bits(speaker_pin.psel_bits()) });
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| w.prescaler().div_1());
+        pwm.countertop.write(|w| unsafe { w.countertop().bits(COUNTERTOP) });
+        // One duty value per step for all channels, each held for REFRESH extra periods
+        pwm.decoder.write(|w| w.load().common().mode().refresh_count());
+
+        pwm.seq0.ptr.write(|w| unsafe { w.bits(buffers[0].as_ptr() as u32) });
+        pwm.seq0.cnt.write(|w| unsafe { w.bits(CHUNK as u32) });
+        pwm.seq0.refresh.write(|w| unsafe { w.bits(REFRESH) });
+        pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+
+        pwm.seq1.ptr.write(|w| unsafe { w.bits(buffers[1].as_ptr() as u32) });
+        pwm.seq1.cnt.write(|w| unsafe { w.bits(CHUNK as u32) });
+        pwm.seq1.refresh.write(|w| unsafe { w.bits(REFRESH) });
+        pwm.seq1.enddelay.write(|w| unsafe { w.bits(0) });
+
+        pwm.loop_.write(|w| unsafe { w.bits(1) });
+        pwm.enable.write(|w| w.enable().enabled());
+
+        Self {
+            pwm,
+            buffers,
+            samples: None,
+            real: [0; 2],
+            _pin: speaker_pin,
+        }
+    }
+
+    /// Start playing `clip` (8 kHz, 8 bit unsigned samples), replacing anything currently playing
+    pub fn play(&mut self, clip: &'static [u8]) {
+        self.stop();
+
+        let mut samples = clip.iter().copied();
+        self.real[0] = pcm::fill(&mut self.buffers[0], &mut samples);
+        self.real[1] = pcm::fill(&mut self.buffers[1], &mut samples);
+        self.samples = Some(samples);
+
+        // The buffers must be written before DMA starts reading them
+        compiler_fence(Ordering::SeqCst);
+
+        self.pwm.events_seqend[0].write(|w| unsafe { w.bits(0) });
+        self.pwm.events_seqend[1].write(|w| unsafe { w.bits(0) });
+        self.pwm.shorts.write(|w| w.loopsdone_seqstart0().enabled());
+        self.pwm.intenset.write(|w| w.seqend0().set().seqend1().set());
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Stop playback immediately
+    pub fn stop(&mut self) {
+        self.pwm.intenclr.write(|w| w.seqend0().clear().seqend1().clear());
+        self.pwm.shorts.reset();
+        self.pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.samples = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.samples.is_some()
+    }
+
+    /// Handle the PWM0 interrupt: refill whichever sequence buffer has just finished playing
+    pub fn on_interrupt(&mut self) {
+        for finished in 0..2 {
+            if self.pwm.events_seqend[finished].read().bits() == 0 {
+                continue;
+            }
+            self.pwm.events_seqend[finished].write(|w| unsafe { w.bits(0) });
+
+            // The other buffer is playing now. If it holds only silence, the whole clip has been played.
+            if self.real[finished ^ 1] == 0 {
+                self.stop();
+                return;
+            }
+
+            if let Some(samples) = self.samples.as_mut() {
+                self.real[finished] = pcm::fill(&mut self.buffers[finished], samples);
+                compiler_fence(Ordering::SeqCst);
+            }
+        }
+    }
+}
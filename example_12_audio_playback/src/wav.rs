@@ -0,0 +1,187 @@
+//! WAV file parsing and conversion to 8 kHz, 8 bit mono PCM.
+//!
+//! Used in two places:
+//! - `build.rs` includes this file to convert the clips in `audio/` into byte tables at build time
+//! - The library exports it so the conversion can be tested on the PC (`tests/wav.rs`)
+//!
+//! Only uncompressed PCM WAV files are supported, with 8 bit (unsigned) or 16 bit (signed) samples and any number
+//! of channels. Conversion mixes the channels down to mono, reduces 16 bit samples to 8 bit and resamples with
+//! linear interpolation. The output is unsigned 8 bit: 128 is silence.
+
+/// Sample rate of the converted clips
+pub const OUTPUT_RATE_HZ: u32 = 8_000;
+
+/// Why a WAV file could not be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavError {
+    /// The file does not start with a `RIFF....WAVE` header
+    NotWave,
+    /// A chunk claims to be longer than the file
+    Truncated,
+    /// No `fmt ` chunk before the `data` chunk
+    MissingFormat,
+    /// No `data` chunk
+    MissingData,
+    /// Not uncompressed PCM (format tag 1); the tag is included
+    UnsupportedFormat(u16),
+    /// Sample size other than 8 or 16 bits
+    UnsupportedBitsPerSample(u16),
+    /// Zero channels or a zero sample rate
+    InvalidFormat,
+}
+
+/// A parsed WAV file, borrowing the sample data from the file contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wav<'a> {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// Raw interleaved sample data (the `data` chunk)
+    pub data: &'a [u8],
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+impl<'a> Wav<'a> {
+    /// Parse the contents of a WAV file. Unknown chunks (`LIST`, `fact`, ...) are skipped.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, WavError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(WavError::NotWave);
+        }
+
+        let mut format = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = u32_at(bytes, offset + 4) as usize;
+            let body = offset + 8;
+            let end = body
+                .checked_add(size)
+                .filter(|&end| end <= bytes.len())
+                .ok_or(WavError::Truncated)?;
+
+            match id {
+                b"fmt " => {
+                    if size < 16 {
+                        return Err(WavError::Truncated);
+                    }
+                    let tag = u16_at(bytes, body);
+                    if tag != 1 {
+                        return Err(WavError::UnsupportedFormat(tag));
+                    }
+                    let channels = u16_at(bytes, body + 2);
+                    let sample_rate = u32_at(bytes, body + 4);
+                    let bits_per_sample = u16_at(bytes, body + 14);
+                    if bits_per_sample != 8 && bits_per_sample != 16 {
+                        return Err(WavError::UnsupportedBitsPerSample(bits_per_sample));
+                    }
+                    if channels == 0 || sample_rate == 0 {
+                        return Err(WavError::InvalidFormat);
+                    }
+                    format = Some((channels, sample_rate, bits_per_sample));
+                }
+                b"data" => {
+                    let (channels, sample_rate, bits_per_sample) = format.ok_or(WavError::MissingFormat)?;
+                    return Ok(Wav {
+                        channels,
+                        sample_rate,
+                        bits_per_sample,
+                        data: &bytes[body..end],
+                    });
+                }
+                _ => {}
+            }
+
+            // Chunks are padded to an even length
+            offset = end + (size & 1);
+        }
+
+        Err(WavError::MissingData)
+    }
+
+    /// Number of sample frames (one sample per channel)
+    pub fn frames(&self) -> usize {
+        self.data.len() / self.frame_size()
+    }
+
+    /// Duration in milliseconds
+    pub fn duration_ms(&self) -> u32 {
+        (self.frames() as u64 * 1000 / u64::from(self.sample_rate)) as u32
+    }
+
+    fn frame_size(&self) -> usize {
+        usize::from(self.channels) * usize::from(self.bits_per_sample / 8)
+    }
+
+    /// Mono sample of one frame as signed 16 bit, channels averaged
+    fn mono(&self, frame: usize) -> i32 {
+        let start = frame * self.frame_size();
+        let channels = i32::from(self.channels);
+        let sum: i32 = (0..usize::from(self.channels))
+            .map(|channel| match self.bits_per_sample {
+                8 => (i32::from(self.data[start + channel]) - 128) << 8,
+                _ => i32::from(u16_at(self.data, start + channel * 2) as i16),
+            })
+            .sum();
+        sum / channels
+    }
+
+    /// Convert to unsigned 8 bit mono at `rate_hz` (normally [`OUTPUT_RATE_HZ`])
+    pub fn to_pcm8(self, rate_hz: u32) -> Pcm8<'a> {
+        Pcm8 {
+            wav: self,
+            rate_hz,
+            index: 0,
+            count: self.frames() as u64 * u64::from(rate_hz) / u64::from(self.sample_rate),
+        }
+    }
+}
+
+/// Iterator over converted samples, see [`Wav::to_pcm8`]
+#[derive(Debug, Clone)]
+pub struct Pcm8<'a> {
+    wav: Wav<'a>,
+    rate_hz: u32,
+    index: u64,
+    count: u64,
+}
+
+impl Iterator for Pcm8<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.index >= self.count {
+            return None;
+        }
+
+        // Position of this output sample in input frames, as a whole part and a 16 bit fraction
+        let position = (self.index << 16) * u64::from(self.wav.sample_rate) / u64::from(self.rate_hz);
+        let frame = (position >> 16) as usize;
+        let fraction = (position & 0xFFFF) as i64;
+        self.index += 1;
+
+        let a = i64::from(self.wav.mono(frame));
+        let b = if frame + 1 < self.wav.frames() {
+            i64::from(self.wav.mono(frame + 1))
+        } else {
+            a
+        };
+        let sample = a + (((b - a) * fraction) >> 16);
+
+        // Signed 16 bit to unsigned 8 bit, rounding to nearest
+        Some(((sample + 128).clamp(-32768, 32767) >> 8) as u8 ^ 0x80)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Pcm8<'_> {}
@@ -0,0 +1,107 @@
+//! Host tests for the WAV converter and PCM to PWM duty conversion, using the sample WAVs in `tests/data/`.
+//!
+//! Run with `cargo test-host` (alias for `cargo test --target host-tuple`).
+
+use std::{fs, path::Path};
+
+use example_12_audio_playback::{
+    clips,
+    pcm::{self, COUNTERTOP, SILENCE},
+    wav::{Wav, WavError, OUTPUT_RATE_HZ},
+};
+
+fn read(path: &str) -> Vec<u8> {
+    fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap()
+}
+
+fn convert(bytes: &[u8]) -> Vec<u8> {
+    Wav::parse(bytes).unwrap().to_pcm8(OUTPUT_RATE_HZ).collect()
+}
+
+#[test]
+fn parses_format() {
+    let bytes = read("tests/data/stereo_16k_s16.wav");
+    let wav = Wav::parse(&bytes).unwrap();
+    assert_eq!((wav.channels, wav.sample_rate, wav.bits_per_sample), (2, 16_000, 16));
+    assert_eq!(wav.frames(), 16);
+    assert_eq!(wav.duration_ms(), 1);
+}
+
+#[test]
+fn eight_bit_mono_at_8khz_is_unchanged() {
+    let bytes = read("tests/data/mono_8k_u8.wav");
+    assert_eq!(convert(&bytes), [128, 255, 0, 128, 200, 56, 128, 129]);
+}
+
+#[test]
+fn sixteen_bit_stereo_is_mixed_downsampled_and_reduced() {
+    // Frames are (i * 1000, i * 1000 + 512) for i = -8..8: mono is i * 1000 + 256, every second frame is kept
+    let bytes = read("tests/data/stereo_16k_s16.wav");
+    let expected: Vec<u8> = (-8..8)
+        .step_by(2)
+        .map(|i: i32| (((i * 1000 + 256 + 128) >> 8) + 128) as u8)
+        .collect();
+    assert_eq!(convert(&bytes), expected);
+}
+
+#[test]
+fn upsampling_interpolates() {
+    // 0, +25600, -25600, 0 at 4 kHz: every second output sample is halfway between two input samples
+    let bytes = read("tests/data/mono_4k_s16.wav");
+    assert_eq!(convert(&bytes), [128, 178, 228, 128, 28, 78, 128, 128]);
+}
+
+#[test]
+fn skips_unknown_chunks_with_padding() {
+    let bytes = read("tests/data/list_chunk_8k_u8.wav");
+    assert_eq!(convert(&bytes), [10, 20, 30]);
+}
+
+#[test]
+fn rejects_unsupported_files() {
+    assert_eq!(
+        Wav::parse(&read("tests/data/float_8k_f32.wav")),
+        Err(WavError::UnsupportedFormat(3))
+    );
+    assert_eq!(Wav::parse(b"RIFF\0\0\0\0AVI "), Err(WavError::NotWave));
+    assert_eq!(Wav::parse(b""), Err(WavError::NotWave));
+
+    // Cut off in the middle of the data chunk
+    let bytes = read("tests/data/mono_8k_u8.wav");
+    assert_eq!(Wav::parse(&bytes[..bytes.len() - 2]), Err(WavError::Truncated));
+
+    // Header only
+    assert_eq!(Wav::parse(&bytes[..12]), Err(WavError::MissingData));
+}
+
+#[test]
+fn build_script_output_matches_converter() {
+    assert_eq!(clips::CHIME, convert(&read("audio/chime.wav")).as_slice());
+    assert_eq!(clips::SWEEP, convert(&read("audio/sweep.wav")).as_slice());
+
+    // 0.6 s and 0.4 s at 8 kHz
+    assert_eq!(clips::CHIME.len(), 4800);
+    assert_eq!(clips::SWEEP.len(), 3200);
+}
+
+#[test]
+fn duty_covers_the_pwm_range() {
+    assert_eq!(pcm::duty(0), 0);
+    assert_eq!(pcm::duty(255), COUNTERTOP);
+    assert_eq!(SILENCE, COUNTERTOP / 2);
+}
+
+#[test]
+fn fill_pads_with_silence() {
+    let mut buffer = [0u16; 4];
+    let mut samples = [0u8, 255, 128, 64, 192, 10].into_iter();
+
+    assert_eq!(pcm::fill(&mut buffer, &mut samples), 4);
+    assert_eq!(buffer, [0, 250, 125, 63]);
+
+    assert_eq!(pcm::fill(&mut buffer, &mut samples), 2);
+    assert_eq!(buffer, [188, 10, SILENCE, SILENCE]);
+
+    assert_eq!(pcm::fill(&mut buffer, &mut samples), 0);
+    assert_eq!(buffer, [SILENCE; 4]);
+}
@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Radio messages between micro:bits running this example: bytes or strings, in groups, on a choice of channels.
+//!
+//! - [`packet`] defines the settings and the frame format
+//! - [`radio`] is the `send` / `receive` API, on top of any [`Transceiver`](radio::Transceiver)
+//! - [`nrf_radio`] drives the nRF52833 RADIO peripheral (target only)
+//!
+//! `packet` and `radio` are plain Rust and are tested on the PC with a loopback transceiver, see `tests/`.
+
+pub mod packet;
+pub mod radio;
+
+#[cfg(target_os = "none")]
+pub mod nrf_radio;
@@ -0,0 +1,91 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::{
+        delay::DelayNs,
+        digital::{InputPin, OutputPin},
+    },
+    example_13_radio::{
+        nrf_radio::{Buffers, NrfRadio},
+        packet::MAX_FRAME_LEN,
+        radio::Radio,
+    },
+    microbit::hal::{clocks::Clocks, gpio::Level, Timer},
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// Group shared with the other micro:bit(s): frames from other groups are dropped
+#[cfg(target_os = "none")]
+const GROUP: u8 = 1;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+
+    // The radio needs the accurate crystal oscillator instead of the internal RC oscillator
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+
+    let buffers: &'static mut Buffers = cortex_m::singleton!(: Buffers = [[0; MAX_FRAME_LEN]; 2]).unwrap();
+    let mut radio = Radio::new(NrfRadio::new(board.RADIO, &clocks, buffers));
+    radio.set_group(GROUP).unwrap();
+
+    let config = radio.config();
+    rprintln!(
+        "Radio on channel {} ({} MHz), group {}, {} dBm",
+        config.channel,
+        config.frequency_mhz(),
+        config.group,
+        config.tx_power_dbm()
+    );
+
+    // Top-left LED flashes for every message received
+    let mut led = board.display_pins.row1.into_push_pull_output(Level::Low);
+    let _col1 = board.display_pins.col1.into_push_pull_output(Level::Low);
+
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_b = board.buttons.button_b.into_floating_input();
+    let mut a_was_pressed = false;
+    let mut b_was_pressed = false;
+    let mut count: u8 = 0;
+    let mut buffer = [0; MAX_FRAME_LEN];
+
+    loop {
+        // Button A sends a string, button B raw bytes
+        let a_pressed = button_a.is_low().unwrap();
+        let b_pressed = button_b.is_low().unwrap();
+        if a_pressed && !a_was_pressed {
+            radio.send_str("hello").unwrap();
+            rprintln!("Sent \"hello\"");
+        }
+        if b_pressed && !b_was_pressed {
+            count = count.wrapping_add(1);
+            radio.send(&[0xB0, count]).unwrap();
+            rprintln!("Sent bytes [0xB0, {}]", count);
+        }
+        a_was_pressed = a_pressed;
+        b_was_pressed = b_pressed;
+
+        while let Some(payload) = radio.receive(&mut buffer) {
+            // Button B's 0xB0 can only continue a UTF-8 character, never start one: the bytes are not text
+            match core::str::from_utf8(payload) {
+                Ok(text) => rprintln!("Received \"{}\"", text),
+                Err(_) => rprintln!("Received bytes {:02X?}", payload),
+            }
+            led.set_high().unwrap();
+        }
+
+        timer0.delay_ms(10); // Simple debounce, and keeps the LED flash visible
+        led.set_low().unwrap();
+    }
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,177 @@
+//! nRF52833 RADIO peripheral as a [`Transceiver`].
+//!
+//! The radio runs in Nordic's proprietary 1 Mbit mode with the micro:bit settings from [`packet`](crate::packet).
+//! It listens whenever it is not transmitting. EasyDMA writes received frames into a RAM buffer; the driver keeps
+//! one frame there until [`receive`](Transceiver::receive) copies it out, so frames arriving before the previous one
+//! has been read are lost: poll often.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use microbit::hal::{
+    clocks::{Clocks, ExternalOscillator},
+    pac::RADIO,
+};
+
+use crate::{
+    packet::{Config, BASE_ADDRESS, HEADER_LEN, MAX_FRAME_LEN},
+    radio::Transceiver,
+};
+
+/// Receive and transmit DMA buffers, must be in RAM
+pub type Buffers = [[u8; MAX_FRAME_LEN]; 2];
+
+const RX: usize = 0;
+const TX: usize = 1;
+
+// SHORTS register bits
+const SHORTS_READY_START: u32 = 1 << 0;
+const SHORTS_END_DISABLE: u32 = 1 << 1;
+
+/// STATE register value when the radio is off
+const STATE_DISABLED: u32 = 0;
+
+/// The RADIO peripheral, see the module documentation
+pub struct NrfRadio {
+    radio: RADIO,
+    buffers: &'static mut Buffers,
+    /// A frame is waiting in the receive buffer, reception is paused until it has been read
+    frame_waiting: bool,
+}
+
+impl NrfRadio {
+    /// Set up the settings every micro:bit shares. The radio needs the crystal oscillator (HFXO) for an accurate
+    /// carrier frequency, which is why `clocks` has to be an `ExternalOscillator` configuration.
+    ///
+    /// The radio starts listening on the first [`configure`](Transceiver::configure), which
+    /// [`Radio::new`](crate::radio::Radio::new) does.
+    pub fn new<L, S>(radio: RADIO, _clocks: &Clocks<ExternalOscillator, L, S>, buffers: &'static mut Buffers) -> Self {
+        // Nordic proprietary 1 Mbit/s
+        radio.mode.write(|w| w.mode().nrf_1mbit());
+
+        // Packet layout: 8 bit LENGTH field, no S0/S1 fields, 4 byte base address + 1 byte prefix, big endian
+        // payload, data whitening enabled. MAXLEN is set in `configure`.
+        radio.pcnf0.write(|w| unsafe { w.bits(8) });
+        radio.base0.write(|w| unsafe { w.bits(BASE_ADDRESS) });
+        radio.txaddress.write(|w| unsafe { w.bits(0) });
+        radio.rxaddresses.write(|w| unsafe { w.bits(1) });
+        radio.datawhiteiv.write(|w| unsafe { w.bits(0x18) });
+
+        // 16 bit CRC-CCITT over address and payload
+        radio.crccnf.write(|w| unsafe { w.bits(2) });
+        radio.crcinit.write(|w| unsafe { w.bits(0xFFFF) });
+        radio.crcpoly.write(|w| unsafe { w.bits(0x11021) });
+
+        Self {
+            radio,
+            buffers,
+            frame_waiting: false,
+        }
+    }
+
+    /// Turn the radio off and wait until it is
+    fn disable(&mut self) {
+        self.radio.shorts.write(|w| unsafe { w.bits(0) });
+        if self.radio.state.read().bits() != STATE_DISABLED {
+            self.radio.events_disabled.write(|w| unsafe { w.bits(0) });
+            self.radio.tasks_disable.write(|w| unsafe { w.bits(1) });
+            while self.radio.events_disabled.read().bits() == 0 {}
+        }
+        self.radio.events_disabled.write(|w| unsafe { w.bits(0) });
+    }
+
+    /// Switch to receive mode. If a frame is still waiting to be read, the radio ramps up but does not start
+    /// receiving, so the frame is not overwritten.
+    fn listen(&mut self) {
+        let shorts = if self.frame_waiting { 0 } else { SHORTS_READY_START };
+        self.radio
+            .packetptr
+            .write(|w| unsafe { w.bits(self.buffers[RX].as_ptr() as u32) });
+        self.radio.shorts.write(|w| unsafe { w.bits(shorts) });
+        self.radio.events_ready.write(|w| unsafe { w.bits(0) });
+        self.radio.events_end.write(|w| unsafe { w.bits(0) });
+        compiler_fence(Ordering::SeqCst);
+        self.radio.tasks_rxen.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// A reception finished: returns true if it holds a valid frame, otherwise starts listening again
+    fn check_reception(&mut self) -> bool {
+        if self.frame_waiting {
+            return true;
+        }
+        if self.radio.events_end.read().bits() == 0 {
+            return false;
+        }
+
+        self.radio.events_end.write(|w| unsafe { w.bits(0) });
+        compiler_fence(Ordering::SeqCst);
+        if self.radio.crcstatus.read().bits() == 0 {
+            // Corrupted frame: still in RXIDLE, so listen for the next one
+            self.radio.tasks_start.write(|w| unsafe { w.bits(1) });
+            return false;
+        }
+        self.frame_waiting = true;
+        true
+    }
+}
+
+impl Transceiver for NrfRadio {
+    fn configure(&mut self, config: &Config) {
+        self.disable();
+
+        self.radio
+            .frequency
+            .write(|w| unsafe { w.bits(u32::from(config.channel)) });
+        self.radio
+            .txpower
+            .write(|w| unsafe { w.bits(u32::from(config.tx_power_dbm() as u8)) });
+        self.radio.prefix0.write(|w| unsafe { w.bits(u32::from(config.group)) });
+
+        // PCNF1: MAXLEN (bits 0-7), 4 byte base address (BALEN, bits 16-18), big endian (bit 24), whitening (bit 25)
+        let max_len = (HEADER_LEN + config.max_payload) as u32;
+        self.radio
+            .pcnf1
+            .write(|w| unsafe { w.bits(max_len | (4 << 16) | (1 << 24) | (1 << 25)) });
+
+        self.listen();
+    }
+
+    fn transmit(&mut self, frame: &[u8]) {
+        // Keep a frame that has already arrived, then stop listening
+        self.check_reception();
+        self.disable();
+
+        let len = frame.len().min(MAX_FRAME_LEN);
+        self.buffers[TX][..len].copy_from_slice(&frame[..len]);
+        self.radio
+            .packetptr
+            .write(|w| unsafe { w.bits(self.buffers[TX].as_ptr() as u32) });
+
+        // TXEN -> READY -> START -> END -> DISABLE, all in hardware
+        self.radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+        compiler_fence(Ordering::SeqCst);
+        self.radio.tasks_txen.write(|w| unsafe { w.bits(1) });
+        while self.radio.events_disabled.read().bits() == 0 {}
+        self.radio.events_disabled.write(|w| unsafe { w.bits(0) });
+
+        self.listen();
+    }
+
+    fn receive(&mut self, frame: &mut [u8; MAX_FRAME_LEN]) -> Option<usize> {
+        if !self.check_reception() {
+            return None;
+        }
+
+        let rx = &self.buffers[RX];
+        let len = (usize::from(rx[0]) + 1).min(MAX_FRAME_LEN);
+        frame[..len].copy_from_slice(&rx[..len]);
+        self.frame_waiting = false;
+
+        // The buffer is free again: start the next reception once the receiver has ramped up
+        while self.radio.events_ready.read().bits() == 0 {}
+        self.radio.shorts.write(|w| unsafe { w.bits(SHORTS_READY_START) });
+        self.radio.tasks_start.write(|w| unsafe { w.bits(1) });
+        Some(len)
+    }
+}
@@ -0,0 +1,150 @@
+//! micro:bit radio packet format and settings.
+//!
+//! The settings and the header follow CODAL's `MicroBitRadio`, the runtime under MakeCode. No frames captured from
+//! micro:bits running MakeCode or MicroPython have been checked against them, so talking to those is not promised:
+//! - Nordic proprietary 1 Mbit mode on 2400 + `channel` MHz
+//! - Base address `0x75626974` ("ubit"), with the group as address prefix
+//! - 16 bit CRC, data whitening
+//!
+//! After the address, every frame is:
+//!
+//! ```text
+//! ┌────────┬─────────┬───────┬──────────┬─────────────────┐
+//! │ LENGTH │ VERSION │ GROUP │ PROTOCOL │ payload ...     │
+//! │ 1 byte │ 1 (=1)  │ 0-255 │ 1 (=1)   │ 0 - max_payload │
+//! └────────┴─────────┴───────┴──────────┴─────────────────┘
+//! LENGTH counts everything after itself
+//! ```
+//!
+//! The receiver drops frames from other groups, unknown versions and unknown protocols.
+
+/// Radio base address shared by all micro:bits: ASCII "ubit"
+pub const BASE_ADDRESS: u32 = 0x7562_6974;
+/// Frame format version
+pub const VERSION: u8 = 1;
+/// Protocol number of plain datagrams, the only protocol this example sends or accepts
+pub const PROTOCOL_DATAGRAM: u8 = 1;
+/// Bytes between LENGTH and the payload: version, group, protocol
+pub const HEADER_LEN: usize = 3;
+/// Largest payload the frame format allows (LENGTH is one byte)
+pub const MAX_PAYLOAD_LIMIT: usize = 251;
+/// Largest frame including the LENGTH byte
+pub const MAX_FRAME_LEN: usize = 1 + HEADER_LEN + MAX_PAYLOAD_LIMIT;
+
+/// Highest channel: 2400 + 83 = 2483 MHz, the top of the 2.4 GHz band
+pub const MAX_CHANNEL: u8 = 83;
+/// Transmit power in dBm for each power level 0-7 (level 6, 0 dBm, is the default)
+pub const TX_POWER_DBM: [i8; 8] = [-30, -20, -16, -12, -8, -4, 0, 4];
+
+/// Radio settings, with the same defaults as MicroPython
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// 0 to [`MAX_CHANNEL`], frequency is 2400 + channel MHz
+    pub channel: u8,
+    /// Only radios in the same group hear each other
+    pub group: u8,
+    /// Power level 0 to 7, see [`TX_POWER_DBM`]
+    pub power: u8,
+    /// Longest payload sent or accepted, up to [`MAX_PAYLOAD_LIMIT`]
+    pub max_payload: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            channel: 7,
+            group: 0,
+            power: 6,
+            max_payload: 32,
+        }
+    }
+}
+
+/// A setting outside its allowed range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    InvalidChannel(u8),
+    InvalidPower(u8),
+    InvalidMaxPayload(usize),
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.channel > MAX_CHANNEL {
+            return Err(ConfigError::InvalidChannel(self.channel));
+        }
+        if usize::from(self.power) >= TX_POWER_DBM.len() {
+            return Err(ConfigError::InvalidPower(self.power));
+        }
+        if self.max_payload == 0 || self.max_payload > MAX_PAYLOAD_LIMIT {
+            return Err(ConfigError::InvalidMaxPayload(self.max_payload));
+        }
+        Ok(())
+    }
+
+    /// Carrier frequency in MHz
+    pub fn frequency_mhz(&self) -> u16 {
+        2400 + u16::from(self.channel)
+    }
+
+    /// Transmit power in dBm
+    pub fn tx_power_dbm(&self) -> i8 {
+        TX_POWER_DBM[usize::from(self.power)]
+    }
+}
+
+/// Why a frame could not be built or was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    /// Payload longer than the configured maximum
+    PayloadTooLong(usize),
+    /// Frame shorter than the header, or shorter than its LENGTH byte claims
+    Truncated,
+    /// Frame for another group
+    WrongGroup(u8),
+    UnsupportedVersion(u8),
+    UnsupportedProtocol(u8),
+}
+
+/// Build a frame for `payload` in `frame`, returning the frame length
+pub fn encode(config: &Config, payload: &[u8], frame: &mut [u8; MAX_FRAME_LEN]) -> Result<usize, PacketError> {
+    if payload.len() > config.max_payload {
+        return Err(PacketError::PayloadTooLong(payload.len()));
+    }
+
+    let len = HEADER_LEN + payload.len();
+    frame[0] = len as u8;
+    frame[1] = VERSION;
+    frame[2] = config.group;
+    frame[3] = PROTOCOL_DATAGRAM;
+    frame[1 + HEADER_LEN..1 + len].copy_from_slice(payload);
+    Ok(1 + len)
+}
+
+/// Check a received frame and return its payload
+pub fn decode<'a>(config: &Config, frame: &'a [u8]) -> Result<&'a [u8], PacketError> {
+    let Some(&len) = frame.first() else {
+        return Err(PacketError::Truncated);
+    };
+    let len = usize::from(len);
+    if len < HEADER_LEN || frame.len() < 1 + len {
+        return Err(PacketError::Truncated);
+    }
+
+    let (version, group, protocol) = (frame[1], frame[2], frame[3]);
+    if version != VERSION {
+        return Err(PacketError::UnsupportedVersion(version));
+    }
+    if group != config.group {
+        return Err(PacketError::WrongGroup(group));
+    }
+    if protocol != PROTOCOL_DATAGRAM {
+        return Err(PacketError::UnsupportedProtocol(protocol));
+    }
+
+    let payload = &frame[1 + HEADER_LEN..1 + len];
+    if payload.len() > config.max_payload {
+        return Err(PacketError::PayloadTooLong(payload.len()));
+    }
+    Ok(payload)
+}
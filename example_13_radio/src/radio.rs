@@ -0,0 +1,216 @@
+//! micro:bit style radio API on top of any [`Transceiver`].
+//!
+//! [`Radio`] builds and checks frames (see [`packet`](crate::packet)), the transceiver only moves raw frames. On
+//! the micro:bit the transceiver is the RADIO peripheral ([`NrfRadio`](crate::nrf_radio::NrfRadio)); in host tests
+//! it is a [`Loopback`], which hands every transmitted frame straight back to the receiver.
+
+use crate::packet::{self, Config, ConfigError, PacketError, MAX_FRAME_LEN};
+
+/// Moves raw frames (LENGTH byte onwards, see [`packet`](crate::packet)) over the air
+pub trait Transceiver {
+    /// Switch to the channel, group (address prefix) and transmit power in `config`
+    fn configure(&mut self, config: &Config);
+
+    /// Send one frame, blocking until it is on its way
+    fn transmit(&mut self, frame: &[u8]);
+
+    /// Copy the oldest frame received with a valid CRC into `frame` and return its length, or `None` if nothing has
+    /// arrived
+    fn receive(&mut self, frame: &mut [u8; MAX_FRAME_LEN]) -> Option<usize>;
+}
+
+/// Radio settings, and sending and receiving bytes or strings
+pub struct Radio<T> {
+    transceiver: T,
+    config: Config,
+    frame: [u8; MAX_FRAME_LEN],
+}
+
+impl<T: Transceiver> Radio<T> {
+    /// Radio with the default settings: channel 7, group 0, power 6, 32 byte payloads
+    pub fn new(mut transceiver: T) -> Self {
+        let config = Config::default();
+        transceiver.configure(&config);
+        Self {
+            transceiver,
+            config,
+            frame: [0; MAX_FRAME_LEN],
+        }
+    }
+
+    /// Current settings
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Apply new settings. Invalid settings are rejected and the old ones stay in effect.
+    pub fn set_config(&mut self, config: Config) -> Result<(), ConfigError> {
+        config.validate()?;
+        self.config = config;
+        self.transceiver.configure(&self.config);
+        Ok(())
+    }
+
+    /// Change the channel (0-83)
+    pub fn set_channel(&mut self, channel: u8) -> Result<(), ConfigError> {
+        self.set_config(Config { channel, ..self.config })
+    }
+
+    /// Change the group (0-255)
+    pub fn set_group(&mut self, group: u8) -> Result<(), ConfigError> {
+        self.set_config(Config { group, ..self.config })
+    }
+
+    /// Change the transmit power level (0-7)
+    pub fn set_power(&mut self, power: u8) -> Result<(), ConfigError> {
+        self.set_config(Config { power, ..self.config })
+    }
+
+    /// Send raw bytes
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), PacketError> {
+        let len = packet::encode(&self.config, payload, &mut self.frame)?;
+        self.transceiver.transmit(&self.frame[..len]);
+        Ok(())
+    }
+
+    /// Send a string: the payload is the text's UTF-8 bytes, in the same frame as any other
+    /// payload
+    pub fn send_str(&mut self, text: &str) -> Result<(), PacketError> {
+        self.send(text.as_bytes())
+    }
+
+    /// Receive raw bytes: copies the payload of the next valid frame into `buffer`
+    /// and returns it. Frames for other groups or with a bad header are dropped. Payloads longer than `buffer` are
+    /// cut short.
+    pub fn receive<'b>(&mut self, buffer: &'b mut [u8]) -> Option<&'b [u8]> {
+        loop {
+            let len = self.transceiver.receive(&mut self.frame)?;
+            if let Ok(payload) = packet::decode(&self.config, &self.frame[..len]) {
+                let len = payload.len().min(buffer.len());
+                buffer[..len].copy_from_slice(&payload[..len]);
+                return Some(&buffer[..len]);
+            }
+        }
+    }
+
+    /// Receive a string: frames whose payload is not valid UTF-8 are dropped. Strings
+    /// longer than `buffer` are cut short at a character boundary.
+    pub fn receive_str<'b>(&mut self, buffer: &'b mut [u8]) -> Option<&'b str> {
+        loop {
+            let len = self.transceiver.receive(&mut self.frame)?;
+            let Ok(payload) = packet::decode(&self.config, &self.frame[..len]) else {
+                continue;
+            };
+            if core::str::from_utf8(payload).is_err() {
+                continue;
+            }
+
+            let len = payload.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&payload[..len]);
+            let valid = match core::str::from_utf8(&buffer[..len]) {
+                Ok(_) => len,
+                Err(error) => error.valid_up_to(),
+            };
+            return core::str::from_utf8(&buffer[..valid]).ok();
+        }
+    }
+
+    /// The transceiver, e.g. to inspect a [`Loopback`] in tests
+    pub fn transceiver(&mut self) -> &mut T {
+        &mut self.transceiver
+    }
+}
+
+/// Number of frames a [`Loopback`] holds before dropping new ones
+pub const LOOPBACK_FRAMES: usize = 4;
+
+/// Stand-in transceiver without a radio: every transmitted frame is received again.
+///
+/// Frames from other senders can be injected with [`inject`](Self::inject). When the queue is full new frames are
+/// lost, as they would be on the air while the receiver is busy.
+pub struct Loopback {
+    frames: [[u8; MAX_FRAME_LEN]; LOOPBACK_FRAMES],
+    lengths: [usize; LOOPBACK_FRAMES],
+    head: usize,
+    count: usize,
+    config: Option<Config>,
+    transmitted: usize,
+    dropped: usize,
+}
+
+impl Default for Loopback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Loopback {
+    pub const fn new() -> Self {
+        Self {
+            frames: [[0; MAX_FRAME_LEN]; LOOPBACK_FRAMES],
+            lengths: [0; LOOPBACK_FRAMES],
+            head: 0,
+            count: 0,
+            config: None,
+            transmitted: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Queue a frame as if another radio had sent it. Frames longer than [`MAX_FRAME_LEN`] are cut short.
+    pub fn inject(&mut self, frame: &[u8]) {
+        if self.count == LOOPBACK_FRAMES {
+            self.dropped += 1;
+            return;
+        }
+
+        let slot = (self.head + self.count) % LOOPBACK_FRAMES;
+        let len = frame.len().min(MAX_FRAME_LEN);
+        self.frames[slot][..len].copy_from_slice(&frame[..len]);
+        self.lengths[slot] = len;
+        self.count += 1;
+    }
+
+    /// Settings last applied with [`Transceiver::configure`]
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    /// Frames waiting to be received
+    pub fn pending(&self) -> usize {
+        self.count
+    }
+
+    /// Frames transmitted so far
+    pub fn transmitted(&self) -> usize {
+        self.transmitted
+    }
+
+    /// Frames lost because the queue was full
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+impl Transceiver for Loopback {
+    fn configure(&mut self, config: &Config) {
+        self.config = Some(*config);
+    }
+
+    fn transmit(&mut self, frame: &[u8]) {
+        self.transmitted += 1;
+        self.inject(frame);
+    }
+
+    fn receive(&mut self, frame: &mut [u8; MAX_FRAME_LEN]) -> Option<usize> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let len = self.lengths[self.head];
+        frame[..len].copy_from_slice(&self.frames[self.head][..len]);
+        self.head = (self.head + 1) % LOOPBACK_FRAMES;
+        self.count -= 1;
+        Some(len)
+    }
+}
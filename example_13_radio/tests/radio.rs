@@ -0,0 +1,244 @@
+//! Host tests for the packet format and the radio API, using the loopback transceiver
+
+use example_13_radio::{
+    packet::{self, Config, ConfigError, PacketError, MAX_FRAME_LEN, MAX_PAYLOAD_LIMIT},
+    radio::{Loopback, Radio, Transceiver, LOOPBACK_FRAMES},
+};
+
+fn frame(group: u8, payload: &[u8]) -> Vec<u8> {
+    let config = Config {
+        group,
+        ..Config::default()
+    };
+    let mut frame = [0; MAX_FRAME_LEN];
+    let len = packet::encode(&config, payload, &mut frame).unwrap();
+    frame[..len].to_vec()
+}
+
+#[test]
+fn frame_layout() {
+    // LENGTH, VERSION, GROUP, PROTOCOL, payload: the header layout of CODAL's MicroBitRadio
+    assert_eq!(frame(5, &[0x2A]), [4, 1, 5, 1, 0x2A]);
+    assert_eq!(frame(0, &[]), [3, 1, 0, 1]);
+}
+
+#[test]
+fn decode_round_trips_and_filters() {
+    let config = Config {
+        group: 5,
+        ..Config::default()
+    };
+    let good = frame(5, b"abc");
+    assert_eq!(packet::decode(&config, &good), Ok(&b"abc"[..]));
+
+    assert_eq!(
+        packet::decode(&config, &frame(6, b"abc")),
+        Err(PacketError::WrongGroup(6))
+    );
+    assert_eq!(
+        packet::decode(&config, &[4, 2, 5, 1, 0]),
+        Err(PacketError::UnsupportedVersion(2))
+    );
+    assert_eq!(
+        packet::decode(&config, &[4, 1, 5, 9, 0]),
+        Err(PacketError::UnsupportedProtocol(9))
+    );
+
+    // Frames shorter than the header, or than LENGTH claims
+    assert_eq!(packet::decode(&config, &[]), Err(PacketError::Truncated));
+    assert_eq!(packet::decode(&config, &[2, 1, 5]), Err(PacketError::Truncated));
+    assert_eq!(
+        packet::decode(&config, &good[..good.len() - 1]),
+        Err(PacketError::Truncated)
+    );
+}
+
+#[test]
+fn payload_length_is_limited() {
+    let config = Config::default();
+    let mut frame = [0; MAX_FRAME_LEN];
+    assert_eq!(packet::encode(&config, &[0; 32], &mut frame), Ok(36));
+    assert_eq!(
+        packet::encode(&config, &[0; 33], &mut frame),
+        Err(PacketError::PayloadTooLong(33))
+    );
+
+    let long = Config {
+        max_payload: MAX_PAYLOAD_LIMIT,
+        ..config
+    };
+    assert_eq!(
+        packet::encode(&long, &[7; MAX_PAYLOAD_LIMIT], &mut frame),
+        Ok(MAX_FRAME_LEN)
+    );
+    assert_eq!(frame[0], 254);
+    // A long frame is dropped by a receiver with the default limit
+    assert_eq!(
+        packet::decode(&config, &frame),
+        Err(PacketError::PayloadTooLong(MAX_PAYLOAD_LIMIT))
+    );
+}
+
+#[test]
+fn config_validation() {
+    let config = Config::default();
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.frequency_mhz(), 2407);
+    assert_eq!(config.tx_power_dbm(), 0);
+
+    let check = |config: Config| config.validate();
+    assert_eq!(check(Config { channel: 83, ..config }), Ok(()));
+    assert_eq!(
+        check(Config { channel: 84, ..config }),
+        Err(ConfigError::InvalidChannel(84))
+    );
+    assert_eq!(check(Config { power: 8, ..config }), Err(ConfigError::InvalidPower(8)));
+    assert_eq!(
+        check(Config {
+            max_payload: 0,
+            ..config
+        }),
+        Err(ConfigError::InvalidMaxPayload(0))
+    );
+    assert_eq!(
+        check(Config {
+            max_payload: 252,
+            ..config
+        }),
+        Err(ConfigError::InvalidMaxPayload(252))
+    );
+}
+
+#[test]
+fn settings_reach_the_transceiver() {
+    let mut radio = Radio::new(Loopback::new());
+    assert_eq!(radio.transceiver().config(), Some(&Config::default()));
+
+    radio.set_channel(42).unwrap();
+    radio.set_group(9).unwrap();
+    radio.set_power(7).unwrap();
+    let applied = *radio.transceiver().config().unwrap();
+    assert_eq!((applied.channel, applied.group, applied.power), (42, 9, 7));
+    assert_eq!(applied.tx_power_dbm(), 4);
+
+    // Rejected settings leave the old ones in place
+    assert_eq!(radio.set_channel(100), Err(ConfigError::InvalidChannel(100)));
+    assert_eq!(radio.config().channel, 42);
+    assert_eq!(radio.transceiver().config().unwrap().channel, 42);
+}
+
+#[test]
+fn bytes_and_strings_loop_back() {
+    let mut radio = Radio::new(Loopback::new());
+    let mut buffer = [0; 64];
+
+    radio.send(&[1, 2, 3]).unwrap();
+    assert_eq!(radio.receive(&mut buffer), Some(&[1, 2, 3][..]));
+    assert_eq!(radio.receive(&mut buffer), None);
+
+    radio.send_str("hello").unwrap();
+    assert_eq!(radio.receive_str(&mut buffer), Some("hello"));
+
+    // A string is its UTF-8 bytes, after the one header every frame has
+    radio.send_str("hi").unwrap();
+    assert_eq!(radio.receive(&mut buffer), Some(&b"hi"[..]));
+    radio.send_str("hi").unwrap();
+    let mut frame = [0; MAX_FRAME_LEN];
+    let len = radio.transceiver().receive(&mut frame).unwrap();
+    assert_eq!(frame[..len], [5, 1, 0, 1, b'h', b'i']);
+    assert_eq!(radio.transceiver().transmitted(), 4);
+}
+
+#[test]
+fn strings_are_limited_by_max_payload() {
+    let mut radio = Radio::new(Loopback::new());
+    // 32 characters fill the default 32 bytes
+    assert_eq!(radio.send_str(&"x".repeat(32)), Ok(()));
+    assert_eq!(radio.send_str(&"x".repeat(33)), Err(PacketError::PayloadTooLong(33)));
+    assert_eq!(radio.transceiver().transmitted(), 1);
+}
+
+#[test]
+fn receive_str_skips_non_strings() {
+    let mut radio = Radio::new(Loopback::new());
+    let mut buffer = [0; 64];
+
+    radio.send(&[0xFF, 0xFE]).unwrap();
+    radio.send(&[b'o', 0xFF]).unwrap(); // Starts like text, but is not UTF-8
+    radio.send_str("ok").unwrap();
+    assert_eq!(radio.receive_str(&mut buffer), Some("ok"));
+    assert_eq!(radio.transceiver().pending(), 0);
+}
+
+#[test]
+fn other_groups_and_bad_frames_are_dropped() {
+    let mut radio = Radio::new(Loopback::new());
+    radio.set_group(3).unwrap();
+    let mut buffer = [0; 64];
+
+    let loopback = radio.transceiver();
+    loopback.inject(&frame(4, b"other group"));
+    loopback.inject(&[4, 7, 3, 1, 0]); // Unknown version
+    loopback.inject(&frame(3, b"mine"));
+    assert_eq!(radio.receive(&mut buffer), Some(&b"mine"[..]));
+    assert_eq!(radio.receive(&mut buffer), None);
+}
+
+#[test]
+fn two_radios_talk_through_shared_air() {
+    // Carry frames from one loopback to the other, as the air would
+    let mut alice = Radio::new(Loopback::new());
+    let mut bob = Radio::new(Loopback::new());
+    let mut frame = [0; MAX_FRAME_LEN];
+    let mut buffer = [0; 64];
+
+    alice.send_str("ping").unwrap();
+    while let Some(len) = alice.transceiver().receive(&mut frame) {
+        bob.transceiver().inject(&frame[..len]);
+    }
+    assert_eq!(bob.receive_str(&mut buffer), Some("ping"));
+
+    // After changing group, bob no longer hears alice
+    bob.set_group(1).unwrap();
+    alice.send_str("ping").unwrap();
+    while let Some(len) = alice.transceiver().receive(&mut frame) {
+        bob.transceiver().inject(&frame[..len]);
+    }
+    assert_eq!(bob.receive_str(&mut buffer), None);
+}
+
+#[test]
+fn loopback_drops_frames_when_full() {
+    let mut radio = Radio::new(Loopback::new());
+    let mut buffer = [0; 64];
+
+    for i in 0..LOOPBACK_FRAMES as u8 + 2 {
+        radio.send(&[i]).unwrap();
+    }
+    assert_eq!(radio.transceiver().dropped(), 2);
+
+    // The oldest frames are kept, in order
+    for i in 0..LOOPBACK_FRAMES as u8 {
+        assert_eq!(radio.receive(&mut buffer), Some(&[i][..]));
+    }
+    assert_eq!(radio.receive(&mut buffer), None);
+}
+
+#[test]
+fn receive_cuts_payload_to_buffer() {
+    let mut radio = Radio::new(Loopback::new());
+    let mut small = [0; 2];
+    radio.send(&[1, 2, 3, 4]).unwrap();
+    assert_eq!(radio.receive(&mut small), Some(&[1, 2][..]));
+}
+
+#[test]
+fn receive_str_cuts_at_character_boundary() {
+    let mut radio = Radio::new(Loopback::new());
+    let mut small = [0; 4];
+    radio.send_str("añb").unwrap(); // 'ñ' is two bytes
+    assert_eq!(radio.receive_str(&mut small), Some("añb"));
+
+    radio.send_str("aññ").unwrap();
+    assert_eq!(radio.receive_str(&mut small), Some("añ"));
+}
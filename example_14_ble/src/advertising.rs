@@ -0,0 +1,278 @@
+//! BLE advertising channel PDUs.
+//!
+//! Advertising happens on three fixed channels with a fixed access address. Every PDU has a two byte header
+//! followed by the advertiser's address and up to 31 bytes of advertising data:
+//!
+//! ```text
+//! ┌──────────────────────────────┬────────┬───────────┬─────────────────────────┐
+//! │ type | ChSel | TxAdd | RxAdd │ length │ AdvA      │ AdvData                 │
+//! │ 4 bit  1 bit   1 bit   1 bit │ 1 byte │ 6 bytes   │ 0-31 bytes of AD fields │
+//! └──────────────────────────────┴────────┴───────────┴─────────────────────────┘
+//! ```
+//!
+//! The advertising data is a list of AD structures: `[length, type, data...]`.
+
+use crate::link::ChannelMap;
+
+/// Access address of all advertising channel packets
+pub const ADVERTISING_ACCESS_ADDRESS: u32 = 0x8E89_BED6;
+/// CRC initial value of all advertising channel packets
+pub const ADVERTISING_CRC_INIT: u32 = 0x55_5555;
+/// Advertising channel indices, in the order they are used
+pub const ADVERTISING_CHANNELS: [u8; 3] = [37, 38, 39];
+/// Longest advertising data
+pub const MAX_AD_LEN: usize = 31;
+/// Longest advertising PDU: header + address + data
+pub const MAX_ADV_PDU_LEN: usize = 2 + 6 + MAX_AD_LEN;
+
+// AD types (Bluetooth Assigned Numbers, section 2.3)
+pub const AD_FLAGS: u8 = 0x01;
+pub const AD_COMPLETE_LIST_128: u8 = 0x07;
+pub const AD_COMPLETE_LOCAL_NAME: u8 = 0x09;
+pub const AD_MANUFACTURER_DATA: u8 = 0xFF;
+
+/// Flags: LE General Discoverable Mode, BR/EDR Not Supported
+pub const FLAGS_LE_ONLY_GENERAL: u8 = 0x06;
+
+/// RF centre frequency of a channel index in MHz. Advertising channels sit between the data channels, in gaps of
+/// the 2.4 GHz band that Wi-Fi leaves free.
+pub fn channel_frequency_mhz(channel: u8) -> u16 {
+    let channel = u16::from(channel);
+    match channel {
+        37 => 2402,
+        38 => 2426,
+        39 => 2480,
+        0..=10 => 2404 + 2 * channel,
+        _ => 2428 + 2 * (channel - 11),
+    }
+}
+
+/// A device address: 6 bytes, least significant first, as sent on the air
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceAddress {
+    pub bytes: [u8; 6],
+    /// Random (true) or public (false) address
+    pub random: bool,
+}
+
+impl DeviceAddress {
+    /// Random static address from 48 device-unique bits (the nRF52 FICR DEVICEADDR registers). The two most
+    /// significant bits of a static address are always 1.
+    pub fn random_static(low: u32, high: u32) -> Self {
+        let low = low.to_le_bytes();
+        let high = high.to_le_bytes();
+        Self {
+            bytes: [low[0], low[1], low[2], low[3], high[0], high[1] | 0xC0],
+            random: true,
+        }
+    }
+}
+
+/// Advertising PDU types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PduType {
+    /// Connectable and scannable undirected advertising
+    AdvInd = 0,
+    AdvDirectInd = 1,
+    /// Non-connectable advertising: a pure beacon
+    AdvNonconnInd = 2,
+    ScanReq = 3,
+    ScanRsp = 4,
+    /// A central asking to connect
+    ConnectInd = 5,
+    AdvScanInd = 6,
+}
+
+impl PduType {
+    fn from_bits(bits: u8) -> Option<Self> {
+        Some(match bits {
+            0 => Self::AdvInd,
+            1 => Self::AdvDirectInd,
+            2 => Self::AdvNonconnInd,
+            3 => Self::ScanReq,
+            4 => Self::ScanRsp,
+            5 => Self::ConnectInd,
+            6 => Self::AdvScanInd,
+            _ => return None,
+        })
+    }
+}
+
+/// Advertising data did not fit in 31 bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLong;
+
+/// Builder for advertising data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdvertisingData {
+    bytes: [u8; MAX_AD_LEN],
+    len: usize,
+}
+
+impl Default for AdvertisingData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdvertisingData {
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; MAX_AD_LEN],
+            len: 0,
+        }
+    }
+
+    /// Append one AD structure
+    pub fn push(mut self, ad_type: u8, data: &[u8]) -> Result<Self, TooLong> {
+        let end = self.len + 2 + data.len();
+        if end > MAX_AD_LEN {
+            return Err(TooLong);
+        }
+        self.bytes[self.len] = (1 + data.len()) as u8;
+        self.bytes[self.len + 1] = ad_type;
+        self.bytes[self.len + 2..end].copy_from_slice(data);
+        self.len = end;
+        Ok(self)
+    }
+
+    pub fn flags(self, flags: u8) -> Result<Self, TooLong> {
+        self.push(AD_FLAGS, &[flags])
+    }
+
+    pub fn complete_local_name(self, name: &str) -> Result<Self, TooLong> {
+        self.push(AD_COMPLETE_LOCAL_NAME, name.as_bytes())
+    }
+
+    /// One 128 bit service UUID, so scanners can filter for the service
+    pub fn service_uuid_128(self, uuid: u128) -> Result<Self, TooLong> {
+        self.push(AD_COMPLETE_LIST_128, &uuid.to_le_bytes())
+    }
+
+    /// Manufacturer specific data, starting with the company identifier
+    pub fn manufacturer_data(self, company: u16, data: &[u8]) -> Result<Self, TooLong> {
+        let mut bytes = [0; MAX_AD_LEN];
+        let len = 2 + data.len();
+        if len > MAX_AD_LEN - 2 {
+            return Err(TooLong);
+        }
+        bytes[..2].copy_from_slice(&company.to_le_bytes());
+        bytes[2..len].copy_from_slice(data);
+        self.push(AD_MANUFACTURER_DATA, &bytes[..len])
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Build an advertising PDU from `address` (AdvA) and `data`, returning its length
+pub fn encode_pdu(pdu_type: PduType, address: &DeviceAddress, data: &[u8], pdu: &mut [u8; MAX_ADV_PDU_LEN]) -> usize {
+    let data = &data[..data.len().min(MAX_AD_LEN)];
+    pdu[0] = pdu_type as u8 | (u8::from(address.random) << 6);
+    pdu[1] = (6 + data.len()) as u8;
+    pdu[2..8].copy_from_slice(&address.bytes);
+    pdu[8..8 + data.len()].copy_from_slice(data);
+    8 + data.len()
+}
+
+/// Why a received advertising PDU was ignored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PduError {
+    /// Shorter than its header says, or than its type requires
+    Truncated,
+    /// A PDU type that is not handled here
+    Unexpected,
+    /// A request addressed to another advertiser
+    NotForUs,
+    /// Connection parameters outside the ranges the specification allows
+    InvalidParameters,
+}
+
+/// CONNECT_IND: a central asks to connect and sets the connection parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectInd {
+    /// Address of the central
+    pub initiator: DeviceAddress,
+    pub access_address: u32,
+    pub crc_init: u32,
+    /// Transmit window size in 1.25 ms units
+    pub win_size: u8,
+    /// Transmit window offset in 1.25 ms units
+    pub win_offset: u16,
+    /// Connection interval in 1.25 ms units
+    pub interval: u16,
+    /// Connection events the peripheral may skip
+    pub latency: u16,
+    /// Supervision timeout in 10 ms units
+    pub timeout: u16,
+    /// One bit per data channel 0-36
+    pub channel_map: [u8; 5],
+    /// Channel selection hop increment, 5-16
+    pub hop: u8,
+    /// Sleep clock accuracy of the central, 0 (worst) to 7 (best)
+    pub sca: u8,
+}
+
+/// Parse a CONNECT_IND addressed to `us`
+pub fn decode_connect_ind(pdu: &[u8], us: &DeviceAddress) -> Result<ConnectInd, PduError> {
+    if pdu.len() < 2 || pdu.len() < 2 + usize::from(pdu[1]) {
+        return Err(PduError::Truncated);
+    }
+    if PduType::from_bits(pdu[0] & 0x0F) != Some(PduType::ConnectInd) {
+        return Err(PduError::Unexpected);
+    }
+    if pdu[1] != 34 {
+        return Err(PduError::Truncated);
+    }
+
+    let tx_random = pdu[0] & (1 << 6) != 0;
+    let rx_random = pdu[0] & (1 << 7) != 0;
+    if pdu[8..14] != us.bytes || rx_random != us.random {
+        return Err(PduError::NotForUs);
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([pdu[i], pdu[i + 1]]);
+    let mut initiator = [0; 6];
+    initiator.copy_from_slice(&pdu[2..8]);
+    let mut channel_map = [0; 5];
+    channel_map.copy_from_slice(&pdu[30..35]);
+
+    let request = ConnectInd {
+        initiator: DeviceAddress {
+            bytes: initiator,
+            random: tx_random,
+        },
+        access_address: u32::from_le_bytes([pdu[14], pdu[15], pdu[16], pdu[17]]),
+        crc_init: u32::from_le_bytes([pdu[18], pdu[19], pdu[20], 0]),
+        win_size: pdu[21],
+        win_offset: u16_at(22),
+        interval: u16_at(24),
+        latency: u16_at(26),
+        timeout: u16_at(28),
+        channel_map,
+        hop: pdu[35] & 0x1F,
+        sca: pdu[35] >> 5,
+    };
+
+    // Ranges from the Core specification, Vol 6 Part B, 2.3.3.1
+    let valid = (1..=8).contains(&request.win_size)
+        && request.win_offset <= request.interval
+        && (6..=3200).contains(&request.interval)
+        && (10..=3200).contains(&request.timeout)
+        && (5..=16).contains(&request.hop)
+        && ChannelMap::valid(&request.channel_map);
+    if !valid {
+        return Err(PduError::InvalidParameters);
+    }
+    Ok(request)
+}
+
+/// Pseudo-random advDelay of 0-10 ms added to every advertising interval, so that two advertisers with the same
+/// interval do not keep colliding. `seed` is an xorshift state and must not be zero.
+pub fn advertising_delay_us(seed: &mut u32) -> u32 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 17;
+    *seed ^= *seed << 5;
+    *seed % 10_001
+}
@@ -0,0 +1,488 @@
+//! The BLE link on the nRF52833 RADIO: advertising until a phone connects, then one connection at a time.
+//!
+//! Everything with timing constraints happens in hardware or in the RADIO interrupt:
+//!
+//! - TIMER0 runs freely at 1 MHz. Its COMPARE[0] starts the radio through the pre-programmed PPI channels 20
+//!   (TXEN) and 21 (RXEN), COMPARE[1] ends a receive window through channel 22 (DISABLE)
+//! - A programmable PPI channel captures the time of every ADDRESS event in CC[2]: the start of each packet from
+//!   the central, which is the anchor point the next connection event is timed from
+//! - Shortcuts turn the radio around within the 150 µs T_IFS: `DISABLED_RXEN` after an advertisement,
+//!   `DISABLED_TXEN` after a packet from the central
+//!
+//! The interrupt only reacts to ADDRESS (a packet is arriving) and DISABLED (the radio finished a step). Anything
+//! the protocol decides (channel, anchor offsets, answers) comes from [`Connection`] and [`AttServer`], which are
+//! tested on the PC.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use microbit::hal::{
+    clocks::{Clocks, ExternalOscillator},
+    pac::{FICR, PPI, RADIO, TIMER0},
+};
+
+use crate::{
+    advertising::{
+        advertising_delay_us, channel_frequency_mhz, decode_connect_ind, encode_pdu, DeviceAddress, PduType,
+        ADVERTISING_ACCESS_ADDRESS, ADVERTISING_CHANNELS, ADVERTISING_CRC_INIT, MAX_ADV_PDU_LEN,
+    },
+    gatt::{AttServer, ATT_MTU},
+    link::{Closed, Connection, Received, Schedule},
+    profile::{SensorValues, TABLE},
+};
+
+/// Receive and transmit DMA buffers, must be in RAM
+pub type Buffers = [[u8; MAX_ADV_PDU_LEN]; 2];
+
+const RX: usize = 0;
+const TX: usize = 1;
+
+/// Time between advertising events, before the random advDelay is added
+pub const ADVERTISING_INTERVAL_US: u32 = 100_000;
+
+// Radio timing in µs, with fast ramp-up
+const RAMP_UP_US: u32 = 40;
+/// ADDRESS comes after the preamble and access address, this long after the packet started
+const ADDRESS_US: u32 = 40;
+const T_IFS_US: u32 = 150;
+/// Extra listening time for interrupt latency and rounding, on top of the window widening
+const MARGIN_US: u32 = 32;
+/// Header, payload and CRC of a CONNECT_IND, sent after its address
+const CONNECT_IND_AFTER_ADDRESS_US: u32 = (2 + 34 + 3) * 8;
+/// A connection event has to be scheduled at least this far ahead, or it counts as missed
+const MIN_LEAD_US: u32 = 100;
+
+// SHORTS register bits
+const SHORTS_READY_START: u32 = 1 << 0;
+const SHORTS_END_DISABLE: u32 = 1 << 1;
+const SHORTS_DISABLED_TXEN: u32 = 1 << 2;
+const SHORTS_DISABLED_RXEN: u32 = 1 << 3;
+
+// INTENSET register bits
+const INTERRUPT_ADDRESS: u32 = 1 << 1;
+const INTERRUPT_DISABLED: u32 = 1 << 4;
+
+// Pre-programmed PPI channels
+const PPI_COMPARE0_TXEN: u32 = 1 << 20;
+const PPI_COMPARE0_RXEN: u32 = 1 << 21;
+const PPI_COMPARE1_DISABLE: u32 = 1 << 22;
+/// Programmable PPI channel used for RADIO ADDRESS -> TIMER0 CAPTURE[2]
+const PPI_ADDRESS_CAPTURE: usize = 0;
+
+// TIMER0 CC registers
+const CC_START: usize = 0;
+const CC_TIMEOUT: usize = 1;
+const CC_ADDRESS: usize = 2;
+const CC_NOW: usize = 3;
+
+/// Maximum payload length the radio accepts (PCNF1 MAXLEN): advertising PDUs are the longest
+const MAX_LEN: u32 = MAX_ADV_PDU_LEN as u32 - 2;
+
+/// What the radio is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Sending ADV_IND on `ADVERTISING_CHANNELS[index]`
+    AdvertisingTx(usize),
+    /// Listening for a CONNECT_IND right after it
+    AdvertisingRx(usize),
+    /// Waiting for the central's packet in a connection event
+    ConnectionRx,
+    /// Answering it
+    ConnectionTx,
+}
+
+/// Changes of the link, for the application to report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkEvent {
+    Connected(DeviceAddress),
+    Disconnected(Closed),
+}
+
+/// The BLE peripheral, see the module documentation. Shared with the RADIO interrupt, which calls
+/// [`on_radio_interrupt`](Self::on_radio_interrupt).
+pub struct Ble {
+    radio: RADIO,
+    timer: TIMER0,
+    ppi: PPI,
+    buffers: &'static mut Buffers,
+    address: DeviceAddress,
+    state: State,
+    /// ADV_IND to send, rebuilt by `set_advertising_data`
+    advertisement: [u8; MAX_ADV_PDU_LEN],
+    advertisement_len: usize,
+    /// Start of the current advertising event
+    advertising_start: u32,
+    /// xorshift state for advDelay
+    seed: u32,
+    connection: Option<Connection>,
+    /// Time the connection's schedule offsets count from: the end of the CONNECT_IND, then the last anchor point
+    reference: u32,
+    server: AttServer,
+    values: SensorValues,
+    /// A new packet from the central waits for `Connection::process`
+    pending: bool,
+    event: Option<LinkEvent>,
+}
+
+impl Ble {
+    /// Set up the radio for BLE. The radio needs the crystal oscillator (HFXO), hence the `ExternalOscillator`
+    /// clocks. The device address is the random static address stored in FICR by Nordic. Nothing is sent until
+    /// [`start`](Self::start).
+    pub fn new<L, S>(
+        radio: RADIO,
+        timer: TIMER0,
+        ppi: PPI,
+        ficr: &FICR,
+        _clocks: &Clocks<ExternalOscillator, L, S>,
+        buffers: &'static mut Buffers,
+    ) -> Self {
+        let address = DeviceAddress::random_static(ficr.deviceaddr[0].read().bits(), ficr.deviceaddr[1].read().bits());
+
+        // BLE 1 Mbit/s with fast (40 µs) ramp-up
+        radio.mode.write(|w| w.mode().ble_1mbit());
+        radio.modecnf0.write(|w| unsafe { w.bits(1) });
+
+        // Packet layout: 1 byte S0 (the PDU header), 8 bit LENGTH, 3 byte base address + 1 byte prefix (the
+        // access address), little endian, data whitening enabled
+        radio.pcnf0.write(|w| unsafe { w.bits(8 | (1 << 8)) });
+        radio
+            .pcnf1
+            .write(|w| unsafe { w.bits(MAX_LEN | (3 << 16) | (1 << 25)) });
+        radio.txaddress.write(|w| unsafe { w.bits(0) });
+        radio.rxaddresses.write(|w| unsafe { w.bits(1) });
+
+        // 24 bit CRC over the PDU only, x^24 + x^10 + x^9 + x^6 + x^4 + x^3 + x + 1
+        radio.crccnf.write(|w| unsafe { w.bits(3 | (1 << 8)) });
+        radio.crcpoly.write(|w| unsafe { w.bits(0x65B) });
+
+        radio.tifs.write(|w| unsafe { w.bits(T_IFS_US) });
+        radio.txpower.write(|w| unsafe { w.bits(0) }); // 0 dBm
+        radio
+            .intenset
+            .write(|w| unsafe { w.bits(INTERRUPT_ADDRESS | INTERRUPT_DISABLED) });
+
+        // Free-running 32 bit timer at 16 MHz / 2^4 = 1 MHz
+        timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        timer.mode.write(|w| w.mode().timer());
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.prescaler.write(|w| unsafe { w.bits(4) });
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+
+        // Timestamp every packet in CC[2]
+        let channel = &ppi.ch[PPI_ADDRESS_CAPTURE];
+        channel
+            .eep
+            .write(|w| unsafe { w.bits(&radio.events_address as *const _ as u32) });
+        channel
+            .tep
+            .write(|w| unsafe { w.bits(&timer.tasks_capture[CC_ADDRESS] as *const _ as u32) });
+        ppi.chenset.write(|w| unsafe { w.bits(1 << PPI_ADDRESS_CAPTURE) });
+
+        Self {
+            radio,
+            timer,
+            ppi,
+            buffers,
+            address,
+            state: State::AdvertisingTx(0),
+            advertisement: [0; MAX_ADV_PDU_LEN],
+            advertisement_len: 0,
+            advertising_start: 0,
+            seed: u32::from_le_bytes([address.bytes[0], address.bytes[1], address.bytes[2], 1]),
+            connection: None,
+            reference: 0,
+            server: AttServer::new(TABLE),
+            values: SensorValues::new(),
+            pending: false,
+            event: None,
+        }
+    }
+
+    pub fn address(&self) -> DeviceAddress {
+        self.address
+    }
+
+    /// Advertising data, sent from the next advertising event on
+    pub fn set_advertising_data(&mut self, data: &[u8]) {
+        self.advertisement_len = encode_pdu(PduType::AdvInd, &self.address, data, &mut self.advertisement);
+    }
+
+    /// Start advertising
+    pub fn start(&mut self) {
+        self.advertise_at(self.now() + 1000);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Values served to the connected phone
+    pub fn values(&mut self) -> &mut SensorValues {
+        &mut self.values
+    }
+
+    /// Send the characteristic `id` to the phone, if it is connected and has notifications on. Returns false if
+    /// nothing was queued.
+    pub fn notify(&mut self, id: u8) -> bool {
+        let Some(connection) = self.connection.as_mut() else {
+            return false;
+        };
+        if !connection.can_send() {
+            return false;
+        }
+        let mut pdu = [0; ATT_MTU];
+        match self.server.notification(id, &mut self.values, &mut pdu) {
+            Some(len) => connection.send_att(&pdu[..len]),
+            None => false,
+        }
+    }
+
+    /// The last connect or disconnect, if it has not been taken yet
+    pub fn take_event(&mut self) -> Option<LinkEvent> {
+        self.event.take()
+    }
+
+    /// Call from the RADIO interrupt
+    pub fn on_radio_interrupt(&mut self) {
+        if self.radio.events_address.read().bits() != 0 {
+            self.radio.events_address.write(|w| unsafe { w.bits(0) });
+            self.on_address();
+        }
+        if self.radio.events_disabled.read().bits() != 0 {
+            self.radio.events_disabled.write(|w| unsafe { w.bits(0) });
+            self.ppi
+                .chenclr
+                .write(|w| unsafe { w.bits(PPI_COMPARE0_TXEN | PPI_COMPARE0_RXEN | PPI_COMPARE1_DISABLE) });
+            self.on_disabled();
+        }
+    }
+
+    /// A packet started
+    fn on_address(&mut self) {
+        match self.state {
+            State::AdvertisingTx(_) => {
+                // START has latched the ADV_IND, so the buffer can be switched for the reply. Listen until just
+                // after a CONNECT_IND would have started, T_IFS after the end of the ADV_IND.
+                self.set_packet_pointer(RX);
+                let sent_us = (u32::from(self.buffers[TX][1]) + 2 + 3) * 8;
+                let timeout = self.captured_address() + sent_us + T_IFS_US + ADDRESS_US + MARGIN_US;
+                self.timer.cc[CC_TIMEOUT].write(|w| unsafe { w.bits(timeout) });
+            }
+            State::AdvertisingRx(_) => {
+                // Something is arriving: let it finish instead of timing out
+                self.ppi.chenclr.write(|w| unsafe { w.bits(PPI_COMPARE1_DISABLE) });
+            }
+            State::ConnectionRx => {
+                // The central's packet: answer it T_IFS after it ends
+                self.ppi.chenclr.write(|w| unsafe { w.bits(PPI_COMPARE1_DISABLE) });
+                self.radio
+                    .shorts
+                    .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE | SHORTS_DISABLED_TXEN) });
+            }
+            State::ConnectionTx => {}
+        }
+    }
+
+    /// The radio finished sending or receiving (or a receive window timed out)
+    fn on_disabled(&mut self) {
+        match self.state {
+            State::AdvertisingTx(index) => {
+                // The `DISABLED_RXEN` shortcut already turned the radio around. Arm the timeout and keep the
+                // radio off after the reception.
+                self.radio
+                    .shorts
+                    .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+                self.radio.events_end.write(|w| unsafe { w.bits(0) });
+                self.ppi.chenset.write(|w| unsafe { w.bits(PPI_COMPARE1_DISABLE) });
+                self.state = State::AdvertisingRx(index);
+            }
+            State::AdvertisingRx(index) => {
+                if self.take_end() && self.radio.crcstatus.read().bits() == 1 {
+                    compiler_fence(Ordering::SeqCst);
+                    if let Ok(request) = decode_connect_ind(&self.buffers[RX], &self.address) {
+                        self.connect(&request.initiator, Connection::new(&request));
+                        return;
+                    }
+                }
+                // Nothing for us: next channel, or the next advertising event
+                if index + 1 < ADVERTISING_CHANNELS.len() {
+                    self.advertise(index + 1, None);
+                } else {
+                    let delay = ADVERTISING_INTERVAL_US + advertising_delay_us(&mut self.seed);
+                    self.advertise_at(self.advertising_start.wrapping_add(delay));
+                }
+            }
+            State::ConnectionRx => {
+                if !self.take_end() {
+                    // Nothing arrived in the window
+                    self.next_event(false);
+                    return;
+                }
+
+                // The `DISABLED_TXEN` shortcut is ramping up the transmitter: the answer has to be in the buffer
+                // within T_IFS
+                compiler_fence(Ordering::SeqCst);
+                let crc_ok = self.radio.crcstatus.read().bits() == 1;
+                let Some(connection) = self.connection.as_mut() else {
+                    return;
+                };
+                let (received, response) = connection.respond(&self.buffers[RX], crc_ok);
+                self.buffers[TX][..response.len()].copy_from_slice(response);
+                self.set_packet_pointer(TX);
+                self.radio
+                    .shorts
+                    .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+                self.reference = self.captured_address().wrapping_sub(ADDRESS_US);
+                self.state = State::ConnectionTx;
+                self.pending = received == Received::New;
+            }
+            State::ConnectionTx => {
+                // Answer sent: now there is time to handle what was received
+                if core::mem::take(&mut self.pending) {
+                    if let Some(connection) = self.connection.as_mut() {
+                        if let Err(closed) = connection.process(&mut self.server, &mut self.values) {
+                            self.disconnect(closed);
+                            return;
+                        }
+                    }
+                }
+                self.next_event(true);
+            }
+        }
+    }
+
+    fn now(&self) -> u32 {
+        self.timer.tasks_capture[CC_NOW].write(|w| unsafe { w.bits(1) });
+        self.timer.cc[CC_NOW].read().bits()
+    }
+
+    fn captured_address(&self) -> u32 {
+        self.timer.cc[CC_ADDRESS].read().bits()
+    }
+
+    /// Whether a packet was received completely since the last call
+    fn take_end(&mut self) -> bool {
+        let end = self.radio.events_end.read().bits() != 0;
+        self.radio.events_end.write(|w| unsafe { w.bits(0) });
+        end
+    }
+
+    fn set_packet_pointer(&mut self, buffer: usize) {
+        self.radio
+            .packetptr
+            .write(|w| unsafe { w.bits(self.buffers[buffer].as_ptr() as u32) });
+    }
+
+    /// Select a channel with its access address and CRC initial value
+    fn tune(&mut self, channel: u8, access_address: u32, crc_init: u32) {
+        let frequency = channel_frequency_mhz(channel) - 2400;
+        self.radio.frequency.write(|w| unsafe { w.bits(u32::from(frequency)) });
+        self.radio
+            .datawhiteiv
+            .write(|w| unsafe { w.bits(u32::from(channel) | 0x40) });
+        self.radio.base0.write(|w| unsafe { w.bits(access_address << 8) });
+        self.radio.prefix0.write(|w| unsafe { w.bits(access_address >> 24) });
+        self.radio.crcinit.write(|w| unsafe { w.bits(crc_init) });
+    }
+
+    /// Start an advertising event at `start`
+    fn advertise_at(&mut self, start: u32) {
+        self.advertising_start = start;
+        self.advertise(0, Some(start));
+    }
+
+    /// Send the ADV_IND on `ADVERTISING_CHANNELS[index]`, at `start` or right away
+    fn advertise(&mut self, index: usize, start: Option<u32>) {
+        self.tune(
+            ADVERTISING_CHANNELS[index],
+            ADVERTISING_ACCESS_ADDRESS,
+            ADVERTISING_CRC_INIT,
+        );
+        let len = self.advertisement_len;
+        self.buffers[TX][..len].copy_from_slice(&self.advertisement[..len]);
+        self.set_packet_pointer(TX);
+
+        // Straight into receive mode after sending, for a CONNECT_IND
+        self.radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE | SHORTS_DISABLED_RXEN) });
+        self.radio.events_end.write(|w| unsafe { w.bits(0) });
+        self.state = State::AdvertisingTx(index);
+        compiler_fence(Ordering::SeqCst);
+
+        match start {
+            Some(time) => {
+                self.timer.cc[CC_START].write(|w| unsafe { w.bits(time) });
+                self.ppi.chenset.write(|w| unsafe { w.bits(PPI_COMPARE0_TXEN) });
+            }
+            None => self.radio.tasks_txen.write(|w| unsafe { w.bits(1) }),
+        }
+    }
+
+    fn connect(&mut self, central: &DeviceAddress, mut connection: Connection) {
+        // The transmit window is timed from the end of the CONNECT_IND
+        self.reference = self.captured_address().wrapping_add(CONNECT_IND_AFTER_ADDRESS_US);
+        let first = connection.first_event();
+        self.connection = Some(connection);
+        self.server.reset();
+        self.event = Some(LinkEvent::Connected(*central));
+        if !self.listen(first) {
+            self.next_event(false);
+        }
+    }
+
+    fn disconnect(&mut self, closed: Closed) {
+        self.connection = None;
+        self.event = Some(LinkEvent::Disconnected(closed));
+        self.start();
+    }
+
+    /// Schedule the next connection event. `anchor`: a packet arrived in the current one.
+    fn next_event(&mut self, mut anchor: bool) {
+        loop {
+            let Some(connection) = self.connection.as_mut() else {
+                return;
+            };
+            match connection.next_event(anchor) {
+                Ok(schedule) if self.listen(schedule) => return,
+                // Too late for this event: skip it
+                Ok(_) => anchor = false,
+                Err(closed) => {
+                    self.disconnect(closed);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Set up the receiver for a connection event. Returns false if it is too close to schedule.
+    fn listen(&mut self, schedule: Schedule) -> bool {
+        let expected = self.reference.wrapping_add(schedule.offset_us);
+        let start = expected.wrapping_sub(schedule.widening_us + RAMP_UP_US + MARGIN_US);
+        let end = expected.wrapping_add(schedule.window_us + schedule.widening_us + ADDRESS_US + MARGIN_US);
+        if (start.wrapping_sub(self.now()) as i32) < MIN_LEAD_US as i32 {
+            return false;
+        }
+
+        let Some(connection) = self.connection.as_ref() else {
+            return false;
+        };
+        let (access_address, crc_init) = (connection.access_address, connection.crc_init);
+        self.tune(schedule.channel, access_address, crc_init);
+        self.set_packet_pointer(RX);
+        // `DISABLED_TXEN` is only added once a packet arrives, a timeout must not start the transmitter
+        self.radio
+            .shorts
+            .write(|w| unsafe { w.bits(SHORTS_READY_START | SHORTS_END_DISABLE) });
+        self.radio.events_end.write(|w| unsafe { w.bits(0) });
+        self.state = State::ConnectionRx;
+
+        self.timer.cc[CC_START].write(|w| unsafe { w.bits(start) });
+        self.timer.cc[CC_TIMEOUT].write(|w| unsafe { w.bits(end) });
+        compiler_fence(Ordering::SeqCst);
+        self.ppi
+            .chenset
+            .write(|w| unsafe { w.bits(PPI_COMPARE0_RXEN | PPI_COMPARE1_DISABLE) });
+        true
+    }
+}
@@ -0,0 +1,539 @@
+//! GATT attribute table and ATT server.
+//!
+//! A GATT server is a table of attributes, each with a 16 bit handle, a type (UUID) and a value. Services and
+//! characteristics are themselves attributes:
+//!
+//! ```text
+//! handle  type                     value
+//! 0x0001  Primary Service  0x2800  service UUID
+//! 0x0002  Characteristic   0x2803  properties, value handle (0x0003), characteristic UUID
+//! 0x0003  <characteristic UUID>    the value, e.g. 6 bytes of acceleration
+//! 0x0004  CCCD             0x2902  notifications on/off (only for characteristics that notify)
+//! ```
+//!
+//! [`AttributeTable`] numbers the attributes of a `&'static [Service]` on the fly, so the table is plain constant
+//! data. [`AttServer`] answers the ATT requests a phone sends to discover and use it; characteristic values are
+//! read and written through the [`Values`] trait.
+
+/// ATT_MTU: this server does not negotiate anything larger than the minimum
+pub const ATT_MTU: usize = 23;
+
+// Attribute types
+pub const PRIMARY_SERVICE: u16 = 0x2800;
+pub const CHARACTERISTIC: u16 = 0x2803;
+pub const CCCD: u16 = 0x2902;
+
+// Characteristic properties
+pub const READ: u8 = 0x02;
+pub const WRITE_WITHOUT_RESPONSE: u8 = 0x04;
+pub const WRITE: u8 = 0x08;
+pub const NOTIFY: u8 = 0x10;
+
+// ATT opcodes
+pub const ERROR_RSP: u8 = 0x01;
+pub const EXCHANGE_MTU_REQ: u8 = 0x02;
+pub const EXCHANGE_MTU_RSP: u8 = 0x03;
+pub const FIND_INFORMATION_REQ: u8 = 0x04;
+pub const FIND_INFORMATION_RSP: u8 = 0x05;
+pub const FIND_BY_TYPE_VALUE_REQ: u8 = 0x06;
+pub const FIND_BY_TYPE_VALUE_RSP: u8 = 0x07;
+pub const READ_BY_TYPE_REQ: u8 = 0x08;
+pub const READ_BY_TYPE_RSP: u8 = 0x09;
+pub const READ_REQ: u8 = 0x0A;
+pub const READ_RSP: u8 = 0x0B;
+pub const READ_BLOB_REQ: u8 = 0x0C;
+pub const READ_BLOB_RSP: u8 = 0x0D;
+pub const READ_BY_GROUP_TYPE_REQ: u8 = 0x10;
+pub const READ_BY_GROUP_TYPE_RSP: u8 = 0x11;
+pub const WRITE_REQ: u8 = 0x12;
+pub const WRITE_RSP: u8 = 0x13;
+pub const HANDLE_VALUE_NTF: u8 = 0x1B;
+pub const WRITE_CMD: u8 = 0x52;
+
+/// ATT error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttError {
+    InvalidHandle = 0x01,
+    ReadNotPermitted = 0x02,
+    WriteNotPermitted = 0x03,
+    InvalidPdu = 0x04,
+    RequestNotSupported = 0x06,
+    InvalidOffset = 0x07,
+    AttributeNotFound = 0x0A,
+    InvalidAttributeValueLength = 0x0D,
+    UnsupportedGroupType = 0x10,
+}
+
+/// 16 bit (Bluetooth SIG assigned) or 128 bit (vendor) UUID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Uuid {
+    Uuid16(u16),
+    Uuid128(u128),
+}
+
+impl Uuid {
+    /// Write the UUID little endian into `out`, returning the number of bytes (2 or 16)
+    pub fn encode(&self, out: &mut [u8]) -> usize {
+        match self {
+            Uuid::Uuid16(uuid) => {
+                out[..2].copy_from_slice(&uuid.to_le_bytes());
+                2
+            }
+            Uuid::Uuid128(uuid) => {
+                out[..16].copy_from_slice(&uuid.to_le_bytes());
+                16
+            }
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes.len() {
+            2 => Some(Uuid::Uuid16(u16::from_le_bytes([bytes[0], bytes[1]]))),
+            16 => {
+                let mut uuid = [0; 16];
+                uuid.copy_from_slice(bytes);
+                Some(Uuid::Uuid128(u128::from_le_bytes(uuid)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A characteristic: a value the client can read, write or subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Characteristic {
+    pub uuid: Uuid,
+    /// [`READ`], [`WRITE`], [`WRITE_WITHOUT_RESPONSE`], [`NOTIFY`]
+    pub properties: u8,
+    /// Application identifier passed to [`Values`]
+    pub id: u8,
+}
+
+/// A primary service: a group of characteristics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Service {
+    pub uuid: Uuid,
+    pub characteristics: &'static [Characteristic],
+}
+
+/// Characteristic values, provided by the application
+pub trait Values {
+    /// Write the current value of characteristic `id` into `out`, returning its length
+    fn read(&mut self, id: u8, out: &mut [u8]) -> usize;
+    /// The client wrote `value` to characteristic `id`
+    fn write(&mut self, id: u8, value: &[u8]) -> Result<(), AttError>;
+}
+
+/// What an attribute is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    /// Service declaration, with the handle of the last attribute in the service
+    Service(&'static Service, u16),
+    /// Characteristic declaration
+    Declaration(&'static Characteristic),
+    /// Characteristic value
+    Value(&'static Characteristic),
+    /// Client Characteristic Configuration Descriptor of a characteristic, with its index among the notifying
+    /// characteristics
+    Cccd(&'static Characteristic, usize),
+}
+
+impl Attribute {
+    pub fn uuid(&self) -> Uuid {
+        match self {
+            Attribute::Service(..) => Uuid::Uuid16(PRIMARY_SERVICE),
+            Attribute::Declaration(_) => Uuid::Uuid16(CHARACTERISTIC),
+            Attribute::Value(characteristic) => characteristic.uuid,
+            Attribute::Cccd(..) => Uuid::Uuid16(CCCD),
+        }
+    }
+}
+
+/// Handles are numbered from 1 in table order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeTable {
+    services: &'static [Service],
+}
+
+/// Number of handles a characteristic takes: declaration, value and maybe a CCCD
+fn handle_count(characteristic: &Characteristic) -> u16 {
+    if characteristic.properties & NOTIFY != 0 {
+        3
+    } else {
+        2
+    }
+}
+
+impl AttributeTable {
+    pub const fn new(services: &'static [Service]) -> Self {
+        Self { services }
+    }
+
+    /// All attributes with their handles, in handle order
+    pub fn iter(&self) -> impl Iterator<Item = (u16, Attribute)> + '_ {
+        let mut handle = 1;
+        let mut notifying = 0;
+        self.services.iter().flat_map(move |service| {
+            let start = handle;
+            let end = start + service.characteristics.iter().map(handle_count).sum::<u16>();
+            handle = end + 1;
+
+            let mut next = start + 1;
+            let mut cccd_index = notifying;
+            notifying += service
+                .characteristics
+                .iter()
+                .filter(|c| c.properties & NOTIFY != 0)
+                .count();
+            let characteristics = service.characteristics.iter().flat_map(move |characteristic| {
+                let declaration = next;
+                next += handle_count(characteristic);
+                let cccd = if characteristic.properties & NOTIFY != 0 {
+                    cccd_index += 1;
+                    Some((declaration + 2, Attribute::Cccd(characteristic, cccd_index - 1)))
+                } else {
+                    None
+                };
+                [
+                    Some((declaration, Attribute::Declaration(characteristic))),
+                    Some((declaration + 1, Attribute::Value(characteristic))),
+                    cccd,
+                ]
+                .into_iter()
+                .flatten()
+            });
+            core::iter::once((start, Attribute::Service(service, end))).chain(characteristics)
+        })
+    }
+
+    pub fn get(&self, handle: u16) -> Option<Attribute> {
+        self.iter().find(|(h, _)| *h == handle).map(|(_, attribute)| attribute)
+    }
+
+    /// Handle of the value of characteristic `id`
+    pub fn value_handle(&self, id: u8) -> Option<u16> {
+        self.iter().find_map(|(handle, attribute)| match attribute {
+            Attribute::Value(characteristic) if characteristic.id == id => Some(handle),
+            _ => None,
+        })
+    }
+
+    fn in_range(&self, start: u16, end: u16) -> impl Iterator<Item = (u16, Attribute)> + '_ {
+        self.iter()
+            .skip_while(move |(handle, _)| *handle < start)
+            .take_while(move |(handle, _)| *handle <= end)
+    }
+}
+
+/// Answers ATT requests for one connection
+pub struct AttServer {
+    table: AttributeTable,
+    /// Notifications enabled, one bit per notifying characteristic in table order
+    notifications: u32,
+}
+
+impl AttServer {
+    pub const fn new(table: AttributeTable) -> Self {
+        Self {
+            table,
+            notifications: 0,
+        }
+    }
+
+    pub fn table(&self) -> &AttributeTable {
+        &self.table
+    }
+
+    /// Forget the client's subscriptions, for a new connection
+    pub fn reset(&mut self) {
+        self.notifications = 0;
+    }
+
+    /// Answer `request`, writing the response PDU into `response`. Returns its length, or `None` for commands
+    /// that have no response.
+    pub fn handle(&mut self, request: &[u8], values: &mut impl Values, response: &mut [u8; ATT_MTU]) -> Option<usize> {
+        let &opcode = request.first()?;
+        match self.respond(opcode, request, values, response) {
+            Ok(len) => len,
+            Err((handle, error)) => {
+                response[0] = ERROR_RSP;
+                response[1] = opcode;
+                response[2..4].copy_from_slice(&handle.to_le_bytes());
+                response[4] = error as u8;
+                Some(5)
+            }
+        }
+    }
+
+    /// Build a notification of the current value of characteristic `id`, if the client has enabled them
+    pub fn notification(&self, id: u8, values: &mut impl Values, pdu: &mut [u8; ATT_MTU]) -> Option<usize> {
+        let (handle, index) = self.table.iter().find_map(|(handle, attribute)| match attribute {
+            Attribute::Cccd(characteristic, index) if characteristic.id == id => Some((handle - 1, index)),
+            _ => None,
+        })?;
+        if self.notifications & (1 << index) == 0 {
+            return None;
+        }
+
+        pdu[0] = HANDLE_VALUE_NTF;
+        pdu[1..3].copy_from_slice(&handle.to_le_bytes());
+        let mut value = [0; ATT_MTU];
+        let len = values.read(id, &mut value).min(ATT_MTU - 3);
+        pdu[3..3 + len].copy_from_slice(&value[..len]);
+        Some(3 + len)
+    }
+
+    fn respond(
+        &mut self,
+        opcode: u8,
+        request: &[u8],
+        values: &mut impl Values,
+        out: &mut [u8; ATT_MTU],
+    ) -> Result<Option<usize>, (u16, AttError)> {
+        let u16_at = |i: usize| -> Result<u16, (u16, AttError)> {
+            match request.get(i..i + 2) {
+                Some(bytes) => Ok(u16::from_le_bytes([bytes[0], bytes[1]])),
+                None => Err((0, AttError::InvalidPdu)),
+            }
+        };
+        let range = || -> Result<(u16, u16), (u16, AttError)> {
+            let (start, end) = (u16_at(1)?, u16_at(3)?);
+            if start == 0 || start > end {
+                return Err((start, AttError::InvalidHandle));
+            }
+            Ok((start, end))
+        };
+
+        let len = match opcode {
+            EXCHANGE_MTU_REQ => {
+                u16_at(1)?;
+                out[0] = EXCHANGE_MTU_RSP;
+                out[1..3].copy_from_slice(&(ATT_MTU as u16).to_le_bytes());
+                3
+            }
+            FIND_INFORMATION_REQ => {
+                let (start, end) = range()?;
+                self.find_information(start, end, out)?
+            }
+            FIND_BY_TYPE_VALUE_REQ => {
+                let (start, end) = range()?;
+                let uuid = u16_at(5)?;
+                self.find_by_type_value(start, end, uuid, &request[7..], out)?
+            }
+            READ_BY_TYPE_REQ => {
+                let (start, end) = range()?;
+                let uuid = Uuid::decode(&request[5..]).ok_or((0, AttError::InvalidPdu))?;
+                self.read_by_type(start, end, uuid, values, out)?
+            }
+            READ_BY_GROUP_TYPE_REQ => {
+                let (start, end) = range()?;
+                let uuid = Uuid::decode(&request[5..]).ok_or((0, AttError::InvalidPdu))?;
+                if uuid != Uuid::Uuid16(PRIMARY_SERVICE) {
+                    return Err((start, AttError::UnsupportedGroupType));
+                }
+                self.read_by_group_type(start, end, out)?
+            }
+            READ_REQ | READ_BLOB_REQ => {
+                let handle = u16_at(1)?;
+                let offset = if opcode == READ_BLOB_REQ { u16_at(3)? } else { 0 };
+                let mut value = [0; 64];
+                let len = self.read(handle, values, &mut value)?;
+                let offset = usize::from(offset);
+                if offset > len {
+                    return Err((handle, AttError::InvalidOffset));
+                }
+                let part = &value[offset..len.min(offset + ATT_MTU - 1)];
+                out[0] = if opcode == READ_REQ { READ_RSP } else { READ_BLOB_RSP };
+                out[1..1 + part.len()].copy_from_slice(part);
+                1 + part.len()
+            }
+            WRITE_REQ | WRITE_CMD => {
+                let handle = u16_at(1)?;
+                let result = self.write(handle, &request[3..], values, opcode == WRITE_CMD);
+                if opcode == WRITE_CMD {
+                    return Ok(None);
+                }
+                result.map_err(|error| (handle, error))?;
+                out[0] = WRITE_RSP;
+                1
+            }
+            // Unknown commands (bit 6 set) are ignored, unknown requests get an error
+            _ if opcode & 0x40 != 0 => return Ok(None),
+            _ => return Err((0, AttError::RequestNotSupported)),
+        };
+        Ok(Some(len))
+    }
+
+    /// Value of any attribute
+    fn read(&self, handle: u16, values: &mut impl Values, out: &mut [u8; 64]) -> Result<usize, (u16, AttError)> {
+        let attribute = self.table.get(handle).ok_or((handle, AttError::InvalidHandle))?;
+        Ok(match attribute {
+            Attribute::Service(service, _) => service.uuid.encode(out),
+            Attribute::Declaration(characteristic) => {
+                out[0] = characteristic.properties;
+                out[1..3].copy_from_slice(&(handle + 1).to_le_bytes());
+                3 + characteristic.uuid.encode(&mut out[3..])
+            }
+            Attribute::Value(characteristic) => {
+                if characteristic.properties & READ == 0 {
+                    return Err((handle, AttError::ReadNotPermitted));
+                }
+                values.read(characteristic.id, out)
+            }
+            Attribute::Cccd(_, index) => {
+                let enabled = self.notifications & (1 << index) != 0;
+                out[..2].copy_from_slice(&u16::from(enabled).to_le_bytes());
+                2
+            }
+        })
+    }
+
+    fn write(&mut self, handle: u16, value: &[u8], values: &mut impl Values, command: bool) -> Result<(), AttError> {
+        match self.table.get(handle).ok_or(AttError::InvalidHandle)? {
+            Attribute::Value(characteristic) => {
+                let allowed = if command { WRITE_WITHOUT_RESPONSE } else { WRITE };
+                if characteristic.properties & allowed == 0 {
+                    return Err(AttError::WriteNotPermitted);
+                }
+                values.write(characteristic.id, value)
+            }
+            Attribute::Cccd(_, index) => {
+                let [low, high] = value else {
+                    return Err(AttError::InvalidAttributeValueLength);
+                };
+                // Bit 0: notifications. Indications (bit 1) are not supported.
+                if u16::from_le_bytes([*low, *high]) & 1 != 0 {
+                    self.notifications |= 1 << index;
+                } else {
+                    self.notifications &= !(1 << index);
+                }
+                Ok(())
+            }
+            _ => Err(AttError::WriteNotPermitted),
+        }
+    }
+
+    /// Handles and types, all 16 bit or all 128 bit UUIDs in one response
+    fn find_information(&self, start: u16, end: u16, out: &mut [u8; ATT_MTU]) -> Result<usize, (u16, AttError)> {
+        let mut len = 2;
+        let mut format = 0;
+        for (handle, attribute) in self.table.in_range(start, end) {
+            let mut uuid = [0; 16];
+            let uuid_len = attribute.uuid().encode(&mut uuid);
+            let entry_format = if uuid_len == 2 { 1 } else { 2 };
+            if format != 0 && entry_format != format || len + 2 + uuid_len > ATT_MTU {
+                break;
+            }
+            format = entry_format;
+            out[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            out[len + 2..len + 2 + uuid_len].copy_from_slice(&uuid[..uuid_len]);
+            len += 2 + uuid_len;
+        }
+        if format == 0 {
+            return Err((start, AttError::AttributeNotFound));
+        }
+        out[0] = FIND_INFORMATION_RSP;
+        out[1] = format;
+        Ok(len)
+    }
+
+    /// Used by clients to find one service by UUID: handle ranges of the matching services
+    fn find_by_type_value(
+        &self,
+        start: u16,
+        end: u16,
+        attribute_type: u16,
+        value: &[u8],
+        out: &mut [u8; ATT_MTU],
+    ) -> Result<usize, (u16, AttError)> {
+        let mut len = 1;
+        for (handle, attribute) in self.table.in_range(start, end) {
+            let Attribute::Service(service, group_end) = attribute else {
+                continue;
+            };
+            let mut uuid = [0; 16];
+            let uuid_len = service.uuid.encode(&mut uuid);
+            if attribute_type != PRIMARY_SERVICE || uuid[..uuid_len] != *value {
+                continue;
+            }
+            if len + 4 > ATT_MTU {
+                break;
+            }
+            out[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            out[len + 2..len + 4].copy_from_slice(&group_end.to_le_bytes());
+            len += 4;
+        }
+        if len == 1 {
+            return Err((start, AttError::AttributeNotFound));
+        }
+        out[0] = FIND_BY_TYPE_VALUE_RSP;
+        Ok(len)
+    }
+
+    /// Handle-value pairs of all attributes of one type with values of equal length, e.g. all characteristic
+    /// declarations
+    fn read_by_type(
+        &self,
+        start: u16,
+        end: u16,
+        uuid: Uuid,
+        values: &mut impl Values,
+        out: &mut [u8; ATT_MTU],
+    ) -> Result<usize, (u16, AttError)> {
+        let mut len = 2;
+        let mut entry_len = 0;
+        for (handle, attribute) in self.table.in_range(start, end) {
+            if attribute.uuid() != uuid {
+                continue;
+            }
+            let mut value = [0; 64];
+            let value_len = match self.read(handle, values, &mut value) {
+                Ok(value_len) => value_len,
+                // Report the error only if it concerns the first match
+                Err(error) if entry_len == 0 => return Err(error),
+                Err(_) => break,
+            };
+            let value_len = value_len.min(ATT_MTU - 4);
+            if entry_len != 0 && 2 + value_len != entry_len || len + 2 + value_len > ATT_MTU {
+                break;
+            }
+            entry_len = 2 + value_len;
+            out[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            out[len + 2..len + entry_len].copy_from_slice(&value[..value_len]);
+            len += entry_len;
+        }
+        if entry_len == 0 {
+            return Err((start, AttError::AttributeNotFound));
+        }
+        out[0] = READ_BY_TYPE_RSP;
+        out[1] = entry_len as u8;
+        Ok(len)
+    }
+
+    /// Service discovery: start handle, end handle and UUID of each service, all with the same UUID length
+    fn read_by_group_type(&self, start: u16, end: u16, out: &mut [u8; ATT_MTU]) -> Result<usize, (u16, AttError)> {
+        let mut len = 2;
+        let mut entry_len = 0;
+        for (handle, attribute) in self.table.in_range(start, end) {
+            let Attribute::Service(service, group_end) = attribute else {
+                continue;
+            };
+            let mut uuid = [0; 16];
+            let uuid_len = service.uuid.encode(&mut uuid);
+            if entry_len != 0 && 4 + uuid_len != entry_len || len + 4 + uuid_len > ATT_MTU {
+                break;
+            }
+            entry_len = 4 + uuid_len;
+            out[len..len + 2].copy_from_slice(&handle.to_le_bytes());
+            out[len + 2..len + 4].copy_from_slice(&group_end.to_le_bytes());
+            out[len + 4..len + entry_len].copy_from_slice(&uuid[..uuid_len]);
+            len += entry_len;
+        }
+        if entry_len == 0 {
+            return Err((start, AttError::AttributeNotFound));
+        }
+        out[0] = READ_BY_GROUP_TYPE_RSP;
+        out[1] = entry_len as u8;
+        Ok(len)
+    }
+}
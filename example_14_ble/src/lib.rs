@@ -0,0 +1,19 @@
+#![no_std]
+
+//! Bluetooth Low Energy peripheral for the micro:bit v2, written from scratch on top of the RADIO peripheral.
+//!
+//! - [`advertising`] builds advertising packets and parses connection requests
+//! - [`link`] is the link layer of a connection: channel hopping, acknowledgements, LL control and L2CAP
+//! - [`gatt`] is the attribute table and the ATT server answering a phone's requests
+//! - [`profile`] defines the micro:bit accelerometer, button and LED services and their value encoding
+//! - [`ble`] drives the radio with precise timing (target only)
+//!
+//! Everything except `ble` is plain Rust and is tested on the PC, see `tests/`.
+
+pub mod advertising;
+pub mod gatt;
+pub mod link;
+pub mod profile;
+
+#[cfg(target_os = "none")]
+pub mod ble;
@@ -0,0 +1,547 @@
+//! BLE link layer of a connected peripheral, without the radio.
+//!
+//! After a CONNECT_IND, central and peripheral meet in **connection events**, one every connection interval, each on
+//! the next data channel. In each event the central sends one data PDU and the peripheral answers 150 µs later:
+//!
+//! ```text
+//! ┌──────────────────────────────┬────────┬──────────────────────┐
+//! │ LLID | NESN | SN | MD | RFU  │ length │ payload              │
+//! │ 2 bit  1 bit  1 bit 1 bit    │ 1 byte │ 0-27 bytes           │
+//! └──────────────────────────────┴────────┴──────────────────────┘
+//! LLID: 1 = empty / L2CAP continuation, 2 = L2CAP start, 3 = LL control
+//! ```
+//!
+//! SN (sequence number) and NESN (next expected sequence number) acknowledge packets: each side repeats its last
+//! packet until the other side's NESN shows it arrived.
+//!
+//! [`Connection`] implements everything that does not depend on timing: channel selection, acknowledgement, LL
+//! control procedures (version, features, connection update, channel map, termination), and the L2CAP channels
+//! for ATT, signalling and security. The radio driver calls [`Connection::respond`] when a packet has arrived,
+//! [`Connection::process`] once the answer is sent, and [`Connection::next_event`] to find out when and where to
+//! listen next.
+
+use crate::{
+    advertising::ConnectInd,
+    gatt::{AttServer, Values, ATT_MTU},
+};
+
+/// Longest data PDU payload (no data length extension)
+pub const MAX_PAYLOAD: usize = 27;
+/// Longest data PDU including the header
+pub const MAX_PDU_LEN: usize = 2 + MAX_PAYLOAD;
+/// Data PDUs waiting to be sent
+pub const TX_QUEUE_LEN: usize = 4;
+
+/// The transmit window starts 1.25 ms after the CONNECT_IND
+pub const TRANSMIT_WINDOW_DELAY_US: u32 = 1250;
+/// Accuracy of our own clock (the HFXO crystal is rated 40 ppm)
+pub const OUR_CLOCK_PPM: u32 = 50;
+
+// LLID values
+const LLID_CONTINUATION: u8 = 1;
+const LLID_START: u8 = 2;
+const LLID_CONTROL: u8 = 3;
+
+// Header bits
+const NESN: u8 = 1 << 2;
+const SN: u8 = 1 << 3;
+
+// LL control opcodes
+pub const LL_CONNECTION_UPDATE_IND: u8 = 0x00;
+pub const LL_CHANNEL_MAP_IND: u8 = 0x01;
+pub const LL_TERMINATE_IND: u8 = 0x02;
+pub const LL_UNKNOWN_RSP: u8 = 0x07;
+pub const LL_FEATURE_REQ: u8 = 0x08;
+pub const LL_FEATURE_RSP: u8 = 0x09;
+pub const LL_VERSION_IND: u8 = 0x0C;
+pub const LL_PING_REQ: u8 = 0x12;
+pub const LL_PING_RSP: u8 = 0x13;
+pub const LL_LENGTH_REQ: u8 = 0x14;
+pub const LL_LENGTH_RSP: u8 = 0x15;
+
+/// Bluetooth version reported in LL_VERSION_IND: 4.2, the last version without mandatory new procedures
+pub const VERSION_4_2: u8 = 0x08;
+/// Company identifier 0xFFFF is reserved for testing and unregistered use
+pub const COMPANY_ID: u16 = 0xFFFF;
+
+// L2CAP channels
+pub const CID_ATT: u16 = 0x0004;
+pub const CID_SIGNALING: u16 = 0x0005;
+pub const CID_SECURITY: u16 = 0x0006;
+
+/// Sleep clock accuracy field of CONNECT_IND to the worst case drift in ppm
+pub fn sca_ppm(sca: u8) -> u32 {
+    [500, 250, 150, 100, 75, 50, 30, 20][usize::from(sca & 7)]
+}
+
+/// Data channels the central uses, from a 37 bit channel map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelMap {
+    map: [u8; 5],
+    used: [u8; 37],
+    count: u8,
+}
+
+impl ChannelMap {
+    /// Whether a central may send `map`: at least 2 used channels, and none above 36 (Vol 6 Part B, 2.3.3.1)
+    pub fn valid(map: &[u8; 5]) -> bool {
+        map[4] & 0xE0 == 0 && map.iter().map(|byte| byte.count_ones()).sum::<u32>() >= 2
+    }
+
+    /// `map` must be [`valid`](Self::valid): [`remap`](Self::remap) divides by the number of used channels
+    pub fn new(map: [u8; 5]) -> Self {
+        let mut used = [0; 37];
+        let mut count = 0;
+        for channel in 0..37 {
+            if map[channel / 8] & (1 << (channel % 8)) != 0 {
+                used[usize::from(count)] = channel as u8;
+                count += 1;
+            }
+        }
+        Self { map, used, count }
+    }
+
+    pub fn is_used(&self, channel: u8) -> bool {
+        self.map[usize::from(channel / 8)] & (1 << (channel % 8)) != 0
+    }
+
+    /// Channel selection algorithm #1 remapping: an unused channel is replaced by one of the used ones
+    pub fn remap(&self, unmapped: u8) -> u8 {
+        if self.is_used(unmapped) {
+            unmapped
+        } else {
+            self.used[usize::from(unmapped % self.count)]
+        }
+    }
+}
+
+/// When and where the next connection event happens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    /// Data channel index 0-36
+    pub channel: u8,
+    /// Time from the last anchor point to the earliest expected start of the central's packet
+    pub offset_us: u32,
+    /// Length of the window in which the packet may start: 0 except for transmit windows
+    pub window_us: u32,
+    /// Extra listening time on both sides to allow for clock drift
+    pub widening_us: u32,
+}
+
+/// Why a connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Closed {
+    /// The central sent LL_TERMINATE_IND with this reason
+    Terminated(u8),
+    /// Nothing was received for the supervision timeout
+    Timeout,
+    /// The central asked for something impossible, e.g. an instant in the past
+    ProtocolError,
+}
+
+/// What [`Connection::respond`] found in the received packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Received {
+    /// A new packet that [`Connection::process`] must handle after the response has been sent
+    New,
+    /// A retransmission or an empty packet, nothing to do
+    Nothing,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConnectionUpdate {
+    instant: u16,
+    win_size: u8,
+    win_offset: u16,
+    interval: u16,
+    timeout: u16,
+}
+
+/// A small ring of data PDUs
+#[derive(Debug)]
+struct PduQueue {
+    pdus: [[u8; MAX_PDU_LEN]; TX_QUEUE_LEN],
+    head: usize,
+    count: usize,
+}
+
+impl PduQueue {
+    const fn new() -> Self {
+        Self {
+            pdus: [[0; MAX_PDU_LEN]; TX_QUEUE_LEN],
+            head: 0,
+            count: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.count == TX_QUEUE_LEN
+    }
+
+    fn push(&mut self, llid: u8, payload: &[u8]) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        let slot = &mut self.pdus[(self.head + self.count) % TX_QUEUE_LEN];
+        slot[0] = llid;
+        slot[1] = payload.len() as u8;
+        slot[2..2 + payload.len()].copy_from_slice(payload);
+        self.count += 1;
+        true
+    }
+
+    fn pop(&mut self) -> Option<[u8; MAX_PDU_LEN]> {
+        if self.count == 0 {
+            return None;
+        }
+        let pdu = self.pdus[self.head];
+        self.head = (self.head + 1) % TX_QUEUE_LEN;
+        self.count -= 1;
+        Some(pdu)
+    }
+}
+
+/// Link layer state of one connection, see the module documentation
+pub struct Connection {
+    pub access_address: u32,
+    pub crc_init: u32,
+    /// Connection interval in 1.25 ms units
+    interval: u16,
+    /// Supervision timeout in 10 ms units
+    timeout: u16,
+    channel_map: ChannelMap,
+    hop: u8,
+    central_ppm: u32,
+
+    event_counter: u16,
+    unmapped_channel: u8,
+    /// Schedule of the current event relative to the last anchor point
+    offset_us: u32,
+    window_us: u32,
+    /// A packet has been received: until then the first event's transmit window is kept
+    established: bool,
+    first_window: (u16, u8),
+
+    pending_update: Option<ConnectionUpdate>,
+    pending_channel_map: Option<(u16, ChannelMap)>,
+
+    /// Our sequence number and the sequence number we expect next
+    sn: bool,
+    nesn: bool,
+    /// The last PDU sent, repeated until acknowledged
+    last_sent: [u8; MAX_PDU_LEN],
+    tx_queue: PduQueue,
+    /// The received PDU waiting for `process`
+    received: [u8; MAX_PDU_LEN],
+}
+
+impl Connection {
+    /// Start a connection from an accepted CONNECT_IND
+    pub fn new(request: &ConnectInd) -> Self {
+        let mut last_sent = [0; MAX_PDU_LEN];
+        last_sent[0] = LLID_CONTINUATION;
+        Self {
+            access_address: request.access_address,
+            crc_init: request.crc_init,
+            interval: request.interval,
+            timeout: request.timeout,
+            channel_map: ChannelMap::new(request.channel_map),
+            hop: request.hop,
+            central_ppm: sca_ppm(request.sca),
+            event_counter: 0,
+            unmapped_channel: 0,
+            offset_us: 0,
+            window_us: 0,
+            established: false,
+            first_window: (request.win_offset, request.win_size),
+            pending_update: None,
+            pending_channel_map: None,
+            sn: false,
+            nesn: false,
+            last_sent,
+            tx_queue: PduQueue::new(),
+            received: [0; MAX_PDU_LEN],
+        }
+    }
+
+    /// Connection interval in µs
+    pub fn interval_us(&self) -> u32 {
+        u32::from(self.interval) * 1250
+    }
+
+    /// Counter of the current connection event
+    pub fn event_counter(&self) -> u16 {
+        self.event_counter
+    }
+
+    fn widening_us(&self, since_anchor_us: u32) -> u32 {
+        let drift = (u64::from(since_anchor_us) * u64::from(self.central_ppm + OUR_CLOCK_PPM)).div_ceil(1_000_000);
+        drift as u32 + 16
+    }
+
+    fn next_channel(&mut self) -> u8 {
+        self.unmapped_channel = (self.unmapped_channel + self.hop) % 37;
+        self.channel_map.remap(self.unmapped_channel)
+    }
+
+    /// The first connection event, timed from the end of the CONNECT_IND instead of an anchor point
+    pub fn first_event(&mut self) -> Schedule {
+        let (win_offset, win_size) = self.first_window;
+        self.offset_us = TRANSMIT_WINDOW_DELAY_US + u32::from(win_offset) * 1250;
+        self.window_us = u32::from(win_size) * 1250;
+        self.schedule()
+    }
+
+    /// Move on to the next connection event. `anchor` tells whether a packet from the central was received in the
+    /// event that just ended; if so its start is the new anchor point and the schedule is relative to it, otherwise
+    /// to the last anchor point.
+    pub fn next_event(&mut self, anchor: bool) -> Result<Schedule, Closed> {
+        if anchor {
+            self.established = true;
+            self.offset_us = 0;
+            self.window_us = 0;
+        }
+        // A connection must be established within 6 events
+        if !self.established && self.event_counter >= 5 {
+            return Err(Closed::Timeout);
+        }
+
+        // Missed events keep the window: the central's packet may still come anywhere in it
+        self.event_counter = self.event_counter.wrapping_add(1);
+        self.offset_us += self.interval_us();
+
+        if let Some((instant, map)) = self.pending_channel_map {
+            if instant == self.event_counter {
+                self.channel_map = map;
+                self.pending_channel_map = None;
+            }
+        }
+        if let Some(update) = self.pending_update {
+            if update.instant == self.event_counter {
+                // The instant event is still at the old interval, then the central moves by the window offset.
+                // Latency is ignored: this peripheral listens in every event.
+                self.offset_us += u32::from(update.win_offset) * 1250;
+                self.window_us = u32::from(update.win_size) * 1250;
+                self.interval = update.interval;
+                self.timeout = update.timeout;
+                self.pending_update = None;
+            }
+        }
+
+        // Supervision timeout: the next event would end too long after the last anchor point
+        if self.offset_us + self.window_us > u32::from(self.timeout) * 10_000 {
+            return Err(Closed::Timeout);
+        }
+        Ok(self.schedule())
+    }
+
+    fn schedule(&mut self) -> Schedule {
+        Schedule {
+            channel: self.next_channel(),
+            offset_us: self.offset_us,
+            window_us: self.window_us,
+            widening_us: self.widening_us(self.offset_us + self.window_us),
+        }
+    }
+
+    /// A packet arrived (`crc_ok` tells whether its CRC was valid): acknowledge it and return the PDU to send back
+    /// 150 µs later. This runs between receiving and transmitting, so it only does the bookkeeping;
+    /// [`process`](Self::process) handles the content afterwards.
+    pub fn respond(&mut self, pdu: &[u8], crc_ok: bool) -> (Received, &[u8]) {
+        let mut received = Received::Nothing;
+        if crc_ok && pdu.len() >= 2 {
+            let header = pdu[0];
+
+            // The central's NESN differs from our SN: our last packet arrived, move on to the next one
+            if (header & NESN != 0) != self.sn {
+                self.sn = !self.sn;
+                self.last_sent = self.tx_queue.pop().unwrap_or_else(|| {
+                    let mut empty = [0; MAX_PDU_LEN];
+                    empty[0] = LLID_CONTINUATION;
+                    empty
+                });
+            }
+
+            // A new packet from the central, and room for whatever answer it needs: acknowledge it.
+            // Otherwise NESN stays, and the central sends it again.
+            let len = usize::from(pdu[1]).min(MAX_PAYLOAD).min(pdu.len() - 2);
+            if (header & SN != 0) == self.nesn && !self.tx_queue.is_full() {
+                self.nesn = !self.nesn;
+                if len > 0 {
+                    self.received[..2 + len].copy_from_slice(&pdu[..2 + len]);
+                    self.received[1] = len as u8;
+                    received = Received::New;
+                }
+            }
+        }
+
+        self.last_sent[0] &= !(SN | NESN);
+        if self.sn {
+            self.last_sent[0] |= SN;
+        }
+        if self.nesn {
+            self.last_sent[0] |= NESN;
+        }
+        let len = 2 + usize::from(self.last_sent[1]);
+        (received, &self.last_sent[..len])
+    }
+
+    /// Handle the packet [`respond`](Self::respond) reported as [`Received::New`]: LL control procedures are
+    /// answered here, ATT requests by `server`
+    pub fn process(&mut self, server: &mut AttServer, values: &mut impl Values) -> Result<(), Closed> {
+        let len = usize::from(self.received[1]);
+        let pdu = self.received;
+        let payload = &pdu[2..2 + len];
+        match pdu[0] & 3 {
+            LLID_CONTROL => self.control(payload),
+            LLID_START => {
+                self.l2cap(payload, server, values);
+                Ok(())
+            }
+            // Fragmented L2CAP frames are never needed with a 23 byte ATT_MTU
+            _ => Ok(()),
+        }
+    }
+
+    /// Queue an ATT PDU (e.g. a notification). Returns false if the queue is full.
+    pub fn send_att(&mut self, att: &[u8]) -> bool {
+        let mut frame = [0; MAX_PAYLOAD];
+        frame[..2].copy_from_slice(&(att.len() as u16).to_le_bytes());
+        frame[2..4].copy_from_slice(&CID_ATT.to_le_bytes());
+        frame[4..4 + att.len()].copy_from_slice(att);
+        self.tx_queue.push(LLID_START, &frame[..4 + att.len()])
+    }
+
+    /// Room for more PDUs in the transmit queue
+    pub fn can_send(&self) -> bool {
+        !self.tx_queue.is_full()
+    }
+
+    fn control(&mut self, payload: &[u8]) -> Result<(), Closed> {
+        let Some((&opcode, data)) = payload.split_first() else {
+            return Ok(());
+        };
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+
+        match opcode {
+            LL_CONNECTION_UPDATE_IND if data.len() == 11 => {
+                let update = ConnectionUpdate {
+                    win_size: data[0],
+                    win_offset: u16_at(1),
+                    interval: u16_at(3),
+                    timeout: u16_at(7),
+                    instant: u16_at(9),
+                };
+                // The same ranges decode_connect_ind checks: an interval of 0 would stop next_event advancing
+                let valid = (1..=8).contains(&update.win_size)
+                    && update.win_offset <= update.interval
+                    && (6..=3200).contains(&update.interval)
+                    && (10..=3200).contains(&update.timeout);
+                if !valid {
+                    return Err(Closed::ProtocolError);
+                }
+                self.check_instant(update.instant)?;
+                self.pending_update = Some(update);
+            }
+            LL_CHANNEL_MAP_IND if data.len() == 7 => {
+                let mut map = [0; 5];
+                map.copy_from_slice(&data[..5]);
+                let instant = u16_at(5);
+                // At least 2 data channels, none above 36: an empty map would leave remap nothing to choose from
+                if !ChannelMap::valid(&map) {
+                    return Err(Closed::ProtocolError);
+                }
+                self.check_instant(instant)?;
+                self.pending_channel_map = Some((instant, ChannelMap::new(map)));
+            }
+            LL_TERMINATE_IND => return Err(Closed::Terminated(data.first().copied().unwrap_or(0))),
+            LL_VERSION_IND => {
+                let company = COMPANY_ID.to_le_bytes();
+                self.tx_queue.push(
+                    LLID_CONTROL,
+                    &[LL_VERSION_IND, VERSION_4_2, company[0], company[1], 0, 0],
+                );
+            }
+            LL_FEATURE_REQ => {
+                // No optional features: no encryption, no data length extension, no 2M PHY, ...
+                self.tx_queue
+                    .push(LLID_CONTROL, &[LL_FEATURE_RSP, 0, 0, 0, 0, 0, 0, 0, 0]);
+            }
+            LL_PING_REQ => {
+                self.tx_queue.push(LLID_CONTROL, &[LL_PING_RSP]);
+            }
+            LL_LENGTH_REQ => {
+                // 27 bytes and 328 µs in both directions: the minimum
+                let (octets, time) = (27u16.to_le_bytes(), 328u16.to_le_bytes());
+                let response = [
+                    LL_LENGTH_RSP,
+                    octets[0],
+                    octets[1],
+                    time[0],
+                    time[1],
+                    octets[0],
+                    octets[1],
+                    time[0],
+                    time[1],
+                ];
+                self.tx_queue.push(LLID_CONTROL, &response);
+            }
+            // Responses to procedures we never start need no answer
+            LL_UNKNOWN_RSP | LL_FEATURE_RSP | LL_PING_RSP | LL_LENGTH_RSP => {}
+            _ => {
+                self.tx_queue.push(LLID_CONTROL, &[LL_UNKNOWN_RSP, opcode]);
+            }
+        }
+        Ok(())
+    }
+
+    /// An instant must be in the future (less than 32767 events ahead)
+    fn check_instant(&self, instant: u16) -> Result<(), Closed> {
+        if instant.wrapping_sub(self.event_counter) >= 0x8000 || instant == self.event_counter {
+            return Err(Closed::ProtocolError);
+        }
+        Ok(())
+    }
+
+    fn l2cap(&mut self, frame: &[u8], server: &mut AttServer, values: &mut impl Values) {
+        if frame.len() < 4 {
+            return;
+        }
+        let len = usize::from(u16::from_le_bytes([frame[0], frame[1]]));
+        let channel = u16::from_le_bytes([frame[2], frame[3]]);
+        let Some(payload) = frame.get(4..4 + len) else {
+            return;
+        };
+
+        match channel {
+            CID_ATT => {
+                let mut response = [0; ATT_MTU];
+                if let Some(len) = server.handle(payload, values, &mut response) {
+                    self.send_att(&response[..len]);
+                }
+            }
+            CID_SIGNALING => {
+                // We never send requests, so anything but a Command Reject is a request to reject: "command not
+                // understood"
+                if let [code, identifier, ..] = payload {
+                    if *code != 0x01 {
+                        self.send_l2cap(CID_SIGNALING, &[0x01, *identifier, 2, 0, 0, 0]);
+                    }
+                }
+            }
+            // Pairing Request: Pairing Failed, "pairing not supported"
+            CID_SECURITY if payload.first() == Some(&0x01) => {
+                self.send_l2cap(CID_SECURITY, &[0x05, 0x05]);
+            }
+            _ => {}
+        }
+    }
+
+    fn send_l2cap(&mut self, channel: u16, payload: &[u8]) {
+        let mut frame = [0; MAX_PAYLOAD];
+        frame[..2].copy_from_slice(&(payload.len() as u16).to_le_bytes());
+        frame[2..4].copy_from_slice(&channel.to_le_bytes());
+        frame[4..4 + payload.len()].copy_from_slice(payload);
+        self.tx_queue.push(LLID_START, &frame[..4 + payload.len()]);
+    }
+}
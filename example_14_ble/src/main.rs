@@ -0,0 +1,203 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::cell::RefCell,
+    cortex_m::{
+        interrupt::{free, CriticalSection, Mutex},
+        register::{basepri, basepri_max},
+    },
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_14_ble::{
+        advertising::{AdvertisingData, FLAGS_LE_ONLY_GENERAL, MAX_ADV_PDU_LEN},
+        ble::{Ble, Buffers, LinkEvent},
+        link::COMPANY_ID,
+        profile::{
+            encode_acceleration, ButtonMonitor, ButtonState, DEVICE_NAME, ID_ACCELERATION, ID_BUTTON_A, ID_BUTTON_B,
+        },
+    },
+    lsm303agr::{AccelMode, AccelOutputDataRate, Lsm303agr},
+    microbit::{
+        display::nonblocking::{BitImage, Display},
+        hal::{clocks::Clocks, twim, Timer},
+        pac::{self, interrupt, twim0::frequency::FREQUENCY_A},
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+// Shared with the interrupt handlers: BLE with RADIO, DISPLAY with TIMER1, see `with_display`
+#[cfg(target_os = "none")]
+static BLE: Mutex<RefCell<Option<Ble>>> = Mutex::new(RefCell::new(None));
+#[cfg(target_os = "none")]
+static DISPLAY: Mutex<RefCell<Option<Display<pac::TIMER1>>>> = Mutex::new(RefCell::new(None));
+
+/// NVIC priorities: RADIO has to load the next packet within 150 µs (T_IFS), the display refresh can wait
+#[cfg(target_os = "none")]
+const RADIO_PRIORITY: u8 = 0;
+#[cfg(target_os = "none")]
+const TIMER1_PRIORITY: u8 = 1 << 5;
+
+/// Buttons are sampled this often
+#[cfg(target_os = "none")]
+const SAMPLE_MS: u32 = 20;
+/// The accelerometer is read every 5th sample: 10 times a second, its data rate
+#[cfg(target_os = "none")]
+const ACCELERATION_EVERY: u32 = 5;
+
+/// Advertising data: discoverable, the name, and a beacon with the acceleration and buttons in the manufacturer data
+#[cfg(target_os = "none")]
+fn advertising_data(acceleration: [u8; 6], button_a: ButtonState, button_b: ButtonState) -> AdvertisingData {
+    let mut beacon = [0; 7];
+    beacon[..6].copy_from_slice(&acceleration);
+    beacon[6] = button_a as u8 | (button_b as u8) << 4;
+    AdvertisingData::new()
+        .flags(FLAGS_LE_ONLY_GENERAL)
+        .and_then(|data| data.complete_local_name(DEVICE_NAME))
+        .and_then(|data| data.manufacturer_data(COMPANY_ID, &beacon))
+        .unwrap()
+}
+
+/// Run `f` on the display, with TIMER1 masked but RADIO still running
+///
+/// Only `main` and TIMER1 use the display, so raising BASEPRI to TIMER1's priority keeps every other user out, as
+/// a critical section would. RADIO is more urgent, is not masked, and never touches the display.
+#[cfg(target_os = "none")]
+fn with_display<R>(f: impl FnOnce(&mut Display<pac::TIMER1>) -> R) -> Option<R> {
+    let old = basepri::read();
+    // BASEPRI_MAX only raises the mask: in TIMER1 itself this changes nothing
+    basepri_max::write(TIMER1_PRIORITY);
+    // Not a real critical section: enough for DISPLAY because no interrupt left running uses it
+    let cs = unsafe { CriticalSection::new() };
+    let result = DISPLAY.borrow(&cs).borrow_mut().as_mut().map(f);
+    unsafe { basepri::write(old) };
+    result
+}
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer2 = Timer::new(board.TIMER2);
+
+    // The radio needs the accurate crystal oscillator instead of the internal RC oscillator
+    let clocks = Clocks::new(board.CLOCK).enable_ext_hfosc();
+
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut accelerometer = Lsm303agr::new_with_i2c(i2c);
+    accelerometer.init().unwrap();
+    accelerometer
+        .set_accel_mode_and_odr(&mut timer2, AccelMode::Normal, AccelOutputDataRate::Hz10)
+        .unwrap();
+
+    let buffers: &'static mut Buffers = cortex_m::singleton!(: Buffers = [[0; MAX_ADV_PDU_LEN]; 2]).unwrap();
+    let mut ble = Ble::new(board.RADIO, board.TIMER0, board.PPI, &board.FICR, &clocks, buffers);
+    ble.set_advertising_data(advertising_data([0; 6], ButtonState::Released, ButtonState::Released).as_bytes());
+    ble.start();
+    rprintln!(
+        "Advertising as \"{}\", address {:02X?}",
+        DEVICE_NAME,
+        ble.address().bytes
+    );
+
+    let display = Display::new(board.TIMER1, board.display_pins);
+    free(|cs| {
+        BLE.borrow(cs).replace(Some(ble));
+        DISPLAY.borrow(cs).replace(Some(display));
+    });
+
+    // The radio has to answer within 150 µs, so the display refresh must not hold it up
+    let mut nvic = board.NVIC;
+    unsafe {
+        nvic.set_priority(pac::Interrupt::RADIO, RADIO_PRIORITY);
+        nvic.set_priority(pac::Interrupt::TIMER1, TIMER1_PRIORITY);
+        pac::NVIC::unmask(pac::Interrupt::RADIO);
+        pac::NVIC::unmask(pac::Interrupt::TIMER1);
+    }
+
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_b = board.buttons.button_b.into_floating_input();
+    let mut monitor_a = ButtonMonitor::new();
+    let mut monitor_b = ButtonMonitor::new();
+    let mut acceleration = (0, 0, 0);
+    let mut sample: u32 = 0;
+
+    loop {
+        let a_changed = monitor_a.update(button_a.is_low().unwrap(), SAMPLE_MS).is_some();
+        let b_changed = monitor_b.update(button_b.is_low().unwrap(), SAMPLE_MS).is_some();
+
+        let mut acceleration_changed = false;
+        if sample % ACCELERATION_EVERY == 0 {
+            let latest = accelerometer.acceleration().unwrap().xyz_mg();
+            acceleration_changed = latest != acceleration;
+            acceleration = latest;
+        }
+        sample = sample.wrapping_add(1);
+
+        // Anything slow happens outside the locks: RADIO waits for as long as main holds BLE
+        let advertising = acceleration_changed.then(|| {
+            let (x, y, z) = acceleration;
+            advertising_data(encode_acceleration(x, y, z), monitor_a.state(), monitor_b.state())
+        });
+
+        // Hand the new values to the BLE link, and take what the phone wrote to the LED matrix
+        let (leds, event) = free(|cs| {
+            let mut ble = BLE.borrow(cs).borrow_mut();
+            let ble = ble.as_mut().unwrap();
+            let values = ble.values();
+            values.acceleration = acceleration;
+            values.button_a = monitor_a.state();
+            values.button_b = monitor_b.state();
+            let leds = values.take_led_update();
+
+            if a_changed {
+                ble.notify(ID_BUTTON_A);
+            }
+            if b_changed {
+                ble.notify(ID_BUTTON_B);
+            }
+            if let Some(data) = &advertising {
+                ble.notify(ID_ACCELERATION);
+                ble.set_advertising_data(data.as_bytes());
+            }
+            (leds, ble.take_event())
+        });
+
+        if let Some(leds) = leds {
+            let image = BitImage::new(&leds);
+            with_display(|display| display.show(&image));
+        }
+
+        match event {
+            Some(LinkEvent::Connected(central)) => rprintln!("Connected to {:02X?}", central.bytes),
+            Some(LinkEvent::Disconnected(reason)) => rprintln!("Disconnected: {:?}, advertising again", reason),
+            None => {}
+        }
+
+        timer2.delay_ms(SAMPLE_MS);
+    }
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn RADIO() {
+    // The most urgent interrupt: the critical section only holds up TIMER1, which waits for RADIO anyway
+    free(|cs| {
+        if let Some(ble) = BLE.borrow(cs).borrow_mut().as_mut() {
+            ble.on_radio_interrupt();
+        }
+    });
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn TIMER1() {
+    with_display(|display| display.handle_display_event());
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,225 @@
+//! The services of the micro:bit Bluetooth profile used by this example.
+//!
+//! UUIDs and value formats follow the official micro:bit profile (the one MakeCode's Bluetooth extension
+//! implements), so existing micro:bit apps can talk to this firmware:
+//!
+//! | Service / characteristic | UUID        | Value |
+//! |--------------------------|-------------|-------|
+//! | Accelerometer service    | `E95D0753…` | |
+//! | Accelerometer data       | `E95DCA4B…` | x, y, z: `i16` little endian, milli-g. Read, notify |
+//! | Button service           | `E95D9882…` | |
+//! | Button A state           | `E95DDA90…` | `u8`: 0 released, 1 pressed, 2 long press. Read, notify |
+//! | Button B state           | `E95DDA91…` | as button A |
+//! | LED service              | `E95DD91D…` | |
+//! | LED matrix state         | `E95D7B77…` | 5 bytes, one per row from the top, bit 4 = left column. Read, write |
+//!
+//! All of them share the base UUID `E95Dxxxx-251D-470A-A062-FA1922DFA9A8`. The standard GAP service with the
+//! device name comes first, because phones expect it.
+
+use crate::gatt::{AttError, AttributeTable, Characteristic, Service, Uuid, Values, NOTIFY, READ, WRITE};
+
+/// micro:bit profile UUID with the 16 bit `short` filled in
+pub const fn microbit_uuid(short: u16) -> u128 {
+    0xE95D_0000_251D_470A_A062_FA19_22DF_A9A8 | ((short as u128) << 96)
+}
+
+pub const DEVICE_NAME: &str = "micro:bit";
+
+// Characteristic identifiers used by `Values`
+pub const ID_DEVICE_NAME: u8 = 0;
+pub const ID_ACCELERATION: u8 = 1;
+pub const ID_BUTTON_A: u8 = 2;
+pub const ID_BUTTON_B: u8 = 3;
+pub const ID_LED_MATRIX: u8 = 4;
+
+pub const ACCELEROMETER_SERVICE: u128 = microbit_uuid(0x0753);
+pub const BUTTON_SERVICE: u128 = microbit_uuid(0x9882);
+pub const LED_SERVICE: u128 = microbit_uuid(0xD91D);
+
+pub static SERVICES: [Service; 4] = [
+    Service {
+        uuid: Uuid::Uuid16(0x1800), // Generic Access
+        characteristics: &[Characteristic {
+            uuid: Uuid::Uuid16(0x2A00), // Device Name
+            properties: READ,
+            id: ID_DEVICE_NAME,
+        }],
+    },
+    Service {
+        uuid: Uuid::Uuid128(ACCELEROMETER_SERVICE),
+        characteristics: &[Characteristic {
+            uuid: Uuid::Uuid128(microbit_uuid(0xCA4B)),
+            properties: READ | NOTIFY,
+            id: ID_ACCELERATION,
+        }],
+    },
+    Service {
+        uuid: Uuid::Uuid128(BUTTON_SERVICE),
+        characteristics: &[
+            Characteristic {
+                uuid: Uuid::Uuid128(microbit_uuid(0xDA90)),
+                properties: READ | NOTIFY,
+                id: ID_BUTTON_A,
+            },
+            Characteristic {
+                uuid: Uuid::Uuid128(microbit_uuid(0xDA91)),
+                properties: READ | NOTIFY,
+                id: ID_BUTTON_B,
+            },
+        ],
+    },
+    Service {
+        uuid: Uuid::Uuid128(LED_SERVICE),
+        characteristics: &[Characteristic {
+            uuid: Uuid::Uuid128(microbit_uuid(0x7B77)),
+            properties: READ | WRITE,
+            id: ID_LED_MATRIX,
+        }],
+    },
+];
+
+pub static TABLE: AttributeTable = AttributeTable::new(&SERVICES);
+
+/// Button state as reported by the button service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ButtonState {
+    #[default]
+    Released = 0,
+    Pressed = 1,
+    LongPress = 2,
+}
+
+/// A button held for this long becomes a long press
+pub const LONG_PRESS_MS: u32 = 1000;
+
+/// Turns periodic button samples into button service states
+#[derive(Debug, Default)]
+pub struct ButtonMonitor {
+    state: ButtonState,
+    held_ms: u32,
+}
+
+impl ButtonMonitor {
+    pub const fn new() -> Self {
+        Self {
+            state: ButtonState::Released,
+            held_ms: 0,
+        }
+    }
+
+    pub fn state(&self) -> ButtonState {
+        self.state
+    }
+
+    /// Feed a sample taken `elapsed_ms` after the previous one. Returns the new state when it changes.
+    pub fn update(&mut self, pressed: bool, elapsed_ms: u32) -> Option<ButtonState> {
+        let state = if !pressed {
+            self.held_ms = 0;
+            ButtonState::Released
+        } else {
+            if self.state != ButtonState::Released {
+                self.held_ms = self.held_ms.saturating_add(elapsed_ms);
+            }
+            if self.held_ms >= LONG_PRESS_MS {
+                ButtonState::LongPress
+            } else {
+                ButtonState::Pressed
+            }
+        };
+
+        if state == self.state {
+            return None;
+        }
+        self.state = state;
+        Some(state)
+    }
+}
+
+/// Acceleration in milli-g as the accelerometer data characteristic sends it
+pub fn encode_acceleration(x: i32, y: i32, z: i32) -> [u8; 6] {
+    let clamp = |mg: i32| (mg.clamp(i16::MIN.into(), i16::MAX.into()) as i16).to_le_bytes();
+    let (x, y, z) = (clamp(x), clamp(y), clamp(z));
+    [x[0], x[1], y[0], y[1], z[0], z[1]]
+}
+
+/// LED matrix as in `microbit::display`: `image[row][column]`, 1 = on
+pub type LedMatrix = [[u8; 5]; 5];
+
+/// LED matrix to the characteristic value: one byte per row, bit 4 is the left column
+pub fn encode_led_matrix(image: &LedMatrix) -> [u8; 5] {
+    image.map(|row| row.iter().fold(0, |bits, &led| (bits << 1) | u8::from(led != 0)))
+}
+
+/// Characteristic value to LED matrix. Exactly 5 bytes are required, the top 3 bits of each are ignored.
+pub fn decode_led_matrix(value: &[u8]) -> Result<LedMatrix, AttError> {
+    let rows: &[u8; 5] = value.try_into().map_err(|_| AttError::InvalidAttributeValueLength)?;
+    Ok(rows.map(|bits| core::array::from_fn(|column| (bits >> (4 - column)) & 1)))
+}
+
+/// Current sensor readings and LED matrix, shared between the application and the GATT server
+#[derive(Debug, Default)]
+pub struct SensorValues {
+    pub acceleration: (i32, i32, i32),
+    pub button_a: ButtonState,
+    pub button_b: ButtonState,
+    pub leds: LedMatrix,
+    /// Set when a client wrote the LED matrix, cleared by [`take_led_update`](Self::take_led_update)
+    leds_written: bool,
+}
+
+impl SensorValues {
+    pub const fn new() -> Self {
+        Self {
+            acceleration: (0, 0, 0),
+            button_a: ButtonState::Released,
+            button_b: ButtonState::Released,
+            leds: [[0; 5]; 5],
+            leds_written: false,
+        }
+    }
+
+    /// The LED matrix, if a client has written it since the last call
+    pub fn take_led_update(&mut self) -> Option<LedMatrix> {
+        core::mem::take(&mut self.leds_written).then_some(self.leds)
+    }
+}
+
+impl Values for SensorValues {
+    fn read(&mut self, id: u8, out: &mut [u8]) -> usize {
+        match id {
+            ID_DEVICE_NAME => {
+                out[..DEVICE_NAME.len()].copy_from_slice(DEVICE_NAME.as_bytes());
+                DEVICE_NAME.len()
+            }
+            ID_ACCELERATION => {
+                let (x, y, z) = self.acceleration;
+                out[..6].copy_from_slice(&encode_acceleration(x, y, z));
+                6
+            }
+            ID_BUTTON_A => {
+                out[0] = self.button_a as u8;
+                1
+            }
+            ID_BUTTON_B => {
+                out[0] = self.button_b as u8;
+                1
+            }
+            ID_LED_MATRIX => {
+                out[..5].copy_from_slice(&encode_led_matrix(&self.leds));
+                5
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, id: u8, value: &[u8]) -> Result<(), AttError> {
+        match id {
+            ID_LED_MATRIX => {
+                self.leds = decode_led_matrix(value)?;
+                self.leds_written = true;
+                Ok(())
+            }
+            _ => Err(AttError::WriteNotPermitted),
+        }
+    }
+}
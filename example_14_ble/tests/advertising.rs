@@ -0,0 +1,150 @@
+//! Host tests for advertising packets
+
+use example_14_ble::advertising::{
+    self, channel_frequency_mhz, AdvertisingData, DeviceAddress, PduError, PduType, TooLong, MAX_ADV_PDU_LEN,
+};
+
+const US: DeviceAddress = DeviceAddress {
+    bytes: [0x11, 0x22, 0x33, 0x44, 0x55, 0xC6],
+    random: true,
+};
+
+/// A CONNECT_IND as a phone sends it: 30 ms interval, all channels, hop 7, 5 s timeout
+fn connect_ind() -> [u8; 36] {
+    let mut pdu = [0; 36];
+    pdu[0] = 0x05 | 0x40 | 0x80; // CONNECT_IND, TxAdd random, RxAdd random
+    pdu[1] = 34;
+    pdu[2..8].copy_from_slice(&[1, 2, 3, 4, 5, 6]); // InitA
+    pdu[8..14].copy_from_slice(&US.bytes); // AdvA
+    pdu[14..18].copy_from_slice(&0x5065_A21Bu32.to_le_bytes()); // Access address
+    pdu[18..21].copy_from_slice(&[0x12, 0x34, 0x56]); // CRC init
+    pdu[21] = 3; // WinSize
+    pdu[22..24].copy_from_slice(&2u16.to_le_bytes()); // WinOffset
+    pdu[24..26].copy_from_slice(&24u16.to_le_bytes()); // Interval
+    pdu[26..28].copy_from_slice(&0u16.to_le_bytes()); // Latency
+    pdu[28..30].copy_from_slice(&500u16.to_le_bytes()); // Timeout
+    pdu[30..35].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]); // Channel map
+    pdu[35] = 7 | (5 << 5); // Hop 7, SCA 5 (50 ppm)
+    pdu
+}
+
+#[test]
+fn channel_frequencies() {
+    assert_eq!(channel_frequency_mhz(37), 2402);
+    assert_eq!(channel_frequency_mhz(38), 2426);
+    assert_eq!(channel_frequency_mhz(39), 2480);
+    assert_eq!(channel_frequency_mhz(0), 2404);
+    assert_eq!(channel_frequency_mhz(10), 2424);
+    assert_eq!(channel_frequency_mhz(11), 2428);
+    assert_eq!(channel_frequency_mhz(36), 2478);
+}
+
+#[test]
+fn random_static_address_sets_top_bits() {
+    let address = DeviceAddress::random_static(0x4433_2211, 0x1234_0655);
+    assert_eq!(address.bytes, [0x11, 0x22, 0x33, 0x44, 0x55, 0xC6]);
+    assert!(address.random);
+}
+
+#[test]
+fn advertising_data_structures() {
+    let data = AdvertisingData::new()
+        .flags(advertising::FLAGS_LE_ONLY_GENERAL)
+        .unwrap()
+        .complete_local_name("bit")
+        .unwrap()
+        .manufacturer_data(0xFFFF, &[0xAB])
+        .unwrap();
+    assert_eq!(
+        data.as_bytes(),
+        [2, 0x01, 0x06, 4, 0x09, b'b', b'i', b't', 4, 0xFF, 0xFF, 0xFF, 0xAB]
+    );
+}
+
+#[test]
+fn advertising_data_is_limited_to_31_bytes() {
+    let data = AdvertisingData::new().flags(0x06).unwrap(); // 3 bytes
+    let data = data
+        .service_uuid_128(0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10)
+        .unwrap(); // 18 bytes
+    assert_eq!(data.as_bytes()[5], 0x10); // UUIDs are sent little endian
+    assert_eq!(
+        data.complete_local_name("12345678"),
+        Ok(data.complete_local_name("12345678").unwrap())
+    );
+    assert_eq!(data.complete_local_name("123456789"), Err(TooLong));
+}
+
+#[test]
+fn adv_ind_layout() {
+    let data = AdvertisingData::new().flags(0x06).unwrap();
+    let mut pdu = [0; MAX_ADV_PDU_LEN];
+    let len = advertising::encode_pdu(PduType::AdvInd, &US, data.as_bytes(), &mut pdu);
+    assert_eq!(len, 11);
+    assert_eq!(pdu[..len], [0x40, 9, 0x11, 0x22, 0x33, 0x44, 0x55, 0xC6, 2, 0x01, 0x06]);
+}
+
+#[test]
+fn connect_ind_is_parsed() {
+    let request = advertising::decode_connect_ind(&connect_ind(), &US).unwrap();
+    assert_eq!(request.initiator.bytes, [1, 2, 3, 4, 5, 6]);
+    assert!(request.initiator.random);
+    assert_eq!(request.access_address, 0x5065_A21B);
+    assert_eq!(request.crc_init, 0x56_3412);
+    assert_eq!((request.win_size, request.win_offset), (3, 2));
+    assert_eq!((request.interval, request.latency, request.timeout), (24, 0, 500));
+    assert_eq!(request.channel_map, [0xFF, 0xFF, 0xFF, 0xFF, 0x1F]);
+    assert_eq!((request.hop, request.sca), (7, 5));
+}
+
+#[test]
+fn connect_ind_for_someone_else_is_ignored() {
+    let mut pdu = connect_ind();
+    pdu[8] ^= 1;
+    assert_eq!(advertising::decode_connect_ind(&pdu, &US), Err(PduError::NotForUs));
+
+    let mut pdu = connect_ind();
+    pdu[0] &= !0x80; // Addressed to a public address with the same bytes
+    assert_eq!(advertising::decode_connect_ind(&pdu, &US), Err(PduError::NotForUs));
+}
+
+#[test]
+fn bad_connect_ind_is_rejected() {
+    let pdu = connect_ind();
+    assert_eq!(
+        advertising::decode_connect_ind(&pdu[..20], &US),
+        Err(PduError::Truncated)
+    );
+
+    let mut scan_req = pdu;
+    scan_req[0] = 0x03 | 0xC0;
+    assert_eq!(
+        advertising::decode_connect_ind(&scan_req, &US),
+        Err(PduError::Unexpected)
+    );
+
+    for (index, value) in [(35, 4), (21, 0), (24, 5), (30, 0)] {
+        let mut pdu = connect_ind();
+        pdu[index] = value;
+        if index == 30 {
+            pdu[31..35].fill(0); // Only one channel left
+            pdu[30] = 1;
+        }
+        assert_eq!(
+            advertising::decode_connect_ind(&pdu, &US),
+            Err(PduError::InvalidParameters),
+            "byte {index}"
+        );
+    }
+}
+
+#[test]
+fn advertising_delay_stays_below_10_ms() {
+    let mut seed = 1;
+    let delays: Vec<u32> = (0..1000)
+        .map(|_| advertising::advertising_delay_us(&mut seed))
+        .collect();
+    assert!(delays.iter().all(|&delay| delay <= 10_000));
+    assert!(delays.iter().any(|&delay| delay < 1_000));
+    assert!(delays.iter().any(|&delay| delay > 9_000));
+}
@@ -0,0 +1,232 @@
+//! Host tests for the ATT server with the micro:bit profile, sending requests the way a phone does
+
+use example_14_ble::{
+    gatt::{AttServer, ATT_MTU},
+    profile::{self, ButtonMonitor, ButtonState, SensorValues, ID_ACCELERATION, ID_BUTTON_A, ID_LED_MATRIX, TABLE},
+};
+
+/// micro:bit profile UUID as it appears on the air (little endian)
+fn uuid(short: u16) -> Vec<u8> {
+    profile::microbit_uuid(short).to_le_bytes().to_vec()
+}
+
+fn request(server: &mut AttServer, values: &mut SensorValues, pdu: &[u8]) -> Option<Vec<u8>> {
+    let mut response = [0; ATT_MTU];
+    server
+        .handle(pdu, values, &mut response)
+        .map(|len| response[..len].to_vec())
+}
+
+fn setup() -> (AttServer, SensorValues) {
+    (AttServer::new(TABLE), SensorValues::new())
+}
+
+#[test]
+fn uuids_match_the_microbit_profile() {
+    let accelerometer_service = profile::ACCELEROMETER_SERVICE.to_be_bytes();
+    assert_eq!(
+        accelerometer_service,
+        [0xE9, 0x5D, 0x07, 0x53, 0x25, 0x1D, 0x47, 0x0A, 0xA0, 0x62, 0xFA, 0x19, 0x22, 0xDF, 0xA9, 0xA8]
+    );
+}
+
+#[test]
+fn handles_are_numbered_in_order() {
+    assert_eq!(TABLE.value_handle(profile::ID_DEVICE_NAME), Some(3));
+    assert_eq!(TABLE.value_handle(ID_ACCELERATION), Some(6));
+    assert_eq!(TABLE.value_handle(ID_BUTTON_A), Some(10));
+    assert_eq!(TABLE.value_handle(profile::ID_BUTTON_B), Some(13));
+    assert_eq!(TABLE.value_handle(ID_LED_MATRIX), Some(17));
+    assert_eq!(TABLE.iter().count(), 17);
+}
+
+#[test]
+fn exchange_mtu_keeps_23() {
+    let (mut server, mut values) = setup();
+    assert_eq!(
+        request(&mut server, &mut values, &[0x02, 0x00, 0x02]),
+        Some(vec![0x03, 23, 0])
+    );
+}
+
+#[test]
+fn primary_service_discovery() {
+    let (mut server, mut values) = setup();
+
+    // Read By Group Type, 0x0001-0xFFFF, primary service: the 16 bit GAP service first
+    let response = request(&mut server, &mut values, &[0x10, 0x01, 0x00, 0xFF, 0xFF, 0x00, 0x28]).unwrap();
+    assert_eq!(response, [0x11, 6, 0x01, 0x00, 0x03, 0x00, 0x00, 0x18]);
+
+    // Continue after handle 3: 128 bit services, one per response (20 bytes each)
+    let response = request(&mut server, &mut values, &[0x10, 0x04, 0x00, 0xFF, 0xFF, 0x00, 0x28]).unwrap();
+    let mut expected = vec![0x11, 20, 0x04, 0x00, 0x07, 0x00];
+    expected.extend(uuid(0x0753));
+    assert_eq!(response, expected);
+
+    let response = request(&mut server, &mut values, &[0x10, 0x08, 0x00, 0xFF, 0xFF, 0x00, 0x28]).unwrap();
+    assert_eq!(response[2..6], [0x08, 0x00, 0x0E, 0x00]);
+    let response = request(&mut server, &mut values, &[0x10, 0x0F, 0x00, 0xFF, 0xFF, 0x00, 0x28]).unwrap();
+    assert_eq!(response[2..6], [0x0F, 0x00, 0x11, 0x00]);
+
+    // Past the last service: Attribute Not Found
+    let response = request(&mut server, &mut values, &[0x10, 0x12, 0x00, 0xFF, 0xFF, 0x00, 0x28]).unwrap();
+    assert_eq!(response, [0x01, 0x10, 0x12, 0x00, 0x0A]);
+}
+
+#[test]
+fn find_service_by_uuid() {
+    let (mut server, mut values) = setup();
+    let mut pdu = vec![0x06, 0x01, 0x00, 0xFF, 0xFF, 0x00, 0x28];
+    pdu.extend(uuid(0x9882));
+    let response = request(&mut server, &mut values, &pdu).unwrap();
+    assert_eq!(response, [0x07, 0x08, 0x00, 0x0E, 0x00]);
+}
+
+#[test]
+fn characteristic_discovery() {
+    let (mut server, mut values) = setup();
+
+    // Read By Type 0x2803 in the button service: declarations of A and B (21 bytes each, one per response)
+    let response = request(&mut server, &mut values, &[0x08, 0x08, 0x00, 0x0E, 0x00, 0x03, 0x28]).unwrap();
+    let mut expected = vec![0x09, 21, 0x09, 0x00, 0x12, 0x0A, 0x00];
+    expected.extend(uuid(0xDA90));
+    assert_eq!(response, expected);
+
+    let response = request(&mut server, &mut values, &[0x08, 0x0A, 0x00, 0x0E, 0x00, 0x03, 0x28]).unwrap();
+    assert_eq!(response[2..7], [0x0C, 0x00, 0x12, 0x0D, 0x00]);
+}
+
+#[test]
+fn descriptor_discovery() {
+    let (mut server, mut values) = setup();
+    // Find Information after the accelerometer value: its CCCD
+    let response = request(&mut server, &mut values, &[0x04, 0x07, 0x00, 0x07, 0x00]).unwrap();
+    assert_eq!(response, [0x05, 0x01, 0x07, 0x00, 0x02, 0x29]);
+
+    // 16 and 128 bit UUIDs are never mixed in one response
+    let response = request(&mut server, &mut values, &[0x04, 0x05, 0x00, 0x07, 0x00]).unwrap();
+    assert_eq!(response, [0x05, 0x01, 0x05, 0x00, 0x03, 0x28]);
+}
+
+#[test]
+fn read_values() {
+    let (mut server, mut values) = setup();
+    values.acceleration = (-1000, 250, 70000);
+    values.button_a = ButtonState::LongPress;
+
+    let response = request(&mut server, &mut values, &[0x0A, 0x06, 0x00]).unwrap();
+    assert_eq!(response, [0x0B, 0x18, 0xFC, 0xFA, 0x00, 0xFF, 0x7F]);
+    let response = request(&mut server, &mut values, &[0x0A, 0x0A, 0x00]).unwrap();
+    assert_eq!(response, [0x0B, 2]);
+    let response = request(&mut server, &mut values, &[0x0A, 0x03, 0x00]).unwrap();
+    assert_eq!(&response[1..], b"micro:bit");
+
+    // Blob read from offset 4
+    let response = request(&mut server, &mut values, &[0x0C, 0x03, 0x00, 0x04, 0x00]).unwrap();
+    assert_eq!(&response[1..], b"o:bit");
+
+    let response = request(&mut server, &mut values, &[0x0A, 0x63, 0x00]).unwrap();
+    assert_eq!(response, [0x01, 0x0A, 0x63, 0x00, 0x01]);
+}
+
+#[test]
+fn write_led_matrix() {
+    let (mut server, mut values) = setup();
+    // A cross: rows 10001, 01010, 00100, 01010, 10001
+    let response = request(
+        &mut server,
+        &mut values,
+        &[0x12, 0x11, 0x00, 0x11, 0x0A, 0x04, 0x0A, 0x11],
+    );
+    assert_eq!(response, Some(vec![0x13]));
+
+    let leds = values.take_led_update().unwrap();
+    assert_eq!(leds[0], [1, 0, 0, 0, 1]);
+    assert_eq!(leds[2], [0, 0, 1, 0, 0]);
+    assert_eq!(values.take_led_update(), None);
+
+    let response = request(&mut server, &mut values, &[0x0A, 0x11, 0x00]).unwrap();
+    assert_eq!(response, [0x0B, 0x11, 0x0A, 0x04, 0x0A, 0x11]);
+
+    // Wrong length, and read-only characteristics
+    let response = request(&mut server, &mut values, &[0x12, 0x11, 0x00, 0x1F]).unwrap();
+    assert_eq!(response, [0x01, 0x12, 0x11, 0x00, 0x0D]);
+    let response = request(&mut server, &mut values, &[0x12, 0x06, 0x00, 0x00]).unwrap();
+    assert_eq!(response, [0x01, 0x12, 0x06, 0x00, 0x03]);
+    // Write commands never get a response, not even an error
+    assert_eq!(request(&mut server, &mut values, &[0x52, 0x11, 0x00, 0x1F]), None);
+}
+
+#[test]
+fn notifications_need_cccd() {
+    let (mut server, mut values) = setup();
+    values.button_a = ButtonState::Pressed;
+    let mut pdu = [0; ATT_MTU];
+    assert_eq!(server.notification(ID_BUTTON_A, &mut values, &mut pdu), None);
+
+    // Enable notifications on button A's CCCD (handle 11)
+    assert_eq!(
+        request(&mut server, &mut values, &[0x12, 0x0B, 0x00, 0x01, 0x00]),
+        Some(vec![0x13])
+    );
+    assert_eq!(
+        request(&mut server, &mut values, &[0x0A, 0x0B, 0x00]),
+        Some(vec![0x0B, 0x01, 0x00])
+    );
+    let len = server.notification(ID_BUTTON_A, &mut values, &mut pdu).unwrap();
+    assert_eq!(pdu[..len], [0x1B, 0x0A, 0x00, 1]);
+    // Only for that characteristic
+    assert_eq!(server.notification(ID_ACCELERATION, &mut values, &mut pdu), None);
+    // LED matrix cannot notify at all
+    assert_eq!(server.notification(ID_LED_MATRIX, &mut values, &mut pdu), None);
+
+    server.reset();
+    assert_eq!(server.notification(ID_BUTTON_A, &mut values, &mut pdu), None);
+}
+
+#[test]
+fn unsupported_requests() {
+    let (mut server, mut values) = setup();
+    // Prepare Write Request: not supported
+    assert_eq!(
+        request(&mut server, &mut values, &[0x16, 0x11, 0x00, 0x00, 0x00]),
+        Some(vec![0x01, 0x16, 0x00, 0x00, 0x06])
+    );
+    // Truncated request
+    assert_eq!(
+        request(&mut server, &mut values, &[0x0A, 0x11]),
+        Some(vec![0x01, 0x0A, 0x00, 0x00, 0x04])
+    );
+    // Invalid handle range
+    assert_eq!(
+        request(&mut server, &mut values, &[0x04, 0x05, 0x00, 0x01, 0x00]),
+        Some(vec![0x01, 0x04, 0x05, 0x00, 0x01])
+    );
+}
+
+#[test]
+fn led_matrix_encoding_round_trips() {
+    let image = [[1, 1, 0, 0, 0], [0; 5], [0, 0, 0, 0, 1], [1; 5], [0, 1, 0, 1, 0]];
+    let bytes = profile::encode_led_matrix(&image);
+    assert_eq!(bytes, [0b11000, 0, 0b00001, 0b11111, 0b01010]);
+    assert_eq!(profile::decode_led_matrix(&bytes), Ok(image));
+    // Bits above bit 4 are ignored
+    assert_eq!(
+        profile::decode_led_matrix(&[0xE0 | 0b11000, 0, 1, 0x1F, 0x0A]),
+        Ok(image)
+    );
+}
+
+#[test]
+fn button_long_press() {
+    let mut button = ButtonMonitor::new();
+    assert_eq!(button.update(false, 20), None);
+    assert_eq!(button.update(true, 20), Some(ButtonState::Pressed));
+    for _ in 0..49 {
+        assert_eq!(button.update(true, 20), None);
+    }
+    assert_eq!(button.update(true, 20), Some(ButtonState::LongPress));
+    assert_eq!(button.update(true, 20), None);
+    assert_eq!(button.update(false, 20), Some(ButtonState::Released));
+    assert_eq!(button.update(true, 20), Some(ButtonState::Pressed));
+}
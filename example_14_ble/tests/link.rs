@@ -0,0 +1,403 @@
+//! Host tests for the link layer: the central's side of a connection is played by the test
+
+use example_14_ble::{
+    advertising::{ConnectInd, DeviceAddress},
+    gatt::AttServer,
+    link::{ChannelMap, Closed, Connection, Received, TRANSMIT_WINDOW_DELAY_US},
+    profile::{SensorValues, TABLE},
+};
+
+fn connect_ind() -> ConnectInd {
+    ConnectInd {
+        initiator: DeviceAddress {
+            bytes: [1, 2, 3, 4, 5, 6],
+            random: true,
+        },
+        access_address: 0x5065_A21B,
+        crc_init: 0x56_3412,
+        win_size: 2,
+        win_offset: 4,
+        interval: 24, // 30 ms
+        latency: 0,
+        timeout: 100, // 1 s
+        channel_map: [0xFF, 0xFF, 0xFF, 0xFF, 0x1F],
+        hop: 7,
+        sca: 0, // 500 ppm
+    }
+}
+
+/// The central's side: tracks SN/NESN and builds data PDUs
+struct Central {
+    sn: bool,
+    nesn: bool,
+}
+
+impl Central {
+    fn new() -> Self {
+        Self { sn: false, nesn: false }
+    }
+
+    fn pdu(&self, llid: u8, payload: &[u8]) -> Vec<u8> {
+        let header = llid | (u8::from(self.nesn) << 2) | (u8::from(self.sn) << 3);
+        let mut pdu = vec![header, payload.len() as u8];
+        pdu.extend_from_slice(payload);
+        pdu
+    }
+
+    /// Send one PDU, expecting it to be acknowledged. Returns the peripheral's answer.
+    fn exchange(&mut self, connection: &mut Connection, llid: u8, payload: &[u8]) -> (Received, Vec<u8>) {
+        let pdu = self.pdu(llid, payload);
+        let (received, response) = connection.respond(&pdu, true);
+        let response = response.to_vec();
+        self.accept(&response);
+        (received, response)
+    }
+
+    /// Take the peripheral's PDU: it acknowledges ours if its NESN moved on, and is new if its SN is the one we expect
+    fn accept(&mut self, response: &[u8]) {
+        let acked = (response[0] & 0x04 != 0) != self.sn;
+        if acked {
+            self.sn = !self.sn;
+        }
+        if (response[0] & 0x08 != 0) == self.nesn {
+            self.nesn = !self.nesn;
+        }
+    }
+}
+
+/// Send a request and poll with empty PDUs until the peripheral answers with a non-empty PDU
+fn request(connection: &mut Connection, central: &mut Central, llid: u8, payload: &[u8]) -> Vec<u8> {
+    let mut server = AttServer::new(TABLE);
+    let mut values = SensorValues::new();
+    let (received, _) = central.exchange(connection, llid, payload);
+    assert_eq!(received, Received::New);
+    connection.process(&mut server, &mut values).unwrap();
+    let (_, response) = central.exchange(connection, 1, &[]);
+    response
+}
+
+#[test]
+fn channel_selection_algorithm_1() {
+    // All channels used: hop through them in steps of 7
+    let mut connection = Connection::new(&connect_ind());
+    let mut channels = vec![connection.first_event().channel];
+    for _ in 0..4 {
+        channels.push(connection.next_event(true).unwrap().channel);
+    }
+    assert_eq!(channels, [7, 14, 21, 28, 35]);
+
+    // Channels 0-8 only: unused channels are remapped to index % 9
+    let map = ChannelMap::new([0xFF, 0x01, 0, 0, 0]);
+    assert_eq!(map.remap(3), 3);
+    assert_eq!(map.remap(14), 5);
+    assert_eq!(map.remap(36), 0);
+}
+
+#[test]
+fn first_event_uses_the_transmit_window() {
+    let mut connection = Connection::new(&connect_ind());
+    let first = connection.first_event();
+    assert_eq!(first.offset_us, TRANSMIT_WINDOW_DELAY_US + 4 * 1250);
+    assert_eq!(first.window_us, 2 * 1250);
+    // 550 ppm over 8.75 ms, plus 16 µs
+    assert_eq!(first.widening_us, 5 + 16);
+
+    // Missed: the window moves on by one interval
+    let second = connection.next_event(false).unwrap();
+    assert_eq!(second.offset_us, first.offset_us + 30_000);
+    assert_eq!(second.window_us, first.window_us);
+
+    // Received: from now on relative to the anchor, without a window
+    let third = connection.next_event(true).unwrap();
+    assert_eq!((third.offset_us, third.window_us), (30_000, 0));
+    assert_eq!(third.widening_us, 17 + 16);
+}
+
+#[test]
+fn connection_must_be_established_within_6_events() {
+    let mut connection = Connection::new(&connect_ind());
+    connection.first_event();
+    for _ in 0..5 {
+        connection.next_event(false).unwrap();
+    }
+    assert_eq!(connection.next_event(false), Err(Closed::Timeout));
+}
+
+#[test]
+fn supervision_timeout() {
+    let mut connection = Connection::new(&connect_ind());
+    connection.first_event();
+    connection.next_event(true).unwrap();
+    // 1 s timeout at 30 ms: the 34th missed event is too late
+    for _ in 0..32 {
+        connection.next_event(false).unwrap();
+    }
+    assert_eq!(connection.next_event(false), Err(Closed::Timeout));
+}
+
+#[test]
+fn acknowledgement_and_retransmission() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+
+    // Empty PDU from the central: acknowledged with an empty PDU
+    let (received, response) = central.exchange(&mut connection, 1, &[]);
+    assert_eq!(received, Received::Nothing);
+    assert_eq!(response, [0x01 | 0x04, 0]); // NESN = 1
+
+    // Same packet again (e.g. our answer was lost): not new
+    central.sn = false;
+    central.nesn = false;
+    let (received, _) = connection.respond(&central.pdu(1, &[]), true);
+    assert_eq!(received, Received::Nothing);
+
+    // Corrupted packets are not acknowledged
+    let mut connection = Connection::new(&connect_ind());
+    let (received, response) = connection.respond(&[0x03, 1, 0x12], false);
+    assert_eq!(received, Received::Nothing);
+    assert_eq!(response[0] & 0x04, 0);
+}
+
+#[test]
+fn answers_are_repeated_until_acknowledged() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+    let mut server = AttServer::new(TABLE);
+    let mut values = SensorValues::new();
+
+    // LL_PING_REQ
+    central.exchange(&mut connection, 3, &[0x12]);
+    connection.process(&mut server, &mut values).unwrap();
+
+    // The central's next packet acknowledges our empty PDU, so the ping response comes next
+    let (_, response) = central.exchange(&mut connection, 1, &[]);
+    assert_eq!(response[2..], [0x13]);
+    let sn = response[0] & 0x08;
+
+    // The central did not get it (NESN unchanged): the same PDU is sent again
+    central.nesn = !central.nesn;
+    let (_, again) = central.exchange(&mut connection, 1, &[]);
+    assert_eq!(again[2..], [0x13]);
+    assert_eq!(again[0] & 0x08, sn);
+
+    // Acknowledged: back to empty PDUs
+    let (_, response) = central.exchange(&mut connection, 1, &[]);
+    assert_eq!(response[1], 0);
+}
+
+#[test]
+fn ll_control_procedures() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+
+    let response = request(&mut connection, &mut central, 3, &[0x0C, 0x09, 0x0F, 0x00, 0x01, 0x00]);
+    assert_eq!(response[0] & 3, 3);
+    assert_eq!(response[2..], [0x0C, 0x08, 0xFF, 0xFF, 0, 0]);
+
+    let response = request(&mut connection, &mut central, 3, &[0x08, 0xFF, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(response[2..], [0x09, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let response = request(
+        &mut connection,
+        &mut central,
+        3,
+        &[0x14, 251, 0, 0x48, 0x08, 251, 0, 0x48, 0x08],
+    );
+    assert_eq!(response[2..], [0x15, 27, 0, 0x48, 0x01, 27, 0, 0x48, 0x01]);
+
+    // LL_PHY_REQ is unknown to us
+    let response = request(&mut connection, &mut central, 3, &[0x16, 1, 1]);
+    assert_eq!(response[2..], [0x07, 0x16]);
+}
+
+#[test]
+fn terminate() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+    let mut server = AttServer::new(TABLE);
+    let mut values = SensorValues::new();
+
+    central.exchange(&mut connection, 3, &[0x02, 0x13]);
+    assert_eq!(
+        connection.process(&mut server, &mut values),
+        Err(Closed::Terminated(0x13))
+    );
+}
+
+#[test]
+fn connection_update_at_instant() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+    let mut server = AttServer::new(TABLE);
+    let mut values = SensorValues::new();
+    connection.first_event();
+
+    // At event 3: 45 ms interval (36 units), window offset 2 (2.5 ms), window size 1, 2 s timeout
+    let mut update = vec![0x00, 1];
+    for value in [2u16, 36, 0, 200, 3] {
+        update.extend_from_slice(&value.to_le_bytes());
+    }
+    central.exchange(&mut connection, 3, &update);
+    connection.process(&mut server, &mut values).unwrap();
+
+    assert_eq!(connection.next_event(true).unwrap().offset_us, 30_000); // Event 1
+    assert_eq!(connection.next_event(true).unwrap().offset_us, 30_000); // Event 2
+    let instant = connection.next_event(true).unwrap(); // Event 3
+    assert_eq!(connection.event_counter(), 3);
+    assert_eq!((instant.offset_us, instant.window_us), (30_000 + 2_500, 1_250));
+    assert_eq!(connection.next_event(true).unwrap().offset_us, 45_000);
+}
+
+#[test]
+fn invalid_connection_update_closes_the_connection() {
+    // Window size, window offset, interval, timeout: interval 0, then each field out of range
+    for [win_size, win_offset, interval, timeout] in [
+        [1u16, 0, 0, 200],
+        [1, 0, 3201, 200],
+        [0, 0, 36, 200],
+        [9, 0, 36, 200],
+        [1, 37, 36, 200],
+        [1, 0, 36, 9],
+        [1, 0, 36, 3201],
+    ] {
+        let mut connection = Connection::new(&connect_ind());
+        let mut central = Central::new();
+        let mut server = AttServer::new(TABLE);
+        let mut values = SensorValues::new();
+        connection.first_event();
+
+        let mut update = vec![0x00, win_size as u8];
+        for value in [win_offset, interval, 0, timeout, 3] {
+            update.extend_from_slice(&value.to_le_bytes());
+        }
+        central.exchange(&mut connection, 3, &update);
+        assert_eq!(
+            connection.process(&mut server, &mut values),
+            Err(Closed::ProtocolError),
+            "{:?}",
+            [win_size, win_offset, interval, timeout]
+        );
+    }
+}
+
+#[test]
+fn instant_in_the_past_closes_the_connection() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+    let mut server = AttServer::new(TABLE);
+    let mut values = SensorValues::new();
+    connection.first_event();
+    for _ in 0..10 {
+        connection.next_event(true).unwrap();
+    }
+
+    central.exchange(&mut connection, 3, &[0x01, 0xFF, 0, 0, 0, 0, 5, 0]); // Channel map, instant 5
+    assert_eq!(connection.process(&mut server, &mut values), Err(Closed::ProtocolError));
+}
+
+#[test]
+fn channel_map_update_at_instant() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+    let mut server = AttServer::new(TABLE);
+    let mut values = SensorValues::new();
+    connection.first_event(); // Channel 7
+
+    // From event 2 on, only channels 0-7
+    central.exchange(&mut connection, 3, &[0x01, 0xFF, 0, 0, 0, 0, 2, 0]);
+    connection.process(&mut server, &mut values).unwrap();
+    assert_eq!(connection.next_event(true).unwrap().channel, 14);
+    // Unmapped 21 is unused: 21 % 8 = 5
+    assert_eq!(connection.next_event(true).unwrap().channel, 5);
+}
+
+#[test]
+fn invalid_channel_map_closes_the_connection() {
+    // No channels at all, then a single one, then data channels plus 37-39 (advertising channels)
+    for map in [[0, 0, 0, 0, 0], [0x01, 0, 0, 0, 0], [0xFF, 0xFF, 0xFF, 0xFF, 0xFF]] {
+        let mut connection = Connection::new(&connect_ind());
+        let mut central = Central::new();
+        let mut server = AttServer::new(TABLE);
+        let mut values = SensorValues::new();
+        connection.first_event();
+
+        let mut payload = vec![0x01];
+        payload.extend_from_slice(&map);
+        payload.extend_from_slice(&[2, 0]);
+        central.exchange(&mut connection, 3, &payload);
+        assert_eq!(
+            connection.process(&mut server, &mut values),
+            Err(Closed::ProtocolError),
+            "{:02X?}",
+            map
+        );
+    }
+    assert!(ChannelMap::valid(&[0x03, 0, 0, 0, 0]));
+}
+
+#[test]
+fn att_over_l2cap() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+
+    // L2CAP length 3, CID 4, ATT Read Request for handle 3 (device name)
+    let response = request(&mut connection, &mut central, 2, &[3, 0, 4, 0, 0x0A, 0x03, 0x00]);
+    assert_eq!(response[0] & 3, 2);
+    assert_eq!(response[2..6], [10, 0, 4, 0]);
+    assert_eq!(response[6], 0x0B);
+    assert_eq!(&response[7..], b"micro:bit");
+}
+
+#[test]
+fn pairing_and_signalling_are_rejected() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+
+    // SMP Pairing Request
+    let response = request(
+        &mut connection,
+        &mut central,
+        2,
+        &[7, 0, 6, 0, 0x01, 0x03, 0, 0x01, 0x10, 0x07, 0x07],
+    );
+    assert_eq!(response[2..], [2, 0, 6, 0, 0x05, 0x05]);
+
+    // Connection Parameter Update Request on the signalling channel
+    let request_pdu = [12, 0, 5, 0, 0x12, 0x07, 8, 0, 6, 0, 12, 0, 0, 0, 0x90, 0x01];
+    let response = request(&mut connection, &mut central, 2, &request_pdu);
+    assert_eq!(response[2..], [6, 0, 5, 0, 0x01, 0x07, 2, 0, 0, 0]);
+}
+
+#[test]
+fn notifications_are_queued() {
+    let mut connection = Connection::new(&connect_ind());
+    let mut central = Central::new();
+
+    assert!(connection.send_att(&[0x1B, 0x06, 0x00, 1, 2, 3, 4, 5, 6]));
+    // Sent as soon as the central has acknowledged the current (empty) PDU
+    central.exchange(&mut connection, 1, &[]);
+    let (_, response) = central.exchange(&mut connection, 1, &[]);
+    assert_eq!(response[2..], [9, 0, 4, 0, 0x1B, 0x06, 0x00, 1, 2, 3, 4, 5, 6]);
+
+    // The queue holds 4 PDUs
+    for _ in 0..4 {
+        assert!(connection.send_att(&[0x1B, 0x06, 0x00]));
+    }
+    assert!(!connection.can_send());
+    assert!(!connection.send_att(&[0x1B, 0x06, 0x00]));
+}
+
+#[test]
+fn full_queue_holds_back_acknowledgement() {
+    let mut connection = Connection::new(&connect_ind());
+    let central = Central::new();
+    for _ in 0..4 {
+        connection.send_att(&[0x1B, 0x06, 0x00]);
+    }
+
+    // No room for an answer: the request is not acknowledged and will be repeated by the central
+    let pdu = central.pdu(3, &[0x12]);
+    let (received, response) = connection.respond(&pdu, true);
+    assert_eq!(received, Received::Nothing);
+    assert_eq!(response[0] & 0x04, 0);
+}
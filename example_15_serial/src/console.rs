@@ -0,0 +1,142 @@
+//! A line-based command console that works over any `core::fmt::Write`: the serial port here, RTT just as well.
+//!
+//! [`LineEditor`] collects typed characters into a line, echoing them and handling backspace, the way a terminal
+//! expects from a device in "raw" mode. [`Command::parse`] turns a finished line into a command.
+
+use core::fmt::{self, Write};
+
+/// Longest command line
+pub const MAX_LINE: usize = 64;
+
+pub const PROMPT: &str = "> ";
+
+pub const HELP: &str = "Commands:
+  help          this text
+  led on|off    switch the top-left LED
+  stats         bytes received and overruns
+  uptime        time since reset
+  echo <text>   print <text>";
+
+/// Collects a line of input, see the module documentation
+#[derive(Debug)]
+pub struct LineEditor {
+    line: [u8; MAX_LINE],
+    len: usize,
+    /// The previous byte ended a line, so the line is reset before the next one
+    complete: bool,
+    /// The previous byte was CR, so a following LF is part of the same line ending
+    after_cr: bool,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineEditor {
+    pub const fn new() -> Self {
+        Self {
+            line: [0; MAX_LINE],
+            len: 0,
+            complete: false,
+            after_cr: false,
+        }
+    }
+
+    /// Handle one received byte, writing the echo to `echo`. Returns the line once Enter (CR, LF or CR LF) is
+    /// pressed. Characters beyond [`MAX_LINE`] and control characters are dropped, and so is anything that is not
+    /// ASCII.
+    pub fn feed(&mut self, byte: u8, echo: &mut impl Write) -> Result<Option<&str>, fmt::Error> {
+        if self.complete {
+            self.complete = false;
+            self.len = 0;
+        }
+        let after_cr = core::mem::replace(&mut self.after_cr, byte == b'\r');
+
+        match byte {
+            b'\n' if after_cr => {}
+            b'\r' | b'\n' => {
+                echo.write_str("\r\n")?;
+                self.complete = true;
+                // Only printable ASCII is ever stored, so this cannot fail
+                return Ok(core::str::from_utf8(&self.line[..self.len]).ok());
+            }
+            // Backspace or DEL, depending on the terminal: move back, blank the character, move back again
+            0x08 | 0x7F if self.len > 0 => {
+                self.len -= 1;
+                echo.write_str("\x08 \x08")?;
+            }
+            b' '..=b'~' if self.len < MAX_LINE => {
+                self.line[self.len] = byte;
+                self.len += 1;
+                echo.write_char(char::from(byte))?;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+}
+
+/// A console command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command<'a> {
+    Help,
+    Led(bool),
+    Stats,
+    Uptime,
+    Echo(&'a str),
+    /// An empty line: nothing to do but show the prompt again
+    Empty,
+}
+
+/// Why a line is not a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError<'a> {
+    Unknown(&'a str),
+    /// Known command, bad or missing argument
+    Usage(&'static str),
+}
+
+impl fmt::Display for CommandError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown command '{}', try 'help'", name),
+            Self::Usage(usage) => write!(f, "usage: {}", usage),
+        }
+    }
+}
+
+impl<'a> Command<'a> {
+    /// Parse a line. Surrounding whitespace is ignored, and so is the case of command names.
+    pub fn parse(line: &'a str) -> Result<Self, CommandError<'a>> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(' ') {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+
+        let command = if name.is_empty() {
+            Self::Empty
+        } else if name.eq_ignore_ascii_case("help") {
+            Self::Help
+        } else if name.eq_ignore_ascii_case("led") {
+            if argument.eq_ignore_ascii_case("on") {
+                Self::Led(true)
+            } else if argument.eq_ignore_ascii_case("off") {
+                Self::Led(false)
+            } else {
+                return Err(CommandError::Usage("led on|off"));
+            }
+        } else if name.eq_ignore_ascii_case("stats") {
+            Self::Stats
+        } else if name.eq_ignore_ascii_case("uptime") {
+            Self::Uptime
+        } else if name.eq_ignore_ascii_case("echo") {
+            Self::Echo(argument)
+        } else {
+            return Err(CommandError::Unknown(name));
+        };
+        Ok(command)
+    }
+}
@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Serial console over the micro:bit v2's USB serial port.
+//!
+//! - [`ring`] is the receive ring buffer that EasyDMA writes into
+//! - [`console`] turns typed characters into lines and lines into commands
+//! - [`serial`] drives UARTE0: DMA reception into the ring, blocking `core::fmt::Write` output (target only)
+//!
+//! `ring` and `console` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod console;
+pub mod ring;
+
+#[cfg(target_os = "none")]
+pub mod serial;
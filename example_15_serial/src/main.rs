@@ -0,0 +1,131 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::{cell::RefCell, fmt::Write},
+    cortex_m::interrupt::{free, Mutex},
+    cortex_m_rt::entry,
+    embedded_hal::digital::{InputPin, OutputPin},
+    example_15_serial::{
+        console::{Command, LineEditor, HELP, PROMPT},
+        serial::{self, Buffers, SerialRx, SerialTx},
+    },
+    microbit::hal::{
+        gpio::Level,
+        pac::{self, interrupt},
+        uarte::Baudrate,
+        Timer,
+    },
+    panic_halt as _,
+};
+
+/// Shared with the UARTE0 interrupt, which keeps reception going
+#[cfg(target_os = "none")]
+static RX: Mutex<RefCell<Option<SerialRx>>> = Mutex::new(RefCell::new(None));
+
+/// Write a log line with the time since reset
+#[cfg(target_os = "none")]
+fn log(tx: &mut SerialTx, uptime_us: u64, message: &str) {
+    let ms = uptime_us / 1000;
+    writeln!(tx, "[{:6}.{:03}] {}", ms / 1000, ms % 1000, message).ok();
+}
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    let board = microbit::Board::take().unwrap();
+
+    let buffers: &'static mut Buffers = cortex_m::singleton!(: Buffers = Buffers::new()).unwrap();
+    let (mut tx, rx) = serial::split(
+        board.UARTE0,
+        board.TIMER1,
+        &board.PPI,
+        board.uart.into(),
+        Baudrate::BAUD115200,
+        buffers,
+    );
+    free(|cs| RX.borrow(cs).replace(Some(rx)));
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::UARTE0_UART0);
+    }
+
+    // Free-running 1 MHz timer for the uptime
+    let mut clock = Timer::periodic(board.TIMER0);
+    clock.start(u32::MAX);
+    let mut last_tick = clock.read();
+    let mut uptime_us: u64 = 0;
+
+    let mut led = board.display_pins.row1.into_push_pull_output(Level::Low);
+    let _col1 = board.display_pins.col1.into_push_pull_output(Level::Low);
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut a_was_pressed = false;
+
+    log(&mut tx, uptime_us, "micro:bit serial console, type 'help'");
+    tx.write_str(PROMPT).ok();
+
+    let mut editor = LineEditor::new();
+    let mut buffer = [0; 16];
+    loop {
+        let tick = clock.read();
+        uptime_us += u64::from(tick.wrapping_sub(last_tick));
+        last_tick = tick;
+
+        // Button presses are logged between commands
+        let a_pressed = button_a.is_low().unwrap();
+        if a_pressed && !a_was_pressed {
+            tx.write_str("\n").ok();
+            log(&mut tx, uptime_us, "button A pressed");
+            tx.write_str(PROMPT).ok();
+        }
+        a_was_pressed = a_pressed;
+
+        let count = free(|cs| RX.borrow(cs).borrow_mut().as_mut().unwrap().read(&mut buffer));
+        for &byte in &buffer[..count] {
+            let Ok(Some(line)) = editor.feed(byte, &mut tx) else {
+                continue;
+            };
+            match Command::parse(line) {
+                Ok(Command::Help) => writeln!(tx, "{}", HELP).ok(),
+                Ok(Command::Led(on)) => {
+                    if on {
+                        led.set_high().unwrap();
+                    } else {
+                        led.set_low().unwrap();
+                    }
+                    writeln!(tx, "LED {}", if on { "on" } else { "off" }).ok()
+                }
+                Ok(Command::Stats) => {
+                    let (received, overruns) = free(|cs| {
+                        let rx = RX.borrow(cs).borrow();
+                        let rx = rx.as_ref().unwrap();
+                        (rx.received(), rx.overruns())
+                    });
+                    writeln!(tx, "{} bytes received, {} overruns", received, overruns).ok()
+                }
+                Ok(Command::Uptime) => {
+                    let ms = uptime_us / 1000;
+                    writeln!(tx, "{}.{:03} s", ms / 1000, ms % 1000).ok()
+                }
+                Ok(Command::Echo(text)) => writeln!(tx, "{}", text).ok(),
+                Ok(Command::Empty) => Some(()),
+                Err(error) => writeln!(tx, "{}", error).ok(),
+            };
+            tx.write_str(PROMPT).ok();
+        }
+    }
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn UARTE0_UART0() {
+    free(|cs| {
+        if let Some(rx) = RX.borrow(cs).borrow_mut().as_mut() {
+            rx.on_interrupt();
+        }
+    });
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
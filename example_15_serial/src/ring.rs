@@ -0,0 +1,128 @@
+//! Byte ring buffer that EasyDMA writes into directly.
+//!
+//! The UARTE receiver is given regions of the ring to fill, one after the other, so received bytes land in place
+//! without being copied:
+//!
+//! ```text
+//!            start          start + len     + reserved
+//!              │  readable    │  DMA region   │      free
+//! ┌────────────┼──────────────┼───────────────┼────────────┐
+//! │            │ h e l l o \n │ ░░░░░░░░░░░░░ │            │
+//! └────────────┴──────────────┴───────────────┴────────────┘
+//! ```
+//!
+//! [`reserve`](RingBuffer::reserve) hands out the next free region, [`commit`](RingBuffer::commit) turns received
+//! bytes at the front of the reserved regions into readable ones, and [`read`](RingBuffer::read) takes them out.
+//! Regions never wrap around the end of the buffer, because DMA can only write a contiguous block.
+
+/// A region of the ring handed to DMA: `len` bytes from `offset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Ring buffer of `N` bytes with DMA reservations, see the module documentation
+#[derive(Debug)]
+pub struct RingBuffer<const N: usize> {
+    buf: [u8; N],
+    /// Index of the oldest readable byte
+    start: usize,
+    /// Readable bytes
+    len: usize,
+    /// Bytes after the readable ones that are reserved for DMA
+    reserved: usize,
+}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RingBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            start: 0,
+            len: 0,
+            reserved: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Bytes ready to read
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes handed to DMA and not committed yet
+    pub fn reserved(&self) -> usize {
+        self.reserved
+    }
+
+    /// Bytes neither readable nor reserved
+    pub fn free(&self) -> usize {
+        N - self.len - self.reserved
+    }
+
+    /// Reserve the next free region of up to `max` bytes for DMA. It may be shorter than `max` where the buffer
+    /// wraps around; returns `None` when the buffer is full.
+    pub fn reserve(&mut self, max: usize) -> Option<Region> {
+        let offset = (self.start + self.len + self.reserved) % N;
+        let len = max.min(self.free()).min(N - offset);
+        if len == 0 {
+            return None;
+        }
+        self.reserved += len;
+        Some(Region { offset, len })
+    }
+
+    /// `count` bytes of the reserved regions have been written, in the order the regions were reserved: make them
+    /// readable. More than was reserved is ignored.
+    pub fn commit(&mut self, count: usize) {
+        let count = count.min(self.reserved);
+        self.reserved -= count;
+        self.len += count;
+    }
+
+    /// Forget all reservations, e.g. when DMA was stopped
+    pub fn cancel_reservations(&mut self) {
+        self.reserved = 0;
+    }
+
+    /// The memory of a reserved region, for DMA (or a test) to write into
+    pub fn region_mut(&mut self, region: Region) -> &mut [u8] {
+        &mut self.buf[region.offset..region.offset + region.len]
+    }
+
+    /// Take the oldest byte
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buf[self.start];
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    /// Take up to `out.len()` bytes, returning how many were copied
+    pub fn read(&mut self, out: &mut [u8]) -> usize {
+        let count = out.len().min(self.len);
+        // At most two pieces: up to the end of the buffer, then from its beginning
+        let first = count.min(N - self.start);
+        out[..first].copy_from_slice(&self.buf[self.start..self.start + first]);
+        out[first..count].copy_from_slice(&self.buf[..count - first]);
+        self.start = (self.start + count) % N;
+        self.len -= count;
+        count
+    }
+}
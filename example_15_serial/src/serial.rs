@@ -0,0 +1,263 @@
+//! UARTE0 serial port with a DMA receive ring buffer and a blocking `core::fmt::Write` transmitter.
+//!
+//! On the micro:bit v2, UARTE TX/RX (P0.06 / P1.08) go to the interface chip, which shows up on the PC as a USB
+//! serial port next to the debug probe.
+//!
+//! Reception never stops: EasyDMA writes straight into the [`RingBuffer`], one region after the other. The
+//! `ENDRX_STARTRX` shortcut restarts reception as soon as a region is full, and the RXSTARTED interrupt hands the
+//! UARTE the region after that, while the current one is filling. To read bytes before their region is full,
+//! TIMER1 counts every received byte (RXDRDY) through PPI: [`SerialRx::read`] commits everything counted so far.
+
+use core::{
+    fmt,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+use microbit::hal::{
+    pac::{PPI, TIMER1, UARTE0},
+    uarte::{Baudrate, Pins},
+};
+
+use crate::ring::RingBuffer;
+
+/// Size of the receive ring buffer
+pub const RX_BUFFER_LEN: usize = 256;
+/// Longest DMA region: the RXSTARTED interrupt has as long as receiving this many bytes takes to respond, 2.8 ms
+/// at 115200 baud
+pub const RX_CHUNK_LEN: usize = 32;
+/// Transmit DMA buffer: EasyDMA cannot read from flash, so text is copied here first
+pub const TX_BUFFER_LEN: usize = 64;
+
+/// DMA buffers, must be in RAM and must not move
+pub struct Buffers {
+    pub rx: RingBuffer<RX_BUFFER_LEN>,
+    pub tx: [u8; TX_BUFFER_LEN],
+}
+
+impl Default for Buffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Buffers {
+    pub const fn new() -> Self {
+        Self {
+            rx: RingBuffer::new(),
+            tx: [0; TX_BUFFER_LEN],
+        }
+    }
+}
+
+/// Programmable PPI channel used for UARTE0 RXDRDY -> TIMER1 COUNT
+const PPI_RXDRDY_COUNT: usize = 0;
+
+// SHORTS register bits
+const SHORTS_ENDRX_STARTRX: u32 = 1 << 5;
+
+// INTENSET register bits
+const INTERRUPT_ENDRX: u32 = 1 << 4;
+const INTERRUPT_ERROR: u32 = 1 << 9;
+const INTERRUPT_RXSTARTED: u32 = 1 << 19;
+
+/// ERRORSRC register: overrun error bit
+const ERRORSRC_OVERRUN: u32 = 1 << 0;
+
+/// Set up UARTE0 (8 data bits, no parity, one stop bit, no flow control) and start receiving. Returns the two
+/// halves: the transmitter for the main loop, the receiver to share with the UARTE0_UART0 interrupt.
+pub fn split(
+    uarte: UARTE0,
+    counter: TIMER1,
+    ppi: &PPI,
+    pins: Pins,
+    baudrate: Baudrate,
+    buffers: &'static mut Buffers,
+) -> (SerialTx, SerialRx) {
+    uarte.psel.txd.write(|w| unsafe { w.bits(pins.txd.psel_bits()) });
+    uarte.psel.rxd.write(|w| unsafe { w.bits(pins.rxd.psel_bits()) });
+    uarte.baudrate.write(|w| w.baudrate().variant(baudrate));
+    uarte.config.write(|w| unsafe { w.bits(0) });
+    uarte.enable.write(|w| w.enable().enabled());
+
+    // Count received bytes
+    counter.mode.write(|w| w.mode().low_power_counter());
+    counter.bitmode.write(|w| w.bitmode()._32bit());
+    counter.tasks_clear.write(|w| unsafe { w.bits(1) });
+    counter.tasks_start.write(|w| unsafe { w.bits(1) });
+    let channel = &ppi.ch[PPI_RXDRDY_COUNT];
+    channel
+        .eep
+        .write(|w| unsafe { w.bits(&uarte.events_rxdrdy as *const _ as u32) });
+    channel
+        .tep
+        .write(|w| unsafe { w.bits(&counter.tasks_count as *const _ as u32) });
+    ppi.chenset.write(|w| unsafe { w.bits(1 << PPI_RXDRDY_COUNT) });
+
+    uarte
+        .intenset
+        .write(|w| unsafe { w.bits(INTERRUPT_RXSTARTED | INTERRUPT_ENDRX | INTERRUPT_ERROR) });
+
+    let Buffers { rx, tx } = buffers;
+    let mut receiver = SerialRx {
+        uarte,
+        counter,
+        ring: rx,
+        counted: 0,
+        receiving: false,
+        stopping: false,
+        received: 0,
+        overruns: 0,
+    };
+    receiver.start();
+    (SerialTx { buffer: tx, last: 0 }, receiver)
+}
+
+/// The receiving half, see the module documentation
+pub struct SerialRx {
+    uarte: UARTE0,
+    counter: TIMER1,
+    ring: &'static mut RingBuffer<RX_BUFFER_LEN>,
+    /// TIMER1 count already committed to the ring
+    counted: u32,
+    /// DMA is running
+    receiving: bool,
+    /// The ring was full when a region was needed: DMA ends with the current one
+    stopping: bool,
+    received: u32,
+    overruns: u32,
+}
+
+impl SerialRx {
+    /// Take up to `out.len()` received bytes
+    pub fn read(&mut self, out: &mut [u8]) -> usize {
+        self.commit_counted();
+        let count = self.ring.read(out);
+        if !self.receiving {
+            self.start();
+        }
+        count
+    }
+
+    /// Total bytes received
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+
+    /// Times bytes were lost because they were not read in time
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+
+    /// Call from the UARTE0_UART0 interrupt
+    pub fn on_interrupt(&mut self) {
+        if self.uarte.events_rxstarted.read().bits() != 0 {
+            self.uarte.events_rxstarted.write(|w| unsafe { w.bits(0) });
+            // The UARTE has latched the region it is filling now: give it the next one
+            if !self.stopping && !self.next_region() {
+                self.uarte.shorts.write(|w| unsafe { w.bits(0) });
+                self.stopping = true;
+            }
+        }
+        if self.uarte.events_endrx.read().bits() != 0 {
+            self.uarte.events_endrx.write(|w| unsafe { w.bits(0) });
+            if self.stopping {
+                // No region to continue in: reception stops until `read` makes room
+                self.commit_counted();
+                self.ring.cancel_reservations();
+                self.receiving = false;
+                self.stopping = false;
+            }
+        }
+        if self.uarte.events_error.read().bits() != 0 {
+            self.uarte.events_error.write(|w| unsafe { w.bits(0) });
+            // Reading ERRORSRC and writing the bits back clears them
+            let source = self.uarte.errorsrc.read().bits();
+            self.uarte.errorsrc.write(|w| unsafe { w.bits(source) });
+            if source & ERRORSRC_OVERRUN != 0 {
+                self.overruns += 1;
+            }
+        }
+    }
+
+    /// Start DMA into the first free region, and keep it going from region to region
+    fn start(&mut self) {
+        if !self.next_region() {
+            return;
+        }
+        // Bytes counted while reception was stopped never reached the ring
+        self.counter.tasks_capture[0].write(|w| unsafe { w.bits(1) });
+        self.counted = self.counter.cc[0].read().bits();
+        self.uarte.shorts.write(|w| unsafe { w.bits(SHORTS_ENDRX_STARTRX) });
+        compiler_fence(Ordering::SeqCst);
+        self.uarte.tasks_startrx.write(|w| unsafe { w.bits(1) });
+        self.receiving = true;
+    }
+
+    /// Point the UARTE at the next free region of the ring. Returns false if the ring is full.
+    fn next_region(&mut self) -> bool {
+        let Some(region) = self.ring.reserve(RX_CHUNK_LEN) else {
+            return false;
+        };
+        let pointer = self.ring.region_mut(region).as_mut_ptr();
+        self.uarte.rxd.ptr.write(|w| unsafe { w.bits(pointer as u32) });
+        self.uarte.rxd.maxcnt.write(|w| unsafe { w.bits(region.len as u32) });
+        true
+    }
+
+    /// Make every byte counted by TIMER1 readable
+    fn commit_counted(&mut self) {
+        self.counter.tasks_capture[0].write(|w| unsafe { w.bits(1) });
+        let count = self.counter.cc[0].read().bits();
+        let new = count.wrapping_sub(self.counted);
+        self.counted = count;
+        self.received = self.received.wrapping_add(new);
+        compiler_fence(Ordering::SeqCst);
+        self.ring.commit(new as usize);
+    }
+}
+
+/// The transmitting half: blocking writes through EasyDMA
+pub struct SerialTx {
+    buffer: &'static mut [u8; TX_BUFFER_LEN],
+    /// Last byte sent, to turn a lone `\n` into `\r\n`
+    last: u8,
+}
+
+impl SerialTx {
+    /// Send `bytes` as they are, returning once the last one has left
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        // Only the TXD registers and TX tasks/events are used here, the receiver owns everything else
+        let uarte = unsafe { &*UARTE0::ptr() };
+        for chunk in bytes.chunks(TX_BUFFER_LEN) {
+            self.buffer[..chunk.len()].copy_from_slice(chunk);
+            uarte.txd.ptr.write(|w| unsafe { w.bits(self.buffer.as_ptr() as u32) });
+            uarte.txd.maxcnt.write(|w| unsafe { w.bits(chunk.len() as u32) });
+            uarte.events_endtx.write(|w| unsafe { w.bits(0) });
+            compiler_fence(Ordering::SeqCst);
+            uarte.tasks_starttx.write(|w| unsafe { w.bits(1) });
+            while uarte.events_endtx.read().bits() == 0 {}
+            compiler_fence(Ordering::SeqCst);
+        }
+        if let Some(&last) = bytes.last() {
+            self.last = last;
+        }
+    }
+}
+
+/// Text for a terminal: every `\n` is sent as `\r\n`, unless it already follows a `\r`
+impl fmt::Write for SerialTx {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let mut lines = text.split('\n');
+        if let Some(first) = lines.next() {
+            self.write_bytes(first.as_bytes());
+        }
+        for line in lines {
+            if self.last != b'\r' {
+                self.write_bytes(b"\r");
+            }
+            self.write_bytes(b"\n");
+            self.write_bytes(line.as_bytes());
+        }
+        Ok(())
+    }
+}
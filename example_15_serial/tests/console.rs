@@ -0,0 +1,83 @@
+//! Host tests for line editing and command parsing
+
+use example_15_serial::console::{Command, CommandError, LineEditor, MAX_LINE};
+
+/// Feed `input` to the editor, returning the completed lines and everything echoed
+fn type_in(editor: &mut LineEditor, input: &[u8]) -> (Vec<String>, String) {
+    let mut echo = String::new();
+    let mut lines = Vec::new();
+    for &byte in input {
+        if let Some(line) = editor.feed(byte, &mut echo).unwrap() {
+            lines.push(line.to_string());
+        }
+    }
+    (lines, echo)
+}
+
+#[test]
+fn enter_completes_a_line() {
+    let mut editor = LineEditor::new();
+    let (lines, echo) = type_in(&mut editor, b"help\r");
+    assert_eq!(lines, ["help"]);
+    assert_eq!(echo, "help\r\n");
+}
+
+#[test]
+fn any_line_ending_is_one_enter() {
+    let mut editor = LineEditor::new();
+    let (lines, _) = type_in(&mut editor, b"a\r\nb\nc\r\rd\n\n");
+    assert_eq!(lines, ["a", "b", "c", "", "d", ""]);
+}
+
+#[test]
+fn backspace_erases() {
+    let mut editor = LineEditor::new();
+    let (lines, echo) = type_in(&mut editor, b"lex\x08d\x7F\x7Fed on\r");
+    assert_eq!(lines, ["led on"]);
+    assert_eq!(echo, "lex\x08 \x08d\x08 \x08\x08 \x08ed on\r\n");
+
+    // Nothing to erase: no echo
+    let (_, echo) = type_in(&mut editor, b"\x08");
+    assert_eq!(echo, "");
+}
+
+#[test]
+fn control_characters_and_non_ascii_are_dropped() {
+    let mut editor = LineEditor::new();
+    let (lines, echo) = type_in(&mut editor, b"o\x1b[Ak\xC3\xA9\r");
+    assert_eq!(lines, ["o[Ak"]);
+    assert_eq!(echo, "o[Ak\r\n");
+}
+
+#[test]
+fn long_lines_are_cut() {
+    let mut editor = LineEditor::new();
+    let mut input = vec![b'x'; MAX_LINE + 10];
+    input.push(b'\r');
+    let (lines, echo) = type_in(&mut editor, &input);
+    assert_eq!(lines[0].len(), MAX_LINE);
+    assert_eq!(echo.len(), MAX_LINE + 2);
+}
+
+#[test]
+fn commands() {
+    assert_eq!(Command::parse("help"), Ok(Command::Help));
+    assert_eq!(Command::parse("  LED  On "), Ok(Command::Led(true)));
+    assert_eq!(Command::parse("led off"), Ok(Command::Led(false)));
+    assert_eq!(Command::parse("stats"), Ok(Command::Stats));
+    assert_eq!(Command::parse("uptime"), Ok(Command::Uptime));
+    assert_eq!(Command::parse("echo Hello,  world"), Ok(Command::Echo("Hello,  world")));
+    assert_eq!(Command::parse("echo"), Ok(Command::Echo("")));
+    assert_eq!(Command::parse("   "), Ok(Command::Empty));
+}
+
+#[test]
+fn command_errors() {
+    assert_eq!(Command::parse("led"), Err(CommandError::Usage("led on|off")));
+    assert_eq!(Command::parse("led blue"), Err(CommandError::Usage("led on|off")));
+    assert_eq!(Command::parse("reboot now"), Err(CommandError::Unknown("reboot")));
+    assert_eq!(
+        Command::parse("reboot").unwrap_err().to_string(),
+        "unknown command 'reboot', try 'help'"
+    );
+}
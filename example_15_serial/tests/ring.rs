@@ -0,0 +1,149 @@
+//! Host tests for the receive ring buffer, with the test playing EasyDMA
+
+use example_15_serial::ring::{Region, RingBuffer};
+
+/// Write `data` into a reserved region, as the UARTE would
+fn dma_write<const N: usize>(ring: &mut RingBuffer<N>, region: Region, data: &[u8]) {
+    ring.region_mut(region)[..data.len()].copy_from_slice(data);
+}
+
+fn read_all<const N: usize>(ring: &mut RingBuffer<N>) -> Vec<u8> {
+    let mut out = vec![0; N];
+    let count = ring.read(&mut out);
+    out.truncate(count);
+    out
+}
+
+#[test]
+fn starts_empty() {
+    let mut ring = RingBuffer::<16>::new();
+    assert_eq!(ring.capacity(), 16);
+    assert!(ring.is_empty());
+    assert_eq!(ring.free(), 16);
+    assert_eq!(ring.pop(), None);
+    assert_eq!(ring.read(&mut [0; 4]), 0);
+}
+
+#[test]
+fn regions_follow_each_other() {
+    let mut ring = RingBuffer::<16>::new();
+    assert_eq!(ring.reserve(4), Some(Region { offset: 0, len: 4 }));
+    assert_eq!(ring.reserve(4), Some(Region { offset: 4, len: 4 }));
+    assert_eq!(ring.reserved(), 8);
+    assert_eq!(ring.free(), 8);
+}
+
+#[test]
+fn committed_bytes_become_readable() {
+    let mut ring = RingBuffer::<16>::new();
+    let first = ring.reserve(4).unwrap();
+    let second = ring.reserve(4).unwrap();
+    dma_write(&mut ring, first, b"abcd");
+    dma_write(&mut ring, second, b"ef");
+
+    // The byte counter says 6 bytes arrived: the first region and part of the second
+    ring.commit(6);
+    assert_eq!(ring.len(), 6);
+    assert_eq!(ring.reserved(), 2);
+    assert_eq!(read_all(&mut ring), b"abcdef");
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn commit_is_limited_to_reservations() {
+    let mut ring = RingBuffer::<16>::new();
+    ring.reserve(4).unwrap();
+    ring.commit(10);
+    assert_eq!(ring.len(), 4);
+    assert_eq!(ring.reserved(), 0);
+}
+
+#[test]
+fn regions_stop_at_the_end_of_the_buffer() {
+    let mut ring = RingBuffer::<16>::new();
+    let region = ring.reserve(12).unwrap();
+    dma_write(&mut ring, region, b"0123456789ab");
+    ring.commit(12);
+    assert_eq!(read_all(&mut ring).len(), 12);
+
+    // 4 bytes left before the end, then it continues at the start
+    assert_eq!(ring.reserve(8), Some(Region { offset: 12, len: 4 }));
+    assert_eq!(ring.reserve(8), Some(Region { offset: 0, len: 8 }));
+}
+
+#[test]
+fn reads_across_the_wrap() {
+    let mut ring = RingBuffer::<8>::new();
+    let region = ring.reserve(6).unwrap();
+    dma_write(&mut ring, region, b"xxxxxx");
+    ring.commit(6);
+    ring.read(&mut [0; 6]);
+
+    let tail = ring.reserve(8).unwrap();
+    let head = ring.reserve(8).unwrap();
+    assert_eq!((tail.len, head.offset), (2, 0));
+    dma_write(&mut ring, tail, b"he");
+    dma_write(&mut ring, head, b"llo");
+    ring.commit(5);
+    assert_eq!(read_all(&mut ring), b"hello");
+}
+
+#[test]
+fn partial_reads_and_pop() {
+    let mut ring = RingBuffer::<8>::new();
+    let region = ring.reserve(8).unwrap();
+    dma_write(&mut ring, region, b"abc");
+    ring.commit(3);
+
+    let mut out = [0; 2];
+    assert_eq!(ring.read(&mut out), 2);
+    assert_eq!(&out, b"ab");
+    assert_eq!(ring.pop(), Some(b'c'));
+    assert_eq!(ring.pop(), None);
+}
+
+#[test]
+fn full_buffer_refuses_regions() {
+    let mut ring = RingBuffer::<8>::new();
+    ring.reserve(8).unwrap();
+    ring.commit(8);
+    assert_eq!(ring.free(), 0);
+    assert_eq!(ring.reserve(4), None);
+
+    // Reading makes room again
+    ring.read(&mut [0; 3]);
+    assert_eq!(ring.reserve(4), Some(Region { offset: 0, len: 3 }));
+}
+
+#[test]
+fn cancelled_reservations_are_free_again() {
+    let mut ring = RingBuffer::<8>::new();
+    ring.reserve(4).unwrap();
+    ring.reserve(4).unwrap();
+    ring.commit(2);
+    ring.cancel_reservations();
+    assert_eq!((ring.len(), ring.reserved(), ring.free()), (2, 0, 6));
+    assert_eq!(ring.reserve(8), Some(Region { offset: 2, len: 6 }));
+}
+
+#[test]
+fn continuous_stream() {
+    // Many small chunks through a small buffer, reading as it goes: nothing lost, nothing reordered
+    let mut ring = RingBuffer::<10>::new();
+    let input: Vec<u8> = (0..=255).collect();
+    let mut output = Vec::new();
+    let mut sent = 0;
+    while output.len() < input.len() {
+        if let Some(region) = ring.reserve(3) {
+            let end = (sent + region.len).min(input.len());
+            dma_write(&mut ring, region, &input[sent..end]);
+            ring.commit(end - sent);
+            ring.cancel_reservations();
+            sent = end;
+        }
+        let mut out = [0; 2];
+        let count = ring.read(&mut out);
+        output.extend_from_slice(&out[..count]);
+    }
+    assert_eq!(output, input);
+}
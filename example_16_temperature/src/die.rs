@@ -0,0 +1,38 @@
+//! The nRF52833 TEMP peripheral: the temperature of the chip itself.
+//!
+//! A measurement takes about 36 µs. The result is a signed count of 0.25 °C steps; the sensor is accurate to
+//! ±5 °C over its range, so it shows changes well but needs calibrating for absolute values.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use microbit::hal::pac::TEMP;
+
+use crate::temperature::Temperature;
+
+/// The TEMP peripheral
+pub struct DieTemperature {
+    temp: TEMP,
+}
+
+impl DieTemperature {
+    pub fn new(temp: TEMP) -> Self {
+        Self { temp }
+    }
+
+    /// Measure, returning the raw TEMP register: 0.25 °C steps
+    pub fn measure_raw(&mut self) -> i32 {
+        self.temp.events_datardy.write(|w| unsafe { w.bits(0) });
+        compiler_fence(Ordering::SeqCst);
+        self.temp.tasks_start.write(|w| unsafe { w.bits(1) });
+        while self.temp.events_datardy.read().bits() == 0 {}
+        let raw = self.temp.temp.read().bits() as i32;
+        // The sensor stops by itself, but the datasheet asks for TASKS_STOP to release the analog part
+        self.temp.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.temp.events_datardy.write(|w| unsafe { w.bits(0) });
+        raw
+    }
+
+    pub fn measure(&mut self) -> Temperature {
+        Temperature::from_die_raw(self.measure_raw())
+    }
+}
@@ -0,0 +1,16 @@
+#![no_std]
+
+//! Die and ambient temperature for the micro:bit v2.
+//!
+//! - [`temperature`] converts raw register values to temperatures, averages them and applies a calibration offset
+//! - [`lsm303agr`] reads the LSM303AGR's temperature sensor over I²C
+//! - [`die`] reads the nRF52833's own TEMP peripheral (target only)
+//!
+//! `temperature` and `lsm303agr` are plain Rust (the latter generic over `embedded-hal` I²C) and are tested on the
+//! PC, see `tests/`.
+
+pub mod lsm303agr;
+pub mod temperature;
+
+#[cfg(target_os = "none")]
+pub mod die;
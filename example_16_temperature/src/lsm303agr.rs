@@ -0,0 +1,111 @@
+//! The temperature sensor inside the LSM303AGR, over any `embedded_hal::i2c::I2c`.
+//!
+//! The sensor is part of the accelerometer and only updates while the accelerometer is running, at its output
+//! data rate. [`TemperatureSensor::init`] starts the accelerometer at 10 Hz in normal mode, which gives 10 bit
+//! temperature readings in 0.25 °C steps, and turns on block data update so the two output bytes always belong
+//! to the same reading.
+//!
+//! The datasheet only specifies the sensor for temperature *changes*: expect an offset of a few degrees, which
+//! [`Calibration`](crate::temperature::Calibration) corrects.
+
+use embedded_hal::i2c::I2c;
+
+use crate::temperature::Temperature;
+
+/// I²C address of the accelerometer (and its temperature sensor)
+pub const ACCEL_ADDR: u8 = 0x19;
+
+// Accelerometer registers (datasheet section 8)
+pub const STATUS_REG_AUX_A: u8 = 0x07;
+pub const OUT_TEMP_L_A: u8 = 0x0C;
+pub const WHO_AM_I_A: u8 = 0x0F;
+pub const TEMP_CFG_REG_A: u8 = 0x1F;
+pub const CTRL_REG1_A: u8 = 0x20;
+pub const CTRL_REG4_A: u8 = 0x23;
+
+/// Value of WHO_AM_I_A on a genuine LSM303AGR
+pub const EXPECTED_ACCEL_ID: u8 = 0x33;
+
+/// TEMP_CFG_REG_A: temperature sensor enable (both TEMP_EN bits)
+pub const TEMP_EN: u8 = 0b11 << 6;
+/// CTRL_REG4_A: block data update
+pub const BDU: u8 = 1 << 7;
+/// CTRL_REG1_A: 10 Hz output data rate (ODR = 0010), normal mode, X, Y and Z enabled
+pub const CTRL_REG1_A_10HZ_NORMAL: u8 = 0x27;
+/// STATUS_REG_AUX_A: new temperature data available
+pub const TDA: u8 = 1 << 2;
+
+/// Set in the register address to read several registers in one transaction
+const AUTO_INCREMENT: u8 = 0x80;
+
+/// Errors reported by [`TemperatureSensor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The I²C bus reported an error
+    Comm(E),
+    /// Something answered on the accelerometer address, but it is not an LSM303AGR
+    UnexpectedId(u8),
+}
+
+/// The LSM303AGR temperature sensor, see the module documentation
+pub struct TemperatureSensor<I2C> {
+    i2c: I2C,
+}
+
+impl<I2C, E> TemperatureSensor<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Wrap an I²C bus. No bus traffic happens until [`init`](Self::init).
+    pub fn new(i2c: I2C) -> Self {
+        Self { i2c }
+    }
+
+    /// Check WHO_AM_I and start the accelerometer and its temperature sensor
+    pub fn init(&mut self) -> Result<(), Error<E>> {
+        let id = self.read_register(WHO_AM_I_A)?;
+        if id != EXPECTED_ACCEL_ID {
+            return Err(Error::UnexpectedId(id));
+        }
+        for (register, value) in [
+            (CTRL_REG1_A, CTRL_REG1_A_10HZ_NORMAL),
+            (CTRL_REG4_A, BDU),
+            (TEMP_CFG_REG_A, TEMP_EN),
+        ] {
+            self.i2c.write(ACCEL_ADDR, &[register, value]).map_err(Error::Comm)?;
+        }
+        Ok(())
+    }
+
+    /// The raw OUT_TEMP_L_A and OUT_TEMP_H_A registers, if a new reading is available
+    pub fn read_raw(&mut self) -> Result<Option<[u8; 2]>, Error<E>> {
+        if self.read_register(STATUS_REG_AUX_A)? & TDA == 0 {
+            return Ok(None);
+        }
+        let mut raw = [0; 2];
+        self.i2c
+            .write_read(ACCEL_ADDR, &[OUT_TEMP_L_A | AUTO_INCREMENT], &mut raw)
+            .map_err(Error::Comm)?;
+        Ok(Some(raw))
+    }
+
+    /// A new reading, if one is available
+    pub fn read(&mut self) -> Result<Option<Temperature>, Error<E>> {
+        Ok(self
+            .read_raw()?
+            .map(|[low, high]| Temperature::from_lsm303agr_raw(low, high)))
+    }
+
+    /// Give the bus back
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn read_register(&mut self, register: u8) -> Result<u8, Error<E>> {
+        let mut value = [0];
+        self.i2c
+            .write_read(ACCEL_ADDR, &[register], &mut value)
+            .map_err(Error::Comm)?;
+        Ok(value[0])
+    }
+}
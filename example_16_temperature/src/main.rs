@@ -0,0 +1,84 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_16_temperature::{
+        die::DieTemperature,
+        lsm303agr::TemperatureSensor,
+        temperature::{Average, Calibration},
+    },
+    microbit::{
+        hal::{twim, Timer},
+        pac::twim0::frequency::FREQUENCY_A,
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// Readings are averaged over the last 8 seconds
+#[cfg(target_os = "none")]
+const AVERAGE_OVER: usize = 8;
+/// Button A is checked this often, the temperatures are read and printed every 10th time
+#[cfg(target_os = "none")]
+const POLL_MS: u32 = 100;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+
+    let mut die = DieTemperature::new(board.TEMP);
+    let i2c = twim::Twim::new(board.TWIM0, board.i2c_internal.into(), FREQUENCY_A::K100);
+    let mut ambient = TemperatureSensor::new(i2c);
+    ambient.init().unwrap();
+
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut a_was_pressed = false;
+
+    let mut die_average = Average::<AVERAGE_OVER>::new();
+    let mut ambient_average = Average::<AVERAGE_OVER>::new();
+    let mut calibration = Calibration::NONE;
+    let mut poll_count: u32 = 0;
+
+    rprintln!("Temperature example started. Press A to calibrate the die sensor against the LSM303AGR");
+
+    loop {
+        if poll_count % 10 == 0 {
+            let die_reading = die.measure();
+            let die_smoothed = die_average.push(die_reading);
+            // The LSM303AGR updates at 10 Hz, so there is always a new reading after a second
+            if let Some(reading) = ambient.read().unwrap() {
+                ambient_average.push(reading);
+            }
+
+            rprintln!(
+                "Die: {} (average {})  LSM303AGR: {}",
+                calibration.apply(die_reading),
+                calibration.apply(die_smoothed),
+                ambient_average.average().unwrap_or_default()
+            );
+        }
+        poll_count = poll_count.wrapping_add(1);
+
+        // Button A: make the averaged die temperature match the averaged LSM303AGR temperature from now on
+        let a_pressed = button_a.is_low().unwrap();
+        if a_pressed && !a_was_pressed {
+            if let (Some(die_smoothed), Some(reference)) = (die_average.average(), ambient_average.average()) {
+                calibration = Calibration::from_reference(die_smoothed, reference);
+                rprintln!("Calibrated: die offset {} m°C", calibration.offset_milli);
+            }
+        }
+        a_was_pressed = a_pressed;
+
+        timer0.delay_ms(POLL_MS);
+    }
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,129 @@
+//! Temperature values, conversion from raw sensor registers, averaging and offset calibration.
+//!
+//! Temperatures are kept in millidegrees Celsius as an `i32`: both sensors have steps of 0.25 °C (or
+//! 1/256 °C before rounding), which integer millidegrees represent well enough without floating point.
+
+use core::fmt;
+
+/// A temperature in millidegrees Celsius
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Temperature(pub i32);
+
+impl Temperature {
+    pub const fn from_milli_celsius(milli: i32) -> Self {
+        Self(milli)
+    }
+
+    pub const fn milli_celsius(self) -> i32 {
+        self.0
+    }
+
+    /// The nRF52833 TEMP register: a signed count of 0.25 °C steps
+    pub const fn from_die_raw(raw: i32) -> Self {
+        Self(raw * 250)
+    }
+
+    /// The LSM303AGR OUT_TEMP_L_A/OUT_TEMP_H_A registers: a left-aligned two's complement difference from
+    /// 25 °C, 256 counts per degree. Normal mode fills the top 10 bits (0.25 °C steps), low-power mode only the
+    /// top 8 bits (1 °C steps).
+    pub fn from_lsm303agr_raw(low: u8, high: u8) -> Self {
+        let counts = i32::from(i16::from_le_bytes([low, high]));
+        Self(25_000 + counts * 1000 / 256)
+    }
+}
+
+/// `23.25 °C`, to two decimals. Rounds towards zero.
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let magnitude = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02} °C", sign, magnitude / 1000, magnitude % 1000 / 10)
+    }
+}
+
+/// Moving average over the last `N` readings, to smooth out the quantisation steps and noise
+#[derive(Debug, Clone)]
+pub struct Average<const N: usize> {
+    samples: [i32; N],
+    /// Next slot to overwrite
+    next: usize,
+    count: usize,
+}
+
+impl<const N: usize> Default for Average<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Average<N> {
+    pub const fn new() -> Self {
+        Self {
+            samples: [0; N],
+            next: 0,
+            count: 0,
+        }
+    }
+
+    /// Add a reading, replacing the oldest once `N` have been added. Returns the new average.
+    pub fn push(&mut self, reading: Temperature) -> Temperature {
+        self.samples[self.next] = reading.0;
+        self.next = (self.next + 1) % N;
+        self.count = (self.count + 1).min(N);
+        self.average().unwrap_or(reading)
+    }
+
+    /// Average of the readings so far, rounded to the nearest millidegree. `None` before the first reading.
+    pub fn average(&self) -> Option<Temperature> {
+        if self.count == 0 {
+            return None;
+        }
+        let sum: i64 = self.samples[..self.count].iter().map(|&sample| i64::from(sample)).sum();
+        let count = self.count as i64;
+        // Round half away from zero
+        let rounded = if sum >= 0 {
+            (sum + count / 2) / count
+        } else {
+            (sum - count / 2) / count
+        };
+        Some(Temperature(rounded as i32))
+    }
+
+    /// Readings averaged so far, at most `N`
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.count = 0;
+        self.next = 0;
+    }
+}
+
+/// A constant correction added to every reading.
+///
+/// The die sensor measures the chip, which runs warmer than the air around it. Calibrating against a reference
+/// (a thermometer, or the other sensor) gives the offset between the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Calibration {
+    pub offset_milli: i32,
+}
+
+impl Calibration {
+    pub const NONE: Self = Self { offset_milli: 0 };
+
+    /// The calibration that turns `measured` into `actual`
+    pub fn from_reference(measured: Temperature, actual: Temperature) -> Self {
+        Self {
+            offset_milli: actual.0 - measured.0,
+        }
+    }
+
+    pub fn apply(&self, reading: Temperature) -> Temperature {
+        Temperature(reading.0 + self.offset_milli)
+    }
+}
@@ -0,0 +1,142 @@
+//! Host tests for the LSM303AGR temperature code, against a fake I²C bus holding the accelerometer's registers
+
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use example_16_temperature::{
+    lsm303agr::{
+        Error, TemperatureSensor, ACCEL_ADDR, BDU, CTRL_REG1_A, CTRL_REG1_A_10HZ_NORMAL, CTRL_REG4_A, STATUS_REG_AUX_A,
+        TDA, TEMP_CFG_REG_A, TEMP_EN, WHO_AM_I_A,
+    },
+    temperature::Temperature,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FakeError(ErrorKind);
+
+impl i2c::Error for FakeError {
+    fn kind(&self) -> ErrorKind {
+        self.0
+    }
+}
+
+/// Register file of the accelerometer. Register addresses with bit 7 set auto-increment.
+struct FakeBus {
+    registers: [u8; 128],
+    writes: Vec<(u8, u8)>,
+}
+
+impl FakeBus {
+    fn new() -> Self {
+        let mut registers = [0; 128];
+        registers[usize::from(WHO_AM_I_A)] = 0x33;
+        Self {
+            registers,
+            writes: Vec::new(),
+        }
+    }
+
+    /// A new temperature reading, as the sensor would latch it
+    fn set_temperature(&mut self, low: u8, high: u8) {
+        self.registers[0x0C] = low;
+        self.registers[0x0D] = high;
+        self.registers[usize::from(STATUS_REG_AUX_A)] |= TDA;
+    }
+}
+
+impl ErrorType for FakeBus {
+    type Error = FakeError;
+}
+
+impl I2c for FakeBus {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        if address != ACCEL_ADDR {
+            return Err(FakeError(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)));
+        }
+        let mut pointer = 0;
+        let mut increment = false;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    pointer = usize::from(bytes[0] & 0x7F);
+                    increment = bytes[0] & 0x80 != 0;
+                    for &value in &bytes[1..] {
+                        self.registers[pointer] = value;
+                        self.writes.push((pointer as u8, value));
+                        pointer += 1;
+                    }
+                }
+                Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.registers[pointer];
+                        // Reading the high byte clears the data available flag
+                        if pointer == 0x0D {
+                            self.registers[usize::from(STATUS_REG_AUX_A)] &= !TDA;
+                        }
+                        if increment {
+                            pointer += 1;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn init_enables_the_temperature_sensor() {
+    let mut sensor = TemperatureSensor::new(FakeBus::new());
+    sensor.init().unwrap();
+    let bus = sensor.release();
+    assert_eq!(
+        bus.writes,
+        [
+            (CTRL_REG1_A, CTRL_REG1_A_10HZ_NORMAL),
+            (CTRL_REG4_A, BDU),
+            (TEMP_CFG_REG_A, TEMP_EN)
+        ]
+    );
+}
+
+#[test]
+fn wrong_chip_is_rejected() {
+    let mut bus = FakeBus::new();
+    bus.registers[usize::from(WHO_AM_I_A)] = 0x44;
+    let mut sensor = TemperatureSensor::new(bus);
+    assert_eq!(sensor.init(), Err(Error::UnexpectedId(0x44)));
+    assert!(sensor.release().writes.is_empty());
+}
+
+#[test]
+fn readings_only_when_new() {
+    let mut sensor = TemperatureSensor::new(FakeBus::new());
+    sensor.init().unwrap();
+    assert_eq!(sensor.read(), Ok(None));
+
+    let mut bus = sensor.release();
+    bus.set_temperature(0x80, 0xFE); // -1.5 °C from 25 °C
+    let mut sensor = TemperatureSensor::new(bus);
+    assert_eq!(sensor.read_raw(), Ok(Some([0x80, 0xFE])));
+    assert_eq!(sensor.read(), Ok(None));
+
+    let mut bus = sensor.release();
+    bus.set_temperature(0x80, 0xFE);
+    let mut sensor = TemperatureSensor::new(bus);
+    assert_eq!(sensor.read(), Ok(Some(Temperature::from_milli_celsius(23_500))));
+}
+
+#[test]
+fn bus_errors_are_reported() {
+    struct DeadBus;
+    impl ErrorType for DeadBus {
+        type Error = FakeError;
+    }
+    impl I2c for DeadBus {
+        fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+            Err(FakeError(ErrorKind::Bus))
+        }
+    }
+
+    let mut sensor = TemperatureSensor::new(DeadBus);
+    assert_eq!(sensor.init(), Err(Error::Comm(FakeError(ErrorKind::Bus))));
+    assert_eq!(sensor.read(), Err(Error::Comm(FakeError(ErrorKind::Bus))));
+}
@@ -0,0 +1,102 @@
+//! Host tests for raw register conversion, averaging and calibration
+
+use example_16_temperature::temperature::{Average, Calibration, Temperature};
+
+fn celsius(milli: i32) -> Temperature {
+    Temperature::from_milli_celsius(milli)
+}
+
+#[test]
+fn die_register_is_in_quarter_degrees() {
+    assert_eq!(Temperature::from_die_raw(0), celsius(0));
+    assert_eq!(Temperature::from_die_raw(97), celsius(24_250));
+    assert_eq!(Temperature::from_die_raw(98), celsius(24_500));
+    assert_eq!(Temperature::from_die_raw(-1), celsius(-250));
+    assert_eq!(Temperature::from_die_raw(-160), celsius(-40_000));
+}
+
+#[test]
+fn lsm303agr_register_is_left_aligned_from_25_degrees() {
+    assert_eq!(Temperature::from_lsm303agr_raw(0x00, 0x00), celsius(25_000));
+    // Normal mode: 10 bits, 0.25 °C per step in bits 15-6
+    assert_eq!(Temperature::from_lsm303agr_raw(0x40, 0x00), celsius(25_250));
+    assert_eq!(Temperature::from_lsm303agr_raw(0x00, 0x01), celsius(26_000));
+    assert_eq!(Temperature::from_lsm303agr_raw(0xC0, 0xFF), celsius(24_750));
+    assert_eq!(Temperature::from_lsm303agr_raw(0x00, 0xFB), celsius(20_000));
+    // Low-power mode: 8 bits, whole degrees
+    assert_eq!(Temperature::from_lsm303agr_raw(0x00, 0x0A), celsius(35_000));
+}
+
+#[test]
+fn display_has_two_decimals() {
+    assert_eq!(celsius(23_250).to_string(), "23.25 °C");
+    assert_eq!(celsius(5).to_string(), "0.00 °C");
+    assert_eq!(celsius(-750).to_string(), "-0.75 °C");
+    assert_eq!(celsius(-12_000).to_string(), "-12.00 °C");
+}
+
+#[test]
+fn average_of_fewer_than_n() {
+    let mut average = Average::<4>::new();
+    assert_eq!(average.average(), None);
+    assert!(average.is_empty());
+
+    assert_eq!(average.push(celsius(24_000)), celsius(24_000));
+    assert_eq!(average.push(celsius(24_500)), celsius(24_250));
+    assert_eq!(average.len(), 2);
+}
+
+#[test]
+fn average_forgets_old_readings() {
+    let mut average = Average::<4>::new();
+    for milli in [20_000, 24_000, 24_000, 24_000, 24_000] {
+        average.push(celsius(milli));
+    }
+    assert_eq!(average.len(), 4);
+    assert_eq!(average.average(), Some(celsius(24_000)));
+}
+
+#[test]
+fn average_smooths_quantisation() {
+    // A true 24.1 °C seen by a sensor with 0.25 °C steps
+    let mut average = Average::<8>::new();
+    for raw in [96, 96, 97, 96, 96, 97, 96, 96] {
+        average.push(Temperature::from_die_raw(raw));
+    }
+    assert_eq!(average.average(), Some(celsius(24_063)));
+}
+
+#[test]
+fn average_rounds_to_nearest() {
+    let mut average = Average::<3>::new();
+    average.push(celsius(1));
+    average.push(celsius(1));
+    average.push(celsius(2));
+    assert_eq!(average.average(), Some(celsius(1)));
+
+    let mut average = Average::<3>::new();
+    average.push(celsius(-1));
+    average.push(celsius(-2));
+    average.push(celsius(-2));
+    assert_eq!(average.average(), Some(celsius(-2)));
+}
+
+#[test]
+fn clear_starts_over() {
+    let mut average = Average::<4>::new();
+    average.push(celsius(30_000));
+    average.clear();
+    assert_eq!(average.average(), None);
+    assert_eq!(average.push(celsius(20_000)), celsius(20_000));
+}
+
+#[test]
+fn calibration_offset() {
+    assert_eq!(Calibration::NONE.apply(celsius(27_500)), celsius(27_500));
+
+    // The die reads 27.5 °C while the room is 22.75 °C
+    let calibration = Calibration::from_reference(celsius(27_500), celsius(22_750));
+    assert_eq!(calibration.offset_milli, -4_750);
+    assert_eq!(calibration.apply(celsius(27_500)), celsius(22_750));
+    assert_eq!(calibration.apply(celsius(30_000)), celsius(25_250));
+}
@@ -0,0 +1,60 @@
+//! Dice: random numbers in a range, without modulo bias, and die faces for the LED display.
+
+use rand_core::RngCore;
+
+/// A random number from `0` to `n - 1`, every value equally likely.
+///
+/// `next_u32() % n` would favour the small values whenever `n` does not divide 2³², so values from the
+/// incomplete last block are rejected and drawn again. That happens rarely: for a die, 4 times in 2³².
+///
+/// Panics if `n` is 0.
+pub fn below(rng: &mut impl RngCore, n: u32) -> u32 {
+    assert!(n > 0, "empty range");
+    // The largest multiple of n that fits, as an exclusive bound
+    let limit = u32::MAX - u32::MAX % n;
+    loop {
+        let value = rng.next_u32();
+        if value < limit {
+            return value % n;
+        }
+    }
+}
+
+/// Roll a die with `sides` sides: 1 to `sides`
+pub fn roll(rng: &mut impl RngCore, sides: u32) -> u32 {
+    below(rng, sides) + 1
+}
+
+/// The pips of a six-sided die, for `microbit::display`. Values outside 1 to 6 show a blank face.
+pub fn face(value: u32) -> [[u8; 5]; 5] {
+    // Pip positions as (row, column)
+    const TOP_LEFT: (usize, usize) = (0, 0);
+    const TOP_RIGHT: (usize, usize) = (0, 4);
+    const MIDDLE_LEFT: (usize, usize) = (2, 0);
+    const CENTRE: (usize, usize) = (2, 2);
+    const MIDDLE_RIGHT: (usize, usize) = (2, 4);
+    const BOTTOM_LEFT: (usize, usize) = (4, 0);
+    const BOTTOM_RIGHT: (usize, usize) = (4, 4);
+
+    let pips: &[(usize, usize)] = match value {
+        1 => &[CENTRE],
+        2 => &[TOP_RIGHT, BOTTOM_LEFT],
+        3 => &[TOP_RIGHT, CENTRE, BOTTOM_LEFT],
+        4 => &[TOP_LEFT, TOP_RIGHT, BOTTOM_LEFT, BOTTOM_RIGHT],
+        5 => &[TOP_LEFT, TOP_RIGHT, CENTRE, BOTTOM_LEFT, BOTTOM_RIGHT],
+        6 => &[
+            TOP_LEFT,
+            TOP_RIGHT,
+            MIDDLE_LEFT,
+            MIDDLE_RIGHT,
+            BOTTOM_LEFT,
+            BOTTOM_RIGHT,
+        ],
+        _ => &[],
+    };
+    let mut leds = [[0; 5]; 5];
+    for &(row, column) in pips {
+        leds[row][column] = 1;
+    }
+    leds
+}
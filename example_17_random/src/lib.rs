@@ -0,0 +1,15 @@
+#![no_std]
+
+//! Random numbers from the nRF52833's hardware RNG.
+//!
+//! - [`pool`] collects random bytes ahead of time and implements `rand_core`'s `RngCore` and `CryptoRng` on top
+//! - [`dice`] turns random numbers into fair dice rolls and die faces for the display
+//! - [`rng`] drives the RNG peripheral with bias correction and fills the pool from its interrupt (target only)
+//!
+//! `pool` and `dice` are plain Rust and are tested on the PC with a fake entropy source, see `tests/`.
+
+pub mod dice;
+pub mod pool;
+
+#[cfg(target_os = "none")]
+pub mod rng;
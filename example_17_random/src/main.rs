@@ -0,0 +1,77 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::digital::InputPin,
+    example_17_random::{dice, rng},
+    microbit::{
+        display::blocking::Display,
+        hal::{pac::interrupt, Timer},
+    },
+    panic_rtt_target as _,
+    rand_core::RngCore,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// The display is refreshed in slices this long, with the buttons checked in between
+#[cfg(target_os = "none")]
+const FRAME_MS: u32 = 50;
+/// Random faces shown while the die tumbles, before the result
+#[cfg(target_os = "none")]
+const TUMBLE_FRAMES: u32 = 8;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+    let mut display = Display::new(board.display_pins);
+
+    let mut rng = rng::init(board.RNG);
+    rprintln!("Random numbers from the hardware RNG. A: roll the die, B: print random bytes");
+    rprintln!("First u64: {:#018x}", rng.next_u64());
+
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_b = board.buttons.button_b.into_floating_input();
+    let mut a_was_pressed = false;
+    let mut b_was_pressed = false;
+    let mut shown = dice::face(dice::roll(&mut rng, 6));
+
+    loop {
+        // Button A rolls the die: a few random faces, then the result
+        let a_pressed = button_a.is_low().unwrap();
+        if a_pressed && !a_was_pressed {
+            for _ in 0..TUMBLE_FRAMES {
+                display.show(&mut timer0, dice::face(dice::roll(&mut rng, 6)), FRAME_MS);
+            }
+            let value = dice::roll(&mut rng, 6);
+            shown = dice::face(value);
+            rprintln!("Rolled {}", value);
+        }
+        a_was_pressed = a_pressed;
+
+        // Button B prints fresh random bytes, and how many are left in the pool
+        let b_pressed = button_b.is_low().unwrap();
+        if b_pressed && !b_was_pressed {
+            let mut bytes = [0; 16];
+            rng.fill_bytes(&mut bytes);
+            rprintln!("{:02x?} ({} bytes pooled)", bytes, rng.source().available());
+        }
+        b_was_pressed = b_pressed;
+
+        display.show(&mut timer0, shown, FRAME_MS);
+    }
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn RNG() {
+    rng::on_interrupt();
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
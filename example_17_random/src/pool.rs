@@ -0,0 +1,150 @@
+//! Entropy pool: random bytes collected ahead of time, so that most requests are answered without waiting.
+//!
+//! The RNG produces one byte at a time, about every 120 µs with bias correction. Its interrupt pushes each byte
+//! into an [`EntropyPool`], and stops the RNG once the pool is full. Taking bytes out starts it again once the
+//! pool has dropped to half, so the RNG runs in bursts instead of being started and stopped for every byte:
+//!
+//! ```text
+//!  RNG ──VALRDY──► push ──► [ ■ ■ ■ ■ ■ ■ ■ ■ · · · · · · · · ] ──► take ──► PoolRng (RngCore)
+//!                   │        full: stop the RNG                        │
+//!                   └──────  half empty: start it again  ◄─────────────┘
+//! ```
+//!
+//! [`PoolRng`] implements `rand_core`'s [`RngCore`] on top of any [`EntropySource`], waiting for the source when
+//! a request needs more bytes than are ready.
+
+use rand_core::{CryptoRng, Error, RngCore};
+
+/// Ring buffer of `N` random bytes, see the module documentation
+#[derive(Debug)]
+pub struct EntropyPool<const N: usize> {
+    buf: [u8; N],
+    /// Index of the oldest byte
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for EntropyPool<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EntropyPool<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Bytes ready to take
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// The pool has dropped to half or less: time to start the RNG again
+    pub fn needs_refill(&self) -> bool {
+        self.len <= N / 2
+    }
+
+    /// Add a fresh byte. Returns false once the pool is full and the RNG should stop; a byte pushed into a full
+    /// pool is dropped.
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.len < N {
+            self.buf[(self.start + self.len) % N] = byte;
+            self.len += 1;
+        }
+        self.len < N
+    }
+
+    /// Take up to `dest.len()` bytes, returning how many were copied. Every byte is handed out only once.
+    pub fn take(&mut self, dest: &mut [u8]) -> usize {
+        let count = dest.len().min(self.len);
+        // At most two pieces: up to the end of the buffer, then from its beginning
+        let first = count.min(N - self.start);
+        dest[..first].copy_from_slice(&self.buf[self.start..self.start + first]);
+        dest[first..count].copy_from_slice(&self.buf[..count - first]);
+        // Taken bytes are wiped, so they cannot leak out of the pool later
+        for index in 0..count {
+            self.buf[(self.start + index) % N] = 0;
+        }
+        self.start = (self.start + count) % N;
+        self.len -= count;
+        count
+    }
+}
+
+/// Where [`PoolRng`] gets its bytes: a pool filled by the RNG interrupt on the micro:bit, a fake in the tests.
+///
+/// Implementations must deliver true random bytes, as [`PoolRng`] is marked [`CryptoRng`].
+pub trait EntropySource {
+    /// Copy up to `dest.len()` bytes that are ready, returning how many. Must not block.
+    fn take(&mut self, dest: &mut [u8]) -> usize;
+
+    /// Block until more bytes may be ready
+    fn wait(&mut self);
+}
+
+/// `rand_core` random number generator on top of an [`EntropySource`]
+#[derive(Debug)]
+pub struct PoolRng<S> {
+    source: S,
+}
+
+impl<S: EntropySource> PoolRng<S> {
+    pub const fn new(source: S) -> Self {
+        Self { source }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    pub fn release(self) -> S {
+        self.source
+    }
+}
+
+impl<S: EntropySource> RngCore for PoolRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Fill `dest`, waiting for the source as long as it takes
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut filled = self.source.take(dest);
+        while filled < dest.len() {
+            self.source.wait();
+            filled += self.source.take(&mut dest[filled..]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<S: EntropySource> CryptoRng for PoolRng<S> {}
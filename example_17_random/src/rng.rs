@@ -0,0 +1,132 @@
+//! The nRF52833 RNG peripheral, filling an [`EntropyPool`] from its interrupt.
+//!
+//! The RNG turns thermal noise into random bits. The raw bits can lean slightly towards 0 or 1; with bias
+//! correction (CONFIG.DERCEN) it combines bit pairs so that 0 and 1 are equally likely, at the cost of speed:
+//! about 120 µs per byte instead of 30 µs. Collecting bytes ahead of time in the pool hides most of that.
+
+use core::cell::RefCell;
+
+use cortex_m::interrupt::{free, Mutex};
+use microbit::hal::pac::{self, RNG};
+
+use crate::pool::{EntropyPool, EntropySource, PoolRng};
+
+/// Random bytes kept ready
+pub const POOL_LEN: usize = 64;
+
+/// CONFIG register: bias correction
+const CONFIG_DERCEN: u32 = 1 << 0;
+/// INTENSET register: VALRDY interrupt
+const INTERRUPT_VALRDY: u32 = 1 << 0;
+
+/// Owned by the RNG interrupt, borrowed by [`PoolSource`] to take bytes out
+struct Shared {
+    rng: RNG,
+    pool: EntropyPool<POOL_LEN>,
+    running: bool,
+}
+
+impl Shared {
+    fn start(&mut self) {
+        if !self.running {
+            self.rng.tasks_start.write(|w| unsafe { w.bits(1) });
+            self.running = true;
+        }
+    }
+
+    fn stop(&mut self) {
+        self.rng.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.running = false;
+    }
+}
+
+static SHARED: Mutex<RefCell<Option<Shared>>> = Mutex::new(RefCell::new(None));
+
+/// The random number generator: `RngCore` and `CryptoRng`, backed by the RNG peripheral
+pub type HardwareRng = PoolRng<PoolSource>;
+
+/// Start the RNG with bias correction and return the generator. Call [`on_interrupt`] from the RNG interrupt.
+pub fn init(rng: RNG) -> HardwareRng {
+    rng.config.write(|w| unsafe { w.bits(CONFIG_DERCEN) });
+    rng.events_valrdy.write(|w| unsafe { w.bits(0) });
+    rng.intenset.write(|w| unsafe { w.bits(INTERRUPT_VALRDY) });
+
+    free(|cs| {
+        let mut shared = Shared {
+            rng,
+            pool: EntropyPool::new(),
+            running: false,
+        };
+        shared.start();
+        SHARED.borrow(cs).replace(Some(shared));
+    });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::RNG);
+    }
+    PoolRng::new(PoolSource { _private: () })
+}
+
+/// Call from the RNG interrupt: move the new byte into the pool
+pub fn on_interrupt() {
+    free(|cs| {
+        let mut shared = SHARED.borrow(cs).borrow_mut();
+        let Some(shared) = shared.as_mut() else {
+            return;
+        };
+        if shared.rng.events_valrdy.read().bits() == 0 {
+            return;
+        }
+        shared.rng.events_valrdy.write(|w| unsafe { w.bits(0) });
+        let byte = shared.rng.value.read().bits() as u8;
+        if !shared.pool.push(byte) {
+            shared.stop();
+        }
+    });
+}
+
+/// The pool behind [`HardwareRng`]. Only [`init`] makes one, so there is only one.
+pub struct PoolSource {
+    _private: (),
+}
+
+impl PoolSource {
+    /// Bytes ready in the pool
+    pub fn available(&self) -> usize {
+        free(|cs| {
+            SHARED
+                .borrow(cs)
+                .borrow()
+                .as_ref()
+                .map_or(0, |shared| shared.pool.len())
+        })
+    }
+}
+
+impl EntropySource for PoolSource {
+    fn take(&mut self, dest: &mut [u8]) -> usize {
+        free(|cs| {
+            let mut shared = SHARED.borrow(cs).borrow_mut();
+            let shared = shared.as_mut().unwrap();
+            let count = shared.pool.take(dest);
+            if shared.pool.needs_refill() {
+                shared.start();
+            }
+            count
+        })
+    }
+
+    fn wait(&mut self) {
+        // Interrupts are masked while checking, so a byte arriving in between still wakes WFI: a pending
+        // interrupt ends WFI even when it cannot run yet, and it runs as soon as `free` returns
+        free(|cs| {
+            let empty = SHARED
+                .borrow(cs)
+                .borrow()
+                .as_ref()
+                .is_none_or(|shared| shared.pool.is_empty());
+            if empty {
+                cortex_m::asm::wfi();
+            }
+        });
+    }
+}
@@ -0,0 +1,105 @@
+//! Host tests for fair ranges and die faces, with a scripted `RngCore`
+
+use example_17_random::dice::{below, face, roll};
+use rand_core::{impls, Error, RngCore};
+
+/// Returns the given `u32`s in order, then repeats the last one
+struct Scripted {
+    values: Vec<u32>,
+    calls: usize,
+}
+
+impl Scripted {
+    fn new(values: &[u32]) -> Self {
+        Self {
+            values: values.to_vec(),
+            calls: 0,
+        }
+    }
+}
+
+impl RngCore for Scripted {
+    fn next_u32(&mut self) -> u32 {
+        let value = self.values[self.calls.min(self.values.len() - 1)];
+        self.calls += 1;
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[test]
+fn below_is_the_remainder() {
+    let mut rng = Scripted::new(&[0, 5, 6, 13]);
+    assert_eq!(below(&mut rng, 6), 0);
+    assert_eq!(below(&mut rng, 6), 5);
+    assert_eq!(below(&mut rng, 6), 0);
+    assert_eq!(below(&mut rng, 6), 1);
+}
+
+#[test]
+fn incomplete_last_block_is_rejected() {
+    // 2³² = 6 × 715827882 + 4: the top 4 values would make 0 to 3 more likely than 4 and 5
+    let mut rng = Scripted::new(&[u32::MAX, u32::MAX - 3, u32::MAX - 4, 0]);
+    assert_eq!(below(&mut rng, 6), 5);
+    assert_eq!(rng.calls, 3);
+}
+
+#[test]
+fn powers_of_two_and_one() {
+    let mut rng = Scripted::new(&[u32::MAX - 4]);
+    assert_eq!(below(&mut rng, 4), 3);
+    assert_eq!(below(&mut rng, 1), 0);
+}
+
+#[test]
+fn every_value_equally_often() {
+    // One full block of each residue: exactly uniform
+    let values: Vec<u32> = (0..600).collect();
+    let mut rng = Scripted::new(&values);
+    let mut counts = [0; 6];
+    for _ in 0..600 {
+        counts[roll(&mut rng, 6) as usize - 1] += 1;
+    }
+    assert_eq!(counts, [100; 6]);
+}
+
+#[test]
+#[should_panic(expected = "empty range")]
+fn empty_range_panics() {
+    below(&mut Scripted::new(&[0]), 0);
+}
+
+#[test]
+fn faces_have_the_right_number_of_pips() {
+    for value in 1..=6 {
+        let pips: u32 = face(value).iter().flatten().map(|&led| u32::from(led)).sum();
+        assert_eq!(pips, value);
+    }
+    assert_eq!(face(0), [[0; 5]; 5]);
+    assert_eq!(face(7), [[0; 5]; 5]);
+}
+
+#[test]
+fn faces_are_symmetric() {
+    // A die face looks the same turned half way round
+    for value in 1..=6 {
+        let leds = face(value);
+        for row in 0..5 {
+            for column in 0..5 {
+                assert_eq!(leds[row][column], leds[4 - row][4 - column], "face {}", value);
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Host tests for the entropy pool and `PoolRng`, with a fake RNG that fills the pool the way the interrupt does
+
+use example_17_random::pool::{EntropyPool, EntropySource, PoolRng};
+use rand_core::{CryptoRng, RngCore};
+
+/// Stands in for the RNG peripheral and its interrupt: while running, every `wait` delivers `burst` bytes of a
+/// counting sequence into the pool, stopping when the pool is full
+struct FakeRng<const N: usize> {
+    pool: EntropyPool<N>,
+    running: bool,
+    next: u8,
+    burst: usize,
+    starts: usize,
+    waits: usize,
+}
+
+impl<const N: usize> FakeRng<N> {
+    fn new(burst: usize) -> Self {
+        Self {
+            pool: EntropyPool::new(),
+            running: true,
+            next: 0,
+            burst,
+            starts: 1,
+            waits: 0,
+        }
+    }
+
+    /// What the VALRDY interrupt does with each byte
+    fn interrupt(&mut self) {
+        if self.running && !self.pool.push(self.next) {
+            self.running = false;
+        }
+        self.next = self.next.wrapping_add(1);
+    }
+}
+
+impl<const N: usize> EntropySource for FakeRng<N> {
+    fn take(&mut self, dest: &mut [u8]) -> usize {
+        let count = self.pool.take(dest);
+        if self.pool.needs_refill() && !self.running {
+            self.running = true;
+            self.starts += 1;
+        }
+        count
+    }
+
+    fn wait(&mut self) {
+        self.waits += 1;
+        for _ in 0..self.burst {
+            if !self.running {
+                break;
+            }
+            self.interrupt();
+        }
+    }
+}
+
+#[test]
+fn push_until_full() {
+    let mut pool = EntropyPool::<4>::new();
+    assert!(pool.is_empty());
+    assert!(pool.push(1));
+    assert!(pool.push(2));
+    assert!(pool.push(3));
+    assert!(!pool.push(4), "the fourth byte fills the pool");
+    assert!(pool.is_full());
+    assert!(!pool.push(5), "dropped");
+    assert_eq!(pool.len(), 4);
+
+    let mut out = [0; 8];
+    assert_eq!(pool.take(&mut out), 4);
+    assert_eq!(out[..4], [1, 2, 3, 4]);
+}
+
+#[test]
+fn take_wraps_around() {
+    let mut pool = EntropyPool::<4>::new();
+    for byte in 1..=3 {
+        pool.push(byte);
+    }
+    let mut out = [0; 2];
+    assert_eq!(pool.take(&mut out), 2);
+    assert_eq!(out, [1, 2]);
+
+    for byte in 4..=6 {
+        pool.push(byte);
+    }
+    let mut out = [0; 4];
+    assert_eq!(pool.take(&mut out), 4);
+    assert_eq!(out, [3, 4, 5, 6]);
+    assert_eq!(pool.take(&mut out), 0);
+}
+
+#[test]
+fn refill_at_half() {
+    let mut pool = EntropyPool::<8>::new();
+    for byte in 0..8 {
+        pool.push(byte);
+    }
+    assert!(!pool.needs_refill());
+    pool.take(&mut [0; 3]);
+    assert!(!pool.needs_refill(), "5 of 8 left");
+    pool.take(&mut [0; 1]);
+    assert!(pool.needs_refill(), "4 of 8 left");
+}
+
+#[test]
+fn bytes_are_never_handed_out_twice() {
+    let mut rng = PoolRng::new(FakeRng::<16>::new(5));
+    let mut first = [0; 40];
+    rng.fill_bytes(&mut first);
+    let mut second = [0; 40];
+    rng.fill_bytes(&mut second);
+
+    let all: Vec<u8> = first.iter().chain(&second).copied().collect();
+    let expected: Vec<u8> = (0..80).collect();
+    assert_eq!(all, expected);
+}
+
+#[test]
+fn full_pool_answers_without_waiting() {
+    let mut source = FakeRng::<16>::new(16);
+    source.wait();
+    assert!(source.pool.is_full());
+    assert!(!source.running, "stopped when full");
+
+    let mut rng = PoolRng::new(source);
+    assert_eq!(rng.next_u32(), u32::from_le_bytes([0, 1, 2, 3]));
+    assert_eq!(rng.next_u32(), u32::from_le_bytes([4, 5, 6, 7]));
+    let source = rng.release();
+    assert_eq!(source.waits, 1, "only the wait that filled the pool");
+    assert!(source.running, "8 of 16 left: started again");
+    assert_eq!(source.starts, 2);
+}
+
+#[test]
+fn large_requests_wait_for_more() {
+    // The pool holds 8 bytes and the fake delivers 3 per wait, so 32 bytes need several rounds
+    let mut rng = PoolRng::new(FakeRng::<8>::new(3));
+    let mut bytes = [0; 32];
+    rng.fill_bytes(&mut bytes);
+    assert_eq!(bytes[31], 31);
+    assert_eq!(rng.release().waits, 11);
+}
+
+#[test]
+fn next_u64_is_little_endian() {
+    let mut rng = PoolRng::new(FakeRng::<16>::new(16));
+    assert_eq!(rng.next_u64(), 0x0706_0504_0302_0100);
+    let mut bytes = [0xFF; 3];
+    rng.try_fill_bytes(&mut bytes).unwrap();
+    assert_eq!(bytes, [8, 9, 10]);
+}
+
+#[test]
+fn taken_bytes_are_wiped() {
+    let mut pool = EntropyPool::<4>::new();
+    for byte in [0xAA, 0xBB, 0xCC, 0xDD] {
+        pool.push(byte);
+    }
+    pool.take(&mut [0; 4]);
+    let debug = format!("{:?}", pool);
+    assert!(debug.contains("buf: [0, 0, 0, 0]"), "{}", debug);
+}
+
+#[test]
+fn is_a_crypto_rng() {
+    fn assert_crypto<R: RngCore + CryptoRng>(_: &R) {}
+    assert_crypto(&PoolRng::new(FakeRng::<4>::new(1)));
+}
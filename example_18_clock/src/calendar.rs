@@ -0,0 +1,210 @@
+//! Calendar dates and times, converted to and from milliseconds since the Unix epoch (1970-01-01 00:00:00).
+//!
+//! Everything is UTC with the proleptic Gregorian calendar and no leap seconds, which is what a log timestamp
+//! needs. The day conversions are Howard Hinnant's `days_from_civil` / `civil_from_days` algorithms, which work
+//! in 400-year eras so that no loop over years or months is needed.
+
+use core::fmt;
+
+pub const MILLIS_PER_SECOND: i64 = 1000;
+pub const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * MILLIS_PER_SECOND;
+
+/// A date and time of day, to the millisecond
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: i32,
+    /// 1 to 12
+    pub month: u8,
+    /// 1 to 31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Monday => "Mon",
+            Self::Tuesday => "Tue",
+            Self::Wednesday => "Wed",
+            Self::Thursday => "Thu",
+            Self::Friday => "Fri",
+            Self::Saturday => "Sat",
+            Self::Sunday => "Sun",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Why a date and time could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// Not `YYYY-MM-DD HH:MM` or `YYYY-MM-DD HH:MM:SS`
+    Format,
+    /// Well formed, but no such date or time, e.g. February 30th or 24:00
+    OutOfRange,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Format => f.write_str("expected YYYY-MM-DD HH:MM[:SS]"),
+            Self::OutOfRange => f.write_str("no such date or time"),
+        }
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Days in `month` (1 to 12) of `year`
+pub fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date
+fn days_from_civil(year: i32, month: u8, day: u8) -> i64 {
+    // Years start in March, so the leap day is the last day of the year
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    // 719468 days from 0000-03-01 to 1970-01-01
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date of a day since 1970-01-01
+fn civil_from_days(days: i64) -> (i32, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u8;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+impl DateTime {
+    /// The date and time `millis` milliseconds after (or before, if negative) the Unix epoch
+    pub fn from_unix_millis(millis: i64) -> Self {
+        let days = millis.div_euclid(MILLIS_PER_DAY);
+        let of_day = millis.rem_euclid(MILLIS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        let seconds = of_day / MILLIS_PER_SECOND;
+        Self {
+            year,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+            millisecond: (of_day % MILLIS_PER_SECOND) as u16,
+        }
+    }
+
+    /// Milliseconds since the Unix epoch
+    pub fn to_unix_millis(&self) -> i64 {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let seconds = i64::from(self.hour) * 3600 + i64::from(self.minute) * 60 + i64::from(self.second);
+        days * MILLIS_PER_DAY + seconds * MILLIS_PER_SECOND + i64::from(self.millisecond)
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        match days_from_civil(self.year, self.month, self.day).rem_euclid(7) {
+            0 => Weekday::Thursday,
+            1 => Weekday::Friday,
+            2 => Weekday::Saturday,
+            3 => Weekday::Sunday,
+            4 => Weekday::Monday,
+            5 => Weekday::Tuesday,
+            _ => Weekday::Wednesday,
+        }
+    }
+
+    /// Parse `YYYY-MM-DD HH:MM:SS`, with `T` allowed instead of the space (ISO 8601) and the seconds optional
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let (date, time) = text.trim().split_once([' ', 'T']).ok_or(ParseError::Format)?;
+        let mut date = date.split('-');
+        let mut time = time.trim().split(':');
+
+        let year = number(date.next(), 4)?;
+        let month = number(date.next(), 2)?;
+        let day = number(date.next(), 2)?;
+        let hour = number(time.next(), 2)?;
+        let minute = number(time.next(), 2)?;
+        let second = match time.next() {
+            Some(field) => number(Some(field), 2)?,
+            None => 0,
+        };
+        if date.next().is_some() || time.next().is_some() {
+            return Err(ParseError::Format);
+        }
+
+        let year = year as i32;
+        let (month, day) = (month as u8, day as u8);
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(ParseError::OutOfRange);
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(ParseError::OutOfRange);
+        }
+        Ok(Self {
+            year,
+            month,
+            day,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+            millisecond: 0,
+        })
+    }
+}
+
+/// A field of exactly `digits` decimal digits
+fn number(field: Option<&str>, digits: usize) -> Result<u32, ParseError> {
+    let field = field.ok_or(ParseError::Format)?;
+    if field.len() != digits || !field.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(ParseError::Format);
+    }
+    field.parse().map_err(|_| ParseError::Format)
+}
+
+/// `2026-10-18 14:03:05.123`
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.millisecond
+        )
+    }
+}
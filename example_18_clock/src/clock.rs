@@ -0,0 +1,108 @@
+//! Time from the RTC: 24-bit counter values extended to 64-bit ticks, and a wall clock on top.
+//!
+//! The RTC counts at 32.768 kHz into a 24-bit COUNTER, which overflows every 512 seconds. To count further, the
+//! RTC interrupt increments a `period` twice per overflow: at the overflow itself and when the counter passes
+//! half way (a COMPARE event at 0x80_0000). The period's lowest bit then says which half the counter should be
+//! in, and [`extend`] combines the two:
+//!
+//! ```text
+//! counter   0 ─────────── 0x80_0000 ─────────── 0xFF_FFFF │ 0 ──────────── 0x80_0000 ──────────
+//! period    0             1                               │ 2              3
+//!           ▲ even: counter in the lower half              ▲ ... and so on, 2 periods per overflow
+//! ```
+//!
+//! This stays correct when `period` is read just before the interrupt that increments it, which is what makes
+//! reading the time safe without a critical section.
+
+use core::fmt;
+
+use crate::calendar::{DateTime, MILLIS_PER_SECOND};
+
+/// RTC ticks per second, with PRESCALER 0
+pub const TICKS_PER_SECOND: u64 = 32_768;
+
+/// Counter value where the second period of each overflow starts
+pub const HALF_PERIOD: u32 = 1 << 23;
+
+/// 64-bit ticks from the period count and the 24-bit counter, see the module documentation
+pub fn extend(period: u32, counter: u32) -> u64 {
+    // Odd periods start half way through the counter, so flipping its top bit gives the ticks since the period
+    // started. A counter that has already moved into the next half comes out past the end of the period, which
+    // is still the right time.
+    (u64::from(period) << 23) + u64::from((counter & 0xFF_FFFF) ^ ((period & 1) << 23))
+}
+
+/// Milliseconds in `ticks`, rounded down
+pub fn ticks_to_millis(ticks: u64) -> u64 {
+    // 1000 / 32768 = 125 / 4096, smaller numbers for the same result
+    ticks * 125 / 4096
+}
+
+/// Calendar time, once it has been set. Until then there is only the time since reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WallClock {
+    /// The tick count when the time was set, and the time it was set to
+    base: Option<(u64, i64)>,
+}
+
+impl WallClock {
+    pub const fn new() -> Self {
+        Self { base: None }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.base.is_some()
+    }
+
+    /// It is `time` at `ticks`
+    pub fn set(&mut self, ticks: u64, time: DateTime) {
+        self.base = Some((ticks, time.to_unix_millis()));
+    }
+
+    /// Milliseconds since the Unix epoch at `ticks`, if the time has been set
+    pub fn unix_millis(&self, ticks: u64) -> Option<i64> {
+        let (base_ticks, base_millis) = self.base?;
+        // Ticks before the time was set count backwards from it
+        let elapsed = if ticks >= base_ticks {
+            ticks_to_millis(ticks - base_ticks) as i64
+        } else {
+            -(ticks_to_millis(base_ticks - ticks) as i64)
+        };
+        Some(base_millis + elapsed)
+    }
+
+    /// The date and time at `ticks`, if the time has been set
+    pub fn now(&self, ticks: u64) -> Option<DateTime> {
+        self.unix_millis(ticks).map(DateTime::from_unix_millis)
+    }
+
+    /// Timestamp for a log line: the date and time if set, the time since reset otherwise
+    pub fn timestamp(&self, ticks: u64) -> Timestamp {
+        match self.now(ticks) {
+            Some(time) => Timestamp::Wall(time),
+            None => Timestamp::Uptime(ticks_to_millis(ticks)),
+        }
+    }
+}
+
+/// A log line timestamp, see [`WallClock::timestamp`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    Wall(DateTime),
+    /// Milliseconds since reset
+    Uptime(u64),
+}
+
+/// `2026-10-18 14:03:05.123`, or `+      12.345` seconds since reset
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wall(time) => write!(f, "{}", time),
+            Self::Uptime(millis) => {
+                let per_second = MILLIS_PER_SECOND as u64;
+                // Same width as a date and time, so log lines stay aligned once the clock is set
+                write!(f, "+{:18}.{:03}", millis / per_second, millis % per_second)
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Commands typed into the RTT terminal.
+
+use core::fmt;
+
+use crate::calendar::{DateTime, ParseError};
+
+pub const HELP: &str = "Commands:
+  set YYYY-MM-DD HH:MM[:SS]   set the date and time (UTC)
+  time                        show the date, time and uptime
+  help                        this text";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Set(DateTime),
+    Time,
+    Help,
+}
+
+/// Why a line is not a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError<'a> {
+    Unknown(&'a str),
+    BadTime(ParseError),
+}
+
+impl fmt::Display for CommandError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown command '{}', try 'help'", name),
+            Self::BadTime(error) => write!(f, "set: {}", error),
+        }
+    }
+}
+
+impl Command {
+    /// Parse a line; `None` for an empty one
+    pub fn parse(line: &str) -> Option<Result<Self, CommandError<'_>>> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let command = if name.is_empty() {
+            return None;
+        } else if name.eq_ignore_ascii_case("set") {
+            DateTime::parse(argument).map(Self::Set).map_err(CommandError::BadTime)
+        } else if name.eq_ignore_ascii_case("time") {
+            Ok(Self::Time)
+        } else if name.eq_ignore_ascii_case("help") {
+            Ok(Self::Help)
+        } else {
+            Err(CommandError::Unknown(name))
+        };
+        Some(command)
+    }
+}
@@ -0,0 +1,17 @@
+#![no_std]
+
+//! A wall clock on the RTC: low-power 64-bit ticks, settable calendar time and timestamps for log lines.
+//!
+//! - [`calendar`] converts between dates and milliseconds since 1970, and parses and formats them
+//! - [`clock`] extends the 24-bit RTC counter to 64 bits and keeps the wall clock
+//! - [`command`] parses the commands typed into the RTT terminal
+//! - [`rtc`] runs RTC1 from the 32.768 kHz clock (target only)
+//!
+//! `calendar`, `clock` and `command` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod calendar;
+pub mod clock;
+pub mod command;
+
+#[cfg(target_os = "none")]
+pub mod rtc;
@@ -0,0 +1,104 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::fmt::Write,
+    cortex_m_rt::entry,
+    example_18_clock::{
+        clock::{ticks_to_millis, WallClock, TICKS_PER_SECOND},
+        command::{Command, HELP},
+        rtc::{self, MonotonicClock},
+    },
+    microbit::hal::{clocks::Clocks, pac::interrupt},
+    panic_rtt_target as _,
+    rtt_target::{rtt_init, DownChannel, UpChannel},
+};
+
+/// A log line is written this often
+#[cfg(target_os = "none")]
+const LOG_INTERVAL_TICKS: u64 = 10 * TICKS_PER_SECOND;
+/// Longest command line
+#[cfg(target_os = "none")]
+const MAX_LINE: usize = 40;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    let channels = rtt_init! {
+        up:   { 0: { size: 1024, name: "Terminal" } }
+        down: { 0: { size:   64, name: "Terminal" } }
+    };
+    let mut up: UpChannel = channels.up.0;
+    let mut down: DownChannel = channels.down.0;
+
+    let board = microbit::Board::take().unwrap();
+    // The internal 32.768 kHz RC oscillator: the micro:bit v2 has no low-frequency crystal
+    let _clocks = Clocks::new(board.CLOCK).start_lfclk();
+    let clock = MonotonicClock::new(board.RTC1);
+    let mut wall = WallClock::new();
+
+    writeln!(
+        up,
+        "{} Clock started, set the time with 'set YYYY-MM-DD HH:MM:SS'",
+        wall.timestamp(clock.now())
+    )
+    .ok();
+
+    let mut next_log = LOG_INTERVAL_TICKS;
+    let mut line = [0u8; MAX_LINE];
+    let mut len = 0;
+    let mut buf = [0u8; 16];
+    loop {
+        let now = clock.now();
+        if now >= next_log {
+            writeln!(up, "{} Still here", wall.timestamp(now)).ok();
+            next_log += LOG_INTERVAL_TICKS;
+        }
+
+        // Collect typed characters until Enter
+        let count = down.read(&mut buf);
+        for &byte in &buf[..count] {
+            if byte != b'\n' && byte != b'\r' {
+                if len < MAX_LINE {
+                    line[len] = byte;
+                    len += 1;
+                }
+                continue;
+            }
+            let text = core::str::from_utf8(&line[..len]).unwrap_or("");
+            let now = clock.now();
+            match Command::parse(text) {
+                Some(Ok(Command::Set(time))) => {
+                    wall.set(now, time);
+                    writeln!(up, "{} Clock set", wall.timestamp(now)).ok();
+                }
+                Some(Ok(Command::Time)) => {
+                    let uptime = ticks_to_millis(now) / 1000;
+                    match wall.now(now) {
+                        Some(time) => writeln!(up, "{} {}, up {} s", time.weekday(), time, uptime).ok(),
+                        None => writeln!(up, "Time not set, up {} s", uptime).ok(),
+                    };
+                }
+                Some(Ok(Command::Help)) => {
+                    writeln!(up, "{}", HELP).ok();
+                }
+                Some(Err(error)) => {
+                    writeln!(up, "{}", error).ok();
+                }
+                None => {}
+            }
+            len = 0;
+        }
+    }
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn RTC1() {
+    rtc::on_interrupt();
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,67 @@
+//! RTC1 as a 64-bit monotonic clock, counting the 32.768 kHz low-frequency clock.
+//!
+//! The RTC keeps running in System ON sleep and draws well under a microamp, unlike the TIMERs, which need the
+//! 16 MHz clock. The interrupt only fires twice every 512 seconds, to extend the 24-bit counter, see
+//! [`crate::clock`].
+
+use core::sync::atomic::{compiler_fence, AtomicU32, Ordering};
+
+use microbit::hal::pac::{self, RTC1};
+
+use crate::clock::{extend, HALF_PERIOD};
+
+// INTENSET / EVTENSET register bits
+const INTERRUPT_OVRFLW: u32 = 1 << 1;
+const INTERRUPT_COMPARE0: u32 = 1 << 16;
+
+/// Half overflows so far, incremented by [`on_interrupt`]
+static PERIOD: AtomicU32 = AtomicU32::new(0);
+
+/// The running RTC1. LFCLK must be started first.
+pub struct MonotonicClock {
+    rtc: RTC1,
+}
+
+impl MonotonicClock {
+    /// Start counting from 0 at 32.768 kHz. Call [`on_interrupt`] from the RTC1 interrupt.
+    pub fn new(rtc: RTC1) -> Self {
+        rtc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        rtc.tasks_clear.write(|w| unsafe { w.bits(1) });
+        rtc.prescaler.write(|w| unsafe { w.bits(0) });
+        rtc.cc[0].write(|w| unsafe { w.bits(HALF_PERIOD) });
+        rtc.events_ovrflw.write(|w| unsafe { w.bits(0) });
+        rtc.events_compare[0].write(|w| unsafe { w.bits(0) });
+        rtc.intenset
+            .write(|w| unsafe { w.bits(INTERRUPT_OVRFLW | INTERRUPT_COMPARE0) });
+        PERIOD.store(0, Ordering::Relaxed);
+
+        unsafe {
+            pac::NVIC::unmask(pac::Interrupt::RTC1);
+        }
+        rtc.tasks_start.write(|w| unsafe { w.bits(1) });
+        Self { rtc }
+    }
+
+    /// Ticks since [`new`](Self::new), 32768 per second
+    pub fn now(&self) -> u64 {
+        // The period first: if the interrupt increments it after this, `extend` still gets it right
+        let period = PERIOD.load(Ordering::Relaxed);
+        compiler_fence(Ordering::Acquire);
+        let counter = self.rtc.counter.read().bits();
+        extend(period, counter)
+    }
+}
+
+/// Call from the RTC1 interrupt
+pub fn on_interrupt() {
+    // Only the events are touched here, `MonotonicClock` owns the rest
+    let rtc = unsafe { &*RTC1::ptr() };
+    if rtc.events_ovrflw.read().bits() != 0 {
+        rtc.events_ovrflw.write(|w| unsafe { w.bits(0) });
+        PERIOD.store(PERIOD.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+    }
+    if rtc.events_compare[0].read().bits() != 0 {
+        rtc.events_compare[0].write(|w| unsafe { w.bits(0) });
+        PERIOD.store(PERIOD.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,143 @@
+//! Host tests for date and time conversion, parsing and formatting
+
+use example_18_clock::calendar::{days_in_month, is_leap_year, DateTime, ParseError, Weekday, MILLIS_PER_DAY};
+
+fn date_time(year: i32, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+    DateTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        millisecond: 0,
+    }
+}
+
+#[test]
+fn epoch() {
+    assert_eq!(DateTime::from_unix_millis(0), date_time(1970, 1, 1, 0, 0, 0));
+    assert_eq!(date_time(1970, 1, 1, 0, 0, 0).to_unix_millis(), 0);
+}
+
+#[test]
+fn known_timestamps() {
+    // Values from `date -u -d @<seconds>`
+    let cases = [
+        (951_782_400, date_time(2000, 2, 29, 0, 0, 0)),
+        (1_234_567_890, date_time(2009, 2, 13, 23, 31, 30)),
+        (1_709_251_199, date_time(2024, 2, 29, 23, 59, 59)),
+        (2_147_483_647, date_time(2038, 1, 19, 3, 14, 7)),
+        (4_102_444_800, date_time(2100, 1, 1, 0, 0, 0)),
+    ];
+    for (seconds, expected) in cases {
+        assert_eq!(DateTime::from_unix_millis(seconds * 1000), expected, "{}", seconds);
+        assert_eq!(expected.to_unix_millis(), seconds * 1000, "{}", expected);
+    }
+}
+
+#[test]
+fn before_the_epoch() {
+    assert_eq!(
+        DateTime::from_unix_millis(-1),
+        DateTime {
+            millisecond: 999,
+            ..date_time(1969, 12, 31, 23, 59, 59)
+        }
+    );
+    assert_eq!(date_time(1900, 1, 1, 0, 0, 0).to_unix_millis(), -2_208_988_800_000);
+}
+
+#[test]
+fn round_trip_every_day_for_800_years() {
+    // Two 400-year eras, with 1 ms and 23:59:59.999 in the day
+    let first = date_time(1800, 1, 1, 0, 0, 0).to_unix_millis() / MILLIS_PER_DAY;
+    let last = date_time(2600, 1, 1, 0, 0, 0).to_unix_millis() / MILLIS_PER_DAY;
+    let mut previous = DateTime::from_unix_millis((first - 1) * MILLIS_PER_DAY);
+    for day in first..last {
+        for millis in [1, MILLIS_PER_DAY - 1] {
+            let time = DateTime::from_unix_millis(day * MILLIS_PER_DAY + millis);
+            assert_eq!(time.to_unix_millis(), day * MILLIS_PER_DAY + millis);
+        }
+        let date = DateTime::from_unix_millis(day * MILLIS_PER_DAY);
+        assert!(date > previous);
+        assert!(date.day >= 1 && date.day <= days_in_month(date.year, date.month));
+        previous = date;
+    }
+}
+
+#[test]
+fn leap_years() {
+    assert!(is_leap_year(2024));
+    assert!(is_leap_year(2000));
+    assert!(!is_leap_year(1900));
+    assert!(!is_leap_year(2023));
+    assert_eq!(days_in_month(2024, 2), 29);
+    assert_eq!(days_in_month(2100, 2), 28);
+    assert_eq!(days_in_month(2026, 4), 30);
+    assert_eq!(days_in_month(2026, 12), 31);
+}
+
+#[test]
+fn weekdays() {
+    assert_eq!(date_time(1970, 1, 1, 0, 0, 0).weekday(), Weekday::Thursday);
+    assert_eq!(date_time(2000, 1, 1, 12, 0, 0).weekday(), Weekday::Saturday);
+    assert_eq!(date_time(2026, 10, 18, 0, 0, 0).weekday(), Weekday::Sunday);
+    assert_eq!(date_time(1969, 12, 29, 0, 0, 0).weekday(), Weekday::Monday);
+    assert_eq!(Weekday::Wednesday.to_string(), "Wed");
+}
+
+#[test]
+fn display() {
+    let time = DateTime {
+        millisecond: 7,
+        ..date_time(2026, 3, 5, 9, 4, 0)
+    };
+    assert_eq!(time.to_string(), "2026-03-05 09:04:00.007");
+}
+
+#[test]
+fn parse() {
+    let expected = date_time(2026, 10, 18, 14, 3, 5);
+    assert_eq!(DateTime::parse("2026-10-18 14:03:05"), Ok(expected));
+    assert_eq!(DateTime::parse("2026-10-18T14:03:05"), Ok(expected));
+    assert_eq!(DateTime::parse("  2026-10-18 14:03:05 "), Ok(expected));
+    assert_eq!(
+        DateTime::parse("2026-10-18 14:03"),
+        Ok(date_time(2026, 10, 18, 14, 3, 0))
+    );
+}
+
+#[test]
+fn parse_rejects_bad_formats() {
+    for text in [
+        "",
+        "2026-10-18",
+        "26-10-18 14:03",
+        "2026-10-18 14",
+        "2026-10-18 14:03:05:00",
+        "2026-10-18-01 14:03",
+        "2026/10/18 14:03",
+        "2026-1-18 14:03",
+        "2026-10-18 +4:03",
+    ] {
+        assert_eq!(DateTime::parse(text), Err(ParseError::Format), "{:?}", text);
+    }
+}
+
+#[test]
+fn parse_rejects_impossible_dates() {
+    for text in [
+        "2026-13-01 00:00",
+        "2026-00-01 00:00",
+        "2026-02-29 00:00",
+        "2026-04-31 00:00",
+        "2026-10-00 00:00",
+        "2026-10-18 24:00",
+        "2026-10-18 12:60",
+        "2026-10-18 12:00:60",
+    ] {
+        assert_eq!(DateTime::parse(text), Err(ParseError::OutOfRange), "{:?}", text);
+    }
+    assert!(DateTime::parse("2024-02-29 00:00").is_ok());
+}
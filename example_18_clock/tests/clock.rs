@@ -0,0 +1,115 @@
+//! Host tests for tick extension and the wall clock, with a simulated RTC
+
+use example_18_clock::{
+    calendar::DateTime,
+    clock::{extend, ticks_to_millis, Timestamp, WallClock, HALF_PERIOD, TICKS_PER_SECOND},
+};
+
+const COUNTER_MASK: u64 = 0xFF_FFFF;
+
+/// The period count the RTC1 interrupt has reached after `ticks`: one per COMPARE0 and one per overflow
+fn period_at(ticks: u64) -> u32 {
+    (ticks / u64::from(HALF_PERIOD)) as u32
+}
+
+#[test]
+fn extends_across_many_overflows() {
+    // Around every half period and overflow for the first 64, and a long way in
+    let edges = (0..128u64).chain([u64::from(u32::MAX) - 2, u64::from(u32::MAX) - 1]);
+    for edge in edges {
+        let middle = edge * u64::from(HALF_PERIOD);
+        for ticks in middle.saturating_sub(3)..middle + 3 {
+            let counter = (ticks & COUNTER_MASK) as u32;
+            assert_eq!(extend(period_at(ticks), counter), ticks, "{:#x}", ticks);
+        }
+    }
+}
+
+#[test]
+fn period_read_just_before_the_interrupt() {
+    // `now` reads the period first: the counter may have moved into the next half before the interrupt has
+    // incremented it
+    for half in 1..64u64 {
+        let edge = half * u64::from(HALF_PERIOD);
+        for ticks in edge..edge + 5 {
+            let counter = (ticks & COUNTER_MASK) as u32;
+            let stale_period = period_at(edge) - 1;
+            assert_eq!(extend(stale_period, counter), ticks, "{:#x}", ticks);
+        }
+    }
+}
+
+#[test]
+fn only_the_24_counter_bits_count() {
+    assert_eq!(extend(2, 0xFF00_0005), extend(2, 5));
+}
+
+#[test]
+fn never_goes_backwards() {
+    let mut last = 0;
+    for ticks in (0..4 * (COUNTER_MASK + 1)).step_by(4093) {
+        let now = extend(period_at(ticks), (ticks & COUNTER_MASK) as u32);
+        assert!(now >= last);
+        last = now;
+    }
+}
+
+#[test]
+fn ticks_in_milliseconds() {
+    assert_eq!(ticks_to_millis(0), 0);
+    assert_eq!(ticks_to_millis(TICKS_PER_SECOND), 1000);
+    assert_eq!(ticks_to_millis(32), 0, "0.98 ms");
+    assert_eq!(ticks_to_millis(33), 1);
+    assert_eq!(
+        ticks_to_millis(TICKS_PER_SECOND * 86_400 * 365 * 100),
+        3_153_600_000_000
+    );
+}
+
+fn at(text: &str) -> DateTime {
+    DateTime::parse(text).unwrap()
+}
+
+#[test]
+fn wall_clock_runs_from_where_it_was_set() {
+    let mut wall = WallClock::new();
+    assert!(!wall.is_set());
+    assert_eq!(wall.now(1234), None);
+
+    let set_at = 100 * TICKS_PER_SECOND;
+    wall.set(set_at, at("2026-12-31 23:59:58"));
+    assert!(wall.is_set());
+    assert_eq!(wall.now(set_at), Some(at("2026-12-31 23:59:58")));
+    assert_eq!(wall.now(set_at + 2 * TICKS_PER_SECOND), Some(at("2027-01-01 00:00:00")));
+    let half_second = wall.now(set_at + TICKS_PER_SECOND / 2).unwrap();
+    assert_eq!(half_second.millisecond, 500);
+    // Earlier ticks count backwards
+    assert_eq!(
+        wall.now(set_at - 60 * TICKS_PER_SECOND),
+        Some(at("2026-12-31 23:58:58"))
+    );
+}
+
+#[test]
+fn setting_again_replaces_the_time() {
+    let mut wall = WallClock::new();
+    wall.set(0, at("2026-01-01 00:00"));
+    wall.set(10 * TICKS_PER_SECOND, at("2026-06-01 12:00"));
+    assert_eq!(wall.now(10 * TICKS_PER_SECOND), Some(at("2026-06-01 12:00")));
+}
+
+#[test]
+fn timestamps() {
+    let mut wall = WallClock::new();
+    let ticks = 12 * TICKS_PER_SECOND + TICKS_PER_SECOND / 4;
+    assert_eq!(wall.timestamp(ticks), Timestamp::Uptime(12_250));
+    assert_eq!(wall.timestamp(ticks).to_string(), "+                12.250");
+
+    wall.set(ticks, at("2026-10-18 14:03:05"));
+    assert_eq!(wall.timestamp(ticks).to_string(), "2026-10-18 14:03:05.000");
+    assert_eq!(
+        wall.timestamp(ticks).to_string().len(),
+        Timestamp::Uptime(0).to_string().len(),
+        "log lines line up"
+    );
+}
@@ -0,0 +1,30 @@
+//! Host tests for the RTT terminal commands
+
+use example_18_clock::{
+    calendar::{DateTime, ParseError},
+    command::{Command, CommandError},
+};
+
+#[test]
+fn commands() {
+    assert_eq!(Command::parse("time"), Some(Ok(Command::Time)));
+    assert_eq!(Command::parse(" HELP "), Some(Ok(Command::Help)));
+    assert_eq!(
+        Command::parse("set 2026-10-18 14:03:05"),
+        Some(Ok(Command::Set(DateTime::parse("2026-10-18 14:03:05").unwrap())))
+    );
+    assert_eq!(Command::parse(""), None);
+    assert_eq!(Command::parse("   "), None);
+}
+
+#[test]
+fn errors() {
+    assert_eq!(Command::parse("reset"), Some(Err(CommandError::Unknown("reset"))));
+    assert_eq!(
+        Command::parse("set"),
+        Some(Err(CommandError::BadTime(ParseError::Format)))
+    );
+    let error = Command::parse("set 2026-02-30 10:00").unwrap().unwrap_err();
+    assert_eq!(error, CommandError::BadTime(ParseError::OutOfRange));
+    assert_eq!(error.to_string(), "set: no such date or time");
+}
@@ -0,0 +1,102 @@
+//! Watchdog settings and the register values they turn into.
+//!
+//! The WDT counts down from CRV at 32.768 kHz. Every enabled reload request channel (RR[0] to RR[7]) has to be
+//! written with [`RELOAD_VALUE`] before it reaches 0, otherwise the chip resets. Giving each task its own
+//! channel means a single task that hangs is enough to trigger the reset, even while the others keep running.
+
+use core::fmt;
+
+/// Magic value that reloads a channel; anything else is ignored
+pub const RELOAD_VALUE: u32 = 0x6E52_4635;
+
+/// Reload request channels in the WDT
+pub const MAX_CHANNELS: usize = 8;
+
+/// LFCLK ticks per second, the watchdog's clock
+pub const TICKS_PER_SECOND: u64 = 32_768;
+
+/// Smallest CRV the WDT accepts
+pub const MIN_CRV: u32 = 0xF;
+
+// CONFIG register bits
+const CONFIG_SLEEP_RUN: u32 = 1 << 0;
+const CONFIG_HALT_RUN: u32 = 1 << 3;
+
+/// Why a configuration cannot be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Shorter than 16 ticks (0.49 ms)
+    TimeoutTooShort,
+    /// Longer than 2³² ticks (36.4 hours)
+    TimeoutTooLong,
+    /// 0 or more than 8 reload channels
+    Channels(usize),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimeoutTooShort => f.write_str("timeout too short, at least 1 ms"),
+            Self::TimeoutTooLong => f.write_str("timeout too long, at most 36 hours"),
+            Self::Channels(count) => write!(f, "{} reload channels, must be 1 to 8", count),
+        }
+    }
+}
+
+/// How the watchdog should run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Time allowed between reloads
+    pub timeout_ms: u32,
+    /// Keep counting while the CPU sleeps (WFI/WFE). Without it, a program that sleeps forever never resets.
+    pub run_in_sleep: bool,
+    /// Keep counting while the debugger has halted the CPU. Without it, stopping at a breakpoint does not reset
+    /// the chip.
+    pub run_when_halted: bool,
+}
+
+impl Config {
+    /// Counting in sleep, paused while halted by the debugger
+    pub const fn new(timeout_ms: u32) -> Self {
+        Self {
+            timeout_ms,
+            run_in_sleep: true,
+            run_when_halted: false,
+        }
+    }
+
+    /// The CRV register: the timeout is (CRV + 1) / 32768 seconds
+    pub fn crv(&self) -> Result<u32, ConfigError> {
+        let ticks = u64::from(self.timeout_ms) * TICKS_PER_SECOND / 1000;
+        let crv = ticks.checked_sub(1).ok_or(ConfigError::TimeoutTooShort)?;
+        if crv < u64::from(MIN_CRV) {
+            return Err(ConfigError::TimeoutTooShort);
+        }
+        u32::try_from(crv).map_err(|_| ConfigError::TimeoutTooLong)
+    }
+
+    /// The CONFIG register
+    pub fn config_bits(&self) -> u32 {
+        let mut bits = 0;
+        if self.run_in_sleep {
+            bits |= CONFIG_SLEEP_RUN;
+        }
+        if self.run_when_halted {
+            bits |= CONFIG_HALT_RUN;
+        }
+        bits
+    }
+}
+
+/// The RREN register enabling the first `count` reload channels
+pub fn rren_bits(count: usize) -> Result<u32, ConfigError> {
+    if count == 0 || count > MAX_CHANNELS {
+        return Err(ConfigError::Channels(count));
+    }
+    Ok((1 << count) - 1)
+}
+
+/// The timeout in milliseconds that a CRV value gives, rounded down
+pub fn timeout_ms(crv: u32) -> u64 {
+    (u64::from(crv) + 1) * 1000 / TICKS_PER_SECOND
+}
@@ -0,0 +1,15 @@
+#![no_std]
+
+//! A watchdog that resets the micro:bit when a task hangs, and a report of why the last reset happened.
+//!
+//! - [`config`] turns a timeout and sleep/debug options into WDT register values
+//! - [`reset`] decodes the POWER peripheral's RESETREAS register
+//! - [`wdt`] starts the WDT with one reload channel per task and reads the reset reason (target only)
+//!
+//! `config` and `reset` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod config;
+pub mod reset;
+
+#[cfg(target_os = "none")]
+pub mod wdt;
@@ -0,0 +1,116 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::cell::RefCell,
+    cortex_m::interrupt::{free, Mutex},
+    cortex_m_rt::entry,
+    embedded_hal::{
+        delay::DelayNs,
+        digital::{InputPin, OutputPin, StatefulOutputPin},
+    },
+    example_19_watchdog::{
+        config::{timeout_ms, Config},
+        reset::Cause,
+        wdt::{self, Reload},
+    },
+    microbit::hal::{
+        gpio::{Level, Output, Pin, PushPull},
+        pac::{self, interrupt, TIMER1},
+        timer::Periodic,
+        Timer,
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// Reset if any task has not checked in for this long
+#[cfg(target_os = "none")]
+const TIMEOUT_MS: u32 = 2000;
+/// The blink task runs every 250 ms (TIMER1 counts at 1 MHz)
+#[cfg(target_os = "none")]
+const BLINK_PERIOD_US: u32 = 250_000;
+
+/// The blink task: an LED toggled from the TIMER1 interrupt, with its own watchdog channel
+#[cfg(target_os = "none")]
+struct Blink {
+    timer: Timer<TIMER1, Periodic>,
+    led: Pin<Output<PushPull>>,
+    reload: Reload,
+}
+
+#[cfg(target_os = "none")]
+static BLINK: Mutex<RefCell<Option<Blink>>> = Mutex::new(RefCell::new(None));
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    let reasons = wdt::take_reset_reasons(&board.POWER);
+    rprintln!("Reset reason: {}", reasons);
+    if reasons.contains(Cause::Watchdog) {
+        rprintln!("  The watchdog caught a task that stopped checking in");
+    }
+
+    // Channel 0 for the main loop, channel 1 for the blink task
+    let [mut main_reload, blink_reload] = wdt::start::<2>(board.WDT, &Config::new(TIMEOUT_MS)).unwrap();
+    rprintln!("Watchdog running: {} ms, 2 channels", timeout_ms(wdt::running_crv()));
+    rprintln!("A: hang the main loop, B: stop the blink task. Either resets the micro:bit");
+
+    let _col1 = board.display_pins.col1.into_push_pull_output(Level::Low);
+    let mut blink_timer = Timer::periodic(board.TIMER1);
+    blink_timer.enable_interrupt();
+    blink_timer.start(BLINK_PERIOD_US);
+    let blink = Blink {
+        timer: blink_timer,
+        led: board.display_pins.row1.into_push_pull_output(Level::Low).degrade(),
+        reload: blink_reload,
+    };
+    free(|cs| BLINK.borrow(cs).replace(Some(blink)));
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::TIMER1);
+    }
+
+    let mut timer0 = Timer::new(board.TIMER0);
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_b = board.buttons.button_b.into_floating_input();
+
+    loop {
+        main_reload.feed();
+
+        if button_a.is_low().unwrap() {
+            rprintln!("Main loop hanging: the blink task still checks in, but channel 0 does not");
+            #[allow(clippy::empty_loop)]
+            loop {}
+        }
+        if button_b.is_low().unwrap() {
+            rprintln!("Blink task stopped: the main loop still checks in, but channel 1 does not");
+            pac::NVIC::mask(pac::Interrupt::TIMER1);
+        }
+
+        timer0.delay_ms(100);
+    }
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn TIMER1() {
+    free(|cs| {
+        if let Some(blink) = BLINK.borrow(cs).borrow_mut().as_mut() {
+            blink.timer.reset_event();
+            if blink.led.is_set_high().unwrap() {
+                blink.led.set_low().unwrap();
+            } else {
+                blink.led.set_high().unwrap();
+            }
+            blink.reload.feed();
+        }
+    });
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
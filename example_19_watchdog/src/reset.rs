@@ -0,0 +1,121 @@
+//! Why the chip last reset, from the POWER peripheral's RESETREAS register.
+//!
+//! Each reset source sets its own bit, and the bits stay set (even across further resets) until software clears
+//! them, so more than one can show up. No bits at all means a power-on or brown-out reset, which clears the
+//! register itself.
+
+use core::fmt;
+
+/// A reset source with its own RESETREAS bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    /// The reset pin, e.g. the button on the back of the micro:bit or the debugger
+    Pin,
+    Watchdog,
+    /// `SCB::sys_reset()` (AIRCR.SYSRESETREQ)
+    Soft,
+    /// The CPU locked up, e.g. a fault inside the HardFault handler
+    Lockup,
+    /// Woken from System OFF by a GPIO DETECT signal
+    WakeGpio,
+    /// Woken from System OFF by the LPCOMP
+    WakeLpcomp,
+    /// Woken from System OFF by the debug interface
+    WakeDebug,
+    /// Woken from System OFF by the NFC field
+    WakeNfc,
+    /// Woken from System OFF by VBUS (USB) rising
+    WakeVbus,
+}
+
+impl Cause {
+    pub const ALL: [Cause; 9] = [
+        Cause::Pin,
+        Cause::Watchdog,
+        Cause::Soft,
+        Cause::Lockup,
+        Cause::WakeGpio,
+        Cause::WakeLpcomp,
+        Cause::WakeDebug,
+        Cause::WakeNfc,
+        Cause::WakeVbus,
+    ];
+
+    /// The RESETREAS bit
+    pub const fn bit(self) -> u32 {
+        match self {
+            Cause::Pin => 1 << 0,
+            Cause::Watchdog => 1 << 1,
+            Cause::Soft => 1 << 2,
+            Cause::Lockup => 1 << 3,
+            Cause::WakeGpio => 1 << 16,
+            Cause::WakeLpcomp => 1 << 17,
+            Cause::WakeDebug => 1 << 18,
+            Cause::WakeNfc => 1 << 19,
+            Cause::WakeVbus => 1 << 20,
+        }
+    }
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Cause::Pin => "reset pin",
+            Cause::Watchdog => "watchdog",
+            Cause::Soft => "soft reset",
+            Cause::Lockup => "CPU lockup",
+            Cause::WakeGpio => "wake from System OFF by GPIO",
+            Cause::WakeLpcomp => "wake from System OFF by LPCOMP",
+            Cause::WakeDebug => "wake from System OFF by the debugger",
+            Cause::WakeNfc => "wake from System OFF by NFC",
+            Cause::WakeVbus => "wake from System OFF by VBUS",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The contents of RESETREAS, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetReasons {
+    bits: u32,
+}
+
+impl ResetReasons {
+    pub const fn from_bits(bits: u32) -> Self {
+        Self { bits }
+    }
+
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    pub fn contains(self, cause: Cause) -> bool {
+        self.bits & cause.bit() != 0
+    }
+
+    /// No reset source bits: the power was switched on or dipped too low
+    pub fn is_power_on(self) -> bool {
+        self.causes().next().is_none()
+    }
+
+    /// The causes in the register, in bit order
+    pub fn causes(self) -> impl Iterator<Item = Cause> {
+        Cause::ALL.into_iter().filter(move |&cause| self.contains(cause))
+    }
+}
+
+/// `watchdog`, `reset pin, soft reset` or `power-on or brown-out`
+impl fmt::Display for ResetReasons {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_power_on() {
+            return f.write_str("power-on or brown-out");
+        }
+        for (index, cause) in self.causes().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", cause)?;
+        }
+        Ok(())
+    }
+}
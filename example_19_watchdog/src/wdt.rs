@@ -0,0 +1,76 @@
+//! The WDT peripheral, with one reload handle per task, and the reset reason from the POWER peripheral.
+//!
+//! Once started, the watchdog cannot be stopped or reconfigured by anything but a reset. Only a power-on, pin or
+//! watchdog reset stops it: after a soft reset or a lockup it is still running, with the old configuration.
+
+use microbit::hal::pac::{POWER, WDT};
+
+use crate::{
+    config::{rren_bits, Config, ConfigError, RELOAD_VALUE},
+    reset::ResetReasons,
+};
+
+/// Why the watchdog could not be started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Config(ConfigError),
+    /// Still running from before a soft reset, with a different number of reload channels
+    AlreadyRunning {
+        rren: u32,
+    },
+}
+
+/// Reloads one channel of the running watchdog. Give one to each task that must keep running.
+pub struct Reload {
+    channel: usize,
+}
+
+impl Reload {
+    /// "This task is still alive". The watchdog restarts its countdown once every channel has been reloaded.
+    pub fn feed(&mut self) {
+        // Only this channel's RR register is written, and it is write-only
+        let wdt = unsafe { &*WDT::ptr() };
+        wdt.rr[self.channel].write(|w| unsafe { w.bits(RELOAD_VALUE) });
+    }
+
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+}
+
+/// Start the watchdog with `N` reload channels and return their handles.
+///
+/// After a soft reset or lockup the watchdog is still running: its handles are returned as long as it has the
+/// same number of channels, but the timeout stays the one it was started with.
+pub fn start<const N: usize>(wdt: WDT, config: &Config) -> Result<[Reload; N], Error> {
+    let rren = rren_bits(N).map_err(Error::Config)?;
+    if wdt.runstatus.read().bits() != 0 {
+        let running = wdt.rren.read().bits();
+        if running != rren {
+            return Err(Error::AlreadyRunning { rren: running });
+        }
+    } else {
+        let crv = config.crv().map_err(Error::Config)?;
+        wdt.crv.write(|w| unsafe { w.bits(crv) });
+        wdt.config.write(|w| unsafe { w.bits(config.config_bits()) });
+        wdt.rren.write(|w| unsafe { w.bits(rren) });
+        wdt.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+    // The WDT is taken by value so it is only started once; from here on the handles only write RR registers
+    Ok(core::array::from_fn(|channel| Reload { channel }))
+}
+
+/// The timeout the running watchdog was configured with, as CRV
+pub fn running_crv() -> u32 {
+    // CRV is only read
+    let wdt = unsafe { &*WDT::ptr() };
+    wdt.crv.read().bits()
+}
+
+/// Read why the chip reset, and clear the register so the next reset starts afresh
+pub fn take_reset_reasons(power: &POWER) -> ResetReasons {
+    let bits = power.resetreas.read().bits();
+    // Bits are cleared by writing 1 to them
+    power.resetreas.write(|w| unsafe { w.bits(bits) });
+    ResetReasons::from_bits(bits)
+}
@@ -0,0 +1,68 @@
+//! Host tests for the watchdog settings
+
+use example_19_watchdog::config::{rren_bits, timeout_ms, Config, ConfigError, MIN_CRV};
+
+#[test]
+fn timeout_to_crv() {
+    // (CRV + 1) / 32768 seconds
+    assert_eq!(Config::new(1000).crv(), Ok(32_767));
+    assert_eq!(Config::new(2000).crv(), Ok(65_535));
+    assert_eq!(Config::new(1).crv(), Ok(31));
+    assert_eq!(Config::new(250).crv(), Ok(8191));
+}
+
+#[test]
+fn crv_back_to_timeout() {
+    for ms in [1, 10, 250, 2000, 60_000, 3_600_000] {
+        let crv = Config::new(ms).crv().unwrap();
+        let actual = timeout_ms(crv);
+        assert!(
+            actual <= u64::from(ms) && actual + 1 >= u64::from(ms),
+            "{} ms -> {}",
+            ms,
+            actual
+        );
+    }
+    assert_eq!(timeout_ms(MIN_CRV), 0, "16 ticks, 0.49 ms");
+}
+
+#[test]
+fn timeout_limits() {
+    assert_eq!(Config::new(0).crv(), Err(ConfigError::TimeoutTooShort));
+    // 36.4 hours is the longest
+    assert!(Config::new(36 * 3600 * 1000).crv().is_ok());
+    assert_eq!(Config::new(37 * 3600 * 1000).crv(), Err(ConfigError::TimeoutTooLong));
+    assert_eq!(Config::new(u32::MAX).crv(), Err(ConfigError::TimeoutTooLong));
+}
+
+#[test]
+fn sleep_and_debug_options() {
+    let config = Config::new(1000);
+    assert_eq!(config.config_bits(), 0b0001, "runs in sleep, pauses when halted");
+
+    let config = Config {
+        run_in_sleep: false,
+        ..config
+    };
+    assert_eq!(config.config_bits(), 0b0000);
+
+    let config = Config {
+        run_in_sleep: true,
+        run_when_halted: true,
+        ..config
+    };
+    assert_eq!(config.config_bits(), 0b1001);
+}
+
+#[test]
+fn reload_channels() {
+    assert_eq!(rren_bits(1), Ok(0x01));
+    assert_eq!(rren_bits(2), Ok(0x03));
+    assert_eq!(rren_bits(8), Ok(0xFF));
+    assert_eq!(rren_bits(0), Err(ConfigError::Channels(0)));
+    assert_eq!(rren_bits(9), Err(ConfigError::Channels(9)));
+    assert_eq!(
+        ConfigError::Channels(9).to_string(),
+        "9 reload channels, must be 1 to 8"
+    );
+}
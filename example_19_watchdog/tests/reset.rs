@@ -0,0 +1,60 @@
+//! Host tests for decoding RESETREAS
+
+use example_19_watchdog::reset::{Cause, ResetReasons};
+
+#[test]
+fn no_bits_is_power_on() {
+    let reasons = ResetReasons::from_bits(0);
+    assert!(reasons.is_power_on());
+    assert_eq!(reasons.causes().count(), 0);
+    assert_eq!(reasons.to_string(), "power-on or brown-out");
+}
+
+#[test]
+fn single_causes() {
+    let cases = [
+        (0x1, Cause::Pin, "reset pin"),
+        (0x2, Cause::Watchdog, "watchdog"),
+        (0x4, Cause::Soft, "soft reset"),
+        (0x8, Cause::Lockup, "CPU lockup"),
+        (0x1_0000, Cause::WakeGpio, "wake from System OFF by GPIO"),
+        (0x4_0000, Cause::WakeDebug, "wake from System OFF by the debugger"),
+        (0x10_0000, Cause::WakeVbus, "wake from System OFF by VBUS"),
+    ];
+    for (bits, cause, text) in cases {
+        let reasons = ResetReasons::from_bits(bits);
+        assert!(!reasons.is_power_on());
+        assert!(reasons.contains(cause));
+        assert_eq!(reasons.causes().collect::<Vec<_>>(), [cause]);
+        assert_eq!(reasons.to_string(), text);
+    }
+}
+
+#[test]
+fn bits_accumulate_until_cleared() {
+    // A pin reset, then the watchdog, without clearing in between
+    let reasons = ResetReasons::from_bits(0x3);
+    assert!(reasons.contains(Cause::Pin));
+    assert!(reasons.contains(Cause::Watchdog));
+    assert!(!reasons.contains(Cause::Soft));
+    assert_eq!(reasons.to_string(), "reset pin, watchdog");
+}
+
+#[test]
+fn unknown_bits_are_ignored() {
+    let reasons = ResetReasons::from_bits(0x8000_0004);
+    assert_eq!(reasons.causes().collect::<Vec<_>>(), [Cause::Soft]);
+    assert_eq!(reasons.bits(), 0x8000_0004);
+
+    let reasons = ResetReasons::from_bits(0x8000_0000);
+    assert!(reasons.is_power_on());
+}
+
+#[test]
+fn every_cause_has_its_own_bit() {
+    let all = Cause::ALL.iter().fold(0, |bits, cause| {
+        assert_eq!(bits & cause.bit(), 0, "{:?}", cause);
+        bits | cause.bit()
+    });
+    assert_eq!(all, 0x1F_000F);
+}
@@ -0,0 +1,13 @@
+/* nRF52833 memory layout, with the last 4 flash pages kept out of FLASH for the key-value store */
+MEMORY
+{
+  /* NOTE 1 K = 1 KiBi = 1024 bytes */
+  /* 512K of flash: the program gets the first 496K, the store the last 16K (4 pages of 4K) */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 496K
+  STORAGE : ORIGIN = 0x0007C000, LENGTH = 16K
+  RAM : ORIGIN = 0x20000000, LENGTH = 128K
+}
+
+/* For src/flash.rs: nothing is placed in STORAGE, the store writes it at run time through the NVMC */
+__storage_start = ORIGIN(STORAGE);
+__storage_end = ORIGIN(STORAGE) + LENGTH(STORAGE);
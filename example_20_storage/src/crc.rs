@@ -0,0 +1,47 @@
+//! CRC-32 (IEEE 802.3, as used by zip and Ethernet), to tell complete records from ones cut short by a power
+//! loss.
+//!
+//! Computed a bit at a time: slower than a lookup table, but the table would cost 1 KiB of flash and records
+//! are short.
+
+/// Reversed polynomial 0x04C11DB7
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// A CRC-32 in progress
+#[derive(Debug, Clone, Copy)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self { state: 0xFFFF_FFFF }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u32::from(byte);
+            for _ in 0..8 {
+                let mask = (self.state & 1).wrapping_neg();
+                self.state = (self.state >> 1) ^ (POLYNOMIAL & mask);
+            }
+        }
+    }
+
+    pub fn finish(self) -> u32 {
+        !self.state
+    }
+}
+
+/// CRC-32 of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
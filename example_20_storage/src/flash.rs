@@ -0,0 +1,31 @@
+//! The store's flash on the micro:bit: the last 4 pages of the nRF52833's 512 KiB, written through the NVMC.
+//!
+//! `memory.x` reserves these pages: its `FLASH` region, where the linker places the program, stops short of
+//! them, so a program that grew into the store would fail to link. The pages are the `STORAGE` region, and
+//! [`storage_start`] and [`storage_len`] read its bounds from the symbols `memory.x` defines.
+
+use microbit::hal::{nvmc::Nvmc, pac::NVMC};
+
+// Set by memory.x: the bounds of the STORAGE region
+extern "C" {
+    static __storage_start: u8;
+    static __storage_end: u8;
+}
+
+/// First byte of the store
+pub fn storage_start() -> usize {
+    // Only the address of the symbol is used
+    unsafe { core::ptr::addr_of!(__storage_start) as usize }
+}
+
+/// Size of the store in bytes: 4 pages of 4 KiB
+pub fn storage_len() -> usize {
+    unsafe { core::ptr::addr_of!(__storage_end) as usize - storage_start() }
+}
+
+/// The NVMC, set up to read, write and erase the storage pages only
+pub fn take(nvmc: NVMC) -> Nvmc<NVMC> {
+    // Nothing else refers to these pages, and the NVMC is taken by value so this only happens once
+    let storage = unsafe { core::slice::from_raw_parts_mut(storage_start() as *mut u8, storage_len()) };
+    Nvmc::new(nvmc, storage)
+}
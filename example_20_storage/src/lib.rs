@@ -0,0 +1,16 @@
+#![no_std]
+
+//! A key-value store in the nRF52833's internal flash that survives resets and power loss.
+//!
+//! - [`store`] keeps values as append-only records in a ring of flash pages, and compacts them as pages fill up
+//! - [`crc`] is the CRC-32 that tells complete records from ones cut short by a power loss
+//! - [`flash`] hands the last flash pages to the store through the NVMC (target only)
+//!
+//! `store` and `crc` are plain Rust and are tested on the PC with a fake flash that can lose power at any
+//! point, see `tests/`.
+
+pub mod crc;
+pub mod store;
+
+#[cfg(target_os = "none")]
+pub mod flash;
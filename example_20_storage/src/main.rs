@@ -0,0 +1,111 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_20_storage::{
+        flash,
+        store::{Key, Store},
+    },
+    microbit::hal::{nvmc::Nvmc, pac::NVMC, Timer},
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// How many times the micro:bit has started
+#[cfg(target_os = "none")]
+const BOOT_COUNT: Key = 1;
+/// A setting changed with the buttons, 0 to 9
+#[cfg(target_os = "none")]
+const LEVEL: Key = 2;
+
+#[cfg(target_os = "none")]
+type FlashStore = Store<Nvmc<NVMC>>;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+
+    rprintln!(
+        "Storage at {:#x}, {} bytes",
+        flash::storage_start(),
+        flash::storage_len()
+    );
+    let mut store = Store::open(flash::take(board.NVMC)).unwrap();
+
+    let mut buf = [0; 4];
+    let boots = match store.get(BOOT_COUNT, &mut buf).unwrap() {
+        Some(bytes) => u32::from_le_bytes(bytes.try_into().unwrap()) + 1,
+        None => 1,
+    };
+    store.set(BOOT_COUNT, &boots.to_le_bytes()).unwrap();
+    rprintln!("Boot number {}", boots);
+    print_level(&mut store);
+    print_stats(&store);
+    rprintln!("A: raise the level, B: forget it. Reset or unplug the micro:bit, the values stay");
+
+    let mut button_a = board.buttons.button_a.into_floating_input();
+    let mut button_b = board.buttons.button_b.into_floating_input();
+    let mut a_was_pressed = false;
+    let mut b_was_pressed = false;
+
+    loop {
+        let a_pressed = button_a.is_low().unwrap();
+        if a_pressed && !a_was_pressed {
+            let level = read_level(&mut store).map_or(0, |level| (level + 1) % 10);
+            match store.set(LEVEL, &[level]) {
+                Ok(()) => rprintln!("Level set to {}", level),
+                Err(error) => rprintln!("Could not save the level: {}", error),
+            }
+            print_stats(&store);
+        }
+        a_was_pressed = a_pressed;
+
+        let b_pressed = button_b.is_low().unwrap();
+        if b_pressed && !b_was_pressed {
+            match store.remove(LEVEL) {
+                Ok(()) => rprintln!("Level forgotten"),
+                Err(error) => rprintln!("Could not remove the level: {}", error),
+            }
+            print_stats(&store);
+        }
+        b_was_pressed = b_pressed;
+
+        timer0.delay_ms(20);
+    }
+}
+
+#[cfg(target_os = "none")]
+fn read_level(store: &mut FlashStore) -> Option<u8> {
+    let mut buf = [0; 1];
+    store.get(LEVEL, &mut buf).ok().flatten().map(|bytes| bytes[0])
+}
+
+#[cfg(target_os = "none")]
+fn print_level(store: &mut FlashStore) {
+    match read_level(store) {
+        Some(level) => rprintln!("Level {}", level),
+        None => rprintln!("No level saved"),
+    }
+}
+
+#[cfg(target_os = "none")]
+fn print_stats(store: &FlashStore) {
+    let stats = store.stats();
+    rprintln!(
+        "  page {} of {}, sequence {}, {} bytes free in the page",
+        stats.active_page,
+        stats.pages,
+        stats.sequence,
+        stats.free_in_page
+    );
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,513 @@
+//! A key-value store in flash: append-only records, safe against power loss, spread over a ring of pages.
+//!
+//! Flash bits can only be cleared by writing, and set again only by erasing a whole page (4 KiB on the
+//! nRF52833), which wears the flash out after about 10 000 cycles. So values are never changed in place: every
+//! `set` appends a new record, and the newest record for a key wins.
+//!
+//! ```text
+//! page      ┌──────────────────────┬─────────────────────────────────────┬─────────────────┐
+//!           │ magic │ seq │ retired│ record │ record │ record │ ...      │ erased (0xFF)   │
+//!           └──────────────────────┴─────────────────────────────────────┴─────────────────┘
+//! record    ┌───────────┬──────────┬──────┬─────┐
+//!           │ key │ len │ CRC-32   │ data │ pad │      written in the order: key/len, data, CRC
+//!           └───────────┴──────────┴──────┴─────┘
+//! ```
+//!
+//! - **Power loss while writing a record**: its CRC is missing or does not match, so it is ignored and the
+//!   previous value stays. An update either happens completely or not at all
+//! - **Full page**: the next page in the ring becomes the active one (with the next sequence number), and the
+//!   oldest page is compacted: records in it that still hold a current value are copied to the active page,
+//!   then it is retired and erased. One page is always kept erased, ready for this
+//! - **Power loss while compacting**: when the store is opened and there is no erased page, the compaction is
+//!   finished. Copying twice does no harm, as the copies hold the same values
+//! - **Wear levelling**: pages are used in turn around the ring, so all of them are erased equally often
+//!
+//! The store works on any [`NorFlash`]: the NVMC on the micro:bit, an in-memory fake in the tests.
+
+use core::fmt;
+
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::crc::Crc32;
+
+/// Keys are 0 to 0xFFFE; 0xFFFF would look like erased flash
+pub type Key = u16;
+
+/// Longest value
+pub const MAX_VALUE_LEN: usize = 256;
+
+/// Marks a page in use. Its sequence number follows, then the retired word, erased until the page is given up.
+const PAGE_MAGIC: u32 = 0x5356_4B56;
+const PAGE_HEADER_LEN: u32 = 12;
+const RECORD_HEADER_LEN: u32 = 8;
+const ERASED: u32 = 0xFFFF_FFFF;
+/// Length field flag for a removed key
+const REMOVED: u16 = 0x8000;
+const INVALID_KEY: Key = 0xFFFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Flash(E),
+    /// Key 0xFFFF
+    InvalidKey,
+    /// Longer than [`MAX_VALUE_LEN`]
+    ValueTooLong,
+    /// The buffer passed to [`Store::get`] is too small for the value
+    BufferTooSmall {
+        needed: usize,
+    },
+    /// The current values no longer fit in a page
+    Full,
+    /// The flash must have at least two pages, and a page size that is a multiple of 4 bytes
+    Layout,
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flash(error) => write!(f, "flash error {:?}", error),
+            Self::InvalidKey => f.write_str("key 0xFFFF is reserved"),
+            Self::ValueTooLong => write!(f, "values are at most {} bytes", MAX_VALUE_LEN),
+            Self::BufferTooSmall { needed } => write!(f, "buffer too small, {} bytes needed", needed),
+            Self::Full => f.write_str("store full"),
+            Self::Layout => f.write_str("unusable flash layout"),
+        }
+    }
+}
+
+/// A record with a valid CRC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Record {
+    page: u32,
+    offset: u32,
+    key: Key,
+    len: u16,
+    removed: bool,
+}
+
+impl Record {
+    fn end(&self) -> u32 {
+        self.offset + record_size(self.len)
+    }
+}
+
+/// Result of looking at the next record of a page
+enum Step {
+    Valid(Record),
+    /// A record that was not written completely, ending at the offset
+    Skip(u32),
+    /// No more records. Appending continues at the offset, which is the end of the page if the last record's
+    /// header is damaged.
+    End(u32),
+}
+
+enum PageState {
+    InUse {
+        sequence: u32,
+    },
+    Erased,
+    /// A retired page, or one damaged by a power loss while it was being activated or erased
+    Invalid,
+}
+
+/// Bytes a record with `len` bytes of data takes
+fn record_size(len: u16) -> u32 {
+    RECORD_HEADER_LEN + u32::from(len).next_multiple_of(4)
+}
+
+/// Where the store stands, for reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub pages: u32,
+    pub active_page: u32,
+    /// Sequence number of the active page: how many pages have been used so far
+    pub sequence: u32,
+    /// Bytes left in the active page
+    pub free_in_page: u32,
+}
+
+/// The key-value store, see the module documentation
+#[derive(Debug)]
+pub struct Store<F> {
+    flash: F,
+    page_size: u32,
+    pages: u32,
+    active: u32,
+    sequence: u32,
+    /// Offset in the active page where the next record goes
+    cursor: u32,
+}
+
+impl<F: NorFlash> Store<F> {
+    /// Open the store in `flash`, formatting it if it is empty, and repairing anything a power loss left
+    /// behind.
+    pub fn open(flash: F) -> Result<Self, Error<F::Error>> {
+        let page_size = F::ERASE_SIZE as u32;
+        let pages = flash.capacity() as u32 / page_size.max(1);
+        let word_access = |size: usize| size <= 4 && 4 % size == 0;
+        if !page_size.is_multiple_of(4) || pages < 2 || !word_access(F::READ_SIZE) || !word_access(F::WRITE_SIZE) {
+            return Err(Error::Layout);
+        }
+        let mut store = Self {
+            flash,
+            page_size,
+            pages,
+            active: 0,
+            sequence: 0,
+            cursor: 0,
+        };
+
+        let mut newest = None;
+        let mut erased = 0;
+        for page in 0..pages {
+            match store.page_state(page)? {
+                PageState::InUse { sequence } => {
+                    if newest.is_none_or(|(_, newest)| sequence > newest) {
+                        newest = Some((page, sequence));
+                    }
+                }
+                PageState::Erased => erased += 1,
+                PageState::Invalid => {
+                    store.erase_page(page)?;
+                    erased += 1;
+                }
+            }
+        }
+
+        match newest {
+            None => store.activate(0, 1)?,
+            Some((page, sequence)) => {
+                store.active = page;
+                store.sequence = sequence;
+                store.cursor = store.end_of(page)?;
+                if erased == 0 {
+                    // Compaction was interrupted
+                    store.compact(store.next_page(page))?;
+                }
+            }
+        }
+        Ok(store)
+    }
+
+    /// Give the flash back
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            pages: self.pages,
+            active_page: self.active,
+            sequence: self.sequence,
+            free_in_page: self.page_size - self.cursor,
+        }
+    }
+
+    /// The value for `key`, copied into `buf`
+    pub fn get<'b>(&mut self, key: Key, buf: &'b mut [u8]) -> Result<Option<&'b [u8]>, Error<F::Error>> {
+        check_key(key)?;
+        let Some(record) = self.find_latest(key)? else {
+            return Ok(None);
+        };
+        if record.removed {
+            return Ok(None);
+        }
+        let len = usize::from(record.len);
+        let buf = buf.get_mut(..len).ok_or(Error::BufferTooSmall { needed: len })?;
+        self.read_bytes(self.data_offset(&record), buf)?;
+        Ok(Some(buf))
+    }
+
+    /// Store `value` for `key`. Nothing is written if the key already has this value.
+    pub fn set(&mut self, key: Key, value: &[u8]) -> Result<(), Error<F::Error>> {
+        check_key(key)?;
+        if value.len() > MAX_VALUE_LEN {
+            return Err(Error::ValueTooLong);
+        }
+        if let Some(record) = self.find_latest(key)? {
+            if !record.removed && usize::from(record.len) == value.len() && self.data_equals(&record, value)? {
+                return Ok(());
+            }
+        }
+        self.append(key, value, false)
+    }
+
+    /// Remove `key`, if it has a value
+    pub fn remove(&mut self, key: Key) -> Result<(), Error<F::Error>> {
+        check_key(key)?;
+        match self.find_latest(key)? {
+            Some(record) if !record.removed => self.append(key, &[], true),
+            _ => Ok(()),
+        }
+    }
+
+    fn append(&mut self, key: Key, value: &[u8], removed: bool) -> Result<(), Error<F::Error>> {
+        let size = record_size(value.len() as u16);
+        if self.cursor + size > self.page_size {
+            self.rotate(size)?;
+        }
+        self.write_record(key, value, removed)
+    }
+
+    /// Write a record at the cursor: the header first, then the data, then the CRC that makes it valid
+    fn write_record(&mut self, key: Key, value: &[u8], removed: bool) -> Result<(), Error<F::Error>> {
+        let len_field = value.len() as u16 | if removed { REMOVED } else { 0 };
+        let header = u32::from(key) | (u32::from(len_field) << 16);
+        let mut crc = Crc32::new();
+        crc.update(&header.to_le_bytes());
+        crc.update(value);
+
+        let base = self.page_base(self.active) + self.cursor;
+        self.write_word(base, header)?;
+        for (index, chunk) in value.chunks(4).enumerate() {
+            let mut word = [0xFF; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.write_word(base + RECORD_HEADER_LEN + 4 * index as u32, u32::from_le_bytes(word))?;
+        }
+        self.write_word(base + 4, crc.finish())?;
+        self.cursor += record_size(value.len() as u16);
+        Ok(())
+    }
+
+    /// Move to the next page in the ring, compacting the oldest page into it. `size` is the record that has to
+    /// fit afterwards; if it would not, nothing is changed.
+    fn rotate(&mut self, size: u32) -> Result<(), Error<F::Error>> {
+        let next = self.next_page(self.active);
+        let oldest = self.next_page(next);
+        let live = self.live_bytes(oldest)?;
+        if PAGE_HEADER_LEN + live + size > self.page_size {
+            return Err(Error::Full);
+        }
+        self.activate(next, self.sequence + 1)?;
+        self.compact(oldest)
+    }
+
+    /// Copy the current values out of `page` into the active page, then erase it
+    fn compact(&mut self, page: u32) -> Result<(), Error<F::Error>> {
+        match self.page_state(page)? {
+            // Early on, not every page has been used yet
+            PageState::Erased => return Ok(()),
+            PageState::Invalid => return self.erase_page(page),
+            PageState::InUse { .. } => {}
+        }
+        let mut offset = PAGE_HEADER_LEN;
+        let mut buf = [0; MAX_VALUE_LEN];
+        loop {
+            match self.step(page, offset)? {
+                Step::End(_) => break,
+                Step::Skip(end) => offset = end,
+                Step::Valid(record) => {
+                    offset = record.end();
+                    // Removals can go: there is nothing older left for them to hide
+                    if record.removed || self.find_latest(record.key)? != Some(record) {
+                        continue;
+                    }
+                    let value = &mut buf[..usize::from(record.len)];
+                    self.read_bytes(self.data_offset(&record), value)?;
+                    if self.cursor + record_size(record.len) > self.page_size {
+                        return Err(Error::Full);
+                    }
+                    self.write_record(record.key, value, false)?;
+                }
+            }
+        }
+        self.erase_page(page)
+    }
+
+    /// Bytes the current values in `page` take
+    fn live_bytes(&mut self, page: u32) -> Result<u32, Error<F::Error>> {
+        let mut bytes = 0;
+        if !matches!(self.page_state(page)?, PageState::InUse { .. }) {
+            return Ok(0);
+        }
+        let mut offset = PAGE_HEADER_LEN;
+        loop {
+            match self.step(page, offset)? {
+                Step::End(_) => return Ok(bytes),
+                Step::Skip(end) => offset = end,
+                Step::Valid(record) => {
+                    offset = record.end();
+                    if !record.removed && self.find_latest(record.key)? == Some(record) {
+                        bytes += record_size(record.len);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The newest valid record for `key`, going through the pages from oldest to newest
+    fn find_latest(&mut self, key: Key) -> Result<Option<Record>, Error<F::Error>> {
+        let mut latest = None;
+        for index in 1..=self.pages {
+            let page = (self.active + index) % self.pages;
+            if !matches!(self.page_state(page)?, PageState::InUse { .. }) {
+                continue;
+            }
+            let mut offset = PAGE_HEADER_LEN;
+            loop {
+                match self.step(page, offset)? {
+                    Step::End(_) => break,
+                    Step::Skip(end) => offset = end,
+                    Step::Valid(record) => {
+                        if record.key == key {
+                            latest = Some(record);
+                        }
+                        offset = record.end();
+                    }
+                }
+            }
+        }
+        Ok(latest)
+    }
+
+    /// Offset after the last record of `page`
+    fn end_of(&mut self, page: u32) -> Result<u32, Error<F::Error>> {
+        let mut offset = PAGE_HEADER_LEN;
+        loop {
+            match self.step(page, offset)? {
+                Step::End(end) => return Ok(end),
+                Step::Skip(end) => offset = end,
+                Step::Valid(record) => offset = record.end(),
+            }
+        }
+    }
+
+    /// Look at the record at `offset` in `page`
+    fn step(&mut self, page: u32, offset: u32) -> Result<Step, Error<F::Error>> {
+        if offset + RECORD_HEADER_LEN > self.page_size {
+            return Ok(Step::End(self.page_size));
+        }
+        let base = self.page_base(page) + offset;
+        let header = self.read_word(base)?;
+        if header == ERASED {
+            return Ok(Step::End(offset));
+        }
+        let key = header as Key;
+        let len_field = (header >> 16) as u16;
+        let len = len_field & !REMOVED;
+        let removed = len_field & REMOVED != 0;
+        if key == INVALID_KEY || usize::from(len) > MAX_VALUE_LEN || (removed && len != 0) {
+            // The header itself was cut short: nothing after it can be trusted, so the page is closed
+            return Ok(Step::End(self.page_size));
+        }
+        let end = offset + record_size(len);
+        if end > self.page_size {
+            return Ok(Step::End(self.page_size));
+        }
+
+        let stored_crc = self.read_word(base + 4)?;
+        let mut crc = Crc32::new();
+        crc.update(&header.to_le_bytes());
+        let mut chunk = [0; 32];
+        let mut done = 0;
+        while done < usize::from(len) {
+            let count = chunk.len().min(usize::from(len) - done);
+            let words = count.next_multiple_of(4);
+            self.flash
+                .read(base + RECORD_HEADER_LEN + done as u32, &mut chunk[..words])
+                .map_err(Error::Flash)?;
+            crc.update(&chunk[..count]);
+            done += count;
+        }
+        if crc.finish() != stored_crc {
+            return Ok(Step::Skip(end));
+        }
+        Ok(Step::Valid(Record {
+            page,
+            offset,
+            key,
+            len,
+            removed,
+        }))
+    }
+
+    fn data_equals(&mut self, record: &Record, value: &[u8]) -> Result<bool, Error<F::Error>> {
+        let mut buf = [0; MAX_VALUE_LEN];
+        let stored = &mut buf[..value.len()];
+        self.read_bytes(self.data_offset(record), stored)?;
+        Ok(stored == value)
+    }
+
+    fn page_state(&mut self, page: u32) -> Result<PageState, Error<F::Error>> {
+        let base = self.page_base(page);
+        let magic = self.read_word(base)?;
+        let sequence = self.read_word(base + 4)?;
+        let retired = self.read_word(base + 8)?;
+        if magic == PAGE_MAGIC && retired == ERASED {
+            return Ok(PageState::InUse { sequence });
+        }
+        for offset in (0..self.page_size).step_by(4) {
+            if self.read_word(base + offset)? != ERASED {
+                return Ok(PageState::Invalid);
+            }
+        }
+        Ok(PageState::Erased)
+    }
+
+    /// Start using `page`, which must be erased
+    fn activate(&mut self, page: u32, sequence: u32) -> Result<(), Error<F::Error>> {
+        if !matches!(self.page_state(page)?, PageState::Erased) {
+            self.erase_page(page)?;
+        }
+        let base = self.page_base(page);
+        // The magic last: only a complete header makes the page count
+        self.write_word(base + 4, sequence)?;
+        self.write_word(base, PAGE_MAGIC)?;
+        self.active = page;
+        self.sequence = sequence;
+        self.cursor = PAGE_HEADER_LEN;
+        Ok(())
+    }
+
+    /// Retire `page`, then erase it. A page that is only partly erased when the power fails could still have
+    /// a valid header and old records; the retired word makes sure it is not used again.
+    fn erase_page(&mut self, page: u32) -> Result<(), Error<F::Error>> {
+        let base = self.page_base(page);
+        if self.read_word(base)? == PAGE_MAGIC && self.read_word(base + 8)? == ERASED {
+            self.write_word(base + 8, 0)?;
+        }
+        self.flash.erase(base, base + self.page_size).map_err(Error::Flash)
+    }
+
+    fn next_page(&self, page: u32) -> u32 {
+        (page + 1) % self.pages
+    }
+
+    fn page_base(&self, page: u32) -> u32 {
+        page * self.page_size
+    }
+
+    /// Where a record's data starts in the flash
+    fn data_offset(&self, record: &Record) -> u32 {
+        self.page_base(record.page) + record.offset + RECORD_HEADER_LEN
+    }
+
+    fn read_word(&mut self, offset: u32) -> Result<u32, Error<F::Error>> {
+        let mut word = [0; 4];
+        self.flash.read(offset, &mut word).map_err(Error::Flash)?;
+        Ok(u32::from_le_bytes(word))
+    }
+
+    fn write_word(&mut self, offset: u32, word: u32) -> Result<(), Error<F::Error>> {
+        self.flash.write(offset, &word.to_le_bytes()).map_err(Error::Flash)
+    }
+
+    /// Read `buf.len()` bytes from a word-aligned `offset`, whatever the flash's read size
+    fn read_bytes(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), Error<F::Error>> {
+        let whole = buf.len() / 4 * 4;
+        let (head, tail) = buf.split_at_mut(whole);
+        self.flash.read(offset, head).map_err(Error::Flash)?;
+        if !tail.is_empty() {
+            let word = self.read_word(offset + whole as u32)?.to_le_bytes();
+            tail.copy_from_slice(&word[..tail.len()]);
+        }
+        Ok(())
+    }
+}
+
+fn check_key<E>(key: Key) -> Result<(), Error<E>> {
+    if key == INVALID_KEY {
+        return Err(Error::InvalidKey);
+    }
+    Ok(())
+}
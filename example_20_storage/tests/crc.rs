@@ -0,0 +1,30 @@
+//! Host tests for CRC-32
+
+use example_20_storage::crc::{crc32, Crc32};
+
+#[test]
+fn check_value() {
+    // The standard check: CRC-32 of the ASCII digits 1 to 9
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+}
+
+#[test]
+fn in_pieces() {
+    let mut crc = Crc32::new();
+    crc.update(b"1234");
+    crc.update(b"");
+    crc.update(b"56789");
+    assert_eq!(crc.finish(), 0xCBF4_3926);
+}
+
+#[test]
+fn single_bit_changes_are_caught() {
+    let reference = crc32(&[0x5A; 64]);
+    for bit in 0..64 * 8 {
+        let mut data = [0x5A; 64];
+        data[bit / 8] ^= 1 << (bit % 8);
+        assert_ne!(crc32(&data), reference, "bit {}", bit);
+    }
+}
@@ -0,0 +1,406 @@
+//! Host tests for the key-value store, on an in-memory flash that can lose power in the middle of any write or
+//! erase
+
+use std::collections::BTreeMap;
+
+use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash};
+use example_20_storage::store::{Error, Key, Store, MAX_VALUE_LEN};
+
+const PAGE_SIZE: usize = 1024;
+const PAGES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FakeError {
+    PowerLoss,
+}
+
+impl NorFlashError for FakeError {
+    fn kind(&self) -> NorFlashErrorKind {
+        NorFlashErrorKind::Other
+    }
+}
+
+/// NOR flash in RAM with the rules of the real thing: writes can only clear bits, a word is written at most
+/// once per erase, and erasing works on whole pages. `power_budget` is how many more word writes or page erases
+/// succeed before the power fails, leaving that operation half done.
+struct FakeFlash {
+    data: Vec<u8>,
+    written: Vec<bool>,
+    erase_counts: [u32; PAGES],
+    power_budget: Option<usize>,
+    powered: bool,
+    operations: usize,
+}
+
+impl FakeFlash {
+    fn new() -> Self {
+        Self {
+            data: vec![0xFF; PAGE_SIZE * PAGES],
+            written: vec![false; PAGE_SIZE * PAGES / 4],
+            erase_counts: [0; PAGES],
+            power_budget: None,
+            powered: true,
+            operations: 0,
+        }
+    }
+
+    /// Whether the next operation completes. Once the budget is used up, the power is off.
+    fn spend(&mut self) -> bool {
+        self.operations += 1;
+        match &mut self.power_budget {
+            Some(0) => {
+                self.powered = false;
+                false
+            }
+            Some(budget) => {
+                *budget -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+
+    fn power_cycle(&mut self) {
+        self.powered = true;
+        self.power_budget = None;
+    }
+}
+
+impl ErrorType for FakeFlash {
+    type Error = FakeError;
+}
+
+impl ReadNorFlash for FakeFlash {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        if !self.powered {
+            return Err(FakeError::PowerLoss);
+        }
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl NorFlash for FakeFlash {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = PAGE_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if !self.powered {
+            return Err(FakeError::PowerLoss);
+        }
+        let (from, to) = (from as usize, to as usize);
+        assert!(
+            from.is_multiple_of(PAGE_SIZE) && to.is_multiple_of(PAGE_SIZE) && from < to,
+            "erase {:#x}..{:#x}",
+            from,
+            to
+        );
+        for page in from / PAGE_SIZE..to / PAGE_SIZE {
+            let range = page * PAGE_SIZE..(page + 1) * PAGE_SIZE;
+            if !self.spend() {
+                // Half the page erased
+                self.data[range.start..range.start + PAGE_SIZE / 2].fill(0xFF);
+                return Err(FakeError::PowerLoss);
+            }
+            self.data[range.clone()].fill(0xFF);
+            self.written[range.start / 4..range.end / 4].fill(false);
+            self.erase_counts[page] += 1;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        if !self.powered {
+            return Err(FakeError::PowerLoss);
+        }
+        let offset = offset as usize;
+        assert!(
+            offset.is_multiple_of(4) && bytes.len().is_multiple_of(4),
+            "unaligned write at {:#x}",
+            offset
+        );
+        for (index, word) in bytes.chunks(4).enumerate() {
+            let at = offset + 4 * index;
+            assert!(!self.written[at / 4], "word at {:#x} written twice", at);
+            let mut word: [u8; 4] = word.try_into().unwrap();
+            if !self.spend() {
+                // Only some of the bits that should be cleared are
+                word[2] = 0xFF;
+                word[3] = 0xFF;
+            }
+            for (cell, new) in self.data[at..at + 4].iter_mut().zip(word) {
+                *cell &= new;
+            }
+            self.written[at / 4] = true;
+            if !self.powered {
+                return Err(FakeError::PowerLoss);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn get(store: &mut Store<FakeFlash>, key: Key) -> Option<Vec<u8>> {
+    let mut buf = [0; MAX_VALUE_LEN];
+    store.get(key, &mut buf).unwrap().map(<[u8]>::to_vec)
+}
+
+#[test]
+fn empty_store() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    assert_eq!(get(&mut store, 1), None);
+    let stats = store.stats();
+    assert_eq!(stats.pages, 4);
+    assert_eq!(stats.sequence, 1);
+}
+
+#[test]
+fn set_get_remove() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    store.set(1, b"hello").unwrap();
+    store.set(2, &[]).unwrap();
+    assert_eq!(get(&mut store, 1).as_deref(), Some(&b"hello"[..]));
+    assert_eq!(get(&mut store, 2).as_deref(), Some(&[][..]));
+
+    store.set(1, b"goodbye!").unwrap();
+    assert_eq!(get(&mut store, 1).as_deref(), Some(&b"goodbye!"[..]));
+
+    store.remove(1).unwrap();
+    assert_eq!(get(&mut store, 1), None);
+    assert_eq!(get(&mut store, 2).as_deref(), Some(&[][..]));
+}
+
+#[test]
+fn values_survive_reopening() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    store.set(7, &42u32.to_le_bytes()).unwrap();
+    store.set(8, b"radio group").unwrap();
+    store.remove(8).unwrap();
+
+    let mut store = Store::open(store.release()).unwrap();
+    assert_eq!(get(&mut store, 7), Some(42u32.to_le_bytes().to_vec()));
+    assert_eq!(get(&mut store, 8), None);
+}
+
+#[test]
+fn argument_errors() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    assert_eq!(store.set(0xFFFF, b"x"), Err(Error::InvalidKey));
+    assert_eq!(store.set(1, &[0; MAX_VALUE_LEN + 1]), Err(Error::ValueTooLong));
+    store.set(1, &[0; MAX_VALUE_LEN]).unwrap();
+
+    store.set(2, b"twelve bytes").unwrap();
+    let mut small = [0; 4];
+    assert_eq!(store.get(2, &mut small), Err(Error::BufferTooSmall { needed: 12 }));
+}
+
+#[test]
+fn unchanged_values_are_not_written() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    store.set(1, b"same").unwrap();
+    let free = store.stats().free_in_page;
+    store.set(1, b"same").unwrap();
+    assert_eq!(store.stats().free_in_page, free);
+    store.set(1, b"diff").unwrap();
+    assert!(store.stats().free_in_page < free);
+
+    // Removing twice, or removing a key that was never set, writes nothing either
+    store.remove(1).unwrap();
+    let free = store.stats().free_in_page;
+    store.remove(1).unwrap();
+    store.remove(3).unwrap();
+    assert_eq!(store.stats().free_in_page, free);
+}
+
+#[test]
+fn pages_are_worn_evenly() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    for count in 0u32..5000 {
+        store.set((count % 5) as Key, &count.to_le_bytes()).unwrap();
+    }
+    for key in 0..5 {
+        let expected = 4995 + u32::from(key);
+        assert_eq!(get(&mut store, key), Some(expected.to_le_bytes().to_vec()));
+    }
+    assert!(store.stats().sequence > 50);
+
+    let flash = store.release();
+    let most = *flash.erase_counts.iter().max().unwrap();
+    let least = *flash.erase_counts.iter().min().unwrap();
+    assert!(most - least <= 1, "{:?}", flash.erase_counts);
+}
+
+#[test]
+fn full_store_keeps_its_values() {
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    // New keys until the current values no longer fit: 4 pages, one of them kept erased
+    let mut stored = 0;
+    let error = loop {
+        match store.set(stored, &[stored as u8; MAX_VALUE_LEN]) {
+            Ok(()) => stored += 1,
+            Err(error) => break error,
+        }
+        assert!(stored < 100, "the store never filled up");
+    };
+    assert_eq!(error, Error::Full);
+    assert!(stored >= 6, "only {} values fit", stored);
+
+    // Nothing was lost, and removing values makes room again
+    for key in 0..stored {
+        assert_eq!(
+            get(&mut store, key),
+            Some(vec![key as u8; MAX_VALUE_LEN]),
+            "key {}",
+            key
+        );
+    }
+    assert_eq!(get(&mut store, stored), None);
+    for key in 0..stored / 2 {
+        store.remove(key).unwrap();
+    }
+    store.set(stored, &[0xAB; MAX_VALUE_LEN]).unwrap();
+    assert_eq!(get(&mut store, stored), Some(vec![0xAB; MAX_VALUE_LEN]));
+    assert_eq!(
+        get(&mut store, stored - 1),
+        Some(vec![(stored - 1) as u8; MAX_VALUE_LEN])
+    );
+}
+
+#[test]
+fn layout_is_checked() {
+    struct OnePage(FakeFlash);
+    impl ErrorType for OnePage {
+        type Error = FakeError;
+    }
+    impl ReadNorFlash for OnePage {
+        const READ_SIZE: usize = 1;
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            self.0.read(offset, bytes)
+        }
+        fn capacity(&self) -> usize {
+            PAGE_SIZE
+        }
+    }
+    impl NorFlash for OnePage {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = PAGE_SIZE;
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.0.erase(from, to)
+        }
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.0.write(offset, bytes)
+        }
+    }
+    assert!(matches!(Store::open(OnePage(FakeFlash::new())), Err(Error::Layout)));
+}
+
+/// A workload with sets, updates, removals and several page changes
+#[derive(Debug, Clone)]
+enum Operation {
+    Set(Key, Vec<u8>),
+    Remove(Key),
+}
+
+fn workload() -> Vec<Operation> {
+    let mut operations = Vec::new();
+    for step in 0u32..70 {
+        let key = (step * 7 % 6) as Key;
+        if step % 9 == 8 {
+            operations.push(Operation::Remove(key));
+        } else {
+            let len = (step * 13 % 90) as usize;
+            operations.push(Operation::Set(key, vec![step as u8; len]));
+        }
+    }
+    operations
+}
+
+fn apply(model: &mut BTreeMap<Key, Vec<u8>>, operation: &Operation) {
+    match operation {
+        Operation::Set(key, value) => {
+            model.insert(*key, value.clone());
+        }
+        Operation::Remove(key) => {
+            model.remove(key);
+        }
+    }
+}
+
+fn run(store: &mut Store<FakeFlash>, operation: &Operation) -> Result<(), Error<FakeError>> {
+    match operation {
+        Operation::Set(key, value) => store.set(*key, value),
+        Operation::Remove(key) => store.remove(*key),
+    }
+}
+
+#[test]
+fn power_loss_at_every_step() {
+    // How many flash operations the whole workload takes
+    let mut store = Store::open(FakeFlash::new()).unwrap();
+    for operation in &workload() {
+        run(&mut store, operation).unwrap();
+    }
+    let total = store.release().operations;
+    assert!(total > 500);
+
+    for budget in 0..total {
+        let mut flash = FakeFlash::new();
+        flash.power_budget = Some(budget);
+
+        // Run until the power fails, keeping track of what has been stored for certain
+        let mut committed = BTreeMap::new();
+        let mut interrupted = None;
+        match Store::open(flash) {
+            Err(_) => {
+                // Power lost while formatting: nothing stored yet
+                continue;
+            }
+            Ok(mut store) => {
+                for operation in &workload() {
+                    if run(&mut store, operation).is_err() {
+                        interrupted = Some(operation.clone());
+                        break;
+                    }
+                    apply(&mut committed, operation);
+                }
+                flash = store.release();
+            }
+        }
+        flash.power_cycle();
+
+        // Every value is the last one stored, or the one being stored when the power failed
+        let mut store = Store::open(flash).unwrap_or_else(|error| panic!("budget {}: {:?}", budget, error));
+        let mut with_interrupted = committed.clone();
+        if let Some(operation) = &interrupted {
+            apply(&mut with_interrupted, operation);
+        }
+        for key in 0..6 {
+            let value = get(&mut store, key);
+            assert!(
+                value.as_ref() == committed.get(&key) || value.as_ref() == with_interrupted.get(&key),
+                "budget {}, key {}: {:?}, interrupted {:?}",
+                budget,
+                key,
+                value,
+                interrupted
+            );
+        }
+
+        // And the store carries on working
+        store.set(100, b"after").unwrap();
+        let mut store = Store::open(store.release()).unwrap();
+        assert_eq!(
+            get(&mut store, 100).as_deref(),
+            Some(&b"after"[..]),
+            "budget {}",
+            budget
+        );
+    }
+}
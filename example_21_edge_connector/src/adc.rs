@@ -0,0 +1,77 @@
+//! Single readings from the SAADC, one pin at a time.
+//!
+//! The SAADC always writes its results to RAM with EasyDMA, even a single one: [`Adc::read`] points it at a local
+//! variable, takes one sample and waits for it. About 15 µs with the default 10 µs acquisition time.
+
+use core::sync::atomic::{compiler_fence, Ordering};
+
+use microbit::hal::pac::SAADC;
+
+use crate::{
+    analog::{pselp, InputConfig, Resolution, VDD_MV},
+    pins::AnalogPin,
+};
+
+/// The SAADC, ready to measure any [`AnalogPin`]
+pub struct Adc {
+    saadc: SAADC,
+    resolution: Resolution,
+}
+
+impl Adc {
+    /// Enable the SAADC and calibrate its offset
+    pub fn new(saadc: SAADC, resolution: Resolution) -> Self {
+        saadc.resolution.write(|w| unsafe { w.bits(resolution.register()) });
+        saadc.oversample.write(|w| w.oversample().bypass());
+        saadc.enable.write(|w| w.enable().enabled());
+
+        saadc.events_calibratedone.write(|w| unsafe { w.bits(0) });
+        saadc.tasks_calibrateoffset.write(|w| unsafe { w.bits(1) });
+        while saadc.events_calibratedone.read().bits() == 0 {}
+        saadc.events_calibratedone.write(|w| unsafe { w.bits(0) });
+
+        Self { saadc, resolution }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Measure `pin`. Borrowing it makes sure it has not been turned into a digital pin meanwhile.
+    pub fn read<P: AnalogPin>(&mut self, pin: &P, config: &InputConfig) -> i16 {
+        let saadc = &self.saadc;
+        saadc.ch[0].pselp.write(|w| unsafe { w.bits(pselp(pin)) });
+        saadc.ch[0].pseln.write(|w| w.pseln().nc());
+        saadc.ch[0].config.write(|w| unsafe { w.bits(config.config_bits()) });
+
+        let mut result: i16 = 0;
+        saadc
+            .result
+            .ptr
+            .write(|w| unsafe { w.ptr().bits(&mut result as *mut i16 as u32) });
+        saadc.result.maxcnt.write(|w| unsafe { w.maxcnt().bits(1) });
+
+        saadc.events_started.write(|w| unsafe { w.bits(0) });
+        saadc.events_end.write(|w| unsafe { w.bits(0) });
+        saadc.tasks_start.write(|w| unsafe { w.bits(1) });
+        while saadc.events_started.read().bits() == 0 {}
+        saadc.tasks_sample.write(|w| unsafe { w.bits(1) });
+        while saadc.events_end.read().bits() == 0 {}
+
+        saadc.events_stopped.write(|w| unsafe { w.bits(0) });
+        saadc.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while saadc.events_stopped.read().bits() == 0 {}
+        // Let go of the pin, so it works as a digital pin again once turned into one
+        saadc.ch[0].pselp.write(|w| w.pselp().nc());
+
+        // DMA wrote `result` behind the compiler's back: do not let it use a value read before END
+        compiler_fence(Ordering::SeqCst);
+        result
+    }
+
+    /// Measure `pin` in millivolts, taking VDD as 3.3 V
+    pub fn read_millivolts<P: AnalogPin>(&mut self, pin: &P, config: &InputConfig) -> i32 {
+        let raw = self.read(pin, config);
+        config.millivolts(raw, self.resolution, VDD_MV)
+    }
+}
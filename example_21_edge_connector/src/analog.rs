@@ -0,0 +1,222 @@
+//! SAADC settings for measuring the voltage on an edge connector pin, and turning readings into millivolts.
+//!
+//! In single-ended mode the SAADC measures between the pin and ground:
+//!
+//! ```text
+//! RESULT = V(pin) × GAIN / REFERENCE × 2^RESOLUTION
+//! ```
+//!
+//! so the voltage that gives the largest reading, full scale, is REFERENCE / GAIN:
+//!
+//! | Reference | Gain | Full scale | Good for |
+//! |-----------|------|------------|----------|
+//! | VDD/4 | 1/4 | VDD | Potentiometers and sensors powered from 3V: readings do not change with the supply |
+//! | Internal 0.6 V | 1/6 | 3.6 V | Absolute voltages, whatever VDD is |
+//! | Internal 0.6 V | 4 | 0.15 V | Small signals |
+//!
+//! Only the six pins with an analog input (P0, P1, P2, P3, P4 and P10) implement [`AnalogPin`], so measuring
+//! any other pin does not compile:
+//!
+//! ```compile_fail
+//! let edge = unsafe { example_21_edge_connector::pins::EdgeConnector::steal() };
+//! let input = example_21_edge_connector::analog::pselp(&edge.p8); // P8 has no analog input
+//! ```
+
+use crate::pins::AnalogPin;
+
+/// The micro:bit's supply when powered from USB. On batteries it is lower, so VDD/4 readings in millivolts are
+/// only as accurate as this.
+pub const VDD_MV: u32 = 3300;
+
+/// The internal reference voltage
+pub const INTERNAL_REFERENCE_MV: u32 = 600;
+
+// CH[n].CONFIG register fields
+const CONFIG_GAIN_SHIFT: u32 = 8;
+const CONFIG_REFSEL_VDD1_4: u32 = 1 << 12;
+const CONFIG_TACQ_SHIFT: u32 = 16;
+
+/// Amplification in front of the converter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gain {
+    OneSixth,
+    OneFifth,
+    OneQuarter,
+    OneThird,
+    OneHalf,
+    One,
+    Two,
+    Four,
+}
+
+impl Gain {
+    /// As a fraction: numerator, denominator
+    pub const fn ratio(self) -> (u32, u32) {
+        match self {
+            Self::OneSixth => (1, 6),
+            Self::OneFifth => (1, 5),
+            Self::OneQuarter => (1, 4),
+            Self::OneThird => (1, 3),
+            Self::OneHalf => (1, 2),
+            Self::One => (1, 1),
+            Self::Two => (2, 1),
+            Self::Four => (4, 1),
+        }
+    }
+}
+
+/// What the input is compared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    /// 0.6 V from a bandgap: independent of the supply
+    Internal,
+    /// A quarter of the supply: readings are a fraction of VDD
+    VddQuarter,
+}
+
+/// How long the SAADC charges its sampling capacitor from the pin. The higher the resistance of what drives the
+/// pin, the longer it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcquisitionTime {
+    /// Sources up to 10 kΩ
+    Us3,
+    /// Up to 40 kΩ
+    Us5,
+    /// Up to 100 kΩ
+    Us10,
+    /// Up to 200 kΩ
+    Us15,
+    /// Up to 400 kΩ
+    Us20,
+    /// Up to 800 kΩ
+    Us40,
+}
+
+/// Bits in a reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Bits8,
+    Bits10,
+    Bits12,
+}
+
+impl Resolution {
+    pub const fn bits(self) -> u32 {
+        match self {
+            Self::Bits8 => 8,
+            Self::Bits10 => 10,
+            Self::Bits12 => 12,
+        }
+    }
+
+    /// The RESOLUTION register
+    pub const fn register(self) -> u32 {
+        match self {
+            Self::Bits8 => 0,
+            Self::Bits10 => 1,
+            Self::Bits12 => 2,
+        }
+    }
+
+    /// The largest reading, at full scale
+    pub const fn max(self) -> i16 {
+        (1 << self.bits()) - 1
+    }
+}
+
+/// How one pin is measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputConfig {
+    pub gain: Gain,
+    pub reference: Reference,
+    pub acquisition: AcquisitionTime,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self::ratiometric()
+    }
+}
+
+impl InputConfig {
+    /// Full scale is VDD: a potentiometer between 3V and GND reads from 0 to the maximum
+    pub const fn ratiometric() -> Self {
+        Self {
+            gain: Gain::OneQuarter,
+            reference: Reference::VddQuarter,
+            acquisition: AcquisitionTime::Us10,
+        }
+    }
+
+    /// Full scale is 0.6 V divided by `gain`
+    pub const fn internal(gain: Gain) -> Self {
+        Self {
+            gain,
+            reference: Reference::Internal,
+            acquisition: AcquisitionTime::Us10,
+        }
+    }
+
+    /// The CH[n].CONFIG register: single ended, no resistor ladder, no burst
+    pub const fn config_bits(&self) -> u32 {
+        let gain = match self.gain {
+            Gain::OneSixth => 0,
+            Gain::OneFifth => 1,
+            Gain::OneQuarter => 2,
+            Gain::OneThird => 3,
+            Gain::OneHalf => 4,
+            Gain::One => 5,
+            Gain::Two => 6,
+            Gain::Four => 7,
+        };
+        let reference = match self.reference {
+            Reference::Internal => 0,
+            Reference::VddQuarter => CONFIG_REFSEL_VDD1_4,
+        };
+        let acquisition = match self.acquisition {
+            AcquisitionTime::Us3 => 0,
+            AcquisitionTime::Us5 => 1,
+            AcquisitionTime::Us10 => 2,
+            AcquisitionTime::Us15 => 3,
+            AcquisitionTime::Us20 => 4,
+            AcquisitionTime::Us40 => 5,
+        };
+        gain << CONFIG_GAIN_SHIFT | reference | acquisition << CONFIG_TACQ_SHIFT
+    }
+
+    /// The reference in millivolts, with VDD at `vdd_mv`
+    pub const fn reference_mv(&self, vdd_mv: u32) -> u32 {
+        match self.reference {
+            Reference::Internal => INTERNAL_REFERENCE_MV,
+            Reference::VddQuarter => vdd_mv / 4,
+        }
+    }
+
+    /// The voltage that gives the largest reading. No pin can go above VDD, whatever this says.
+    pub const fn full_scale_mv(&self, vdd_mv: u32) -> u32 {
+        let (numerator, denominator) = self.gain.ratio();
+        self.reference_mv(vdd_mv) * denominator / numerator
+    }
+
+    /// A reading in millivolts, rounded to the nearest. Readings near 0 V can be slightly negative: the SAADC
+    /// measures against ground with some offset.
+    pub fn millivolts(&self, raw: i16, resolution: Resolution, vdd_mv: u32) -> i32 {
+        let (numerator, denominator) = self.gain.ratio();
+        let scaled = i64::from(raw) * i64::from(self.reference_mv(vdd_mv)) * i64::from(denominator);
+        let divisor = i64::from(numerator) << resolution.bits();
+        (scaled * 2 + divisor).div_euclid(divisor * 2) as i32
+    }
+}
+
+/// A reading as a fraction of full scale, from 0 to `max`. Negative readings count as 0.
+pub fn scale(raw: i16, resolution: Resolution, max: u16) -> u16 {
+    let raw = raw.clamp(0, resolution.max()) as u32;
+    let full = resolution.max() as u32;
+    ((raw * u32::from(max) + full / 2) / full) as u16
+}
+
+/// The PSELP register value that connects the SAADC to `pin`
+pub fn pselp<P: AnalogPin>(_pin: &P) -> u32 {
+    // 0 is "not connected", AIN0 is 1
+    u32::from(P::AIN) + 1
+}
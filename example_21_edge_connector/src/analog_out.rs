@@ -0,0 +1,94 @@
+//! Analog output on up to four edge connector pins from one PWM instance.
+//!
+//! The four channels share the frequency, each has its own level. The PWM reads the levels from RAM with EasyDMA
+//! (one value per channel, the "individual" decoder mode), plays the sequence once, and keeps outputting its last
+//! values after that. So changing a level is: write the new value, start the sequence again.
+
+use core::{
+    ops::Deref,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+use microbit::hal::{
+    gpio::{Level, Output, Pin, PushPull},
+    pac::pwm0::RegisterBlock,
+};
+
+use crate::{
+    edge::IntoGpio,
+    pins::EdgePin,
+    pwm::{Channel, FrequencyError, Period},
+};
+
+/// The sequence the PWM plays, one value per channel. It must be in RAM and live forever, e.g. from
+/// `cortex_m::singleton!`.
+pub type Levels = [u16; 4];
+
+/// PSEL.OUT value for an unused channel
+const PSEL_DISCONNECTED: u32 = 1 << 31;
+
+/// A PWM instance driving edge connector pins
+pub struct AnalogOut<T> {
+    pwm: T,
+    period: Period,
+    levels: &'static mut Levels,
+    pins: [Option<Pin<Output<PushPull>>>; 4],
+}
+
+impl<T: Deref<Target = RegisterBlock>> AnalogOut<T> {
+    /// Take over a PWM instance (PWM0 to PWM3), with no pins yet
+    pub fn new(pwm: T, frequency_hz: u32, levels: &'static mut Levels) -> Result<Self, FrequencyError> {
+        let period = Period::for_frequency(frequency_hz)?;
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| unsafe { w.bits(period.prescaler) });
+        pwm.countertop
+            .write(|w| unsafe { w.countertop().bits(period.countertop) });
+        // One value for each channel, played once
+        pwm.decoder.write(|w| w.load().individual().mode().refresh_count());
+        pwm.loop_.write(|w| unsafe { w.bits(0) });
+
+        *levels = [period.sequence_value(0); 4];
+        pwm.seq0.ptr.write(|w| unsafe { w.bits(levels.as_ptr() as u32) });
+        pwm.seq0.cnt.write(|w| unsafe { w.bits(levels.len() as u32) });
+        pwm.seq0.refresh.write(|w| unsafe { w.bits(0) });
+        pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+        pwm.enable.write(|w| w.enable().enabled());
+
+        Ok(Self {
+            pwm,
+            period,
+            levels,
+            pins: [None, None, None, None],
+        })
+    }
+
+    /// The frequency the PWM really runs at
+    pub fn frequency_hz(&self) -> u32 {
+        self.period.frequency_hz()
+    }
+
+    /// Output `channel` on `pin`, starting at level 0. A pin already on the channel is returned, as a low output.
+    pub fn connect<P: EdgePin>(&mut self, channel: Channel, pin: P) -> Option<Pin<Output<PushPull>>> {
+        let pin = pin.into_gpio().into_push_pull_output(Level::Low);
+        let index = channel.index();
+        self.pwm.psel.out[index].write(|w| unsafe { w.bits(P::INFO.psel_bits()) });
+        let previous = self.pins[index].replace(pin);
+        self.write(channel, 0);
+        previous
+    }
+
+    /// Stop outputting `channel`, and give its pin back as a low output
+    pub fn disconnect(&mut self, channel: Channel) -> Option<Pin<Output<PushPull>>> {
+        self.pwm.psel.out[channel.index()].write(|w| unsafe { w.bits(PSEL_DISCONNECTED) });
+        self.pins[channel.index()].take()
+    }
+
+    /// Set the level of `channel`, from 0 (always low) to 1023 (always high)
+    pub fn write(&mut self, channel: Channel, level: u16) {
+        self.levels[channel.index()] = self.period.sequence_value(level);
+        // The new value must be in RAM before the PWM reads it
+        compiler_fence(Ordering::SeqCst);
+        self.pwm.events_seqend[0].write(|w| unsafe { w.bits(0) });
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+    }
+}
@@ -0,0 +1,66 @@
+//! Getting the edge connector pins from the board, and turning them into HAL pins for digital I/O.
+//!
+//! The board support crate hands out the edge connector pins in several structs, named after the nRF52833's
+//! GPIOs. [`take`] exchanges them for an [`EdgeConnector`] named after the edge connector, and [`IntoGpio`]
+//! turns any of its pins into a HAL pin, with every GPIO mode the HAL has.
+
+use microbit::{
+    board::{Buttons, Edge, I2CExternalPins, Pins},
+    gpio::DisplayPins,
+    hal::{
+        gpio::{Disconnected, Pin},
+        pac::{NVMC, UICR},
+    },
+};
+
+use crate::pins::{ButtonPins, DisplayColumns, EdgeConnector, EdgePin};
+
+/// The free edge connector pins, in exchange for the board's structs that hold them
+pub fn take(_edge: Edge, _pins: Pins, _i2c_external: I2CExternalPins) -> EdgeConnector {
+    // The board's pin structs are gone, so nothing else can use these pins
+    unsafe { EdgeConnector::steal() }
+}
+
+/// P3, P4, P6, P7 and P10, in exchange for the display
+pub fn release_display(_display_pins: DisplayPins) -> DisplayColumns {
+    unsafe { DisplayColumns::steal() }
+}
+
+/// P5 and P11, in exchange for the buttons
+pub fn release_buttons(_buttons: Buttons) -> ButtonPins {
+    unsafe { ButtonPins::steal() }
+}
+
+/// An edge connector pin as a HAL pin
+pub trait IntoGpio: EdgePin + Sized {
+    /// The HAL pin, disconnected: `into_push_pull_output`, `into_pullup_input` and the rest from here
+    fn into_gpio(self) -> Pin<Disconnected> {
+        // `self` is the only value of this pin type, and it is used up
+        unsafe { Pin::from_psel_bits(Self::INFO.psel_bits()) }
+    }
+}
+
+impl<P: EdgePin> IntoGpio for P {}
+
+// UICR.NFCPINS: bit 0 set means P0.09 and P0.10 are the NFC antenna
+const NFCPINS_PROTECT_NFC: u32 = 1;
+
+/// Make P8 and P9 (P0.10 and P0.09) usable as GPIOs.
+///
+/// Out of the factory they are the NFC antenna pins, protected against being driven. Changing this is a setting
+/// in UICR, which is flash: it is written once, then the chip resets to apply it. The setting stays until the
+/// whole flash is erased, so this only resets the first time.
+pub fn nfc_pins_as_gpio(nvmc: &NVMC) {
+    // Only NFCPINS is read and written
+    let uicr = unsafe { &*UICR::ptr() };
+    if uicr.nfcpins.read().bits() & NFCPINS_PROTECT_NFC == 0 {
+        return;
+    }
+    nvmc.config.write(|w| w.wen().wen());
+    while nvmc.ready.read().ready().is_busy() {}
+    uicr.nfcpins.write(|w| unsafe { w.bits(!NFCPINS_PROTECT_NFC) });
+    while nvmc.ready.read().ready().is_busy() {}
+    nvmc.config.write(|w| w.wen().ren());
+    while nvmc.ready.read().ready().is_busy() {}
+    cortex_m::peripheral::SCB::sys_reset();
+}
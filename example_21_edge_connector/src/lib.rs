@@ -0,0 +1,25 @@
+#![no_std]
+
+//! The micro:bit v2 edge connector: typed pins for digital I/O, analog input and analog (PWM) output.
+//!
+//! - [`pins`] has a type for each pin P0 to P20, handed out so that pins used by the display or the buttons are
+//!   only available once those are given up
+//! - [`analog`] turns SAADC reference, gain and resolution settings into register values, and readings into
+//!   millivolts
+//! - [`pwm`] works out the PWM period for a frequency, and the duty cycle for a level from 0 to 1023
+//! - [`edge`] exchanges the board's pins for the typed ones and turns them into HAL pins (target only)
+//! - [`adc`] reads a pin with the SAADC (target only)
+//! - [`analog_out`] outputs levels on up to four pins with a PWM instance (target only)
+//!
+//! `pins`, `analog` and `pwm` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod analog;
+pub mod pins;
+pub mod pwm;
+
+#[cfg(target_os = "none")]
+pub mod adc;
+#[cfg(target_os = "none")]
+pub mod analog_out;
+#[cfg(target_os = "none")]
+pub mod edge;
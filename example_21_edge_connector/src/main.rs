@@ -0,0 +1,87 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::digital::{InputPin, OutputPin},
+    example_21_edge_connector::{
+        adc::Adc,
+        analog::{self, InputConfig, Resolution, VDD_MV},
+        analog_out::{AnalogOut, Levels},
+        edge::{self, IntoGpio},
+        pwm::{Channel, DEFAULT_FREQUENCY_HZ, MAX_LEVEL},
+    },
+    microbit::{
+        display::blocking::Display,
+        hal::{gpio::Level, Timer},
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// The display is refreshed in slices this long, with the pins read in between
+#[cfg(target_os = "none")]
+const FRAME_MS: u32 = 50;
+/// Print the reading every this many frames
+#[cfg(target_os = "none")]
+const PRINT_EVERY: u32 = 10;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+    // The display keeps its pins: P3, P4, P6, P7 and P10 are out of reach below
+    let mut display = Display::new(board.display_pins);
+
+    let edge = edge::take(board.edge, board.pins, board.i2c_external);
+    let mut adc = Adc::new(board.SAADC, Resolution::Bits12);
+    let levels: &'static mut Levels = cortex_m::singleton!(: Levels = [0; 4]).unwrap();
+    let mut out = AnalogOut::new(board.PWM0, DEFAULT_FREQUENCY_HZ, levels).unwrap();
+    out.connect(Channel::C0, edge.p1);
+    let mut switch = edge.p2.into_gpio().into_pullup_input();
+    let mut heartbeat = edge.p16.into_gpio().into_push_pull_output(Level::Low);
+
+    let config = InputConfig::ratiometric();
+    rprintln!(
+        "P0: analog in (0 to {} mV), P1: analog out at {} Hz, P2: switch to GND, P16: heartbeat",
+        config.full_scale_mv(VDD_MV),
+        out.frequency_hz()
+    );
+
+    let mut frame = 0;
+    loop {
+        // P0 sets the level of P1, an LED on P1 follows the potentiometer on P0
+        let raw = adc.read(&edge.p0, &config);
+        let level = analog::scale(raw, adc.resolution(), MAX_LEVEL);
+        let closed = switch.is_low().unwrap();
+        out.write(Channel::C0, if closed { MAX_LEVEL } else { level });
+
+        // A bar graph of the level: up to 25 LEDs, row by row
+        let lit = usize::from(analog::scale(raw, adc.resolution(), 25));
+        let mut leds = [[0; 5]; 5];
+        for (index, led) in leds.iter_mut().flatten().enumerate() {
+            *led = u8::from(index < lit);
+        }
+
+        if frame % PRINT_EVERY == 0 {
+            rprintln!(
+                "P0 {:>4} = {:>4} mV, P1 level {:>4}, P2 {}",
+                raw,
+                config.millivolts(raw, adc.resolution(), VDD_MV),
+                level,
+                if closed { "closed (P1 full on)" } else { "open" }
+            );
+            heartbeat.set_state((frame / PRINT_EVERY % 2 == 0).into()).unwrap();
+        }
+        frame = frame.wrapping_add(1);
+
+        display.show(&mut timer0, leds, FRAME_MS);
+    }
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,219 @@
+//! The edge connector pins as types, so that the compiler keeps track of who owns which pin.
+//!
+//! Each pin P0 to P20 is a zero-sized type, and there is only ever one value of each. The values are handed out
+//! in groups:
+//!
+//! - [`EdgeConnector`]: the pins nothing on the board uses, including the big rings 0, 1 and 2
+//! - [`DisplayColumns`]: P3, P4, P6, P7 and P10, which drive the LED display's columns
+//! - [`ButtonPins`]: P5 and P11, wired to buttons A and B
+//!
+//! The last two groups can only be had by giving up the display or the buttons (see `edge::release_display` and
+//! `edge::release_buttons`), so a program cannot use a display column as a pin while the display still drives
+//! it. Using one of them without giving up the display does not compile:
+//!
+//! ```compile_fail
+//! let edge = unsafe { example_21_edge_connector::pins::EdgeConnector::steal() };
+//! let p3 = edge.p3; // P3 is display column 3: not part of the free pins
+//! ```
+//!
+//! P17 and P18 are not signals: they are the 3V supply.
+
+/// What a pin is wired to on the micro:bit besides the edge connector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Usage {
+    Free,
+    /// An LED display column (1 to 5)
+    DisplayColumn(u8),
+    /// Button A or B, with a pull-up resistor on the board
+    Button(char),
+    /// NFC antenna pin, see `edge::nfc_pins_as_gpio`
+    Nfc,
+}
+
+/// Where an edge connector pin goes on the nRF52833
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinInfo {
+    /// Name on the edge connector
+    pub label: &'static str,
+    /// GPIO port, 0 or 1
+    pub port: u8,
+    /// Pin in the port
+    pub pin: u8,
+    /// SAADC analog input, for pins that have one
+    pub ain: Option<u8>,
+    pub usage: Usage,
+}
+
+impl PinInfo {
+    /// The value PSEL registers (and `Pin::from_psel_bits`) take for this pin: port in bit 5, pin below it
+    pub const fn psel_bits(&self) -> u32 {
+        (self.port as u32) << 5 | self.pin as u32
+    }
+}
+
+/// An edge connector pin
+pub trait EdgePin {
+    const INFO: PinInfo;
+}
+
+/// An edge connector pin that the SAADC can measure
+pub trait AnalogPin: EdgePin {
+    /// SAADC analog input AIN0 to AIN7
+    const AIN: u8;
+}
+
+macro_rules! edge_pins {
+    ($($(#[$doc:meta])* $name:ident: port $port:literal pin $pin:literal, $usage:expr $(, AIN $ain:literal)?;)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug)]
+            pub struct $name {
+                _private: (),
+            }
+
+            impl EdgePin for $name {
+                const INFO: PinInfo = PinInfo {
+                    label: stringify!($name),
+                    port: $port,
+                    pin: $pin,
+                    ain: edge_pins!(@ain $($ain)?),
+                    usage: $usage,
+                };
+            }
+
+            $(
+                impl AnalogPin for $name {
+                    const AIN: u8 = $ain;
+                }
+            )?
+        )*
+
+        /// Every signal pin on the edge connector, P0 to P20
+        pub const PINS: &[PinInfo] = &[$($name::INFO),*];
+    };
+    (@ain $ain:literal) => {
+        Some($ain)
+    };
+    (@ain) => {
+        None
+    };
+}
+
+edge_pins! {
+    /// Big ring 0
+    P0: port 0 pin 2, Usage::Free, AIN 0;
+    /// Big ring 1
+    P1: port 0 pin 3, Usage::Free, AIN 1;
+    /// Big ring 2
+    P2: port 0 pin 4, Usage::Free, AIN 2;
+    P3: port 0 pin 31, Usage::DisplayColumn(3), AIN 7;
+    P4: port 0 pin 28, Usage::DisplayColumn(1), AIN 4;
+    P5: port 0 pin 14, Usage::Button('A');
+    P6: port 1 pin 5, Usage::DisplayColumn(4);
+    P7: port 0 pin 11, Usage::DisplayColumn(2);
+    P8: port 0 pin 10, Usage::Nfc;
+    P9: port 0 pin 9, Usage::Nfc;
+    P10: port 0 pin 30, Usage::DisplayColumn(5), AIN 6;
+    P11: port 0 pin 23, Usage::Button('B');
+    P12: port 0 pin 12, Usage::Free;
+    /// SPI SCK by convention
+    P13: port 0 pin 17, Usage::Free;
+    /// SPI MISO by convention
+    P14: port 0 pin 1, Usage::Free;
+    /// SPI MOSI by convention
+    P15: port 0 pin 13, Usage::Free;
+    P16: port 1 pin 2, Usage::Free;
+    /// External I2C SCL by convention
+    P19: port 0 pin 26, Usage::Free;
+    /// External I2C SDA by convention
+    P20: port 1 pin 0, Usage::Free;
+}
+
+/// The edge connector pins that are free to use
+#[derive(Debug)]
+pub struct EdgeConnector {
+    pub p0: P0,
+    pub p1: P1,
+    pub p2: P2,
+    pub p8: P8,
+    pub p9: P9,
+    pub p12: P12,
+    pub p13: P13,
+    pub p14: P14,
+    pub p15: P15,
+    pub p16: P16,
+    pub p19: P19,
+    pub p20: P20,
+}
+
+impl EdgeConnector {
+    /// # Safety
+    ///
+    /// There must be only one `EdgeConnector`, and nothing else may use its pins. `edge::take` makes sure of
+    /// this by taking the board's pins in exchange.
+    pub unsafe fn steal() -> Self {
+        Self {
+            p0: P0 { _private: () },
+            p1: P1 { _private: () },
+            p2: P2 { _private: () },
+            p8: P8 { _private: () },
+            p9: P9 { _private: () },
+            p12: P12 { _private: () },
+            p13: P13 { _private: () },
+            p14: P14 { _private: () },
+            p15: P15 { _private: () },
+            p16: P16 { _private: () },
+            p19: P19 { _private: () },
+            p20: P20 { _private: () },
+        }
+    }
+}
+
+/// The pins of the display columns that are on the edge connector
+#[derive(Debug)]
+pub struct DisplayColumns {
+    pub p3: P3,
+    pub p4: P4,
+    pub p6: P6,
+    pub p7: P7,
+    pub p10: P10,
+}
+
+impl DisplayColumns {
+    /// # Safety
+    ///
+    /// Nothing else may drive the display: `edge::release_display` takes the display pins in exchange.
+    pub unsafe fn steal() -> Self {
+        Self {
+            p3: P3 { _private: () },
+            p4: P4 { _private: () },
+            p6: P6 { _private: () },
+            p7: P7 { _private: () },
+            p10: P10 { _private: () },
+        }
+    }
+}
+
+/// The pins of buttons A and B
+#[derive(Debug)]
+pub struct ButtonPins {
+    pub p5: P5,
+    pub p11: P11,
+}
+
+impl ButtonPins {
+    /// # Safety
+    ///
+    /// Nothing else may use the buttons: `edge::release_buttons` takes them in exchange.
+    pub unsafe fn steal() -> Self {
+        Self {
+            p5: P5 { _private: () },
+            p11: P11 { _private: () },
+        }
+    }
+}
+
+/// Where the pin `_pin` is, for a pin value at hand
+pub fn info<P: EdgePin>(_pin: &P) -> PinInfo {
+    P::INFO
+}
@@ -0,0 +1,113 @@
+//! Analog output: a PWM square wave whose duty cycle sets the average voltage on a pin, like MakeCode's
+//! "analog write pin".
+//!
+//! Levels go from 0 (always low) to [`MAX_LEVEL`] (always high). An LED on the pin looks dimmer or brighter, and
+//! an RC filter turns the square wave into a steady voltage of `level / 1023 × 3.3 V`.
+//!
+//! ```text
+//!              ┌────┐         ┌────┐           high for compare counts,
+//! level 256:   │    │         │    │           low until COUNTERTOP
+//!           ───┘    └─────────┘    └───
+//!              ├─────────────┤
+//!                 period
+//! ```
+
+use core::fmt;
+
+/// Highest level, always on
+pub const MAX_LEVEL: u16 = 1023;
+
+/// The PWM clock before the prescaler
+pub const PWM_CLOCK_HZ: u32 = 16_000_000;
+
+/// Fast enough that an LED does not flicker. MakeCode uses 50 Hz, which suits servos.
+pub const DEFAULT_FREQUENCY_HZ: u32 = 1000;
+
+/// COUNTERTOP is 15 bits, and must be at least 3
+const MAX_COUNTERTOP: u32 = 32_767;
+const MIN_COUNTERTOP: u32 = 3;
+/// PRESCALER divides the clock by 2^0 to 2^7
+const MAX_PRESCALER: u32 = 7;
+
+/// Sequence value bit 15: the period starts high, and goes low at the compare value
+const POLARITY_FALLING_EDGE: u16 = 1 << 15;
+
+/// One of the four outputs of a PWM instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    C0,
+    C1,
+    C2,
+    C3,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 4] = [Channel::C0, Channel::C1, Channel::C2, Channel::C3];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A frequency the PWM cannot make
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyError {
+    /// Below 4 Hz: the period would not fit in COUNTERTOP even with the largest prescaler
+    TooLow,
+    /// Above 5.3 MHz: COUNTERTOP would be below 3
+    TooHigh,
+}
+
+impl fmt::Display for FrequencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLow => f.write_str("frequency too low, at least 4 Hz"),
+            Self::TooHigh => f.write_str("frequency too high, at most 5.3 MHz"),
+        }
+    }
+}
+
+/// PRESCALER and COUNTERTOP for a frequency
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    /// The clock is divided by 2^prescaler
+    pub prescaler: u32,
+    /// Clocks per period
+    pub countertop: u16,
+}
+
+impl Period {
+    /// The smallest prescaler that fits, which gives the finest steps between levels
+    pub fn for_frequency(frequency_hz: u32) -> Result<Self, FrequencyError> {
+        if frequency_hz == 0 {
+            return Err(FrequencyError::TooLow);
+        }
+        for prescaler in 0..=MAX_PRESCALER {
+            let countertop = (PWM_CLOCK_HZ >> prescaler) / frequency_hz;
+            if countertop < MIN_COUNTERTOP {
+                return Err(FrequencyError::TooHigh);
+            }
+            if countertop <= MAX_COUNTERTOP {
+                return Ok(Self {
+                    prescaler,
+                    countertop: countertop as u16,
+                });
+            }
+        }
+        Err(FrequencyError::TooLow)
+    }
+
+    /// The frequency this really gives, which can be slightly off the one asked for
+    pub fn frequency_hz(&self) -> u32 {
+        (PWM_CLOCK_HZ >> self.prescaler) / u32::from(self.countertop)
+    }
+
+    /// The sequence value for `level` (0 to [`MAX_LEVEL`], higher is clamped): the compare value, scaled to
+    /// COUNTERTOP, with the polarity bit that makes the output high for the start of the period
+    pub fn sequence_value(&self, level: u16) -> u16 {
+        let level = u32::from(level.min(MAX_LEVEL));
+        let top = u32::from(self.countertop);
+        let compare = (level * top + u32::from(MAX_LEVEL) / 2) / u32::from(MAX_LEVEL);
+        compare as u16 | POLARITY_FALLING_EDGE
+    }
+}
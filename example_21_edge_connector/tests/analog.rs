@@ -0,0 +1,78 @@
+//! Host tests for SAADC settings and conversions
+
+use example_21_edge_connector::{
+    analog::{pselp, scale, AcquisitionTime, Gain, InputConfig, Reference, Resolution, VDD_MV},
+    pins::EdgeConnector,
+};
+
+#[test]
+fn config_register() {
+    // Gain 1/4 is 2 in bits 8-10, VDD/4 is bit 12, 10 µs is 2 in bits 16-18
+    assert_eq!(InputConfig::ratiometric().config_bits(), 0x0002_1200);
+    assert_eq!(InputConfig::internal(Gain::OneSixth).config_bits(), 0x0002_0000);
+    let config = InputConfig {
+        gain: Gain::Four,
+        reference: Reference::Internal,
+        acquisition: AcquisitionTime::Us40,
+    };
+    assert_eq!(config.config_bits(), 0x0005_0700);
+}
+
+#[test]
+fn full_scale() {
+    assert_eq!(InputConfig::ratiometric().full_scale_mv(VDD_MV), 3300);
+    assert_eq!(InputConfig::internal(Gain::OneSixth).full_scale_mv(VDD_MV), 3600);
+    assert_eq!(InputConfig::internal(Gain::One).full_scale_mv(VDD_MV), 600);
+    assert_eq!(InputConfig::internal(Gain::Four).full_scale_mv(VDD_MV), 150);
+    // Ratiometric follows the supply
+    assert_eq!(InputConfig::ratiometric().full_scale_mv(3000), 3000);
+}
+
+#[test]
+fn millivolts() {
+    let config = InputConfig::internal(Gain::OneSixth);
+    assert_eq!(config.millivolts(0, Resolution::Bits12, VDD_MV), 0);
+    // 3.6 V full scale, 4096 steps
+    assert_eq!(config.millivolts(1024, Resolution::Bits12, VDD_MV), 900);
+    assert_eq!(config.millivolts(2048, Resolution::Bits12, VDD_MV), 1800);
+    assert_eq!(config.millivolts(128, Resolution::Bits8, VDD_MV), 1800);
+    assert_eq!(config.millivolts(512, Resolution::Bits10, VDD_MV), 1800);
+    assert_eq!(config.millivolts(-5, Resolution::Bits12, VDD_MV), -4);
+
+    let config = InputConfig::ratiometric();
+    assert_eq!(config.millivolts(4095, Resolution::Bits12, VDD_MV), 3299);
+    let config = InputConfig::internal(Gain::Two);
+    assert_eq!(config.millivolts(4095, Resolution::Bits12, VDD_MV), 300);
+}
+
+#[test]
+fn resolution() {
+    assert_eq!(Resolution::Bits8.max(), 255);
+    assert_eq!(Resolution::Bits10.max(), 1023);
+    assert_eq!(Resolution::Bits12.max(), 4095);
+    assert_eq!(
+        [Resolution::Bits8, Resolution::Bits10, Resolution::Bits12].map(Resolution::register),
+        [0, 1, 2]
+    );
+}
+
+#[test]
+fn scaling() {
+    assert_eq!(scale(0, Resolution::Bits12, 1023), 0);
+    assert_eq!(scale(4095, Resolution::Bits12, 1023), 1023);
+    assert_eq!(scale(2048, Resolution::Bits12, 1023), 512);
+    assert_eq!(scale(-3, Resolution::Bits12, 1023), 0);
+    // Readings over the maximum cannot happen at 12 bits, but 8 bit values in an i16 could be anything
+    assert_eq!(scale(300, Resolution::Bits8, 25), 25);
+    assert_eq!(scale(255, Resolution::Bits8, 25), 25);
+    assert_eq!(scale(5, Resolution::Bits8, 25), 0);
+}
+
+#[test]
+fn analog_input_selection() {
+    let edge = unsafe { EdgeConnector::steal() };
+    // PSELP: 0 is not connected, then AIN0 to AIN7
+    assert_eq!(pselp(&edge.p0), 1);
+    assert_eq!(pselp(&edge.p1), 2);
+    assert_eq!(pselp(&edge.p2), 3);
+}
@@ -0,0 +1,103 @@
+//! Host tests for the edge connector pin map
+
+use std::collections::HashSet;
+
+use example_21_edge_connector::pins::{self, AnalogPin, EdgeConnector, EdgePin, Usage, PINS};
+
+#[test]
+fn every_signal_pin_once() {
+    let labels: Vec<_> = PINS.iter().map(|pin| pin.label).collect();
+    let expected: Vec<_> = (0..=20)
+        .filter(|n| *n != 17 && *n != 18)
+        .map(|n| format!("P{}", n))
+        .collect();
+    assert_eq!(labels, expected);
+
+    let gpios: HashSet<_> = PINS.iter().map(|pin| (pin.port, pin.pin)).collect();
+    assert_eq!(gpios.len(), PINS.len(), "two edge pins on the same GPIO");
+    assert!(PINS.iter().all(|pin| pin.port <= 1 && pin.pin < 32));
+}
+
+#[test]
+fn big_rings() {
+    assert_eq!(pins::P0::INFO.psel_bits(), 2);
+    assert_eq!(pins::P1::INFO.psel_bits(), 3);
+    assert_eq!(pins::P2::INFO.psel_bits(), 4);
+}
+
+#[test]
+fn port_1_pins() {
+    // Port 1 is bit 5 of PSEL
+    assert_eq!(pins::P6::INFO.psel_bits(), 32 + 5);
+    assert_eq!(pins::P16::INFO.psel_bits(), 32 + 2);
+    assert_eq!(pins::P20::INFO.psel_bits(), 32);
+}
+
+#[test]
+fn analog_inputs_match_the_nrf52833() {
+    // nRF52833: AIN0-AIN7 are P0.02, P0.03, P0.04, P0.05, P0.28, P0.29, P0.30, P0.31
+    const AIN_GPIO: [u8; 8] = [2, 3, 4, 5, 28, 29, 30, 31];
+    for pin in PINS {
+        let expected = AIN_GPIO.iter().position(|gpio| pin.port == 0 && *gpio == pin.pin);
+        assert_eq!(pin.ain.map(usize::from), expected, "{}", pin.label);
+    }
+
+    fn ain<P: AnalogPin>() -> (Option<u8>, u8) {
+        (P::INFO.ain, P::AIN)
+    }
+    for (info, ain) in [
+        ain::<pins::P0>(),
+        ain::<pins::P1>(),
+        ain::<pins::P2>(),
+        ain::<pins::P3>(),
+        ain::<pins::P4>(),
+        ain::<pins::P10>(),
+    ] {
+        assert_eq!(info, Some(ain));
+    }
+}
+
+#[test]
+fn free_pins_are_the_edge_connector_struct() {
+    let edge = unsafe { EdgeConnector::steal() };
+    let handed_out = [
+        pins::info(&edge.p0),
+        pins::info(&edge.p1),
+        pins::info(&edge.p2),
+        pins::info(&edge.p8),
+        pins::info(&edge.p9),
+        pins::info(&edge.p12),
+        pins::info(&edge.p13),
+        pins::info(&edge.p14),
+        pins::info(&edge.p15),
+        pins::info(&edge.p16),
+        pins::info(&edge.p19),
+        pins::info(&edge.p20),
+    ];
+    let free: Vec<_> = PINS
+        .iter()
+        .filter(|pin| matches!(pin.usage, Usage::Free | Usage::Nfc))
+        .copied()
+        .collect();
+    assert_eq!(handed_out.to_vec(), free);
+}
+
+#[test]
+fn display_and_button_pins() {
+    let columns: Vec<_> = PINS
+        .iter()
+        .filter_map(|pin| match pin.usage {
+            Usage::DisplayColumn(column) => Some((column, pin.label)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(columns, [(3, "P3"), (1, "P4"), (4, "P6"), (2, "P7"), (5, "P10")]);
+
+    // Buttons A and B are P0.14 and P0.23, the display columns P0.28, P0.11, P0.31, P1.05 and P0.30
+    assert_eq!((pins::P5::INFO.usage, pins::P5::INFO.pin), (Usage::Button('A'), 14));
+    assert_eq!((pins::P11::INFO.usage, pins::P11::INFO.pin), (Usage::Button('B'), 23));
+    assert_eq!(pins::P4::INFO.psel_bits(), 28);
+    assert_eq!(pins::P7::INFO.psel_bits(), 11);
+    assert_eq!(pins::P3::INFO.psel_bits(), 31);
+    assert_eq!(pins::P10::INFO.psel_bits(), 30);
+}
@@ -0,0 +1,64 @@
+//! Host tests for PWM periods and levels
+
+use example_21_edge_connector::pwm::{FrequencyError, Period, DEFAULT_FREQUENCY_HZ, MAX_LEVEL};
+
+#[test]
+fn period_for_frequency() {
+    // 16 MHz / 1 kHz = 16000 clocks, fits without a prescaler
+    assert_eq!(
+        Period::for_frequency(DEFAULT_FREQUENCY_HZ),
+        Ok(Period {
+            prescaler: 0,
+            countertop: 16_000
+        })
+    );
+    // 50 Hz: 320000 clocks, 20000 after dividing by 16
+    assert_eq!(
+        Period::for_frequency(50),
+        Ok(Period {
+            prescaler: 4,
+            countertop: 20_000
+        })
+    );
+    assert_eq!(Period::for_frequency(4).unwrap().prescaler, 7);
+}
+
+#[test]
+fn frequency_limits() {
+    assert_eq!(Period::for_frequency(0), Err(FrequencyError::TooLow));
+    assert_eq!(Period::for_frequency(3), Err(FrequencyError::TooLow));
+    assert_eq!(Period::for_frequency(5_333_333).unwrap().countertop, 3);
+    assert_eq!(Period::for_frequency(5_400_000), Err(FrequencyError::TooHigh));
+}
+
+#[test]
+fn real_frequency() {
+    for frequency in [4, 50, 440, 1000, 20_000, 1_000_000] {
+        let period = Period::for_frequency(frequency).unwrap();
+        let real = period.frequency_hz();
+        assert!(
+            real.abs_diff(frequency) * 100 <= frequency,
+            "{} Hz gives {} Hz",
+            frequency,
+            real
+        );
+    }
+}
+
+#[test]
+fn levels() {
+    let period = Period::for_frequency(DEFAULT_FREQUENCY_HZ).unwrap();
+    // Polarity bit 15 set: high from the start of the period until the compare value
+    assert_eq!(period.sequence_value(0), 0x8000);
+    assert_eq!(period.sequence_value(MAX_LEVEL), 0x8000 | 16_000);
+    assert_eq!(period.sequence_value(MAX_LEVEL + 100), 0x8000 | 16_000);
+    assert_eq!(period.sequence_value(512) & 0x7FFF, 8008);
+
+    // Every level gives a compare value at least as high as the one below
+    let mut previous = 0;
+    for level in 0..=MAX_LEVEL {
+        let compare = period.sequence_value(level) & 0x7FFF;
+        assert!(compare >= previous);
+        previous = compare;
+    }
+}
@@ -0,0 +1,156 @@
+//! One SPI bus shared by several devices, each with its own chip select pin.
+//!
+//! SCK, MOSI and MISO go to every device on the bus; a device only listens (and only drives MISO) while its
+//! chip select is low. So talking to a device means: CS low, exchange bytes, CS high. Two devices must never be
+//! selected at once, or both drive MISO.
+//!
+//! ```text
+//!            SCK, MOSI, MISO  ─────┬─────────────┬──────────
+//!  nRF52833                   ┌────┴────┐   ┌────┴────┐
+//!            CS P16 ─────────►│ SD card │   │ 74HC595 │◄── CS P12
+//!                             └─────────┘   └─────────┘
+//! ```
+//!
+//! [`SharedBus`] owns the bus (the SPIM peripheral on the micro:bit, a simulator in the tests), and hands out a
+//! [`Device`] per chip select pin. `Device` implements `embedded-hal`'s `SpiDevice`: every transaction selects
+//! the device, runs, and deselects it again, so drivers written against `SpiDevice` never see the other
+//! devices. Each device also gets a delay, for the `Operation::DelayNs` steps a transaction can contain. For
+//! protocols that need to read until a device answers, like SD cards, [`Device::select`] keeps the device
+//! selected for as long as a closure runs.
+//!
+//! The bus is in a `RefCell`: fine for sharing in `main`, but not with interrupt handlers.
+
+use core::{cell::RefCell, fmt};
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::OutputPin,
+    spi::{self, ErrorKind, ErrorType, Operation, SpiBus, SpiDevice},
+};
+
+/// An SPI bus that several [`Device`]s take turns on
+pub struct SharedBus<B> {
+    bus: RefCell<B>,
+}
+
+impl<B: SpiBus> SharedBus<B> {
+    pub fn new(bus: B) -> Self {
+        Self { bus: RefCell::new(bus) }
+    }
+
+    /// A device on the bus, selected by `cs`. The pin is set high (deselected) straight away.
+    ///
+    /// `delay` waits out the `Operation::DelayNs` steps of the device's transactions.
+    pub fn device<CS: OutputPin, D: DelayNs>(&self, mut cs: CS, delay: D) -> Result<Device<'_, B, CS, D>, CS::Error> {
+        cs.set_high()?;
+        Ok(Device {
+            bus: &self.bus,
+            cs,
+            delay,
+        })
+    }
+
+    /// Clock `bytes` bytes of 0xFF with no device selected. SD cards need at least 74 clocks like this after
+    /// power-up before they answer.
+    pub fn idle_clocks(&self, bytes: usize) -> Result<(), B::Error> {
+        let mut bus = self.bus.borrow_mut();
+        for _ in 0..bytes {
+            bus.write(&[0xFF])?;
+        }
+        bus.flush()
+    }
+
+    /// Give the bus back
+    pub fn release(self) -> B {
+        self.bus.into_inner()
+    }
+}
+
+/// What went wrong in a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceError<B, P> {
+    Bus(B),
+    ChipSelect(P),
+}
+
+impl<B: spi::Error, P: fmt::Debug> spi::Error for DeviceError<B, P> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Bus(error) => error.kind(),
+            Self::ChipSelect(_) => ErrorKind::ChipSelectFault,
+        }
+    }
+}
+
+/// One device on a [`SharedBus`]
+pub struct Device<'a, B, CS, D> {
+    bus: &'a RefCell<B>,
+    cs: CS,
+    delay: D,
+}
+
+impl<B: SpiBus, CS: OutputPin, D> Device<'_, B, CS, D> {
+    /// Select the device, run `f` with the bus, and deselect it again, even if `f` failed.
+    ///
+    /// Panics if another device is in the middle of a transaction, which can only happen if `f` uses another
+    /// device on the same bus.
+    pub fn select<T, E>(&mut self, f: impl FnOnce(&mut B) -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<DeviceError<B::Error, CS::Error>>,
+    {
+        select(self.bus, &mut self.cs, f)
+    }
+
+    /// Give the chip select pin and the delay back
+    pub fn release(self) -> (CS, D) {
+        (self.cs, self.delay)
+    }
+}
+
+impl<B: SpiBus, CS: OutputPin, D> ErrorType for Device<'_, B, CS, D> {
+    type Error = DeviceError<B::Error, CS::Error>;
+}
+
+impl<B: SpiBus, CS: OutputPin, D: DelayNs> SpiDevice for Device<'_, B, CS, D> {
+    /// Run `operations` with the device selected.
+    ///
+    /// For `Operation::DelayNs` the bus is flushed first, so the delay starts after the last byte has gone out.
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        // Split `self`: the closure needs the delay while the bus and the pin are borrowed
+        let Self { bus, cs, delay } = self;
+        select(bus, cs, |bus| {
+            for operation in operations {
+                match operation {
+                    Operation::Read(buf) => bus.read(buf),
+                    Operation::Write(buf) => bus.write(buf),
+                    Operation::Transfer(read, write) => bus.transfer(read, write),
+                    Operation::TransferInPlace(buf) => bus.transfer_in_place(buf),
+                    Operation::DelayNs(ns) => bus.flush().map(|()| delay.delay_ns(*ns)),
+                }
+                .map_err(DeviceError::Bus)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// [`Device::select`], on the parts of a device
+fn select<B: SpiBus, CS: OutputPin, T, E>(
+    bus: &RefCell<B>,
+    cs: &mut CS,
+    f: impl FnOnce(&mut B) -> Result<T, E>,
+) -> Result<T, E>
+where
+    E: From<DeviceError<B::Error, CS::Error>>,
+{
+    let mut bus = bus.try_borrow_mut().expect("SPI bus in use by another device");
+    cs.set_low().map_err(DeviceError::ChipSelect)?;
+    let result = f(&mut bus);
+    let flushed = bus.flush().map_err(DeviceError::Bus);
+    let deselected = cs.set_high().map_err(DeviceError::ChipSelect);
+    // The first error wins: whatever `f` ran into explains what went wrong better than what followed
+    let value = result?;
+    flushed?;
+    deselected?;
+    Ok(value)
+}
@@ -0,0 +1,31 @@
+//! The two CRCs of the SD card protocol: CRC-7 protects commands, CRC-16 protects data blocks.
+//!
+//! In SPI mode a card only checks command CRCs once CRC checking is turned on with CMD59, except for CMD0 and
+//! CMD8, which are always checked. The driver always sends correct CRCs, and turns checking on.
+
+/// CRC-7 with polynomial x⁷ + x³ + 1, over the first five bytes of a command
+pub fn crc7(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        for bit in (0..8).rev() {
+            let feedback = ((byte >> bit) ^ (crc >> 6)) & 1;
+            crc = (crc << 1) & 0x7F;
+            if feedback != 0 {
+                crc ^= 0x09;
+            }
+        }
+    }
+    crc
+}
+
+/// CRC-16-CCITT (XMODEM: polynomial 0x1021, starting at 0), over a data block
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in bytes {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
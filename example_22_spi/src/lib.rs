@@ -0,0 +1,21 @@
+#![no_std]
+
+//! SPI on the edge connector: a bus shared by several devices, an SD card driver and a shift register driver.
+//!
+//! - [`bus`] shares one SPI bus between devices, each selected by its own chip select pin
+//! - [`sd`] drives an SD card in SPI mode: initialisation, capacity, reading and writing blocks
+//! - [`shift_register`] drives a 74HC595, eight outputs set over SPI
+//! - [`crc`] computes the CRC-7 and CRC-16 the SD card protocol uses
+//!
+//! Everything in here is written against the `embedded-hal` SPI and output pin traits rather than the nRF52833
+//! `Spim`, so the same code runs on the micro:bit and on the PC against the simulated bus in [`sim`]. See
+//! `tests/` for the host tests.
+
+pub mod bus;
+pub mod crc;
+pub mod sd;
+pub mod shift_register;
+
+// The simulated bus needs `std` (shared state, block storage) so it only exists in host builds
+#[cfg(not(target_os = "none"))]
+pub mod sim;
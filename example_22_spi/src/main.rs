@@ -0,0 +1,121 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_22_spi::{
+        bus::SharedBus,
+        sd::{CardType, SdCard, BLOCK_LEN},
+        shift_register::ShiftRegister,
+    },
+    microbit::hal::{
+        gpio::{Level, Output, Pin, PushPull},
+        pac::{SPIM2, TIMER1},
+        spim::{self, Frequency, Spim},
+        Timer,
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// The SD card on the bus
+#[cfg(target_os = "none")]
+type Card<'a> = SdCard<'a, Spim<SPIM2>, Pin<Output<PushPull>>, Timer<TIMER1>>;
+
+/// How long each LED of the running light stays on
+#[cfg(target_os = "none")]
+const STEP_MS: u32 = 100;
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+    let mut button_a = board.buttons.button_a;
+
+    // SCK on P13, MISO on P14, MOSI on P15. Cards must be initialised at 100-400 kHz.
+    let pins = spim::Pins {
+        sck: Some(board.pins.p0_17.into_push_pull_output(Level::Low).degrade()),
+        mosi: Some(board.pins.p0_13.into_push_pull_output(Level::Low).degrade()),
+        miso: Some(board.pins.p0_01.into_floating_input().degrade()),
+    };
+    let spim = Spim::new(board.SPIM2, pins, Frequency::K250, spim::MODE_0, 0xFF);
+    let bus = SharedBus::new(spim);
+
+    // The SD card is selected by P16, the 74HC595 by P12
+    let card_cs = board.edge.e16.into_push_pull_output(Level::High).degrade();
+    let leds_cs = board.edge.e12.into_push_pull_output(Level::High).degrade();
+    // Each device gets a timer of its own for delays inside its transactions
+    let mut card: Card = SdCard::new(bus.device(card_cs, Timer::new(board.TIMER1)).unwrap());
+    let mut leds = ShiftRegister::new(bus.device(leds_cs, Timer::new(board.TIMER2)).unwrap());
+    leds.set(0).unwrap();
+
+    // At least 74 clocks with no card selected before the first command
+    bus.idle_clocks(10).unwrap();
+    match card.init(&mut timer0) {
+        Ok(card_type) => {
+            set_frequency(Frequency::M8);
+            let kind = match card_type {
+                CardType::SdscV1 => "SDSC (version 1)",
+                CardType::SdscV2 => "SDSC (version 2)",
+                CardType::Sdhc => "SDHC/SDXC",
+            };
+            match card.num_blocks() {
+                Ok(blocks) => rprintln!("{} card, {} blocks, {} MiB", kind, blocks, blocks / 2048),
+                Err(error) => rprintln!("{} card, capacity unknown: {}", kind, error),
+            }
+        }
+        Err(error) => rprintln!("No SD card: {}", error),
+    }
+
+    // Block 0 is the master boot record on a card formatted with partitions: it ends with 55 AA
+    let mut block = 0;
+    let mut buf = [0; BLOCK_LEN];
+    if card.card_type().is_some() {
+        print_block(&mut card, block, &mut buf);
+    }
+
+    rprintln!("Running light on the 74HC595, button A reads the next block");
+    let mut step = 0u32;
+    let mut was_pressed = false;
+    loop {
+        leds.set(1 << (step % 8)).unwrap();
+        step = step.wrapping_add(1);
+
+        let pressed = button_a.is_low().unwrap();
+        if pressed && !was_pressed && card.card_type().is_some() {
+            block += 1;
+            print_block(&mut card, block, &mut buf);
+        }
+        was_pressed = pressed;
+
+        timer0.delay_ms(STEP_MS);
+    }
+}
+
+/// Read block `block` and print its first bytes and its last two
+#[cfg(target_os = "none")]
+fn print_block(card: &mut Card<'_>, block: u32, buf: &mut [u8; BLOCK_LEN]) {
+    if let Err(error) = card.read_block(block, buf) {
+        rprintln!("Block {}: {}", block, error);
+        return;
+    }
+    let end = &buf[BLOCK_LEN - 2..];
+    let note = if end == [0x55, 0xAA] { " (boot signature)" } else { "" };
+    rprintln!("Block {}: {:02x?} ... {:02x?}{}", block, &buf[..16], end, note);
+}
+
+/// Speed up the bus once the card is initialised. The HAL only sets the frequency in `Spim::new`.
+#[cfg(target_os = "none")]
+fn set_frequency(frequency: Frequency) {
+    // Only FREQUENCY is written, and no transfer is running
+    let spim = unsafe { &*SPIM2::ptr() };
+    spim.frequency.write(|w| w.frequency().variant(frequency));
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
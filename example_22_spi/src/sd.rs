@@ -0,0 +1,467 @@
+//! An SD card in SPI mode: initialisation, card capacity, and reading and writing 512 byte blocks.
+//!
+//! Every exchange starts with a 6 byte command from the host, and the card answers with a one byte R1 status
+//! (0 is "all good", bit 0 "still initialising", the other bits are errors), some commands with a few more
+//! bytes. Data blocks follow a start token and end with a CRC-16:
+//!
+//! ```text
+//! command   01 cccccc │ argument (4 bytes, MSB first) │ CRC-7 1        then 0xFF until R1 (up to 8 bytes)
+//! read      CMD17 ──► R1 ──► 0xFF ... 0xFF ──► 0xFE │ 512 data bytes │ CRC-16
+//! write     CMD24 ──► R1 ──► 0xFE │ 512 data bytes │ CRC-16 ──► data response ──► 0x00 while busy
+//! ```
+//!
+//! Initialisation works out which kind of card it is:
+//!
+//! | Step | Command | |
+//! |------|---------|---|
+//! | 1 | CMD0 | Reset, and switch to SPI mode (CS is low) |
+//! | 2 | CMD8 | "Do you do 2.7-3.6 V?" Version 1 cards do not know the command |
+//! | 3 | CMD59 | Turn CRC checking on |
+//! | 4 | ACMD41 | Start initialising, repeated until R1 is 0 (up to a second). Version 2 hosts offer high capacity |
+//! | 5 | CMD58 | Version 2: read the OCR. Its CCS bit tells SDHC/SDXC (block addresses) from SDSC (byte addresses) |
+//! | 6 | CMD16 | SDSC: set the block length to 512 |
+//!
+//! The card needs at least 74 clocks with CS high before this, and a clock of at most 400 kHz until it is
+//! done; afterwards up to 25 MHz. Both are up to the caller, see [`SharedBus::idle_clocks`].
+//!
+//! [`SharedBus::idle_clocks`]: crate::bus::SharedBus::idle_clocks
+
+use core::fmt;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{self, OutputPin},
+    spi::{self, SpiBus},
+};
+
+use crate::{
+    bus::{Device, DeviceError},
+    crc::{crc16, crc7},
+};
+
+/// Bytes in a block
+pub const BLOCK_LEN: usize = 512;
+
+// Commands
+const CMD0_GO_IDLE_STATE: u8 = 0;
+const CMD8_SEND_IF_COND: u8 = 8;
+const CMD9_SEND_CSD: u8 = 9;
+const CMD13_SEND_STATUS: u8 = 13;
+const CMD16_SET_BLOCKLEN: u8 = 16;
+const CMD17_READ_SINGLE_BLOCK: u8 = 17;
+const CMD24_WRITE_BLOCK: u8 = 24;
+const CMD55_APP_CMD: u8 = 55;
+const CMD58_READ_OCR: u8 = 58;
+const CMD59_CRC_ON_OFF: u8 = 59;
+const ACMD41_SD_SEND_OP_COND: u8 = 41;
+
+/// CMD8 argument: 2.7-3.6 V, and a check pattern the card echoes back
+const IF_COND_ARGUMENT: u32 = 0x1AA;
+/// ACMD41 argument: the host supports high capacity cards
+const HCS: u32 = 1 << 30;
+/// OCR: high capacity card
+const OCR_CCS: u32 = 1 << 30;
+
+// R1 bits
+const R1_IDLE: u8 = 1 << 0;
+const R1_ILLEGAL_COMMAND: u8 = 1 << 2;
+
+// Data tokens
+const START_BLOCK: u8 = 0xFE;
+const DATA_RESPONSE_MASK: u8 = 0x1F;
+const DATA_ACCEPTED: u8 = 0x05;
+
+/// CMD0 attempts before giving up: there is no card, or it is not in SPI mode
+const RESET_ATTEMPTS: u32 = 10;
+/// Bytes to wait for R1
+const RESPONSE_BYTES: u32 = 8;
+/// ACMD41 attempts 10 ms apart: the card has a second to initialise
+const INIT_ATTEMPTS: u32 = 100;
+const INIT_POLL_MS: u32 = 10;
+/// Bytes to wait for a data block (100 ms at 8 MHz)
+const READ_TIMEOUT_BYTES: u32 = 100_000;
+/// Bytes to wait for a write to finish (250 ms at 8 MHz)
+const WRITE_TIMEOUT_BYTES: u32 = 250_000;
+
+/// What sort of card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardType {
+    /// Standard capacity, version 1: up to 2 GB, byte addresses
+    SdscV1,
+    /// Standard capacity, version 2: up to 2 GB, byte addresses
+    SdscV2,
+    /// High or extended capacity (SDHC, SDXC): block addresses
+    Sdhc,
+}
+
+/// What went wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Spi(E),
+    /// No R1 within 8 bytes: no card, or not in SPI mode
+    NoResponse,
+    /// The card answered a command with error bits in R1
+    Command {
+        command: u8,
+        r1: u8,
+    },
+    /// Not an SD card this driver knows: wrong voltage, or an MMC card
+    Unsupported,
+    /// Still initialising after a second
+    InitTimeout,
+    /// [`SdCard::init`] has not succeeded yet
+    NotInitialized,
+    /// No data block within 100 ms
+    ReadTimeout,
+    /// The card sent an error token instead of a data block
+    ReadError(u8),
+    /// A data block arrived damaged
+    Crc,
+    /// The card did not accept written data: 0x0B CRC error, 0x0D write error
+    WriteRejected(u8),
+    /// Still busy writing after 250 ms
+    WriteTimeout,
+    /// Past the end of a standard capacity card's byte addresses
+    OutOfRange,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Self::Spi(error)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spi(error) => write!(f, "SPI error {:?}", error),
+            Self::NoResponse => f.write_str("no response, is there a card?"),
+            Self::Command { command, r1 } => write!(f, "CMD{} failed, R1 {:#04x}", command, r1),
+            Self::Unsupported => f.write_str("unsupported card"),
+            Self::InitTimeout => f.write_str("card did not finish initialising"),
+            Self::NotInitialized => f.write_str("card not initialised"),
+            Self::ReadTimeout => f.write_str("timeout waiting for data"),
+            Self::ReadError(token) => write!(f, "read error token {:#04x}", token),
+            Self::Crc => f.write_str("data CRC mismatch"),
+            Self::WriteRejected(response) => write!(f, "write rejected, response {:#04x}", response),
+            Self::WriteTimeout => f.write_str("timeout waiting for the write to finish"),
+            Self::OutOfRange => f.write_str("block out of range"),
+        }
+    }
+}
+
+/// The error of a [`Device`] on bus `B` with chip select `CS`
+pub type SpiError<B, CS> = DeviceError<<B as spi::ErrorType>::Error, <CS as digital::ErrorType>::Error>;
+
+/// An SD card on a shared SPI bus
+pub struct SdCard<'a, B, CS, D> {
+    device: Device<'a, B, CS, D>,
+    card_type: Option<CardType>,
+}
+
+impl<'a, B: SpiBus, CS: OutputPin, D> SdCard<'a, B, CS, D> {
+    /// The card must still be initialised with [`SdCard::init`]
+    pub fn new(device: Device<'a, B, CS, D>) -> Self {
+        Self {
+            device,
+            card_type: None,
+        }
+    }
+
+    /// Give the device back
+    pub fn release(self) -> Device<'a, B, CS, D> {
+        self.device
+    }
+
+    /// What sort of card, once initialised
+    pub fn card_type(&self) -> Option<CardType> {
+        self.card_type
+    }
+
+    /// Bring the card from power-up to ready, see the module documentation
+    pub fn init(&mut self, delay: &mut impl DelayNs) -> Result<CardType, Error<SpiError<B, CS>>> {
+        self.card_type = None;
+
+        let mut reset = false;
+        for _ in 0..RESET_ATTEMPTS {
+            match self.command(CMD0_GO_IDLE_STATE, 0) {
+                Ok(R1_IDLE) => {
+                    reset = true;
+                    break;
+                }
+                Ok(_) | Err(Error::NoResponse) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        if !reset {
+            return Err(Error::NoResponse);
+        }
+
+        let version2 = self.selected(|bus| {
+            let r1 = command(bus, CMD8_SEND_IF_COND, IF_COND_ARGUMENT)?;
+            if r1 & R1_ILLEGAL_COMMAND != 0 {
+                return Ok(false);
+            }
+            let r7 = receive(bus)?;
+            if r1 != R1_IDLE {
+                return Err(Error::Command {
+                    command: CMD8_SEND_IF_COND,
+                    r1,
+                });
+            }
+            if u32::from_be_bytes(r7) & 0xFFF != IF_COND_ARGUMENT {
+                return Err(Error::Unsupported);
+            }
+            Ok(true)
+        })?;
+
+        self.expect(CMD59_CRC_ON_OFF, 1, R1_IDLE)?;
+
+        let argument = if version2 { HCS } else { 0 };
+        let mut ready = false;
+        for _ in 0..INIT_ATTEMPTS {
+            let r1 = self.selected(|bus| {
+                let r1 = command(bus, CMD55_APP_CMD, 0)?;
+                if r1 & !R1_IDLE != 0 {
+                    // Version 1 cards that do not know application commands are MMC cards
+                    return Err(Error::Unsupported);
+                }
+                command(bus, ACMD41_SD_SEND_OP_COND, argument)
+            })?;
+            match r1 {
+                0 => {
+                    ready = true;
+                    break;
+                }
+                R1_IDLE => delay.delay_ms(INIT_POLL_MS),
+                r1 => {
+                    return Err(Error::Command {
+                        command: ACMD41_SD_SEND_OP_COND,
+                        r1,
+                    })
+                }
+            }
+        }
+        if !ready {
+            return Err(Error::InitTimeout);
+        }
+
+        let card_type = if version2 {
+            let ocr = self.selected(|bus| {
+                let r1 = command(bus, CMD58_READ_OCR, 0)?;
+                let ocr = receive(bus)?;
+                if r1 != 0 {
+                    return Err(Error::Command {
+                        command: CMD58_READ_OCR,
+                        r1,
+                    });
+                }
+                Ok(u32::from_be_bytes(ocr))
+            })?;
+            if ocr & OCR_CCS != 0 {
+                CardType::Sdhc
+            } else {
+                CardType::SdscV2
+            }
+        } else {
+            CardType::SdscV1
+        };
+        if card_type != CardType::Sdhc {
+            self.expect(CMD16_SET_BLOCKLEN, BLOCK_LEN as u32, 0)?;
+        }
+
+        self.card_type = Some(card_type);
+        Ok(card_type)
+    }
+
+    /// The card's capacity in blocks, from its CSD register
+    pub fn num_blocks(&mut self) -> Result<u32, Error<SpiError<B, CS>>> {
+        self.card_type.ok_or(Error::NotInitialized)?;
+        let mut csd = [0; 16];
+        self.selected(|bus| {
+            let r1 = command(bus, CMD9_SEND_CSD, 0)?;
+            if r1 != 0 {
+                return Err(Error::Command {
+                    command: CMD9_SEND_CSD,
+                    r1,
+                });
+            }
+            read_data(bus, &mut csd)
+        })?;
+        csd_blocks(&csd).ok_or(Error::Unsupported)
+    }
+
+    /// Read block number `block`
+    pub fn read_block(&mut self, block: u32, buf: &mut [u8; BLOCK_LEN]) -> Result<(), Error<SpiError<B, CS>>> {
+        let address = self.address(block)?;
+        self.selected(|bus| {
+            let r1 = command(bus, CMD17_READ_SINGLE_BLOCK, address)?;
+            if r1 != 0 {
+                return Err(Error::Command {
+                    command: CMD17_READ_SINGLE_BLOCK,
+                    r1,
+                });
+            }
+            read_data(bus, buf)
+        })
+    }
+
+    /// Write block number `block`, and check that the card stored it
+    pub fn write_block(&mut self, block: u32, data: &[u8; BLOCK_LEN]) -> Result<(), Error<SpiError<B, CS>>> {
+        let address = self.address(block)?;
+        self.selected(|bus| {
+            let r1 = command(bus, CMD24_WRITE_BLOCK, address)?;
+            if r1 != 0 {
+                return Err(Error::Command {
+                    command: CMD24_WRITE_BLOCK,
+                    r1,
+                });
+            }
+            bus.write(&[0xFF, START_BLOCK]).map_err(bus_error)?;
+            bus.write(data).map_err(bus_error)?;
+            bus.write(&crc16(data).to_be_bytes()).map_err(bus_error)?;
+
+            let response = exchange(bus, 0xFF)? & DATA_RESPONSE_MASK;
+            if response != DATA_ACCEPTED {
+                return Err(Error::WriteRejected(response));
+            }
+            // The card holds MISO low while it programs the flash
+            for _ in 0..WRITE_TIMEOUT_BYTES {
+                if exchange(bus, 0xFF)? != 0 {
+                    return Ok(());
+                }
+            }
+            Err(Error::WriteTimeout)
+        })?;
+
+        // Programming errors are only reported by SEND_STATUS
+        let status = self.selected(|bus| {
+            let r1 = command(bus, CMD13_SEND_STATUS, 0)?;
+            let [r2] = receive(bus)?;
+            Ok((r1, r2))
+        })?;
+        if status != (0, 0) {
+            return Err(Error::Command {
+                command: CMD13_SEND_STATUS,
+                r1: status.0 | status.1,
+            });
+        }
+        Ok(())
+    }
+
+    /// The address argument for `block`: a block number for SDHC, a byte address for SDSC
+    fn address(&self, block: u32) -> Result<u32, Error<SpiError<B, CS>>> {
+        match self.card_type.ok_or(Error::NotInitialized)? {
+            CardType::Sdhc => Ok(block),
+            CardType::SdscV1 | CardType::SdscV2 => block.checked_mul(BLOCK_LEN as u32).ok_or(Error::OutOfRange),
+        }
+    }
+
+    /// Run `f` with the card selected
+    fn selected<T>(
+        &mut self,
+        f: impl FnOnce(&mut B) -> Result<T, Error<SpiError<B, CS>>>,
+    ) -> Result<T, Error<SpiError<B, CS>>> {
+        self.device.select(f)
+    }
+
+    /// A command on its own, returning R1
+    fn command(&mut self, command_index: u8, argument: u32) -> Result<u8, Error<SpiError<B, CS>>> {
+        self.selected(|bus| command(bus, command_index, argument))
+    }
+
+    /// A command on its own, which must answer with `expected`
+    fn expect(&mut self, command_index: u8, argument: u32, expected: u8) -> Result<(), Error<SpiError<B, CS>>> {
+        match self.command(command_index, argument)? {
+            r1 if r1 == expected => Ok(()),
+            r1 => Err(Error::Command {
+                command: command_index,
+                r1,
+            }),
+        }
+    }
+}
+
+/// The number of blocks in the card, from the CSD register (version 1 or 2), or `None` for an unknown version
+pub fn csd_blocks(csd: &[u8; 16]) -> Option<u32> {
+    match csd[0] >> 6 {
+        0 => {
+            // Capacity = (C_SIZE + 1) × 2^(C_SIZE_MULT + 2) × 2^READ_BL_LEN bytes
+            let read_bl_len = u32::from(csd[5] & 0x0F);
+            let c_size = u32::from(csd[6] & 0x03) << 10 | u32::from(csd[7]) << 2 | u32::from(csd[8]) >> 6;
+            let c_size_mult = u32::from(csd[9] & 0x03) << 1 | u32::from(csd[10]) >> 7;
+            let shift = (c_size_mult + 2 + read_bl_len).checked_sub(9)?;
+            Some((c_size + 1) << shift)
+        }
+        1 => {
+            // Capacity = (C_SIZE + 1) × 512 KiB
+            let c_size = u32::from(csd[7] & 0x3F) << 16 | u32::from(csd[8]) << 8 | u32::from(csd[9]);
+            Some((c_size + 1) * 1024)
+        }
+        _ => None,
+    }
+}
+
+fn bus_error<BE, PE>(error: BE) -> Error<DeviceError<BE, PE>> {
+    Error::Spi(DeviceError::Bus(error))
+}
+
+/// Send `byte`, return the byte received at the same time
+fn exchange<B: SpiBus, PE>(bus: &mut B, byte: u8) -> Result<u8, Error<DeviceError<B::Error, PE>>> {
+    let mut buf = [byte];
+    bus.transfer_in_place(&mut buf).map_err(bus_error)?;
+    Ok(buf[0])
+}
+
+/// Clock in `N` bytes, sending 0xFF: the card must see MOSI high while it talks
+fn receive<B: SpiBus, PE, const N: usize>(bus: &mut B) -> Result<[u8; N], Error<DeviceError<B::Error, PE>>> {
+    let mut buf = [0xFF; N];
+    bus.transfer_in_place(&mut buf).map_err(bus_error)?;
+    Ok(buf)
+}
+
+/// Send a command and return R1. The card must be selected.
+fn command<B: SpiBus, PE>(bus: &mut B, command: u8, argument: u32) -> Result<u8, Error<DeviceError<B::Error, PE>>> {
+    // Eight clocks first: some cards need them between commands
+    exchange(bus, 0xFF)?;
+    let mut frame = [0; 6];
+    frame[0] = 0x40 | command;
+    frame[1..5].copy_from_slice(&argument.to_be_bytes());
+    frame[5] = crc7(&frame[..5]) << 1 | 1;
+    bus.write(&frame).map_err(bus_error)?;
+
+    for _ in 0..RESPONSE_BYTES {
+        let r1 = exchange(bus, 0xFF)?;
+        if r1 & 0x80 == 0 {
+            return Ok(r1);
+        }
+    }
+    Err(Error::NoResponse)
+}
+
+/// Wait for a data block and read it into `buf`, checking its CRC
+fn read_data<B: SpiBus, PE>(bus: &mut B, buf: &mut [u8]) -> Result<(), Error<DeviceError<B::Error, PE>>> {
+    let mut started = false;
+    for _ in 0..READ_TIMEOUT_BYTES {
+        match exchange(bus, 0xFF)? {
+            START_BLOCK => {
+                started = true;
+                break;
+            }
+            0xFF => {}
+            // Error tokens are 0000xxxx
+            token if token & 0xF0 == 0 => return Err(Error::ReadError(token)),
+            _ => {}
+        }
+    }
+    if !started {
+        return Err(Error::ReadTimeout);
+    }
+    buf.fill(0xFF);
+    bus.transfer_in_place(buf).map_err(bus_error)?;
+    let crc = receive(bus)?;
+    if u16::from_be_bytes(crc) != crc16(buf) {
+        return Err(Error::Crc);
+    }
+    Ok(())
+}
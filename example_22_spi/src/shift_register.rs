@@ -0,0 +1,50 @@
+//! A 74HC595 shift register: eight outputs, set over SPI. A cheap way to get more output pins, for LEDs say.
+//!
+//! The chip shifts in a bit on every SCK rising edge, and copies the shifted byte to its outputs on a rising
+//! edge of RCLK. With RCLK wired to chip select, every byte written in a transaction appears on the outputs as
+//! the transaction ends, with no glitches while it is shifted in.
+//!
+//! ```text
+//!  MOSI ──► SER        QA ──► output 0 (the last bit sent: bit 0 of the byte)
+//!  SCK  ──► SRCLK      ...
+//!  CS   ──► RCLK       QH ──► output 7 (the first bit sent: bit 7)
+//! ```
+
+use embedded_hal::spi::SpiDevice;
+
+/// A 74HC595 on an SPI device
+pub struct ShiftRegister<D> {
+    device: D,
+    outputs: u8,
+}
+
+impl<D: SpiDevice> ShiftRegister<D> {
+    /// The outputs keep whatever they showed until the first [`ShiftRegister::set`]
+    pub fn new(device: D) -> Self {
+        Self { device, outputs: 0 }
+    }
+
+    /// Set all eight outputs: bit n is output n
+    pub fn set(&mut self, outputs: u8) -> Result<(), D::Error> {
+        self.device.write(&[outputs])?;
+        self.outputs = outputs;
+        Ok(())
+    }
+
+    /// Turn one output on or off, leaving the others as they are
+    pub fn set_output(&mut self, output: u8, on: bool) -> Result<(), D::Error> {
+        let mask = 1 << (output & 7);
+        let outputs = if on { self.outputs | mask } else { self.outputs & !mask };
+        self.set(outputs)
+    }
+
+    /// What the outputs were last set to
+    pub fn outputs(&self) -> u8 {
+        self.outputs
+    }
+
+    /// Give the device back
+    pub fn release(self) -> D {
+        self.device
+    }
+}
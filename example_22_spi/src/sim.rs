@@ -0,0 +1,606 @@
+//! Simulated SPI bus with an SD card and a 74HC595 shift register on it, for host tests.
+//!
+//! [`SimBus`] implements `embedded_hal::spi::SpiBus`, and every device on it gets a chip select pin
+//! ([`SimPin`], an `OutputPin`). Bytes go to the device whose pin is low, like on a real bus:
+//! - Selecting two devices at once panics: on a real bus both would drive MISO
+//! - With nothing selected, MISO reads 0xFF and the bytes are counted as idle clocks
+//!
+//! [`SimCard`] answers the SD commands the driver uses, byte by byte, as a card in SPI mode does: it only talks
+//! after CMD0, checks the CRC of CMD0 and CMD8 (and of every command and data block once CMD59 turns checking
+//! on), answers one byte after each command, and takes a few ACMD41 calls to initialise. It can be a version 1,
+//! version 2 standard capacity or a high capacity card. Reads can be delayed, corrupted or fail with an error
+//! token, and writes keep the card busy for a while.
+//!
+//! [`SimShiftRegister`] shifts in the bytes sent while it is selected and latches the last one onto its outputs
+//! when its chip select goes high, like a 74HC595 with RCLK on chip select.
+//!
+//! [`SimDelay`] waits no time at all, and records the delays it was asked for along with the device that was
+//! selected at the time.
+//!
+//! The bus is a cheap handle around shared state: clone it, give one copy to the code under test and keep the
+//! other to look at the devices.
+
+extern crate std;
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    rc::Rc,
+    vec,
+    vec::Vec,
+};
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{self, OutputPin},
+    spi::{self, SpiBus},
+};
+
+use crate::{
+    crc::{crc16, crc7},
+    sd::{CardType, BLOCK_LEN},
+};
+
+const R1_IDLE: u8 = 0x01;
+const R1_ILLEGAL_COMMAND: u8 = 0x04;
+const R1_COM_CRC_ERROR: u8 = 0x08;
+const R1_ADDRESS_ERROR: u8 = 0x20;
+const R1_PARAMETER_ERROR: u8 = 0x40;
+
+/// Error token for a read past the end of the card
+const TOKEN_OUT_OF_RANGE: u8 = 0x08;
+
+/// What a card is doing with the bytes it receives
+enum Phase {
+    /// Waiting for the start of a command
+    Idle,
+    Command(Vec<u8>),
+    /// After CMD24, waiting for the start token
+    WriteToken {
+        block: u32,
+    },
+    WriteData {
+        block: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// A simulated SD card, see the module documentation
+pub struct SimCard {
+    card_type: CardType,
+    num_blocks: u32,
+    blocks: HashMap<u32, [u8; BLOCK_LEN]>,
+    /// In SPI mode: CMD0 was received with CS low
+    spi_mode: bool,
+    initialized: bool,
+    crc_checks: bool,
+    app_command: bool,
+    /// ACMD41 calls left before the card is ready
+    init_polls: u32,
+    phase: Phase,
+    /// Bytes queued for MISO
+    out: VecDeque<u8>,
+    /// Bytes left for which MISO is held low after a write
+    busy: u32,
+    /// 0xFF bytes before a data block
+    read_delay: u32,
+    busy_bytes: u32,
+    corrupt_next_read: bool,
+    fail_next_read: Option<u8>,
+    commands: Vec<u8>,
+}
+
+impl SimCard {
+    /// A card of `num_blocks` blocks, all zero
+    pub fn new(card_type: CardType, num_blocks: u32) -> Self {
+        Self {
+            card_type,
+            num_blocks,
+            blocks: HashMap::new(),
+            spi_mode: false,
+            initialized: false,
+            crc_checks: false,
+            app_command: false,
+            init_polls: 3,
+            phase: Phase::Idle,
+            out: VecDeque::new(),
+            busy: 0,
+            read_delay: 10,
+            busy_bytes: 20,
+            corrupt_next_read: false,
+            fail_next_read: None,
+            commands: Vec::new(),
+        }
+    }
+
+    /// How many ACMD41 calls it takes until the card is ready
+    pub fn set_init_polls(&mut self, polls: u32) {
+        self.init_polls = polls;
+    }
+
+    /// 0xFF bytes before a data block
+    pub fn set_read_delay(&mut self, bytes: u32) {
+        self.read_delay = bytes;
+    }
+
+    /// Bytes MISO is held low after each write
+    pub fn set_busy_bytes(&mut self, bytes: u32) {
+        self.busy_bytes = bytes;
+    }
+
+    /// Flip a bit in the next data block sent, so its CRC no longer matches
+    pub fn corrupt_next_read(&mut self) {
+        self.corrupt_next_read = true;
+    }
+
+    /// Send an error token instead of the next data block
+    pub fn fail_next_read(&mut self, token: u8) {
+        self.fail_next_read = Some(token);
+    }
+
+    pub fn block(&self, block: u32) -> [u8; BLOCK_LEN] {
+        self.blocks.get(&block).copied().unwrap_or([0; BLOCK_LEN])
+    }
+
+    pub fn set_block(&mut self, block: u32, data: [u8; BLOCK_LEN]) {
+        self.blocks.insert(block, data);
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    pub fn crc_checks(&self) -> bool {
+        self.crc_checks
+    }
+
+    /// Command indexes received so far, application commands included
+    pub fn commands(&self) -> &[u8] {
+        &self.commands
+    }
+
+    /// The CSD register for this card's size
+    pub fn csd(&self) -> [u8; 16] {
+        csd(self.card_type, self.num_blocks)
+    }
+
+    /// One byte each way
+    fn exchange(&mut self, mosi: u8) -> u8 {
+        let miso = if let Some(byte) = self.out.pop_front() {
+            byte
+        } else if self.busy > 0 {
+            self.busy -= 1;
+            0x00
+        } else {
+            0xFF
+        };
+        self.receive(mosi);
+        miso
+    }
+
+    fn receive(&mut self, byte: u8) {
+        match &mut self.phase {
+            Phase::Idle => {
+                if byte & 0xC0 == 0x40 {
+                    self.phase = Phase::Command(vec![byte]);
+                }
+            }
+            Phase::Command(frame) => {
+                frame.push(byte);
+                if frame.len() == 6 {
+                    let frame: [u8; 6] = frame.as_slice().try_into().unwrap();
+                    self.phase = Phase::Idle;
+                    self.command(frame);
+                }
+            }
+            Phase::WriteToken { block } => match byte {
+                0xFE => {
+                    self.phase = Phase::WriteData {
+                        block: *block,
+                        data: Vec::new(),
+                    }
+                }
+                0xFF => {}
+                _ => self.phase = Phase::Idle,
+            },
+            Phase::WriteData { block, data } => {
+                data.push(byte);
+                if data.len() == BLOCK_LEN + 2 {
+                    let block = *block;
+                    let crc = u16::from_be_bytes([data[BLOCK_LEN], data[BLOCK_LEN + 1]]);
+                    let data: [u8; BLOCK_LEN] = data[..BLOCK_LEN].try_into().unwrap();
+                    self.phase = Phase::Idle;
+                    if self.crc_checks && crc != crc16(&data) {
+                        // Data response: CRC error
+                        self.out.push_back(0xEB);
+                    } else {
+                        self.blocks.insert(block, data);
+                        // Data response: accepted, then busy while programming
+                        self.out.push_back(0xE5);
+                        self.busy = self.busy_bytes;
+                    }
+                }
+            }
+        }
+    }
+
+    fn command(&mut self, frame: [u8; 6]) {
+        let index = frame[0] & 0x3F;
+        let argument = u32::from_be_bytes([frame[1], frame[2], frame[3], frame[4]]);
+        let crc_ok = frame[5] == crc7(&frame[..5]) << 1 | 1;
+        let app_command = core::mem::take(&mut self.app_command);
+        self.commands.push(index);
+
+        if !self.spi_mode {
+            // In SD mode a card ignores SPI framing, until CMD0 with CS low switches it over
+            if index == 0 && crc_ok {
+                self.spi_mode = true;
+            } else {
+                return;
+            }
+        }
+        let idle = if self.initialized { 0 } else { R1_IDLE };
+        // NCR: one byte before the answer
+        self.out.push_back(0xFF);
+
+        if !crc_ok && (self.crc_checks || index == 0 || index == 8) {
+            self.out.push_back(idle | R1_COM_CRC_ERROR);
+            return;
+        }
+
+        match (app_command, index) {
+            (_, 0) => {
+                self.initialized = false;
+                self.crc_checks = false;
+                self.out.push_back(R1_IDLE);
+            }
+            (_, 8) => {
+                if self.card_type == CardType::SdscV1 {
+                    self.out.push_back(idle | R1_ILLEGAL_COMMAND);
+                } else {
+                    // Voltage accepted if the host asked for 2.7-3.6 V, and the check pattern echoed
+                    let voltage = if argument >> 8 & 0xF == 1 { 1 } else { 0 };
+                    self.out.extend([idle, 0, 0, voltage, argument as u8]);
+                }
+            }
+            (_, 55) => {
+                self.app_command = true;
+                self.out.push_back(idle);
+            }
+            (true, 41) => {
+                let high_capacity_ok = argument & 1 << 30 != 0 || self.card_type != CardType::Sdhc;
+                if self.init_polls > 0 {
+                    self.init_polls -= 1;
+                } else if high_capacity_ok {
+                    self.initialized = true;
+                }
+                self.out.push_back(if self.initialized { 0 } else { R1_IDLE });
+            }
+            (_, 58) => {
+                let mut ocr: u32 = 0x00FF_8000;
+                if self.initialized {
+                    ocr |= 1 << 31;
+                    if self.card_type == CardType::Sdhc {
+                        ocr |= 1 << 30;
+                    }
+                }
+                self.out.push_back(idle);
+                self.out.extend(ocr.to_be_bytes());
+            }
+            (_, 59) => {
+                self.crc_checks = argument & 1 != 0;
+                self.out.push_back(idle);
+            }
+            _ if !self.initialized => self.out.push_back(R1_IDLE | R1_ILLEGAL_COMMAND),
+            (_, 9) => {
+                self.out.push_back(0);
+                let csd = self.csd();
+                self.send_block(&csd);
+            }
+            (_, 13) => self.out.extend([0, 0]),
+            (_, 16) => {
+                let ok = self.card_type == CardType::Sdhc || argument == BLOCK_LEN as u32;
+                self.out.push_back(if ok { 0 } else { R1_PARAMETER_ERROR });
+            }
+            (_, 17) => match self.block_number(argument) {
+                Err(r1) => self.out.push_back(r1),
+                Ok(block) => {
+                    self.out.push_back(0);
+                    if block >= self.num_blocks {
+                        self.out.push_back(TOKEN_OUT_OF_RANGE);
+                    } else if let Some(token) = self.fail_next_read.take() {
+                        self.out.push_back(token);
+                    } else {
+                        let data = self.block(block);
+                        self.send_block(&data);
+                    }
+                }
+            },
+            (_, 24) => match self.block_number(argument) {
+                Err(r1) => self.out.push_back(r1),
+                Ok(block) if block >= self.num_blocks => self.out.push_back(R1_PARAMETER_ERROR),
+                Ok(block) => {
+                    self.out.push_back(0);
+                    self.phase = Phase::WriteToken { block };
+                }
+            },
+            _ => self.out.push_back(R1_ILLEGAL_COMMAND),
+        }
+    }
+
+    /// The block an address argument means: block number on SDHC, byte address on SDSC
+    fn block_number(&self, argument: u32) -> Result<u32, u8> {
+        match self.card_type {
+            CardType::Sdhc => Ok(argument),
+            _ if !argument.is_multiple_of(BLOCK_LEN as u32) => Err(R1_ADDRESS_ERROR),
+            _ => Ok(argument / BLOCK_LEN as u32),
+        }
+    }
+
+    /// Queue a data block: the delay, the start token, the data and its CRC
+    fn send_block(&mut self, data: &[u8]) {
+        let crc = crc16(data);
+        self.out.extend(core::iter::repeat_n(0xFF, self.read_delay as usize));
+        self.out.push_back(0xFE);
+        let start = self.out.len();
+        self.out.extend(data);
+        if core::mem::take(&mut self.corrupt_next_read) {
+            self.out[start] ^= 0x01;
+        }
+        self.out.extend(crc.to_be_bytes());
+    }
+
+    /// The card drops whatever it was doing when it is deselected
+    fn deselect(&mut self) {
+        self.phase = Phase::Idle;
+        self.out.clear();
+    }
+}
+
+/// The CSD register of a card with `num_blocks` blocks: version 2 for SDHC, version 1 for standard capacity.
+///
+/// Version 1 can only describe sizes that are a multiple of 512 blocks (256 KiB), and with the 512 byte
+/// READ_BL_LEN used here at most 1 GiB. Version 2 needs a multiple of 1024 blocks (512 KiB).
+pub fn csd(card_type: CardType, num_blocks: u32) -> [u8; 16] {
+    let mut csd = [0; 16];
+    match card_type {
+        CardType::Sdhc => {
+            let c_size = num_blocks / 1024 - 1;
+            csd[0] = 0x40;
+            csd[5] = 0x59; // READ_BL_LEN 9, and card command classes
+            csd[7] = (c_size >> 16) as u8 & 0x3F;
+            csd[8] = (c_size >> 8) as u8;
+            csd[9] = c_size as u8;
+        }
+        CardType::SdscV1 | CardType::SdscV2 => {
+            // READ_BL_LEN 9 (512 bytes) and C_SIZE_MULT 7 (× 512): C_SIZE + 1 units of 512 blocks
+            let c_size = num_blocks / 512 - 1;
+            assert!(c_size < 4096, "too big for a standard capacity card");
+            csd[5] = 0x59;
+            csd[6] = (c_size >> 10) as u8 & 0x03;
+            csd[7] = (c_size >> 2) as u8;
+            csd[8] = (c_size as u8 & 0x03) << 6;
+            csd[9] = 0x03;
+            csd[10] = 0x80;
+        }
+    }
+    csd[15] = crc7(&csd[..15]) << 1 | 1;
+    csd
+}
+
+/// A simulated 74HC595, see the module documentation
+#[derive(Default)]
+pub struct SimShiftRegister {
+    shifted: u8,
+    outputs: u8,
+}
+
+impl SimShiftRegister {
+    /// What the eight outputs show
+    pub fn outputs(&self) -> u8 {
+        self.outputs
+    }
+}
+
+enum SimDevice {
+    Card(SimCard),
+    ShiftRegister(SimShiftRegister),
+}
+
+#[derive(Default)]
+struct State {
+    devices: Vec<SimDevice>,
+    selected: Vec<bool>,
+    idle_bytes: usize,
+    /// Every chip select change, as (device, low)
+    chip_selects: Vec<(usize, bool)>,
+    /// Every delay, as (selected device, ns)
+    delays: Vec<(Option<usize>, u32)>,
+}
+
+impl State {
+    fn exchange(&mut self, mosi: u8) -> u8 {
+        let mut selected = self.selected.iter().enumerate().filter(|(_, selected)| **selected);
+        let Some((index, _)) = selected.next() else {
+            self.idle_bytes += 1;
+            return 0xFF;
+        };
+        assert!(selected.next().is_none(), "two devices selected at once");
+        match &mut self.devices[index] {
+            SimDevice::Card(card) => card.exchange(mosi),
+            SimDevice::ShiftRegister(register) => {
+                register.shifted = mosi;
+                // The 74HC595 has no MISO: the line floats, and pulls up to 0xFF on the board
+                0xFF
+            }
+        }
+    }
+}
+
+/// Simulated SPI bus, see the module documentation
+#[derive(Clone, Default)]
+pub struct SimBus {
+    state: Rc<RefCell<State>>,
+}
+
+impl SimBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put `card` on the bus, and return its chip select pin
+    pub fn add_card(&self, card: SimCard) -> SimPin {
+        self.add(SimDevice::Card(card))
+    }
+
+    /// Put a shift register on the bus, and return its chip select pin
+    pub fn add_shift_register(&self) -> SimPin {
+        self.add(SimDevice::ShiftRegister(SimShiftRegister::default()))
+    }
+
+    fn add(&self, device: SimDevice) -> SimPin {
+        let mut state = self.state.borrow_mut();
+        state.devices.push(device);
+        state.selected.push(false);
+        SimPin {
+            state: self.state.clone(),
+            device: state.devices.len() - 1,
+        }
+    }
+
+    /// Look at or change the card selected by `device` (see [`SimPin::device`])
+    pub fn with_card<R>(&self, device: usize, f: impl FnOnce(&mut SimCard) -> R) -> R {
+        match &mut self.state.borrow_mut().devices[device] {
+            SimDevice::Card(card) => f(card),
+            SimDevice::ShiftRegister(_) => panic!("device {} is a shift register", device),
+        }
+    }
+
+    /// The outputs of the shift register selected by `device`
+    pub fn shift_register_outputs(&self, device: usize) -> u8 {
+        match &self.state.borrow().devices[device] {
+            SimDevice::ShiftRegister(register) => register.outputs(),
+            SimDevice::Card(_) => panic!("device {} is a card", device),
+        }
+    }
+
+    /// Bytes clocked with no device selected
+    pub fn idle_bytes(&self) -> usize {
+        self.state.borrow().idle_bytes
+    }
+
+    /// Every chip select change so far, as (device, low)
+    pub fn chip_selects(&self) -> Vec<(usize, bool)> {
+        self.state.borrow().chip_selects.clone()
+    }
+    /// A delay that records on this bus, for [`SharedBus::device`](crate::bus::SharedBus::device)
+    pub fn delay(&self) -> SimDelay {
+        SimDelay {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Every delay so far, as (selected device, ns)
+    pub fn delays(&self) -> Vec<(Option<usize>, u32)> {
+        self.state.borrow().delays.clone()
+    }
+}
+
+impl spi::ErrorType for SimBus {
+    type Error = Infallible;
+}
+
+impl SpiBus for SimBus {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        for word in words {
+            *word = state.exchange(0xFF);
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        for &word in words {
+            state.exchange(word);
+        }
+        Ok(())
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        for index in 0..read.len().max(write.len()) {
+            let miso = state.exchange(write.get(index).copied().unwrap_or(0xFF));
+            if let Some(word) = read.get_mut(index) {
+                *word = miso;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        for word in words {
+            *word = state.exchange(*word);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Chip select pin of one device on a [`SimBus`]
+pub struct SimPin {
+    state: Rc<RefCell<State>>,
+    device: usize,
+}
+
+impl SimPin {
+    /// Which device this pin selects
+    pub fn device(&self) -> usize {
+        self.device
+    }
+}
+
+impl digital::ErrorType for SimPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for SimPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        state.selected[self.device] = true;
+        state.chip_selects.push((self.device, true));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+        let was_selected = core::mem::replace(&mut state.selected[self.device], false);
+        state.chip_selects.push((self.device, false));
+        if was_selected {
+            match &mut state.devices[self.device] {
+                SimDevice::Card(card) => card.deselect(),
+                // RCLK rising edge: the shifted byte appears on the outputs
+                SimDevice::ShiftRegister(register) => register.outputs = register.shifted,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A delay that returns at once, and records what it was asked for on its [`SimBus`]
+pub struct SimDelay {
+    state: Rc<RefCell<State>>,
+}
+
+impl DelayNs for SimDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        let mut state = self.state.borrow_mut();
+        let selected = state.selected.iter().position(|selected| *selected);
+        state.delays.push((selected, ns));
+    }
+}
@@ -0,0 +1,110 @@
+//! Host tests for the shared bus and the shift register driver, on the simulated bus
+
+use embedded_hal::spi::{Operation, SpiDevice};
+use example_22_spi::{bus::SharedBus, sd::Error, shift_register::ShiftRegister, sim::SimBus};
+
+#[test]
+fn devices_start_deselected() {
+    let sim = SimBus::new();
+    let first = sim.add_shift_register();
+    let second = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let _first = bus.device(first, sim.delay()).unwrap();
+    let _second = bus.device(second, sim.delay()).unwrap();
+    assert_eq!(sim.chip_selects(), [(0, false), (1, false)]);
+}
+
+#[test]
+fn each_transaction_selects_its_device_once() {
+    let sim = SimBus::new();
+    let first = sim.add_shift_register();
+    let second = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let mut first = bus.device(first, sim.delay()).unwrap();
+    let mut second = bus.device(second, sim.delay()).unwrap();
+
+    first.write(&[0x01]).unwrap();
+    second.write(&[0x02]).unwrap();
+    first.write(&[0x03, 0x04]).unwrap();
+
+    assert_eq!(
+        sim.chip_selects()[2..],
+        [(0, true), (0, false), (1, true), (1, false), (0, true), (0, false)]
+    );
+    // Only the device that was selected saw the bytes
+    assert_eq!(sim.shift_register_outputs(0), 0x04);
+    assert_eq!(sim.shift_register_outputs(1), 0x02);
+    assert_eq!(sim.idle_bytes(), 0);
+}
+
+#[test]
+fn delays_run_with_the_device_selected() {
+    let sim = SimBus::new();
+    let first = sim.add_shift_register();
+    let second = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let _first = bus.device(first, sim.delay()).unwrap();
+    let mut second = bus.device(second, sim.delay()).unwrap();
+
+    second
+        .transaction(&mut [
+            Operation::Write(&[0x05]),
+            Operation::DelayNs(1_500),
+            Operation::Write(&[0x06]),
+        ])
+        .unwrap();
+
+    assert_eq!(sim.delays(), [(Some(1), 1_500)]);
+    assert_eq!(sim.chip_selects()[2..], [(1, true), (1, false)]);
+    assert_eq!(sim.shift_register_outputs(1), 0x06);
+}
+
+#[test]
+fn idle_clocks_select_nothing() {
+    let sim = SimBus::new();
+    let pin = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let _device = bus.device(pin, sim.delay()).unwrap();
+    bus.idle_clocks(10).unwrap();
+    assert_eq!(sim.idle_bytes(), 10);
+    assert_eq!(sim.chip_selects(), [(0, false)]);
+    assert_eq!(sim.shift_register_outputs(0), 0);
+}
+
+#[test]
+fn select_deselects_after_an_error() {
+    let sim = SimBus::new();
+    let pin = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let mut device = bus.device(pin, sim.delay()).unwrap();
+    let result: Result<(), _> = device.select(|_| Err(Error::NoResponse));
+    assert_eq!(result, Err(Error::NoResponse));
+    assert_eq!(sim.chip_selects(), [(0, false), (0, true), (0, false)]);
+}
+
+#[test]
+#[should_panic(expected = "SPI bus in use")]
+fn nested_transactions_panic() {
+    let sim = SimBus::new();
+    let first = sim.add_shift_register();
+    let second = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let mut first = bus.device(first, sim.delay()).unwrap();
+    let mut second = bus.device(second, sim.delay()).unwrap();
+    let _ = first.select(|_| second.write(&[0]));
+}
+
+#[test]
+fn shift_register_latches_at_the_end_of_the_transaction() {
+    let sim = SimBus::new();
+    let pin = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let mut leds = ShiftRegister::new(bus.device(pin, sim.delay()).unwrap());
+
+    leds.set(0b1010_0101).unwrap();
+    assert_eq!(sim.shift_register_outputs(0), 0b1010_0101);
+    leds.set_output(1, true).unwrap();
+    leds.set_output(0, false).unwrap();
+    assert_eq!(leds.outputs(), 0b1010_0110);
+    assert_eq!(sim.shift_register_outputs(0), 0b1010_0110);
+}
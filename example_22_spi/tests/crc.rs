@@ -0,0 +1,31 @@
+//! Host tests for the CRCs, against values from the SD specification
+
+use example_22_spi::crc::{crc16, crc7};
+
+#[test]
+fn command_crcs() {
+    // The last byte of each command is CRC-7 << 1 | 1: the spec's examples
+    assert_eq!(crc7(&[0x40, 0, 0, 0, 0]) << 1 | 1, 0x95); // CMD0
+    assert_eq!(crc7(&[0x48, 0, 0, 0x01, 0xAA]) << 1 | 1, 0x87); // CMD8, 2.7-3.6 V, pattern 0xAA
+    assert_eq!(crc7(&[0x51, 0, 0, 0, 0]) << 1 | 1, 0x55); // CMD17, block 0
+    assert_eq!(crc7(&[0x11, 0, 0, 0x09, 0]) << 1 | 1, 0x67); // R1 of CMD17
+}
+
+#[test]
+fn data_crcs() {
+    // The spec's example: a block of 512 0xFF bytes
+    assert_eq!(crc16(&[0xFF; 512]), 0x7FA1);
+    // The standard check for CRC-16/XMODEM
+    assert_eq!(crc16(b"123456789"), 0x31C3);
+    assert_eq!(crc16(&[]), 0);
+}
+
+#[test]
+fn single_bit_changes_are_caught() {
+    let reference = crc16(&[0xA5; 512]);
+    for bit in 0..512 * 8 {
+        let mut data = [0xA5; 512];
+        data[bit / 8] ^= 1 << (bit % 8);
+        assert_ne!(crc16(&data), reference, "bit {}", bit);
+    }
+}
@@ -0,0 +1,271 @@
+//! Host tests for the SD card driver, against the simulated card
+
+use std::convert::Infallible;
+
+use embedded_hal::delay::DelayNs;
+use example_22_spi::{
+    bus::SharedBus,
+    sd::{csd_blocks, CardType, Error, SdCard, BLOCK_LEN},
+    sim::{self, SimBus, SimCard},
+};
+
+/// 1 GiB for the standard capacity cards, 8 GiB for SDHC
+fn num_blocks(card_type: CardType) -> u32 {
+    match card_type {
+        CardType::SdscV1 | CardType::SdscV2 => 2 * 1024 * 1024,
+        CardType::Sdhc => 16 * 1024 * 1024,
+    }
+}
+
+/// Counts the time it was asked to wait instead of waiting
+#[derive(Default)]
+struct NoDelay {
+    ns: u64,
+}
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.ns += u64::from(ns);
+    }
+}
+
+/// A pattern that is different in every block
+fn pattern(block: u32) -> [u8; BLOCK_LEN] {
+    core::array::from_fn(|index| (index as u32 ^ block.wrapping_mul(31)) as u8)
+}
+
+/// A bus with `card` on it, after the power-up clocks
+fn setup(card: SimCard) -> (SimBus, SharedBus<SimBus>, sim::SimPin) {
+    let sim = SimBus::new();
+    let pin = sim.add_card(card);
+    let bus = SharedBus::new(sim.clone());
+    bus.idle_clocks(10).unwrap();
+    (sim, bus, pin)
+}
+
+#[test]
+fn init_finds_every_card_type() {
+    for card_type in [CardType::SdscV1, CardType::SdscV2, CardType::Sdhc] {
+        let (sim, bus, pin) = setup(SimCard::new(card_type, num_blocks(card_type)));
+        let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+        let mut delay = NoDelay::default();
+
+        assert_eq!(card.init(&mut delay), Ok(card_type));
+        assert_eq!(card.card_type(), Some(card_type));
+        assert!(sim.with_card(0, |card| card.is_initialized()));
+        assert!(sim.with_card(0, |card| card.crc_checks()), "{:?}", card_type);
+        // Three ACMD41 calls still busy, 10 ms apart
+        assert_eq!(delay.ns, 30_000_000);
+        assert_eq!(card.num_blocks(), Ok(num_blocks(card_type)));
+    }
+}
+
+#[test]
+fn init_sequence() {
+    let (sim, bus, pin) = setup(SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc)));
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    assert_eq!(
+        sim.with_card(0, |card| card.commands().to_vec()),
+        [0, 8, 59, 55, 41, 55, 41, 55, 41, 55, 41, 58]
+    );
+    // Standard capacity cards also get the block length set
+    let (sim, bus, pin) = setup(SimCard::new(CardType::SdscV1, num_blocks(CardType::SdscV1)));
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    assert_eq!(
+        sim.with_card(0, |card| card.commands().to_vec()),
+        [0, 8, 59, 55, 41, 55, 41, 55, 41, 55, 41, 16]
+    );
+}
+
+#[test]
+fn init_gives_up_on_a_card_that_stays_busy() {
+    let mut sim_card = SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc));
+    sim_card.set_init_polls(u32::MAX);
+    let (sim, bus, pin) = setup(sim_card);
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    let mut delay = NoDelay::default();
+    assert_eq!(card.init(&mut delay), Err(Error::InitTimeout));
+    assert_eq!(card.card_type(), None);
+    // About a second of polling
+    assert_eq!(delay.ns, 1_000_000_000);
+}
+
+#[test]
+fn no_card() {
+    // Nothing drives MISO: every byte reads 0xFF
+    let sim = SimBus::new();
+    let pin = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    assert_eq!(card.init(&mut NoDelay::default()), Err(Error::NoResponse));
+}
+
+#[test]
+fn not_initialized() {
+    let (sim, bus, pin) = setup(SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc)));
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    let mut buf = [0; BLOCK_LEN];
+    assert_eq!(card.read_block(0, &mut buf), Err(Error::NotInitialized));
+    assert_eq!(card.write_block(0, &buf), Err(Error::NotInitialized));
+    assert_eq!(card.num_blocks(), Err(Error::NotInitialized));
+}
+
+#[test]
+fn read_and_write() {
+    for card_type in [CardType::SdscV1, CardType::SdscV2, CardType::Sdhc] {
+        let last = num_blocks(card_type) - 1;
+        let mut sim_card = SimCard::new(card_type, num_blocks(card_type));
+        sim_card.set_block(7, pattern(7));
+        let (sim, bus, pin) = setup(sim_card);
+        let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+        card.init(&mut NoDelay::default()).unwrap();
+
+        let mut buf = [0; BLOCK_LEN];
+        card.read_block(7, &mut buf).unwrap();
+        assert_eq!(buf, pattern(7));
+
+        for block in [0, 1, 1000, last] {
+            card.write_block(block, &pattern(block)).unwrap();
+            assert_eq!(
+                sim.with_card(0, |card| card.block(block)),
+                pattern(block),
+                "{:?}",
+                card_type
+            );
+            card.read_block(block, &mut buf).unwrap();
+            assert_eq!(buf, pattern(block));
+        }
+        // Writing one block leaves its neighbours alone
+        card.read_block(2, &mut buf).unwrap();
+        assert_eq!(buf, [0; BLOCK_LEN]);
+    }
+}
+
+#[test]
+fn slow_cards() {
+    let mut sim_card = SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc));
+    sim_card.set_read_delay(5000);
+    sim_card.set_busy_bytes(50_000);
+    let (sim, bus, pin) = setup(sim_card);
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    card.write_block(3, &pattern(3)).unwrap();
+    let mut buf = [0; BLOCK_LEN];
+    card.read_block(3, &mut buf).unwrap();
+    assert_eq!(buf, pattern(3));
+}
+
+#[test]
+fn timeouts() {
+    let mut sim_card = SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc));
+    sim_card.set_read_delay(200_000);
+    sim_card.set_busy_bytes(300_000);
+    let (sim, bus, pin) = setup(sim_card);
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    let mut buf = [0; BLOCK_LEN];
+    assert_eq!(card.read_block(0, &mut buf), Err(Error::ReadTimeout));
+    assert_eq!(card.write_block(0, &buf), Err(Error::WriteTimeout));
+}
+
+#[test]
+fn corrupted_data_is_caught() {
+    let (sim, bus, pin) = setup(SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc)));
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    let mut buf = [0; BLOCK_LEN];
+
+    sim.with_card(0, |card| card.corrupt_next_read());
+    assert_eq!(card.read_block(0, &mut buf), Err(Error::Crc));
+    // Only the one read
+    card.read_block(0, &mut buf).unwrap();
+}
+
+#[test]
+fn error_tokens() {
+    let (sim, bus, pin) = setup(SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc)));
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    let mut buf = [0; BLOCK_LEN];
+
+    // ECC failed
+    sim.with_card(0, |card| card.fail_next_read(0x04));
+    assert_eq!(card.read_block(0, &mut buf), Err(Error::ReadError(0x04)));
+    // Out of range
+    let end = num_blocks(CardType::Sdhc);
+    assert_eq!(card.read_block(end, &mut buf), Err(Error::ReadError(0x08)));
+    assert_eq!(
+        card.write_block(end, &buf),
+        Err(Error::Command { command: 24, r1: 0x40 })
+    );
+}
+
+#[test]
+fn byte_addresses_that_do_not_fit() {
+    let (sim, bus, pin) = setup(SimCard::new(CardType::SdscV2, num_blocks(CardType::SdscV2)));
+    let mut card = SdCard::new(bus.device(pin, sim.delay()).unwrap());
+    card.init(&mut NoDelay::default()).unwrap();
+    let mut buf = [0; BLOCK_LEN];
+    assert_eq!(card.read_block(1 << 23, &mut buf), Err(Error::OutOfRange));
+}
+
+#[test]
+fn card_shares_the_bus() {
+    let sim = SimBus::new();
+    let card_pin = sim.add_card(SimCard::new(CardType::Sdhc, num_blocks(CardType::Sdhc)));
+    let leds_pin = sim.add_shift_register();
+    let bus = SharedBus::new(sim.clone());
+    bus.idle_clocks(10).unwrap();
+    let mut card = SdCard::new(bus.device(card_pin, sim.delay()).unwrap());
+    let mut leds = example_22_spi::shift_register::ShiftRegister::new(bus.device(leds_pin, sim.delay()).unwrap());
+
+    card.init(&mut NoDelay::default()).unwrap();
+    leds.set(0x0F).unwrap();
+    card.write_block(5, &pattern(5)).unwrap();
+    leds.set(0xF0).unwrap();
+    let mut buf = [0; BLOCK_LEN];
+    card.read_block(5, &mut buf).unwrap();
+
+    assert_eq!(buf, pattern(5));
+    assert_eq!(sim.shift_register_outputs(1), 0xF0);
+    // Never both selected, and every selection ends
+    let mut selected = None;
+    for (device, low) in sim.chip_selects() {
+        if low {
+            assert_eq!(selected, None);
+            selected = Some(device);
+        } else if selected == Some(device) {
+            selected = None;
+        }
+    }
+    assert_eq!(selected, None);
+}
+
+#[test]
+fn csd_capacity() {
+    for blocks in [512, 512 * 1024, 2 * 1024 * 1024] {
+        assert_eq!(csd_blocks(&sim::csd(CardType::SdscV1, blocks)), Some(blocks));
+    }
+    for blocks in [1024, 512 * 1024, 2 * 1024 * 1024 - 1024] {
+        assert_eq!(csd_blocks(&sim::csd(CardType::Sdhc, blocks)), Some(blocks));
+    }
+    assert_eq!(
+        csd_blocks(&sim::csd(CardType::Sdhc, 64 * 1024 * 1024)),
+        Some(64 * 1024 * 1024)
+    );
+    let mut unknown = sim::csd(CardType::Sdhc, 1024);
+    unknown[0] = 0x80;
+    assert_eq!(csd_blocks(&unknown), None);
+}
+
+#[test]
+fn errors_display() {
+    let error: Error<Infallible> = Error::Command { command: 17, r1: 0x04 };
+    assert_eq!(error.to_string(), "CMD17 failed, R1 0x04");
+    assert_eq!(
+        Error::<Infallible>::ReadError(0x08).to_string(),
+        "read error token 0x08"
+    );
+}
@@ -0,0 +1,62 @@
+//! Animations: each function draws one frame into a slice of pixels, from a step number that the caller counts
+//! up. Nothing is kept between frames, so changing the strip length or switching animations needs no setup.
+
+use crate::color::{Rgb, HUE_STEPS};
+
+/// Every pixel `color`
+pub fn fill(pixels: &mut [Rgb], color: Rgb) {
+    pixels.fill(color);
+}
+
+/// One turn of the colour wheel spread along the strip, starting at `first_hue`. Counting `first_hue` up makes
+/// the rainbow move along the strip.
+pub fn rainbow(pixels: &mut [Rgb], first_hue: u16) {
+    let len = pixels.len().max(1) as u32;
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let offset = index as u32 * u32::from(HUE_STEPS) / len;
+        *pixel = Rgb::hsv(first_hue.wrapping_add(offset as u16) % HUE_STEPS, 255, 255);
+    }
+}
+
+/// Theatre lights: every third pixel on, moving one pixel per step
+pub fn chase(pixels: &mut [Rgb], color: Rgb, step: usize) {
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = if index % 3 == step % 3 { color } else { Rgb::BLACK };
+    }
+}
+
+/// A bright head at `head` with a tail of `tail` pixels behind it (towards the start) fading out
+pub fn comet(pixels: &mut [Rgb], head: usize, tail: usize, color: Rgb) {
+    pixels.fill(Rgb::BLACK);
+    for distance in 0..=tail {
+        let Some(index) = head.checked_sub(distance) else {
+            break;
+        };
+        if let Some(pixel) = pixels.get_mut(index) {
+            // Linear fade: the head at full brightness, the pixel past the tail would be black
+            let brightness = 255 - (distance * 255 / (tail + 1)) as u8;
+            *pixel = color.scale(brightness);
+        }
+    }
+}
+
+/// A position that goes from 0 to `len - 1` and back again as `step` counts up
+pub fn bounce(step: usize, len: usize) -> usize {
+    if len < 2 {
+        return 0;
+    }
+    let period = 2 * (len - 1);
+    let phase = step % period;
+    if phase < len {
+        phase
+    } else {
+        period - phase
+    }
+}
+
+/// The whole strip fading up and down, one breath every 512 steps
+pub fn breathe(pixels: &mut [Rgb], color: Rgb, step: usize) {
+    let phase = (step % 512) as u16;
+    let level = if phase < 256 { phase } else { 511 - phase } as u8;
+    pixels.fill(color.scale(level));
+}
@@ -0,0 +1,135 @@
+//! Colours: RGB values, hue/saturation/value, brightness and gamma correction.
+//!
+//! A WS2812 takes 8 bits per colour, and the light it gives is proportional to the value: 128 is half the
+//! light of 255. Eyes do not work like that, 128 looks nearly as bright as 255 and the steps near black look
+//! huge. Gamma correction maps the values so that equal steps look equal. [`Correction`] applies it together
+//! with the strip's overall brightness, just before the colours are sent.
+
+/// A colour, 0 to 255 for each of red, green and blue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Hue steps around the colour wheel, see [`Rgb::hsv`]
+pub const HUE_STEPS: u16 = 6 * 256;
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+    pub const RED: Rgb = Rgb::new(255, 0, 0);
+    pub const GREEN: Rgb = Rgb::new(0, 255, 0);
+    pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+    pub const YELLOW: Rgb = Rgb::new(255, 255, 0);
+    pub const CYAN: Rgb = Rgb::new(0, 255, 255);
+    pub const MAGENTA: Rgb = Rgb::new(255, 0, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// A colour from hue, saturation and value.
+    ///
+    /// The hue goes round the colour wheel in [`HUE_STEPS`] steps, and wraps: red at 0, yellow at 256, green at
+    /// 512, cyan at 768, blue at 1024, magenta at 1280. Saturation 0 is white, 255 the pure colour; value 0 is
+    /// black, 255 full brightness.
+    pub fn hsv(hue: u16, saturation: u8, value: u8) -> Self {
+        let hue = hue % HUE_STEPS;
+        let rising = (hue % 256) as u8;
+        let falling = 255 - rising;
+        let (r, g, b) = match hue / 256 {
+            0 => (255, rising, 0),
+            1 => (falling, 255, 0),
+            2 => (0, 255, rising),
+            3 => (0, falling, 255),
+            4 => (rising, 0, 255),
+            _ => (255, 0, falling),
+        };
+        // Less saturation mixes in white, less value scales towards black
+        let channel = |c: u8| scale8(255 - scale8(255 - c, saturation), value);
+        Self::new(channel(r), channel(g), channel(b))
+    }
+
+    /// The colour at `brightness` (255 unchanged, 0 black)
+    pub fn scale(self, brightness: u8) -> Self {
+        Self::new(
+            scale8(self.r, brightness),
+            scale8(self.g, brightness),
+            scale8(self.b, brightness),
+        )
+    }
+
+    /// Between `self` (amount 0) and `other` (amount 255)
+    pub fn blend(self, other: Rgb, amount: u8) -> Self {
+        let mix = |a: u8, b: u8| scale8(a, 255 - amount) + scale8(b, amount);
+        Self::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
+
+    /// Gamma corrected, see the module documentation
+    pub fn gamma(self) -> Self {
+        Self::new(
+            GAMMA[usize::from(self.r)],
+            GAMMA[usize::from(self.g)],
+            GAMMA[usize::from(self.b)],
+        )
+    }
+}
+
+/// `value` × (`scale` + 1) / 256: `scale` 255 leaves `value` as it is, 0 gives 0
+pub fn scale8(value: u8, scale: u8) -> u8 {
+    ((u16::from(value) * (u16::from(scale) + 1)) >> 8) as u8
+}
+
+/// What happens to colours on their way to the strip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Correction {
+    /// Overall brightness, 255 is full. Applied before gamma, so that halving it looks about half as bright.
+    pub brightness: u8,
+    /// Gamma correct the colours
+    pub gamma: bool,
+}
+
+impl Correction {
+    /// The colour as it is sent to the strip
+    pub fn apply(&self, color: Rgb) -> Rgb {
+        let color = color.scale(self.brightness);
+        if self.gamma {
+            color.gamma()
+        } else {
+            color
+        }
+    }
+}
+
+impl Default for Correction {
+    /// Full brightness, gamma corrected
+    fn default() -> Self {
+        Self {
+            brightness: 255,
+            gamma: true,
+        }
+    }
+}
+
+/// 255 × (i / 255)^2.8, rounded: the usual gamma for LEDs
+#[rustfmt::skip]
+pub const GAMMA: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2,
+    2, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 5, 5, 5,
+    5, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10,
+    10, 10, 11, 11, 11, 12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16,
+    17, 17, 18, 18, 19, 19, 20, 20, 21, 21, 22, 22, 23, 24, 24, 25,
+    25, 26, 27, 27, 28, 29, 29, 30, 31, 32, 32, 33, 34, 35, 35, 36,
+    37, 38, 39, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 50,
+    51, 52, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 66, 67, 68,
+    69, 70, 72, 73, 74, 75, 77, 78, 79, 81, 82, 83, 85, 86, 87, 89,
+    90, 92, 93, 95, 96, 98, 99, 101, 102, 104, 105, 107, 109, 110, 112, 114,
+    115, 117, 119, 120, 122, 124, 126, 127, 129, 131, 133, 135, 137, 138, 140, 142,
+    144, 146, 148, 150, 152, 154, 156, 158, 160, 162, 164, 167, 169, 171, 173, 175,
+    177, 180, 182, 184, 186, 189, 191, 193, 196, 198, 200, 203, 205, 208, 210, 213,
+    215, 218, 220, 223, 225, 228, 231, 233, 236, 239, 241, 244, 247, 249, 252, 255,
+];
@@ -0,0 +1,100 @@
+//! The WS2812 bit encoding, as a PWM sequence.
+//!
+//! A WS2812 has a single data line. Each bit is a high pulse followed by a low, 1.25 µs in all, and the length
+//! of the high pulse is the bit: short is 0, long is 1. A pixel takes 24 bits, green, red then blue, most
+//! significant bit first; it keeps the first 24 bits it receives and passes the rest on down the strip. Holding
+//! the line low for a while ends the frame, and every pixel shows its new colour.
+//!
+//! ```text
+//!          ┌──────┐                    ┌─────────────┐
+//! 0 bit    │375 ns│       875 ns       │   812 ns    │    438 ns     1 bit
+//!       ───┘      └────────────────────┘             └───────────
+//!          ├─────────── 1.25 µs ───────┤
+//! ```
+//!
+//! The PWM makes these pulses: at 16 MHz with COUNTERTOP 20 each PWM period is one bit, and each value in the
+//! sequence is a compare value, the number of 62.5 ns clocks the output stays high. EasyDMA feeds the values to
+//! the PWM from RAM, so the timing is exact whatever the CPU does meanwhile. The cost is RAM: two bytes per bit,
+//! 48 per pixel.
+
+use core::fmt;
+
+use crate::color::{Correction, Rgb};
+
+/// The PWM clock, no prescaler
+pub const PWM_CLOCK_HZ: u32 = 16_000_000;
+/// Clocks per bit: 1.25 µs
+pub const COUNTERTOP: u16 = 20;
+/// Clocks high for a 0 bit: 375 ns (the datasheet wants 400 ± 150 ns)
+pub const T0H: u16 = 6;
+/// Clocks high for a 1 bit: 812.5 ns (the datasheet wants 800 ± 150 ns)
+pub const T1H: u16 = 13;
+
+/// Sequence value bit 15: the period starts high, and goes low at the compare value
+const POLARITY_FALLING_EDGE: u16 = 1 << 15;
+
+/// Sequence value for a 0 bit
+pub const ZERO: u16 = T0H | POLARITY_FALLING_EDGE;
+/// Sequence value for a 1 bit
+pub const ONE: u16 = T1H | POLARITY_FALLING_EDGE;
+/// Sequence value that keeps the line low for a whole period
+pub const LOW: u16 = POLARITY_FALLING_EDGE;
+
+/// Sequence values per pixel, one per bit
+pub const WORDS_PER_PIXEL: usize = 24;
+/// Low periods after the last pixel: 300 µs. Older WS2812s latch after 50 µs, newer ones need 280 µs.
+///
+/// They also leave the line low afterwards: the PWM repeats the last value of a sequence until the next one.
+pub const RESET_WORDS: usize = 240;
+
+/// The sequence values for `pixels` pixels
+pub const fn buffer_len(pixels: usize) -> usize {
+    pixels * WORDS_PER_PIXEL + RESET_WORDS
+}
+
+/// The buffer cannot hold this many pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall;
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("too many pixels for the buffer")
+    }
+}
+
+/// The sequence values for one pixel: green, red, blue, most significant bit first
+pub fn encode_pixel(color: Rgb) -> [u16; WORDS_PER_PIXEL] {
+    let bits = u32::from(color.g) << 16 | u32::from(color.r) << 8 | u32::from(color.b);
+    core::array::from_fn(|index| if bits & 1 << (23 - index) != 0 { ONE } else { ZERO })
+}
+
+/// Fill `buffer` with the sequence for `pixels`, corrected by `correction`, and the reset after them.
+///
+/// Returns how many values of `buffer` make up the sequence.
+pub fn encode(pixels: &[Rgb], correction: Correction, buffer: &mut [u16]) -> Result<usize, BufferTooSmall> {
+    let len = buffer_len(pixels.len());
+    let buffer = buffer.get_mut(..len).ok_or(BufferTooSmall)?;
+    let (data, reset) = buffer.split_at_mut(pixels.len() * WORDS_PER_PIXEL);
+    for (words, &pixel) in data.chunks_exact_mut(WORDS_PER_PIXEL).zip(pixels) {
+        words.copy_from_slice(&encode_pixel(correction.apply(pixel)));
+    }
+    reset.fill(LOW);
+    Ok(len)
+}
+
+/// Turn a sequence back into colours, the way a strip reads it. For tests, and for checking a buffer in a
+/// debugger.
+///
+/// Stops at the first value that is neither [`ZERO`] nor [`ONE`], so it returns the pixels before the reset.
+pub fn decode(sequence: &[u16]) -> impl Iterator<Item = Rgb> + '_ {
+    sequence
+        .chunks_exact(WORDS_PER_PIXEL)
+        .map_while(|words| {
+            words.iter().try_fold(0u32, |bits, &word| match word {
+                ZERO => Some(bits << 1),
+                ONE => Some(bits << 1 | 1),
+                _ => None,
+            })
+        })
+        .map(|bits| Rgb::new((bits >> 8) as u8, (bits >> 16) as u8, bits as u8))
+}
@@ -0,0 +1,17 @@
+#![no_std]
+
+//! WS2812 ("NeoPixel") RGB LED strips on an edge connector pin, driven by the PWM with EasyDMA.
+//!
+//! - [`color`] has RGB colours, hue/saturation/value, brightness scaling and gamma correction
+//! - [`encode`] turns colours into the PWM sequence that makes the WS2812 bit timing
+//! - [`animation`] draws frames of a few animations: rainbow, chase, comet, breathing
+//! - [`strip`] plays the sequence on a pin with a PWM instance (target only)
+//!
+//! `color`, `encode` and `animation` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod animation;
+pub mod color;
+pub mod encode;
+
+#[cfg(target_os = "none")]
+pub mod strip;
@@ -0,0 +1,112 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::{delay::DelayNs, digital::InputPin},
+    example_23_neopixel::{
+        animation,
+        color::{Rgb, HUE_STEPS},
+        encode,
+        strip::Strip,
+    },
+    microbit::hal::{gpio::Level, Timer},
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// The number of LEDs on the strip
+#[cfg(target_os = "none")]
+const PIXELS: usize = 8;
+/// The PWM sequence for the whole strip
+#[cfg(target_os = "none")]
+const BUFFER_LEN: usize = encode::buffer_len(PIXELS);
+/// Time between frames
+#[cfg(target_os = "none")]
+const FRAME_MS: u32 = 20;
+/// Button B steps through these. Low by default: a pixel at full white draws 60 mA.
+#[cfg(target_os = "none")]
+const BRIGHTNESS: [u8; 5] = [16, 32, 64, 128, 255];
+
+#[cfg(target_os = "none")]
+#[derive(Debug, Clone, Copy)]
+enum Animation {
+    Rainbow,
+    Comet,
+    Chase,
+    Breathe,
+}
+
+#[cfg(target_os = "none")]
+impl Animation {
+    fn next(self) -> Self {
+        match self {
+            Self::Rainbow => Self::Comet,
+            Self::Comet => Self::Chase,
+            Self::Chase => Self::Breathe,
+            Self::Breathe => Self::Rainbow,
+        }
+    }
+
+    fn draw(self, pixels: &mut [Rgb], step: usize) {
+        match self {
+            Self::Rainbow => animation::rainbow(pixels, (step * 8 % usize::from(HUE_STEPS)) as u16),
+            Self::Comet => {
+                let head = animation::bounce(step / 3, pixels.len());
+                animation::comet(pixels, head, 3, Rgb::CYAN)
+            }
+            Self::Chase => animation::chase(pixels, Rgb::YELLOW, step / 8),
+            Self::Breathe => animation::breathe(pixels, Rgb::MAGENTA, step * 2),
+        }
+    }
+}
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+    let mut timer0 = Timer::new(board.TIMER0);
+    let mut button_a = board.buttons.button_a;
+    let mut button_b = board.buttons.button_b;
+
+    // The strip's data input on P0 (big ring 0)
+    let pin = board.edge.e00.into_push_pull_output(Level::Low).degrade();
+    let buffer = cortex_m::singleton!(: [u16; BUFFER_LEN] = [0; BUFFER_LEN]).unwrap();
+    let mut strip = Strip::new(board.PWM0, pin, buffer);
+
+    let mut brightness = 0;
+    strip.set_brightness(BRIGHTNESS[brightness]);
+    let mut animation = Animation::Rainbow;
+    rprintln!("{} pixels on P0. A: next animation, B: brightness", PIXELS);
+    rprintln!("{:?}, brightness {}", animation, strip.brightness());
+
+    let mut pixels = [Rgb::BLACK; PIXELS];
+    let mut step = 0usize;
+    let (mut a_was_pressed, mut b_was_pressed) = (false, false);
+    loop {
+        animation.draw(&mut pixels, step);
+        strip.write(&pixels).unwrap();
+        step = step.wrapping_add(1);
+
+        let a_pressed = button_a.is_low().unwrap();
+        let b_pressed = button_b.is_low().unwrap();
+        if a_pressed && !a_was_pressed {
+            animation = animation.next();
+            rprintln!("{:?}", animation);
+        }
+        if b_pressed && !b_was_pressed {
+            brightness = (brightness + 1) % BRIGHTNESS.len();
+            strip.set_brightness(BRIGHTNESS[brightness]);
+            rprintln!("Brightness {}", strip.brightness());
+        }
+        (a_was_pressed, b_was_pressed) = (a_pressed, b_pressed);
+
+        timer0.delay_ms(FRAME_MS);
+    }
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
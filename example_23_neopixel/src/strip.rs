@@ -0,0 +1,92 @@
+//! A WS2812 strip on one pin, driven by a PWM instance.
+//!
+//! The PWM plays the sequence from [`encode`](crate::encode) once, with one value per period ("common" decoder
+//! mode, all four compare registers get the same value), and [`Strip::write`] waits until it is done. That takes
+//! 30 µs per pixel plus the 300 µs reset, and the CPU is free to take interrupts meanwhile.
+
+use core::{
+    ops::Deref,
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+use microbit::hal::{
+    gpio::{Output, Pin, PushPull},
+    pac::pwm0::RegisterBlock,
+};
+
+use crate::{
+    color::{Correction, Rgb},
+    encode::{self, BufferTooSmall, COUNTERTOP, RESET_WORDS, WORDS_PER_PIXEL},
+};
+
+/// A WS2812 strip
+pub struct Strip<T> {
+    pwm: T,
+    buffer: &'static mut [u16],
+    correction: Correction,
+    _pin: Pin<Output<PushPull>>,
+}
+
+impl<T: Deref<Target = RegisterBlock>> Strip<T> {
+    /// Take over a PWM instance (PWM0 to PWM3) to drive a strip on `pin`. `buffer` holds the sequence: it must be
+    /// in RAM and live forever, e.g. from `cortex_m::singleton!`, with [`encode::buffer_len`] values for the
+    /// longest frame.
+    pub fn new(pwm: T, pin: Pin<Output<PushPull>>, buffer: &'static mut [u16]) -> Self {
+        pwm.psel.out[0].write(|w| unsafe { w.bits(pin.psel_bits()) });
+        pwm.mode.write(|w| w.updown().up());
+        // 16 MHz, 20 clocks: one 1.25 µs period per bit
+        pwm.prescaler.write(|w| w.prescaler().div_1());
+        pwm.countertop.write(|w| unsafe { w.countertop().bits(COUNTERTOP) });
+        // Each value goes to every channel, and the sequence is played once
+        pwm.decoder.write(|w| w.load().common().mode().refresh_count());
+        pwm.loop_.write(|w| unsafe { w.bits(0) });
+        pwm.seq0.refresh.write(|w| unsafe { w.bits(0) });
+        pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+        pwm.enable.write(|w| w.enable().enabled());
+
+        Self {
+            pwm,
+            buffer,
+            correction: Correction::default(),
+            _pin: pin,
+        }
+    }
+
+    /// The longest strip the buffer can drive
+    pub fn max_pixels(&self) -> usize {
+        self.buffer.len().saturating_sub(RESET_WORDS) / WORDS_PER_PIXEL
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.correction.brightness
+    }
+
+    /// Overall brightness for the next [`Strip::write`], 255 is full
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.correction.brightness = brightness;
+    }
+
+    /// Gamma correct the colours from the next [`Strip::write`] on (the default)
+    pub fn set_gamma(&mut self, gamma: bool) {
+        self.correction.gamma = gamma;
+    }
+
+    /// Show `pixels`, the first one nearest the micro:bit. Returns once the strip has them.
+    pub fn write(&mut self, pixels: &[Rgb]) -> Result<(), BufferTooSmall> {
+        let len = encode::encode(pixels, self.correction, self.buffer)?;
+
+        // The sequence must be in RAM before the PWM reads it
+        compiler_fence(Ordering::SeqCst);
+        self.pwm
+            .seq0
+            .ptr
+            .write(|w| unsafe { w.bits(self.buffer.as_ptr() as u32) });
+        self.pwm.seq0.cnt.write(|w| unsafe { w.bits(len as u32) });
+        self.pwm.events_seqend[0].write(|w| unsafe { w.bits(0) });
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_seqend[0].read().bits() == 0 {}
+        // And the PWM is done with the buffer before it is written again
+        compiler_fence(Ordering::SeqCst);
+        Ok(())
+    }
+}
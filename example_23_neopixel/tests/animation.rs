@@ -0,0 +1,77 @@
+//! Host tests for the animations
+
+use example_23_neopixel::{
+    animation::{bounce, breathe, chase, comet, fill, rainbow},
+    color::{Rgb, HUE_STEPS},
+};
+
+#[test]
+fn fill_and_breathe() {
+    let mut pixels = [Rgb::BLACK; 5];
+    fill(&mut pixels, Rgb::RED);
+    assert_eq!(pixels, [Rgb::RED; 5]);
+
+    breathe(&mut pixels, Rgb::WHITE, 0);
+    assert_eq!(pixels, [Rgb::BLACK; 5]);
+    breathe(&mut pixels, Rgb::WHITE, 255);
+    assert_eq!(pixels, [Rgb::WHITE; 5]);
+    breathe(&mut pixels, Rgb::WHITE, 256);
+    assert_eq!(pixels, [Rgb::WHITE; 5]);
+    breathe(&mut pixels, Rgb::WHITE, 511);
+    assert_eq!(pixels, [Rgb::BLACK; 5]);
+    breathe(&mut pixels, Rgb::WHITE, 512 + 255);
+    assert_eq!(pixels, [Rgb::WHITE; 5]);
+}
+
+#[test]
+fn rainbow_spreads_the_wheel() {
+    let mut pixels = [Rgb::BLACK; 6];
+    rainbow(&mut pixels, 0);
+    assert_eq!(
+        pixels,
+        [Rgb::RED, Rgb::YELLOW, Rgb::GREEN, Rgb::CYAN, Rgb::BLUE, Rgb::MAGENTA]
+    );
+    // Moves along with the first hue, and wraps
+    rainbow(&mut pixels, HUE_STEPS - 256);
+    assert_eq!(pixels[0], Rgb::MAGENTA);
+    assert_eq!(pixels[1], Rgb::RED);
+    // No pixels, nothing to do
+    rainbow(&mut [], 0);
+}
+
+#[test]
+fn chase_moves_one_pixel_per_step() {
+    let mut pixels = [Rgb::BLACK; 7];
+    let lit = |pixels: &[Rgb]| -> Vec<usize> { (0..pixels.len()).filter(|&i| pixels[i] != Rgb::BLACK).collect() };
+    chase(&mut pixels, Rgb::BLUE, 0);
+    assert_eq!(lit(&pixels), [0, 3, 6]);
+    chase(&mut pixels, Rgb::BLUE, 1);
+    assert_eq!(lit(&pixels), [1, 4]);
+    chase(&mut pixels, Rgb::BLUE, 5);
+    assert_eq!(lit(&pixels), [2, 5]);
+}
+
+#[test]
+fn comet_tail_fades() {
+    let mut pixels = [Rgb::WHITE; 8];
+    comet(&mut pixels, 5, 3, Rgb::WHITE);
+    assert_eq!(pixels[5], Rgb::WHITE);
+    assert!(pixels[4].r > pixels[3].r && pixels[3].r > pixels[2].r && pixels[2].r > 0);
+    for index in [0, 1, 6, 7] {
+        assert_eq!(pixels[index], Rgb::BLACK, "pixel {}", index);
+    }
+    // Near the start the tail is cut off, past the end only the tail shows
+    comet(&mut pixels, 1, 3, Rgb::WHITE);
+    assert_eq!(pixels[1], Rgb::WHITE);
+    comet(&mut pixels, 9, 3, Rgb::WHITE);
+    assert_eq!(pixels[..6], [Rgb::BLACK; 6]);
+    assert!(pixels[7] != Rgb::BLACK && pixels[7] != Rgb::WHITE);
+}
+
+#[test]
+fn bounce_goes_back_and_forth() {
+    let positions: Vec<usize> = (0..10).map(|step| bounce(step, 4)).collect();
+    assert_eq!(positions, [0, 1, 2, 3, 2, 1, 0, 1, 2, 3]);
+    assert_eq!(bounce(5, 1), 0);
+    assert_eq!(bounce(5, 0), 0);
+}
@@ -0,0 +1,88 @@
+//! Host tests for colours, brightness and gamma
+
+use example_23_neopixel::color::{scale8, Correction, Rgb, GAMMA, HUE_STEPS};
+
+#[test]
+fn hue_wheel() {
+    assert_eq!(Rgb::hsv(0, 255, 255), Rgb::RED);
+    assert_eq!(Rgb::hsv(256, 255, 255), Rgb::YELLOW);
+    assert_eq!(Rgb::hsv(512, 255, 255), Rgb::GREEN);
+    assert_eq!(Rgb::hsv(768, 255, 255), Rgb::CYAN);
+    assert_eq!(Rgb::hsv(1024, 255, 255), Rgb::BLUE);
+    assert_eq!(Rgb::hsv(1280, 255, 255), Rgb::MAGENTA);
+    // Wraps around
+    assert_eq!(Rgb::hsv(HUE_STEPS, 255, 255), Rgb::RED);
+    assert_eq!(Rgb::hsv(HUE_STEPS + 512, 255, 255), Rgb::GREEN);
+    // Halfway between red and yellow
+    assert_eq!(Rgb::hsv(128, 255, 255), Rgb::new(255, 128, 0));
+}
+
+#[test]
+fn hue_wheel_is_smooth() {
+    // Neighbouring hues differ by at most one step in one channel
+    for hue in 0..HUE_STEPS {
+        let a = Rgb::hsv(hue, 255, 255);
+        let b = Rgb::hsv(hue + 1, 255, 255);
+        let diff = a.r.abs_diff(b.r) + a.g.abs_diff(b.g) + a.b.abs_diff(b.b);
+        assert!(diff <= 1, "hue {}: {:?} then {:?}", hue, a, b);
+    }
+}
+
+#[test]
+fn saturation_and_value() {
+    assert_eq!(Rgb::hsv(300, 0, 255), Rgb::WHITE);
+    assert_eq!(Rgb::hsv(300, 255, 0), Rgb::BLACK);
+    assert_eq!(Rgb::hsv(0, 127, 255), Rgb::new(255, 128, 128));
+    assert_eq!(Rgb::hsv(512, 255, 127), Rgb::new(0, 127, 0));
+}
+
+#[test]
+fn scaling() {
+    assert_eq!(scale8(255, 255), 255);
+    assert_eq!(scale8(200, 255), 200);
+    assert_eq!(scale8(255, 0), 0);
+    assert_eq!(scale8(255, 127), 127);
+    assert_eq!(Rgb::new(200, 100, 2).scale(127), Rgb::new(100, 50, 1));
+    assert_eq!(Rgb::WHITE.scale(0), Rgb::BLACK);
+}
+
+#[test]
+fn blending() {
+    assert_eq!(Rgb::RED.blend(Rgb::BLUE, 0), Rgb::RED);
+    assert_eq!(Rgb::RED.blend(Rgb::BLUE, 255), Rgb::BLUE);
+    assert_eq!(Rgb::RED.blend(Rgb::BLUE, 127), Rgb::new(128, 0, 127));
+    // Never overflows
+    for amount in 0..=255 {
+        assert_eq!(Rgb::WHITE.blend(Rgb::WHITE, amount), Rgb::WHITE);
+    }
+}
+
+#[test]
+fn gamma_table() {
+    for (index, &value) in GAMMA.iter().enumerate() {
+        let expected = (255.0 * (index as f64 / 255.0).powf(2.8)).round() as u8;
+        assert_eq!(value, expected, "GAMMA[{}]", index);
+    }
+    assert!(GAMMA.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(Rgb::new(0, 128, 255).gamma(), Rgb::new(0, 37, 255));
+}
+
+#[test]
+fn correction() {
+    let color = Rgb::new(255, 128, 10);
+    assert_eq!(
+        Correction {
+            brightness: 255,
+            gamma: false
+        }
+        .apply(color),
+        color
+    );
+    // Brightness first, then gamma
+    let dim = Correction {
+        brightness: 127,
+        gamma: true,
+    };
+    assert_eq!(dim.apply(color), color.scale(127).gamma());
+    assert_eq!(Correction::default().apply(color), color.gamma());
+}
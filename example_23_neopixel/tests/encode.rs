@@ -0,0 +1,96 @@
+//! Host tests for the WS2812 bit encoding
+
+use example_23_neopixel::{
+    color::{Correction, Rgb},
+    encode::{
+        buffer_len, decode, encode, encode_pixel, BufferTooSmall, COUNTERTOP, LOW, ONE, PWM_CLOCK_HZ, RESET_WORDS, T0H,
+        T1H, WORDS_PER_PIXEL, ZERO,
+    },
+};
+
+/// 62.5 ns
+const PS_PER_CLOCK: u32 = 1_000_000_000 / (PWM_CLOCK_HZ / 1000);
+
+/// No brightness scaling, no gamma: the colours go out as they are
+const RAW: Correction = Correction {
+    brightness: 255,
+    gamma: false,
+};
+
+#[test]
+fn timing_is_within_the_datasheet() {
+    // WS2812B: T0H 400 ns, T1H 800 ns, each ± 150 ns; a bit 1.25 µs ± 600 ns
+    let ns = |clocks: u16| u32::from(clocks) * PS_PER_CLOCK / 1000;
+    assert!((250..=550).contains(&ns(T0H)), "T0H {} ns", ns(T0H));
+    assert!((650..=950).contains(&ns(T1H)), "T1H {} ns", ns(T1H));
+    assert_eq!(ns(COUNTERTOP), 1250);
+    // The lows: T0L 800 ns and T1L 450 ns, ± 150 ns
+    assert!((650..=950).contains(&ns(COUNTERTOP - T0H)));
+    assert!((300..=600).contains(&ns(COUNTERTOP - T1H)));
+    // Newer WS2812Bs only latch after 280 µs low
+    assert!(RESET_WORDS as u32 * ns(COUNTERTOP) >= 280_000);
+}
+
+#[test]
+fn sequence_values() {
+    // Polarity bit set: high from the start of the period until the compare value
+    assert_eq!(ZERO, 0x8000 | 6);
+    assert_eq!(ONE, 0x8000 | 13);
+    assert_eq!(LOW, 0x8000);
+}
+
+#[test]
+fn pixels_go_out_green_red_blue_msb_first() {
+    let words = encode_pixel(Rgb::new(0x81, 0x0F, 0x00));
+    let bits: String = words
+        .iter()
+        .map(|&word| match word {
+            ZERO => '0',
+            ONE => '1',
+            _ => panic!("unexpected value {:#x}", word),
+        })
+        .collect();
+    assert_eq!(bits, "00001111_10000001_00000000".replace('_', ""));
+
+    assert_eq!(encode_pixel(Rgb::BLACK), [ZERO; WORDS_PER_PIXEL]);
+    assert_eq!(encode_pixel(Rgb::WHITE), [ONE; WORDS_PER_PIXEL]);
+    assert_eq!(encode_pixel(Rgb::GREEN)[..8], [ONE; 8]);
+    assert_eq!(encode_pixel(Rgb::GREEN)[8..], [ZERO; 16]);
+}
+
+#[test]
+fn frame_ends_with_the_reset() {
+    let pixels = [Rgb::RED, Rgb::GREEN, Rgb::BLUE];
+    let mut buffer = [0xAAAA; buffer_len(4)];
+    let len = encode(&pixels, RAW, &mut buffer).unwrap();
+
+    assert_eq!(len, 3 * 24 + RESET_WORDS);
+    assert_eq!(buffer[..24], encode_pixel(Rgb::RED));
+    assert_eq!(buffer[48..72], encode_pixel(Rgb::BLUE));
+    assert!(buffer[72..len].iter().all(|&word| word == LOW));
+    // The rest of the buffer is left alone
+    assert!(buffer[len..].iter().all(|&word| word == 0xAAAA));
+    assert_eq!(decode(&buffer[..len]).collect::<Vec<_>>(), pixels);
+}
+
+#[test]
+fn buffer_too_small() {
+    let mut buffer = [0; buffer_len(2)];
+    assert_eq!(encode(&[Rgb::WHITE; 3], RAW, &mut buffer), Err(BufferTooSmall));
+    assert_eq!(encode(&[Rgb::WHITE; 2], RAW, &mut buffer), Ok(buffer_len(2)));
+    assert_eq!(encode(&[], RAW, &mut buffer), Ok(RESET_WORDS));
+}
+
+#[test]
+fn correction_is_applied() {
+    let mut buffer = [0; buffer_len(1)];
+    let half = Correction {
+        brightness: 127,
+        gamma: false,
+    };
+    encode(&[Rgb::new(255, 100, 0)], half, &mut buffer).unwrap();
+    assert_eq!(decode(&buffer).next(), Some(Rgb::new(127, 50, 0)));
+
+    encode(&[Rgb::new(255, 128, 0)], Correction::default(), &mut buffer).unwrap();
+    assert_eq!(decode(&buffer).next(), Some(Rgb::new(255, 37, 0)));
+}
@@ -0,0 +1,8 @@
+/* nRF52833 memory layout: the board support crate usually provides this, Embassy does not */
+MEMORY
+{
+  /* NOTE 1 K = 1 KiBi = 1024 bytes */
+  /* These values correspond to the nRF52833 with 512K flash and 128K RAM */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 512K
+  RAM : ORIGIN = 0x20000000, LENGTH = 128K
+}
\ No newline at end of file
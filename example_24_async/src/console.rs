@@ -0,0 +1,115 @@
+//! Commands typed into the RTT terminal.
+//!
+//! The probe-rs terminal sends a whole line at a time once Enter is pressed, and shows what was typed itself,
+//! so unlike a serial console there is nothing to echo. [`LineBuffer`] collects the bytes into lines and
+//! [`Command::parse`] turns a line into a command.
+
+use core::fmt;
+
+/// Longest command line
+pub const MAX_LINE: usize = 32;
+
+/// Accelerometer readings are this far apart at least...
+pub const MIN_RATE_MS: u32 = 10;
+/// ...and at most
+pub const MAX_RATE_MS: u32 = 10_000;
+
+pub const HELP: &str = "Commands:
+  help          this text
+  status        uptime, button presses and the last accelerometer reading
+  rate <ms>     read the accelerometer every <ms> milliseconds (10 to 10000)";
+
+/// Collects bytes into lines
+#[derive(Debug)]
+pub struct LineBuffer {
+    line: [u8; MAX_LINE],
+    len: usize,
+}
+
+impl Default for LineBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        Self {
+            line: [0; MAX_LINE],
+            len: 0,
+        }
+    }
+
+    /// Add one byte. Returns the line at the end of one (CR or LF; CR LF gives an extra empty line). Characters
+    /// beyond [`MAX_LINE`] are dropped, and so is anything that is not printable ASCII.
+    pub fn push(&mut self, byte: u8) -> Option<&str> {
+        match byte {
+            b'\r' | b'\n' => {
+                let len = core::mem::take(&mut self.len);
+                // Only printable ASCII is ever stored, so this cannot fail
+                core::str::from_utf8(&self.line[..len]).ok()
+            }
+            b' '..=b'~' if self.len < MAX_LINE => {
+                self.line[self.len] = byte;
+                self.len += 1;
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A console command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Status,
+    /// Accelerometer readings this many milliseconds apart
+    Rate(u32),
+    /// An empty line: nothing to do
+    Empty,
+}
+
+/// Why a line is not a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError<'a> {
+    Unknown(&'a str),
+    /// Known command, bad or missing argument
+    Usage(&'static str),
+}
+
+impl fmt::Display for CommandError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown command '{}', try 'help'", name),
+            Self::Usage(usage) => write!(f, "usage: {}", usage),
+        }
+    }
+}
+
+impl Command {
+    /// Parse a line. Surrounding whitespace is ignored, and so is the case of command names.
+    pub fn parse(line: &str) -> Result<Self, CommandError<'_>> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(' ') {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+
+        let command = if name.is_empty() {
+            Self::Empty
+        } else if name.eq_ignore_ascii_case("help") {
+            Self::Help
+        } else if name.eq_ignore_ascii_case("status") {
+            Self::Status
+        } else if name.eq_ignore_ascii_case("rate") {
+            match argument.parse() {
+                Ok(ms) if (MIN_RATE_MS..=MAX_RATE_MS).contains(&ms) => Self::Rate(ms),
+                _ => return Err(CommandError::Usage("rate <10-10000 ms>")),
+            }
+        } else {
+            return Err(CommandError::Unknown(name));
+        };
+        Ok(command)
+    }
+}
@@ -0,0 +1,34 @@
+//! What the button and accelerometer tasks tell the console task
+
+use core::fmt;
+
+use crate::tilt::Tilt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    A,
+    B,
+}
+
+impl Button {
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A button was pressed, for the `count`th time since reset
+    Pressed { button: Button, count: u32 },
+    /// The board was turned a new way up
+    Tilted(Tilt),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pressed { button, count } => write!(f, "Button {:?} pressed ({} so far)", button, count),
+            Self::Tilted(tilt) => write!(f, "Tilted: {}", tilt),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+#![no_std]
+
+//! The buttons, a timer, RTT and the accelerometer as async tasks on the Embassy executor.
+//!
+//! - [`tilt`] works out which way up the board is from accelerometer readings, with hysteresis
+//! - [`event`] is what the button and accelerometer tasks send to the console task
+//! - [`console`] collects RTT input into lines and parses them into commands
+//! - [`tasks`] has the tasks: heartbeat LED, buttons, accelerometer and console (target only)
+//!
+//! `tilt`, `event` and `console` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod console;
+pub mod event;
+pub mod tilt;
+
+#[cfg(target_os = "none")]
+pub mod tasks;
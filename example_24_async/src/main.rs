@@ -0,0 +1,52 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    embassy_executor::Spawner,
+    embassy_nrf::{
+        bind_interrupts,
+        gpio::{Input, Level, Output, OutputDrive, Pull},
+        peripherals, twim,
+    },
+    example_24_async::{event::Button, tasks},
+    panic_rtt_target as _,
+    rtt_target::rtt_init,
+};
+
+// TWIM0 shares its interrupt with SPIM0, SPIS0 and TWIS0: the driver's handler wakes the accelerometer task
+#[cfg(target_os = "none")]
+bind_interrupts!(struct Irqs {
+    SPIM0_SPIS0_TWIM0_TWIS0_SPI0_TWI0 => twim::InterruptHandler<peripherals::TWISPI0>;
+});
+
+#[cfg(target_os = "none")]
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let channels = rtt_init! {
+        up:   { 0: { size: 1024, name: "Terminal" } }
+        down: { 0: { size:   64, name: "Terminal" } }
+    };
+    // Also starts RTC1, which runs the timers
+    let p = embassy_nrf::init(Default::default());
+
+    // The top-left LED: row 1 (P0.21) high, column 1 (P0.28) low
+    let row1 = Output::new(p.P0_21, Level::Low, OutputDrive::Standard);
+    let col1 = Output::new(p.P0_28, Level::Low, OutputDrive::Standard);
+    // The buttons have pull-ups on the board
+    let button_a = Input::new(p.P0_14, Pull::None);
+    let button_b = Input::new(p.P0_23, Pull::None);
+    // The internal I2C bus: SDA P0.16, SCL P0.08
+    let i2c = twim::Twim::new(p.TWISPI0, Irqs, p.P0_16, p.P0_08, twim::Config::default());
+
+    spawner.spawn(tasks::heartbeat(row1, col1)).unwrap();
+    spawner.spawn(tasks::button(button_a, Button::A)).unwrap();
+    spawner.spawn(tasks::button(button_b, Button::B)).unwrap();
+    spawner.spawn(tasks::accelerometer(i2c)).unwrap();
+    spawner.spawn(tasks::console(channels.up.0, channels.down.0)).unwrap();
+    // `main` is a task too, and can end: the others keep running
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,152 @@
+//! The tasks. Each one is an `async fn` that loops forever, and the executor runs whichever of them can make
+//! progress; when none can, the CPU sleeps until an interrupt (GPIOTE, the RTC1 timer, TWIM) wakes one up.
+//!
+//! The tasks share nothing mutable directly: events go to the console through a [`Channel`], and the console
+//! reads the rest from atomics and a critical-section mutex.
+
+use core::{
+    cell::Cell,
+    fmt::Write,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use embassy_futures::select::{select, Either};
+use embassy_nrf::{
+    gpio::{Input, Output},
+    peripherals::TWISPI0,
+    twim::Twim,
+};
+use embassy_sync::{
+    blocking_mutex::{raw::CriticalSectionRawMutex, Mutex},
+    channel::Channel,
+};
+use embassy_time::{Delay, Duration, Instant, Ticker, Timer};
+use lsm303agr::{AccelMode, AccelOutputDataRate, Lsm303agr};
+use rtt_target::{DownChannel, UpChannel};
+
+use crate::{
+    console::{Command, CommandError, LineBuffer, HELP},
+    event::{Button, Event},
+    tilt::TiltDetector,
+};
+
+/// Button contacts bounce for a few milliseconds after a press
+const DEBOUNCE_MS: u64 = 20;
+/// How often the console looks for input: RTT has no interrupt
+const CONSOLE_POLL_MS: u64 = 50;
+
+/// Events for the console, waiting to be printed
+static EVENTS: Channel<CriticalSectionRawMutex, Event, 8> = Channel::new();
+/// Presses of A and B since reset
+static PRESSES: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+/// Time between accelerometer readings, set with `rate` on the console
+static RATE_MS: AtomicU32 = AtomicU32::new(100);
+/// The last accelerometer reading, x, y and z in mg
+static LAST_READING: Mutex<CriticalSectionRawMutex, Cell<Option<(i32, i32, i32)>>> = Mutex::new(Cell::new(None));
+
+/// Blink the top-left LED: a sign that the executor is running, while every other task is waiting
+#[embassy_executor::task]
+pub async fn heartbeat(mut row: Output<'static>, _col: Output<'static>) {
+    loop {
+        row.set_high();
+        Timer::after_millis(50).await;
+        row.set_low();
+        Timer::after_millis(950).await;
+    }
+}
+
+/// Count presses of one button. Two instances run, one per button.
+#[embassy_executor::task(pool_size = 2)]
+pub async fn button(mut pin: Input<'static>, button: Button) {
+    loop {
+        // GPIOTE wakes the task on the edge: no polling, no interrupt handler to write
+        pin.wait_for_falling_edge().await;
+        let count = PRESSES[button.index()].fetch_add(1, Ordering::Relaxed) + 1;
+        EVENTS.send(Event::Pressed { button, count }).await;
+        // Ignore the bounces, then wait for the release
+        Timer::after_millis(DEBOUNCE_MS).await;
+        pin.wait_for_high().await;
+        Timer::after_millis(DEBOUNCE_MS).await;
+    }
+}
+
+/// Read the accelerometer every [`RATE_MS`], and report when the board is turned a new way up
+#[embassy_executor::task]
+pub async fn accelerometer(i2c: Twim<'static, TWISPI0>) {
+    let mut sensor = Lsm303agr::new_with_i2c(i2c);
+    sensor.init().await.unwrap();
+    sensor
+        .set_accel_mode_and_odr(&mut Delay, AccelMode::Normal, AccelOutputDataRate::Hz100)
+        .await
+        .unwrap();
+
+    let mut detector = TiltDetector::new();
+    loop {
+        Timer::after_millis(u64::from(RATE_MS.load(Ordering::Relaxed))).await;
+        // The I2C transfer runs with EasyDMA: other tasks run while it does
+        let (x, y, z) = sensor.acceleration().await.unwrap().xyz_mg();
+        LAST_READING.lock(|reading| reading.set(Some((x, y, z))));
+        if let Some(tilt) = detector.update(x, y, z) {
+            EVENTS.send(Event::Tilted(tilt)).await;
+        }
+    }
+}
+
+/// Print events as they come, and run commands typed into the RTT terminal
+#[embassy_executor::task]
+pub async fn console(mut up: UpChannel, mut down: DownChannel) {
+    let _ = writeln!(up, "Async tasks running. Press A or B, tilt the board, or type 'help'.");
+    let mut line = LineBuffer::new();
+    let mut input = [0; 16];
+    let mut poll = Ticker::every(Duration::from_millis(CONSOLE_POLL_MS));
+    loop {
+        // Whichever comes first: an event to print, or time to look for input
+        match select(EVENTS.receive(), poll.next()).await {
+            Either::First(event) => {
+                let _ = writeln!(up, "{}", event);
+            }
+            Either::Second(()) => {
+                let len = down.read(&mut input);
+                for &byte in &input[..len] {
+                    if let Some(text) = line.push(byte) {
+                        run(&mut up, Command::parse(text));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Run a command from the console
+fn run(up: &mut UpChannel, command: Result<Command, CommandError<'_>>) {
+    let _ = match command {
+        Ok(Command::Help) => writeln!(up, "{}", HELP),
+        Ok(Command::Status) => {
+            let reading = LAST_READING.lock(|reading| reading.get());
+            let _ = writeln!(
+                up,
+                "Up {} s, A pressed {} times, B {} times",
+                Instant::now().as_secs(),
+                PRESSES[Button::A.index()].load(Ordering::Relaxed),
+                PRESSES[Button::B.index()].load(Ordering::Relaxed)
+            );
+            match reading {
+                Some((x, y, z)) => writeln!(
+                    up,
+                    "Accelerometer every {} ms: x {} y {} z {} mg",
+                    RATE_MS.load(Ordering::Relaxed),
+                    x,
+                    y,
+                    z
+                ),
+                None => writeln!(up, "No accelerometer reading yet"),
+            }
+        }
+        Ok(Command::Rate(ms)) => {
+            RATE_MS.store(ms, Ordering::Relaxed);
+            writeln!(up, "Reading the accelerometer every {} ms", ms)
+        }
+        Ok(Command::Empty) => Ok(()),
+        Err(error) => writeln!(up, "{}", error),
+    };
+}
@@ -0,0 +1,99 @@
+//! Which way up the board is, from the accelerometer, with hysteresis so that it does not flicker between two
+//! directions when held in between.
+//!
+//! At rest the accelerometer measures gravity only: about 1000 mg along the axis that points up, and little
+//! along the others. The direction is the axis and sign with the largest reading. It only changes when another
+//! direction reads [`HYSTERESIS_MG`] more than the current one, and at least [`MIN_MG`]: shaking or a board
+//! held at 45° keeps the last direction.
+
+use core::fmt;
+
+/// A direction needs at least this much of gravity to be picked
+pub const MIN_MG: i32 = 500;
+/// How much more than the current direction a new one needs
+pub const HYSTERESIS_MG: i32 = 200;
+
+/// Which accelerometer axis points up. Which edge of the board that is depends on how the chip is mounted,
+/// `status` on the console shows the readings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tilt {
+    /// +Z up: lying flat, face up
+    Flat,
+    /// -Z up: lying face down
+    UpsideDown,
+    XUp,
+    XDown,
+    YUp,
+    YDown,
+}
+
+impl Tilt {
+    pub const ALL: [Tilt; 6] = [
+        Tilt::Flat,
+        Tilt::UpsideDown,
+        Tilt::XUp,
+        Tilt::XDown,
+        Tilt::YUp,
+        Tilt::YDown,
+    ];
+
+    /// How much of the reading points this way up, in mg
+    pub fn component(self, x_mg: i32, y_mg: i32, z_mg: i32) -> i32 {
+        match self {
+            Self::Flat => z_mg,
+            Self::UpsideDown => -z_mg,
+            Self::XUp => x_mg,
+            Self::XDown => -x_mg,
+            Self::YUp => y_mg,
+            Self::YDown => -y_mg,
+        }
+    }
+}
+
+impl fmt::Display for Tilt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Flat => "flat",
+            Self::UpsideDown => "upside down",
+            Self::XUp => "X up",
+            Self::XDown => "X down",
+            Self::YUp => "Y up",
+            Self::YDown => "Y down",
+        })
+    }
+}
+
+/// Tracks the direction across readings, see the module documentation
+#[derive(Debug, Default)]
+pub struct TiltDetector {
+    current: Option<Tilt>,
+}
+
+impl TiltDetector {
+    /// No direction until the first clear reading
+    pub const fn new() -> Self {
+        Self { current: None }
+    }
+
+    pub fn current(&self) -> Option<Tilt> {
+        self.current
+    }
+
+    /// Take a reading. Returns the new direction if it changed.
+    pub fn update(&mut self, x_mg: i32, y_mg: i32, z_mg: i32) -> Option<Tilt> {
+        let (best, best_mg) = Tilt::ALL
+            .into_iter()
+            .map(|tilt| (tilt, tilt.component(x_mg, y_mg, z_mg)))
+            .max_by_key(|&(_, mg)| mg)?;
+        if best_mg < MIN_MG || Some(best) == self.current {
+            return None;
+        }
+        if let Some(current) = self.current {
+            if best_mg < current.component(x_mg, y_mg, z_mg) + HYSTERESIS_MG {
+                return None;
+            }
+        }
+        self.current = Some(best);
+        Some(best)
+    }
+}
@@ -0,0 +1,69 @@
+//! Host tests for console input and commands
+
+use example_24_async::{
+    console::{Command, CommandError, LineBuffer, MAX_LINE},
+    event::{Button, Event},
+    tilt::Tilt,
+};
+
+/// Feed `input` to a fresh buffer, collecting the lines it returns
+fn lines(input: &[u8]) -> Vec<String> {
+    let mut buffer = LineBuffer::new();
+    input
+        .iter()
+        .filter_map(|&byte| buffer.push(byte).map(String::from))
+        .collect()
+}
+
+#[test]
+fn line_endings() {
+    assert_eq!(lines(b"help\n"), ["help"]);
+    assert_eq!(lines(b"status\r"), ["status"]);
+    assert_eq!(lines(b"rate 50\r\nhelp\n"), ["rate 50", "", "help"]);
+    assert!(lines(b"no end yet").is_empty());
+}
+
+#[test]
+fn long_lines_and_odd_bytes_are_dropped() {
+    let long = [b'x'; MAX_LINE + 10];
+    let mut input = long.to_vec();
+    input.push(b'\n');
+    assert_eq!(lines(&input), ["x".repeat(MAX_LINE)]);
+    assert_eq!(lines(b"st\x00at\xC3\xA9us\n"), ["status"]);
+}
+
+#[test]
+fn commands() {
+    assert_eq!(Command::parse("help"), Ok(Command::Help));
+    assert_eq!(Command::parse("  STATUS "), Ok(Command::Status));
+    assert_eq!(Command::parse("rate 250"), Ok(Command::Rate(250)));
+    assert_eq!(Command::parse("rate   10"), Ok(Command::Rate(10)));
+    assert_eq!(Command::parse(""), Ok(Command::Empty));
+    assert_eq!(Command::parse("   "), Ok(Command::Empty));
+}
+
+#[test]
+fn bad_commands() {
+    assert_eq!(Command::parse("blink"), Err(CommandError::Unknown("blink")));
+    let usage = Err(CommandError::Usage("rate <10-10000 ms>"));
+    assert_eq!(Command::parse("rate"), usage);
+    assert_eq!(Command::parse("rate fast"), usage);
+    assert_eq!(Command::parse("rate 9"), usage);
+    assert_eq!(Command::parse("rate 10001"), usage);
+    assert_eq!(
+        CommandError::Unknown("blink").to_string(),
+        "unknown command 'blink', try 'help'"
+    );
+}
+
+#[test]
+fn events() {
+    let pressed = Event::Pressed {
+        button: Button::B,
+        count: 3,
+    };
+    assert_eq!(pressed.to_string(), "Button B pressed (3 so far)");
+    assert_eq!(Event::Tilted(Tilt::XUp).to_string(), "Tilted: X up");
+    assert_eq!(Button::A.index(), 0);
+    assert_eq!(Button::B.index(), 1);
+}
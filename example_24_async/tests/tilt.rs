@@ -0,0 +1,59 @@
+//! Host tests for the tilt detector
+
+use example_24_async::tilt::{Tilt, TiltDetector};
+
+#[test]
+fn each_axis() {
+    let cases = [
+        ((0, 0, 1000), Tilt::Flat),
+        ((0, 0, -1000), Tilt::UpsideDown),
+        ((1000, 0, 0), Tilt::XUp),
+        ((-1000, 0, 0), Tilt::XDown),
+        ((0, 1000, 0), Tilt::YUp),
+        ((0, -1000, 0), Tilt::YDown),
+    ];
+    for ((x, y, z), tilt) in cases {
+        let mut detector = TiltDetector::new();
+        assert_eq!(detector.update(x, y, z), Some(tilt));
+        assert_eq!(detector.current(), Some(tilt));
+    }
+}
+
+#[test]
+fn only_changes_are_reported() {
+    let mut detector = TiltDetector::new();
+    assert_eq!(detector.current(), None);
+    assert_eq!(detector.update(20, -30, 990), Some(Tilt::Flat));
+    assert_eq!(detector.update(-15, 10, 1010), None);
+    assert_eq!(detector.update(980, 0, 100), Some(Tilt::XUp));
+    assert_eq!(detector.current(), Some(Tilt::XUp));
+}
+
+#[test]
+fn weak_readings_are_ignored() {
+    // In free fall, or shaken about: nothing clearly up
+    let mut detector = TiltDetector::new();
+    assert_eq!(detector.update(100, 200, 300), None);
+    assert_eq!(detector.update(0, 0, 0), None);
+    assert_eq!(detector.current(), None);
+}
+
+#[test]
+fn hysteresis() {
+    let mut detector = TiltDetector::new();
+    detector.update(0, 0, 1000);
+    // Tilted 45° towards X: both read about 707 mg, and flat stays
+    assert_eq!(detector.update(707, 0, 707), None);
+    assert_eq!(detector.update(800, 0, 650), None);
+    // X now clearly more than Z
+    assert_eq!(detector.update(850, 0, 600), Some(Tilt::XUp));
+    // And going back needs the same margin the other way
+    assert_eq!(detector.update(707, 0, 707), None);
+    assert_eq!(detector.update(600, 0, 850), Some(Tilt::Flat));
+}
+
+#[test]
+fn display() {
+    assert_eq!(Tilt::UpsideDown.to_string(), "upside down");
+    assert_eq!(Tilt::YDown.to_string(), "Y down");
+}
@@ -0,0 +1,177 @@
+//! A cooperative executor: a fixed pool of tasks, each polled only after something woke it.
+//!
+//! A task is any `Future<Output = ()>`, usually an `async fn` that loops forever. The executor keeps up to `N`
+//! of them, pinned wherever the caller put them: `main` never returns, so its stack is as good as a static.
+//! Each task has a ready flag in a [`Ready`], which must be a `static`:
+//!
+//! ```text
+//!   waker.wake()  ──►  flag[i] = true, pend PendSV (on the micro:bit)
+//!                           │
+//!   Executor::run  ◄────────┘   poll every task whose flag is set, clearing the flag first;
+//!                               nothing set: idle (WFE) until an exception wakes the core
+//! ```
+//!
+//! A waker is just a pointer to its task's flag, so waking is safe from interrupt handlers, and since the flags
+//! are `static` a waker kept after its task finished does no harm: at worst the task that took over the slot
+//! is polled once for nothing.
+//!
+//! Pending PendSV is what gets the executor out of WFE. `main` gives PendSV the lowest priority, so when an interrupt
+//! handler wakes a task it runs after the handler; its handler only executes SEV. That covers a wake that
+//! comes in between the executor checking the flags and going to sleep: SEV sets the event register, and WFE
+//! returns straight away when it is set.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+/// The ready flags of an executor's tasks, see the module documentation. Each executor needs its own.
+pub struct Ready<const N: usize> {
+    flags: [AtomicBool; N],
+}
+
+impl<const N: usize> Ready<N> {
+    pub const fn new() -> Self {
+        Self {
+            flags: [const { AtomicBool::new(false) }; N],
+        }
+    }
+}
+
+impl<const N: usize> Default for Ready<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A task, pinned in place
+pub type Task<'t> = Pin<&'t mut dyn Future<Output = ()>>;
+
+/// All `N` task slots are taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolFull;
+
+/// Runs up to `N` tasks, see the module documentation
+pub struct Executor<'t, const N: usize> {
+    ready: &'static Ready<N>,
+    tasks: [Option<Task<'t>>; N],
+}
+
+impl<'t, const N: usize> Executor<'t, N> {
+    /// An executor with no tasks, using the flags in `ready`
+    pub fn new(ready: &'static Ready<N>) -> Self {
+        for flag in &ready.flags {
+            flag.store(false, Ordering::Relaxed);
+        }
+        Self {
+            ready,
+            tasks: [const { None }; N],
+        }
+    }
+
+    /// Add a task, to be polled for the first time on the next round. Returns its slot number.
+    pub fn spawn(&mut self, task: Task<'t>) -> Result<usize, PoolFull> {
+        let index = self.tasks.iter().position(Option::is_none).ok_or(PoolFull)?;
+        self.tasks[index] = Some(task);
+        self.ready.flags[index].store(true, Ordering::Release);
+        Ok(index)
+    }
+
+    /// Tasks that have not finished yet
+    pub fn tasks(&self) -> usize {
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+
+    /// No task is waiting to be polled
+    pub fn is_idle(&self) -> bool {
+        !self.ready.flags.iter().any(|flag| flag.load(Ordering::Acquire))
+    }
+
+    /// One round: poll each task that was woken, once. Returns how many were polled.
+    ///
+    /// A task that wakes itself while it is polled (see [`yield_now`]) is polled again on the next round, not
+    /// in this one, so one busy task cannot starve the others.
+    pub fn poll_ready(&mut self) -> usize {
+        let mut polled = 0;
+        for (flag, slot) in self.ready.flags.iter().zip(&mut self.tasks) {
+            // Clear the flag before polling: a wake during the poll must not be lost
+            if !flag.swap(false, Ordering::AcqRel) {
+                continue;
+            }
+            let Some(task) = slot else {
+                continue;
+            };
+            let waker = waker(flag);
+            let mut cx = Context::from_waker(&waker);
+            if task.as_mut().poll(&mut cx).is_ready() {
+                *slot = None;
+            }
+            polled += 1;
+        }
+        polled
+    }
+
+    /// Run the tasks forever, calling `idle` whenever none of them is ready. On the micro:bit `idle` is WFE,
+    /// which sleeps until an interrupt or a wake.
+    pub fn run(&mut self, mut idle: impl FnMut()) -> ! {
+        loop {
+            if self.poll_ready() == 0 {
+                idle();
+            }
+        }
+    }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+/// A waker that sets `flag`
+fn waker(flag: &'static AtomicBool) -> Waker {
+    let raw = RawWaker::new(flag as *const AtomicBool as *const (), &VTABLE);
+    // The vtable functions only ever treat the pointer as a `&'static AtomicBool`
+    unsafe { Waker::from_raw(raw) }
+}
+
+unsafe fn clone(flag: *const ()) -> RawWaker {
+    RawWaker::new(flag, &VTABLE)
+}
+
+unsafe fn wake(flag: *const ()) {
+    let flag = unsafe { &*(flag as *const AtomicBool) };
+    flag.store(true, Ordering::Release);
+    #[cfg(target_os = "none")]
+    {
+        /// Interrupt Control and State Register, and its PENDSVSET bit
+        const SCB_ICSR: *mut u32 = 0xE000_ED04 as *mut u32;
+        const PENDSVSET: u32 = 1 << 28;
+        // Writing 0 to the other bits has no effect
+        unsafe { core::ptr::write_volatile(SCB_ICSR, PENDSVSET) };
+    }
+}
+
+unsafe fn drop(_: *const ()) {}
+
+/// Let the other tasks run, and continue on the next round
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// The future returned by [`yield_now`]
+#[must_use = "futures do nothing unless awaited"]
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
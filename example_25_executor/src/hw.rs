@@ -0,0 +1,121 @@
+//! The few registers the executor and its tasks need, written to directly as in example 03: GPIO, the NVIC,
+//! PendSV's priority, and PRIMASK for critical sections.
+
+use core::{
+    arch::asm,
+    cell::{Cell, UnsafeCell},
+};
+
+// GPIO registers for nRF52833
+const GPIO_P0_OUT: *mut u32 = 0x5000_0504 as *mut u32;
+const GPIO_P0_OUTSET: *mut u32 = 0x5000_0508 as *mut u32;
+const GPIO_P0_OUTCLR: *mut u32 = 0x5000_050C as *mut u32;
+const GPIO_P0_IN: *const u32 = 0x5000_0510 as *const u32;
+const GPIO_P0_PIN_CNF: *mut u32 = 0x5000_0700 as *mut u32;
+
+// PIN_CNF values: DIR=1 for an output; DIR=0 with the input buffer connected (INPUT=0) for an input
+const PIN_CNF_OUTPUT: u32 = 1;
+const PIN_CNF_INPUT: u32 = 0;
+
+// NVIC Interrupt Set-Enable register, one bit per interrupt 0-31
+const NVIC_ISER0: *mut u32 = 0xE000_E100 as *mut u32;
+
+// System Handler Priority Register 3: PendSV's priority is bits 16-23
+const SCB_SHPR3: *mut u32 = 0xE000_ED20 as *mut u32;
+const SHPR3_PENDSV_MASK: u32 = 0xFF << 16;
+
+/// Make `pin` on P0 an output, starting at `high`
+pub fn output(pin: u32, high: bool) {
+    set(pin, high);
+    unsafe { core::ptr::write_volatile(GPIO_P0_PIN_CNF.add(pin as usize), PIN_CNF_OUTPUT) };
+}
+
+/// Make `pin` on P0 an input
+pub fn input(pin: u32) {
+    unsafe { core::ptr::write_volatile(GPIO_P0_PIN_CNF.add(pin as usize), PIN_CNF_INPUT) };
+}
+
+/// Drive output `pin` high or low
+pub fn set(pin: u32, high: bool) {
+    let register = if high { GPIO_P0_OUTSET } else { GPIO_P0_OUTCLR };
+    unsafe { core::ptr::write_volatile(register, 1 << pin) };
+}
+
+/// Invert output `pin`
+pub fn toggle(pin: u32) {
+    // Reading OUT and writing OUTSET/OUTCLR only changes this pin, whoever else is driving theirs
+    let high = unsafe { core::ptr::read_volatile(GPIO_P0_OUT) } & (1 << pin) != 0;
+    set(pin, !high);
+}
+
+/// The level on input `pin`
+pub fn is_high(pin: u32) -> bool {
+    unsafe { core::ptr::read_volatile(GPIO_P0_IN) & (1 << pin) != 0 }
+}
+
+/// Let interrupt `irq` (0-31) run
+pub fn enable_interrupt(irq: u32) {
+    unsafe { core::ptr::write_volatile(NVIC_ISER0, 1 << irq) };
+}
+
+/// Give PendSV the lowest priority, so the executor's wake-ups never get in the way of an interrupt
+pub fn lowest_pendsv_priority() {
+    unsafe {
+        let shpr3 = core::ptr::read_volatile(SCB_SHPR3);
+        core::ptr::write_volatile(SCB_SHPR3, shpr3 | SHPR3_PENDSV_MASK);
+    }
+}
+
+/// Sleep until an event: an interrupt, or SEV since the last WFE
+pub fn wfe() {
+    unsafe { asm!("wfe", options(nomem, nostack, preserves_flags)) };
+}
+
+/// Set the event register, so the next WFE returns at once
+pub fn sev() {
+    unsafe { asm!("sev", options(nomem, nostack, preserves_flags)) };
+}
+
+/// Run `f` with interrupts disabled
+pub fn interrupt_free<R>(f: impl FnOnce() -> R) -> R {
+    let primask: u32;
+    unsafe {
+        asm!("mrs {}, PRIMASK", out(reg) primask, options(nomem, nostack, preserves_flags));
+        asm!("cpsid i", options(nostack, preserves_flags));
+    }
+    let result = f();
+    // Only enable interrupts again if they were enabled before: critical sections may nest. Without `nomem`,
+    // the compiler keeps memory accesses in `f` between CPSID and CPSIE.
+    if primask & 1 == 0 {
+        unsafe { asm!("cpsie i", options(nostack, preserves_flags)) };
+    }
+    result
+}
+
+/// Data shared between `main` and interrupt handlers, only touched with interrupts disabled
+pub struct IrqCell<T> {
+    value: UnsafeCell<T>,
+    locked: Cell<bool>,
+}
+
+// There is a single core, and `lock` disables interrupts: nobody else can get at the value meanwhile
+unsafe impl<T: Send> Sync for IrqCell<T> {}
+
+impl<T> IrqCell<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            locked: Cell::new(false),
+        }
+    }
+
+    /// Run `f` on the value with interrupts disabled. Panics if `f` calls `lock` on the same cell again.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        interrupt_free(|| {
+            assert!(!self.locked.replace(true), "IrqCell locked twice");
+            let result = f(unsafe { &mut *self.value.get() });
+            self.locked.set(false);
+            result
+        })
+    }
+}
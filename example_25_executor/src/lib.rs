@@ -0,0 +1,19 @@
+#![no_std]
+
+//! A cooperative async executor and a timer queue on the RTC, with no dependencies at all, for the example 03
+//! runtime.
+//!
+//! - [`executor`] runs a fixed pool of tasks, polling each one only after its waker was called
+//! - [`timer`] has the 64-bit tick count, the `Sleep` future and the queue of deadlines tasks are sleeping until
+//! - [`hw`] has the registers: GPIO, the NVIC, PendSV's priority and critical sections (target only)
+//! - [`rtc`] is the clock the tasks sleep on, RTC1 with an alarm for the earliest deadline (target only)
+//!
+//! `executor` and `timer` are plain Rust and are tested on the PC, see `tests/`.
+
+pub mod executor;
+pub mod timer;
+
+#[cfg(target_os = "none")]
+pub mod hw;
+#[cfg(target_os = "none")]
+pub mod rtc;
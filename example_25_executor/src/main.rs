@@ -0,0 +1,227 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    core::pin::pin,
+    example_25_executor::{
+        executor::{Executor, Ready},
+        hw,
+        rtc::{self, RTC, RTC1_IRQ},
+        timer::{millis_to_ticks, sleep_ms, sleep_until, Clock},
+    },
+};
+
+// ============================================================================
+// VECTOR TABLE & RESET HANDLER - The example 03 runtime, with interrupts
+// ============================================================================
+
+// External symbols from linker script
+#[cfg(target_os = "none")]
+extern "C" {
+    static mut _sbss: u32; // Start of .bss section
+    static mut _ebss: u32; // End of .bss section
+    static mut _sdata: u32; // Start of .data section in RAM
+    static mut _edata: u32; // End of .data section in RAM
+    static _sidata: u32; // Initial values for .data (in flash)
+}
+
+// Reset handler - this is where execution begins after power-on
+/// # Safety
+///
+/// Only the core calls this, once, out of reset: nothing may use RAM before it is initialised here
+#[cfg(target_os = "none")]
+#[no_mangle]
+pub unsafe extern "C" fn Reset() -> ! {
+    // Copy .data section from flash to RAM
+    let mut src = core::ptr::addr_of!(_sidata);
+    let mut dest = core::ptr::addr_of_mut!(_sdata);
+    let end_data = core::ptr::addr_of_mut!(_edata);
+
+    while dest < end_data {
+        core::ptr::write_volatile(dest, core::ptr::read_volatile(src));
+        dest = dest.offset(1);
+        src = src.offset(1);
+    }
+
+    // Zero out .bss section
+    let mut dest = core::ptr::addr_of_mut!(_sbss);
+    let end_bss = core::ptr::addr_of_mut!(_ebss);
+
+    while dest < end_bss {
+        core::ptr::write_volatile(dest, 0);
+        dest = dest.offset(1);
+    }
+
+    main();
+}
+
+// Default handler for unused exceptions
+#[cfg(target_os = "none")]
+#[no_mangle]
+pub extern "C" fn DefaultHandler() -> ! {
+    loop {
+        hw::wfe();
+    }
+}
+
+// Default handler for unused interrupts: none of them is enabled, so this never runs
+#[cfg(target_os = "none")]
+#[no_mangle]
+pub extern "C" fn DefaultInterrupt() {
+    loop {
+        hw::wfe();
+    }
+}
+
+// Pended by every waker: taking it wakes the executor from WFE, and SEV covers a wake that came just before WFE
+#[cfg(target_os = "none")]
+#[no_mangle]
+pub extern "C" fn PendSV() {
+    hw::sev();
+}
+
+// The alarm, and the RTC counter's half and full overflows
+#[cfg(target_os = "none")]
+#[no_mangle]
+pub extern "C" fn RTC1() {
+    rtc::on_interrupt();
+}
+
+/// The nRF52833 has 48 interrupts, after the 16 Cortex-M exceptions
+#[cfg(target_os = "none")]
+const INTERRUPTS: usize = 48;
+
+// ARM Cortex-M Vector Table - using function pointers
+// This MUST be placed at address 0x00000000 (start of flash)
+#[cfg(target_os = "none")]
+#[repr(C)]
+pub struct VectorTable {
+    pub stack_pointer: u32,
+    pub reset: unsafe extern "C" fn() -> !,
+    pub nmi: unsafe extern "C" fn() -> !,
+    pub hard_fault: unsafe extern "C" fn() -> !,
+    pub mem_manage: unsafe extern "C" fn() -> !,
+    pub bus_fault: unsafe extern "C" fn() -> !,
+    pub usage_fault: unsafe extern "C" fn() -> !,
+    pub reserved1: [u32; 4],
+    pub sv_call: unsafe extern "C" fn() -> !,
+    pub debug_monitor: unsafe extern "C" fn() -> !,
+    pub reserved2: u32,
+    pub pend_sv: unsafe extern "C" fn(),
+    pub sys_tick: unsafe extern "C" fn() -> !,
+    pub interrupts: [unsafe extern "C" fn(); INTERRUPTS],
+}
+
+#[cfg(target_os = "none")]
+#[link_section = ".vector_table"]
+#[no_mangle]
+pub static VECTOR_TABLE: VectorTable = VectorTable {
+    stack_pointer: 0x20020000, // End of 128K RAM
+    reset: Reset,
+    nmi: DefaultHandler,
+    hard_fault: DefaultHandler,
+    mem_manage: DefaultHandler,
+    bus_fault: DefaultHandler,
+    usage_fault: DefaultHandler,
+    reserved1: [0; 4],
+    sv_call: DefaultHandler,
+    debug_monitor: DefaultHandler,
+    reserved2: 0,
+    pend_sv: PendSV,
+    sys_tick: DefaultHandler,
+    interrupts: interrupts(),
+};
+
+/// The interrupt part of the vector table: RTC1, and `DefaultInterrupt` for the rest
+#[cfg(target_os = "none")]
+const fn interrupts() -> [unsafe extern "C" fn(); INTERRUPTS] {
+    let mut table = [DefaultInterrupt as unsafe extern "C" fn(); INTERRUPTS];
+    table[RTC1_IRQ as usize] = RTC1;
+    table
+}
+
+// Custom panic handler - replaces panic-halt crate
+#[cfg(target_os = "none")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+// ============================================================================
+// TASKS
+// ============================================================================
+
+// micro:bit LED matrix pins: row 1 stays high, and each column lights one LED while it is low
+#[cfg(target_os = "none")]
+const ROW1_PIN: u32 = 21; // P0.21
+#[cfg(target_os = "none")]
+const COL1_PIN: u32 = 28; // P0.28
+#[cfg(target_os = "none")]
+const COL3_PIN: u32 = 31; // P0.31
+#[cfg(target_os = "none")]
+const COL5_PIN: u32 = 30; // P0.30
+#[cfg(target_os = "none")]
+const BUTTON_A_PIN: u32 = 14; // P0.14, low while pressed
+
+/// How often the button task looks at button A
+#[cfg(target_os = "none")]
+const BUTTON_POLL_MS: u64 = 10;
+
+/// Toggle the LED on column `pin` every `millis` milliseconds
+#[cfg(target_os = "none")]
+async fn blink(pin: u32, millis: u64) {
+    // Deadlines a fixed step apart, rather than sleeping for `millis` each time: the blinking does not drift
+    // however late the task gets polled
+    let mut deadline = RTC.now();
+    loop {
+        hw::toggle(pin);
+        deadline += millis_to_ticks(millis);
+        sleep_until(&RTC, deadline).await;
+    }
+}
+
+/// Toggle the LED on column 3 each time button A is pressed
+#[cfg(target_os = "none")]
+async fn button() {
+    let mut was_pressed = false;
+    loop {
+        let pressed = !hw::is_high(BUTTON_A_PIN);
+        if pressed && !was_pressed {
+            hw::toggle(COL3_PIN);
+        }
+        was_pressed = pressed;
+        // Polling every 10 ms also ignores the contact bounce
+        sleep_ms(&RTC, BUTTON_POLL_MS).await;
+    }
+}
+
+#[cfg(target_os = "none")]
+#[no_mangle]
+pub extern "C" fn main() -> ! {
+    hw::lowest_pendsv_priority();
+    hw::output(ROW1_PIN, true);
+    for col in [COL1_PIN, COL3_PIN, COL5_PIN] {
+        hw::output(col, true); // LED off
+    }
+    hw::input(BUTTON_A_PIN);
+    rtc::start();
+
+    // The tasks live on main's stack, which is never popped: main does not return
+    let heartbeat = pin!(blink(COL1_PIN, 500));
+    let fast = pin!(blink(COL5_PIN, 150));
+    let buttons = pin!(button());
+
+    static READY: Ready<3> = Ready::new();
+    let mut executor = Executor::new(&READY);
+    executor.spawn(heartbeat).unwrap();
+    executor.spawn(fast).unwrap();
+    executor.spawn(buttons).unwrap();
+
+    // Sleep whenever every task is waiting
+    executor.run(hw::wfe)
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
@@ -0,0 +1,150 @@
+//! RTC1 as the executor's clock: a 64-bit tick count, and an alarm for the earliest deadline in the timer queue.
+//!
+//! COMPARE0 at [`HALF_PERIOD`] and the overflow extend the counter, as in example 18. COMPARE1 is the alarm, set
+//! by [`plan_alarm`] whenever the earliest deadline changes. Every RTC1 interrupt, whichever event caused it,
+//! wakes the tasks whose time has come and sets the alarm again.
+
+use core::{
+    sync::atomic::{compiler_fence, AtomicU32, Ordering},
+    task::Waker,
+};
+
+use crate::{
+    hw::{self, IrqCell},
+    timer::{extend, plan_alarm, Alarm, Clock, TimerQueue, HALF_PERIOD},
+};
+
+// CLOCK registers: the RTC counts the 32.768 kHz low-frequency clock, which has to be started first
+const CLOCK_TASKS_LFCLKSTART: *mut u32 = 0x4000_0008 as *mut u32;
+const CLOCK_EVENTS_LFCLKSTARTED: *mut u32 = 0x4000_0104 as *mut u32;
+const CLOCK_LFCLKSRC: *mut u32 = 0x4000_0518 as *mut u32;
+/// LFCLKSRC value for the internal RC oscillator: no crystal needed
+const LFCLKSRC_RC: u32 = 0;
+
+// RTC1 registers
+const RTC1_TASKS_START: *mut u32 = 0x4001_1000 as *mut u32;
+const RTC1_TASKS_CLEAR: *mut u32 = 0x4001_1008 as *mut u32;
+const RTC1_EVENTS_OVRFLW: *mut u32 = 0x4001_1104 as *mut u32;
+const RTC1_EVENTS_COMPARE0: *mut u32 = 0x4001_1140 as *mut u32;
+const RTC1_EVENTS_COMPARE1: *mut u32 = 0x4001_1144 as *mut u32;
+const RTC1_INTENSET: *mut u32 = 0x4001_1304 as *mut u32;
+const RTC1_INTENCLR: *mut u32 = 0x4001_1308 as *mut u32;
+const RTC1_COUNTER: *const u32 = 0x4001_1504 as *const u32;
+const RTC1_PRESCALER: *mut u32 = 0x4001_1508 as *mut u32;
+const RTC1_CC0: *mut u32 = 0x4001_1540 as *mut u32;
+const RTC1_CC1: *mut u32 = 0x4001_1544 as *mut u32;
+
+// INTENSET / INTENCLR register bits
+const INTERRUPT_OVRFLW: u32 = 1 << 1;
+const INTERRUPT_COMPARE0: u32 = 1 << 16;
+const INTERRUPT_COMPARE1: u32 = 1 << 17;
+
+/// RTC1's interrupt number
+pub const RTC1_IRQ: u32 = 17;
+
+/// Timer slots: one per task that sleeps is enough, a task only sleeps on one deadline at a time
+const TIMERS: usize = 8;
+
+/// Half overflows so far, incremented by [`on_interrupt`]
+static PERIOD: AtomicU32 = AtomicU32::new(0);
+/// The deadlines the tasks are waiting for
+static QUEUE: IrqCell<TimerQueue<TIMERS>> = IrqCell::new(TimerQueue::new());
+
+/// The clock the tasks sleep on, once [`start`] has been called
+pub static RTC: Rtc = Rtc;
+
+/// RTC1, see the module documentation
+pub struct Rtc;
+
+/// Start the low-frequency clock and RTC1, counting from 0 at 32.768 kHz. Call [`on_interrupt`] from the RTC1
+/// interrupt.
+pub fn start() {
+    unsafe {
+        core::ptr::write_volatile(CLOCK_LFCLKSRC, LFCLKSRC_RC);
+        core::ptr::write_volatile(CLOCK_EVENTS_LFCLKSTARTED, 0);
+        core::ptr::write_volatile(CLOCK_TASKS_LFCLKSTART, 1);
+        while core::ptr::read_volatile(CLOCK_EVENTS_LFCLKSTARTED) == 0 {}
+
+        core::ptr::write_volatile(RTC1_TASKS_CLEAR, 1);
+        core::ptr::write_volatile(RTC1_PRESCALER, 0);
+        core::ptr::write_volatile(RTC1_CC0, HALF_PERIOD);
+        core::ptr::write_volatile(RTC1_EVENTS_OVRFLW, 0);
+        core::ptr::write_volatile(RTC1_EVENTS_COMPARE0, 0);
+        core::ptr::write_volatile(RTC1_EVENTS_COMPARE1, 0);
+        core::ptr::write_volatile(RTC1_INTENSET, INTERRUPT_OVRFLW | INTERRUPT_COMPARE0);
+    }
+    PERIOD.store(0, Ordering::Relaxed);
+    hw::enable_interrupt(RTC1_IRQ);
+    unsafe { core::ptr::write_volatile(RTC1_TASKS_START, 1) };
+}
+
+impl Clock for Rtc {
+    fn now(&self) -> u64 {
+        // The period first: if the interrupt increments it after this, `extend` still gets it right
+        let period = PERIOD.load(Ordering::Relaxed);
+        compiler_fence(Ordering::Acquire);
+        let counter = unsafe { core::ptr::read_volatile(RTC1_COUNTER) };
+        extend(period, counter)
+    }
+
+    fn wake_at(&self, deadline: u64, waker: &Waker) {
+        QUEUE.lock(|queue| {
+            let earliest_changed = queue
+                .insert(deadline, waker)
+                .expect("more sleeping tasks than timer slots");
+            if earliest_changed {
+                set_alarm(queue);
+            }
+        });
+    }
+}
+
+/// Call from the RTC1 interrupt
+pub fn on_interrupt() {
+    unsafe {
+        if core::ptr::read_volatile(RTC1_EVENTS_OVRFLW) != 0 {
+            core::ptr::write_volatile(RTC1_EVENTS_OVRFLW, 0);
+            PERIOD.store(PERIOD.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+        }
+        if core::ptr::read_volatile(RTC1_EVENTS_COMPARE0) != 0 {
+            core::ptr::write_volatile(RTC1_EVENTS_COMPARE0, 0);
+            PERIOD.store(PERIOD.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+        }
+        core::ptr::write_volatile(RTC1_EVENTS_COMPARE1, 0);
+    }
+    QUEUE.lock(|queue| {
+        queue.expire(RTC.now());
+        set_alarm(queue);
+    });
+}
+
+/// Point COMPARE1 at the earliest deadline, waking the tasks whose deadline passed while doing so
+fn set_alarm(queue: &mut TimerQueue<TIMERS>) {
+    loop {
+        let Some(deadline) = queue.next_deadline() else {
+            unsafe { core::ptr::write_volatile(RTC1_INTENCLR, INTERRUPT_COMPARE1) };
+            return;
+        };
+        match plan_alarm(RTC.now(), deadline) {
+            Alarm::Expired => {
+                queue.expire(RTC.now());
+            }
+            Alarm::At(counter) => {
+                unsafe {
+                    core::ptr::write_volatile(RTC1_CC1, counter);
+                    core::ptr::write_volatile(RTC1_INTENSET, INTERRUPT_COMPARE1);
+                }
+                // If the counter got there while the alarm was being set, the event may never come
+                if RTC.now() < deadline {
+                    return;
+                }
+                queue.expire(RTC.now());
+            }
+            Alarm::TooFar => {
+                // The next COMPARE0 or overflow comes first, and sets the alarm again
+                unsafe { core::ptr::write_volatile(RTC1_INTENCLR, INTERRUPT_COMPARE1) };
+                return;
+            }
+        }
+    }
+}
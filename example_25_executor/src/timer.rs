@@ -0,0 +1,186 @@
+//! Time for the tasks: a 64-bit tick count from the RTC, the [`Sleep`] future, and the queue of deadlines the
+//! sleeping tasks are waiting for.
+//!
+//! The RTC counts at 32.768 kHz into a 24-bit COUNTER, extended to 64 bits the same way as in example 18: the
+//! RTC interrupt increments a period count at the overflow and half way, and [`extend`] combines the two.
+//!
+//! A task that sleeps puts its deadline and its waker in a [`TimerQueue`]. Only the earliest deadline needs an
+//! alarm; when it goes off, the interrupt handler wakes every task whose deadline has passed and sets the alarm
+//! for the next one:
+//!
+//! ```text
+//! sleep_ms(150).await ──► queue: (deadline, waker) ──► earliest changed? set the RTC compare (plan_alarm)
+//!                                                                  │
+//! task polled again  ◄── waker.wake() ◄── TimerQueue::expire ◄── RTC interrupt
+//! ```
+//!
+//! None of this touches the hardware: the RTC is behind the [`Clock`] trait, so the tests drive the queue and
+//! the tasks with a clock of their own.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+/// RTC ticks per second, with PRESCALER 0
+pub const TICKS_PER_SECOND: u64 = 32_768;
+
+/// Counter value where the second period of each overflow starts
+pub const HALF_PERIOD: u32 = 1 << 23;
+
+/// The RTC may miss a compare value less than two ticks ahead of the counter
+pub const MIN_ALARM_TICKS: u64 = 3;
+
+/// 64-bit ticks from the period count and the 24-bit counter
+pub fn extend(period: u32, counter: u32) -> u64 {
+    // Odd periods start half way through the counter, so flipping its top bit gives the ticks since the period
+    // started. A counter that has already moved into the next half comes out past the end of the period, which
+    // is still the right time.
+    (u64::from(period) << 23) + u64::from((counter & 0xFF_FFFF) ^ ((period & 1) << 23))
+}
+
+/// Ticks in `millis`, rounded up so that a sleep is never shorter than asked for
+pub fn millis_to_ticks(millis: u64) -> u64 {
+    // 32768 / 1000 = 4096 / 125
+    (millis * 4096).div_ceil(125)
+}
+
+/// Milliseconds in `ticks`, rounded down
+pub fn ticks_to_millis(ticks: u64) -> u64 {
+    ticks * 125 / 4096
+}
+
+/// Where the time comes from, and who to tell about a deadline
+pub trait Clock {
+    /// Ticks since the clock started
+    fn now(&self) -> u64;
+
+    /// Wake `waker` once `now()` has reached `deadline`
+    fn wake_at(&self, deadline: u64, waker: &Waker);
+}
+
+/// Wait until `clock` reaches `deadline`
+pub fn sleep_until<C: Clock>(clock: &C, deadline: u64) -> Sleep<'_, C> {
+    Sleep { clock, deadline }
+}
+
+/// Wait for `millis` milliseconds from now
+pub fn sleep_ms<C: Clock>(clock: &C, millis: u64) -> Sleep<'_, C> {
+    sleep_until(clock, clock.now() + millis_to_ticks(millis))
+}
+
+/// The future returned by [`sleep_until`] and [`sleep_ms`]
+#[must_use = "futures do nothing unless awaited"]
+pub struct Sleep<'c, C> {
+    clock: &'c C,
+    deadline: u64,
+}
+
+impl<C> Sleep<'_, C> {
+    /// The tick count this sleep ends at
+    pub fn deadline(&self) -> u64 {
+        self.deadline
+    }
+}
+
+impl<C: Clock> Future for Sleep<'_, C> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.clock.now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        // Registered again on every poll: the task may have been woken for something else in the meantime
+        self.clock.wake_at(self.deadline, cx.waker());
+        Poll::Pending
+    }
+}
+
+/// All `N` timer slots are taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+/// The deadlines tasks are sleeping until, and their wakers
+pub struct TimerQueue<const N: usize> {
+    entries: [Option<(u64, Waker)>; N],
+}
+
+impl<const N: usize> TimerQueue<N> {
+    pub const fn new() -> Self {
+        Self {
+            entries: [const { None }; N],
+        }
+    }
+
+    /// Wake `waker` at `deadline`. A task that is already waiting keeps its slot, with the earlier of the two
+    /// deadlines. Returns true if the earliest deadline changed, and the alarm has to be moved.
+    pub fn insert(&mut self, deadline: u64, waker: &Waker) -> Result<bool, QueueFull> {
+        let earliest = self.next_deadline();
+        if let Some((at, _)) = self.entries.iter_mut().flatten().find(|(_, w)| w.will_wake(waker)) {
+            *at = (*at).min(deadline);
+        } else {
+            let slot = self.entries.iter_mut().find(|entry| entry.is_none()).ok_or(QueueFull)?;
+            *slot = Some((deadline, waker.clone()));
+        }
+        Ok(self.next_deadline() != earliest)
+    }
+
+    /// The earliest deadline, if any task is waiting
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.entries.iter().flatten().map(|(at, _)| *at).min()
+    }
+
+    /// Tasks waiting
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wake and remove every task whose deadline is at or before `now`. Returns how many were woken.
+    pub fn expire(&mut self, now: u64) -> usize {
+        let mut woken = 0;
+        for entry in &mut self.entries {
+            if entry.as_ref().is_some_and(|(at, _)| *at <= now) {
+                if let Some((_, waker)) = entry.take() {
+                    waker.wake();
+                    woken += 1;
+                }
+            }
+        }
+        woken
+    }
+}
+
+impl<const N: usize> Default for TimerQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What to do with the RTC's alarm for the next deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alarm {
+    /// The deadline has passed already: expire the queue now
+    Expired,
+    /// Set the compare register to this counter value
+    At(u32),
+    /// More than half an overflow away. No alarm is needed: the period interrupt comes first, and plans again.
+    TooFar,
+}
+
+/// The alarm for `deadline`, at `now`
+pub fn plan_alarm(now: u64, deadline: u64) -> Alarm {
+    if deadline <= now {
+        Alarm::Expired
+    } else if deadline - now > u64::from(HALF_PERIOD) {
+        Alarm::TooFar
+    } else {
+        // A compare value right next to the counter may be missed, so never set one closer than that
+        let at = deadline.max(now + MIN_ALARM_TICKS);
+        Alarm::At((at & 0xFF_FFFF) as u32)
+    }
+}
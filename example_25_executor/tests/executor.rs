@@ -0,0 +1,246 @@
+//! Host tests for the executor's scheduling
+
+use std::{
+    cell::{Cell, RefCell},
+    future::{pending, poll_fn, Future},
+    pin::pin,
+    task::{Poll, Waker},
+    thread,
+};
+
+use example_25_executor::executor::{yield_now, Executor, PoolFull, Ready};
+
+/// Something a task can wait for, and someone else can set
+#[derive(Default)]
+struct Signal {
+    set: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl Signal {
+    fn set(&self) {
+        self.set.set(true);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn wait(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| {
+            if self.set.replace(false) {
+                Poll::Ready(())
+            } else {
+                *self.waker.borrow_mut() = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+    }
+}
+
+/// Count its polls, forever
+async fn count_polls(polls: &Cell<u32>) {
+    poll_fn(|_| {
+        polls.set(polls.get() + 1);
+        Poll::<()>::Pending
+    })
+    .await
+}
+
+#[test]
+fn spawned_tasks_are_polled_once() {
+    static READY: Ready<4> = Ready::new();
+    let (a, b) = (Cell::new(0), Cell::new(0));
+    let task_a = pin!(count_polls(&a));
+    let task_b = pin!(count_polls(&b));
+    let mut executor = Executor::new(&READY);
+    assert!(executor.is_idle());
+    assert_eq!(executor.spawn(task_a), Ok(0));
+    assert_eq!(executor.spawn(task_b), Ok(1));
+    assert!(!executor.is_idle());
+
+    assert_eq!(executor.poll_ready(), 2);
+    // Nobody woke them: nothing to do
+    assert_eq!(executor.poll_ready(), 0);
+    assert!(executor.is_idle());
+    assert_eq!((a.get(), b.get()), (1, 1));
+    assert_eq!(executor.tasks(), 2);
+}
+
+#[test]
+fn only_woken_tasks_are_polled() {
+    static READY: Ready<2> = Ready::new();
+    let (signal_a, signal_b) = (Signal::default(), Signal::default());
+    let (a, b) = (Cell::new(0), Cell::new(0));
+    let task_a = pin!(async {
+        loop {
+            signal_a.wait().await;
+            a.set(a.get() + 1);
+        }
+    });
+    let task_b = pin!(async {
+        loop {
+            signal_b.wait().await;
+            b.set(b.get() + 1);
+        }
+    });
+    let mut executor = Executor::new(&READY);
+    executor.spawn(task_a).unwrap();
+    executor.spawn(task_b).unwrap();
+    assert_eq!(executor.poll_ready(), 2);
+
+    signal_b.set();
+    assert!(!executor.is_idle());
+    assert_eq!(executor.poll_ready(), 1);
+    assert_eq!((a.get(), b.get()), (0, 1));
+
+    signal_a.set();
+    signal_b.set();
+    assert_eq!(executor.poll_ready(), 2);
+    assert_eq!((a.get(), b.get()), (1, 2));
+    assert_eq!(executor.poll_ready(), 0);
+}
+
+#[test]
+fn tasks_wake_each_other() {
+    static READY: Ready<2> = Ready::new();
+    let (ping, pong) = (Signal::default(), Signal::default());
+    let log = RefCell::new(Vec::new());
+    let pinger = pin!(async {
+        for round in 0..3 {
+            log.borrow_mut().push(("ping", round));
+            ping.set();
+            pong.wait().await;
+        }
+    });
+    let ponger = pin!(async {
+        for round in 0..3 {
+            ping.wait().await;
+            log.borrow_mut().push(("pong", round));
+            pong.set();
+        }
+    });
+    let mut executor = Executor::new(&READY);
+    executor.spawn(pinger).unwrap();
+    executor.spawn(ponger).unwrap();
+    let mut rounds = 0;
+    while !executor.is_idle() {
+        executor.poll_ready();
+        rounds += 1;
+    }
+    assert_eq!(
+        *log.borrow(),
+        [
+            ("ping", 0),
+            ("pong", 0),
+            ("ping", 1),
+            ("pong", 1),
+            ("ping", 2),
+            ("pong", 2)
+        ]
+    );
+    assert_eq!(executor.tasks(), 0);
+    assert!(rounds <= 6, "{} rounds", rounds);
+}
+
+#[test]
+fn finished_tasks_free_their_slot() {
+    static READY: Ready<1> = Ready::new();
+    let done = Cell::new(0);
+    let first = pin!(async { done.set(done.get() + 1) });
+    let second = pin!(async { done.set(done.get() + 1) });
+    let mut executor = Executor::new(&READY);
+    assert_eq!(executor.spawn(first), Ok(0));
+    assert_eq!(executor.tasks(), 1);
+    assert_eq!(executor.poll_ready(), 1);
+    assert_eq!(executor.tasks(), 0);
+    assert_eq!(executor.spawn(second), Ok(0));
+    assert_eq!(executor.poll_ready(), 1);
+    assert_eq!(done.get(), 2);
+}
+
+#[test]
+fn pool_full() {
+    static READY: Ready<2> = Ready::new();
+    let (a, b, c) = (pin!(pending()), pin!(pending()), pin!(pending()));
+    let mut executor = Executor::new(&READY);
+    executor.spawn(a).unwrap();
+    executor.spawn(b).unwrap();
+    assert_eq!(executor.spawn(c), Err(PoolFull));
+}
+
+#[test]
+fn yielding_lets_the_others_run() {
+    static READY: Ready<2> = Ready::new();
+    let log = RefCell::new(Vec::new());
+    let busy = pin!(async {
+        for step in 0..3 {
+            log.borrow_mut().push(("busy", step));
+            yield_now().await;
+        }
+    });
+    let other = pin!(async {
+        for step in 0..3 {
+            log.borrow_mut().push(("other", step));
+            yield_now().await;
+        }
+    });
+    let mut executor = Executor::new(&READY);
+    executor.spawn(busy).unwrap();
+    executor.spawn(other).unwrap();
+    // A task that wakes itself is polled again on the next round, not straight away
+    assert_eq!(executor.poll_ready(), 2);
+    assert_eq!(*log.borrow(), [("busy", 0), ("other", 0)]);
+    while !executor.is_idle() {
+        executor.poll_ready();
+    }
+    assert_eq!(log.borrow().len(), 6);
+    assert_eq!(log.borrow()[4..], [("busy", 2), ("other", 2)]);
+    assert_eq!(executor.tasks(), 0);
+}
+
+#[test]
+fn woken_from_another_thread() {
+    // As an interrupt handler would: the waker only sets an atomic flag
+    static READY: Ready<1> = Ready::new();
+    let waker = RefCell::new(None);
+    let polls = Cell::new(0);
+    let task = pin!(poll_fn(|cx| {
+        polls.set(polls.get() + 1);
+        *waker.borrow_mut() = Some(cx.waker().clone());
+        Poll::<()>::Pending
+    }));
+    let mut executor = Executor::new(&READY);
+    executor.spawn(task).unwrap();
+    executor.poll_ready();
+    assert!(executor.is_idle());
+
+    let waker = waker.take().unwrap();
+    thread::spawn(move || waker.wake()).join().unwrap();
+    assert!(!executor.is_idle());
+    assert_eq!(executor.poll_ready(), 1);
+    assert_eq!(polls.get(), 2);
+}
+
+#[test]
+fn wakers_outlive_their_task() {
+    // The flags are static, so waking a finished task is harmless
+    static READY: Ready<1> = Ready::new();
+    let waker = RefCell::new(None);
+    let first = pin!(poll_fn(|cx| {
+        *waker.borrow_mut() = Some(cx.waker().clone());
+        Poll::Ready(())
+    }));
+    let polls = Cell::new(0);
+    let second = pin!(count_polls(&polls));
+    let mut executor = Executor::new(&READY);
+    executor.spawn(first).unwrap();
+    executor.poll_ready();
+    executor.spawn(second).unwrap();
+    executor.poll_ready();
+    assert_eq!(polls.get(), 1);
+
+    // The new task has the same slot, so it is polled once for nothing: a spurious wake, which futures allow
+    waker.take().unwrap().wake();
+    assert_eq!(executor.poll_ready(), 1);
+    assert_eq!(polls.get(), 2);
+}
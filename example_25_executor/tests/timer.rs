@@ -0,0 +1,239 @@
+//! Host tests for the timer queue, the alarm and sleeping tasks
+
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use example_25_executor::{
+    executor::{Executor, Ready},
+    timer::{
+        extend, millis_to_ticks, plan_alarm, sleep_ms, sleep_until, ticks_to_millis, Alarm, Clock, QueueFull,
+        TimerQueue, HALF_PERIOD, MIN_ALARM_TICKS, TICKS_PER_SECOND,
+    },
+};
+
+/// A waker that counts its wakes
+struct Counter(AtomicUsize);
+
+impl Wake for Counter {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn counting_waker() -> (Arc<Counter>, Waker) {
+    let counter = Arc::new(Counter(AtomicUsize::new(0)));
+    (counter.clone(), Waker::from(counter))
+}
+
+fn wakes(counter: &Counter) -> usize {
+    counter.0.load(Ordering::Relaxed)
+}
+
+/// A clock the test moves on by hand, with the queue the RTC1 interrupt would look after
+struct FakeClock {
+    now: Cell<u64>,
+    queue: RefCell<TimerQueue<4>>,
+}
+
+impl FakeClock {
+    fn new() -> Self {
+        Self {
+            now: Cell::new(0),
+            queue: RefCell::new(TimerQueue::new()),
+        }
+    }
+
+    /// Jump to the next deadline and wake whoever waits for it, as the alarm would. False if nobody waits.
+    fn next_alarm(&self) -> bool {
+        let Some(deadline) = self.queue.borrow().next_deadline() else {
+            return false;
+        };
+        self.now.set(deadline);
+        self.queue.borrow_mut().expire(deadline);
+        true
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn wake_at(&self, deadline: u64, waker: &Waker) {
+        self.queue.borrow_mut().insert(deadline, waker).unwrap();
+    }
+}
+
+#[test]
+fn millis_and_ticks() {
+    assert_eq!(millis_to_ticks(1000), TICKS_PER_SECOND);
+    // 32.768 ticks: rounded up, a sleep is never short
+    assert_eq!(millis_to_ticks(1), 33);
+    assert_eq!(millis_to_ticks(150), 4916);
+    assert_eq!(ticks_to_millis(TICKS_PER_SECOND), 1000);
+    assert_eq!(ticks_to_millis(32), 0);
+    for millis in [1, 10, 150, 500, 86_400_000] {
+        assert!(millis_to_ticks(millis) * 1000 >= millis * TICKS_PER_SECOND);
+        assert_eq!(ticks_to_millis(millis_to_ticks(millis)), millis);
+    }
+}
+
+#[test]
+fn extends_the_counter() {
+    // Counter half way, in the period before and after the COMPARE0 interrupt
+    let half = u64::from(HALF_PERIOD);
+    assert_eq!(extend(0, HALF_PERIOD - 1), half - 1);
+    assert_eq!(extend(0, HALF_PERIOD), half);
+    assert_eq!(extend(1, HALF_PERIOD), half);
+    // Overflow, in the period before and after the OVRFLW interrupt
+    assert_eq!(extend(1, 0), 2 * half);
+    assert_eq!(extend(2, 0), 2 * half);
+    // A counter already past the end of its period
+    assert_eq!(extend(5, 7), 6 * half + 7);
+}
+
+#[test]
+fn queue_keeps_the_earliest_deadline() {
+    let mut queue = TimerQueue::<4>::new();
+    let (_, a) = counting_waker();
+    let (_, b) = counting_waker();
+    let (_, c) = counting_waker();
+    assert!(queue.is_empty());
+    assert_eq!(queue.next_deadline(), None);
+
+    // Only a deadline earlier than all the others moves the alarm
+    assert_eq!(queue.insert(500, &a), Ok(true));
+    assert_eq!(queue.insert(800, &b), Ok(false));
+    assert_eq!(queue.insert(200, &c), Ok(true));
+    assert_eq!(queue.next_deadline(), Some(200));
+    assert_eq!(queue.len(), 3);
+}
+
+#[test]
+fn queue_has_one_slot_per_task() {
+    let mut queue = TimerQueue::<2>::new();
+    let (_, a) = counting_waker();
+    let (_, b) = counting_waker();
+    let (_, c) = counting_waker();
+    queue.insert(500, &a).unwrap();
+    // The same task again keeps the earlier deadline
+    assert_eq!(queue.insert(300, &a.clone()), Ok(true));
+    assert_eq!(queue.insert(900, &a), Ok(false));
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue.next_deadline(), Some(300));
+
+    queue.insert(400, &b).unwrap();
+    assert_eq!(queue.insert(100, &c), Err(QueueFull));
+    assert_eq!(queue.next_deadline(), Some(300));
+}
+
+#[test]
+fn expire_wakes_the_tasks_whose_time_has_come() {
+    let mut queue = TimerQueue::<4>::new();
+    let (count_a, a) = counting_waker();
+    let (count_b, b) = counting_waker();
+    let (count_c, c) = counting_waker();
+    queue.insert(100, &a).unwrap();
+    queue.insert(300, &b).unwrap();
+    queue.insert(200, &c).unwrap();
+
+    assert_eq!(queue.expire(99), 0);
+    assert_eq!(queue.expire(200), 2);
+    assert_eq!((wakes(&count_a), wakes(&count_b), wakes(&count_c)), (1, 0, 1));
+    assert_eq!(queue.next_deadline(), Some(300));
+    assert_eq!(queue.expire(1000), 1);
+    assert_eq!(wakes(&count_b), 1);
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn alarm_for_the_next_deadline() {
+    let half = u64::from(HALF_PERIOD);
+    assert_eq!(plan_alarm(1000, 1000), Alarm::Expired);
+    assert_eq!(plan_alarm(1000, 999), Alarm::Expired);
+    assert_eq!(plan_alarm(1000, 5000), Alarm::At(5000));
+    // Never right next to the counter, where the RTC might miss it
+    assert_eq!(plan_alarm(1000, 1001), Alarm::At(1000 + MIN_ALARM_TICKS as u32));
+    // The compare register only has the counter's 24 bits
+    assert_eq!(plan_alarm(0xFF_FFF0, 0x100_0010), Alarm::At(0x10));
+    assert_eq!(plan_alarm(5 * half, 6 * half), Alarm::At(0));
+    assert_eq!(plan_alarm(0, half + 1), Alarm::TooFar);
+}
+
+#[test]
+fn sleep_waits_for_the_clock() {
+    let clock = FakeClock::new();
+    clock.now.set(1000);
+    let (count, waker) = counting_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut sleep = pin!(sleep_ms(&clock, 10));
+    assert_eq!(sleep.deadline(), 1000 + 328);
+    assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+    assert_eq!(clock.queue.borrow().next_deadline(), Some(1328));
+
+    // Woken early: still pending, and still in the queue
+    clock.queue.borrow_mut().expire(1200);
+    clock.now.set(1200);
+    assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Pending);
+    assert_eq!(clock.queue.borrow().len(), 1);
+
+    assert!(clock.next_alarm());
+    assert_eq!(wakes(&count), 1);
+    assert_eq!(sleep.as_mut().poll(&mut cx), Poll::Ready(()));
+    assert!(clock.queue.borrow().is_empty());
+}
+
+#[test]
+fn sleep_until_the_past_is_ready() {
+    let clock = FakeClock::new();
+    clock.now.set(50);
+    let (_, waker) = counting_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(pin!(sleep_until(&clock, 50)).poll(&mut cx), Poll::Ready(()));
+    assert!(clock.queue.borrow().is_empty());
+}
+
+#[test]
+fn tasks_blink_on_time() {
+    // Two blinking tasks as in main.rs, run by the executor on the fake clock for a second
+    static READY: Ready<2> = Ready::new();
+    let clock = FakeClock::new();
+    let toggles = RefCell::new(Vec::new());
+    let blink = |name: &'static str, millis: u64| {
+        let (clock, toggles) = (&clock, &toggles);
+        async move {
+            let mut deadline = clock.now();
+            loop {
+                toggles.borrow_mut().push((ticks_to_millis(clock.now()), name));
+                deadline += millis_to_ticks(millis);
+                sleep_until(clock, deadline).await;
+            }
+        }
+    };
+    let slow = pin!(blink("slow", 500));
+    let fast = pin!(blink("fast", 150));
+    let mut executor = Executor::new(&READY);
+    executor.spawn(slow).unwrap();
+    executor.spawn(fast).unwrap();
+
+    while clock.now() < TICKS_PER_SECOND {
+        while executor.poll_ready() > 0 {}
+        assert!(clock.next_alarm());
+    }
+    #[rustfmt::skip]
+    let expected = [
+        (0, "slow"), (0, "fast"), (150, "fast"), (300, "fast"), (450, "fast"), (500, "slow"),
+        (600, "fast"), (750, "fast"), (900, "fast"),
+    ];
+    assert_eq!(toggles.borrow()[..expected.len()], expected);
+    assert_eq!(executor.tasks(), 2);
+}
@@ -0,0 +1,46 @@
+//! Software debouncing: a button's level only counts once it has read the same a few samples in a row.
+//!
+//! Contacts bounce for a few milliseconds when pressed or released, so one press can raise several GPIOTE
+//! events. The GPIOTE interrupt therefore only starts a debounce task, which samples the pin every
+//! [`SAMPLE_MS`] until the level settles:
+//!
+//! ```text
+//! pin       ‾‾‾‾|_|‾|__|‾|_____________________________|‾|_|‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾‾
+//! samples           x   x    x    x    x    x              x    x    x    x
+//!                                 └ pressed: 4 low in a row     └ released: 4 high in a row
+//! ```
+
+/// Time between samples
+pub const SAMPLE_MS: u64 = 5;
+
+/// Samples in a row that have to agree: 20 ms, longer than the bounces of the micro:bit's buttons
+pub const SAMPLES: u8 = 4;
+
+/// Reports a level once it has been sampled [`SAMPLES`] times in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Debouncer {
+    last: bool,
+    run: u8,
+}
+
+impl Debouncer {
+    pub const fn new() -> Self {
+        Self { last: false, run: 0 }
+    }
+
+    /// Add a sample, true while the button is pressed. Returns the level once it has settled.
+    pub fn sample(&mut self, pressed: bool) -> Option<bool> {
+        if pressed != self.last {
+            self.last = pressed;
+            self.run = 0;
+        }
+        self.run = self.run.saturating_add(1);
+        (self.run >= SAMPLES).then_some(pressed)
+    }
+}
+
+impl Default for Debouncer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
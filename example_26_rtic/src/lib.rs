@@ -0,0 +1,11 @@
+#![no_std]
+
+//! Buttons and the LED matrix under RTIC: hardware tasks at different priorities, debouncing in software tasks.
+//!
+//! - [`debounce`] decides when a bouncing button has settled
+//! - [`tally`] counts the presses and draws them on the LED matrix
+//!
+//! Both are plain Rust and are tested on the PC, see `tests/`. The RTIC application is in `main.rs`.
+
+pub mod debounce;
+pub mod tally;
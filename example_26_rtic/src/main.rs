@@ -0,0 +1,145 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    embedded_hal::digital::InputPin,
+    example_26_rtic::debounce::{Debouncer, SAMPLE_MS},
+    microbit::hal::gpio::{Input, Pin, PullUp},
+    panic_halt as _,
+    rtic_monotonics::nrf::timer::prelude::*,
+};
+
+// The monotonic the software tasks sleep on: TIMER0 at 1 MHz, with its interrupt handler
+#[cfg(target_os = "none")]
+nrf_timer0_monotonic!(Mono);
+
+/// A button, pulled up: low while pressed
+#[cfg(target_os = "none")]
+type ButtonPin = Pin<Input<PullUp>>;
+
+// RTIC generates the interrupt handlers, the NVIC setup and the resource locks from this module. SWI0, an
+// interrupt no peripheral uses, runs the software tasks.
+#[cfg(target_os = "none")]
+#[rtic::app(device = microbit::pac, peripherals = true, dispatchers = [SWI0_EGU0])]
+mod app {
+    use microbit::{
+        display::nonblocking::{Display, GreyscaleImage},
+        hal::gpiote::Gpiote,
+        pac::TIMER1,
+        Board,
+    };
+
+    use example_26_rtic::tally::{Button, Presses};
+    use rtic::Mutex;
+
+    use super::{settle, Mono};
+
+    #[shared]
+    struct Shared {
+        /// Refreshed at priority 3, drawn on at priority 1: needs a lock
+        display: Display<TIMER1>,
+        /// Only the two debounce tasks use this, both at priority 1: they cannot preempt each other, so no lock
+        #[lock_free]
+        presses: Presses,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        button_a: super::ButtonPin,
+        button_b: super::ButtonPin,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        let board = Board::new(cx.device, cx.core);
+        Mono::start(board.TIMER0);
+
+        let button_a = board.buttons.button_a.into_pullup_input().degrade();
+        let button_b = board.buttons.button_b.into_pullup_input().degrade();
+
+        // Channel 0 for button A, channel 1 for button B, both on the press
+        let gpiote = Gpiote::new(board.GPIOTE);
+        gpiote.channel0().input_pin(&button_a).hi_to_lo().enable_interrupt();
+        gpiote.channel1().input_pin(&button_b).hi_to_lo().enable_interrupt();
+
+        let presses = Presses::new();
+        let mut display = Display::new(board.TIMER1, board.display_pins);
+        display.show(&GreyscaleImage::new(&presses.image()));
+
+        // No `NVIC::unmask` here: RTIC enables GPIOTE and TIMER1 because tasks are bound to them
+        (
+            Shared { display, presses },
+            Local {
+                gpiote,
+                button_a,
+                button_b,
+            },
+        )
+    }
+
+    /// Drive the next row of the LED matrix. The highest priority, so the picture never flickers, whatever the
+    /// other tasks are doing.
+    #[task(binds = TIMER1, priority = 3, shared = [display])]
+    fn refresh(mut cx: refresh::Context) {
+        cx.shared.display.lock(|display| display.handle_display_event());
+    }
+
+    /// A button went down. Only starts a debounce task: the bounces that follow raise more events, and while
+    /// that task is still running, spawning it again fails and they are ignored.
+    #[task(binds = GPIOTE, priority = 2, local = [gpiote])]
+    fn buttons(cx: buttons::Context) {
+        let gpiote = cx.local.gpiote;
+        if gpiote.channel0().is_event_triggered() {
+            gpiote.channel0().reset_events();
+            debounce_a::spawn().ok();
+        }
+        if gpiote.channel1().is_event_triggered() {
+            gpiote.channel1().reset_events();
+            debounce_b::spawn().ok();
+        }
+    }
+
+    #[task(priority = 1, local = [button_a], shared = [display, presses])]
+    async fn debounce_a(mut cx: debounce_a::Context) {
+        if settle(cx.local.button_a).await {
+            cx.shared.presses.press(Button::A);
+            show(cx.shared.display, cx.shared.presses);
+            // Wait for the release, whose bounces must not count as presses either
+            while settle(cx.local.button_a).await {}
+        }
+    }
+
+    #[task(priority = 1, local = [button_b], shared = [display, presses])]
+    async fn debounce_b(mut cx: debounce_b::Context) {
+        if settle(cx.local.button_b).await {
+            cx.shared.presses.press(Button::B);
+            show(cx.shared.display, cx.shared.presses);
+            while settle(cx.local.button_b).await {}
+        }
+    }
+
+    /// Draw the press counts. The refresh task can preempt the debounce tasks, so the display is locked while
+    /// the new image goes in: for that short while, TIMER1 waits.
+    fn show(mut display: impl Mutex<T = Display<TIMER1>>, presses: &Presses) {
+        let image = GreyscaleImage::new(&presses.image());
+        display.lock(|display| display.show(&image));
+    }
+}
+
+/// Sample `button` every few milliseconds until its level settles, and return it: true if pressed
+#[cfg(target_os = "none")]
+async fn settle(button: &mut ButtonPin) -> bool {
+    let mut debouncer = Debouncer::new();
+    loop {
+        if let Some(pressed) = debouncer.sample(button.is_low().unwrap()) {
+            return pressed;
+        }
+        Mono::delay(SAMPLE_MS.millis()).await;
+    }
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
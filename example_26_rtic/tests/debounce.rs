@@ -0,0 +1,47 @@
+//! Host tests for the debouncer
+
+use example_26_rtic::debounce::{Debouncer, SAMPLES};
+
+/// Feed `samples` in, and return what the debouncer reported after each
+fn run(samples: &[bool]) -> Vec<Option<bool>> {
+    let mut debouncer = Debouncer::new();
+    samples.iter().map(|&pressed| debouncer.sample(pressed)).collect()
+}
+
+#[test]
+fn steady_press_settles_after_samples() {
+    let reports = run(&[true; 6]);
+    let first = reports.iter().position(Option::is_some).unwrap();
+    assert_eq!(first, usize::from(SAMPLES) - 1);
+    assert!(reports[first..].iter().all(|&report| report == Some(true)));
+}
+
+#[test]
+fn bounces_restart_the_count() {
+    // Low, high, low, low, low, low: the bounce at the second sample delays the press
+    let reports = run(&[true, false, true, true, true, true]);
+    assert_eq!(reports, [None, None, None, None, None, Some(true)]);
+}
+
+#[test]
+fn a_glitch_settles_as_released() {
+    // One low sample, then the pin stays high: not a press
+    let reports = run(&[true, false, false, false, false]);
+    assert_eq!(reports.last(), Some(&Some(false)));
+    assert!(!reports.contains(&Some(true)));
+}
+
+#[test]
+fn release_after_press() {
+    let mut debouncer = Debouncer::new();
+    let pressed: Vec<_> = (0..SAMPLES).map(|_| debouncer.sample(true)).collect();
+    assert_eq!(pressed.last(), Some(&Some(true)));
+    // Bouncing on the way up, then high for good
+    for sample in [false, true, false] {
+        assert_eq!(debouncer.sample(sample), None);
+    }
+    // The last bounce was already high, so one sample fewer is enough
+    let released: Vec<_> = (1..SAMPLES).map(|_| debouncer.sample(false)).collect();
+    assert_eq!(released.last(), Some(&Some(false)));
+    assert!(released[..released.len() - 1].iter().all(Option::is_none));
+}
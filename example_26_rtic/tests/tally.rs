@@ -0,0 +1,51 @@
+//! Host tests for the press counts and their image
+
+use example_26_rtic::tally::{Button, Presses, BAR, DIVIDER};
+
+/// The bar heights in an image: lit rows in columns 0 and 3, checking the columns beside them match
+fn bars(image: &[[u8; 5]; 5]) -> (usize, usize) {
+    for row in image {
+        assert_eq!(row[0], row[1]);
+        assert_eq!(row[3], row[4]);
+        assert_eq!(row[2], DIVIDER);
+    }
+    let height = |col: usize| image.iter().filter(|row| row[col] == BAR).count();
+    (height(0), height(3))
+}
+
+#[test]
+fn counts_each_button() {
+    let mut presses = Presses::new();
+    assert_eq!(presses.press(Button::A), 1);
+    assert_eq!(presses.press(Button::A), 2);
+    assert_eq!(presses.press(Button::B), 1);
+    assert_eq!(presses, Presses { a: 2, b: 1 });
+}
+
+#[test]
+fn empty_image_has_only_the_divider() {
+    let image = Presses::new().image();
+    assert_eq!(bars(&image), (0, 0));
+    assert!(image.iter().all(|row| *row == [0, 0, DIVIDER, 0, 0]));
+}
+
+#[test]
+fn bars_grow_from_the_bottom() {
+    let image = Presses { a: 2, b: 4 }.image();
+    assert_eq!(bars(&image), (2, 4));
+    #[rustfmt::skip]
+    let expected = [
+        [0,   0,   DIVIDER, 0,   0],
+        [0,   0,   DIVIDER, BAR, BAR],
+        [0,   0,   DIVIDER, BAR, BAR],
+        [BAR, BAR, DIVIDER, BAR, BAR],
+        [BAR, BAR, DIVIDER, BAR, BAR],
+    ];
+    assert_eq!(image, expected);
+}
+
+#[test]
+fn bars_start_again_after_five() {
+    let heights: Vec<_> = (0..13).map(|a| bars(&Presses { a, b: 0 }.image()).0).collect();
+    assert_eq!(heights, [0, 1, 2, 3, 4, 5, 0, 1, 2, 3, 4, 5, 0]);
+}
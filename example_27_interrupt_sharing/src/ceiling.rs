@@ -0,0 +1,129 @@
+//! [`CeilingLock`]: data shared between interrupts of different priorities, locked by raising BASEPRI instead of
+//! disabling every interrupt.
+//!
+//! A critical section ([`crate::shared::Shared`]) blocks all interrupts, even those that never touch the data.
+//! The priority ceiling protocol only blocks the ones that might: every user of the data runs at or below some
+//! priority, the *ceiling*, and while one of them holds the lock, BASEPRI masks every interrupt up to that
+//! ceiling. Anything more urgent still runs straight away.
+//!
+//! ```text
+//! priority 3   RADIO, say          ── not a user: runs even while the lock is held
+//! priority 2   GPIOTE              ── user ┐
+//! priority 1   TIMER0              ── user ├ ceiling 2: while any of them holds the lock, priorities 1-2 wait
+//! priority 0   main                ── user ┘
+//! ```
+//!
+//! Priorities here are *logical*, as in RTIC: 0 is `main`, 1 to [`MAX_PRIORITY`] are interrupts, higher is more
+//! urgent. The NVIC counts the other way round and only uses the top [`PRIORITY_BITS`] bits of each priority
+//! byte; [`hw_priority`] converts. BASEPRI cannot mask the most urgent hardware level, 0, so a lock whose ceiling
+//! is [`MAX_PRIORITY`] falls back to a critical section.
+//!
+//! The protocol only holds if the ceiling really is at least the priority of every user. That cannot be checked
+//! here, so [`CeilingLock::new`] is `unsafe`; RTIC works the ceilings out from its task list at compile time.
+
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Priority bits the nRF52833's NVIC implements: 8 levels
+pub const PRIORITY_BITS: u8 = 3;
+
+/// The most urgent logical priority
+pub const MAX_PRIORITY: u8 = 1 << PRIORITY_BITS;
+
+/// The NVIC priority byte for logical priority `priority`, 1 to [`MAX_PRIORITY`]: 1 is the least urgent
+/// hardware level, 0xE0, and [`MAX_PRIORITY`] the most urgent, 0
+pub const fn hw_priority(priority: u8) -> u8 {
+    assert!(
+        priority >= 1 && priority <= MAX_PRIORITY,
+        "logical priorities are 1 to MAX_PRIORITY"
+    );
+    (MAX_PRIORITY - priority) << (8 - PRIORITY_BITS)
+}
+
+/// How a lock with a given ceiling keeps the other users out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mask {
+    /// Raise BASEPRI to this value: every interrupt at or below the ceiling waits
+    Basepri(u8),
+    /// The ceiling is the most urgent level, which BASEPRI cannot mask: disable interrupts
+    All,
+}
+
+/// How to lock with `ceiling`, 1 to [`MAX_PRIORITY`]
+pub const fn mask_for(ceiling: u8) -> Mask {
+    if ceiling == MAX_PRIORITY {
+        Mask::All
+    } else {
+        Mask::Basepri(hw_priority(ceiling))
+    }
+}
+
+/// Data shared by tasks at priorities up to `ceiling`, see the module documentation
+pub struct CeilingLock<T> {
+    value: UnsafeCell<T>,
+    mask: Mask,
+    locked: AtomicBool,
+}
+
+// Every user runs at or below the ceiling, and the lock masks them all: only one of them can be in `lock`
+unsafe impl<T: Send> Sync for CeilingLock<T> {}
+
+impl<T> CeilingLock<T> {
+    /// `value`, locked at `ceiling`, 1 to [`MAX_PRIORITY`]
+    ///
+    /// # Safety
+    ///
+    /// No code running above `ceiling` may use the lock: it would not be masked, and could get at the value
+    /// while another user holds it.
+    pub const unsafe fn new(value: T, ceiling: u8) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            mask: mask_for(ceiling),
+            locked: AtomicBool::new(false),
+        }
+    }
+
+    /// How this lock masks interrupts
+    pub fn mask(&self) -> Mask {
+        self.mask
+    }
+
+    /// Run `f` on the value, with every other user masked
+    ///
+    /// Panics if `f` locks this same lock again, which would be a second `&mut` to the value.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        mask(self.mask, || {
+            // Any other user is masked, so this can only be set by a `lock` further up this same call stack
+            assert!(!self.locked.swap(true, Ordering::Acquire), "CeilingLock locked twice");
+            let result = f(unsafe { &mut *self.value.get() });
+            self.locked.store(false, Ordering::Release);
+            result
+        })
+    }
+}
+
+/// Run `f` with the interrupts `how` says masked, then put things back as they were
+#[cfg(target_os = "none")]
+fn mask<R>(how: Mask, f: impl FnOnce() -> R) -> R {
+    use cortex_m::register::{basepri, basepri_max};
+
+    match how {
+        Mask::Basepri(level) => {
+            let old = basepri::read();
+            // BASEPRI_MAX only ever raises the mask: inside a lock with a higher ceiling, this changes nothing
+            basepri_max::write(level);
+            let result = f();
+            unsafe { basepri::write(old) };
+            result
+        }
+        Mask::All => cortex_m::interrupt::free(|_| f()),
+    }
+}
+
+/// On the PC there are no interrupts to mask: the lock is only checked for being taken twice
+#[cfg(not(target_os = "none"))]
+fn mask<R>(_how: Mask, f: impl FnOnce() -> R) -> R {
+    f()
+}
@@ -0,0 +1,16 @@
+#![no_std]
+
+//! Ways to share state between `main` and interrupt handlers, beyond a `static AtomicBool`.
+//!
+//! - [`shared`] moves a value, a peripheral or a pin say, into a `static` that interrupt handlers can use, behind
+//!   a critical section
+//! - [`spsc`] is a lock-free queue from one producer to one consumer, for events or data flowing one way
+//! - [`ceiling`] locks data by raising BASEPRI to the highest priority that uses it, so more urgent interrupts
+//!   keep running
+//!
+//! All three are plain Rust and are tested on the PC, see `tests/`. On the PC there are no interrupts: the
+//! critical section is `critical-section`'s global lock, and [`ceiling`] masks nothing.
+
+pub mod ceiling;
+pub mod shared;
+pub mod spsc;
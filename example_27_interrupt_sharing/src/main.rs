@@ -0,0 +1,196 @@
+#![cfg_attr(target_os = "none", no_main)]
+#![cfg_attr(target_os = "none", no_std)]
+
+#[cfg(target_os = "none")]
+use {
+    cortex_m_rt::entry,
+    embedded_hal::digital::{OutputPin, StatefulOutputPin},
+    example_27_interrupt_sharing::{
+        ceiling::{hw_priority, CeilingLock},
+        shared::Shared,
+        spsc::{Producer, Queue},
+    },
+    microbit::hal::{
+        gpio::{Level, Output, Pin, PushPull},
+        gpiote::Gpiote,
+        pac::{self, interrupt, TIMER0},
+        timer::Periodic,
+        Timer,
+    },
+    panic_rtt_target as _,
+    rtt_target::{rprintln, rtt_init_print},
+};
+
+/// Interrupt priorities: a button press may interrupt the heartbeat
+#[cfg(target_os = "none")]
+const GPIOTE_PRIORITY: u8 = 2;
+#[cfg(target_os = "none")]
+const TIMER0_PRIORITY: u8 = 1;
+
+/// The heartbeat interrupt comes twice a second (TIMER0 counts at 1 MHz)
+#[cfg(target_os = "none")]
+const HEARTBEAT_US: u32 = 500_000;
+
+/// Button events waiting for `main` to print them
+#[cfg(target_os = "none")]
+const EVENTS: usize = 16;
+
+#[cfg(target_os = "none")]
+#[derive(Clone, Copy)]
+enum Button {
+    A,
+    B,
+}
+
+/// What the GPIOTE interrupt tells `main`
+#[cfg(target_os = "none")]
+struct Event {
+    button: Button,
+    /// Heartbeats before the press
+    beats: u32,
+}
+
+/// Counters that all three priorities update or read
+#[cfg(target_os = "none")]
+struct Stats {
+    presses: [u32; 2],
+    beats: u32,
+    /// Events the queue had no room for
+    dropped: u32,
+}
+
+/// The heartbeat LED and its timer, moved in by `main` and used by the TIMER0 interrupt
+#[cfg(target_os = "none")]
+static LED: Shared<Pin<Output<PushPull>>> = Shared::new();
+#[cfg(target_os = "none")]
+static HEARTBEAT: Shared<Timer<TIMER0, Periodic>> = Shared::new();
+
+/// GPIOTE, and the producer end of the event queue, on their way from `main` to the GPIOTE interrupt
+#[cfg(target_os = "none")]
+static BUTTONS: Shared<(Gpiote, Producer<'static, Event, EVENTS>)> = Shared::new();
+
+/// Used by `main` (priority 0), TIMER0 (1) and GPIOTE (2), so the ceiling is 2: a lock masks TIMER0 and GPIOTE,
+/// and leaves anything more urgent alone
+#[cfg(target_os = "none")]
+static STATS: CeilingLock<Stats> = unsafe {
+    CeilingLock::new(
+        Stats {
+            presses: [0; 2],
+            beats: 0,
+            dropped: 0,
+        },
+        GPIOTE_PRIORITY,
+    )
+};
+
+#[cfg(target_os = "none")]
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let board = microbit::Board::take().unwrap();
+
+    // The queue has to outlive both ends: the producer goes into a static
+    let queue: &'static mut Queue<Event, EVENTS> = cortex_m::singleton!(: Queue<Event, EVENTS> = Queue::new()).unwrap();
+    let (producer, mut consumer) = queue.split();
+
+    // Heartbeat: top-left LED, toggled by TIMER0
+    let _col1 = board.display_pins.col1.into_push_pull_output(Level::Low);
+    LED.put(board.display_pins.row1.into_push_pull_output(Level::Low).degrade());
+    let mut heartbeat = Timer::periodic(board.TIMER0);
+    heartbeat.enable_interrupt();
+    heartbeat.start(HEARTBEAT_US);
+    HEARTBEAT.put(heartbeat);
+
+    // Buttons: GPIOTE channel 0 for A, 1 for B, on the press
+    let button_a = board.buttons.button_a.into_pullup_input().degrade();
+    let button_b = board.buttons.button_b.into_pullup_input().degrade();
+    let gpiote = Gpiote::new(board.GPIOTE);
+    gpiote.channel0().input_pin(&button_a).hi_to_lo().enable_interrupt();
+    gpiote.channel1().input_pin(&button_b).hi_to_lo().enable_interrupt();
+    BUTTONS.put((gpiote, producer));
+
+    let mut nvic = board.NVIC;
+    unsafe {
+        nvic.set_priority(pac::Interrupt::GPIOTE, hw_priority(GPIOTE_PRIORITY));
+        nvic.set_priority(pac::Interrupt::TIMER0, hw_priority(TIMER0_PRIORITY));
+        pac::NVIC::unmask(pac::Interrupt::GPIOTE);
+        pac::NVIC::unmask(pac::Interrupt::TIMER0);
+    }
+
+    rprintln!("Press A or B");
+    loop {
+        // The queue needs no lock: `main` is the only consumer
+        while let Some(event) = consumer.dequeue() {
+            let (name, index) = match event.button {
+                Button::A => ("A", 0),
+                Button::B => ("B", 1),
+            };
+            let (presses, dropped) = STATS.lock(|stats| (stats.presses[index], stats.dropped));
+            rprintln!(
+                "{} pressed after {} beats: {} presses, {} events dropped",
+                name,
+                event.beats,
+                presses,
+                dropped
+            );
+        }
+        // An event queued between the check above and here waits for the next interrupt, at most one heartbeat
+        cortex_m::asm::wfi();
+    }
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn TIMER0() {
+    // Both in one critical section
+    critical_section::with(|cs| {
+        HEARTBEAT.with_cs(cs, |timer| timer.reset_event());
+        LED.with_cs(cs, |led| {
+            if led.is_set_high().unwrap() {
+                led.set_low().unwrap();
+            } else {
+                led.set_high().unwrap();
+            }
+        });
+    });
+    // Masks GPIOTE for these few instructions
+    STATS.lock(|stats| stats.beats += 1);
+}
+
+#[cfg(target_os = "none")]
+#[interrupt]
+fn GPIOTE() {
+    // Moved out of the `Shared` on the first interrupt: from then on this handler owns them, and needs no
+    // critical section to use them
+    static mut OWNED: Option<(Gpiote, Producer<'static, Event, EVENTS>)> = None;
+    if OWNED.is_none() {
+        *OWNED = BUTTONS.take();
+    }
+    let Some((gpiote, events)) = OWNED.as_mut() else {
+        return;
+    };
+
+    let pressed = [
+        gpiote.channel0().is_event_triggered(),
+        gpiote.channel1().is_event_triggered(),
+    ];
+    gpiote.reset_events();
+    // No debouncing here, so a bouncing button may count twice: see example 26
+    for (index, button) in [Button::A, Button::B].into_iter().enumerate() {
+        if !pressed[index] {
+            continue;
+        }
+        // This is the ceiling priority: nothing else that uses STATS can run meanwhile
+        let beats = STATS.lock(|stats| {
+            stats.presses[index] += 1;
+            stats.beats
+        });
+        if events.enqueue(Event { button, beats }).is_err() {
+            STATS.lock(|stats| stats.dropped += 1);
+        }
+    }
+}
+
+// `cargo test-host` also builds this binary for the PC, where there is no micro:bit to talk to
+#[cfg(not(target_os = "none"))]
+fn main() {}
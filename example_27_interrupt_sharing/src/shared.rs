@@ -0,0 +1,71 @@
+//! [`Shared`]: a value that `main` moves into an interrupt handler, or shares with it, behind a critical section.
+//!
+//! This is the `Mutex<RefCell<Option<T>>>` pattern from the earlier examples, wrapped so the three layers do not
+//! have to be spelled out at every use:
+//!
+//! - the `Option`, because a `static` has to exist before `main` has the peripheral or pin to put in it
+//! - the `RefCell`, to get `&mut T` out of a `static`
+//! - the critical section `Mutex`, so the interrupt handler cannot run while `main` holds that `&mut T`
+//!
+//! ```ignore
+//! static LED: Shared<Pin<Output<PushPull>>> = Shared::new();
+//!
+//! LED.put(led);                      // in main: the pin moves into the static
+//! LED.with(|led| led.toggle());      // in the interrupt handler: None until it is there
+//! ```
+//!
+//! Every access disables interrupts for as long as the closure runs, so keep the closures short. For data that
+//! flows one way, the lock-free [`crate::spsc`] queue avoids that; to block only some interrupts, see
+//! [`crate::ceiling`].
+
+use core::cell::RefCell;
+
+use critical_section::{CriticalSection, Mutex};
+
+/// A value shared with interrupt handlers, see the module documentation
+pub struct Shared<T> {
+    inner: Mutex<RefCell<Option<T>>>,
+}
+
+impl<T> Shared<T> {
+    /// Empty, until [`put`](Self::put)
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Move `value` in, and return what was there before
+    pub fn put(&self, value: T) -> Option<T> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).replace(value))
+    }
+
+    /// Move the value out again, leaving it empty
+    pub fn take(&self) -> Option<T> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).take())
+    }
+
+    /// Run `f` on the value with interrupts disabled. `None` if there is no value yet.
+    ///
+    /// Panics if `f` uses this same `Shared` again, which would be a second `&mut` to the value.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        critical_section::with(|cs| self.with_cs(cs, f))
+    }
+
+    /// [`with`](Self::with), inside a critical section the caller already has: for using several `Shared`
+    /// values together, without leaving the critical section in between
+    pub fn with_cs<R>(&self, cs: CriticalSection<'_>, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.inner.borrow_ref_mut(cs).as_mut().map(f)
+    }
+
+    /// There is a value in it
+    pub fn is_some(&self) -> bool {
+        critical_section::with(|cs| self.inner.borrow_ref(cs).is_some())
+    }
+}
+
+impl<T> Default for Shared<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
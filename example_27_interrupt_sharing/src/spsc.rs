@@ -0,0 +1,171 @@
+//! A bounded single-producer single-consumer queue, with no locks at all.
+//!
+//! For data that flows one way, say button events from an interrupt handler to `main`, a queue is better than a
+//! [`crate::shared::Shared`] buffer: neither side ever disables interrupts, and neither has to wait for the
+//! other. [`Queue::split`] hands out one [`Producer`] and one [`Consumer`], and since there is only one of each,
+//! each index has only one writer:
+//!
+//! ```text
+//!            head (Consumer)         tail (Producer)
+//!                 ▼                       ▼
+//! slots   [ .   | e1  | e2  | e3  | .   | .   ]      len = tail - head, between 0 and N
+//! ```
+//!
+//! The producer writes a slot, then publishes it by moving `tail` on with `Release` ordering; the consumer
+//! reads `tail` with `Acquire` ordering, so it always sees the slot written. The same goes the other way for
+//! freed slots and `head`. Both indices count round from 0 to 2N - 1, so that a full queue (`tail` N ahead)
+//! and an empty one (`tail` equal to `head`) look different; the slot is the index modulo `N`.
+
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Room for `N` values of `T`
+pub struct Queue<T, const N: usize> {
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+// The producer and consumer may be in different contexts, an interrupt handler and `main`, each with its own
+// half. The indices keep them from ever touching the same slot at the same time.
+unsafe impl<T: Send, const N: usize> Sync for Queue<T, N> {}
+
+impl<T, const N: usize> Queue<T, N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "a queue needs room for at least one value");
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+        }
+    }
+
+    /// The two ends. Borrowing the queue mutably makes sure there is only ever one of each.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        let queue: &Self = self;
+        (Producer { queue }, Consumer { queue })
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Values waiting
+    pub fn len(&self) -> usize {
+        distance(self.head.load(Ordering::Acquire), self.tail.load(Ordering::Acquire), N)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        self.slots[index % N].get()
+    }
+}
+
+/// How far `tail` is ahead of `head`, both counting round 0 to 2N - 1
+fn distance(head: usize, tail: usize, n: usize) -> usize {
+    if tail >= head {
+        tail - head
+    } else {
+        tail + 2 * n - head
+    }
+}
+
+/// The index after `index`, counting round 0 to 2N - 1
+fn next(index: usize, n: usize) -> usize {
+    if index + 1 == 2 * n {
+        0
+    } else {
+        index + 1
+    }
+}
+
+impl<T, const N: usize> Default for Queue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Queue<T, N> {
+    fn drop(&mut self) {
+        // Both halves are gone, so nobody else is looking at the slots
+        let (head, tail) = (*self.head.get_mut(), *self.tail.get_mut());
+        let mut index = head;
+        while index != tail {
+            unsafe { (*self.slot(index)).assume_init_drop() };
+            index = next(index, N);
+        }
+    }
+}
+
+/// The end values go into
+pub struct Producer<'q, T, const N: usize> {
+    queue: &'q Queue<T, N>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Add `value` at the back. If the queue is full, `value` comes back.
+    pub fn enqueue(&mut self, value: T) -> Result<(), T> {
+        // Only the producer writes `tail`, so Relaxed is enough for its own index
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        let head = self.queue.head.load(Ordering::Acquire);
+        if distance(head, tail, N) == N {
+            return Err(value);
+        }
+        // The consumer has finished with this slot: `head` has moved past it
+        unsafe { (*self.queue.slot(tail)).write(value) };
+        self.queue.tail.store(next(tail, N), Ordering::Release);
+        Ok(())
+    }
+
+    /// There is room for at least one more value
+    pub fn ready(&self) -> bool {
+        !self.queue.is_full()
+    }
+}
+
+/// The end values come out of
+pub struct Consumer<'q, T, const N: usize> {
+    queue: &'q Queue<T, N>,
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Take the value at the front, if there is one
+    pub fn dequeue(&mut self) -> Option<T> {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        let tail = self.queue.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        // The producer wrote this slot before moving `tail` past it
+        let value = unsafe { (*self.queue.slot(head)).assume_init_read() };
+        self.queue.head.store(next(head, N), Ordering::Release);
+        Some(value)
+    }
+
+    /// The value at the front, without taking it
+    pub fn peek(&self) -> Option<&T> {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        let tail = self.queue.tail.load(Ordering::Acquire);
+        // The slot stays put until `dequeue`, which needs `&mut self`
+        (head != tail).then(|| unsafe { (*self.queue.slot(head)).assume_init_ref() })
+    }
+
+    /// Values waiting
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
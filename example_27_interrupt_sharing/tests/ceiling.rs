@@ -0,0 +1,54 @@
+//! Host tests for the priority arithmetic, and for the lock catching itself being taken twice
+
+use example_27_interrupt_sharing::ceiling::{hw_priority, mask_for, CeilingLock, Mask, MAX_PRIORITY};
+
+#[test]
+fn logical_to_hardware_priority() {
+    assert_eq!(hw_priority(1), 0xE0);
+    assert_eq!(hw_priority(2), 0xC0);
+    assert_eq!(hw_priority(7), 0x20);
+    assert_eq!(hw_priority(MAX_PRIORITY), 0);
+}
+
+#[test]
+fn more_urgent_is_a_lower_hardware_number() {
+    for priority in 1..MAX_PRIORITY {
+        assert!(hw_priority(priority + 1) < hw_priority(priority));
+    }
+}
+
+#[test]
+#[should_panic]
+fn priority_zero_is_main() {
+    hw_priority(0);
+}
+
+#[test]
+fn masks() {
+    assert_eq!(mask_for(1), Mask::Basepri(0xE0));
+    assert_eq!(mask_for(2), Mask::Basepri(0xC0));
+    // BASEPRI = 0 would mean no masking at all
+    assert_eq!(mask_for(MAX_PRIORITY), Mask::All);
+}
+
+#[test]
+fn lock_gives_the_value() {
+    let counter = unsafe { CeilingLock::new(0u32, 2) };
+    assert_eq!(counter.mask(), Mask::Basepri(0xC0));
+    counter.lock(|count| *count += 5);
+    assert_eq!(counter.lock(|count| *count), 5);
+}
+
+#[test]
+fn different_locks_nest() {
+    let outer = unsafe { CeilingLock::new(1, 2) };
+    let inner = unsafe { CeilingLock::new(2, 3) };
+    assert_eq!(outer.lock(|a| inner.lock(|b| *a + *b)), 3);
+}
+
+#[test]
+#[should_panic(expected = "CeilingLock locked twice")]
+fn same_lock_twice_panics() {
+    let counter = unsafe { CeilingLock::new(0u32, 2) };
+    counter.lock(|_| counter.lock(|_| ()));
+}
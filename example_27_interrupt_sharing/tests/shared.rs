@@ -0,0 +1,79 @@
+//! Host tests for the critical-section cell. critical-section's `std` feature makes the critical section a
+//! process-wide lock, so the behaviour is the same as on the micro:bit.
+
+use example_27_interrupt_sharing::shared::Shared;
+
+#[test]
+fn empty_until_put() {
+    static CELL: Shared<u32> = Shared::new();
+    assert!(!CELL.is_some());
+    assert_eq!(CELL.with(|value| *value), None);
+
+    assert_eq!(CELL.put(7), None);
+    assert!(CELL.is_some());
+    assert_eq!(CELL.with(|value| *value), Some(7));
+}
+
+#[test]
+fn put_returns_the_old_value() {
+    let cell = Shared::new();
+    cell.put("first");
+    assert_eq!(cell.put("second"), Some("first"));
+    assert_eq!(cell.with(|value| *value), Some("second"));
+}
+
+#[test]
+fn take_empties_it() {
+    let cell = Shared::new();
+    cell.put(vec![1, 2, 3]);
+    assert_eq!(cell.take(), Some(vec![1, 2, 3]));
+    assert!(!cell.is_some());
+    assert_eq!(cell.take(), None);
+}
+
+#[test]
+fn with_changes_the_value() {
+    let cell = Shared::new();
+    cell.put(0u32);
+    for _ in 0..3 {
+        cell.with(|count| *count += 1);
+    }
+    assert_eq!(cell.take(), Some(3));
+}
+
+#[test]
+fn several_in_one_critical_section() {
+    let (led, count) = (Shared::new(), Shared::new());
+    led.put(false);
+    count.put(0u32);
+    critical_section::with(|cs| {
+        led.with_cs(cs, |on| *on = !*on);
+        count.with_cs(cs, |count| *count += 1);
+    });
+    assert_eq!(led.take(), Some(true));
+    assert_eq!(count.take(), Some(1));
+}
+
+#[test]
+fn shared_between_threads() {
+    static COUNT: Shared<u32> = Shared::new();
+    COUNT.put(0);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    COUNT.with(|count| *count += 1);
+                }
+            });
+        }
+    });
+    assert_eq!(COUNT.take(), Some(4000));
+}
+
+#[test]
+#[should_panic]
+fn nested_use_panics() {
+    let cell = Shared::new();
+    cell.put(1);
+    cell.with(|_| cell.with(|_| ()));
+}
@@ -0,0 +1,121 @@
+//! Host tests for the single-producer single-consumer queue, including a producer and a consumer on different
+//! threads standing in for an interrupt handler and `main`
+
+use std::rc::Rc;
+
+use example_27_interrupt_sharing::spsc::Queue;
+
+#[test]
+fn first_in_first_out() {
+    let mut queue: Queue<u32, 4> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    assert_eq!(consumer.dequeue(), None);
+    for value in 1..=3 {
+        producer.enqueue(value).unwrap();
+    }
+    assert_eq!(consumer.dequeue(), Some(1));
+    assert_eq!(consumer.dequeue(), Some(2));
+    assert_eq!(consumer.dequeue(), Some(3));
+    assert_eq!(consumer.dequeue(), None);
+}
+
+#[test]
+fn full_queue_gives_the_value_back() {
+    let mut queue: Queue<char, 2> = Queue::new();
+    assert_eq!(queue.capacity(), 2);
+    let (mut producer, mut consumer) = queue.split();
+    producer.enqueue('a').unwrap();
+    assert!(producer.ready());
+    producer.enqueue('b').unwrap();
+    assert!(!producer.ready());
+    assert_eq!(producer.enqueue('c'), Err('c'));
+
+    assert_eq!(consumer.dequeue(), Some('a'));
+    assert!(producer.ready());
+    producer.enqueue('c').unwrap();
+    assert_eq!(consumer.dequeue(), Some('b'));
+    assert_eq!(consumer.dequeue(), Some('c'));
+}
+
+#[test]
+fn len_and_peek() {
+    let mut queue: Queue<u8, 3> = Queue::new();
+    {
+        let (mut producer, mut consumer) = queue.split();
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.peek(), None);
+        producer.enqueue(10).unwrap();
+        producer.enqueue(20).unwrap();
+        assert_eq!(consumer.len(), 2);
+        assert_eq!(consumer.peek(), Some(&10));
+        // Peeking does not take it
+        assert_eq!(consumer.peek(), Some(&10));
+        assert_eq!(consumer.dequeue(), Some(10));
+        assert_eq!(consumer.len(), 1);
+    }
+    assert_eq!(queue.len(), 1);
+    assert!(!queue.is_full());
+}
+
+/// N = 3 is not a power of two, so the indices wrap at 6 rather than at `usize::MAX`: go round many times
+#[test]
+fn wraps_round() {
+    let mut queue: Queue<usize, 3> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    let (mut next_in, mut next_out) = (0, 0);
+    for round in 0..1000 {
+        // Fill it, then take out one, two or three values, so the ends move round at different rates
+        while producer.enqueue(next_in).is_ok() {
+            next_in += 1;
+        }
+        assert_eq!(consumer.len(), 3);
+        for _ in 0..round % 3 + 1 {
+            assert_eq!(consumer.dequeue(), Some(next_out));
+            next_out += 1;
+        }
+    }
+}
+
+#[test]
+fn drops_what_is_left() {
+    let value = Rc::new(());
+    {
+        let mut queue: Queue<Rc<()>, 4> = Queue::new();
+        let (mut producer, mut consumer) = queue.split();
+        for _ in 0..3 {
+            producer.enqueue(value.clone()).unwrap();
+        }
+        drop(consumer.dequeue());
+        assert_eq!(Rc::strong_count(&value), 3);
+    }
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn producer_and_consumer_on_different_threads() {
+    const VALUES: u32 = 100_000;
+    let mut queue: Queue<u32, 5> = Queue::new();
+    let (mut producer, mut consumer) = queue.split();
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for value in 0..VALUES {
+                let mut value = value;
+                while let Err(back) = producer.enqueue(value) {
+                    value = back;
+                    std::thread::yield_now();
+                }
+            }
+        });
+        let mut expected = 0;
+        while expected < VALUES {
+            match consumer.dequeue() {
+                Some(value) => {
+                    assert_eq!(value, expected);
+                    expected += 1;
+                }
+                None => std::thread::yield_now(),
+            }
+        }
+    });
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,200 @@
+//! The nRF52833's memory map, as far as the examples need it.
+//!
+//! | Addresses | What | Access |
+//! |-----------|------|--------|
+//! | `0x0000_0000` - `0x0007_FFFF` | Flash, 512 KiB | read only, any width and alignment |
+//! | `0x2000_0000` - `0x2001_FFFF` | RAM, 128 KiB | read/write, any width and alignment |
+//! | `0x4000_8000`, `0x4000_9000`, `0x4000_A000`, `0x4001_A000`, `0x4001_B000` | TIMER0 to TIMER4 | aligned words |
+//! | `0x5000_0000` | GPIO P0 (registers from `+0x504`) | aligned words |
+//! | `0x5000_0300` | GPIO P1 (registers from `+0x504`) | aligned words |
+//! | `0xE000_E000` - `0xE000_EFFF` | System control space: NVIC, SCB, SysTick | read/write, as plain memory |
+//!
+//! Anything else is a [`BusError`], as is writing to flash, which on the chip needs the NVMC. The system control
+//! space stores what is written to it and does nothing else, which is enough for a reset handler that turns the
+//! FPU on through CPACR, or a program that sets interrupt priorities.
+//!
+//! The bus also keeps the time: [`Bus::tick`] moves it on after each instruction, and the peripherals see it.
+
+use crate::{gpio::Gpio, timer::Timer};
+
+pub const FLASH_START: u32 = 0x0000_0000;
+pub const FLASH_SIZE: u32 = 512 * 1024;
+pub const RAM_START: u32 = 0x2000_0000;
+pub const RAM_SIZE: u32 = 128 * 1024;
+
+pub const P0_BASE: u32 = 0x5000_0000;
+pub const P1_BASE: u32 = 0x5000_0300;
+pub const TIMER_BASES: [u32; 5] = [0x4000_8000, 0x4000_9000, 0x4000_A000, 0x4001_A000, 0x4001_B000];
+
+const SCS_START: u32 = 0xE000_E000;
+const SCS_SIZE: u32 = 0x1000;
+
+/// GPIO registers are from +0x504 to +0x77F: this range covers P0's
+const GPIO_SPAN: u32 = 0x300;
+const GPIO_FIRST_REGISTER: u32 = 0x500;
+/// Register space of one peripheral
+const PERIPHERAL_SIZE: u32 = 0x1000;
+
+/// An access to an address with nothing behind it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusError {
+    pub address: u32,
+}
+
+/// Memory and peripherals
+#[derive(Debug, Clone)]
+pub struct Bus {
+    flash: Vec<u8>,
+    ram: Vec<u8>,
+    scs: Vec<u8>,
+    pub p0: Gpio,
+    pub p1: Gpio,
+    pub timers: [Timer; 5],
+    /// CPU cycles since reset
+    now: u64,
+}
+
+impl Bus {
+    /// Erased flash (all 0xFF), zeroed RAM, peripherals as after reset
+    pub fn new() -> Self {
+        Self {
+            flash: vec![0xFF; FLASH_SIZE as usize],
+            ram: vec![0; RAM_SIZE as usize],
+            scs: vec![0; SCS_SIZE as usize],
+            p0: Gpio::new(),
+            p1: Gpio::new(),
+            timers: core::array::from_fn(|_| Timer::new()),
+            now: 0,
+        }
+    }
+
+    /// Put `data` in flash or RAM at `address`, as a debug probe would
+    pub fn load(&mut self, address: u32, data: &[u8]) -> Result<(), BusError> {
+        let size = u32::try_from(data.len()).map_err(|_| BusError { address })?;
+        let (memory, offset) = match region(address, size) {
+            Some(Region::Flash(offset)) => (&mut self.flash, offset),
+            Some(Region::Ram(offset)) => (&mut self.ram, offset),
+            _ => return Err(BusError { address }),
+        };
+        memory[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Read `size` bytes (1, 2 or 4) at `address`, little-endian
+    pub fn read(&mut self, address: u32, size: u32) -> Result<u32, BusError> {
+        let error = BusError { address };
+        let (memory, offset) = match region(address, size) {
+            Some(Region::Flash(offset)) => (&self.flash, offset),
+            Some(Region::Ram(offset)) => (&self.ram, offset),
+            Some(Region::Scs(offset)) => (&self.scs, offset),
+            Some(Region::Peripheral) if size == 4 && address.is_multiple_of(4) => {
+                return self.read_register(address).ok_or(error);
+            }
+            _ => return Err(error),
+        };
+        let bytes = &memory[offset..offset + size as usize];
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | u32::from(byte)))
+    }
+
+    /// Write the low `size` bytes (1, 2 or 4) of `value` at `address`, little-endian
+    pub fn write(&mut self, address: u32, size: u32, value: u32) -> Result<(), BusError> {
+        let error = BusError { address };
+        let (memory, offset) = match region(address, size) {
+            Some(Region::Ram(offset)) => (&mut self.ram, offset),
+            Some(Region::Scs(offset)) => (&mut self.scs, offset),
+            Some(Region::Peripheral) if size == 4 && address.is_multiple_of(4) => {
+                return self.write_register(address, value).ok_or(error);
+            }
+            _ => return Err(error),
+        };
+        let bytes = &mut memory[offset..offset + size as usize];
+        bytes.copy_from_slice(&value.to_le_bytes()[..size as usize]);
+        Ok(())
+    }
+
+    /// Let `cycles` CPU cycles pass
+    pub fn tick(&mut self, cycles: u32) {
+        self.now += u64::from(cycles);
+        for timer in &mut self.timers {
+            timer.tick(cycles);
+        }
+    }
+
+    /// CPU cycles since reset
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    fn read_register(&self, address: u32) -> Option<u32> {
+        match peripheral(address)? {
+            (Peripheral::P0, offset) => self.p0.read(offset),
+            (Peripheral::P1, offset) => self.p1.read(offset),
+            (Peripheral::Timer(index), offset) => self.timers[index].read(offset),
+        }
+    }
+
+    fn write_register(&mut self, address: u32, value: u32) -> Option<()> {
+        let now = self.now;
+        match peripheral(address)? {
+            (Peripheral::P0, offset) => self.p0.write(offset, value, now),
+            (Peripheral::P1, offset) => self.p1.write(offset, value, now),
+            (Peripheral::Timer(index), offset) => self.timers[index].write(offset, value),
+        }
+    }
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What is at an address, with the offset into it for memory
+enum Region {
+    Flash(usize),
+    Ram(usize),
+    Scs(usize),
+    Peripheral,
+}
+
+/// Where the `size` bytes at `address` are. Memory accesses must not run off the end.
+fn region(address: u32, size: u32) -> Option<Region> {
+    let inside = |start: u32, length: u32| {
+        let offset = address.checked_sub(start)?;
+        (offset.checked_add(size)? <= length).then_some(offset as usize)
+    };
+    if let Some(offset) = inside(FLASH_START, FLASH_SIZE) {
+        Some(Region::Flash(offset))
+    } else if let Some(offset) = inside(RAM_START, RAM_SIZE) {
+        Some(Region::Ram(offset))
+    } else if let Some(offset) = inside(SCS_START, SCS_SIZE) {
+        Some(Region::Scs(offset))
+    } else {
+        peripheral(address).map(|_| Region::Peripheral)
+    }
+}
+
+enum Peripheral {
+    P0,
+    P1,
+    Timer(usize),
+}
+
+/// The peripheral at `address`, and the offset into its registers
+fn peripheral(address: u32) -> Option<(Peripheral, u32)> {
+    let p0 = address.wrapping_sub(P0_BASE);
+    let p1 = address.wrapping_sub(P1_BASE);
+    if (GPIO_FIRST_REGISTER..GPIO_FIRST_REGISTER + GPIO_SPAN).contains(&p0) {
+        return Some((Peripheral::P0, p0));
+    }
+    if (GPIO_FIRST_REGISTER..GPIO_FIRST_REGISTER + GPIO_SPAN).contains(&p1) {
+        return Some((Peripheral::P1, p1));
+    }
+    TIMER_BASES.iter().enumerate().find_map(|(index, &base)| {
+        let offset = address.wrapping_sub(base);
+        (offset < PERIPHERAL_SIZE).then_some((Peripheral::Timer(index), offset))
+    })
+}
@@ -0,0 +1,1342 @@
+//! The Cortex-M4 core: sixteen registers, the APSR flags, and the Thumb-2 instruction set.
+//!
+//! Every integer instruction of ARMv7-M is here, plus the bitfield, saturate and pack instructions of the DSP
+//! extension. The FPU, the SIMD instructions (`SADD16`, `SEL`, ...) and the DSP multiplies (`SMULBB`, ...) are
+//! not: they stop the program with [`Fault::Undefined`], as does every encoding that is undefined on the chip.
+//!
+//! There are no exceptions either: no interrupts, no SysTick, no fault handlers. Where the chip would take a
+//! fault, [`Cpu::step`] returns a [`Fault`] and the program goes no further. `WFI` and `WFE` do nothing, and
+//! the core is always privileged, in Thread mode.
+//!
+//! ## Cycles
+//!
+//! [`Cpu::step`] returns the cycles the instruction took, from the Cortex-M4 Technical Reference Manual, with no
+//! flash wait states and a pipeline refill of 2 cycles:
+//!
+//! | Instructions | Cycles |
+//! |--------------|--------|
+//! | Data processing, `MUL`, `MLA`, long multiplies, bitfields, hints | 1 |
+//! | `LDR`, `STR` and their byte, halfword and exclusive forms | 2 |
+//! | `LDRD`, `STRD` | 3 |
+//! | `LDM`, `STM`, `PUSH`, `POP` | 1 + registers |
+//! | `UDIV`, `SDIV` | 2 to 12, fewer for small quotients |
+//! | Branch taken, `BL`, `BX`, or any other write to the PC | + 2 |
+//!
+//! The real core overlaps some loads and stores with the next instruction, so this runs a little slow.
+
+use std::fmt;
+
+use crate::bus::{Bus, BusError};
+
+/// The nRF52833's core clock
+pub const CPU_HZ: u64 = 64_000_000;
+
+/// Register numbers
+pub const R0: usize = 0;
+pub const R1: usize = 1;
+pub const SP: usize = 13;
+pub const LR: usize = 14;
+pub const PC: usize = 15;
+
+/// Cycles to refill the pipeline after a branch
+const REFILL: u32 = 2;
+
+/// Why the program stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// An undefined instruction, or one this emulator does not implement; `instruction` has the first halfword
+    /// in its top 16 bits for a 32-bit instruction
+    Undefined { pc: u32, instruction: u32 },
+    /// A load, store or fetch at an address with nothing behind it, or a store to flash
+    Bus { pc: u32, address: u32 },
+    /// `LDM`, `STM`, `LDRD`, `STRD` and the exclusive accesses need aligned addresses
+    Unaligned { pc: u32, address: u32 },
+    /// Execution reached `pc` with the Thumb bit clear: a vector or a branch target without its `+ 1`
+    NotThumb { pc: u32 },
+    /// `BKPT`
+    Breakpoint { pc: u32, imm: u8 },
+    /// `SVC`, with no handler to run
+    SupervisorCall { pc: u32, imm: u8 },
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undefined { pc, instruction } => {
+                write!(f, "undefined instruction {:#x} at {:#010x}", instruction, pc)
+            }
+            Self::Bus { pc, address } => write!(f, "bus fault at {:#010x}, accessing {:#010x}", pc, address),
+            Self::Unaligned { pc, address } => {
+                write!(f, "unaligned access at {:#010x}, to {:#010x}", pc, address)
+            }
+            Self::NotThumb { pc } => write!(f, "branch to {:#010x} without the Thumb bit", pc),
+            Self::Breakpoint { pc, imm } => write!(f, "BKPT #{} at {:#010x}", imm, pc),
+            Self::SupervisorCall { pc, imm } => write!(f, "SVC #{} at {:#010x}", imm, pc),
+        }
+    }
+}
+
+/// The APSR condition flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Flags {
+    /// Negative
+    pub n: bool,
+    /// Zero
+    pub z: bool,
+    /// Carry, or not borrow
+    pub c: bool,
+    /// Signed overflow
+    pub v: bool,
+    /// Sticky saturation, set by `SSAT` and `USAT`
+    pub q: bool,
+}
+
+impl Flags {
+    /// As the top five bits of the APSR
+    pub fn apsr(&self) -> u32 {
+        u32::from(self.n) << 31
+            | u32::from(self.z) << 30
+            | u32::from(self.c) << 29
+            | u32::from(self.v) << 28
+            | u32::from(self.q) << 27
+    }
+
+    fn set_apsr(&mut self, value: u32) {
+        self.n = value & (1 << 31) != 0;
+        self.z = value & (1 << 30) != 0;
+        self.c = value & (1 << 29) != 0;
+        self.v = value & (1 << 28) != 0;
+        self.q = value & (1 << 27) != 0;
+    }
+}
+
+/// The core's state
+#[derive(Debug, Clone)]
+pub struct Cpu {
+    /// R0 to R15. R13 is whichever stack pointer is in use; R15 is the address of the next instruction.
+    regs: [u32; 16],
+    pub flags: Flags,
+    /// ITSTATE: the condition in the top four bits and what is left of the mask in the bottom four, 0 outside
+    /// an IT block
+    it: u8,
+    /// EPSR.T
+    thumb: bool,
+    pub primask: bool,
+    pub faultmask: bool,
+    pub basepri: u8,
+    control: u32,
+    /// The stack pointer not in use: PSP while R13 is MSP, MSP while it is PSP
+    other_sp: u32,
+    /// The local exclusive monitor: set by `LDREX`, cleared by `STREX` and `CLREX`
+    exclusive: bool,
+
+    /// The address of the instruction being executed
+    current: u32,
+    /// Where the next instruction is: after this one, or a branch target
+    next: u32,
+    /// Cycles this instruction has taken so far
+    cycles: u32,
+}
+
+impl Cpu {
+    /// A core before reset: everything zero
+    pub fn new() -> Self {
+        Self {
+            regs: [0; 16],
+            flags: Flags::default(),
+            it: 0,
+            thumb: true,
+            primask: false,
+            faultmask: false,
+            basepri: 0,
+            control: 0,
+            other_sp: 0,
+            exclusive: false,
+            current: 0,
+            next: 0,
+            cycles: 0,
+        }
+    }
+
+    /// Reset with the first two vector table entries: the initial stack pointer and the reset handler
+    pub fn reset(&mut self, stack_pointer: u32, reset_vector: u32) {
+        *self = Self::new();
+        self.regs[SP] = stack_pointer & !3;
+        self.regs[LR] = u32::MAX;
+        self.regs[PC] = reset_vector & !1;
+        self.thumb = reset_vector & 1 != 0;
+    }
+
+    /// Register `n`. For the PC this is the address of the next instruction to run.
+    pub fn reg(&self, n: usize) -> u32 {
+        self.regs[n]
+    }
+
+    /// Set register `n`. Setting the PC sets where the next instruction comes from.
+    pub fn set_reg(&mut self, n: usize, value: u32) {
+        self.regs[n] = if n == PC { value & !1 } else { value };
+    }
+
+    pub fn pc(&self) -> u32 {
+        self.regs[PC]
+    }
+
+    /// Run one instruction, and return the cycles it took
+    ///
+    /// On a fault the PC still points at the instruction, but anything it did before faulting, a register
+    /// written back or the first half of an `STRD`, stays done.
+    pub fn step(&mut self, bus: &mut Bus) -> Result<u32, Fault> {
+        let pc = self.regs[PC];
+        self.current = pc;
+        if !self.thumb {
+            return Err(Fault::NotThumb { pc });
+        }
+        let first = self.fetch(bus, pc)?;
+        // 0b11101, 0b11110 and 0b11111 in the top five bits start a 32-bit instruction
+        let wide = first >> 11 >= 0b11101;
+        let second = if wide { self.fetch(bus, pc.wrapping_add(2))? } else { 0 };
+        self.next = pc.wrapping_add(if wide { 4 } else { 2 });
+        self.cycles = 1;
+
+        let in_it = self.it & 0xF != 0;
+        if !in_it || self.passed(u32::from(self.it >> 4)) {
+            if wide {
+                self.execute32(bus, first, second)?;
+            } else {
+                self.execute16(bus, first)?;
+            }
+        }
+        if in_it {
+            self.advance_it();
+        }
+        self.regs[PC] = self.next;
+        Ok(self.cycles)
+    }
+
+    // ========================================================================
+    // 16-bit instructions
+    // ========================================================================
+
+    fn execute16(&mut self, bus: &mut Bus, op: u32) -> Result<(), Fault> {
+        // Inside an IT block the 16-bit data processing instructions leave the flags alone
+        let set_flags = self.it & 0xF == 0;
+        let low = |shift: u32| ((op >> shift) & 7) as usize;
+        let imm8 = op & 0xFF;
+
+        match op >> 11 {
+            // LSLS, LSRS, ASRS (immediate); LSLS #0 is MOVS
+            0b00000..=0b00010 => {
+                let (shift, amount) = decode_imm_shift(op >> 11, (op >> 6) & 0x1F);
+                let (result, carry) = shift_c(self.regs[low(3)], shift, amount, self.flags.c);
+                self.regs[low(0)] = result;
+                if set_flags {
+                    self.set_nz(result);
+                    self.flags.c = carry;
+                }
+            }
+            // ADDS, SUBS (register or 3-bit immediate)
+            0b00011 => {
+                let operand = if op & (1 << 10) != 0 {
+                    (op >> 6) & 7
+                } else {
+                    self.regs[low(6)]
+                };
+                let n = self.regs[low(3)];
+                let (result, carry, overflow) = if op & (1 << 9) == 0 {
+                    add_with_carry(n, operand, false)
+                } else {
+                    add_with_carry(n, !operand, true)
+                };
+                self.regs[low(0)] = result;
+                if set_flags {
+                    self.set_nzcv(result, carry, overflow);
+                }
+            }
+            // MOVS (immediate)
+            0b00100 => {
+                self.regs[low(8)] = imm8;
+                if set_flags {
+                    self.set_nz(imm8);
+                }
+            }
+            // CMP (immediate)
+            0b00101 => {
+                let (result, carry, overflow) = add_with_carry(self.regs[low(8)], !imm8, true);
+                self.set_nzcv(result, carry, overflow);
+            }
+            // ADDS, SUBS (8-bit immediate)
+            0b00110 | 0b00111 => {
+                let n = self.regs[low(8)];
+                let (result, carry, overflow) = if op >> 11 == 0b00110 {
+                    add_with_carry(n, imm8, false)
+                } else {
+                    add_with_carry(n, !imm8, true)
+                };
+                self.regs[low(8)] = result;
+                if set_flags {
+                    self.set_nzcv(result, carry, overflow);
+                }
+            }
+            0b01000 if op & (1 << 10) == 0 => self.data_processing16(op, set_flags),
+            0b01000 => self.special16(op),
+            // LDR (literal)
+            0b01001 => {
+                let value = self.load(bus, align4(self.read(PC)).wrapping_add(imm8 << 2), 4)?;
+                self.regs[low(8)] = value;
+            }
+            // Load and store, register offset
+            0b01010 | 0b01011 => {
+                let address = self.regs[low(3)].wrapping_add(self.regs[low(6)]);
+                let rt = low(0);
+                match (op >> 9) & 7 {
+                    0 => self.store_reg(bus, rt, address, 4)?,
+                    1 => self.store_reg(bus, rt, address, 2)?,
+                    2 => self.store_reg(bus, rt, address, 1)?,
+                    3 => self.load_reg(bus, rt, address, 1, true)?,
+                    4 => self.load_reg(bus, rt, address, 4, false)?,
+                    5 => self.load_reg(bus, rt, address, 2, false)?,
+                    6 => self.load_reg(bus, rt, address, 1, false)?,
+                    _ => self.load_reg(bus, rt, address, 2, true)?,
+                }
+            }
+            // Load and store, 5-bit immediate offset
+            0b01100..=0b10001 => {
+                let size = match op >> 11 {
+                    0b01100 | 0b01101 => 4,
+                    0b01110 | 0b01111 => 1,
+                    _ => 2,
+                };
+                let address = self.regs[low(3)].wrapping_add(((op >> 6) & 0x1F) * size);
+                if op & (1 << 11) == 0 {
+                    self.store_reg(bus, low(0), address, size)?;
+                } else {
+                    self.load_reg(bus, low(0), address, size, false)?;
+                }
+            }
+            // STR, LDR (SP relative)
+            0b10010 => self.store_reg(bus, low(8), self.regs[SP].wrapping_add(imm8 << 2), 4)?,
+            0b10011 => self.load_reg(bus, low(8), self.regs[SP].wrapping_add(imm8 << 2), 4, false)?,
+            // ADR, ADD Rd, SP, #imm
+            0b10100 => self.regs[low(8)] = align4(self.read(PC)).wrapping_add(imm8 << 2),
+            0b10101 => self.regs[low(8)] = self.regs[SP].wrapping_add(imm8 << 2),
+            0b10110 | 0b10111 => self.misc16(bus, op)?,
+            // STM, LDM (increment after, with writeback unless LDM loads the base)
+            0b11000 => self.store_multiple(bus, low(8), imm8, false, true)?,
+            0b11001 => {
+                let writeback = imm8 & (1 << low(8)) == 0;
+                self.load_multiple(bus, low(8), imm8, false, writeback)?;
+            }
+            // B<cond>, UDF, SVC
+            0b11010 | 0b11011 => match (op >> 8) & 0xF {
+                0b1110 => return Err(self.undefined(op)),
+                0b1111 => {
+                    return Err(Fault::SupervisorCall {
+                        pc: self.current,
+                        imm: imm8 as u8,
+                    })
+                }
+                cond => {
+                    if self.passed(cond) {
+                        self.branch(self.read(PC).wrapping_add(sign_extend(imm8 << 1, 9)));
+                    }
+                }
+            },
+            // B
+            0b11100 => self.branch(self.read(PC).wrapping_add(sign_extend((op & 0x7FF) << 1, 12))),
+            _ => return Err(self.undefined(op)),
+        }
+        Ok(())
+    }
+
+    /// AND, EOR, LSL, LSR, ASR, ADC, SBC, ROR, TST, RSB, CMP, CMN, ORR, MUL, BIC, MVN on low registers
+    fn data_processing16(&mut self, op: u32, set_flags: bool) {
+        let rm = ((op >> 3) & 7) as usize;
+        let rdn = (op & 7) as usize;
+        let (m, dn) = (self.regs[rm], self.regs[rdn]);
+        let Flags { c, v, .. } = self.flags;
+        let opcode = (op >> 6) & 0xF;
+        let (result, carry, overflow) = match opcode {
+            0b0000 | 0b1000 => (dn & m, c, v),
+            0b0001 => (dn ^ m, c, v),
+            0b0010..=0b0100 | 0b0111 => {
+                let shift = decode_reg_shift(match opcode {
+                    0b0010 => 0,
+                    0b0011 => 1,
+                    0b0100 => 2,
+                    _ => 3,
+                });
+                let (result, carry) = shift_c(dn, shift, m & 0xFF, c);
+                (result, carry, v)
+            }
+            0b0101 => add_with_carry(dn, m, c),
+            0b0110 => add_with_carry(dn, !m, c),
+            // RSBS Rd, Rn, #0: Rn is in the Rm field
+            0b1001 => add_with_carry(!m, 0, true),
+            0b1010 => add_with_carry(dn, !m, true),
+            0b1011 => add_with_carry(dn, m, false),
+            0b1100 => (dn | m, c, v),
+            0b1101 => (dn.wrapping_mul(m), c, v),
+            0b1110 => (dn & !m, c, v),
+            _ => (!m, c, v),
+        };
+        // TST, CMP and CMN only set the flags, and always do
+        let compare = matches!(opcode, 0b1000 | 0b1010 | 0b1011);
+        if !compare {
+            self.regs[rdn] = result;
+        }
+        if set_flags || compare {
+            self.set_nzcv(result, carry, overflow);
+        }
+    }
+
+    /// ADD, CMP and MOV with high registers, BX and BLX
+    fn special16(&mut self, op: u32) {
+        let rm = ((op >> 3) & 0xF) as usize;
+        let rdn = ((op & 7) | ((op >> 4) & 8)) as usize;
+        match (op >> 8) & 3 {
+            0 => self.write_alu(rdn, self.read(rdn).wrapping_add(self.read(rm))),
+            1 => {
+                let (result, carry, overflow) = add_with_carry(self.read(rdn), !self.read(rm), true);
+                self.set_nzcv(result, carry, overflow);
+            }
+            2 => self.write_alu(rdn, self.read(rm)),
+            _ => {
+                let target = self.read(rm);
+                if op & (1 << 7) != 0 {
+                    self.regs[LR] = self.next | 1;
+                }
+                self.branch_exchange(target);
+            }
+        }
+    }
+
+    /// The 0b1011 group: SP adjustment, CBZ, extends, PUSH, POP, CPS, REV, BKPT, IT and hints
+    fn misc16(&mut self, bus: &mut Bus, op: u32) -> Result<(), Fault> {
+        let (rd, m) = ((op & 7) as usize, self.regs[((op >> 3) & 7) as usize]);
+        match (op >> 8) & 0xF {
+            0b0000 => {
+                let imm = (op & 0x7F) << 2;
+                self.regs[SP] = if op & (1 << 7) == 0 {
+                    self.regs[SP].wrapping_add(imm)
+                } else {
+                    self.regs[SP].wrapping_sub(imm)
+                };
+            }
+            // CBZ, CBNZ
+            0b0001 | 0b0011 | 0b1001 | 0b1011 => {
+                let offset = ((op >> 9) & 1) << 6 | ((op >> 3) & 0x1F) << 1;
+                let nonzero = op & (1 << 11) != 0;
+                if (self.regs[rd] != 0) == nonzero {
+                    self.branch(self.read(PC).wrapping_add(offset));
+                }
+            }
+            // SXTH, SXTB, UXTH, UXTB
+            0b0010 => {
+                self.regs[rd] = match (op >> 6) & 3 {
+                    0 => sign_extend(m & 0xFFFF, 16),
+                    1 => sign_extend(m & 0xFF, 8),
+                    2 => m & 0xFFFF,
+                    _ => m & 0xFF,
+                };
+            }
+            // PUSH, with LR in bit 8
+            0b0100 | 0b0101 => self.store_multiple(bus, SP, (op & 0xFF) | (op & 0x100) << 6, true, true)?,
+            // CPSIE, CPSID
+            0b0110 if op & 0xFFE8 == 0xB660 => {
+                let disable = op & (1 << 4) != 0;
+                if op & 2 != 0 {
+                    self.primask = disable;
+                }
+                if op & 1 != 0 {
+                    self.faultmask = disable;
+                }
+            }
+            0b1010 => {
+                self.regs[rd] = match (op >> 6) & 3 {
+                    0 => m.swap_bytes(),
+                    1 => rev16(m),
+                    3 => sign_extend(u32::from((m as u16).swap_bytes()), 16),
+                    _ => return Err(self.undefined(op)),
+                };
+            }
+            // POP, with PC in bit 8
+            0b1100 | 0b1101 => self.load_multiple(bus, SP, (op & 0xFF) | (op & 0x100) << 7, false, true)?,
+            0b1110 => {
+                return Err(Fault::Breakpoint {
+                    pc: self.current,
+                    imm: op as u8,
+                })
+            }
+            // IT, or with an empty mask NOP, YIELD, WFE, WFI, SEV: with no interrupts or other cores, all NOPs
+            0b1111 => {
+                if op & 0xF != 0 {
+                    self.it = op as u8;
+                }
+            }
+            _ => return Err(self.undefined(op)),
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // 32-bit instructions
+    // ========================================================================
+
+    fn execute32(&mut self, bus: &mut Bus, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let instruction = hw1 << 16 | hw2;
+        match (hw1 >> 11) & 3 {
+            0b01 => {
+                if hw1 & (1 << 10) != 0 {
+                    // Coprocessor: the FPU
+                    Err(self.undefined(instruction))
+                } else if hw1 & (1 << 9) != 0 {
+                    self.data_processing_shifted(hw1, hw2)
+                } else if hw1 & (1 << 6) != 0 {
+                    self.dual_exclusive_table(bus, hw1, hw2)
+                } else {
+                    self.load_store_multiple(bus, hw1, hw2)
+                }
+            }
+            0b10 => {
+                if hw2 & (1 << 15) != 0 {
+                    self.branch_misc(hw1, hw2)
+                } else if hw1 & (1 << 9) != 0 {
+                    self.plain_immediate(hw1, hw2)
+                } else {
+                    self.modified_immediate(hw1, hw2)
+                }
+            }
+            _ => match (hw1 >> 4) & 0x7F {
+                op2 if op2 & 0b110_0000 == 0 => self.load_store_single(bus, hw1, hw2),
+                op2 if op2 & 0b111_0000 == 0b010_0000 => self.data_processing_register(hw1, hw2),
+                op2 if op2 & 0b111_1000 == 0b011_0000 => self.multiply(hw1, hw2),
+                op2 if op2 & 0b111_1000 == 0b011_1000 => self.long_multiply_divide(hw1, hw2),
+                // Coprocessor
+                _ => Err(self.undefined(instruction)),
+            },
+        }
+    }
+
+    /// The operations shared by the modified-immediate and shifted-register encodings: AND, BIC, ORR, ORN, EOR,
+    /// ADD, ADC, SBC, SUB, RSB, with their compare (TST, TEQ, CMN, CMP) and move (MOV, MVN) forms
+    #[allow(clippy::too_many_arguments)]
+    fn data_processing(
+        &mut self,
+        op: u32,
+        set_flags: bool,
+        rn: usize,
+        rd: usize,
+        operand: u32,
+        carry: bool,
+        instruction: u32,
+    ) -> Result<(), Fault> {
+        let n = self.read(rn);
+        let Flags { c, v, .. } = self.flags;
+        let compare = set_flags && rd == PC;
+        let (result, carry, overflow) = match op {
+            0b0000 => (n & operand, carry, v),
+            0b0001 => (n & !operand, carry, v),
+            0b0010 if rn == PC => (operand, carry, v),
+            0b0010 => (n | operand, carry, v),
+            0b0011 if rn == PC => (!operand, carry, v),
+            0b0011 => (n | !operand, carry, v),
+            0b0100 => (n ^ operand, carry, v),
+            0b1000 => add_with_carry(n, operand, false),
+            0b1010 => add_with_carry(n, operand, c),
+            0b1011 => add_with_carry(n, !operand, c),
+            0b1101 => add_with_carry(n, !operand, true),
+            0b1110 => add_with_carry(!n, operand, true),
+            _ => return Err(self.undefined(instruction)),
+        };
+        // With Rd = PC and S set, AND, EOR, ADD and SUB are TST, TEQ, CMN and CMP
+        if !compare {
+            self.write_alu(rd, result);
+        }
+        if set_flags {
+            self.set_nzcv(result, carry, overflow);
+        }
+        Ok(())
+    }
+
+    fn modified_immediate(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let imm12 = (hw1 >> 10 & 1) << 11 | (hw2 >> 12 & 7) << 8 | (hw2 & 0xFF);
+        let (operand, carry) = thumb_expand_imm_c(imm12, self.flags.c);
+        let (rn, rd) = ((hw1 & 0xF) as usize, (hw2 >> 8 & 0xF) as usize);
+        self.data_processing(
+            hw1 >> 5 & 0xF,
+            hw1 & (1 << 4) != 0,
+            rn,
+            rd,
+            operand,
+            carry,
+            hw1 << 16 | hw2,
+        )
+    }
+
+    fn data_processing_shifted(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let (rn, rd, rm) = ((hw1 & 0xF) as usize, (hw2 >> 8 & 0xF) as usize, (hw2 & 0xF) as usize);
+        let (shift, amount) = decode_imm_shift(hw2 >> 4 & 3, (hw2 >> 12 & 7) << 2 | (hw2 >> 6 & 3));
+        let (operand, carry) = shift_c(self.read(rm), shift, amount, self.flags.c);
+        let op = hw1 >> 5 & 0xF;
+        if op == 0b0110 {
+            // PKHBT, PKHTB: the halfwords of Rn and the shifted Rm
+            let n = self.read(rn);
+            self.regs[rd] = if hw2 & (1 << 5) == 0 {
+                (n & 0xFFFF) | (operand & 0xFFFF_0000)
+            } else {
+                (n & 0xFFFF_0000) | (operand & 0xFFFF)
+            };
+            return Ok(());
+        }
+        self.data_processing(op, hw1 & (1 << 4) != 0, rn, rd, operand, carry, hw1 << 16 | hw2)
+    }
+
+    /// ADDW, SUBW, MOVW, MOVT, the bitfield instructions and SSAT, USAT
+    fn plain_immediate(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let (rn, rd) = ((hw1 & 0xF) as usize, (hw2 >> 8 & 0xF) as usize);
+        let imm12 = (hw1 >> 10 & 1) << 11 | (hw2 >> 12 & 7) << 8 | (hw2 & 0xFF);
+        let imm16 = (hw1 & 0xF) << 12 | imm12;
+        let lsb = (hw2 >> 12 & 7) << 2 | (hw2 >> 6 & 3);
+        let low5 = hw2 & 0x1F;
+        let base = if rn == PC { align4(self.read(PC)) } else { self.read(rn) };
+        self.regs[rd] = match hw1 >> 4 & 0x1F {
+            0b00000 => base.wrapping_add(imm12),
+            0b01010 => base.wrapping_sub(imm12),
+            0b00100 => imm16,
+            0b01100 => (self.regs[rd] & 0xFFFF) | imm16 << 16,
+            // SBFX, UBFX
+            0b10100 | 0b11100 if lsb + low5 < 32 => {
+                let width = low5 + 1;
+                let field = base << (32 - lsb - width);
+                if hw1 & (1 << 7) == 0 {
+                    ((field as i32) >> (32 - width)) as u32
+                } else {
+                    field >> (32 - width)
+                }
+            }
+            // BFI, or BFC with Rn = PC
+            0b10110 if low5 >= lsb => {
+                let mask = (u32::MAX >> (31 - (low5 - lsb))) << lsb;
+                let source = if rn == PC { 0 } else { base << lsb };
+                (self.regs[rd] & !mask) | (source & mask)
+            }
+            // SSAT, USAT: LSL, or ASR with bit 5 set; with ASR #0, these are SSAT16 and USAT16
+            op @ (0b10000 | 0b10010 | 0b11000 | 0b11010) if !(op & 0b10 != 0 && lsb == 0) => {
+                let shifted = if op & 0b10 == 0 {
+                    base << lsb
+                } else {
+                    ((base as i32) >> lsb) as u32
+                } as i32;
+                let (result, saturated) = if op & 0b01000 == 0 {
+                    signed_saturate(shifted, low5 + 1)
+                } else {
+                    unsigned_saturate(shifted, low5)
+                };
+                self.flags.q |= saturated;
+                result
+            }
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        };
+        Ok(())
+    }
+
+    /// B (conditional and not), BL, MSR, MRS, hints, barriers, CLREX
+    fn branch_misc(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let s = hw1 >> 10 & 1;
+        let (j1, j2) = (hw2 >> 13 & 1, hw2 >> 11 & 1);
+        match hw2 >> 12 & 0b101 {
+            0b000 => {
+                let op = hw1 >> 4 & 0x7F;
+                if op & 0b011_1000 != 0b011_1000 {
+                    // B<cond>.W
+                    let offset = s << 20 | j2 << 19 | j1 << 18 | (hw1 & 0x3F) << 12 | (hw2 & 0x7FF) << 1;
+                    if self.passed(hw1 >> 6 & 0xF) {
+                        self.branch(self.read(PC).wrapping_add(sign_extend(offset, 21)));
+                    }
+                    return Ok(());
+                }
+                match op {
+                    0b011_1000 | 0b011_1001 => self.msr(hw1, hw2)?,
+                    // NOP.W, YIELD.W, WFE.W, WFI.W, SEV.W, DBG: nothing to do
+                    0b011_1010 => {}
+                    0b011_1011 => match hw2 >> 4 & 0xF {
+                        0b0010 => self.exclusive = false,
+                        // DSB, DMB, ISB: one core, no caches, no reordering
+                        0b0100..=0b0110 => {}
+                        _ => return Err(self.undefined(hw1 << 16 | hw2)),
+                    },
+                    0b011_1110 | 0b011_1111 => self.mrs(hw1, hw2)?,
+                    _ => return Err(self.undefined(hw1 << 16 | hw2)),
+                }
+            }
+            0b001 | 0b101 => {
+                let (i1, i2) = (!(j1 ^ s) & 1, !(j2 ^ s) & 1);
+                let offset = s << 24 | i1 << 23 | i2 << 22 | (hw1 & 0x3FF) << 12 | (hw2 & 0x7FF) << 1;
+                if hw2 & (1 << 14) != 0 {
+                    self.regs[LR] = self.next | 1;
+                }
+                self.branch(self.read(PC).wrapping_add(sign_extend(offset, 25)));
+            }
+            // BLX (immediate) would switch to ARM state, which M-profile cores do not have
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        }
+        Ok(())
+    }
+
+    fn mrs(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let rd = (hw2 >> 8 & 0xF) as usize;
+        let sysm = hw2 & 0xFF;
+        let (msp, psp) = self.stack_pointers();
+        self.regs[rd] = match sysm {
+            // APSR, IAPSR, EAPSR, xPSR, IPSR, EPSR, IEPSR: the IPSR is 0 in Thread mode, the EPSR reads as 0
+            0..=3 | 5..=7 if sysm & 4 == 0 => self.flags.apsr(),
+            0..=3 | 5..=7 => 0,
+            8 => msp,
+            9 => psp,
+            16 => u32::from(self.primask),
+            17 | 18 => u32::from(self.basepri),
+            19 => u32::from(self.faultmask),
+            20 => self.control,
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        };
+        Ok(())
+    }
+
+    fn msr(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let value = self.read((hw1 & 0xF) as usize);
+        let spsel = self.control & 2 != 0;
+        match hw2 & 0xFF {
+            sysm @ (0..=3 | 5..=7) => {
+                // The mask's bit 1 selects NZCVQ; bit 0 would be the DSP GE bits, which are not modelled
+                if sysm & 4 == 0 && hw2 & (1 << 11) != 0 {
+                    self.flags.set_apsr(value);
+                }
+            }
+            8 if spsel => self.other_sp = value & !3,
+            8 => self.regs[SP] = value & !3,
+            9 if spsel => self.regs[SP] = value & !3,
+            9 => self.other_sp = value & !3,
+            16 => self.primask = value & 1 != 0,
+            17 => self.basepri = value as u8,
+            // BASEPRI_MAX only raises the priority: 0 means no masking
+            18 => {
+                let value = value as u8;
+                if value != 0 && (self.basepri == 0 || value < self.basepri) {
+                    self.basepri = value;
+                }
+            }
+            19 => self.faultmask = value & 1 != 0,
+            20 => {
+                let control = value & 7;
+                if (control & 2 != 0) != spsel {
+                    std::mem::swap(&mut self.regs[SP], &mut self.other_sp);
+                }
+                self.control = control;
+            }
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        }
+        Ok(())
+    }
+
+    /// MSP and PSP, whichever of them is in R13
+    fn stack_pointers(&self) -> (u32, u32) {
+        if self.control & 2 != 0 {
+            (self.other_sp, self.regs[SP])
+        } else {
+            (self.regs[SP], self.other_sp)
+        }
+    }
+
+    /// LDR, LDRB, LDRH, LDRSB, LDRSH, STR, STRB, STRH, in all their addressing modes, and PLD, PLI
+    fn load_store_single(&mut self, bus: &mut Bus, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let instruction = hw1 << 16 | hw2;
+        let signed = hw1 & (1 << 8) != 0;
+        let size = 1 << (hw1 >> 5 & 3);
+        let load = hw1 & (1 << 4) != 0;
+        let (rn, rt) = ((hw1 & 0xF) as usize, (hw2 >> 12) as usize);
+        if size == 8 || (signed && (!load || size == 4)) {
+            return Err(self.undefined(instruction));
+        }
+
+        let base = self.read(rn);
+        let (address, writeback) = if rn == PC {
+            // Literal: the U bit says add or subtract
+            if !load {
+                return Err(self.undefined(instruction));
+            }
+            let base = align4(base);
+            let imm = hw2 & 0xFFF;
+            (
+                if hw1 & (1 << 7) != 0 {
+                    base.wrapping_add(imm)
+                } else {
+                    base.wrapping_sub(imm)
+                },
+                None,
+            )
+        } else if hw1 & (1 << 7) != 0 {
+            (base.wrapping_add(hw2 & 0xFFF), None)
+        } else if hw2 & (1 << 11) != 0 {
+            // 8-bit immediate: pre-indexed, post-indexed or plain offset, added or subtracted
+            let (index, add, wback) = (hw2 & (1 << 10) != 0, hw2 & (1 << 9) != 0, hw2 & (1 << 8) != 0);
+            if !index && !wback {
+                return Err(self.undefined(instruction));
+            }
+            let imm = hw2 & 0xFF;
+            let offset_address = if add {
+                base.wrapping_add(imm)
+            } else {
+                base.wrapping_sub(imm)
+            };
+            (
+                if index { offset_address } else { base },
+                wback.then_some(offset_address),
+            )
+        } else if hw2 & 0xFC0 == 0 {
+            let offset = self.read((hw2 & 0xF) as usize) << (hw2 >> 4 & 3);
+            (base.wrapping_add(offset), None)
+        } else {
+            return Err(self.undefined(instruction));
+        };
+
+        if load {
+            if rt == PC && size != 4 {
+                // PLD, PLI: there is no cache to warm
+                return Ok(());
+            }
+            let value = self.load_value(bus, address, size, signed)?;
+            if let Some(offset_address) = writeback {
+                self.regs[rn] = offset_address;
+            }
+            self.write_loaded(rt, value);
+        } else {
+            self.store_reg(bus, rt, address, size)?;
+            if let Some(offset_address) = writeback {
+                self.regs[rn] = offset_address;
+            }
+        }
+        Ok(())
+    }
+
+    /// LDM, STM, and their PUSH.W and POP.W forms
+    fn load_store_multiple(&mut self, bus: &mut Bus, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let rn = (hw1 & 0xF) as usize;
+        let writeback = hw1 & (1 << 5) != 0;
+        let before = match hw1 >> 7 & 3 {
+            0b01 => false,
+            0b10 => true,
+            // SRS and RFE are not in M-profile
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        };
+        if hw1 & (1 << 4) != 0 {
+            self.load_multiple(bus, rn, hw2, before, writeback)
+        } else {
+            self.store_multiple(bus, rn, hw2, before, writeback)
+        }
+    }
+
+    /// LDRD, STRD, LDREX, STREX and their byte and halfword forms, TBB, TBH
+    fn dual_exclusive_table(&mut self, bus: &mut Bus, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let instruction = hw1 << 16 | hw2;
+        let (rn, rt) = ((hw1 & 0xF) as usize, (hw2 >> 12) as usize);
+        let (index, add, wback, load) = (
+            hw1 & (1 << 8) != 0,
+            hw1 & (1 << 7) != 0,
+            hw1 & (1 << 5) != 0,
+            hw1 & (1 << 4) != 0,
+        );
+
+        if index || wback {
+            // LDRD, STRD: Rt and Rt2 at a word-aligned address
+            let rt2 = (hw2 >> 8 & 0xF) as usize;
+            let imm = (hw2 & 0xFF) << 2;
+            let base = if rn == PC { align4(self.read(PC)) } else { self.read(rn) };
+            let offset_address = if add {
+                base.wrapping_add(imm)
+            } else {
+                base.wrapping_sub(imm)
+            };
+            let address = if index { offset_address } else { base };
+            self.check_aligned(address, 4)?;
+            if load {
+                let low = self.load(bus, address, 4)?;
+                let high = self.load(bus, address.wrapping_add(4), 4)?;
+                if wback {
+                    self.regs[rn] = offset_address;
+                }
+                self.regs[rt] = low;
+                self.regs[rt2] = high;
+            } else {
+                self.store(bus, address, 4, self.read(rt))?;
+                self.store(bus, address.wrapping_add(4), 4, self.read(rt2))?;
+                if wback {
+                    self.regs[rn] = offset_address;
+                }
+            }
+            self.cycles += 2;
+            return Ok(());
+        }
+
+        match (add, load) {
+            // STREX, LDREX: word, with an offset
+            (false, false) => {
+                let rd = (hw2 >> 8 & 0xF) as usize;
+                let address = self.read(rn).wrapping_add((hw2 & 0xFF) << 2);
+                self.store_exclusive(bus, rd, rt, address, 4)?;
+            }
+            (false, true) => {
+                let address = self.read(rn).wrapping_add((hw2 & 0xFF) << 2);
+                self.load_exclusive(bus, rt, address, 4)?;
+            }
+            // STREXB, STREXH
+            (true, false) => {
+                let size = match hw2 >> 4 & 0xF {
+                    0b0100 => 1,
+                    0b0101 => 2,
+                    _ => return Err(self.undefined(instruction)),
+                };
+                self.store_exclusive(bus, (hw2 & 0xF) as usize, rt, self.read(rn), size)?;
+            }
+            (true, true) => match hw2 >> 4 & 0xF {
+                // TBB, TBH: a table of forward branch offsets, in halfwords
+                op @ (0b0000 | 0b0001) => {
+                    let (size, m) = (op + 1, self.read((hw2 & 0xF) as usize));
+                    let entry = self.load(bus, self.read(rn).wrapping_add(m * size), size)?;
+                    self.cycles += 1;
+                    self.branch(self.read(PC).wrapping_add(entry << 1));
+                }
+                0b0100 => self.load_exclusive(bus, rt, self.read(rn), 1)?,
+                0b0101 => self.load_exclusive(bus, rt, self.read(rn), 2)?,
+                _ => return Err(self.undefined(instruction)),
+            },
+        }
+        Ok(())
+    }
+
+    /// LSL, LSR, ASR, ROR by a register, the extends, REV, REV16, RBIT, REVSH, CLZ
+    fn data_processing_register(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let instruction = hw1 << 16 | hw2;
+        if hw2 >> 12 != 0xF {
+            return Err(self.undefined(instruction));
+        }
+        let (rn, rd, rm) = ((hw1 & 0xF) as usize, (hw2 >> 8 & 0xF) as usize, (hw2 & 0xF) as usize);
+        let (op1, op2) = (hw1 >> 4 & 0xF, hw2 >> 4 & 0xF);
+        let (n, m) = (self.read(rn), self.read(rm));
+        self.regs[rd] = match (op1, op2) {
+            (0b0000..=0b0111, 0) => {
+                let (result, carry) = shift_c(n, decode_reg_shift(op1 >> 1), m & 0xFF, self.flags.c);
+                if op1 & 1 != 0 {
+                    self.set_nz(result);
+                    self.flags.c = carry;
+                }
+                result
+            }
+            // SXTAH, UXTAH, SXTAB, UXTAB, and without Rn, SXTH, UXTH, SXTB, UXTB
+            (0b0000 | 0b0001 | 0b0100 | 0b0101, 0b1000..=0b1011) => {
+                let rotated = m.rotate_right((op2 & 3) * 8);
+                let extended = match op1 {
+                    0b0000 => sign_extend(rotated & 0xFFFF, 16),
+                    0b0001 => rotated & 0xFFFF,
+                    0b0100 => sign_extend(rotated & 0xFF, 8),
+                    _ => rotated & 0xFF,
+                };
+                if rn == PC {
+                    extended
+                } else {
+                    n.wrapping_add(extended)
+                }
+            }
+            (0b1001, 0b1000) => m.swap_bytes(),
+            (0b1001, 0b1001) => rev16(m),
+            (0b1001, 0b1010) => m.reverse_bits(),
+            (0b1001, 0b1011) => sign_extend(u32::from((m as u16).swap_bytes()), 16),
+            (0b1011, 0b1000) => m.leading_zeros(),
+            _ => return Err(self.undefined(instruction)),
+        };
+        Ok(())
+    }
+
+    /// MUL, MLA, MLS
+    fn multiply(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let (rn, ra, rd, rm) = (
+            (hw1 & 0xF) as usize,
+            (hw2 >> 12) as usize,
+            (hw2 >> 8 & 0xF) as usize,
+            (hw2 & 0xF) as usize,
+        );
+        let product = self.read(rn).wrapping_mul(self.read(rm));
+        self.regs[rd] = match (hw1 >> 4 & 7, hw2 >> 4 & 0xF) {
+            (0, 0) if ra == PC => product,
+            (0, 0) => self.read(ra).wrapping_add(product),
+            (0, 1) => self.read(ra).wrapping_sub(product),
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        };
+        Ok(())
+    }
+
+    /// SMULL, UMULL, SMLAL, UMLAL, UMAAL, SDIV, UDIV
+    fn long_multiply_divide(&mut self, hw1: u32, hw2: u32) -> Result<(), Fault> {
+        let (rn, rd_lo, rd_hi, rm) = (
+            (hw1 & 0xF) as usize,
+            (hw2 >> 12) as usize,
+            (hw2 >> 8 & 0xF) as usize,
+            (hw2 & 0xF) as usize,
+        );
+        let (n, m) = (self.read(rn), self.read(rm));
+        let accumulator = u64::from(self.read(rd_hi)) << 32 | u64::from(self.read(rd_lo));
+        let signed = (i64::from(n as i32) * i64::from(m as i32)) as u64;
+        let unsigned = u64::from(n) * u64::from(m);
+        let result = match (hw1 >> 4 & 7, hw2 >> 4 & 0xF) {
+            (0b000, 0b0000) => signed,
+            (0b010, 0b0000) => unsigned,
+            (0b100, 0b0000) => accumulator.wrapping_add(signed),
+            (0b110, 0b0000) => accumulator.wrapping_add(unsigned),
+            (0b110, 0b0110) => unsigned + u64::from(self.read(rd_lo)) + u64::from(self.read(rd_hi)),
+            // SDIV, UDIV: the quotient goes in the RdHi field's register. Dividing by zero gives 0, unless
+            // CCR.DIV_0_TRP is set, which it is not after reset.
+            (op1 @ (0b001 | 0b011), 0b1111) => {
+                let quotient = match (m, op1) {
+                    (0, _) => 0,
+                    (_, 0b001) => (n as i32).wrapping_div(m as i32) as u32,
+                    _ => n / m,
+                };
+                self.regs[rd_hi] = quotient;
+                self.cycles += divide_cycles(n, m);
+                return Ok(());
+            }
+            _ => return Err(self.undefined(hw1 << 16 | hw2)),
+        };
+        self.regs[rd_lo] = result as u32;
+        self.regs[rd_hi] = (result >> 32) as u32;
+        Ok(())
+    }
+
+    // ========================================================================
+    // Helpers
+    // ========================================================================
+
+    /// Register `n` as an instruction sees it: the PC reads as this instruction's address + 4
+    fn read(&self, n: usize) -> u32 {
+        if n == PC {
+            self.current.wrapping_add(4)
+        } else {
+            self.regs[n]
+        }
+    }
+
+    /// The result of a data-processing instruction: to the PC, it is a branch
+    fn write_alu(&mut self, rd: usize, value: u32) {
+        if rd == PC {
+            self.branch(value);
+        } else {
+            self.regs[rd] = value;
+        }
+    }
+
+    fn branch(&mut self, target: u32) {
+        self.next = target & !1;
+        self.cycles += REFILL;
+    }
+
+    /// BX, BLX, and loads to the PC: bit 0 is the Thumb bit, which must be set
+    fn branch_exchange(&mut self, target: u32) {
+        self.thumb = target & 1 != 0;
+        self.branch(target);
+    }
+
+    fn undefined(&self, instruction: u32) -> Fault {
+        Fault::Undefined {
+            pc: self.current,
+            instruction,
+        }
+    }
+
+    fn bus_fault(&self, error: BusError) -> Fault {
+        Fault::Bus {
+            pc: self.current,
+            address: error.address,
+        }
+    }
+
+    fn check_aligned(&self, address: u32, size: u32) -> Result<(), Fault> {
+        if address.is_multiple_of(size) {
+            Ok(())
+        } else {
+            Err(Fault::Unaligned {
+                pc: self.current,
+                address,
+            })
+        }
+    }
+
+    fn fetch(&self, bus: &mut Bus, address: u32) -> Result<u32, Fault> {
+        bus.read(address, 2).map_err(|error| self.bus_fault(error))
+    }
+
+    fn load(&self, bus: &mut Bus, address: u32, size: u32) -> Result<u32, Fault> {
+        bus.read(address, size).map_err(|error| self.bus_fault(error))
+    }
+
+    fn store(&self, bus: &mut Bus, address: u32, size: u32, value: u32) -> Result<(), Fault> {
+        bus.write(address, size, value).map_err(|error| self.bus_fault(error))
+    }
+
+    /// A single load, zero- or sign-extended, counting its cycle
+    fn load_value(&mut self, bus: &mut Bus, address: u32, size: u32, signed: bool) -> Result<u32, Fault> {
+        let value = self.load(bus, address, size)?;
+        self.cycles += 1;
+        Ok(if signed { sign_extend(value, size * 8) } else { value })
+    }
+
+    /// A loaded value to its register: to the PC, it is a branch
+    fn write_loaded(&mut self, rt: usize, value: u32) {
+        if rt == PC {
+            self.branch_exchange(value);
+        } else {
+            self.regs[rt] = value;
+        }
+    }
+
+    fn load_reg(&mut self, bus: &mut Bus, rt: usize, address: u32, size: u32, signed: bool) -> Result<(), Fault> {
+        let value = self.load_value(bus, address, size, signed)?;
+        self.write_loaded(rt, value);
+        Ok(())
+    }
+
+    fn store_reg(&mut self, bus: &mut Bus, rt: usize, address: u32, size: u32) -> Result<(), Fault> {
+        self.store(bus, address, size, self.read(rt))?;
+        self.cycles += 1;
+        Ok(())
+    }
+
+    fn load_exclusive(&mut self, bus: &mut Bus, rt: usize, address: u32, size: u32) -> Result<(), Fault> {
+        self.check_aligned(address, size)?;
+        self.regs[rt] = self.load_value(bus, address, size, false)?;
+        self.exclusive = true;
+        Ok(())
+    }
+
+    /// Store, and put 0 in `rd`, only if the monitor is still set from the `LDREX`; otherwise put 1 there
+    fn store_exclusive(&mut self, bus: &mut Bus, rd: usize, rt: usize, address: u32, size: u32) -> Result<(), Fault> {
+        self.check_aligned(address, size)?;
+        if self.exclusive {
+            self.store_reg(bus, rt, address, size)?;
+        }
+        self.regs[rd] = u32::from(!self.exclusive);
+        self.exclusive = false;
+        Ok(())
+    }
+
+    /// STM (increment after) or STMDB (decrement before): the lowest register goes at the lowest address
+    fn store_multiple(
+        &mut self,
+        bus: &mut Bus,
+        rn: usize,
+        list: u32,
+        before: bool,
+        writeback: bool,
+    ) -> Result<(), Fault> {
+        let (start, end) = self.multiple_range(rn, list, before)?;
+        let mut address = start;
+        for reg in (0..16).filter(|reg| list & (1 << reg) != 0) {
+            self.store(bus, address, 4, self.read(reg))?;
+            address = address.wrapping_add(4);
+        }
+        if writeback {
+            self.regs[rn] = if before { start } else { end };
+        }
+        self.cycles += list.count_ones();
+        Ok(())
+    }
+
+    /// LDM (increment after) or LDMDB (decrement before). Loading the PC is a branch.
+    fn load_multiple(
+        &mut self,
+        bus: &mut Bus,
+        rn: usize,
+        list: u32,
+        before: bool,
+        writeback: bool,
+    ) -> Result<(), Fault> {
+        let (start, end) = self.multiple_range(rn, list, before)?;
+        let mut values = [0; 16];
+        let mut address = start;
+        for reg in (0..16).filter(|reg| list & (1 << reg) != 0) {
+            values[reg] = self.load(bus, address, 4)?;
+            address = address.wrapping_add(4);
+        }
+        if writeback {
+            self.regs[rn] = if before { start } else { end };
+        }
+        for reg in (0..16).filter(|reg| list & (1 << reg) != 0) {
+            self.write_loaded(reg, values[reg]);
+        }
+        self.cycles += list.count_ones();
+        Ok(())
+    }
+
+    /// The lowest address an LDM or STM accesses, and the address after the highest
+    fn multiple_range(&self, rn: usize, list: u32, before: bool) -> Result<(u32, u32), Fault> {
+        let (base, bytes) = (self.read(rn), list.count_ones() * 4);
+        let start = if before { base.wrapping_sub(bytes) } else { base };
+        self.check_aligned(start, 4)?;
+        Ok((start, start.wrapping_add(bytes)))
+    }
+
+    fn set_nz(&mut self, result: u32) {
+        self.flags.n = result >> 31 != 0;
+        self.flags.z = result == 0;
+    }
+
+    fn set_nzcv(&mut self, result: u32, carry: bool, overflow: bool) {
+        self.set_nz(result);
+        self.flags.c = carry;
+        self.flags.v = overflow;
+    }
+
+    /// Whether condition `cond` (EQ = 0 to AL = 14) holds
+    fn passed(&self, cond: u32) -> bool {
+        let Flags { n, z, c, v, .. } = self.flags;
+        let result = match cond >> 1 {
+            0 => z,
+            1 => c,
+            2 => n,
+            3 => v,
+            4 => c && !z,
+            5 => n == v,
+            6 => !z && n == v,
+            _ => return true,
+        };
+        result != (cond & 1 != 0)
+    }
+
+    /// Move on to the next instruction of the IT block, or out of it
+    fn advance_it(&mut self) {
+        self.it = if self.it & 7 == 0 {
+            0
+        } else {
+            (self.it & 0xE0) | (self.it << 1 & 0x1F)
+        };
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shift {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+    /// Rotate right by one through the carry flag
+    Rrx,
+}
+
+/// A shift encoded as a type and a 5-bit amount: LSR #0 and ASR #0 mean 32, ROR #0 means RRX
+fn decode_imm_shift(kind: u32, imm5: u32) -> (Shift, u32) {
+    let amount = if imm5 == 0 { 32 } else { imm5 };
+    match kind {
+        0 => (Shift::Lsl, imm5),
+        1 => (Shift::Lsr, amount),
+        2 => (Shift::Asr, amount),
+        _ if imm5 == 0 => (Shift::Rrx, 1),
+        _ => (Shift::Ror, imm5),
+    }
+}
+
+/// A shift by a register: the type alone
+fn decode_reg_shift(kind: u32) -> Shift {
+    match kind {
+        0 => Shift::Lsl,
+        1 => Shift::Lsr,
+        2 => Shift::Asr,
+        _ => Shift::Ror,
+    }
+}
+
+/// Shift `value` by `amount` (0 to 255), and return the result and the carry out
+fn shift_c(value: u32, shift: Shift, amount: u32, carry_in: bool) -> (u32, bool) {
+    if amount == 0 && shift != Shift::Rrx {
+        return (value, carry_in);
+    }
+    let bit = |n: u32| value >> n & 1 != 0;
+    match shift {
+        Shift::Lsl if amount < 32 => (value << amount, bit(32 - amount)),
+        Shift::Lsl => (0, amount == 32 && bit(0)),
+        Shift::Lsr if amount < 32 => (value >> amount, bit(amount - 1)),
+        Shift::Lsr => (0, amount == 32 && bit(31)),
+        Shift::Asr if amount < 32 => (((value as i32) >> amount) as u32, bit(amount - 1)),
+        Shift::Asr => (((value as i32) >> 31) as u32, bit(31)),
+        Shift::Ror => {
+            let result = value.rotate_right(amount % 32);
+            (result, result >> 31 != 0)
+        }
+        Shift::Rrx => (u32::from(carry_in) << 31 | value >> 1, bit(0)),
+    }
+}
+
+/// x + y + carry, with the carry out and signed overflow
+fn add_with_carry(x: u32, y: u32, carry: bool) -> (u32, bool, bool) {
+    let unsigned = u64::from(x) + u64::from(y) + u64::from(carry);
+    let signed = i64::from(x as i32) + i64::from(y as i32) + i64::from(carry);
+    let result = unsigned as u32;
+    (result, unsigned >> 32 != 0, i64::from(result as i32) != signed)
+}
+
+/// A 12-bit modified immediate: a byte repeated in one of four patterns, or a rotated 8-bit value with its top
+/// bit set
+fn thumb_expand_imm_c(imm12: u32, carry: bool) -> (u32, bool) {
+    let imm8 = imm12 & 0xFF;
+    if imm12 >> 10 == 0 {
+        let value = match imm12 >> 8 & 3 {
+            0 => imm8,
+            1 => imm8 << 16 | imm8,
+            2 => imm8 << 24 | imm8 << 8,
+            _ => imm8 * 0x0101_0101,
+        };
+        (value, carry)
+    } else {
+        let value = (0x80 | (imm12 & 0x7F)).rotate_right(imm12 >> 7);
+        (value, value >> 31 != 0)
+    }
+}
+
+/// The low `bits` bits of `value`, sign-extended
+fn sign_extend(value: u32, bits: u32) -> u32 {
+    let shift = 32 - bits;
+    (((value << shift) as i32) >> shift) as u32
+}
+
+/// The byte order of each halfword reversed
+fn rev16(value: u32) -> u32 {
+    (value & 0xFF00_FF00) >> 8 | (value & 0x00FF_00FF) << 8
+}
+
+fn align4(address: u32) -> u32 {
+    address & !3
+}
+
+/// `value` clamped to a signed `bits`-bit range, and whether it had to be
+fn signed_saturate(value: i32, bits: u32) -> (u32, bool) {
+    let max = (1i64 << (bits - 1)) - 1;
+    let min = -(1i64 << (bits - 1));
+    let clamped = i64::from(value).clamp(min, max);
+    (clamped as u32, clamped != i64::from(value))
+}
+
+/// `value` clamped to an unsigned `bits`-bit range, and whether it had to be
+fn unsigned_saturate(value: i32, bits: u32) -> (u32, bool) {
+    let max = (1i64 << bits) - 1;
+    let clamped = i64::from(value).clamp(0, max);
+    (clamped as u32, clamped != i64::from(value))
+}
+
+/// Extra cycles for a division: the M4 stops early once the quotient's bits are found, taking 2 to 12 cycles
+fn divide_cycles(dividend: u32, divisor: u32) -> u32 {
+    let quotient_bits = divisor.leading_zeros().saturating_sub(dividend.leading_zeros());
+    (1 + quotient_bits / 3).min(11)
+}
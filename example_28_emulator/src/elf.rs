@@ -0,0 +1,176 @@
+//! Just enough of the ELF format to load a Cortex-M program: its loadable segments and its symbol table.
+//!
+//! `cargo build` for `thumbv7em-none-eabihf` writes a 32-bit little-endian ARM ELF file. Only two parts of it
+//! matter here:
+//!
+//! - the program headers, which say which bytes go where in the chip's memory. Each segment is loaded at its
+//!   *physical* address, the one `probe-rs` flashes it to: for `.data`, that is its copy in flash, which the
+//!   reset handler copies to RAM
+//! - the symbol table, so tests can find functions and statics by name
+//!
+//! Everything else (section contents, debug information, relocations) is ignored.
+
+use std::fmt;
+
+/// `e_machine` for 32-bit ARM
+const EM_ARM: u16 = 40;
+/// `p_type` of a segment to load
+const PT_LOAD: u32 = 1;
+/// `sh_type` of the symbol table
+const SHT_SYMTAB: u32 = 2;
+
+/// Why a file could not be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    /// The file does not start with `\x7fELF`
+    NotElf,
+    /// A 64-bit or big-endian file
+    NotArm32,
+    /// Built for another architecture; the `e_machine` value is included
+    Machine(u16),
+    /// A header or table runs past the end of the file
+    Truncated,
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotElf => f.write_str("not an ELF file"),
+            Self::NotArm32 => f.write_str("not a 32-bit little-endian ELF file"),
+            Self::Machine(machine) => write!(f, "built for machine {}, not ARM", machine),
+            Self::Truncated => f.write_str("truncated ELF file"),
+        }
+    }
+}
+
+/// Bytes to load into memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment<'a> {
+    /// Where they go: the physical (load) address
+    pub address: u32,
+    pub data: &'a [u8],
+}
+
+/// A named address: a function, a static, or a linker script symbol such as `_stack_start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol<'a> {
+    pub name: &'a str,
+    /// The address. For a Thumb function, bit 0 is set.
+    pub value: u32,
+    pub size: u32,
+}
+
+/// A parsed ELF file, borrowing from its contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elf<'a> {
+    /// `e_entry`. A Cortex-M core ignores it and starts from the vector table.
+    pub entry: u32,
+    pub segments: Vec<Segment<'a>>,
+    pub symbols: Vec<Symbol<'a>>,
+}
+
+fn bytes_at(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], ElfError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or(ElfError::Truncated)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, ElfError> {
+    let b = bytes_at(bytes, offset, 2)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, ElfError> {
+    let b = bytes_at(bytes, offset, 4)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// `u32_at` for offsets and sizes
+fn usize_at(bytes: &[u8], offset: usize) -> Result<usize, ElfError> {
+    u32_at(bytes, offset).map(|value| value as usize)
+}
+
+impl<'a> Elf<'a> {
+    /// Parse the contents of an ELF file
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ElfError> {
+        if bytes.len() < 4 || &bytes[0..4] != b"\x7fELF" {
+            return Err(ElfError::NotElf);
+        }
+        // EI_CLASS 1 = 32-bit, EI_DATA 1 = little-endian
+        if bytes_at(bytes, 4, 2)? != [1, 1] {
+            return Err(ElfError::NotArm32);
+        }
+        let machine = u16_at(bytes, 18)?;
+        if machine != EM_ARM {
+            return Err(ElfError::Machine(machine));
+        }
+
+        let entry = u32_at(bytes, 24)?;
+        let ph_offset = usize_at(bytes, 28)?;
+        let sh_offset = usize_at(bytes, 32)?;
+        let ph_size = usize::from(u16_at(bytes, 42)?);
+        let ph_count = usize::from(u16_at(bytes, 44)?);
+        let sh_size = usize::from(u16_at(bytes, 46)?);
+        let sh_count = usize::from(u16_at(bytes, 48)?);
+
+        let mut segments = Vec::new();
+        for index in 0..ph_count {
+            let header = ph_offset + index * ph_size;
+            if u32_at(bytes, header)? != PT_LOAD {
+                continue;
+            }
+            let offset = usize_at(bytes, header + 4)?;
+            let address = u32_at(bytes, header + 12)?;
+            let file_size = usize_at(bytes, header + 16)?;
+            // Only the bytes in the file: the rest of the segment (.bss) is zeroed by the reset handler
+            if file_size > 0 {
+                segments.push(Segment {
+                    address,
+                    data: bytes_at(bytes, offset, file_size)?,
+                });
+            }
+        }
+
+        let mut symbols = Vec::new();
+        for index in 0..sh_count {
+            let header = sh_offset + index * sh_size;
+            if u32_at(bytes, header + 4)? != SHT_SYMTAB {
+                continue;
+            }
+            let table = bytes_at(bytes, usize_at(bytes, header + 16)?, usize_at(bytes, header + 20)?)?;
+            let entry_size = usize_at(bytes, header + 36)?.max(16);
+            // sh_link: the section holding the names
+            let strings = sh_offset + usize_at(bytes, header + 24)? * sh_size;
+            let names = bytes_at(bytes, usize_at(bytes, strings + 16)?, usize_at(bytes, strings + 20)?)?;
+
+            // Entry 0 is always the null symbol
+            for entry in table.chunks_exact(entry_size).skip(1) {
+                let name_offset = usize_at(entry, 0)?;
+                let name = names
+                    .get(name_offset..)
+                    .and_then(|rest| rest.split(|&byte| byte == 0).next())
+                    .and_then(|name| std::str::from_utf8(name).ok())
+                    .unwrap_or("");
+                if !name.is_empty() {
+                    symbols.push(Symbol {
+                        name,
+                        value: u32_at(entry, 4)?,
+                        size: u32_at(entry, 8)?,
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            entry,
+            segments,
+            symbols,
+        })
+    }
+
+    /// The symbol called `name`
+    pub fn symbol(&self, name: &str) -> Option<&Symbol<'a>> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+}
@@ -0,0 +1,166 @@
+//! A GPIO port (P0 or P1): its registers, the level on each pin, and a log of every change of level.
+//!
+//! A pin drives its `OUT` bit once its `DIR` bit makes it an output, through `PIN_CNF[n]`, `DIR`, `DIRSET` or
+//! `DIRCLR`. Until then it is an input, and nothing on the port drives it. Writes to `OUT`, `OUTSET` and
+//! `OUTCLR` only show on the pin when it is an output:
+//!
+//! ```text
+//! PIN_CNF[21] = 1    P0.21 Floating -> Low     (OUT.21 is 0)
+//! OUTSET = 1 << 21   P0.21 Low -> High
+//! OUTCLR = 1 << 21   P0.21 High -> Low
+//! ```
+//!
+//! Each change is a [`Transition`], stamped with the CPU cycle of the write. The pull resistors, drive strength
+//! and SENSE fields of `PIN_CNF` are stored but change nothing, and `LATCH` and `DETECTMODE` are not modelled.
+
+/// Pins on a port
+pub const PINS: usize = 32;
+
+// Register offsets from the port's base address
+const OUT: u32 = 0x504;
+const OUTSET: u32 = 0x508;
+const OUTCLR: u32 = 0x50C;
+const IN: u32 = 0x510;
+const DIR: u32 = 0x514;
+const DIRSET: u32 = 0x518;
+const DIRCLR: u32 = 0x51C;
+const PIN_CNF: u32 = 0x700;
+
+/// PIN_CNF after reset: input, with the input buffer disconnected
+const PIN_CNF_RESET: u32 = 0x2;
+
+/// What a pin is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Driven low: an output with its `OUT` bit clear
+    Low,
+    /// Driven high: an output with its `OUT` bit set
+    High,
+    /// Not driven: an input
+    Floating,
+}
+
+/// A pin changed level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    /// The CPU cycle of the register write that changed it
+    pub cycle: u64,
+    pub pin: u8,
+    /// The level from then on
+    pub level: Level,
+}
+
+/// One GPIO port
+#[derive(Debug, Clone)]
+pub struct Gpio {
+    out: u32,
+    dir: u32,
+    pin_cnf: [u32; PINS],
+    /// The levels outside circuits put on the pins, read through `IN` where a pin is an input
+    inputs: u32,
+    transitions: Vec<Transition>,
+}
+
+impl Gpio {
+    /// A port as it is after reset: every pin an input, `OUT` all zeros
+    pub fn new() -> Self {
+        Self {
+            out: 0,
+            dir: 0,
+            pin_cnf: [PIN_CNF_RESET; PINS],
+            inputs: 0,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Read the register at `offset` from the port's base address. `None` if there is none there.
+    pub fn read(&self, offset: u32) -> Option<u32> {
+        match offset {
+            OUT | OUTSET | OUTCLR => Some(self.out),
+            IN => Some((self.out & self.dir) | (self.inputs & !self.dir)),
+            DIR | DIRSET | DIRCLR => Some(self.dir),
+            _ => self.pin_cnf_index(offset).map(|pin| self.pin_cnf[pin]),
+        }
+    }
+
+    /// Write `value` to the register at `offset`, at CPU cycle `cycle`. `None` if there is no register there.
+    pub fn write(&mut self, offset: u32, value: u32, cycle: u64) -> Option<()> {
+        let (mut out, mut dir) = (self.out, self.dir);
+        match offset {
+            OUT => out = value,
+            OUTSET => out |= value,
+            OUTCLR => out &= !value,
+            IN => {}
+            DIR => dir = value,
+            DIRSET => dir |= value,
+            DIRCLR => dir &= !value,
+            _ => {
+                let pin = self.pin_cnf_index(offset)?;
+                self.pin_cnf[pin] = value;
+                dir = (dir & !(1 << pin)) | ((value & 1) << pin);
+            }
+        }
+        self.update(out, dir, cycle);
+        Some(())
+    }
+
+    /// Put `high` on input pin `pin`, as a button or another chip would: `IN` reads it while the pin is an input
+    pub fn set_input(&mut self, pin: u8, high: bool) {
+        self.inputs = (self.inputs & !(1 << pin)) | (u32::from(high) << pin);
+    }
+
+    /// What `pin` is doing now
+    pub fn level(&self, pin: u8) -> Level {
+        level(self.out, self.dir, pin)
+    }
+
+    /// `PIN_CNF[pin]`
+    pub fn pin_cnf(&self, pin: u8) -> u32 {
+        self.pin_cnf[usize::from(pin)]
+    }
+
+    /// Every change of level so far, oldest first
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// The changes of level of one pin, oldest first
+    pub fn pin_transitions(&self, pin: u8) -> impl Iterator<Item = &Transition> + '_ {
+        self.transitions.iter().filter(move |transition| transition.pin == pin)
+    }
+
+    fn pin_cnf_index(&self, offset: u32) -> Option<usize> {
+        let index = offset.checked_sub(PIN_CNF)? / 4;
+        (offset.is_multiple_of(4) && (index as usize) < PINS).then_some(index as usize)
+    }
+
+    /// Change `OUT` and `DIR`, and log the pins whose level changed
+    fn update(&mut self, out: u32, dir: u32, cycle: u64) {
+        for pin in 0..PINS as u8 {
+            let (before, after) = (level(self.out, self.dir, pin), level(out, dir, pin));
+            if before != after {
+                self.transitions.push(Transition {
+                    cycle,
+                    pin,
+                    level: after,
+                });
+            }
+        }
+        self.out = out;
+        self.dir = dir;
+    }
+}
+
+impl Default for Gpio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn level(out: u32, dir: u32, pin: u8) -> Level {
+    match (dir >> pin & 1, out >> pin & 1) {
+        (0, _) => Level::Floating,
+        (_, 0) => Level::Low,
+        _ => Level::High,
+    }
+}
@@ -0,0 +1,22 @@
+//! A Cortex-M4 emulator with just enough of the nRF52833 around it to run the bare-metal examples 02, 03 and 04
+//! on the PC, and check what they do to the pins.
+//!
+//! - [`elf`] reads the loadable segments and symbols out of the ELF file `cargo build` produces
+//! - [`bus`] is the memory map: flash, RAM, the GPIO ports, the timers and the system control space
+//! - [`gpio`] models a GPIO port, and records every change of level on its pins with the cycle it happened in
+//! - [`timer`] models the TIMER peripherals, counting from the CPU clock
+//! - [`cpu`] is the core: registers, flags, the Thumb-2 instruction set and its cycle counts
+//! - [`semihosting`] does what a program asks the debugger for with `bkpt 0xAB`: printing, and exiting
+//! - [`rtt`] reads what a program writes to its RTT channels, as `probe-rs` does
+//! - [`machine`] puts the core and the bus together, resets from the vector table and runs
+//!
+//! Unlike the other examples this one builds for the PC, and `cargo test` runs the tests directly.
+
+pub mod bus;
+pub mod cpu;
+pub mod elf;
+pub mod gpio;
+pub mod machine;
+pub mod rtt;
+pub mod semihosting;
+pub mod timer;
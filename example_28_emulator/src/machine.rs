@@ -0,0 +1,144 @@
+//! The core and the bus together: load an ELF file, reset from its vector table, and run.
+//!
+//! ```text
+//! let mut machine = Machine::from_elf(&bytes)?;      // segments to flash and RAM, then reset
+//! machine.run(64_000_000)?;                          // one second at 64 MHz
+//! machine.bus.p0.pin_transitions(21)                 // what the LED row did
+//! ```
+//!
+//! Reset is what the chip does: the stack pointer from word 0 of flash, the PC from word 1. Everything from there
+//! on, copying `.data` and zeroing `.bss` included, is the program's own code.
+//!
+//! A `bkpt 0xAB` is a semihosting call rather than a fault: the machine does it, see [`Semihosting`], and carries
+//! on after the breakpoint. Once the program has exited that way, [`Machine::run`] and [`Machine::run_until`]
+//! return at once.
+
+use std::fmt;
+
+use crate::{
+    bus::{Bus, BusError, FLASH_START},
+    cpu::{Cpu, Fault, PC, R0, R1},
+    elf::{Elf, ElfError},
+    semihosting::{Semihosting, BKPT_SEMIHOSTING},
+};
+
+/// Why a program could not be loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// The file could not be read
+    Elf(ElfError),
+    /// A segment goes somewhere other than flash or RAM
+    Outside { address: u32 },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Elf(error) => write!(f, "{}", error),
+            Self::Outside { address } => write!(f, "segment at {:#010x} is outside flash and RAM", address),
+        }
+    }
+}
+
+impl From<ElfError> for LoadError {
+    fn from(error: ElfError) -> Self {
+        Self::Elf(error)
+    }
+}
+
+/// A Cortex-M4 with the nRF52833's memory map
+#[derive(Debug, Clone, Default)]
+pub struct Machine {
+    pub cpu: Cpu,
+    pub bus: Bus,
+    pub semihosting: Semihosting,
+}
+
+impl Machine {
+    /// Erased flash and a core before reset: [`Bus::load`] a program, then [`Machine::reset`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every segment of an ELF file and reset
+    pub fn from_elf(bytes: &[u8]) -> Result<Self, LoadError> {
+        let elf = Elf::parse(bytes)?;
+        let mut machine = Self::new();
+        for segment in &elf.segments {
+            machine
+                .bus
+                .load(segment.address, segment.data)
+                .map_err(|BusError { address }| LoadError::Outside { address })?;
+        }
+        machine.reset();
+        Ok(machine)
+    }
+
+    /// Reset the core from the vector table at the start of flash. Memory and peripherals are left as they are.
+    pub fn reset(&mut self) {
+        // Flash is always there to read, erased or not
+        let mut vector = |index: u32| self.bus.read(FLASH_START + index * 4, 4).unwrap_or(u32::MAX);
+        let (stack_pointer, reset_vector) = (vector(0), vector(1));
+        self.cpu.reset(stack_pointer, reset_vector);
+    }
+
+    /// Run one instruction
+    pub fn step(&mut self) -> Result<(), Fault> {
+        let cycles = match self.cpu.step(&mut self.bus) {
+            Ok(cycles) => cycles,
+            Err(fault @ Fault::Breakpoint { pc, imm }) if imm == BKPT_SEMIHOSTING => {
+                self.semihosting_call(pc, fault)?
+            }
+            Err(fault) => return Err(fault),
+        };
+        self.bus.tick(cycles);
+        Ok(())
+    }
+
+    /// Do the semihosting call the `BKPT` at `pc` asks for, and move on past it. An operation the emulator does
+    /// not know stays the `fault` it was.
+    fn semihosting_call(&mut self, pc: u32, fault: Fault) -> Result<u32, Fault> {
+        let (operation, parameter) = (self.cpu.reg(R0), self.cpu.reg(R1));
+        let result = self
+            .semihosting
+            .call(&mut self.bus, operation, parameter)
+            .map_err(|BusError { address }| Fault::Bus { pc, address })?
+            .ok_or(fault)?;
+        self.cpu.set_reg(R0, result);
+        self.cpu.set_reg(PC, pc + 2);
+        // The core is halted while the host works: only the breakpoint itself takes time
+        Ok(1)
+    }
+
+    /// Whether the program has exited through semihosting
+    pub fn exited(&self) -> bool {
+        self.semihosting.exit.is_some()
+    }
+
+    /// Run for at least `cycles` more CPU cycles, or until the program exits
+    pub fn run(&mut self, cycles: u64) -> Result<(), Fault> {
+        let end = self.cycles() + cycles;
+        while self.cycles() < end && !self.exited() {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Run until `done` returns true, checking after every instruction, or until `max_cycles` have passed or the
+    /// program exits. Returns whether `done` did.
+    pub fn run_until(&mut self, max_cycles: u64, mut done: impl FnMut(&Self) -> bool) -> Result<bool, Fault> {
+        let end = self.cycles() + max_cycles;
+        while self.cycles() < end && !self.exited() {
+            self.step()?;
+            if done(self) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// CPU cycles since the program started
+    pub fn cycles(&self) -> u64 {
+        self.bus.now()
+    }
+}
@@ -0,0 +1,134 @@
+//! Run an example's ELF file and print what its pins did, or run a test binary and exit with its status
+//!
+//! ```text
+//! cargo run -- ../example_04_hello_world_asm/target/thumbv7em-none-eabihf/debug/main 2
+//! cargo run -- --test ../example_30_target_tests/target/thumbv7em-none-eabihf/debug/deps/gpio-...
+//! ```
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use example_28_emulator::{
+    cpu::CPU_HZ,
+    elf::Elf,
+    gpio::{Gpio, Transition},
+    machine::Machine,
+    rtt::{self, Rtt, RttError},
+};
+
+// ============================================================================
+// ARGUMENTS
+// ============================================================================
+
+const USAGE: &str = "usage: main [--test] <elf file> [seconds, default 1, or 60 with --test]
+
+--test  run a test binary: print only what it prints, and exit with the status it exits with through
+        semihosting; running out of time is a failure";
+
+/// How often the RTT up channel is emptied, in cycles: 1 ms, well before `rtt-target`'s 1 KiB buffer fills
+const RTT_POLL_CYCLES: u64 = CPU_HZ / 1000;
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1).peekable();
+    let test = args.next_if_eq("--test").is_some();
+    let Some(path) = args.next() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let seconds = match args.next().map(|seconds| seconds.parse::<f64>()) {
+        None if test => 60.0,
+        None => 1.0,
+        Some(Ok(seconds)) if seconds > 0.0 => seconds,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // ========================================================================
+    // LOAD AND RUN
+    // ========================================================================
+
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut machine = match Machine::from_elf(&bytes) {
+        Ok(machine) => machine,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let rtt = Elf::parse(&bytes)
+        .ok()
+        .and_then(|elf| elf.symbol(rtt::SYMBOL).map(|symbol| Rtt::new(symbol.value)));
+
+    // Run a millisecond at a time, printing what the program writes to RTT channel 0 and through semihosting
+    let end = (seconds * CPU_HZ as f64) as u64;
+    let mut result = Ok(());
+    while result.is_ok() && machine.cycles() < end && !machine.exited() {
+        result = machine.run(RTT_POLL_CYCLES.min(end - machine.cycles()));
+        print_output(&mut machine, rtt);
+    }
+
+    // ========================================================================
+    // REPORT
+    // ========================================================================
+
+    if !test {
+        print_transitions("P0", &machine.bus.p0);
+        print_transitions("P1", &machine.bus.p1);
+    }
+    match (result, machine.semihosting.exit) {
+        (Err(fault), _) => {
+            println!("stopped after {} cycles: {}", machine.cycles(), fault);
+            ExitCode::FAILURE
+        }
+        (Ok(()), Some(status)) => {
+            if !test {
+                println!("exited with status {} after {} cycles", status, machine.cycles());
+            }
+            ExitCode::from(status)
+        }
+        (Ok(()), None) if test => {
+            println!("did not exit in {} seconds", seconds);
+            ExitCode::FAILURE
+        }
+        (Ok(()), None) => {
+            println!("ran {} cycles", machine.cycles());
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Print, and forget, what the program has written since the last call
+fn print_output(machine: &mut Machine, rtt: Option<Rtt>) {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(&machine.semihosting.output);
+    machine.semihosting.output.clear();
+    if let Some(rtt) = rtt {
+        match rtt.read(&mut machine.bus, 0) {
+            Ok(data) => {
+                let _ = stdout.write_all(&data);
+            }
+            // Not set up yet, or a program without RTT that happens to have the symbol
+            Err(RttError::NotInitialised | RttError::NoChannel(_)) => {}
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+    let _ = stdout.flush();
+}
+
+fn print_transitions(port: &str, gpio: &Gpio) {
+    for &Transition { cycle, pin, level } in gpio.transitions() {
+        let ms = cycle as f64 * 1000.0 / CPU_HZ as f64;
+        println!("{:>12} cycles {:>10.3} ms  {}.{:02} {:?}", cycle, ms, port, pin, level);
+    }
+}
@@ -0,0 +1,111 @@
+//! RTT, read the way `probe-rs` reads it: from the program's RAM, while it runs.
+//!
+//! `rtt-target` puts a control block called `_SEGGER_RTT` in RAM. Each *up* channel in it is a ring buffer that
+//! the program writes and the debugger reads:
+//!
+//! ```text
+//! _SEGGER_RTT
+//!   +0   "SEGGER RTT\0\0\0\0\0\0"   written last by the program, so a half-initialised block is never read
+//!   +16  up channels, down channels (counts)
+//!   +24  up channel 0:   name, buffer, size, write offset, read offset, flags    (6 words)
+//!   +48  up channel 1:   ...
+//!        down channels:  the same
+//! ```
+//!
+//! Reading copies out the bytes from the read offset to the write offset, wrapping at the end of the buffer, and
+//! moves the read offset up to the write offset so the program can reuse the space.
+
+use std::fmt;
+
+use crate::bus::{Bus, BusError};
+
+/// What the control block starts with once it is initialised
+pub const ID: &[u8; 16] = b"SEGGER RTT\0\0\0\0\0\0";
+
+/// The symbol `rtt-target` gives the control block
+pub const SYMBOL: &str = "_SEGGER_RTT";
+
+const HEADER_SIZE: u32 = 24;
+const CHANNEL_SIZE: u32 = 24;
+
+// Offsets in a channel
+const BUFFER: u32 = 4;
+const SIZE: u32 = 8;
+const WRITE: u32 = 12;
+const READ: u32 = 16;
+
+/// Why a channel could not be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RttError {
+    /// The ID is not there yet: the program has not initialised RTT
+    NotInitialised,
+    /// The program has fewer up channels
+    NoChannel(u32),
+    /// The offsets are outside the buffer
+    Corrupt,
+    Bus(BusError),
+}
+
+impl fmt::Display for RttError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotInitialised => f.write_str("RTT control block not initialised"),
+            Self::NoChannel(channel) => write!(f, "no RTT up channel {}", channel),
+            Self::Corrupt => f.write_str("RTT channel offsets outside its buffer"),
+            Self::Bus(BusError { address }) => write!(f, "RTT control block points at {:#010x}", address),
+        }
+    }
+}
+
+impl From<BusError> for RttError {
+    fn from(error: BusError) -> Self {
+        Self::Bus(error)
+    }
+}
+
+/// A control block, at the address of its symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rtt {
+    pub address: u32,
+}
+
+impl Rtt {
+    pub fn new(address: u32) -> Self {
+        Self { address }
+    }
+
+    /// Take what the program has written to up channel `channel` since the last read
+    pub fn read(&self, bus: &mut Bus, channel: u32) -> Result<Vec<u8>, RttError> {
+        for (offset, &byte) in (0..).zip(ID) {
+            if bus.read(self.address + offset, 1)? != u32::from(byte) {
+                return Err(RttError::NotInitialised);
+            }
+        }
+        if channel >= bus.read(self.address + 16, 4)? {
+            return Err(RttError::NoChannel(channel));
+        }
+
+        let base = self.address + HEADER_SIZE + channel * CHANNEL_SIZE;
+        let buffer = bus.read(base + BUFFER, 4)?;
+        let size = bus.read(base + SIZE, 4)?;
+        let write = bus.read(base + WRITE, 4)?;
+        let read = bus.read(base + READ, 4)?;
+        if write >= size || read >= size {
+            return Err(RttError::Corrupt);
+        }
+
+        // At most two pieces: up to the end of the buffer, then from its beginning
+        let mut data = Vec::new();
+        let end = if write >= read { write } else { size };
+        for offset in read..end {
+            data.push(bus.read(buffer + offset, 1)? as u8);
+        }
+        if write < read {
+            for offset in 0..write {
+                data.push(bus.read(buffer + offset, 1)? as u8);
+            }
+        }
+        bus.write(base + READ, 4, write)?;
+        Ok(data)
+    }
+}
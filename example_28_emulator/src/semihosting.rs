@@ -0,0 +1,80 @@
+//! Semihosting: what a program asks the debugger for with `bkpt 0xAB`, done by the emulator instead.
+//!
+//! The program puts an operation number in `r0` and a parameter in `r1`, and the result comes back in `r0`. These
+//! are the operations test runners use:
+//!
+//! | Operation | `r0` | `r1` |
+//! |-----------|------|------|
+//! | `SYS_WRITEC` | `0x03` | Address of a byte to print |
+//! | `SYS_WRITE0` | `0x04` | Address of a NUL-terminated string to print |
+//! | `SYS_EXIT` | `0x18` | Reason: `0x20026` (application exit) is status 0, anything else status 1 |
+//! | `SYS_EXIT_EXTENDED` | `0x20` | Address of two words: the reason, and the status for an application exit |
+//!
+//! The exit statuses are the ones QEMU and `probe-rs run` exit with.
+
+use crate::bus::{Bus, BusError};
+
+/// The `BKPT` immediate that asks for semihosting on M-profile cores
+pub const BKPT_SEMIHOSTING: u8 = 0xAB;
+
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+
+/// The exit reason that means the program finished
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+
+/// Longest string `SYS_WRITE0` prints: a missing NUL stops here instead of reading to the end of RAM
+const MAX_STRING: u32 = 4096;
+
+/// What the program has done through semihosting
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Semihosting {
+    /// Everything it printed
+    pub output: Vec<u8>,
+    /// Its exit status, once it has asked to stop: 0 for success
+    pub exit: Option<u8>,
+}
+
+impl Semihosting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Do `operation` with `parameter`, reading the program's memory through `bus`. Returns the result for `r0`,
+    /// or `None` for an operation this emulator does not do.
+    pub fn call(&mut self, bus: &mut Bus, operation: u32, parameter: u32) -> Result<Option<u32>, BusError> {
+        match operation {
+            SYS_WRITEC => {
+                let byte = bus.read(parameter, 1)?;
+                self.output.push(byte as u8);
+            }
+            SYS_WRITE0 => {
+                for address in parameter..parameter.saturating_add(MAX_STRING) {
+                    match bus.read(address, 1)? {
+                        0 => break,
+                        byte => self.output.push(byte as u8),
+                    }
+                }
+            }
+            SYS_EXIT => self.exit = Some(status(parameter, 0)),
+            SYS_EXIT_EXTENDED => {
+                let reason = bus.read(parameter, 4)?;
+                let code = bus.read(parameter.wrapping_add(4), 4)?;
+                self.exit = Some(status(reason, code));
+            }
+            _ => return Ok(None),
+        }
+        Ok(Some(0))
+    }
+}
+
+/// The exit status for a `reason`, and for an application exit the program's own `code`
+fn status(reason: u32, code: u32) -> u8 {
+    if reason == ADP_STOPPED_APPLICATION_EXIT {
+        code as u8
+    } else {
+        1
+    }
+}
@@ -0,0 +1,182 @@
+//! A TIMER peripheral (TIMER0 to TIMER4), counting from the CPU clock.
+//!
+//! The timer's clock is 16 MHz divided by 2^`PRESCALER`, so one tick is `4 << PRESCALER` CPU cycles at 64 MHz.
+//! The counter itself cannot be read: `TASKS_CAPTURE[n]` copies it into `CC[n]`. When it reaches `CC[n]`,
+//! `EVENTS_COMPARE[n]` is set, and the `SHORTS` can clear or stop it:
+//!
+//! ```text
+//! PRESCALER = 4, CC[0] = 1000, SHORTS = COMPARE0_CLEAR
+//! ticks   0 1 2 ... 999 1000=0 1 ...      one tick every 64 cycles (1 µs)
+//!                        ▲ EVENTS_COMPARE[0] = 1, every 1000 µs
+//! ```
+//!
+//! There are no interrupts: `INTENSET` is stored, but nothing is delivered, so a program has to poll the events.
+//! All five timers have six CC registers, though on the chip TIMER0 to TIMER2 only have four.
+
+/// CC registers and compare events
+pub const CHANNELS: usize = 6;
+
+// Register offsets from the timer's base address
+const TASKS_START: u32 = 0x000;
+const TASKS_STOP: u32 = 0x004;
+const TASKS_COUNT: u32 = 0x008;
+const TASKS_CLEAR: u32 = 0x00C;
+const TASKS_SHUTDOWN: u32 = 0x010;
+const TASKS_CAPTURE: u32 = 0x040;
+const EVENTS_COMPARE: u32 = 0x140;
+const SHORTS: u32 = 0x200;
+const INTENSET: u32 = 0x304;
+const INTENCLR: u32 = 0x308;
+const MODE: u32 = 0x504;
+const BITMODE: u32 = 0x508;
+const PRESCALER: u32 = 0x510;
+const CC: u32 = 0x540;
+
+/// Reset values
+const BITMODE_16: u32 = 0;
+const PRESCALER_RESET: u32 = 4;
+
+/// One TIMER
+#[derive(Debug, Clone)]
+pub struct Timer {
+    running: bool,
+    count: u32,
+    /// CPU cycles since the last tick
+    cycles: u32,
+    mode: u32,
+    bitmode: u32,
+    prescaler: u32,
+    cc: [u32; CHANNELS],
+    compare: [u32; CHANNELS],
+    shorts: u32,
+    inten: u32,
+}
+
+impl Timer {
+    /// A timer as it is after reset: stopped, 16 bits, 1 MHz
+    pub fn new() -> Self {
+        Self {
+            running: false,
+            count: 0,
+            cycles: 0,
+            mode: 0,
+            bitmode: BITMODE_16,
+            prescaler: PRESCALER_RESET,
+            cc: [0; CHANNELS],
+            compare: [0; CHANNELS],
+            shorts: 0,
+            inten: 0,
+        }
+    }
+
+    /// Read the register at `offset` from the timer's base address. `None` if there is none there.
+    pub fn read(&self, offset: u32) -> Option<u32> {
+        match offset {
+            // Tasks read as 0
+            TASKS_START..=TASKS_SHUTDOWN if offset.is_multiple_of(4) => Some(0),
+            SHORTS => Some(self.shorts),
+            INTENSET | INTENCLR => Some(self.inten),
+            MODE => Some(self.mode),
+            BITMODE => Some(self.bitmode),
+            PRESCALER => Some(self.prescaler),
+            _ => {
+                if channel(offset, TASKS_CAPTURE).is_some() {
+                    Some(0)
+                } else if let Some(channel) = channel(offset, EVENTS_COMPARE) {
+                    Some(self.compare[channel])
+                } else {
+                    channel(offset, CC).map(|channel| self.cc[channel])
+                }
+            }
+        }
+    }
+
+    /// Write `value` to the register at `offset`. `None` if there is no register there.
+    pub fn write(&mut self, offset: u32, value: u32) -> Option<()> {
+        let task = value & 1 != 0;
+        match offset {
+            TASKS_START if task => self.running = true,
+            TASKS_STOP | TASKS_SHUTDOWN if task => self.running = false,
+            TASKS_COUNT if task && self.mode != 0 => self.increment(),
+            TASKS_CLEAR if task => {
+                self.count = 0;
+                self.cycles = 0;
+            }
+            TASKS_START..=TASKS_SHUTDOWN if offset.is_multiple_of(4) => {}
+            SHORTS => self.shorts = value,
+            INTENSET => self.inten |= value,
+            INTENCLR => self.inten &= !value,
+            MODE => self.mode = value & 3,
+            BITMODE => self.bitmode = value & 3,
+            PRESCALER => self.prescaler = (value & 0xF).min(9),
+            _ => {
+                if let Some(channel) = channel(offset, TASKS_CAPTURE) {
+                    if task {
+                        self.cc[channel] = self.count;
+                    }
+                } else if let Some(channel) = channel(offset, EVENTS_COMPARE) {
+                    self.compare[channel] = value & 1;
+                } else {
+                    let channel = channel(offset, CC)?;
+                    self.cc[channel] = value & self.mask();
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Let `cycles` CPU cycles pass
+    pub fn tick(&mut self, cycles: u32) {
+        // MODE 0 is Timer: the other modes count TASKS_COUNT instead
+        if !self.running || self.mode != 0 {
+            return;
+        }
+        let per_tick = 4 << self.prescaler;
+        self.cycles += cycles;
+        while self.cycles >= per_tick && self.running {
+            self.cycles -= per_tick;
+            self.increment();
+        }
+    }
+
+    /// The counter is running
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn mask(&self) -> u32 {
+        match self.bitmode {
+            0 => 0xFFFF,
+            1 => 0xFF,
+            2 => 0xFF_FFFF,
+            _ => u32::MAX,
+        }
+    }
+
+    fn increment(&mut self) {
+        self.count = self.count.wrapping_add(1) & self.mask();
+        for channel in 0..CHANNELS {
+            if self.count == self.cc[channel] {
+                self.compare[channel] = 1;
+                if self.shorts & (1 << channel) != 0 {
+                    self.count = 0;
+                }
+                if self.shorts & (1 << (8 + channel)) != 0 {
+                    self.running = false;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The channel whose register in the array at `base` is at `offset`
+fn channel(offset: u32, base: u32) -> Option<usize> {
+    let index = offset.checked_sub(base)? / 4;
+    (offset.is_multiple_of(4) && (index as usize) < CHANNELS).then_some(index as usize)
+}
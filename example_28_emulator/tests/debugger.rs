@@ -0,0 +1,171 @@
+//! Host tests for what a debugger would do: semihosting calls from small machine-code programs, and reading an
+//! RTT control block laid out in RAM the way `rtt-target` lays it out. Encodings from `llvm-mc`, as in
+//! `tests/thumb.rs`.
+
+use example_28_emulator::{
+    bus::RAM_START,
+    cpu::Fault,
+    machine::Machine,
+    rtt::{Rtt, RttError, ID},
+};
+
+const CODE: u32 = 0x100;
+const STACK: u32 = 0x2000_1000;
+
+/// Load `program` at `CODE`, and `data` at the start of RAM
+fn load(program: &[u16], data: &[u8]) -> Machine {
+    let mut machine = Machine::new();
+    let vectors: Vec<u8> = [STACK, CODE | 1].iter().flat_map(|word| word.to_le_bytes()).collect();
+    let code: Vec<u8> = program.iter().flat_map(|halfword| halfword.to_le_bytes()).collect();
+    machine.bus.load(0, &vectors).unwrap();
+    machine.bus.load(CODE, &code).unwrap();
+    machine.bus.load(RAM_START, data).unwrap();
+    machine.reset();
+    machine
+}
+
+// ============================================================================
+// SEMIHOSTING
+// ============================================================================
+
+#[test]
+fn prints_and_exits() {
+    let mut machine = load(
+        &[
+            0xF240, 0x0100, // movw r1, #0
+            0xF2C2, 0x0100, // movt r1, #0x2000
+            0x2004, // movs r0, #4           SYS_WRITE0
+            0xBEAB, // bkpt #0xab
+            0x2003, // movs r0, #3           SYS_WRITEC
+            0x3101, // adds r1, #1
+            0xBEAB, // bkpt #0xab
+            0x2018, // movs r0, #0x18        SYS_EXIT
+            0xF240, 0x0126, // movw r1, #0x26
+            0xF2C0, 0x0102, // movt r1, #2   ADP_Stopped_ApplicationExit
+            0xBEAB, // bkpt #0xab
+            0xE7FE, // b .
+        ],
+        b"ok\n\0",
+    );
+    // Stops at the exit, long before the cycles run out
+    machine.run(1_000_000).unwrap();
+    assert!(machine.exited());
+    assert!(machine.cycles() < 100);
+    assert_eq!(machine.semihosting.output, b"ok\nk");
+    assert_eq!(machine.semihosting.exit, Some(0));
+    // Past the last breakpoint, with 0 returned in r0
+    assert_eq!(machine.cpu.pc(), CODE + 30);
+    assert_eq!(machine.cpu.reg(0), 0);
+}
+
+#[test]
+fn other_exit_reasons_are_failures() {
+    let mut machine = load(
+        &[
+            0x2018, // movs r0, #0x18        SYS_EXIT
+            0xF240, 0x0123, // movw r1, #0x23
+            0xF2C0, 0x0102, // movt r1, #2   ADP_Stopped_RunTimeErrorUnknown
+            0xBEAB, // bkpt #0xab
+        ],
+        &[],
+    );
+    machine.run(1_000).unwrap();
+    assert_eq!(machine.semihosting.exit, Some(1));
+}
+
+#[test]
+fn extended_exit_has_a_status() {
+    let block: Vec<u8> = [0x20026u32, 3].iter().flat_map(|word| word.to_le_bytes()).collect();
+    let mut machine = load(
+        &[
+            0x2020, // movs r0, #0x20        SYS_EXIT_EXTENDED
+            0xF240, 0x0100, // movw r1, #0
+            0xF2C2, 0x0100, // movt r1, #0x2000
+            0xBEAB, // bkpt #0xab
+        ],
+        &block,
+    );
+    machine.run(1_000).unwrap();
+    assert_eq!(machine.semihosting.exit, Some(3));
+}
+
+#[test]
+fn unknown_operations_stay_breakpoints() {
+    let mut machine = load(
+        &[
+            0x2099, // movs r0, #0x99
+            0xBEAB, // bkpt #0xab
+        ],
+        &[],
+    );
+    assert_eq!(
+        machine.run(1_000),
+        Err(Fault::Breakpoint {
+            pc: CODE + 2,
+            imm: 0xAB,
+        })
+    );
+    assert!(!machine.exited());
+}
+
+// ============================================================================
+// RTT
+// ============================================================================
+
+/// Where the control block goes, and its up channel's buffer
+const BLOCK: u32 = RAM_START + 0x100;
+const BUFFER: u32 = RAM_START + 0x200;
+const SIZE: u32 = 8;
+
+/// A control block with one up channel and one down channel, and `text` in the up buffer from offset `read`
+fn rtt(text: &[u8], read: u32) -> Machine {
+    let mut machine = Machine::new();
+    machine.bus.load(BLOCK, ID).unwrap();
+    let words = [
+        1, 1, // up and down channels
+        0, BUFFER, SIZE, 0, read, 0, // up channel 0: name, buffer, size, write, read, flags
+    ];
+    for (index, word) in (0..).zip(words) {
+        machine.bus.write(BLOCK + 16 + index * 4, 4, word).unwrap();
+    }
+    for (index, &byte) in (0..).zip(text) {
+        machine
+            .bus
+            .write(BUFFER + (read + index) % SIZE, 1, u32::from(byte))
+            .unwrap();
+    }
+    let write = (read + text.len() as u32) % SIZE;
+    machine.bus.write(BLOCK + 24 + 12, 4, write).unwrap();
+    machine
+}
+
+#[test]
+fn rtt_reads_and_marks_read() {
+    let mut machine = rtt(b"test", 0);
+    let rtt = Rtt::new(BLOCK);
+    assert_eq!(rtt.read(&mut machine.bus, 0), Ok(b"test".to_vec()));
+    // The read offset moved up: nothing new
+    assert_eq!(machine.bus.read(BLOCK + 24 + 16, 4), Ok(4));
+    assert_eq!(rtt.read(&mut machine.bus, 0), Ok(Vec::new()));
+}
+
+#[test]
+fn rtt_reads_across_the_wrap() {
+    let mut machine = rtt(b"wrap", 6);
+    assert_eq!(Rtt::new(BLOCK).read(&mut machine.bus, 0), Ok(b"wrap".to_vec()));
+}
+
+#[test]
+fn rtt_before_initialisation_and_bad_channels() {
+    let mut machine = rtt(b"", 0);
+    let rtt = Rtt::new(BLOCK);
+    assert_eq!(rtt.read(&mut machine.bus, 1), Err(RttError::NoChannel(1)));
+
+    // An offset past the end of the buffer
+    machine.bus.write(BLOCK + 24 + 12, 4, SIZE).unwrap();
+    assert_eq!(rtt.read(&mut machine.bus, 0), Err(RttError::Corrupt));
+
+    // The program writes the ID last: until then, nothing is read
+    machine.bus.write(BLOCK, 1, 0).unwrap();
+    assert_eq!(rtt.read(&mut machine.bus, 0), Err(RttError::NotInitialised));
+}
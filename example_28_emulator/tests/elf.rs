@@ -0,0 +1,164 @@
+//! Host tests for the ELF reader, on a small file built here: two segments, one of them with a `.bss` tail, and
+//! a symbol table
+
+use example_28_emulator::{
+    elf::{Elf, ElfError, Segment},
+    machine::{LoadError, Machine},
+};
+
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+const SHDR_SIZE: usize = 40;
+
+/// An ARM ELF file with a vector table and code in flash, `.data` loaded at `0x100` for `0x2000_0000`, and
+/// the symbols `Reset` and `COUNTER`
+fn example() -> Vec<u8> {
+    let text: Vec<u8> = [0x2000_1000u32, 0x0000_0009, 0xE7FE_BF00]
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect();
+    let data = [1u8, 2, 3, 4];
+    let names = b"\0Reset\0COUNTER\0";
+    let mut symbols = vec![0u8; 16];
+    for (name, value, size) in [(1u32, 9u32, 4u32), (7, 0x2000_0000, 4)] {
+        for word in [name, value, size, 0] {
+            symbols.extend(word.to_le_bytes());
+        }
+    }
+
+    let text_offset = EHDR_SIZE + 2 * PHDR_SIZE;
+    let data_offset = text_offset + text.len();
+    let symbols_offset = data_offset + data.len();
+    let names_offset = symbols_offset + symbols.len();
+    let sh_offset = names_offset + names.len();
+
+    let mut file = Vec::new();
+    let half = |file: &mut Vec<u8>, value: u16| file.extend(value.to_le_bytes());
+    let word = |file: &mut Vec<u8>, value: usize| file.extend((value as u32).to_le_bytes());
+
+    // ELF header: 32-bit, little-endian, executable, ARM
+    file.extend(b"\x7fELF\x01\x01\x01\0\0\0\0\0\0\0\0\0");
+    half(&mut file, 2);
+    half(&mut file, 40);
+    word(&mut file, 1);
+    word(&mut file, 9);
+    word(&mut file, EHDR_SIZE);
+    word(&mut file, sh_offset);
+    word(&mut file, 0x0500_0400);
+    half(&mut file, EHDR_SIZE as u16);
+    half(&mut file, PHDR_SIZE as u16);
+    half(&mut file, 2);
+    half(&mut file, SHDR_SIZE as u16);
+    half(&mut file, 3);
+    half(&mut file, 0);
+
+    // Program headers: type, offset, vaddr, paddr, filesz, memsz, flags, align
+    for header in [
+        [1, text_offset, 0, 0, text.len(), text.len(), 5, 4],
+        [1, data_offset, 0x2000_0000, 0x100, data.len(), 16, 6, 4],
+    ] {
+        for value in header {
+            word(&mut file, value);
+        }
+    }
+
+    file.extend(&text);
+    file.extend(data);
+    file.extend(&symbols);
+    file.extend(names);
+
+    // Section headers: null, .symtab linked to .strtab, .strtab
+    // name, type, flags, addr, offset, size, link, info, addralign, entsize
+    for header in [
+        [0; 10],
+        [0, 2, 0, 0, symbols_offset, symbols.len(), 2, 1, 4, 16],
+        [0, 3, 0, 0, names_offset, names.len(), 0, 0, 1, 0],
+    ] {
+        for value in header {
+            word(&mut file, value);
+        }
+    }
+    file
+}
+
+#[test]
+fn segments_at_their_load_addresses() {
+    let file = example();
+    let elf = Elf::parse(&file).unwrap();
+    assert_eq!(elf.entry, 9);
+    assert_eq!(elf.segments.len(), 2);
+    assert_eq!(elf.segments[0].address, 0);
+    assert_eq!(elf.segments[0].data.len(), 12);
+    // .data goes to flash, for the reset handler to copy; only the bytes in the file are loaded
+    assert_eq!(
+        elf.segments[1],
+        Segment {
+            address: 0x100,
+            data: &[1, 2, 3, 4],
+        }
+    );
+}
+
+#[test]
+fn symbols_by_name() {
+    let file = example();
+    let elf = Elf::parse(&file).unwrap();
+    assert_eq!(elf.symbol("Reset").map(|symbol| symbol.value), Some(9));
+    assert_eq!(
+        elf.symbol("COUNTER").map(|symbol| (symbol.value, symbol.size)),
+        Some((0x2000_0000, 4))
+    );
+    assert_eq!(elf.symbol("main"), None);
+}
+
+#[test]
+fn machine_resets_from_the_vector_table() {
+    let mut machine = Machine::from_elf(&example()).unwrap();
+    assert_eq!(machine.cpu.pc(), 8);
+    assert_eq!(machine.cpu.reg(13), 0x2000_1000);
+    assert_eq!(machine.bus.read(0x100, 4), Ok(0x0403_0201));
+    // nop, then b . for ever
+    machine.run(1000).unwrap();
+    assert_eq!(machine.cpu.pc(), 10);
+}
+
+#[test]
+fn not_elf() {
+    assert_eq!(Elf::parse(b"MZ\x90\0"), Err(ElfError::NotElf));
+    assert_eq!(Elf::parse(b""), Err(ElfError::NotElf));
+}
+
+#[test]
+fn not_32_bit() {
+    let mut file = example();
+    file[4] = 2;
+    assert_eq!(Elf::parse(&file), Err(ElfError::NotArm32));
+}
+
+#[test]
+fn not_arm() {
+    let mut file = example();
+    // EM_X86_64
+    file[18] = 62;
+    assert_eq!(Elf::parse(&file), Err(ElfError::Machine(62)));
+}
+
+#[test]
+fn truncated() {
+    let file = example();
+    assert_eq!(Elf::parse(&file[..60]), Err(ElfError::Truncated));
+    // Without the string table the symbol table links to
+    assert_eq!(Elf::parse(&file[..file.len() - SHDR_SIZE]), Err(ElfError::Truncated));
+}
+
+#[test]
+fn segment_outside_memory() {
+    let mut file = example();
+    // The second segment's physical address, in the peripherals
+    let paddr = EHDR_SIZE + PHDR_SIZE + 12;
+    file[paddr..paddr + 4].copy_from_slice(&0x5000_0000u32.to_le_bytes());
+    assert_eq!(
+        Machine::from_elf(&file).err(),
+        Some(LoadError::Outside { address: 0x5000_0000 })
+    );
+}
@@ -0,0 +1,147 @@
+//! The examples themselves: build 02, 03 and 04 for the micro:bit as `cargo build` in their own directories
+//! would, run each ELF file, and check that the LED pins do what the code says.
+//!
+//! Each example configures P0.21 (row 1) and P0.28 (column 1) as outputs, drives the column low once, then
+//! toggles the row with a busy-wait delay between. What differs is the delays: 02 waits as long low as high,
+//! 03 twice as long high as low, and 04 ten times as long low as high.
+//!
+//! Example 04 reads its delays from Rust statics, so it also checks its assembly reset handler: RAM is empty
+//! when the machine starts, and only `Reset` puts the statics' values there.
+//!
+//! Building needs the target: `rustup target add thumbv7em-none-eabihf`.
+
+use std::{fs, path::PathBuf, process::Command};
+
+use example_28_emulator::{
+    cpu::CPU_HZ,
+    elf::Elf,
+    gpio::{Level, Transition},
+    machine::Machine,
+};
+
+const ROW1: u8 = 21;
+const COL1: u8 = 28;
+
+/// Enough for the slowest example, 02 in a debug build, to toggle its row four times
+const MAX_CYCLES: u64 = 60 * CPU_HZ;
+
+/// Build `example` and read its ELF file
+fn build(example: &str) -> Vec<u8> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join(example);
+    // The example's .cargo/config.toml picks the target and the linker script
+    let status = Command::new(env!("CARGO"))
+        .arg("build")
+        .current_dir(&dir)
+        .env_remove("CARGO_TARGET_DIR")
+        .env_remove("CARGO_BUILD_TARGET")
+        .status()
+        .expect("cargo should run");
+    assert!(
+        status.success(),
+        "building {} failed: is the target installed? `rustup target add thumbv7em-none-eabihf`",
+        example
+    );
+    let elf = dir.join("target/thumbv7em-none-eabihf/debug/main");
+    fs::read(&elf).unwrap_or_else(|error| panic!("{}: {}", elf.display(), error))
+}
+
+/// Run `example` until P0.21 has changed level `transitions` times, and return what P0.21 and P0.28 did
+fn run(example: &str, transitions: usize) -> (Vec<Transition>, Vec<Transition>) {
+    let mut machine = Machine::from_elf(&build(example)).unwrap();
+    let done = machine
+        .run_until(MAX_CYCLES, |machine| {
+            machine.bus.p0.pin_transitions(ROW1).count() >= transitions
+        })
+        .unwrap_or_else(|fault| panic!("{}: {}", example, fault));
+    assert!(
+        done,
+        "{}: P0.21 changed fewer than {} times in {} cycles",
+        example, transitions, MAX_CYCLES
+    );
+
+    let row = machine.bus.p0.pin_transitions(ROW1).copied().collect();
+    let column = machine.bus.p0.pin_transitions(COL1).copied().collect();
+    (row, column)
+}
+
+/// Check the pins, and return how long the row stayed low and how long high, in cycles, each time
+fn blink(example: &str) -> (Vec<u64>, Vec<u64>) {
+    let (row, column) = run(example, 5);
+
+    // The column goes from input to low output, once
+    assert_eq!(column.len(), 1, "{}: {:?}", example, column);
+    assert_eq!(column[0].level, Level::Low);
+
+    // The row goes from input to low output, then toggles
+    let levels: Vec<Level> = row.iter().map(|transition| transition.level).collect();
+    assert_eq!(
+        levels,
+        [Level::Low, Level::High, Level::Low, Level::High, Level::Low],
+        "{}",
+        example
+    );
+
+    let durations: Vec<u64> = row.windows(2).map(|pair| pair[1].cycle - pair[0].cycle).collect();
+    let low = durations.iter().step_by(2).copied().collect();
+    let high = durations.iter().skip(1).step_by(2).copied().collect();
+    (low, high)
+}
+
+/// Every time the same length, give or take 1%
+fn steady(durations: &[u64]) -> f64 {
+    let first = durations[0] as f64;
+    for &duration in durations {
+        assert!((duration as f64 - first).abs() < first / 100.0, "{:?}", durations);
+    }
+    first
+}
+
+#[test]
+fn example_02_blinks_evenly() {
+    let (low, high) = blink("example_02_hello_world_minimal_dependencies");
+    let ratio = steady(&high) / steady(&low);
+    assert!((0.9..1.1).contains(&ratio), "high / low = {}", ratio);
+}
+
+#[test]
+fn example_03_stays_high_twice_as_long() {
+    let (low, high) = blink("example_03_hello_world_no_dependencies");
+    // Each loop iteration is the same code, but the loop overheads differ a little
+    let ratio = steady(&high) / steady(&low);
+    assert!((1.8..2.5).contains(&ratio), "high / low = {}", ratio);
+}
+
+#[test]
+fn example_04_stays_low_ten_times_as_long() {
+    let (low, high) = blink("example_04_hello_world_asm");
+    let ratio = steady(&low) / steady(&high);
+    assert!((9.9..10.1).contains(&ratio), "low / high = {}", ratio);
+    // SUBS and a taken BNE: 4 cycles for each of the 8 000 000 iterations
+    assert_eq!(low[0] / 1_000_000, 32);
+}
+
+#[test]
+fn example_04_sets_up_ram_before_main() {
+    let bytes = build("example_04_hello_world_asm");
+    let elf = Elf::parse(&bytes).unwrap();
+    let on_delay = elf.symbol("ON_DELAY").expect("ON_DELAY is #[no_mangle]").value;
+    let off_delay = elf.symbol("OFF_DELAY").expect("OFF_DELAY is #[no_mangle]").value;
+    assert!(on_delay >= 0x2000_0000 && off_delay >= 0x2000_0000, "statics in RAM");
+
+    let mut machine = Machine::from_elf(&bytes).unwrap();
+    assert_eq!(machine.bus.read(on_delay, 4), Ok(0));
+    // main's first write: by then Reset has done everything it does
+    let started = machine
+        .run_until(CPU_HZ, |machine| machine.bus.p0.pin_transitions(ROW1).count() > 0)
+        .unwrap();
+    assert!(started);
+
+    // .data copied from flash, .bss zeroed and then set by the .init_array constructor
+    assert_eq!(machine.bus.read(on_delay, 4), Ok(8_000_000));
+    assert_eq!(machine.bus.read(off_delay, 4), Ok(800_000));
+    // CPACR: CP10 and CP11 full access
+    assert_eq!(
+        machine.bus.read(0xE000_ED88, 4).map(|cpacr| cpacr & (0xF << 20)),
+        Ok(0xF << 20)
+    );
+}
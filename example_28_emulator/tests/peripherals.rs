@@ -0,0 +1,205 @@
+//! Host tests for the memory map and the peripherals, driven through the bus as the CPU would drive them
+
+use example_28_emulator::{
+    bus::{Bus, BusError, P0_BASE, P1_BASE, RAM_START, TIMER_BASES},
+    gpio::{Level, Transition},
+};
+
+const PIN_CNF: u32 = 0x700;
+const OUTSET: u32 = 0x508;
+const OUTCLR: u32 = 0x50C;
+const OUT: u32 = 0x504;
+const IN: u32 = 0x510;
+const DIRSET: u32 = 0x518;
+
+// TIMER registers
+const TASKS_START: u32 = 0x000;
+const TASKS_CLEAR: u32 = 0x00C;
+const TASKS_CAPTURE: u32 = 0x040;
+const EVENTS_COMPARE: u32 = 0x140;
+const SHORTS: u32 = 0x200;
+const BITMODE: u32 = 0x508;
+const PRESCALER: u32 = 0x510;
+const CC: u32 = 0x540;
+
+// ============================================================================
+// MEMORY
+// ============================================================================
+
+#[test]
+fn ram_is_little_endian_at_any_alignment() {
+    let mut bus = Bus::new();
+    bus.write(RAM_START + 1, 4, 0x1122_3344).unwrap();
+    assert_eq!(bus.read(RAM_START + 1, 1), Ok(0x44));
+    assert_eq!(bus.read(RAM_START + 3, 2), Ok(0x1122));
+    assert_eq!(bus.read(RAM_START, 4), Ok(0x2233_4400));
+}
+
+#[test]
+fn flash_is_loaded_not_written() {
+    let mut bus = Bus::new();
+    // Erased flash reads as all ones
+    assert_eq!(bus.read(0x100, 4), Ok(u32::MAX));
+    bus.load(0x100, &[1, 2, 3, 4]).unwrap();
+    assert_eq!(bus.read(0x100, 4), Ok(0x0403_0201));
+    assert_eq!(bus.write(0x100, 4, 0), Err(BusError { address: 0x100 }));
+}
+
+#[test]
+fn nothing_past_the_end_of_ram() {
+    let mut bus = Bus::new();
+    let end = RAM_START + 128 * 1024;
+    assert!(bus.read(end - 4, 4).is_ok());
+    assert_eq!(bus.read(end - 2, 4), Err(BusError { address: end - 2 }));
+    assert_eq!(bus.load(end - 2, &[0; 4]), Err(BusError { address: end - 2 }));
+}
+
+#[test]
+fn peripherals_are_aligned_words_only() {
+    let mut bus = Bus::new();
+    assert!(bus.write(P0_BASE + OUTSET, 4, 1).is_ok());
+    assert!(bus.write(P0_BASE + OUTSET, 1, 1).is_err());
+    assert!(bus.read(P0_BASE + OUTSET + 2, 4).is_err());
+    // Between the registers there is nothing
+    assert!(bus.read(P0_BASE + 0x600, 4).is_err());
+}
+
+#[test]
+fn system_control_space_keeps_what_is_written() {
+    let mut bus = Bus::new();
+    // CPACR: full access to CP10 and CP11, the FPU
+    bus.write(0xE000_ED88, 4, 0xF << 20).unwrap();
+    assert_eq!(bus.read(0xE000_ED88, 4), Ok(0xF << 20));
+}
+
+// ============================================================================
+// GPIO
+// ============================================================================
+
+#[test]
+fn led_row_toggles() {
+    let mut bus = Bus::new();
+    bus.write(P0_BASE + PIN_CNF + 21 * 4, 4, 1).unwrap();
+    bus.tick(100);
+    bus.write(P0_BASE + OUTSET, 4, 1 << 21).unwrap();
+    bus.tick(50);
+    bus.write(P0_BASE + OUTCLR, 4, 1 << 21).unwrap();
+
+    assert_eq!(
+        bus.p0.transitions(),
+        [
+            Transition {
+                cycle: 0,
+                pin: 21,
+                level: Level::Low,
+            },
+            Transition {
+                cycle: 100,
+                pin: 21,
+                level: Level::High,
+            },
+            Transition {
+                cycle: 150,
+                pin: 21,
+                level: Level::Low,
+            },
+        ]
+    );
+    assert_eq!(bus.p0.pin_cnf(21), 1);
+    assert_eq!(bus.read(P0_BASE + OUT, 4), Ok(0));
+}
+
+#[test]
+fn inputs_do_not_drive_the_pin() {
+    let mut bus = Bus::new();
+    bus.write(P0_BASE + OUTSET, 4, 1 << 28).unwrap();
+    assert_eq!(bus.p0.level(28), Level::Floating);
+    assert!(bus.p0.transitions().is_empty());
+
+    // Once it is an output, the OUT bit already set shows
+    bus.write(P0_BASE + DIRSET, 4, 1 << 28).unwrap();
+    assert_eq!(bus.p0.level(28), Level::High);
+}
+
+#[test]
+fn in_reads_outputs_and_outside_levels() {
+    let mut bus = Bus::new();
+    bus.p0.set_input(14, true);
+    bus.write(P0_BASE + PIN_CNF + 21 * 4, 4, 1).unwrap();
+    bus.write(P0_BASE + OUTSET, 4, 1 << 21).unwrap();
+    assert_eq!(bus.read(P0_BASE + IN, 4), Ok(1 << 21 | 1 << 14));
+}
+
+#[test]
+fn ports_are_separate() {
+    let mut bus = Bus::new();
+    bus.write(P1_BASE + PIN_CNF + 5 * 4, 4, 1).unwrap();
+    assert_eq!(bus.p1.level(5), Level::Low);
+    assert_eq!(bus.p0.level(5), Level::Floating);
+    assert_eq!(bus.p1.pin_transitions(5).count(), 1);
+    assert!(bus.p0.transitions().is_empty());
+}
+
+// ============================================================================
+// TIMER
+// ============================================================================
+
+fn timer(bus: &mut Bus, offset: u32, value: u32) {
+    bus.write(TIMER_BASES[0] + offset, 4, value).unwrap();
+}
+
+#[test]
+fn timer_counts_microseconds() {
+    let mut bus = Bus::new();
+    timer(&mut bus, TASKS_START, 1);
+    // One tick every 64 cycles at the reset prescaler of 4
+    bus.tick(64 * 10 + 63);
+    timer(&mut bus, TASKS_CAPTURE, 1);
+    assert_eq!(bus.read(TIMER_BASES[0] + CC, 4), Ok(10));
+}
+
+#[test]
+fn timer_compare_event_and_clear_short() {
+    let mut bus = Bus::new();
+    timer(&mut bus, PRESCALER, 0);
+    timer(&mut bus, CC, 5);
+    timer(&mut bus, SHORTS, 1);
+    timer(&mut bus, TASKS_START, 1);
+
+    // 16 MHz: a tick every 4 cycles
+    bus.tick(4 * 4);
+    assert_eq!(bus.read(TIMER_BASES[0] + EVENTS_COMPARE, 4), Ok(0));
+    bus.tick(4);
+    assert_eq!(bus.read(TIMER_BASES[0] + EVENTS_COMPARE, 4), Ok(1));
+
+    // The short cleared the counter when it matched
+    timer(&mut bus, EVENTS_COMPARE, 0);
+    bus.tick(4 * 2);
+    timer(&mut bus, TASKS_CAPTURE + 4, 1);
+    assert_eq!(bus.read(TIMER_BASES[0] + CC + 4, 4), Ok(2));
+}
+
+#[test]
+fn timer_wraps_at_its_bit_width() {
+    let mut bus = Bus::new();
+    // 8 bits
+    timer(&mut bus, BITMODE, 1);
+    timer(&mut bus, PRESCALER, 0);
+    timer(&mut bus, TASKS_START, 1);
+    bus.tick(4 * 258);
+    timer(&mut bus, TASKS_CAPTURE, 1);
+    assert_eq!(bus.read(TIMER_BASES[0] + CC, 4), Ok(2));
+
+    timer(&mut bus, TASKS_CLEAR, 1);
+    timer(&mut bus, TASKS_CAPTURE, 1);
+    assert_eq!(bus.read(TIMER_BASES[0] + CC, 4), Ok(0));
+}
+
+#[test]
+fn stopped_timer_does_not_count() {
+    let mut bus = Bus::new();
+    bus.tick(1_000_000);
+    assert!(!bus.timers[0].is_running());
+    timer(&mut bus, TASKS_CAPTURE, 1);
+    assert_eq!(bus.read(TIMER_BASES[0] + CC, 4), Ok(0));
+}
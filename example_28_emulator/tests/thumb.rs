@@ -0,0 +1,485 @@
+//! Host tests for the instruction set: small machine-code programs, each ending in `bkpt`, and the registers
+//! they leave behind. The encodings come from `llvm-mc -triple=thumbv7em-none-eabi`, with the source alongside.
+
+use example_28_emulator::{
+    cpu::{Fault, LR, SP},
+    machine::Machine,
+};
+
+/// Where the programs go, after the two-word vector table
+const CODE: u32 = 0x100;
+/// The initial stack pointer
+const STACK: u32 = 0x2000_1000;
+const RAM: u32 = 0x2000_0000;
+
+/// Load `program` at `CODE` and run it to its fault
+fn fault(program: &[u16]) -> (Machine, Fault) {
+    let mut machine = Machine::new();
+    let vectors: Vec<u8> = [STACK, CODE | 1].iter().flat_map(|word| word.to_le_bytes()).collect();
+    let code: Vec<u8> = program.iter().flat_map(|halfword| halfword.to_le_bytes()).collect();
+    machine.bus.load(0, &vectors).unwrap();
+    machine.bus.load(CODE, &code).unwrap();
+    machine.reset();
+    let fault = machine.run(1_000_000).expect_err("the program should stop");
+    (machine, fault)
+}
+
+/// Load `program` and run it to its `bkpt`
+fn run(program: &[u16]) -> Machine {
+    let (machine, fault) = fault(program);
+    assert!(matches!(fault, Fault::Breakpoint { .. }), "{}", fault);
+    machine
+}
+
+fn regs(machine: &Machine, from: usize, count: usize) -> Vec<u32> {
+    (from..from + count).map(|n| machine.cpu.reg(n)).collect()
+}
+
+// ============================================================================
+// DATA PROCESSING
+// ============================================================================
+
+#[test]
+fn flags() {
+    let machine = run(&[
+        0x2000, // movs r0, #0
+        0x3801, // subs r0, #1
+        0xF3EF, 0x8100, // mrs r1, apsr
+        0x227F, // movs r2, #0x7f
+        0x0612, // lsls r2, r2, #24
+        0x1892, // adds r2, r2, r2
+        0xF3EF, 0x8300, // mrs r3, apsr
+        0x4280, // cmp r0, r0
+        0xF3EF, 0x8400, // mrs r4, apsr
+        0x2501, // movs r5, #1
+        0x426D, // rsbs r5, r5, #0
+        0xBE00, // bkpt #0
+    ]);
+    // 0 - 1 borrows: N, and not C
+    assert_eq!(machine.cpu.reg(1), 0x8000_0000);
+    // 0x7F000000 + 0x7F000000 overflows into the sign bit: N and V
+    assert_eq!(machine.cpu.reg(3), 0x9000_0000);
+    // Equal: Z, and C for no borrow
+    assert_eq!(machine.cpu.reg(4), 0x6000_0000);
+    assert_eq!(machine.cpu.reg(5), u32::MAX);
+}
+
+#[test]
+fn shifts() {
+    let machine = run(&[
+        0x2081, // movs r0, #0x81
+        0x0841, // lsrs r1, r0, #1
+        0xF3EF, 0x8200, // mrs r2, apsr
+        0x0643, // lsls r3, r0, #25
+        0x111C, // asrs r4, r3, #4
+        0x2508, // movs r5, #8
+        0x41E8, // rors r0, r5
+        0xEA4F, 0x0633, // mov.w r6, r3, rrx
+        0xFA03, 0xF705, // lsl.w r7, r3, r5
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(machine.cpu.reg(1), 0x40);
+    // The 1 shifted out is the carry
+    assert_eq!(machine.cpu.reg(2), 0x2000_0000);
+    assert_eq!(regs(&machine, 3, 2), [0x0200_0000, 0x0020_0000]);
+    assert_eq!(machine.cpu.reg(0), 0x8100_0000);
+    // RRX brings in the carry the ROR left
+    assert_eq!(machine.cpu.reg(6), 0x8100_0000);
+    assert_eq!(machine.cpu.reg(7), 0);
+}
+
+#[test]
+fn immediates() {
+    let machine = run(&[
+        0xF04F, 0x20FF, // mov.w r0, #0xFF00FF00
+        0xF04F, 0x11AB, // mov.w r1, #0x00AB00AB
+        0xF04F, 0x32AB, // mov.w r2, #0xABABABAB
+        0xF44F, 0x337F, // mov.w r3, #0x3FC00
+        0xF040, 0x04FF, // orr r4, r0, #0xFF
+        0xF022, 0x05FF, // bic r5, r2, #0xFF
+        0xF06F, 0x0600, // mvn r6, #0
+        0xF245, 0x6778, // movw r7, #0x5678
+        0xF2C1, 0x2734, // movt r7, #0x1234
+        0xF607, 0x78FF, // addw r8, r7, #0xFFF
+        0xF2A7, 0x6978, // subw r9, r7, #0x678
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(
+        regs(&machine, 0, 10),
+        [
+            0xFF00_FF00,
+            0x00AB_00AB,
+            0xABAB_ABAB,
+            0x0003_FC00,
+            0xFF00_FFFF,
+            0xABAB_AB00,
+            0xFFFF_FFFF,
+            0x1234_5678,
+            0x1234_6677,
+            0x1234_5000,
+        ]
+    );
+}
+
+#[test]
+fn it_blocks() {
+    let machine = run(&[
+        0x2005, // movs r0, #5
+        0x2500, // movs r5, #0
+        0x2805, // cmp r0, #5
+        0xBF0C, // ite eq
+        0x2101, // moveq r1, #1
+        0x2102, // movne r1, #2
+        0xBF1C, // itt ne
+        0x2201, // movne r2, #1
+        0x2301, // movne r3, #1
+        0xBF08, // it eq
+        0x1F04, // subeq r4, r0, #4
+        0xF3EF, 0x8600, // mrs r6, apsr
+        0xBFC8, // it gt
+        0x3501, // addgt r5, #1
+        0xBE00, // bkpt #0
+    ]);
+    // ITE EQ ran the first move only, ITT NE neither
+    assert_eq!(regs(&machine, 1, 3), [1, 0, 0]);
+    // A 16-bit SUB inside an IT block leaves the flags alone, so Z from the CMP is still set...
+    assert_eq!(machine.cpu.reg(4), 1);
+    assert_eq!(machine.cpu.reg(6), 0x6000_0000);
+    // ...and GT fails
+    assert_eq!(machine.cpu.reg(5), 0);
+}
+
+#[test]
+fn bitfields_and_bytes() {
+    let machine = run(&[
+        0xF643, 0x405A, // movw r0, #0x3C5A
+        0xF2C8, 0x0000, // movt r0, #0x8000
+        0xF3C0, 0x1107, // ubfx r1, r0, #4, #8
+        0xF340, 0x7203, // sbfx r2, r0, #28, #4
+        0xF04F, 0x33FF, // mov.w r3, #0xFFFFFFFF
+        0xF360, 0x230F, // bfi r3, r0, #8, #8
+        0xF36F, 0x0303, // bfc r3, #0, #4
+        0xFAB0, 0xF480, // clz r4, r0
+        0xFA90, 0xF5A0, // rbit r5, r0
+        0xBA06, // rev r6, r0
+        0xBA47, // rev16 r7, r0
+        0xFA90, 0xF8B0, // revsh r8, r0
+        0xFA0F, 0xF980, // sxth r9, r0
+        0xFA5F, 0xFA80, // uxtb r10, r0
+        0xF300, 0x0B07, // ssat r11, #8, r0
+        0xF3A0, 0x2C08, // usat r12, #8, r0, asr #8
+        0xF3EF, 0x8E00, // mrs lr, apsr
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(machine.cpu.reg(1), 0xC5);
+    assert_eq!(machine.cpu.reg(2), 0xFFFF_FFF8);
+    assert_eq!(machine.cpu.reg(3), 0xFFFF_5AF0);
+    assert_eq!(machine.cpu.reg(4), 0);
+    assert_eq!(machine.cpu.reg(5), 0x5A3C_0001);
+    assert_eq!(regs(&machine, 6, 5), [0x5A3C_0080, 0x0080_5A3C, 0x5A3C, 0x3C5A, 0x5A]);
+    // Both saturate, and set Q
+    assert_eq!(machine.cpu.reg(11), 0xFFFF_FF80);
+    assert_eq!(machine.cpu.reg(12), 0);
+    assert_eq!(machine.cpu.reg(LR), 0x0800_0000);
+}
+
+#[test]
+fn multiply_and_divide() {
+    let machine = run(&[
+        0x2007, // movs r0, #7
+        0xF06F, 0x0102, // mvn r1, #2
+        0xFB00, 0xF201, // mul r2, r0, r1
+        0xFB00, 0x1300, // mla r3, r0, r0, r1
+        0xFB00, 0x1410, // mls r4, r0, r0, r1
+        0xFBA1, 0x5601, // umull r5, r6, r1, r1
+        0xFB81, 0x7801, // smull r7, r8, r1, r1
+        0xFB94, 0xF9F0, // sdiv r9, r4, r0
+        0xFBB1, 0xFAF0, // udiv r10, r1, r0
+        0xF05F, 0x0C00, // movs r12, #0
+        0xFBB0, 0xFBFC, // udiv r11, r0, r12
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(machine.cpu.reg(2), -21i32 as u32);
+    assert_eq!(machine.cpu.reg(3), 46);
+    assert_eq!(machine.cpu.reg(4), -52i32 as u32);
+    // 0xFFFFFFFD squared, unsigned and signed
+    assert_eq!(regs(&machine, 5, 2), [9, 0xFFFF_FFFA]);
+    assert_eq!(regs(&machine, 7, 2), [9, 0]);
+    // Division rounds towards zero, and dividing by zero gives zero
+    assert_eq!(machine.cpu.reg(9), -7i32 as u32);
+    assert_eq!(machine.cpu.reg(10), 0x2492_4924);
+    assert_eq!(machine.cpu.reg(11), 0);
+}
+
+// ============================================================================
+// BRANCHES
+// ============================================================================
+
+#[test]
+fn loop_cycles() {
+    let machine = run(&[
+        0x200A, // movs r0, #10
+        // loop:
+        0x3801, // subs r0, #1
+        0xD1FD, // bne loop
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(machine.cpu.reg(0), 0);
+    // MOVS, then ten SUBS, nine taken branches at 1 + 2 cycles and one not taken
+    assert_eq!(machine.cycles(), 1 + 10 + 9 * 3 + 1);
+}
+
+#[test]
+fn call_and_return() {
+    let machine = run(&[
+        0x2003, // movs r0, #3
+        0x2409, // movs r4, #9
+        0xF000, 0xF802, // bl double
+        0x3001, // adds r0, #1
+        0xBE00, // bkpt #0
+        // double:
+        0xB510, // push {r4, lr}
+        0x1804, // adds r4, r0, r0
+        0x4620, // mov r0, r4
+        0xBD10, // pop {r4, pc}
+    ]);
+    assert_eq!(machine.cpu.reg(0), 7);
+    // PUSH and POP saved and restored it
+    assert_eq!(machine.cpu.reg(4), 9);
+    assert_eq!(machine.cpu.reg(SP), STACK);
+    assert_eq!(machine.cpu.reg(LR), (CODE + 8) | 1);
+}
+
+#[test]
+fn compare_and_branch_on_zero() {
+    let machine = run(&[
+        0x2000, // movs r0, #0
+        0x2100, // movs r1, #0
+        0xB100, // cbz r0, zero
+        0x2101, // movs r1, #1
+        // zero:
+        0xB900, // cbnz r0, done
+        0x2202, // movs r2, #2
+        // done:
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(regs(&machine, 1, 2), [0, 2]);
+}
+
+#[test]
+fn table_branch() {
+    let machine = run(&[
+        0x2002, // movs r0, #2
+        0xE8DF, 0xF010, // tbh [pc, r0, lsl #1]
+        // table:
+        0x0003, // .short (case0 - table) / 2
+        0x0005, // .short (case1 - table) / 2
+        0x0007, // .short (case2 - table) / 2
+        // case0:
+        0x210A, // movs r1, #10
+        0xBE00, // bkpt #0
+        // case1:
+        0x210B, // movs r1, #11
+        0xBE00, // bkpt #0
+        // case2:
+        0x210C, // movs r1, #12
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(machine.cpu.reg(1), 12);
+}
+
+// ============================================================================
+// MEMORY
+// ============================================================================
+
+#[test]
+fn loads_and_stores() {
+    let mut machine = run(&[
+        0xF240, 0x0700, // movw r7, #0
+        0xF2C2, 0x0700, // movt r7, #0x2000
+        0xF245, 0x6078, // movw r0, #0x5678
+        0xF2C1, 0x2034, // movt r0, #0x1234
+        0x6038, // str r0, [r7]
+        0x7839, // ldrb r1, [r7]
+        0x78FA, // ldrb r2, [r7, #3]
+        0x887B, // ldrh r3, [r7, #2]
+        0x2480, // movs r4, #0x80
+        0x713C, // strb r4, [r7, #4]
+        0xF997, 0x5004, // ldrsb.w r5, [r7, #4]
+        0xF847, 0x0F08, // str r0, [r7, #8]!
+        0xF857, 0x6B04, // ldr r6, [r7], #4
+        0xF8DF, 0x800C, // ldr r8, literal
+        0xF04F, 0x39FF, // mov.w r9, #-1
+        0xF857, 0xA029, // ldr.w r10, [r7, r9, lsl #2]
+        0xBE00, // bkpt #0
+        0xBF00, // nop, to align the word
+        // literal:
+        0xF00D, 0xCAFE, // .word 0xCAFEF00D
+    ]);
+    assert_eq!(regs(&machine, 1, 3), [0x78, 0x12, 0x1234]);
+    assert_eq!(machine.cpu.reg(5), 0xFFFF_FF80);
+    // Pre-indexed then post-indexed: the base moved on by 8, then 4
+    assert_eq!(machine.cpu.reg(6), 0x1234_5678);
+    assert_eq!(machine.cpu.reg(7), RAM + 12);
+    assert_eq!(machine.cpu.reg(8), 0xCAFE_F00D);
+    assert_eq!(machine.cpu.reg(10), 0x1234_5678);
+    assert_eq!(machine.bus.read(RAM + 4, 4), Ok(0x80));
+}
+
+#[test]
+fn load_and_store_multiple() {
+    let mut machine = run(&[
+        0xF240, 0x0700, // movw r7, #0
+        0xF2C2, 0x0700, // movt r7, #0x2000
+        0x2001, // movs r0, #1
+        0x2102, // movs r1, #2
+        0x2203, // movs r2, #3
+        0xC707, // stmia r7!, {r0-r2}
+        0xE937, 0x0038, // ldmdb r7!, {r3-r5}
+        0xE9C7, 0x0204, // strd r0, r2, [r7, #16]
+        0xE9D7, 0x8904, // ldrd r8, r9, [r7, #16]
+        0xBE00, // bkpt #0
+    ]);
+    assert_eq!(regs(&machine, 3, 3), [1, 2, 3]);
+    assert_eq!(machine.cpu.reg(7), RAM);
+    assert_eq!(regs(&machine, 8, 2), [1, 3]);
+    assert_eq!(machine.bus.read(RAM + 8, 4), Ok(3));
+}
+
+#[test]
+fn exclusive_access() {
+    let mut machine = run(&[
+        0xF240, 0x0700, // movw r7, #0
+        0xF2C2, 0x0700, // movt r7, #0x2000
+        0x202A, // movs r0, #42
+        0xE857, 0x1F00, // ldrex r1, [r7]
+        0xE847, 0x0200, // strex r2, r0, [r7]
+        0xE847, 0x0301, // strex r3, r0, [r7, #4]
+        0xE857, 0x4F00, // ldrex r4, [r7]
+        0xF3BF, 0x8F2F, // clrex
+        0xE847, 0x0501, // strex r5, r0, [r7, #4]
+        0x687E, // ldr r6, [r7, #4]
+        0xBE00, // bkpt #0
+    ]);
+    // The STREX after the LDREX stores and gives 0; the next, with no LDREX before it, gives 1
+    assert_eq!(regs(&machine, 1, 3), [0, 0, 1]);
+    assert_eq!(machine.cpu.reg(4), 42);
+    // CLREX clears the monitor
+    assert_eq!(regs(&machine, 5, 2), [1, 0]);
+    assert_eq!(machine.bus.read(RAM, 4), Ok(42));
+}
+
+// ============================================================================
+// SYSTEM
+// ============================================================================
+
+#[test]
+fn stack_pointers_and_masks() {
+    let machine = run(&[
+        0xF640, 0x0000, // movw r0, #0x0800
+        0xF2C2, 0x0000, // movt r0, #0x2000
+        0xF380, 0x8809, // msr psp, r0
+        0x2102, // movs r1, #2
+        0xF381, 0x8814, // msr control, r1
+        0xF3BF, 0x8F6F, // isb
+        0x466A, // mov r2, sp
+        0xF3EF, 0x8308, // mrs r3, msp
+        0xB402, // push {r1}
+        0xF3EF, 0x8409, // mrs r4, psp
+        0x2100, // movs r1, #0
+        0xF381, 0x8814, // msr control, r1
+        0x466D, // mov r5, sp
+        0xB672, // cpsid i
+        0xF3EF, 0x8610, // mrs r6, primask
+        0x2140, // movs r1, #0x40
+        0xF381, 0x8811, // msr basepri, r1
+        0xF3EF, 0x8711, // mrs r7, basepri
+        0xBE00, // bkpt #0
+    ]);
+    // CONTROL.SPSEL = 1: SP is the PSP
+    assert_eq!(machine.cpu.reg(2), RAM + 0x800);
+    assert_eq!(machine.cpu.reg(3), STACK);
+    assert_eq!(machine.cpu.reg(4), RAM + 0x7FC);
+    // And back to the MSP
+    assert_eq!(machine.cpu.reg(5), STACK);
+    assert_eq!(regs(&machine, 6, 2), [1, 0x40]);
+    assert!(machine.cpu.primask);
+}
+
+#[test]
+fn undefined_instruction() {
+    let (_, fault) = fault(&[
+        0xDE00, // udf #0
+    ]);
+    assert_eq!(
+        fault,
+        Fault::Undefined {
+            pc: CODE,
+            instruction: 0xDE00
+        }
+    );
+}
+
+#[test]
+fn unaligned_load_multiple() {
+    let (_, fault) = fault(&[
+        0xF240, 0x0002, // movw r0, #2
+        0xF2C2, 0x0000, // movt r0, #0x2000
+        0xE890, 0x0006, // ldm r0, {r1, r2}
+    ]);
+    assert_eq!(
+        fault,
+        Fault::Unaligned {
+            pc: CODE + 8,
+            address: RAM + 2
+        }
+    );
+}
+
+#[test]
+fn nothing_at_the_address() {
+    let (_, fault) = fault(&[
+        0x2001, // movs r0, #1
+        0x0700, // lsls r0, r0, #28
+        0x6801, // ldr r1, [r0]
+    ]);
+    assert_eq!(
+        fault,
+        Fault::Bus {
+            pc: CODE + 4,
+            address: 0x1000_0000
+        }
+    );
+}
+
+#[test]
+fn flash_is_read_only() {
+    let (_, fault) = fault(&[
+        0x2000, // movs r0, #0
+        0x6000, // str r0, [r0]
+    ]);
+    assert_eq!(
+        fault,
+        Fault::Bus {
+            pc: CODE + 2,
+            address: 0
+        }
+    );
+}
+
+#[test]
+fn branch_without_the_thumb_bit() {
+    let (_, fault) = fault(&[
+        0x2080, // movs r0, #0x80
+        0x4700, // bx r0
+    ]);
+    assert_eq!(fault, Fault::NotThumb { pc: 0x80 });
+}
+
+#[test]
+fn supervisor_call() {
+    let (_, fault) = fault(&[
+        0xDF05, // svc #5
+    ]);
+    assert_eq!(fault, Fault::SupervisorCall { pc: CODE, imm: 5 });
+}
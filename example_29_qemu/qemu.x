@@ -0,0 +1,63 @@
+/*
+ * Linker script for QEMU's mps2-an386 board (Cortex-M4)
+ * The example 03 layout, with the memory of the MPS2 board
+ *
+ * The runtime lives in the library, so its vector table has to be asked for
+ * by name: the linker only takes what is referenced out of a library
+ */
+
+MEMORY
+{
+  /* NOTE 1 K = 1 KiBi = 1024 bytes */
+  /* SSRAM1 holds the code and is where the core reads the vector table; SSRAM2 and 3 are the RAM */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 4M
+  RAM   : ORIGIN = 0x20000000, LENGTH = 4M
+}
+
+/* Entry point - where execution begins */
+ENTRY(Reset)
+
+/* Pull the vector table out of the library */
+EXTERN(VECTOR_TABLE)
+
+SECTIONS
+{
+    /* Vector table must be at the very start of memory (0x00000000) */
+    .vector_table ORIGIN(FLASH) : {
+        KEEP(*(.vector_table))
+    } > FLASH
+
+    /* Program code and constants */
+    .text : {
+        *(.text .text.*)
+        *(.rodata .rodata.*)
+    } > FLASH
+
+    /* Initialized data - loaded after the code, copied to RAM at startup */
+    .data : {
+        . = ALIGN(4);
+        _sdata = .;
+        *(.data .data.*)
+        . = ALIGN(4);
+        _edata = .;
+    } > RAM AT > FLASH
+
+    _sidata = LOADADDR(.data);
+
+    /* Uninitialized data - zeroed at startup */
+    .bss (NOLOAD) : {
+        . = ALIGN(4);
+        _sbss = .;
+        *(.bss .bss.*)
+        *(COMMON)
+        . = ALIGN(4);
+        _ebss = .;
+    } > RAM
+
+    /DISCARD/ : {
+        *(.ARM.exidx .ARM.exidx.*)
+    }
+}
+
+/* Stack grows down from end of RAM */
+_stack_start = ORIGIN(RAM) + LENGTH(RAM);
@@ -0,0 +1,21 @@
+#![no_std]
+
+//! Runs the board-independent modules of the other examples on a Cortex-M4 in QEMU, as Thumb code.
+//!
+//! - [`rt`] is the reset handler and vector table, the example 03 runtime with the FPU switched on
+//! - [`semihosting`] prints to QEMU's console and ends the program with an exit code
+//! - [`runner`] runs a list of tests, prints a line for each and exits with pass or fail
+//!
+//! The modules under test are copies of other examples' files, so this example stands on its own:
+//!
+//! - [`ring`] and [`console`] from example 15
+//! - [`debounce`] and [`tally`] from example 26
+
+pub mod rt;
+pub mod runner;
+pub mod semihosting;
+
+pub mod console;
+pub mod debounce;
+pub mod ring;
+pub mod tally;
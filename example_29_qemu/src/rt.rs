@@ -0,0 +1,133 @@
+//! The example 03 runtime for QEMU: copy `.data`, zero `.bss`, switch on the FPU, then call the test binary's
+//! `main`, which the [`tests!`](crate::tests) macro writes.
+//!
+//! A fault is a failed test too: the handlers print which exception it was and exit with failure, so a test
+//! that reads an address nothing answers at fails instead of hanging QEMU.
+
+use core::fmt::Write;
+
+use crate::semihosting::{self, Stdout};
+
+// External symbols from linker script
+extern "C" {
+    static mut _sbss: u32; // Start of .bss section
+    static mut _ebss: u32; // End of .bss section
+    static mut _sdata: u32; // Start of .data section in RAM
+    static mut _edata: u32; // End of .data section in RAM
+    static _sidata: u32; // Initial values for .data (after the code)
+
+    /// Written by `tests!` in each test binary
+    fn main() -> !;
+}
+
+/// Coprocessor Access Control Register
+const CPACR: *mut u32 = 0xE000_ED88 as *mut u32;
+/// Full access to CP10 and CP11, which are the FPU
+const CPACR_FPU: u32 = 0b1111 << 20;
+
+// Reset handler - this is where execution begins after power-on
+/// # Safety
+///
+/// Only the core calls this, once, out of reset: nothing may use RAM before it is initialised here
+#[no_mangle]
+pub unsafe extern "C" fn Reset() -> ! {
+    // Copy .data section to RAM
+    let mut src = core::ptr::addr_of!(_sidata);
+    let mut dest = core::ptr::addr_of_mut!(_sdata);
+    let end_data = core::ptr::addr_of_mut!(_edata);
+
+    while dest < end_data {
+        core::ptr::write_volatile(dest, core::ptr::read_volatile(src));
+        dest = dest.offset(1);
+        src = src.offset(1);
+    }
+
+    // Zero out .bss section
+    let mut dest = core::ptr::addr_of_mut!(_sbss);
+    let end_bss = core::ptr::addr_of_mut!(_ebss);
+
+    while dest < end_bss {
+        core::ptr::write_volatile(dest, 0);
+        dest = dest.offset(1);
+    }
+
+    // The hard-float target may use FPU registers anywhere, and they fault until CPACR allows them
+    core::ptr::write_volatile(CPACR, core::ptr::read_volatile(CPACR) | CPACR_FPU);
+    core::arch::asm!("dsb", "isb", options(nostack, preserves_flags));
+
+    main();
+}
+
+/// Report a fault as a failed test
+fn fault(name: &str) -> ! {
+    let _ = writeln!(Stdout, "FAILED\n\n{}\n\ntest result: FAILED", name);
+    semihosting::exit(false);
+}
+
+#[no_mangle]
+pub extern "C" fn NonMaskableInt() -> ! {
+    fault("NMI")
+}
+
+#[no_mangle]
+pub extern "C" fn HardFault() -> ! {
+    fault("HardFault")
+}
+
+#[no_mangle]
+pub extern "C" fn MemoryManagement() -> ! {
+    fault("MemoryManagement fault")
+}
+
+#[no_mangle]
+pub extern "C" fn BusFault() -> ! {
+    fault("BusFault")
+}
+
+#[no_mangle]
+pub extern "C" fn UsageFault() -> ! {
+    fault("UsageFault")
+}
+
+// Default handler for the exceptions nothing enables
+#[no_mangle]
+pub extern "C" fn DefaultHandler() -> ! {
+    fault("unexpected exception")
+}
+
+// ARM Cortex-M Vector Table - using function pointers
+// This MUST be placed at address 0x00000000. No interrupts are enabled, so the table stops after the exceptions.
+#[repr(C)]
+pub struct VectorTable {
+    pub stack_pointer: u32,
+    pub reset: unsafe extern "C" fn() -> !,
+    pub nmi: extern "C" fn() -> !,
+    pub hard_fault: extern "C" fn() -> !,
+    pub mem_manage: extern "C" fn() -> !,
+    pub bus_fault: extern "C" fn() -> !,
+    pub usage_fault: extern "C" fn() -> !,
+    pub reserved1: [u32; 4],
+    pub sv_call: extern "C" fn() -> !,
+    pub debug_monitor: extern "C" fn() -> !,
+    pub reserved2: u32,
+    pub pend_sv: extern "C" fn() -> !,
+    pub sys_tick: extern "C" fn() -> !,
+}
+
+#[link_section = ".vector_table"]
+#[no_mangle]
+pub static VECTOR_TABLE: VectorTable = VectorTable {
+    stack_pointer: 0x20400000, // End of 4M RAM
+    reset: Reset,
+    nmi: NonMaskableInt,
+    hard_fault: HardFault,
+    mem_manage: MemoryManagement,
+    bus_fault: BusFault,
+    usage_fault: UsageFault,
+    reserved1: [0; 4],
+    sv_call: DefaultHandler,
+    debug_monitor: DefaultHandler,
+    reserved2: 0,
+    pend_sv: DefaultHandler,
+    sys_tick: DefaultHandler,
+};
@@ -0,0 +1,73 @@
+//! The test runner: a list of named functions, run in order, with output in the style of `cargo test`.
+//!
+//! ```text
+//! running 3 tests
+//! test starts_empty ... ok
+//! test regions_follow_each_other ... ok
+//! test reads_across_the_wrap ... FAILED
+//!
+//! panicked at tests/ring.rs:52:5:
+//! assertion `left == right` failed
+//!   left: 4
+//!  right: 5
+//!
+//! test result: FAILED
+//! ```
+//!
+//! There is no unwinding on the target, so the first failure ends the run: the panic handler prints the
+//! message and exits with failure, and the tests after it do not run.
+
+use core::{fmt::Write, panic::PanicInfo};
+
+use crate::semihosting::{self, Stdout};
+
+/// A test: a function that panics to fail
+pub struct Test {
+    pub name: &'static str,
+    pub run: fn(),
+}
+
+/// Run `tests` in order, then exit: QEMU's exit status is 0 if they all passed
+pub fn run(tests: &[Test]) -> ! {
+    let _ = writeln!(Stdout, "\nrunning {} tests", tests.len());
+    for test in tests {
+        let _ = write!(Stdout, "test {} ... ", test.name);
+        (test.run)();
+        let _ = writeln!(Stdout, "ok");
+    }
+    let _ = writeln!(Stdout, "\ntest result: ok. {} passed\n", tests.len());
+    semihosting::exit(true);
+}
+
+/// Write `main` for a test binary, running the listed test functions in order:
+///
+/// ```ignore
+/// #![no_std]
+/// #![no_main]
+///
+/// use example_29_qemu::tests;
+///
+/// fn starts_empty() { /* ... */ }
+/// fn reads_across_the_wrap() { /* ... */ }
+///
+/// tests!(starts_empty, reads_across_the_wrap);
+/// ```
+#[macro_export]
+macro_rules! tests {
+    ($($test:ident),* $(,)?) => {
+        #[no_mangle]
+        pub extern "C" fn main() -> ! {
+            $crate::runner::run(&[$($crate::runner::Test {
+                name: stringify!($test),
+                run: $test,
+            }),*])
+        }
+    };
+}
+
+// A failed assertion: the running test has printed its name, this finishes the line
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    let _ = writeln!(Stdout, "FAILED\n\n{}\n\ntest result: FAILED", info);
+    semihosting::exit(false);
+}
@@ -0,0 +1,61 @@
+//! ARM semihosting: the program asks the debugger, or QEMU, to do its I/O.
+//!
+//! `bkpt 0xAB` with an operation number in `r0` and its parameter in `r1` stops the core; the host does the
+//! operation and resumes it after the breakpoint. Without a debugger attached the breakpoint is a HardFault, so
+//! this is for QEMU and debug probes only.
+
+use core::{arch::asm, fmt};
+
+/// Print a NUL-terminated string
+const SYS_WRITE0: u32 = 0x04;
+/// Stop the program, for a reason
+const SYS_EXIT: u32 = 0x18;
+
+/// Exit reasons: QEMU exits with status 0 for the first and 1 for any other
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+const ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN: u32 = 0x20023;
+
+/// Longest piece printed in one call: strings are copied to the stack to add the NUL
+const CHUNK: usize = 64;
+
+/// Ask the host to do `operation`, returning its result
+///
+/// # Safety
+///
+/// `parameter` must be what `operation` expects: a value, or a pointer to memory that is valid for the call
+unsafe fn call(operation: u32, parameter: u32) -> u32 {
+    let result;
+    asm!("bkpt 0xAB", inout("r0") operation => result, in("r1") parameter, options(nostack));
+    result
+}
+
+/// The host's console, for `write!` and `writeln!`
+pub struct Stdout;
+
+impl fmt::Write for Stdout {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut buf = [0u8; CHUNK + 1];
+        for piece in s.as_bytes().chunks(CHUNK) {
+            buf[..piece.len()].copy_from_slice(piece);
+            buf[piece.len()] = 0;
+            // `buf` is NUL-terminated and lives until the host has printed it
+            unsafe { call(SYS_WRITE0, buf.as_ptr() as u32) };
+        }
+        Ok(())
+    }
+}
+
+/// End the program: QEMU exits with status 0 if `success`, 1 otherwise
+pub fn exit(success: bool) -> ! {
+    let reason = if success {
+        ADP_STOPPED_APPLICATION_EXIT
+    } else {
+        ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN
+    };
+    // SYS_EXIT takes the reason itself, not a pointer
+    unsafe { call(SYS_EXIT, reason) };
+    // Only a host that ignores the request gets here
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,59 @@
+//! Presses of each button, and the picture of them on the LED matrix.
+//!
+//! The two left columns fill up from the bottom with presses of A, the two right columns with presses of B,
+//! five presses to a full column and back to empty on the sixth. The middle column is a dim divider.
+
+/// Brightness of a lit bar, on the display's 0-9 scale
+pub const BAR: u8 = 9;
+/// Brightness of the divider
+pub const DIVIDER: u8 = 2;
+
+/// Rows on the LED matrix, and so the most presses a bar can show
+const ROWS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    A,
+    B,
+}
+
+/// Presses since reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Presses {
+    pub a: u32,
+    pub b: u32,
+}
+
+impl Presses {
+    pub const fn new() -> Self {
+        Self { a: 0, b: 0 }
+    }
+
+    /// Count a press, and return the new total for that button
+    pub fn press(&mut self, button: Button) -> u32 {
+        let count = match button {
+            Button::A => &mut self.a,
+            Button::B => &mut self.b,
+        };
+        *count = count.wrapping_add(1);
+        *count
+    }
+
+    /// The display image: brightness 0-9 for each LED, row by row from the top
+    pub fn image(&self) -> [[u8; 5]; 5] {
+        let (a, b) = (bar_height(self.a), bar_height(self.b));
+        let mut image = [[0; 5]; 5];
+        for (row, leds) in image.iter_mut().enumerate() {
+            // Row 4 is the bottom, where the bars start
+            let height = (ROWS as usize - row) as u32;
+            let level = |bar| if bar >= height { BAR } else { 0 };
+            *leds = [level(a), level(a), DIVIDER, level(b), level(b)];
+        }
+        image
+    }
+}
+
+/// Lit rows for `count` presses: 0 to 5, then round again
+fn bar_height(count: u32) -> u32 {
+    count % (ROWS + 1)
+}
@@ -0,0 +1,104 @@
+//! Example 15's line editor and command parser, on the Cortex-M4
+
+#![no_std]
+#![no_main]
+
+use core::fmt::{self, Write};
+
+use example_29_qemu::{
+    console::{Command, CommandError, LineEditor, MAX_LINE},
+    tests,
+};
+
+/// What the editor echoed, in a fixed buffer: there is no `String` here
+struct Echo {
+    buf: [u8; 128],
+    len: usize,
+}
+
+impl Echo {
+    fn new() -> Self {
+        Self { buf: [0; 128], len: 0 }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+}
+
+impl Write for Echo {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Feed `input` to the editor, calling `line` with each completed line
+fn type_in(editor: &mut LineEditor, echo: &mut Echo, input: &[u8], mut line: impl FnMut(&str)) {
+    for &byte in input {
+        if let Some(completed) = editor.feed(byte, echo).unwrap() {
+            line(completed);
+        }
+    }
+}
+
+fn enter_completes_a_line() {
+    let (mut editor, mut echo) = (LineEditor::new(), Echo::new());
+    let mut lines = 0;
+    type_in(&mut editor, &mut echo, b"help\r", |line| {
+        assert_eq!(line, "help");
+        lines += 1;
+    });
+    assert_eq!(lines, 1);
+    assert_eq!(echo.as_str(), "help\r\n");
+}
+
+fn backspace_erases() {
+    let (mut editor, mut echo) = (LineEditor::new(), Echo::new());
+    let mut lines = 0;
+    type_in(&mut editor, &mut echo, b"lex\x08d\x7F\x7Fed on\r", |line| {
+        assert_eq!(line, "led on");
+        lines += 1;
+    });
+    assert_eq!(lines, 1);
+    assert_eq!(echo.as_str(), "lex\x08 \x08d\x08 \x08\x08 \x08ed on\r\n");
+}
+
+fn long_lines_are_cut() {
+    let (mut editor, mut echo) = (LineEditor::new(), Echo::new());
+    let mut length = 0;
+    type_in(&mut editor, &mut echo, &[b'x'; MAX_LINE + 10], |_| {});
+    type_in(&mut editor, &mut echo, b"\r", |line| length = line.len());
+    assert_eq!(length, MAX_LINE);
+    assert_eq!(echo.len, MAX_LINE + 2);
+}
+
+fn commands_parse() {
+    assert_eq!(Command::parse("help"), Ok(Command::Help));
+    assert_eq!(Command::parse("  LED on "), Ok(Command::Led(true)));
+    assert_eq!(Command::parse("led off"), Ok(Command::Led(false)));
+    assert_eq!(Command::parse("echo  hello there "), Ok(Command::Echo("hello there")));
+    assert_eq!(Command::parse(""), Ok(Command::Empty));
+}
+
+fn bad_commands_explain_themselves() {
+    assert_eq!(Command::parse("led"), Err(CommandError::Usage("led on|off")));
+    assert_eq!(Command::parse("reboot now"), Err(CommandError::Unknown("reboot")));
+
+    let mut message = Echo::new();
+    write!(message, "{}", CommandError::Unknown("reboot")).unwrap();
+    assert_eq!(message.as_str(), "unknown command 'reboot', try 'help'");
+}
+
+tests!(
+    enter_completes_a_line,
+    backspace_erases,
+    long_lines_are_cut,
+    commands_parse,
+    bad_commands_explain_themselves,
+);
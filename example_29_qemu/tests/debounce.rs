@@ -0,0 +1,41 @@
+//! Example 26's debouncer, on the Cortex-M4
+
+#![no_std]
+#![no_main]
+
+use example_29_qemu::{
+    debounce::{Debouncer, SAMPLES},
+    tests,
+};
+
+/// Feed `samples` in, and return what the debouncer reported after the last
+fn last_report(samples: &[bool]) -> Option<bool> {
+    let mut debouncer = Debouncer::new();
+    samples.iter().fold(None, |_, &pressed| debouncer.sample(pressed))
+}
+
+fn steady_press_settles_after_samples() {
+    let mut debouncer = Debouncer::new();
+    for _ in 1..SAMPLES {
+        assert_eq!(debouncer.sample(true), None);
+    }
+    assert_eq!(debouncer.sample(true), Some(true));
+    assert_eq!(debouncer.sample(true), Some(true));
+}
+
+fn bounces_restart_the_count() {
+    // Low, high, low, low, low, low: the bounce at the second sample delays the press
+    assert_eq!(last_report(&[true, false, true, true, true]), None);
+    assert_eq!(last_report(&[true, false, true, true, true, true]), Some(true));
+}
+
+fn a_glitch_settles_as_released() {
+    // One low sample, then the pin stays high: not a press
+    assert_eq!(last_report(&[true, false, false, false, false]), Some(false));
+}
+
+tests!(
+    steady_press_settles_after_samples,
+    bounces_restart_the_count,
+    a_glitch_settles_as_released,
+);
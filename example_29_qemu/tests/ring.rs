@@ -0,0 +1,114 @@
+//! Example 15's receive ring buffer, on the Cortex-M4, with the test playing EasyDMA
+
+#![no_std]
+#![no_main]
+
+use example_29_qemu::{
+    ring::{Region, RingBuffer},
+    tests,
+};
+
+/// Write `data` into a reserved region, as the UARTE would
+fn dma_write<const N: usize>(ring: &mut RingBuffer<N>, region: Region, data: &[u8]) {
+    ring.region_mut(region)[..data.len()].copy_from_slice(data);
+}
+
+fn starts_empty() {
+    let mut ring = RingBuffer::<16>::new();
+    assert_eq!(ring.capacity(), 16);
+    assert!(ring.is_empty());
+    assert_eq!(ring.free(), 16);
+    assert_eq!(ring.pop(), None);
+    assert_eq!(ring.read(&mut [0; 4]), 0);
+}
+
+fn committed_bytes_become_readable() {
+    let mut ring = RingBuffer::<16>::new();
+    let first = ring.reserve(4).unwrap();
+    let second = ring.reserve(4).unwrap();
+    dma_write(&mut ring, first, b"abcd");
+    dma_write(&mut ring, second, b"ef");
+
+    // The byte counter says 6 bytes arrived: the first region and part of the second
+    ring.commit(6);
+    assert_eq!(ring.len(), 6);
+    assert_eq!(ring.reserved(), 2);
+    let mut out = [0; 16];
+    assert_eq!(ring.read(&mut out), 6);
+    assert_eq!(&out[..6], b"abcdef");
+}
+
+fn regions_stop_at_the_end_of_the_buffer() {
+    let mut ring = RingBuffer::<16>::new();
+    let region = ring.reserve(12).unwrap();
+    dma_write(&mut ring, region, b"0123456789ab");
+    ring.commit(12);
+    assert_eq!(ring.read(&mut [0; 16]), 12);
+
+    // 4 bytes left before the end, then it continues at the start
+    assert_eq!(ring.reserve(8), Some(Region { offset: 12, len: 4 }));
+    assert_eq!(ring.reserve(8), Some(Region { offset: 0, len: 8 }));
+}
+
+fn reads_across_the_wrap() {
+    let mut ring = RingBuffer::<8>::new();
+    let region = ring.reserve(6).unwrap();
+    dma_write(&mut ring, region, b"xxxxxx");
+    ring.commit(6);
+    ring.read(&mut [0; 6]);
+
+    let tail = ring.reserve(8).unwrap();
+    let head = ring.reserve(8).unwrap();
+    assert_eq!((tail.len, head.offset), (2, 0));
+    dma_write(&mut ring, tail, b"he");
+    dma_write(&mut ring, head, b"llo");
+    ring.commit(5);
+    let mut out = [0; 8];
+    assert_eq!(ring.read(&mut out), 5);
+    assert_eq!(&out[..5], b"hello");
+}
+
+fn full_buffer_refuses_regions() {
+    let mut ring = RingBuffer::<8>::new();
+    ring.reserve(8).unwrap();
+    ring.commit(8);
+    assert_eq!(ring.free(), 0);
+    assert_eq!(ring.reserve(4), None);
+
+    // Reading makes room again
+    ring.read(&mut [0; 3]);
+    assert_eq!(ring.reserve(4), Some(Region { offset: 0, len: 3 }));
+}
+
+fn continuous_stream() {
+    // Every byte value through a small buffer in small chunks, reading as it goes: nothing lost, nothing reordered
+    let mut ring = RingBuffer::<10>::new();
+    let mut sent = 0usize;
+    let mut received = 0usize;
+    while received < 256 {
+        if let Some(region) = ring.reserve(3) {
+            let end = (sent + region.len).min(256);
+            for (slot, value) in ring.region_mut(region).iter_mut().zip(sent..end) {
+                *slot = value as u8;
+            }
+            ring.commit(end - sent);
+            ring.cancel_reservations();
+            sent = end;
+        }
+        let mut out = [0; 2];
+        let count = ring.read(&mut out);
+        for &byte in &out[..count] {
+            assert_eq!(usize::from(byte), received);
+            received += 1;
+        }
+    }
+}
+
+tests!(
+    starts_empty,
+    committed_bytes_become_readable,
+    regions_stop_at_the_end_of_the_buffer,
+    reads_across_the_wrap,
+    full_buffer_refuses_regions,
+    continuous_stream,
+);
@@ -0,0 +1,56 @@
+//! Example 26's press counts and the LED matrix image drawn from them, on the Cortex-M4
+
+#![no_std]
+#![no_main]
+
+use example_29_qemu::{
+    tally::{Button, Presses, BAR, DIVIDER},
+    tests,
+};
+
+/// The bar heights in an image: lit rows in columns 0 and 3, checking the columns beside them match
+fn bars(image: &[[u8; 5]; 5]) -> (usize, usize) {
+    for row in image {
+        assert_eq!(row[0], row[1]);
+        assert_eq!(row[3], row[4]);
+        assert_eq!(row[2], DIVIDER);
+    }
+    let height = |col: usize| image.iter().filter(|row| row[col] == BAR).count();
+    (height(0), height(3))
+}
+
+fn counts_each_button() {
+    let mut presses = Presses::new();
+    assert_eq!(presses.press(Button::A), 1);
+    assert_eq!(presses.press(Button::A), 2);
+    assert_eq!(presses.press(Button::B), 1);
+    assert_eq!(presses, Presses { a: 2, b: 1 });
+}
+
+fn bars_grow_from_the_bottom() {
+    let image = Presses { a: 2, b: 4 }.image();
+    assert_eq!(bars(&image), (2, 4));
+    #[rustfmt::skip]
+    let expected = [
+        [0,   0,   DIVIDER, 0,   0],
+        [0,   0,   DIVIDER, BAR, BAR],
+        [0,   0,   DIVIDER, BAR, BAR],
+        [BAR, BAR, DIVIDER, BAR, BAR],
+        [BAR, BAR, DIVIDER, BAR, BAR],
+    ];
+    assert_eq!(image, expected);
+}
+
+fn sixth_press_empties_the_bar() {
+    let mut presses = Presses::new();
+    for expected in [1, 2, 3, 4, 5, 0, 1] {
+        presses.press(Button::B);
+        assert_eq!(bars(&presses.image()), (0, expected));
+    }
+}
+
+tests!(
+    counts_each_button,
+    bars_grow_from_the_bottom,
+    sixth_press_empties_the_bar
+);
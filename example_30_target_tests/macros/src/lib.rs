@@ -0,0 +1,154 @@
+//! The `#[tests]` attribute of `example_30_target_tests`: it turns a module of `#[test]` functions into the
+//! test binary's `main`.
+//!
+//! ```ignore
+//! #[example_30_target_tests::tests]
+//! mod tests {
+//!     #[init]
+//!     fn init() -> Timer<TIMER0> { /* ... */ }
+//!
+//!     #[test]
+//!     fn counts_up(timer: &mut Timer<TIMER0>) { /* ... */ }
+//! }
+//! ```
+//!
+//! The module keeps its items, without the `#[init]` and `#[test]` attributes, and gets one more: a function
+//! exported as `main`, which cortex-m-rt's reset handler calls. It calls `init` once, then each test in the order
+//! they are written, through `example_30_target_tests::runner`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, FnArg, Ident, Item, ItemFn, ItemMod, ReturnType, Type};
+
+#[proc_macro_attribute]
+pub fn tests(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return Error::new(Span::call_site(), "`#[tests]` takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let module = parse_macro_input!(input as ItemMod);
+    match expand(module) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// A `#[test]` function, and whether it takes the state
+struct Test {
+    name: Ident,
+    takes_state: bool,
+}
+
+fn expand(mut module: ItemMod) -> Result<proc_macro2::TokenStream, Error> {
+    let Some((_, items)) = module.content.as_mut() else {
+        return Err(Error::new(
+            module.span(),
+            "`#[tests]` needs the module's items here, `mod tests { ... }`, not in another file",
+        ));
+    };
+
+    // The `#[init]` function first, wherever it is: whether there is one decides what the tests may take
+    let mut init: Option<Ident> = None;
+    for item in items.iter_mut() {
+        let Item::Fn(function) = item else { continue };
+        if take_attribute(function, "init") {
+            check_init(function, init.is_some())?;
+            init = Some(function.sig.ident.clone());
+        }
+    }
+
+    let mut tests = Vec::new();
+    for item in items.iter_mut() {
+        let Item::Fn(function) = item else { continue };
+        if take_attribute(function, "test") {
+            let takes_state = check_test(function, init.is_some())?;
+            tests.push(Test {
+                name: function.sig.ident.clone(),
+                takes_state,
+            });
+        }
+    }
+
+    let count = tests.len();
+    let state = init.map(|init| quote!(let mut state = #init();));
+    let runs = tests.iter().map(|Test { name, takes_state }| {
+        let label = name.to_string();
+        let call = if *takes_state {
+            quote!(#name(&mut state))
+        } else {
+            quote!(#name())
+        };
+        quote!(::example_30_target_tests::runner::run(#label, || #call);)
+    });
+
+    items.push(syn::parse_quote! {
+        #[export_name = "main"]
+        unsafe extern "C" fn __example_30_target_tests_main() -> ! {
+            ::example_30_target_tests::runner::start(#count);
+            #state
+            #(#runs)*
+            ::example_30_target_tests::runner::finish()
+        }
+    });
+    Ok(quote!(#module))
+}
+
+/// Remove `#[name]` from `function`, returning whether it was there
+fn take_attribute(function: &mut ItemFn, name: &str) -> bool {
+    let before = function.attrs.len();
+    function.attrs.retain(|attribute| !attribute.path().is_ident(name));
+    function.attrs.len() != before
+}
+
+/// `#[init]`: the only one, with no arguments, returning the state
+fn check_init(function: &ItemFn, another: bool) -> Result<(), Error> {
+    let signature = &function.sig;
+    if another {
+        return Err(Error::new(signature.ident.span(), "only one function can be `#[init]`"));
+    }
+    if !signature.inputs.is_empty() {
+        return Err(Error::new(signature.inputs.span(), "`#[init]` takes no arguments"));
+    }
+    if matches!(signature.output, ReturnType::Default) {
+        return Err(Error::new(
+            signature.span(),
+            "`#[init]` returns the state the tests take, e.g. `fn init() -> Timer<TIMER0>`",
+        ));
+    }
+    check_plain(function)
+}
+
+/// `#[test]`: no arguments, or one `&mut` of the state if there is an `#[init]`. Returns which.
+fn check_test(function: &ItemFn, have_init: bool) -> Result<bool, Error> {
+    check_plain(function)?;
+    let inputs = &function.sig.inputs;
+    match inputs.first() {
+        None => Ok(false),
+        Some(FnArg::Typed(argument)) if inputs.len() == 1 && have_init => match &*argument.ty {
+            Type::Reference(reference) if reference.mutability.is_some() => Ok(true),
+            _ => Err(Error::new(argument.ty.span(), "the state is passed as `&mut`")),
+        },
+        Some(_) if have_init => Err(Error::new(inputs.span(), "a test takes nothing, or `&mut` the state")),
+        Some(_) => Err(Error::new(
+            inputs.span(),
+            "a test takes no arguments, unless an `#[init]` function returns a state to pass",
+        )),
+    }
+}
+
+/// Nothing the generated `main` cannot call directly: no `async`, no generics
+fn check_plain(function: &ItemFn) -> Result<(), Error> {
+    let signature = &function.sig;
+    if let Some(asyncness) = signature.asyncness {
+        return Err(Error::new(
+            asyncness.span(),
+            "there is no executor to run an `async` test",
+        ));
+    }
+    if !signature.generics.params.is_empty() {
+        return Err(Error::new(signature.generics.span(), "a test cannot be generic"));
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+#![no_std]
+
+//! Tests that run on the micro:bit: `#[test]` functions in a `no_std` test binary, results over RTT, and the
+//! exit status through semihosting, so `cargo test` passes or fails on what `probe-rs run` exits with.
+//!
+//! - [`tests`] is the attribute for a test binary's module of tests, from `macros/`
+//! - [`runner`] runs each test and prints its result; the attribute's `main` calls it
+//! - [`semihosting`] ends the test binary with an exit status
+//!
+//! The test binaries are in `tests/`. They run on the micro:bit, or in example 28's emulator.
+
+pub use example_30_target_tests_macros::tests;
+
+pub mod runner;
+pub mod semihosting;
+
+// cortex-m-rt's reset handler calls the `main` that `#[tests]` writes, and its link.x wants the HAL's interrupt
+// vectors: both have to be linked into every test binary, whatever the tests use
+use cortex_m_rt as _;
+use nrf52833_hal as _;
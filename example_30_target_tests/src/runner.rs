@@ -0,0 +1,95 @@
+//! Runs the tests `#[tests]` collected, printing the results over RTT in the style of `cargo test`:
+//!
+//! ```text
+//! running 3 tests
+//! test outputs_start_at_their_initial_level ... ok
+//! test set_high_drives_the_pin ... ok
+//! test toggle_changes_only_its_pin ... FAILED
+//!
+//! panicked at tests/gpio.rs:61:9:
+//! assertion failed: level(COL1)
+//!
+//! test result: FAILED. 2 passed; 1 failed
+//! ```
+//!
+//! There is no unwinding on the target, so a failed test ends the run: the panic handler, or an `Err` returned by
+//! the test, prints the failure and exits with status 1 through semihosting. `probe-rs run` then exits with that
+//! status, and `cargo test` reports the test binary as failed.
+
+use core::{
+    convert::Infallible,
+    fmt::{self, Debug},
+    panic::PanicInfo,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rtt_target::{rprint, rprintln, rtt_init_print};
+
+use crate::semihosting;
+
+/// Tests passed so far, for the summary
+static PASSED: AtomicUsize = AtomicUsize::new(0);
+
+/// What a test function returns: nothing, or a `Result` whose `Err` fails the test like a panic
+pub trait Outcome {
+    type Error: Debug;
+
+    fn into_result(self) -> Result<(), Self::Error>;
+}
+
+impl Outcome for () {
+    type Error = Infallible;
+
+    fn into_result(self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl<E: Debug> Outcome for Result<(), E> {
+    type Error = E;
+
+    fn into_result(self) -> Self {
+        self
+    }
+}
+
+/// Set up RTT and announce `count` tests
+pub fn start(count: usize) {
+    // Blocking: the results matter more than the speed, and the debugger is reading
+    rtt_init_print!(BlockIfFull);
+    rprintln!("\nrunning {} tests", count);
+}
+
+/// Run one test
+pub fn run<T: Outcome>(name: &str, test: impl FnOnce() -> T) {
+    rprint!("test {} ... ", name);
+    match test().into_result() {
+        Ok(()) => {
+            PASSED.fetch_add(1, Ordering::Relaxed);
+            rprintln!("ok");
+        }
+        Err(error) => fail(format_args!("Error: {:?}", error)),
+    }
+}
+
+/// All the tests passed: print the summary and exit with status 0
+pub fn finish() -> ! {
+    rprintln!("\ntest result: ok. {} passed\n", PASSED.load(Ordering::Relaxed));
+    semihosting::exit(true);
+}
+
+/// The running test failed: finish its line with `message`, print the summary and exit with status 1
+fn fail(message: fmt::Arguments) -> ! {
+    rprintln!("FAILED\n\n{}\n", message);
+    rprintln!(
+        "test result: FAILED. {} passed; 1 failed\n",
+        PASSED.load(Ordering::Relaxed)
+    );
+    semihosting::exit(false);
+}
+
+// A failed assertion, or a panic in `#[init]`
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    fail(format_args!("{}", info))
+}
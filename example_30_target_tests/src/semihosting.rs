@@ -0,0 +1,34 @@
+//! ARM semihosting, just enough to end a test binary with an exit status.
+//!
+//! `bkpt 0xAB` with an operation number in `r0` and its parameter in `r1` stops the core, and the host attached to
+//! it does the operation. `probe-rs run` does `SYS_EXIT`: it stops running the program and exits itself, with status
+//! 0 for an application exit and 1 for any other reason. Without a debugger attached the breakpoint is a HardFault,
+//! so a test binary only makes sense run from `probe-rs` (or example 28's emulator, which does the same).
+//!
+//! The results themselves go over RTT, see [`runner`](crate::runner).
+
+use core::arch::asm;
+
+/// Stop the program, for a reason
+const SYS_EXIT: u32 = 0x18;
+
+/// Exit reasons: the host exits with status 0 for the first and 1 for any other
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+const ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN: u32 = 0x20023;
+
+/// End the program: `probe-rs run` exits with status 0 if `success`, 1 otherwise
+pub fn exit(success: bool) -> ! {
+    let reason = if success {
+        ADP_STOPPED_APPLICATION_EXIT
+    } else {
+        ADP_STOPPED_RUN_TIME_ERROR_UNKNOWN
+    };
+    // SYS_EXIT takes the reason itself, not a pointer
+    unsafe {
+        asm!("bkpt 0xAB", in("r0") SYS_EXIT, in("r1") reason, options(nostack));
+    }
+    // Only a host that ignores the request gets here
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,72 @@
+//! The HAL's GPIO driver, on the pins of the LED matrix's row 1 and column 1: what its calls do to the pins,
+//! read back through P0's IN register
+
+#![no_std]
+#![no_main]
+
+#[example_30_target_tests::tests]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::{OutputPin, StatefulOutputPin};
+    use nrf52833_hal::{
+        gpio::{p0, Level, Output, Pin, PushPull},
+        pac,
+    };
+
+    // P0 IN register: the level on every pin, outputs included, as their input buffers stay connected
+    const GPIO_P0_IN: *const u32 = 0x5000_0510 as *const u32;
+
+    // micro:bit LED matrix pins: the LED lights while the row is high and the column low
+    const ROW1_PIN: u32 = 21; // P0.21
+    const COL1_PIN: u32 = 28; // P0.28
+
+    struct Pins {
+        row1: Pin<Output<PushPull>>,
+        col1: Pin<Output<PushPull>>,
+    }
+
+    /// The level on P0 `pin`, from the register rather than the driver
+    fn is_high(pin: u32) -> bool {
+        unsafe { core::ptr::read_volatile(GPIO_P0_IN) & (1 << pin) != 0 }
+    }
+
+    // The pins straight from the HAL, not `Board::take()`: the tests touch nothing but the GPIO they test
+    #[init]
+    fn init() -> Pins {
+        let p0 = p0::Parts::new(pac::Peripherals::take().unwrap().P0);
+        Pins {
+            row1: p0.p0_21.into_push_pull_output(Level::Low).degrade(),
+            // High: the LED stays dark whatever the row does
+            col1: p0.p0_28.into_push_pull_output(Level::High).degrade(),
+        }
+    }
+
+    #[test]
+    fn outputs_start_at_their_initial_level(pins: &mut Pins) {
+        assert!(pins.row1.is_set_low().unwrap());
+        assert!(pins.col1.is_set_high().unwrap());
+        assert!(!is_high(ROW1_PIN));
+        assert!(is_high(COL1_PIN));
+    }
+
+    #[test]
+    fn set_high_and_low_drive_the_pin(pins: &mut Pins) -> Result<(), Infallible> {
+        pins.row1.set_high()?;
+        assert!(is_high(ROW1_PIN));
+        pins.row1.set_low()?;
+        assert!(!is_high(ROW1_PIN));
+        Ok(())
+    }
+
+    #[test]
+    fn toggle_changes_only_its_pin(pins: &mut Pins) -> Result<(), Infallible> {
+        pins.row1.toggle()?;
+        assert!(is_high(ROW1_PIN));
+        assert!(is_high(COL1_PIN));
+        pins.row1.toggle()?;
+        assert!(!is_high(ROW1_PIN));
+        assert!(is_high(COL1_PIN));
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+//! The HAL's TIMER driver: counting, one-shot timeouts and delays, with a second timer as the clock
+
+#![no_std]
+#![no_main]
+
+#[example_30_target_tests::tests]
+mod tests {
+    use embedded_hal::delay::DelayNs;
+    use nrf52833_hal::{
+        pac::{self, TIMER0, TIMER1},
+        timer::Timer,
+    };
+
+    /// Cycles of the 64 MHz CPU clock in a microsecond, one timer tick
+    const CYCLES_PER_US: u32 = 64;
+
+    struct Timers {
+        /// The timer under test
+        timer: Timer<TIMER0>,
+        /// Counts microseconds from the start, to time the other with
+        clock: Timer<TIMER1>,
+    }
+
+    #[init]
+    fn init() -> Timers {
+        let peripherals = pac::Peripherals::take().unwrap();
+        let mut clock = Timer::new(peripherals.TIMER1);
+        clock.start(u32::MAX);
+        Timers {
+            timer: Timer::new(peripherals.TIMER0),
+            clock,
+        }
+    }
+
+    #[test]
+    fn counts_microseconds(timers: &mut Timers) {
+        let timer = &mut timers.timer;
+        timer.start(u32::MAX);
+        let before = timer.read();
+        // At least 1000 µs: `delay` counts 2 cycles a loop, and the loop takes more than that
+        cortex_m::asm::delay(1_000 * CYCLES_PER_US);
+        let elapsed = timer.read() - before;
+        assert!((1_000..4_000).contains(&elapsed), "{} µs", elapsed);
+    }
+
+    #[test]
+    fn one_shot_finishes_once(timers: &mut Timers) {
+        let timer = &mut timers.timer;
+        timer.start(100);
+        assert!(!timer.reset_if_finished());
+        cortex_m::asm::delay(200 * CYCLES_PER_US);
+        assert!(timer.reset_if_finished());
+        // The compare event stopped it, and reading the event reset it
+        assert!(!timer.reset_if_finished());
+    }
+
+    #[test]
+    fn delay_ms_waits_as_long_as_asked(timers: &mut Timers) {
+        let before = timers.clock.read();
+        timers.timer.delay_ms(5);
+        let elapsed = timers.clock.read() - before;
+        // A few microseconds over for starting the timer and polling its event
+        assert!((5_000..5_050).contains(&elapsed), "{} µs", elapsed);
+    }
+}